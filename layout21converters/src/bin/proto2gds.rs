@@ -9,7 +9,7 @@ use clap::Parser;
 use layout21protos::conv as proto_converters;
 use layout21protos::tech as protos;
 use layout21raw as raw;
-use raw::utils::Ptr;
+use raw::utils::{Progress, ProgressHook, Ptr};
 use std::error::Error;
 
 #[derive(Parser)]
@@ -22,6 +22,26 @@ struct ProgramOptions {
     tech: String,
     #[arg(short, long)]
     verbose: bool,
+    /// Maximum number of elements in any single GDS struct
+    #[arg(long, default_value_t = 10_000_000)]
+    max_cell_elems: usize,
+    /// Maximum number of elements across all GDS structs
+    #[arg(long, default_value_t = 100_000_000)]
+    max_total_elems: usize,
+    /// Maximum number of GDS structs
+    #[arg(long, default_value_t = 1_000_000)]
+    max_structs: usize,
+    /// Export only this cell and its dependencies
+    #[arg(long)]
+    top: Option<String>,
+}
+
+/// Progress reporter, printing each exported cell
+struct PrintProgress;
+impl Progress for PrintProgress {
+    fn on_cell_start(&self, name: &str, idx: usize, total: usize) {
+        println!("exporting cell {}/{}: {}", idx + 1, total, name);
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -59,7 +79,25 @@ fn _main(options: &ProgramOptions) -> Result<(), Box<dyn Error>> {
         Ok(lib) => lib,
     };
 
-    let gds_library = match library.to_gds() {
+    let progress = if options.verbose {
+        ProgressHook::new(PrintProgress)
+    } else {
+        ProgressHook::default()
+    };
+    let export_options = raw::gds::GdsExportOptions {
+        limits: raw::gds::GdsExportLimits {
+            max_cell_elems: Some(options.max_cell_elems),
+            max_total_elems: Some(options.max_total_elems),
+            max_structs: Some(options.max_structs),
+        },
+        progress,
+        ..Default::default()
+    };
+    let gds_library = match options.top {
+        Some(ref top) => library.to_gds_cell(top, &export_options),
+        None => library.to_gds_with_options(&export_options),
+    };
+    let gds_library = match gds_library {
         Err(err) => panic!("Couldn't convert to GDS library: {}", err),
         Ok(lib) => lib,
     };
@@ -103,6 +141,10 @@ mod tests {
             gds: output_path.clone(),
             tech: golden_tech_path,
             verbose: true,
+            max_cell_elems: 10_000_000,
+            max_total_elems: 100_000_000,
+            max_structs: 1_000_000,
+            top: None,
         };
 
         // Run the main function, producing file `output_path`
@@ -0,0 +1,95 @@
+//!
+//! # Blackbox Views
+//!
+//! Strip [Cell]s down to "blackbox" content, e.g. for hand-off of cells under NDA:
+//! only their elements on a set of kept (layer, datatype) number-pairs,
+//! typically those of the boundary and pins. Instances and text annotations are removed.
+//!
+
+// Local Imports
+use crate::data::{Element, Layers, Library};
+use crate::error::{LayoutError, LayoutResult};
+
+/// # Blackbox Report
+///
+/// Summary of the content stripped by [Library::blackbox], per [Cell](crate::Cell).
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlackboxReport {
+    /// Stripped content of each blackboxed cell, in the order requested
+    pub cells: Vec<BlackboxedCell>,
+}
+/// # Blackboxed Cell
+///
+/// Content removed from a single [Cell](crate::Cell) by [Library::blackbox].
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlackboxedCell {
+    /// Cell name
+    pub name: String,
+    /// Number of elements kept
+    pub elems_kept: usize,
+    /// Number of elements removed
+    pub elems_removed: usize,
+    /// Names of the instances removed
+    pub insts_removed: Vec<String>,
+    /// Number of text annotations removed
+    pub annotations_removed: usize,
+}
+impl Library {
+    /// Reduce each [Cell](crate::Cell) named in `cells` to a blackbox view.
+    ///
+    /// Removes each cell's instances, text annotations, and elements on any (layer, datatype) pair not in `keep_layers`.
+    /// Elements whose purpose has no datatype number on their layer are removed.
+    /// Cells which instantiate the blackboxed cells are not modified, and continue to refer to them.
+    /// All names are checked before any cell is modified, failing if any are not defined.
+    pub fn blackbox(
+        &mut self,
+        cells: &[&str],
+        keep_layers: &[(i16, i16)],
+    ) -> LayoutResult<BlackboxReport> {
+        let mut ptrs = Vec::with_capacity(cells.len());
+        let mut missing = Vec::new();
+        for name in cells.iter() {
+            match self.cell_named(name)? {
+                Some(ptr) => ptrs.push(ptr),
+                None => missing.push(*name),
+            }
+        }
+        if !missing.is_empty() {
+            return LayoutError::fail(format!(
+                "Cells not found in Library {}: {}",
+                self.name,
+                missing.join(", ")
+            ));
+        }
+        let layers = self.layers.read()?;
+        let mut report = BlackboxReport::default();
+        for ptr in ptrs.iter() {
+            let mut cell = ptr.write()?;
+            let mut stripped = BlackboxedCell {
+                name: cell.name.clone(),
+                ..Default::default()
+            };
+            if let Some(ref mut layout) = cell.layout {
+                let before = layout.elems.len();
+                layout.elems.retain(
+                    |e| matches!(layerspec(&layers, e), Some(spec) if keep_layers.contains(&spec)),
+                );
+                stripped.elems_kept = layout.elems.len();
+                stripped.elems_removed = before - layout.elems.len();
+                stripped.insts_removed = layout.insts.drain(..).map(|i| i.inst_name).collect();
+                stripped.annotations_removed = layout.annotations.len();
+                layout.annotations.clear();
+            }
+            report.cells.push(stripped);
+        }
+        Ok(report)
+    }
+}
+/// Get the (layer, datatype) number-pair of [Element] `elem`, if its layer and purpose are defined in `layers`
+fn layerspec(layers: &Layers, elem: &Element) -> Option<(i16, i16)> {
+    let layer = layers.get(elem.layer)?;
+    let datatype = layer.num(&elem.purpose)?;
+    Some((layer.layernum, datatype))
+}
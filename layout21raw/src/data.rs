@@ -6,7 +6,7 @@
 //!
 
 // Std-Lib
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::Hash;
 
 // Crates.io
@@ -17,7 +17,7 @@ use slotmap::{new_key_type, SlotMap};
 use crate::{
     bbox::{BoundBox, BoundBoxTrait},
     error::{LayoutError, LayoutResult},
-    geom::{Point, Polygon, Shape, Transform, TransformTrait},
+    geom::{Dir, Point, Polygon, Rect, Shape, Transform, TransformTrait},
     utils::{Ptr, PtrList},
 };
 
@@ -36,7 +36,7 @@ new_key_type! {
 
 /// Distance Units Enumeration
 /// FIXME: deprecate in favor of [SiUnits]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Units {
     /// Micrometers, or microns for we olde folke
     Micro,
@@ -53,6 +53,31 @@ impl Default for Units {
         Units::Nano
     }
 }
+impl std::fmt::Display for Units {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Self::Micro => "Micro",
+            Self::Nano => "Nano",
+            Self::Angstrom => "Angstrom",
+            Self::Pico => "Pico",
+        };
+        write!(f, "{}", s)
+    }
+}
+impl std::str::FromStr for Units {
+    type Err = LayoutError;
+    /// Parse [Units], case-insensitively, from either their name or abbreviation,
+    /// e.g. `nano` or `nm`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "micro" | "um" => Ok(Self::Micro),
+            "nano" | "nm" => Ok(Self::Nano),
+            "angstrom" | "a" => Ok(Self::Angstrom),
+            "pico" | "pm" => Ok(Self::Pico),
+            _ => LayoutError::fail(format!("Invalid Units `{}`", s)),
+        }
+    }
+}
 /// Enumerated SI Units
 #[allow(dead_code)] // FIXME!
 enum SiUnits {
@@ -115,7 +140,14 @@ impl SiUnits {
 }
 
 /// Instance of another Cell
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Instances compare equal if all of their fields are equal, with two caveats:
+/// * Floating-point `angle`s are compared bitwise, so that comparisons are reflexive and [Eq] holds.
+/// * `cell`s are compared by pointer, or failing that by name,
+///   so that [Instance]s in separately created [Library]s can compare equal.
+///   Cell content is compared as part of its [Library].
+///
+#[derive(Debug, Clone)]
 pub struct Instance {
     /// Instance Name
     pub inst_name: String,
@@ -124,24 +156,106 @@ pub struct Instance {
     /// Location of `cell` origin
     /// regardless of rotation or reflection
     pub loc: Point,
-    /// Vertical reflection,
-    /// applied *before* rotation
+    /// Vertical reflection: mirror about the x-axis (negating y),
+    /// about the instance origin, and applied *before* rotation.
+    /// Equivalent to the GDSII `STRANS` reflection bit.
     pub reflect_vert: bool,
     /// Angle of rotation (degrees),
-    /// Clockwise and applied *after* reflection
+    /// Counter-clockwise about `loc`, and applied *after* reflection.
+    /// Only multiples of 90 degrees are supported by [Instance::transform] and its users.
     pub angle: Option<f64>,
 }
 
+impl Instance {
+    /// Create the [Transform] from `cell`'s coordinates to those of the instance's parent.
+    /// Shares its semantics with GDSII import and export, via [Transform::from_instance].
+    /// Fails for rotation angles which are not multiples of 90 degrees.
+    pub fn transform(&self) -> LayoutResult<Transform> {
+        if let Some(angle) = self.angle {
+            if angle % 90.0 != 0.0 {
+                return LayoutError::fail(format!(
+                    "Unsupported rotation angle {} for Instance {}. Angles must be multiples of 90 degrees.",
+                    angle, self.inst_name
+                ));
+            }
+        }
+        Ok(Transform::from_instance(
+            &self.loc,
+            self.reflect_vert,
+            self.angle,
+        ))
+    }
+    /// Create a rectangular [BoundBox] surrounding the instance, in its parent's coordinates.
+    /// Uses the cell's [Layout] if present, or otherwise its [Abstract] outline.
+    pub fn bbox(&self) -> LayoutResult<BoundBox> {
+        let trans = self.transform()?;
+        let cell = self.cell.read()?;
+        let bbox = if let Some(ref layout) = cell.layout {
+            layout.bbox()?
+        } else if let Some(ref abs) = cell.abs {
+            abs.outline.points.bbox()
+        } else {
+            BoundBox::empty()
+        };
+        if bbox.is_empty() {
+            return Ok(bbox);
+        }
+        // Transform all four corners, as rotations move each
+        let corners = [
+            bbox.p0,
+            Point::new(bbox.p1.x, bbox.p0.y),
+            bbox.p1,
+            Point::new(bbox.p0.x, bbox.p1.y),
+        ];
+        let corners: Vec<Point> = corners.iter().map(|p| p.transform(&trans)).collect();
+        Ok(corners.bbox())
+    }
+}
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        self.inst_name == other.inst_name
+            && self.loc == other.loc
+            && self.reflect_vert == other.reflect_vert
+            && angle_eq(self.angle, other.angle)
+            && cell_ptr_eq(&self.cell, &other.cell)
+    }
+}
+impl Eq for Instance {}
+/// Compare two optional rotation angles bitwise
+fn angle_eq(a: Option<f64>, b: Option<f64>) -> bool {
+    a.map(f64::to_bits) == b.map(f64::to_bits)
+}
+/// Compare two [Cell] pointers, by address or failing that by name.
+/// Cells with poisoned locks compare unequal.
+fn cell_ptr_eq(a: &Ptr<Cell>, b: &Ptr<Cell>) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.read(), b.read()) {
+        (Ok(a), Ok(b)) => a.name == b.name,
+        _ => false,
+    }
+}
+
 /// # Layer Set & Manager
 ///
 /// Keep track of active layers, and index them by name and number.
+/// Comparisons are by [Layer] content and [LayerKey], in key order.
 ///
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Layers {
     pub slots: SlotMap<LayerKey, Layer>,
-    pub nums: HashMap<i16, LayerKey>,
-    pub names: HashMap<String, LayerKey>,
+    pub nums: BTreeMap<i16, LayerKey>,
+    pub names: BTreeMap<String, LayerKey>,
 }
+impl PartialEq for Layers {
+    fn eq(&self, other: &Self) -> bool {
+        // `nums` and `names` are indices into `slots`, and need no separate comparison
+        self.slots.iter().eq(other.slots.iter())
+    }
+}
+impl Eq for Layers {}
 impl Layers {
     /// Add a [Layer] to our slot-map and number-map, and name-map
     pub fn add(&mut self, layer: Layer) -> LayerKey {
@@ -230,7 +344,7 @@ impl Layers {
 /// Layer-Purpose Enumeration
 /// Includes the common use-cases for each shape,
 /// and two "escape hatches", one named and one not.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LayerPurpose {
     // First-class enumerated purposes
     Drawing,
@@ -268,9 +382,9 @@ pub struct Layer {
     /// Layer Name
     pub name: Option<String>,
     /// Number => Purpose Lookup
-    purps: HashMap<i16, LayerPurpose>,
+    purps: BTreeMap<i16, LayerPurpose>,
     /// Purpose => Number Lookup
-    nums: HashMap<LayerPurpose, i16>,
+    nums: BTreeMap<LayerPurpose, i16>,
 }
 
 impl PartialOrd for Layer {
@@ -316,7 +430,8 @@ impl Layer {
         }
         Ok(self)
     }
-    /// Add a new [LayerPurpose]
+    /// Add a new [LayerPurpose].
+    /// Re-adding an existing mapping is a no-op. Fails if `num` is already mapped to a different purpose.
     pub fn add_purpose(&mut self, num: i16, purp: LayerPurpose) -> LayoutResult<()> {
         // If we get a numbered purpose, make sure its id matches `num`.
         match purp {
@@ -327,6 +442,14 @@ impl Layer {
             }
             _ => (),
         };
+        if let Some(existing) = self.purps.get(&num) {
+            if *existing != purp {
+                return LayoutError::fail(format!(
+                    "Datatype {} of layer {} is already {:?}, cannot add {:?}",
+                    num, self.layernum, existing, purp
+                ));
+            }
+        }
         self.purps.insert(num, purp.clone());
         self.nums.insert(purp, num);
         Ok(())
@@ -354,7 +477,7 @@ pub struct Abstract {
     /// Ports
     pub ports: Vec<AbstractPort>,
     /// Blockages
-    pub blockages: HashMap<LayerKey, Vec<Shape>>,
+    pub blockages: BTreeMap<LayerKey, Vec<Shape>>,
 }
 impl Abstract {
     /// Create a new [Abstract] with the given `name`
@@ -364,7 +487,7 @@ impl Abstract {
             name,
             outline,
             ports: Vec::new(),
-            blockages: HashMap::new(),
+            blockages: BTreeMap::new(),
         }
     }
 }
@@ -374,7 +497,7 @@ pub struct AbstractPort {
     /// Net Name
     pub net: String,
     /// Shapes, with paired [Layer] keys
-    pub shapes: HashMap<LayerKey, Vec<Shape>>,
+    pub shapes: BTreeMap<LayerKey, Vec<Shape>>,
 }
 impl AbstractPort {
     /// Create a new [AbstractPort] with the given `name`
@@ -382,13 +505,18 @@ impl AbstractPort {
         let net = net.into();
         Self {
             net,
-            shapes: HashMap::new(),
+            shapes: BTreeMap::new(),
         }
     }
 }
 
 /// # Raw Layout Library  
 /// A collection of cell-definitions and sub-library definitions
+///
+/// Libraries compare by content, including that of their [Layers] and [Cell]s,
+/// rather than by the addresses of their pointers.
+/// [Library::diff] reports which fields differ.
+///
 #[derive(Debug, Clone, Default)]
 pub struct Library {
     /// Library Name
@@ -409,6 +537,171 @@ impl Library {
             ..Default::default()
         }
     }
+    /// Get the [Cell] named `name`, if present
+    pub fn cell_named(&self, name: &str) -> LayoutResult<Option<Ptr<Cell>>> {
+        for ptr in self.cells.iter() {
+            if ptr.read()?.name == name {
+                return Ok(Some(ptr.clone()));
+            }
+        }
+        Ok(None)
+    }
+    /// Get the [Cell] named `top` and every [Cell] it references, directly or transitively.
+    /// Returned in the order of our `cells`. Fails if `top` is not defined.
+    pub fn dependencies(&self, top: &str) -> LayoutResult<Vec<Ptr<Cell>>> {
+        let topptr = self.top_cell(top)?;
+        let mut order = DepOrder {
+            lib: self,
+            stack: Vec::new(),
+            seen: HashSet::new(),
+        };
+        order.push(&topptr);
+        Ok(self
+            .cells
+            .iter()
+            .filter(|ptr| order.seen.contains(*ptr))
+            .cloned()
+            .collect())
+    }
+    /// Get every leaf [Placement] in the hierarchy of the [Cell] named `top`, in depth-first instance order.
+    ///
+    /// Leaves are cells with no instances, whether defined by a [Layout] or an [Abstract].
+    /// A `top` with no instances produces a single, identity-transformed [Placement] of itself.
+    /// Fails if `top` is not defined, or if any cell recursively instantiates itself.
+    pub fn placements(&self, top: &str) -> LayoutResult<Vec<Placement>> {
+        let topptr = self.top_cell(top)?;
+        let mut placements = Vec::new();
+        let mut visiting = Vec::new();
+        placements_helper(
+            &topptr,
+            top,
+            &Transform::identity(),
+            0,
+            &mut visiting,
+            &mut placements,
+        )?;
+        Ok(placements)
+    }
+    /// Get the [Cell] named `top`, or fail
+    fn top_cell(&self, top: &str) -> LayoutResult<Ptr<Cell>> {
+        match self.cell_named(top)? {
+            Some(ptr) => Ok(ptr),
+            None => LayoutError::fail(format!("Cell {} not found in Library {}", top, self.name)),
+        }
+    }
+    /// Compare against `other`, returning a list of the paths to each differing field,
+    /// e.g. `cells[1].layout.elems[3]`. Empty if the two are equal.
+    pub fn diff(&self, other: &Library) -> LayoutResult<Vec<String>> {
+        let mut diffs = Vec::new();
+        if self.name != other.name {
+            diffs.push("name".to_string());
+        }
+        if self.units != other.units {
+            diffs.push("units".to_string());
+        }
+        if self.layers != other.layers && *self.layers.read()? != *other.layers.read()? {
+            diffs.push("layers".to_string());
+        }
+        if self.cells.len() != other.cells.len() {
+            diffs.push("cells.len".to_string());
+        }
+        for (idx, (a, b)) in self.cells.iter().zip(other.cells.iter()).enumerate() {
+            if a == b {
+                continue; // Same pointer
+            }
+            let (a, b) = (a.read()?, b.read()?);
+            let path = format!("cells[{}]", idx);
+            if a.name != b.name {
+                diffs.push(format!("{}.name", path));
+            }
+            if a.abs != b.abs {
+                diffs.push(format!("{}.abs", path));
+            }
+            if a.props != b.props {
+                diffs.push(format!("{}.props", path));
+            }
+            match (&a.layout, &b.layout) {
+                (Some(a), Some(b)) => a.diff(b, &format!("{}.layout", path), &mut diffs),
+                (None, None) => (),
+                _ => diffs.push(format!("{}.layout", path)),
+            }
+        }
+        Ok(diffs)
+    }
+}
+impl PartialEq for Library {
+    fn eq(&self, other: &Self) -> bool {
+        match self.diff(other) {
+            Ok(diffs) => diffs.is_empty(),
+            Err(_) => false, // Poisoned locks compare unequal
+        }
+    }
+}
+impl Eq for Library {}
+
+/// # Flattened Placement
+///
+/// A leaf [Cell] placed somewhere in the hierarchy of a top-level cell, as produced by [Library::placements].
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// Instance path from the top-level cell, e.g. `top/u_core/u_alu`
+    pub path: String,
+    /// Name of the placed [Cell]
+    pub cell: String,
+    /// Number of instance-levels below the top-level cell
+    pub depth: usize,
+    /// Location of the cell's origin, in top-level coordinates
+    pub loc: Point,
+    /// Accumulated [Transform] from the cell's coordinates to those of the top-level cell
+    pub transform: Transform,
+}
+/// Internal helper and core logic for [Library::placements].
+fn placements_helper(
+    ptr: &Ptr<Cell>,
+    path: &str,
+    trans: &Transform,
+    depth: usize,
+    visiting: &mut Vec<Ptr<Cell>>,
+    placements: &mut Vec<Placement>,
+) -> LayoutResult<()> {
+    let cell = ptr.read()?;
+    let insts = match cell.layout {
+        Some(ref layout) if !layout.insts.is_empty() => &layout.insts,
+        _ => {
+            // A leaf. Add it and we're done.
+            placements.push(Placement {
+                path: path.to_string(),
+                cell: cell.name.clone(),
+                depth,
+                loc: Point::new(0, 0).transform(trans),
+                transform: *trans,
+            });
+            return Ok(());
+        }
+    };
+    visiting.push(ptr.clone());
+    for inst in insts.iter() {
+        if visiting.contains(&inst.cell) {
+            return LayoutError::fail(format!(
+                "Instance {} of Cell {} recursively instantiates one of its parent Cells",
+                inst.inst_name, cell.name
+            ));
+        }
+        // Cascade the parent's and instance's transforms, and recursively visit the instance
+        let inst_trans = Transform::cascade(trans, &inst.transform()?);
+        let inst_path = format!("{}/{}", path, inst.inst_name);
+        placements_helper(
+            &inst.cell,
+            &inst_path,
+            &inst_trans,
+            depth + 1,
+            visiting,
+            placements,
+        )?;
+    }
+    visiting.pop();
+    Ok(())
 }
 
 /// # Dependency-Orderer
@@ -450,7 +743,7 @@ impl<'lib> DepOrder<'lib> {
 }
 
 /// Collection of the Views describing a Cell
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Cell {
     // Cell Name
     pub name: String,
@@ -458,6 +751,9 @@ pub struct Cell {
     pub abs: Option<Abstract>,
     // Layout Implementation
     pub layout: Option<Layout>,
+    /// Cell-level properties, e.g. generator names, versions and parameter hashes.
+    /// Exported to GDSII as text annotations, per [GdsExportOptions::cell_props](crate::gds::GdsExportOptions::cell_props).
+    pub props: BTreeMap<String, String>,
 }
 impl Cell {
     /// Create a new and empty Cell named `name`
@@ -468,6 +764,20 @@ impl Cell {
             ..Default::default()
         }
     }
+    /// Coalesce the rectangles of our [Layout], if we have one, along `axis`. See [Layout::coalesce].
+    pub fn coalesce(&mut self, axis: Dir) {
+        if let Some(ref mut layout) = self.layout {
+            layout.coalesce(axis);
+        }
+    }
+    /// Remove zero-area elements from our [Layout], if we have one. See [Layout::drop_degenerate].
+    /// Returns the number of elements removed.
+    pub fn drop_degenerate(&mut self) -> usize {
+        match self.layout {
+            Some(ref mut layout) => layout.drop_degenerate(),
+            None => 0,
+        }
+    }
 }
 impl From<Abstract> for Cell {
     fn from(src: Abstract) -> Self {
@@ -493,7 +803,7 @@ impl From<Layout> for Cell {
 /// The geometric-level layout-definition of a [Cell].
 /// Comprised of geometric [Element]s and instances of other [Cell] [Layout]s.
 ///
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Layout {
     /// Cell Name
     pub name: String,
@@ -505,13 +815,20 @@ pub struct Layout {
     pub annotations: Vec<TextElement>,
 }
 impl Layout {
-    /// Create a rectangular [BoundBox] surrounding all elements in the [Layout].
-    pub fn bbox(&self) -> BoundBox {
+    /// Create a rectangular [BoundBox] surrounding all elements and instances in the [Layout].
+    /// Instance orientations are applied, and fail for angles which are not multiples of 90 degrees.
+    pub fn bbox(&self) -> LayoutResult<BoundBox> {
         let mut bbox = BoundBox::empty();
         for elem in &self.elems {
             bbox = elem.inner.union(&bbox);
         }
-        bbox
+        for inst in &self.insts {
+            let inst_bbox = inst.bbox()?;
+            if !inst_bbox.is_empty() {
+                bbox = bbox.union(&inst_bbox);
+            }
+        }
+        Ok(bbox)
     }
     /// Flatten a [Layout], particularly its hierarchical instances, to a vector of [Element]s
     pub fn flatten(&self) -> LayoutResult<Vec<Element>> {
@@ -520,6 +837,123 @@ impl Layout {
         flatten_helper(self, &Transform::identity(), &mut elems)?;
         Ok(elems)
     }
+    /// Remove zero-area elements, per [Shape::is_degenerate]. Returns the number removed.
+    ///
+    /// Such elements arise from e.g. cuts exactly covering a track segment, or zero-width track entries,
+    /// and are dropped by default during GDSII export.
+    pub fn drop_degenerate(&mut self) -> usize {
+        let before = self.elems.len();
+        self.elems.retain(|e| !e.inner.is_degenerate());
+        before - self.elems.len()
+    }
+    /// Merge [Rect] elements which abut or overlap along `axis`.
+    ///
+    /// Rectangles merge if they share a net, layer, and purpose, and have identical extents in the other direction.
+    /// Elements without a net are never merged, nor are those of other shapes,
+    /// nor those with [Label]s, whose positions are relative to their own extent.
+    /// Zero-area candidate rectangles are removed.
+    /// Each merged rectangle takes the place of the first of its constituents, keeping the order of all other elements.
+    /// As GDSII net labels are generated per element, this also leaves a single label per merged shape.
+    pub fn coalesce(&mut self, axis: Dir) {
+        // Group candidate rectangles by (net, layer, purpose, extent in the other direction),
+        // noting their indices and extents along `axis`
+        type GroupKey = (String, LayerKey, LayerPurpose, (Int, Int));
+        let mut groups: HashMap<GroupKey, Vec<(usize, Int, Int)>> = HashMap::new();
+        let span = |r: &Rect, dir: Dir| match dir {
+            Dir::Horiz => (r.p0.x.min(r.p1.x), r.p0.x.max(r.p1.x)),
+            Dir::Vert => (r.p0.y.min(r.p1.y), r.p0.y.max(r.p1.y)),
+        };
+        let mut removed: HashSet<usize> = HashSet::new();
+        for (idx, elem) in self.elems.iter().enumerate() {
+            if let (Some(net), Shape::Rect(r)) = (&elem.net, &elem.inner) {
+                if !elem.labels.is_empty() {
+                    continue;
+                }
+                if elem.inner.is_degenerate() {
+                    removed.insert(idx);
+                    continue;
+                }
+                let key = (
+                    net.clone(),
+                    elem.layer,
+                    elem.purpose.clone(),
+                    span(r, axis.other()),
+                );
+                let (lo, hi) = span(r, axis);
+                groups.entry(key).or_default().push((idx, lo, hi));
+            }
+        }
+        // Merge each group into runs of touching extents, recording the replacement for each run's first element,
+        // and marking the rest for removal
+        let mut replacements: HashMap<usize, Rect> = HashMap::new();
+        for ((_, _, _, (olo, ohi)), mut members) in groups {
+            if members.len() < 2 {
+                continue;
+            }
+            members.sort_by_key(|(idx, lo, _)| (*lo, *idx));
+            let mut runs: Vec<Vec<(usize, Int, Int)>> = Vec::new();
+            for member in members {
+                match runs.last_mut() {
+                    Some(run) if member.1 <= run.iter().map(|m| m.2).max().unwrap() => {
+                        run.push(member)
+                    }
+                    _ => runs.push(vec![member]),
+                }
+            }
+            for run in runs.into_iter().filter(|r| r.len() > 1) {
+                let first = run.iter().map(|m| m.0).min().unwrap();
+                let lo = run.iter().map(|m| m.1).min().unwrap();
+                let hi = run.iter().map(|m| m.2).max().unwrap();
+                let rect = match axis {
+                    Dir::Horiz => Rect {
+                        p0: Point::new(lo, olo),
+                        p1: Point::new(hi, ohi),
+                    },
+                    Dir::Vert => Rect {
+                        p0: Point::new(olo, lo),
+                        p1: Point::new(ohi, hi),
+                    },
+                };
+                replacements.insert(first, rect);
+                removed.extend(run.iter().map(|m| m.0).filter(|idx| *idx != first));
+            }
+        }
+        if replacements.is_empty() && removed.is_empty() {
+            return;
+        }
+        let elems = std::mem::take(&mut self.elems);
+        for (idx, mut elem) in elems.into_iter().enumerate() {
+            if removed.contains(&idx) {
+                continue;
+            }
+            if let Some(rect) = replacements.remove(&idx) {
+                elem.inner = Shape::Rect(rect);
+            }
+            self.elems.push(elem);
+        }
+    }
+    /// Add the paths of each field which differs from `other` to `diffs`, prefixed by `path`
+    fn diff(&self, other: &Layout, path: &str, diffs: &mut Vec<String>) {
+        if self.name != other.name {
+            diffs.push(format!("{}.name", path));
+        }
+        diff_vecs(&self.insts, &other.insts, &format!("{}.insts", path), diffs);
+        diff_vecs(&self.elems, &other.elems, &format!("{}.elems", path), diffs);
+        let annotations = format!("{}.annotations", path);
+        diff_vecs(&self.annotations, &other.annotations, &annotations, diffs);
+    }
+}
+/// Add the paths of each differing entry between `a` and `b` to `diffs`, or of their lengths if they differ
+fn diff_vecs<T: PartialEq>(a: &[T], b: &[T], path: &str, diffs: &mut Vec<String>) {
+    if a.len() != b.len() {
+        diffs.push(format!("{}.len", path));
+        return;
+    }
+    for (idx, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+        if a != b {
+            diffs.push(format!("{}[{}]", path, idx));
+        }
+    }
 }
 /// Internal helper and core logic for [Layout::flatten].
 fn flatten_helper(
@@ -532,8 +966,9 @@ fn flatten_helper(
         // Clone all other data (layer, net, etc.)
         // FIXME: hierarchy flattening of net labels
         let mut new_elem = elem.clone();
-        // And translate the inner shape by `trans`
+        // And translate the inner shape by `trans`, restoring its canonical form
         new_elem.inner = elem.inner.transform(trans);
+        new_elem.inner.normalize();
         elems.push(new_elem);
     }
     // Note text-valued "annotations" are ignored
@@ -545,7 +980,7 @@ fn flatten_helper(
         let layout = cell.layout.as_ref().unwrap();
 
         // Create a new [Transform], cascading the parent's and instance's
-        let inst_trans = Transform::from_instance(&inst.loc, inst.reflect_vert, inst.angle);
+        let inst_trans = inst.transform()?;
         let trans = Transform::cascade(&trans, &inst_trans);
 
         // And recursively add its elements
@@ -584,25 +1019,91 @@ pub struct Element {
     pub purpose: LayerPurpose,
     /// Shape
     pub inner: Shape,
+    /// Additional text [Label]s, beyond that of our `net`.
+    /// Exported to GDSII as text elements; not represented in ProtoBuf.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Label>,
 }
+/// # Element Label
+///
+/// Text label on an [Element], in addition to the one generated for its `net`.
+/// Labels are annotations only: connectivity remains solely set by the element's `net`.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    /// Label Text
+    pub text: String,
+    /// Layer purpose, overriding the default [LayerPurpose::Label]
+    pub purpose: Option<LayerPurpose>,
+    /// Position along the element, as a fraction of its length.
+    /// Zero is its start, i.e. its bottom or left end, and one its end. Clamped to this range.
+    pub pos: f64,
+}
+impl Label {
+    /// Create a new [Label] with text `text`, at fractional position `pos`
+    pub fn new(text: impl Into<String>, pos: f64) -> Self {
+        Self {
+            text: text.into(),
+            purpose: None,
+            pos,
+        }
+    }
+    /// Get our location on [Shape] `shape`.
+    /// Runs along the longer dimension of its bounding box, centered in the shorter one.
+    pub fn location(&self, shape: &Shape) -> Point {
+        let bbox = shape.bbox();
+        let pos = if self.pos.is_nan() {
+            0.0
+        } else {
+            self.pos.clamp(0.0, 1.0)
+        };
+        let along = |lo: Int, hi: Int| lo + ((hi - lo) as f64 * pos).round() as Int;
+        let (x0, x1, y0, y1) = (bbox.p0.x, bbox.p1.x, bbox.p0.y, bbox.p1.y);
+        if x1 - x0 >= y1 - y0 {
+            Point::new(along(x0, x1), (y0 + y1) / 2)
+        } else {
+            Point::new((x0 + x1) / 2, along(y0, y1))
+        }
+    }
+}
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+            && self.purpose == other.purpose
+            && self.pos.to_bits() == other.pos.to_bits()
+    }
+}
+impl Eq for Label {}
 
 /// Location, orientation, and angular rotation for an [Instance]
 /// Note these fields exist "flat" in [Instance] as well,
 /// and are grouped here for convenience.
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy)]
 pub struct InstancePlace {
     /// Location of `cell` origin
     /// regardless of rotation or reflection
     pub loc: Point,
-    /// Vertical reflection,
-    /// applied *before* rotation
+    /// Vertical reflection: mirror about the x-axis (negating y),
+    /// about the instance origin, and applied *before* rotation.
+    /// Equivalent to the GDSII `STRANS` reflection bit.
     pub reflect_vert: bool,
     /// Angle of rotation (degrees),
-    /// Clockwise and applied *after* reflection
+    /// Counter-clockwise about `loc`, and applied *after* reflection.
+    /// Only multiples of 90 degrees are supported by [Instance::transform] and its users.
     pub angle: Option<f64>,
 }
 
+impl PartialEq for InstancePlace {
+    /// Compare, with `angle`s compared bitwise, as for [Instance]
+    fn eq(&self, other: &Self) -> bool {
+        self.loc == other.loc
+            && self.reflect_vert == other.reflect_vert
+            && angle_eq(self.angle, other.angle)
+    }
+}
+impl Eq for InstancePlace {}
+
 // pub struct Flatten<'l> {
 //     lib: &'l Library,
 //     top: &'l Layout,
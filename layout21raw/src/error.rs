@@ -2,6 +2,9 @@
 //! # Layout Result and Error Types
 //!
 
+// Std-Lib Imports
+use std::path::PathBuf;
+
 // Local Imports
 pub use crate::utils::{self, ErrorContext};
 
@@ -36,6 +39,15 @@ pub enum LayoutError {
     /// Caused by trouble with a [Ptr]: either deadlock, or panic while holding a lock.
     /// Generally caused by a [std::sync::PoisonError], which is not forwardable due to lifetime constraints.
     PtrLock,
+    /// # Cancellation
+    /// Operation stopped early, at the request of a [CancelToken](utils::CancelToken)
+    Cancelled,
+    /// # Unwritable Output
+    /// Output location `path` cannot be written, e.g. as found ahead of a lengthy export
+    Unwritable { path: PathBuf, message: String },
+    /// # Invalid Output Path
+    /// Output `path` is not valid on the host OS, e.g. due to invalid characters or excessive length
+    InvalidPath { path: PathBuf, message: String },
 }
 impl LayoutError {
     /// Create a [LayoutError::Message] from anything String-convertible
@@ -69,6 +81,13 @@ impl std::fmt::Debug for LayoutError {
             LayoutError::Boxed(err) => err.fmt(f),
             LayoutError::Str(err) => err.fmt(f),
             LayoutError::PtrLock => write!(f, "[std::sync::PoisonError]"),
+            LayoutError::Cancelled => write!(f, "Cancelled"),
+            LayoutError::Unwritable { path, message } => {
+                write!(f, "Cannot write {}: {}", path.display(), message)
+            }
+            LayoutError::InvalidPath { path, message } => {
+                write!(f, "Invalid path {}: {}", path.display(), message)
+            }
         }
     }
 }
@@ -103,6 +122,11 @@ impl From<std::num::TryFromIntError> for LayoutError {
         Self::Boxed(Box::new(e))
     }
 }
+impl From<std::io::Error> for LayoutError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Boxed(Box::new(e))
+    }
+}
 impl From<utils::ser::Error> for LayoutError {
     fn from(e: utils::ser::Error) -> Self {
         Self::Boxed(Box::new(e))
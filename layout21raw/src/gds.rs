@@ -5,22 +5,23 @@
 //!
 
 // Std-Lib
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::hash::Hash;
 
-use gds21::GdsElement;
+use gds21::{GdsElement, HasLayer};
 // Crates.io
+use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, SlotMap};
 
 // Local imports
 use crate::{
-    bbox::BoundBoxTrait,
+    bbox::{BoundBox, BoundBoxTrait},
     error::{LayoutError, LayoutResult},
-    geom::{Path, Point, Polygon, Rect, Shape, ShapeTrait},
-    utils::{ErrorContext, ErrorHelper, Ptr, Unwrapper},
-    Abstract, AbstractPort, Cell, Dir, Element, Instance, Int, LayerKey, LayerPurpose, Layers,
-    Layout, Library, TextElement, Units,
+    geom::{Path, Point, Polygon, Rect, Shape, ShapeTrait, Transform},
+    utils::{fnv1a, ErrorContext, ErrorHelper, ProgressHook, Ptr, PtrList, Unwrapper},
+    Abstract, AbstractPort, Cell, Dir, Element, Instance, Int, Label, LayerKey, LayerMap,
+    LayerPurpose, Layers, Layout, Library, TextElement, Units,
 };
 pub use gds21;
 
@@ -30,6 +31,77 @@ impl Library {
     pub fn to_gds(&self) -> LayoutResult<gds21::GdsLibrary> {
         GdsExporter::export(&self)
     }
+    /// Convert to a GDSII Library, with [GdsExportOptions] `opts`
+    pub fn to_gds_with_options(&self, opts: &GdsExportOptions) -> LayoutResult<gds21::GdsLibrary> {
+        GdsExporter::export_with_options(&self, opts)
+    }
+    /// Convert to a GDSII Library, with [GdsExportOptions] `opts`.
+    /// Also returns a [GdsExportReport], e.g. of any labels truncated per [GdsExportOptions::label_limit].
+    pub fn to_gds_with_report(
+        &self,
+        opts: &GdsExportOptions,
+    ) -> LayoutResult<(gds21::GdsLibrary, GdsExportReport)> {
+        GdsExporter::export_with_report(self, opts)
+    }
+    /// Convert the [Cell] named `top`, and only the cells it depends upon, to a GDSII Library,
+    /// with [GdsExportOptions] `opts`. Fails if `top` is not defined.
+    pub fn to_gds_cell(
+        &self,
+        top: &str,
+        opts: &GdsExportOptions,
+    ) -> LayoutResult<gds21::GdsLibrary> {
+        // Create a shallow copy of ourselves, sharing our cells, with only `top`'s dependencies
+        let subset = Library {
+            name: self.name.clone(),
+            units: self.units,
+            layers: self.layers.clone(),
+            cells: PtrList::from_ptrs(self.dependencies(top)?),
+        };
+        GdsExporter::export_with_options(&subset, opts)
+    }
+    /// Export each [Cell] named in `tops` to its own GDSII file in directory `dir`, named after the cell,
+    /// e.g. `dir/inv.gds`, with [GdsExportOptions] `opts`. Returns the paths written, in the order of `tops`.
+    ///
+    /// Each file includes its top cell and all the cells it depends upon, per [Library::to_gds_cell].
+    /// Dependencies shared between several `tops` are duplicated into each of their files.
+    /// All names are checked before any file is written, failing if any are not defined.
+    /// Creates `dir` and any missing parents.
+    pub fn export_each(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+        tops: &[&str],
+        opts: &GdsExportOptions,
+    ) -> LayoutResult<Vec<std::path::PathBuf>> {
+        let missing: Vec<&str> = tops
+            .iter()
+            .copied()
+            .filter(|top| !matches!(self.cell_named(top), Ok(Some(_))))
+            .collect();
+        if !missing.is_empty() {
+            return LayoutError::fail(format!(
+                "Cells not found in Library {}: {}",
+                self.name,
+                missing.join(", ")
+            ));
+        }
+        // Create the output directory, if it does not already exist
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| {
+            LayoutError::msg(format!(
+                "Could not create output directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        let mut paths = Vec::with_capacity(tops.len());
+        for top in tops.iter() {
+            let gds = self.to_gds_cell(top, opts)?;
+            let path = dir.join(format!("{}.gds", top));
+            gds.save(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
     /// Create from GDSII
     pub fn from_gds(
         gdslib: &gds21::GdsLibrary,
@@ -37,6 +109,25 @@ impl Library {
     ) -> LayoutResult<Library> {
         GdsImporter::import(&gdslib, layers)
     }
+    /// Create from GDSII, with [Layers] defined by [LayerMap] `layermap`, per [GdsImportOptions::layermap]
+    pub fn from_gds_with_layermap(
+        gdslib: &gds21::GdsLibrary,
+        layermap: &LayerMap,
+    ) -> LayoutResult<Library> {
+        let opts = GdsImportOptions {
+            layermap: Some(layermap.clone()),
+            ..Default::default()
+        };
+        GdsImporter::import_with_options(gdslib, None, &opts)
+    }
+    /// Create from GDSII, with [GdsImportOptions] `opts`
+    pub fn from_gds_with_options(
+        gdslib: &gds21::GdsLibrary,
+        layers: Option<Ptr<Layers>>,
+        opts: &GdsImportOptions,
+    ) -> LayoutResult<Library> {
+        GdsImporter::import_with_options(gdslib, layers, opts)
+    }
 }
 
 new_key_type! {
@@ -50,6 +141,206 @@ impl From<gds21::GdsError> for LayoutError {
     }
 }
 
+/// Convert [Instance] orientation fields `reflect_vert` and `angle` to a [gds21::GdsStrans],
+/// or `None` if neither reflected nor rotated.
+///
+/// The GDSII `reflected` flag, like `reflect_vert`, mirrors about the x-axis before rotation.
+/// Both therefore share the semantics of [Transform::from_instance]. The inverse of [import_strans].
+pub fn export_strans(reflect_vert: bool, angle: Option<f64>) -> Option<gds21::GdsStrans> {
+    if !reflect_vert && angle.is_none() {
+        return None;
+    }
+    Some(gds21::GdsStrans {
+        reflected: reflect_vert,
+        angle,
+        ..Default::default()
+    })
+}
+/// Convert [gds21::GdsStrans] `strans` to [Instance] orientation fields `(reflect_vert, angle)`.
+/// Magnification and absolute settings are ignored. The inverse of [export_strans].
+pub fn import_strans(strans: &Option<gds21::GdsStrans>) -> (bool, Option<f64>) {
+    match strans {
+        Some(s) => (s.reflected, s.angle),
+        None => (false, None),
+    }
+}
+
+/// # GDSII Export Options
+#[derive(Debug, Clone, Default)]
+pub struct GdsExportOptions {
+    /// Cell-name labels, placed on each cell's boundary. Disabled if `None`.
+    pub cell_labels: Option<CellLabelOptions>,
+    /// Size limits
+    pub limits: GdsExportLimits,
+    /// Net-name label orientation and placement
+    pub net_labels: NetLabelOptions,
+    /// Progress callbacks, invoked as each cell is exported
+    pub progress: ProgressHook,
+    /// Keep zero-area elements, e.g. for debugging. By default these are dropped,
+    /// as by [Layout::drop_degenerate].
+    pub keep_degenerate: bool,
+    /// (Layer, datatype) number-pairs whose elements are omitted, along with their net labels,
+    /// e.g. debug overlays which must not reach tape-out. Empty by default.
+    pub exclude_layers: Vec<(i16, i16)>,
+    /// (Layer, texttype) number-pair of cell-property annotations.
+    /// If set, each of a cell's [Cell::props] is exported as a `key=value` text element at its origin.
+    /// GDSII has no struct-level properties, so these are otherwise omitted. Disabled if `None`.
+    pub cell_props: Option<(i16, i16)>,
+    /// Maximum net-label length, and handling of longer labels. Unlimited if `None`.
+    pub label_limit: Option<LabelLimit>,
+}
+/// # Label-Length Limit
+///
+/// Guardrail for consumers which reject or silently truncate long text strings.
+/// Applies to the net-name labels of [Element]s and [AbstractPort]s.
+/// Cell-name labels and cell properties are not affected.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LabelLimit {
+    /// Maximum label length, in characters
+    pub max_len: usize,
+    /// Handling of labels longer than `max_len`
+    pub policy: LongLabelPolicy,
+}
+/// # Long-Label Policy
+///
+/// Handling of labels exceeding [LabelLimit::max_len].
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongLabelPolicy {
+    /// Fail export, listing every label over the limit
+    #[default]
+    Error,
+    /// Truncate, replacing each label's tail with `_` and a deterministic 6-character hash of its full text.
+    /// Distinct labels therefore remain distinct. The mapping is reported in [GdsExportReport::labels].
+    Truncate,
+    /// Export labels as-is, regardless of length
+    Allow,
+}
+/// # GDSII Export Report
+///
+/// Summary of adjustments made during export, returned by [Library::to_gds_with_report].
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GdsExportReport {
+    /// Map from each truncated label's original text to its exported text,
+    /// per [LongLabelPolicy::Truncate]
+    pub labels: BTreeMap<String, String>,
+    /// Non-fatal issues, e.g. cell views named differently from their cells.
+    /// Each is also printed to the console, for callers of the report-less export methods.
+    pub warnings: Vec<String>,
+}
+impl GdsExportReport {
+    /// Format `labels` as a text table, one `exported original` pair per line, sorted by original text
+    pub fn labels_table(&self) -> String {
+        self.labels
+            .iter()
+            .map(|(orig, short)| format!("{} {}\n", short, orig))
+            .collect()
+    }
+}
+/// # GDSII Export Limits
+///
+/// Guardrails against accidentally exporting enormous libraries.
+/// Exceeding any limit aborts export with an error naming the offending cell.
+/// Each limit defaults to `None`, i.e. unlimited.
+///
+#[derive(Debug, Clone, Default)]
+pub struct GdsExportLimits {
+    /// Maximum number of [gds21::GdsElement]s in any single struct
+    pub max_cell_elems: Option<usize>,
+    /// Maximum number of [gds21::GdsElement]s across all structs
+    pub max_total_elems: Option<usize>,
+    /// Maximum number of structs
+    pub max_structs: Option<usize>,
+}
+/// # Net-Label Options
+///
+/// Orientation and placement of the text-labels of each net-assigned [Element] and [AbstractPort] shape.
+/// Labels are rotated 90 degrees for mostly-vertical shapes,
+/// i.e. those whose height exceeds `rotate_ratio` times their width,
+/// unless their layer has a fixed orientation in `orientations`.
+///
+#[derive(Debug, Clone)]
+pub struct NetLabelOptions {
+    /// Height-to-width ratio above which labels are rotated to vertical
+    pub rotate_ratio: f64,
+    /// Fixed per-layer orientations, overriding `rotate_ratio`
+    pub orientations: BTreeMap<LayerKey, Dir>,
+    /// Label placement within each shape
+    pub placement: LabelPlacement,
+}
+impl Default for NetLabelOptions {
+    fn default() -> Self {
+        Self {
+            rotate_ratio: 1.0,
+            orientations: BTreeMap::new(),
+            placement: LabelPlacement::default(),
+        }
+    }
+}
+impl NetLabelOptions {
+    /// Get the label orientation for [Shape] `shape` on layer `layer`
+    pub fn orientation(&self, shape: &Shape, layer: &LayerKey) -> Dir {
+        if let Some(dir) = self.orientations.get(layer) {
+            return *dir;
+        }
+        match shape {
+            Shape::Rect(r) => {
+                let width = (r.p1.x - r.p0.x).abs() as f64;
+                let height = (r.p1.y - r.p0.y).abs() as f64;
+                if height > self.rotate_ratio * width {
+                    Dir::Vert
+                } else {
+                    Dir::Horiz
+                }
+            }
+            _ => shape.orientation(),
+        }
+    }
+    /// Get the label location for [Shape] `shape`, per our [LabelPlacement]
+    pub fn location(&self, shape: &Shape) -> LayoutResult<Point> {
+        match (self.placement, shape) {
+            (LabelPlacement::Start, Shape::Rect(r)) => {
+                let (x0, x1) = (r.p0.x.min(r.p1.x), r.p0.x.max(r.p1.x));
+                let (y0, y1) = (r.p0.y.min(r.p1.y), r.p0.y.max(r.p1.y));
+                // Center the label in the square at the shape's bottom or left end
+                if x1 - x0 >= y1 - y0 {
+                    Ok(Point::new(x0 + (y1 - y0) / 2, (y0 + y1) / 2))
+                } else {
+                    Ok(Point::new((x0 + x1) / 2, y0 + (x1 - x0) / 2))
+                }
+            }
+            _ => shape.label_location(),
+        }
+    }
+}
+/// # Net-Label Placement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelPlacement {
+    /// At the shape's center
+    #[default]
+    Center,
+    /// Near the start of the shape, i.e. its bottom or left end.
+    /// Applies to rectangles; other shapes are labeled per [LabelPlacement::Center].
+    Start,
+}
+/// # Cell-Name Label Options
+///
+/// Settings for text-labels of each cell's name, placed at the center of its boundary.
+/// Boundaries are the outlines of [Abstract]s,
+/// and the first [LayerPurpose::Outline] [Element] of each [Layout].
+/// Layouts without such an element are not labeled.
+///
+#[derive(Debug, Clone, Default)]
+pub struct CellLabelOptions {
+    /// Label layer and purpose.
+    /// Defaults to the [LayerPurpose::Label] datatype of each boundary's layer.
+    pub layer: Option<(LayerKey, LayerPurpose)>,
+    /// Text magnification
+    pub mag: Option<f64>,
+}
+
 /// # Gds21 Exporter
 /// Converts a [raw::Library] to a GDSII library ([gds21::GdsLibrary]).
 /// The sole valid top-level entity for conversion is always a [Library].
@@ -57,16 +348,57 @@ impl From<gds21::GdsError> for LayoutError {
 pub struct GdsExporter<'lib> {
     /// Source [Library]
     lib: &'lib Library,
+    /// Export Options
+    opts: GdsExportOptions,
+    /// Number of elements exported so far, across all structs
+    total_elems: usize,
+    /// Map from truncated labels' original text to their exported text
+    labels: BTreeMap<String, String>,
+    /// Labels over our [LabelLimit], collected for reporting per [LongLabelPolicy::Error]
+    long_labels: BTreeSet<String>,
+    /// Non-fatal issues, collected for [GdsExportReport::warnings]
+    warnings: Vec<String>,
     ctx: Vec<ErrorContext>,
 }
 impl<'lib> GdsExporter<'lib> {
     /// Export `lib` to a GDSII library.
     pub fn export(lib: &'lib Library) -> LayoutResult<gds21::GdsLibrary> {
-        Self {
+        Self::export_with_options(lib, &GdsExportOptions::default())
+    }
+    /// Export `lib` to a GDSII library, with [GdsExportOptions] `opts`.
+    pub fn export_with_options(
+        lib: &'lib Library,
+        opts: &GdsExportOptions,
+    ) -> LayoutResult<gds21::GdsLibrary> {
+        Ok(Self::export_with_report(lib, opts)?.0)
+    }
+    /// Export `lib` to a GDSII library, with [GdsExportOptions] `opts`, along with a [GdsExportReport].
+    pub fn export_with_report(
+        lib: &'lib Library,
+        opts: &GdsExportOptions,
+    ) -> LayoutResult<(gds21::GdsLibrary, GdsExportReport)> {
+        let mut exporter = Self {
             lib,
+            opts: opts.clone(),
+            total_elems: 0,
+            labels: BTreeMap::new(),
+            long_labels: BTreeSet::new(),
+            warnings: Vec::new(),
             ctx: Vec::new(),
-        }
-        .export_lib()
+        };
+        let gdslib = exporter.export_lib()?;
+        let report = GdsExportReport {
+            labels: exporter.labels,
+            warnings: exporter.warnings,
+        };
+        Ok((gdslib, report))
+    }
+    /// Generate a warning.
+    /// Collected for [GdsExportReport::warnings], and printed to the console.
+    fn warn(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        eprintln!("Warning: {}", msg);
+        self.warnings.push(msg);
     }
     /// Primary internal method for exporting [Library] `self.lib`.
     fn export_lib(&mut self) -> LayoutResult<gds21::GdsLibrary> {
@@ -82,12 +414,36 @@ impl<'lib> GdsExporter<'lib> {
             Units::Pico => gds21::GdsUnits::new(1e-6, 1e-12),
         };
         // And convert each of our `cells` into its `structs`
-        for cell in self.lib.cells.iter() {
+        let total = self.lib.cells.len();
+        for (idx, cell) in self.lib.cells.iter().enumerate() {
             let cell = cell.read()?;
-            if let Some(strukt) = self.export_cell(&*cell)? {
+            self.opts.progress.on_cell_start(&cell.name, idx, total);
+            let strukt = self.export_cell(&*cell)?;
+            let nelems = strukt.as_ref().map_or(0, |s| s.elems.len());
+            self.opts.progress.on_cell_done(&cell.name, nelems);
+            if let Some(strukt) = strukt {
+                if let Some(max) = self.opts.limits.max_structs {
+                    if gdslib.structs.len() >= max {
+                        return self.fail(format!(
+                            "Cell {} would be struct number {}, exceeding the limit of {} structs",
+                            cell.name,
+                            gdslib.structs.len() + 1,
+                            max
+                        ));
+                    }
+                }
                 gdslib.structs.push(strukt);
             }
         }
+        if !self.long_labels.is_empty() {
+            let max_len = self.opts.label_limit.map_or(0, |l| l.max_len);
+            let long: Vec<&str> = self.long_labels.iter().map(|s| s.as_str()).collect();
+            return self.fail(format!(
+                "Labels longer than the limit of {} characters: {}",
+                max_len,
+                long.join(", ")
+            ));
+        }
         self.ctx.pop();
         Ok(gdslib)
     }
@@ -100,7 +456,10 @@ impl<'lib> GdsExporter<'lib> {
     fn export_cell(&mut self, cell: &Cell) -> LayoutResult<Option<gds21::GdsStruct>> {
         self.ctx.push(ErrorContext::Cell(cell.name.clone()));
 
-        let strukt_option = if let Some(ref lay) = cell.layout {
+        let mut strukt_option = if let Some(ref lay) = cell.layout {
+            // Check our element-limits before converting,
+            // using the fact that each [Instance] and [Element] produces at least one GDS element
+            self.check_elem_limits(&cell.name, lay.elems.len() + lay.insts.len())?;
             // If the cell has a layout implementation, export that
             Some(self.export_layout(lay)?)
         } else if let Some(ref a) = cell.abs {
@@ -116,9 +475,69 @@ impl<'lib> GdsExporter<'lib> {
             None
         };
 
+        // Add any cell-property annotations
+        if let (Some(ref mut strukt), Some((layer, texttype))) =
+            (&mut strukt_option, self.opts.cell_props)
+        {
+            for (key, value) in cell.props.iter() {
+                if key.contains('=') {
+                    return self.fail(format!(
+                        "Cell {} property {} includes an '=', which cannot be exported",
+                        cell.name, key
+                    ));
+                }
+                strukt.elems.push(
+                    gds21::GdsTextElem {
+                        string: format!("{}={}", key, value),
+                        layer,
+                        texttype,
+                        xy: gds21::GdsPoint::new(0, 0),
+                        ..Default::default()
+                    }
+                    .into(),
+                );
+            }
+        }
+        // Name the struct after the cell, which its instances reference, regardless of its views' names
+        if let Some(ref mut strukt) = strukt_option {
+            if strukt.name != cell.name {
+                self.warn(format!(
+                    "View of Cell {} is named {}; exported as {}",
+                    cell.name, strukt.name, cell.name
+                ));
+                strukt.name = cell.name.clone();
+            }
+        }
+        // Check the element-limits against the converted struct, and update our total count
+        if let Some(ref strukt) = strukt_option {
+            self.check_elem_limits(&cell.name, strukt.elems.len())?;
+            self.total_elems += strukt.elems.len();
+        }
         self.ctx.pop();
         Ok(strukt_option)
     }
+    /// Check whether adding a struct with `num_elems` elements, for cell `name`, would exceed our [GdsExportLimits]
+    fn check_elem_limits(&self, name: &str, num_elems: usize) -> LayoutResult<()> {
+        if let Some(max) = self.opts.limits.max_cell_elems {
+            if num_elems > max {
+                return self.fail(format!(
+                    "Cell {} has {} elements, exceeding the per-cell limit of {}",
+                    name, num_elems, max
+                ));
+            }
+        }
+        if let Some(max) = self.opts.limits.max_total_elems {
+            if self.total_elems + num_elems > max {
+                return self.fail(format!(
+                    "Cell {} brings the total element count to {}, exceeding the limit of {}",
+                    name,
+                    self.total_elems + num_elems,
+                    max
+                ));
+            }
+        }
+        Ok(())
+    }
     /// Convert a [Abstract]
     fn export_abstract(&mut self, abs: &Abstract) -> LayoutResult<gds21::GdsStruct> {
         self.ctx.push(ErrorContext::Abstract);
@@ -144,6 +563,17 @@ impl<'lib> GdsExporter<'lib> {
         // Conversion includes the abstract's name, outline and ports.
         elems.push(outline);
 
+        // Add the optional cell-name label
+        if let Some(label_opts) = self.opts.cell_labels.clone() {
+            // Abstract outlines have no [Layer], so default to the same numbers as the outline
+            let layerspec = match label_opts.layer {
+                Some((ref layer, ref purpose)) => self.export_layerspec(layer, purpose)?,
+                None => gds21::GdsLayerSpec::new(i16::MAX, i16::MAX),
+            };
+            let outline = Shape::Polygon(abs.outline.clone());
+            elems.push(self.export_cell_label(&abs.name, &outline, &layerspec, label_opts.mag)?);
+        }
+
         // Convert each [AbstractPort]
         for port in abs.ports.iter() {
             elems.extend(self.export_abstract_port(&port)?);
@@ -169,7 +599,7 @@ impl<'lib> GdsExporter<'lib> {
             for shape in shapes.iter() {
                 elems.push(self.export_shape(shape, &drawing_spec)?);
                 elems.push(self.export_shape(shape, &pin_spec)?);
-                elems.push(self.export_shape_label(&port.net, shape, &label_spec)?);
+                elems.push(self.export_shape_label(&port.net, shape, layerkey, &label_spec)?);
             }
         }
         Ok(elems)
@@ -186,10 +616,30 @@ impl<'lib> GdsExporter<'lib> {
         // Note each can produce more than one [GdsElement]
         self.ctx.push(ErrorContext::Geometry);
         for elem in cell.elems.iter() {
+            if !self.opts.keep_degenerate && elem.inner.is_degenerate() {
+                continue;
+            }
             for gdselem in self.export_element(elem)?.into_iter() {
                 elems.push(gdselem);
             }
         }
+        // Add the optional cell-name label, if the layout has a boundary
+        let boundary = cell
+            .elems
+            .iter()
+            .find(|e| e.purpose == LayerPurpose::Outline);
+        if let (Some(label_opts), Some(boundary)) = (self.opts.cell_labels.clone(), boundary) {
+            let (layer, purpose) = label_opts
+                .layer
+                .unwrap_or((boundary.layer, LayerPurpose::Label));
+            let layerspec = self.export_layerspec(&layer, &purpose)?;
+            elems.push(self.export_cell_label(
+                &cell.name,
+                &boundary.inner,
+                &layerspec,
+                label_opts.mag,
+            )?);
+        }
         self.ctx.pop();
         // Create and return a [GdsStruct]
         let mut strukt = gds21::GdsStruct::new(&cell.name);
@@ -202,15 +652,7 @@ impl<'lib> GdsExporter<'lib> {
         self.ctx
             .push(ErrorContext::Instance(inst.inst_name.clone()));
         // Convert the orientation to a [gds21::GdsStrans] option
-        let mut strans = None;
-        if inst.reflect_vert || inst.angle.is_some() {
-            let angle = inst.angle.map(|a| f64::from(a));
-            strans = Some(gds21::GdsStrans {
-                reflected: inst.reflect_vert,
-                angle,
-                ..Default::default()
-            });
-        }
+        let strans = export_strans(inst.reflect_vert, inst.angle);
         let cell = inst.cell.read()?;
         let gdsinst = gds21::GdsStructRef {
             name: cell.name.clone(),
@@ -252,16 +694,48 @@ impl<'lib> GdsExporter<'lib> {
     pub fn export_element(&mut self, elem: &Element) -> LayoutResult<Vec<gds21::GdsElement>> {
         // Get the element's layer-numbers pair
         let layerspec = self.export_layerspec(&elem.layer, &elem.purpose)?;
+        if self
+            .opts
+            .exclude_layers
+            .contains(&(layerspec.layer, layerspec.xtype))
+        {
+            return Ok(Vec::new());
+        }
         // Convert its core inner [Shape]
         let mut gds_elems = vec![self.export_shape(&elem.inner, &layerspec)?];
         // If there's an assigned net, create a corresponding text-element
         if let Some(name) = &elem.net {
             // Get the element's layer-numbers pair
             let layerspec = self.export_layerspec(&elem.layer, &LayerPurpose::Label)?;
-            gds_elems.push(self.export_shape_label(name, &elem.inner, &layerspec)?);
+            gds_elems.push(self.export_shape_label(name, &elem.inner, &elem.layer, &layerspec)?);
+        }
+        // And one for each of its additional labels
+        for label in elem.labels.iter() {
+            gds_elems.push(self.export_element_label(label, elem)?);
         }
         Ok(gds_elems)
     }
+    /// Convert [Label] `label` of [Element] `elem` to a [gds21::GdsTextElem].
+    /// Oriented as for net labels, per our [NetLabelOptions].
+    /// Unlike net labels, these are not subject to our [LabelLimit].
+    pub fn export_element_label(
+        &mut self,
+        label: &Label,
+        elem: &Element,
+    ) -> LayoutResult<gds21::GdsElement> {
+        let purpose = label.purpose.as_ref().unwrap_or(&LayerPurpose::Label);
+        let layerspec = self.export_layerspec(&elem.layer, purpose)?;
+        let strans = self.label_strans(&elem.inner, &elem.layer);
+        Ok(gds21::GdsTextElem {
+            string: label.text.clone(),
+            layer: layerspec.layer,
+            texttype: layerspec.xtype,
+            xy: self.export_point(&label.location(&elem.inner))?,
+            strans,
+            ..Default::default()
+        }
+        .into())
+    }
     /// Convert a [Shape] to a [gds21::GdsElement]
     /// Layer and datatype must be previously converted to gds21's [gds21::GdsLayerSpec] format.
     ///
@@ -275,6 +749,9 @@ impl<'lib> GdsExporter<'lib> {
     ) -> LayoutResult<gds21::GdsElement> {
         let elem = match shape {
             Shape::Rect(r) => {
+                // Emit counter-clockwise from the lower-left corner, regardless of corner order
+                let mut r = r.clone();
+                r.normalize();
                 let (p0, p1) = (&r.p0, &r.p1);
                 let x0 = p0.x.try_into()?;
                 let y0 = p0.y.try_into()?;
@@ -327,27 +804,93 @@ impl<'lib> GdsExporter<'lib> {
         };
         Ok(elem)
     }
-    /// Create a labeling [gds21::GdsElement] for [Shape] `shape`
+    /// Create a labeling [gds21::GdsElement] for [Shape] `shape` on layer `layer`,
+    /// oriented and placed per our [NetLabelOptions]
     pub fn export_shape_label(
         &mut self,
         net: &str,
         shape: &Shape,
+        layer: &LayerKey,
         layerspec: &gds21::GdsLayerSpec,
     ) -> LayoutResult<gds21::GdsElement> {
         // Sort out a location to place the text
-        let loc = shape.label_location()?;
+        let loc = self.opts.net_labels.location(shape)?;
 
         // Rotate that text 90 degrees for mostly-vertical shapes
-        let strans = match shape.orientation() {
+        let strans = self.label_strans(shape, layer);
+        // And return a converted [GdsTextElem]
+        Ok(gds21::GdsTextElem {
+            string: self.export_label(net)?,
+            layer: layerspec.layer,
+            texttype: layerspec.xtype,
+            xy: self.export_point(&loc)?,
+            strans,
+            ..Default::default()
+        }
+        .into())
+    }
+    /// Get the text-transform for labels of `shape` on `layer`, rotated 90 degrees if oriented vertically
+    fn label_strans(&self, shape: &Shape, layer: &LayerKey) -> Option<gds21::GdsStrans> {
+        match self.opts.net_labels.orientation(shape, layer) {
             Dir::Horiz => None,
             Dir::Vert => Some(gds21::GdsStrans {
                 angle: Some(90.0),
                 ..Default::default()
             }),
+        }
+    }
+    /// Apply our [LabelLimit] to label-text `label`, returning the text to be exported
+    fn export_label(&mut self, label: &str) -> LayoutResult<String> {
+        let limit = match self.opts.label_limit {
+            Some(limit) if label.chars().count() > limit.max_len => limit,
+            _ => return Ok(label.into()),
         };
-        // And return a converted [GdsTextElem]
+        match limit.policy {
+            LongLabelPolicy::Allow => Ok(label.into()),
+            LongLabelPolicy::Error => {
+                // Collect these for a single error, listing all of them, at the end of export
+                self.long_labels.insert(label.into());
+                Ok(label.into())
+            }
+            LongLabelPolicy::Truncate => {
+                if let Some(short) = self.labels.get(label) {
+                    return Ok(short.clone());
+                }
+                // Keep room for the `_` separator and 6-character hash
+                if limit.max_len < 8 {
+                    return self.fail(format!(
+                        "Label limit of {} characters is too short for truncation, which requires at least 8",
+                        limit.max_len
+                    ));
+                }
+                let prefix: String = label.chars().take(limit.max_len - 7).collect();
+                let short = format!("{}_{:06x}", prefix, fnv1a(label) & 0xFF_FFFF);
+                if let Some((other, _)) = self.labels.iter().find(|(_, s)| **s == short) {
+                    return self.fail(format!(
+                        "Labels {} and {} both truncate to {}",
+                        other, label, short
+                    ));
+                }
+                self.labels.insert(label.into(), short.clone());
+                Ok(short)
+            }
+        }
+    }
+    /// Create a text-label of cell-name `name`, located at the center of its `boundary`
+    fn export_cell_label(
+        &mut self,
+        name: &str,
+        boundary: &Shape,
+        layerspec: &gds21::GdsLayerSpec,
+        mag: Option<f64>,
+    ) -> LayoutResult<gds21::GdsElement> {
+        let loc = boundary.label_location()?;
+        let strans = mag.map(|mag| gds21::GdsStrans {
+            mag: Some(mag),
+            ..Default::default()
+        });
         Ok(gds21::GdsTextElem {
-            string: net.into(),
+            string: name.into(),
             layer: layerspec.layer,
             texttype: layerspec.xtype,
             xy: self.export_point(&loc)?,
@@ -491,10 +1034,22 @@ impl<'a> GdsDepOrder<'a> {
         }
     }
 }
+/// # GDSII Import Options
+#[derive(Debug, Clone, Default)]
+pub struct GdsImportOptions {
+    /// (Layer, texttype) number-pair of cell-property annotations, as written per [GdsExportOptions::cell_props].
+    /// If set, `key=value` text elements on it are imported into each [Cell::props],
+    /// rather than as net-labels or annotations. Disabled if `None`.
+    pub cell_props: Option<(i16, i16)>,
+    /// [LayerMap] naming imported layers, added to the [Layers] provided to the importer per [LayerMap::add_to].
+    /// Layers not in the map are imported unnamed. Disabled if `None`.
+    pub layermap: Option<LayerMap>,
+}
 /// # GDSII Importer
 #[derive(Debug, Default)]
 pub struct GdsImporter {
     pub layers: Ptr<Layers>,
+    opts: GdsImportOptions,
     ctx: Vec<ErrorContext>,
     unsupported: Vec<gds21::GdsElement>,
     cell_map: HashMap<String, Ptr<Cell>>,
@@ -505,15 +1060,28 @@ impl GdsImporter {
     pub fn import(
         gdslib: &gds21::GdsLibrary,
         layers: Option<Ptr<Layers>>,
+    ) -> LayoutResult<Library> {
+        Self::import_with_options(gdslib, layers, &GdsImportOptions::default())
+    }
+    /// Import a [gds21::GdsLibrary] into a [Library], with [GdsImportOptions] `opts`
+    pub fn import_with_options(
+        gdslib: &gds21::GdsLibrary,
+        layers: Option<Ptr<Layers>>,
+        opts: &GdsImportOptions,
     ) -> LayoutResult<Library> {
         // Create a default [Layers] if none were provided
         let layers = match layers {
             Some(l) => l,
             None => Ptr::new(Layers::default()),
         };
+        // Add any layer-map's layers
+        if let Some(ref layermap) = opts.layermap {
+            layermap.add_to(&mut *layers.write()?)?;
+        }
         // Create the importer
         let mut importer = Self {
             layers,
+            opts: opts.clone(),
             ..Default::default()
         };
         // Run the main import method
@@ -600,10 +1168,30 @@ impl GdsImporter {
     /// Import a GDS Cell ([gds21::GdsStruct]) into a [Cell]
     fn import_cell(&mut self, strukt: &gds21::GdsStruct) -> LayoutResult<Cell> {
         self.ctx.push(ErrorContext::Cell(strukt.name.clone()));
-        let cell = self.import_layout(strukt)?.into();
+        let mut cell: Cell = self.import_layout(strukt)?.into();
+        cell.props = self.import_cell_props(strukt);
         self.ctx.pop();
         Ok(cell)
     }
+    /// Import the cell-properties of `strukt`, per our [GdsImportOptions::cell_props]
+    fn import_cell_props(&self, strukt: &gds21::GdsStruct) -> BTreeMap<String, String> {
+        let mut props = BTreeMap::new();
+        for elem in strukt.elems.iter() {
+            if let gds21::GdsElement::GdsTextElem(ref x) = elem {
+                if let Some((key, value)) = self.cell_prop(x) {
+                    props.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        props
+    }
+    /// Split text element `x` into a cell-property (key, value) pair, if it is on our [GdsImportOptions::cell_props] layer
+    fn cell_prop<'t>(&self, x: &'t gds21::GdsTextElem) -> Option<(&'t str, &'t str)> {
+        match self.opts.cell_props {
+            Some(spec) if spec == (x.layer, x.texttype) => x.string.split_once('='),
+            _ => None,
+        }
+    }
     /// Import a GDS Cell ([gds21::GdsStruct]) into a [Layout]
     fn import_layout(&mut self, strukt: &gds21::GdsStruct) -> LayoutResult<Layout> {
         let mut layout = Layout::default();
@@ -633,6 +1221,8 @@ impl GdsImporter {
                 GdsBox(ref x) => Yes(self.import_box(x)?),
                 GdsArrayRef(ref x) => No(layout.insts.extend(self.import_instance_array(x)?)),
                 GdsStructRef(ref x) => No(layout.insts.push(self.import_instance(x)?)),
+                // Cell-properties are imported separately, by [GdsImporter::import_cell_props]
+                GdsTextElem(ref x) if self.cell_prop(x).is_some() => No(()),
                 GdsTextElem(ref x) => No(texts.push(x)),
                 // GDSII "Node" elements are fairly rare, and are not supported.
                 // (Maybe some day we'll even learn what they are.)
@@ -731,10 +1321,12 @@ impl GdsImporter {
                     && pts[3].x == pts[0].x))
         {
             // That makes this a Rectangle.
-            Shape::Rect(Rect {
+            let mut rect = Rect {
                 p0: pts[0].clone(),
                 p1: pts[2].clone(),
-            })
+            };
+            rect.normalize();
+            Shape::Rect(rect)
         } else {
             // Otherwise, it's a polygon
             Shape::Polygon(Polygon { points: pts })
@@ -748,6 +1340,7 @@ impl GdsImporter {
             layer,
             purpose,
             inner,
+            labels: Vec::new(),
         };
         self.ctx.pop();
         Ok(e)
@@ -760,10 +1353,11 @@ impl GdsImporter {
         // This does not check fox "box validity", and imports the
         // first and third of those five coordinates,
         // which are by necessity for a valid [GdsBox] located at opposite corners.
-        let inner = Shape::Rect(Rect {
+        let mut inner = Shape::Rect(Rect {
             p0: self.import_point(&x.xy[0])?,
             p1: self.import_point(&x.xy[2])?,
         });
+        inner.normalize();
 
         // Grab (or create) its [Layer]
         let (layer, purpose) = self.import_element_layer(x)?;
@@ -773,6 +1367,7 @@ impl GdsImporter {
             layer,
             purpose,
             inner,
+            labels: Vec::new(),
         };
         self.ctx.pop();
         Ok(e)
@@ -798,6 +1393,7 @@ impl GdsImporter {
             layer,
             purpose,
             inner,
+            labels: Vec::new(),
         };
         self.ctx.pop();
         Ok(e)
@@ -815,26 +1411,21 @@ impl GdsImporter {
         let cell = Ptr::clone(cell);
         // Convert its location
         let loc = self.import_point(&sref.xy)?;
-        let inst_name = "".into(); // FIXME: should we create imported instance names?
-        let mut inst = Instance {
-            // Already-converted fields
-            inst_name,
-            cell,
-            loc,
-            // Initial default values for orientation
-            reflect_vert: false,
-            angle: None,
-        };
         // If defined, convert orientation settings
         if let Some(strans) = &sref.strans {
             if strans.abs_mag || strans.abs_angle {
                 return self.fail("Unsupported GDSII Instance Feature: Absolute Magnitude/ Angle");
             }
-            // The [GdsStrans] `reflect` field indicates reflection "about the x-axis",
-            // i.e. the [Instance] `reflect_vert` field.
-            inst.reflect_vert = strans.reflected;
-            inst.angle = strans.angle;
         }
+        let inst_name = "".into(); // FIXME: should we create imported instance names?
+        let (reflect_vert, angle) = import_strans(&sref.strans);
+        let inst = Instance {
+            inst_name,
+            cell,
+            loc,
+            reflect_vert,
+            angle,
+        };
         self.ctx.pop();
         Ok(inst)
     }
@@ -956,6 +1547,325 @@ impl ErrorHelper for GdsImporter {
     }
 }
 
+/// Summarize the GDSII file at path `fname`.
+///
+/// Reads the file via [gds21], but does not convert it into a raw [Library].
+/// Intended for quick inspection of exported or third-party GDSII content.
+pub fn gds_summary(fname: impl AsRef<std::path::Path>) -> LayoutResult<GdsSummary> {
+    let gdslib = gds21::GdsLibrary::open(fname)?;
+    GdsSummary::from_gds(&gdslib)
+}
+
+/// # GDSII Library Summary
+///
+/// Read-only overview of a [gds21::GdsLibrary]'s content, including
+/// per-struct element counts, layer usage, bounding boxes, and the struct-reference graph.
+/// Created by [gds_summary] or [GdsSummary::from_gds].
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct GdsSummary {
+    /// Library Name
+    pub name: String,
+    /// Spatial Units
+    pub units: gds21::GdsUnits,
+    /// Aggregate Element Statistics
+    pub stats: gds21::GdsStats,
+    /// Per-Struct Summaries, in library order
+    pub structs: Vec<GdsStructSummary>,
+}
+/// # Summary of a single [gds21::GdsStruct]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GdsStructSummary {
+    /// Struct Name
+    pub name: String,
+    /// Total number of elements, of all types
+    pub elems: usize,
+    /// Element counts per (layer, datatype) pair, sorted by layer-numbers
+    pub layers: Vec<GdsLayerCount>,
+    /// Bounding box, including that of all referenced structs.
+    /// `None` for structs with no geometric content.
+    pub bbox: Option<BoundBox>,
+    /// Names of referenced structs, sorted and de-duplicated
+    pub refs: Vec<String>,
+}
+/// # Element count on a single GDSII (layer, datatype) pair
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GdsLayerCount {
+    /// Layer Number
+    pub layer: i16,
+    /// DataType (or TextType, etc.) Number
+    pub datatype: i16,
+    /// Number of elements
+    pub count: usize,
+}
+impl GdsSummary {
+    /// Summarize an in-memory [gds21::GdsLibrary]
+    pub fn from_gds(gdslib: &gds21::GdsLibrary) -> LayoutResult<Self> {
+        GdsSummarizer::summarize(gdslib)
+    }
+    /// Get the summary of struct `name`, if present
+    pub fn strukt(&self, name: &str) -> Option<&GdsStructSummary> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+}
+impl GdsStructSummary {
+    /// Get the element count on (`layer`, `datatype`). Zero if not present.
+    pub fn count(&self, layer: i16, datatype: i16) -> usize {
+        self.layers
+            .iter()
+            .find(|l| l.layer == layer && l.datatype == datatype)
+            .map(|l| l.count)
+            .unwrap_or(0)
+    }
+}
+impl std::fmt::Display for GdsSummary {
+    /// Display as a table, one row per struct
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "GDS Library `{}` ({} structs, db-unit {:e}m)",
+            self.name,
+            self.structs.len(),
+            self.units.db_unit()
+        )?;
+        // Format the content of each cell first, so we can size the columns
+        let header = ["Struct", "Elements", "BBox", "Layers", "References"];
+        let rows: Vec<[String; 5]> = self
+            .structs
+            .iter()
+            .map(|s| {
+                let bbox = match &s.bbox {
+                    Some(b) => format!("({}, {}) - ({}, {})", b.p0.x, b.p0.y, b.p1.x, b.p1.y),
+                    None => "-".into(),
+                };
+                let layers = s
+                    .layers
+                    .iter()
+                    .map(|l| format!("{}/{}: {}", l.layer, l.datatype, l.count))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                [
+                    s.name.clone(),
+                    s.elems.to_string(),
+                    bbox,
+                    layers,
+                    s.refs.join(", "),
+                ]
+            })
+            .collect();
+        let mut widths = header.map(|h| h.len());
+        for row in rows.iter() {
+            for (w, col) in widths.iter_mut().zip(row.iter()) {
+                *w = (*w).max(col.len());
+            }
+        }
+        let header = header.map(String::from);
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            for (w, col) in widths.iter().zip(row.iter()) {
+                write!(f, "| {:<w$} ", col, w = *w)?;
+            }
+            writeln!(f, "|")?;
+        }
+        Ok(())
+    }
+}
+
+/// # GDSII Summarizer
+///
+/// Internal implementation of [GdsSummary::from_gds].
+/// Struct bounding boxes are computed recursively and cached by struct name.
+///
+#[derive(Debug)]
+struct GdsSummarizer<'a> {
+    /// Map from names to structs
+    strukts: HashMap<String, &'a gds21::GdsStruct>,
+    /// Cached bounding boxes, by struct name
+    bboxes: HashMap<String, BoundBox>,
+    /// Names of structs with bounding boxes in progress, for detecting recursive references
+    pending: HashSet<String>,
+    ctx: Vec<ErrorContext>,
+}
+impl<'a> GdsSummarizer<'a> {
+    fn summarize(gdslib: &'a gds21::GdsLibrary) -> LayoutResult<GdsSummary> {
+        let mut me = Self {
+            strukts: gdslib.structs.iter().map(|s| (s.name.clone(), s)).collect(),
+            bboxes: HashMap::new(),
+            pending: HashSet::new(),
+            ctx: vec![ErrorContext::Library(gdslib.name.clone())],
+        };
+        let structs = gdslib
+            .structs
+            .iter()
+            .map(|s| me.summarize_struct(s))
+            .collect::<LayoutResult<Vec<_>>>()?;
+        Ok(GdsSummary {
+            name: gdslib.name.clone(),
+            units: gdslib.units.clone(),
+            stats: gdslib.stats(),
+            structs,
+        })
+    }
+    /// Summarize a single [gds21::GdsStruct]
+    fn summarize_struct(&mut self, strukt: &'a gds21::GdsStruct) -> LayoutResult<GdsStructSummary> {
+        use gds21::GdsElement::*;
+        self.ctx.push(ErrorContext::Cell(strukt.name.clone()));
+        let mut layers: BTreeMap<(i16, i16), usize> = BTreeMap::new();
+        let mut refs: BTreeSet<String> = BTreeSet::new();
+        for elem in strukt.elems.iter() {
+            let spec = match elem {
+                GdsBoundary(ref x) => Some(x.layerspec()),
+                GdsPath(ref x) => Some(x.layerspec()),
+                GdsTextElem(ref x) => Some(x.layerspec()),
+                GdsNode(ref x) => Some(x.layerspec()),
+                GdsBox(ref x) => Some(x.layerspec()),
+                GdsStructRef(ref x) => {
+                    refs.insert(x.name.clone());
+                    None
+                }
+                GdsArrayRef(ref x) => {
+                    refs.insert(x.name.clone());
+                    None
+                }
+            };
+            if let Some(spec) = spec {
+                *layers.entry((spec.layer, spec.xtype)).or_insert(0) += 1;
+            }
+        }
+        let bbox = self.bbox(strukt)?;
+        let bbox = if bbox.is_empty() { None } else { Some(bbox) };
+        self.ctx.pop();
+        Ok(GdsStructSummary {
+            name: strukt.name.clone(),
+            elems: strukt.elems.len(),
+            layers: layers
+                .into_iter()
+                .map(|((layer, datatype), count)| GdsLayerCount {
+                    layer,
+                    datatype,
+                    count,
+                })
+                .collect(),
+            bbox,
+            refs: refs.into_iter().collect(),
+        })
+    }
+    /// Get the bounding box of `strukt`, including all of its referenced structs.
+    /// Returns an empty [BoundBox] for structs without geometric content.
+    fn bbox(&mut self, strukt: &'a gds21::GdsStruct) -> LayoutResult<BoundBox> {
+        if let Some(bbox) = self.bboxes.get(&strukt.name) {
+            return Ok(*bbox);
+        }
+        if !self.pending.insert(strukt.name.clone()) {
+            return self.fail(format!("Recursive reference to struct {}", strukt.name));
+        }
+        use gds21::GdsElement::*;
+        let mut bbox = BoundBox::empty();
+        for elem in strukt.elems.iter() {
+            let elem_bbox = match elem {
+                GdsBoundary(ref x) => self.points_bbox(&x.xy),
+                GdsNode(ref x) => self.points_bbox(&x.xy),
+                GdsBox(ref x) => self.points_bbox(&x.xy),
+                GdsTextElem(ref x) => self.points_bbox(std::slice::from_ref(&x.xy)),
+                GdsPath(ref x) => {
+                    let mut b = self.points_bbox(&x.xy);
+                    if let Some(w) = x.width {
+                        b.expand(w.abs() as Int / 2);
+                    }
+                    b
+                }
+                GdsStructRef(ref x) => {
+                    let child = self.child_bbox(&x.name)?;
+                    let trans = self.transform(&x.xy, &x.strans);
+                    transformed_bbox(&child, &trans)
+                }
+                GdsArrayRef(ref x) => {
+                    let child = self.child_bbox(&x.name)?;
+                    // Array elements sit on a lattice spanning the three `xy` points.
+                    // Being affine, its extent is set by the elements in its four corners.
+                    let (cols, rows) = (i32::from(x.cols.max(1)), i32::from(x.rows.max(1)));
+                    let colstep = (
+                        (x.xy[1].x - x.xy[0].x) / cols,
+                        (x.xy[1].y - x.xy[0].y) / cols,
+                    );
+                    let rowstep = (
+                        (x.xy[2].x - x.xy[0].x) / rows,
+                        (x.xy[2].y - x.xy[0].y) / rows,
+                    );
+                    let mut b = BoundBox::empty();
+                    for (c, r) in [(0, 0), (cols - 1, 0), (0, rows - 1), (cols - 1, rows - 1)] {
+                        let loc = gds21::GdsPoint::new(
+                            x.xy[0].x + c * colstep.0 + r * rowstep.0,
+                            x.xy[0].y + c * colstep.1 + r * rowstep.1,
+                        );
+                        let trans = self.transform(&loc, &x.strans);
+                        b = b.union(&transformed_bbox(&child, &trans));
+                    }
+                    b
+                }
+            };
+            if !elem_bbox.is_empty() {
+                bbox = bbox.union(&elem_bbox);
+            }
+        }
+        self.pending.remove(&strukt.name);
+        self.bboxes.insert(strukt.name.clone(), bbox);
+        Ok(bbox)
+    }
+    /// Get the bounding box of the struct named `name`, which must be defined in the library.
+    fn child_bbox(&mut self, name: &str) -> LayoutResult<BoundBox> {
+        let child = *self.unwrap(
+            self.strukts.get(name),
+            format!("Reference to undefined struct {}", name),
+        )?;
+        self.bbox(child)
+    }
+    /// Get the bounding box of a list of [gds21::GdsPoint]s
+    fn points_bbox(&self, pts: &[gds21::GdsPoint]) -> BoundBox {
+        let mut bbox = BoundBox::empty();
+        for p in pts.iter() {
+            bbox = Point::new(p.x as Int, p.y as Int).union(&bbox);
+        }
+        bbox
+    }
+    /// Create the [Transform] for a reference located at `loc` with settings `strans`
+    fn transform(&self, loc: &gds21::GdsPoint, strans: &Option<gds21::GdsStrans>) -> Transform {
+        let loc = Point::new(loc.x as Int, loc.y as Int);
+        let (reflect_vert, angle) = import_strans(strans);
+        let mut trans = Transform::from_instance(&loc, reflect_vert, angle);
+        if let Some(mag) = strans.as_ref().and_then(|s| s.mag) {
+            for row in trans.a.iter_mut() {
+                for entry in row.iter_mut() {
+                    *entry *= mag;
+                }
+            }
+        }
+        trans
+    }
+}
+impl ErrorHelper for GdsSummarizer<'_> {
+    type Error = LayoutError;
+    fn err(&self, msg: impl Into<String>) -> LayoutError {
+        LayoutError::Import {
+            message: msg.into(),
+            stack: self.ctx.clone(),
+        }
+    }
+}
+/// Apply [Transform] `trans` to [BoundBox] `bbox`, returning the bounding box of the result.
+/// Empty boxes remain empty.
+fn transformed_bbox(bbox: &BoundBox, trans: &Transform) -> BoundBox {
+    if bbox.is_empty() {
+        return BoundBox::empty();
+    }
+    let corners = vec![
+        bbox.p0.transform(trans),
+        Point::new(bbox.p1.x, bbox.p0.y).transform(trans),
+        bbox.p1.transform(trans),
+        Point::new(bbox.p0.x, bbox.p1.y).transform(trans),
+    ];
+    corners.bbox()
+}
+
 /// Import a GDS Cell with two polygons:
 /// One assigned to a net, and the other not.
 #[cfg(all(test, feature = "gds"))]
@@ -12,7 +12,7 @@ use std::convert::TryFrom;
 use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::{bbox::BoundBoxTrait, Int};
+use crate::{bbox::BoundBoxTrait, error::LayoutError, Int};
 
 /// # Point in two-dimensional layout-space
 #[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -68,7 +68,7 @@ impl Point {
     }
 }
 /// Direction Enumeration
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Dir {
     Horiz,
     Vert,
@@ -82,6 +82,26 @@ impl Dir {
         }
     }
 }
+impl std::fmt::Display for Dir {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Horiz => write!(f, "Horiz"),
+            Self::Vert => write!(f, "Vert"),
+        }
+    }
+}
+impl std::str::FromStr for Dir {
+    type Err = LayoutError;
+    /// Parse a [Dir], case-insensitively, from either its short or full name,
+    /// e.g. `horiz` or `Horizontal`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "horiz" | "horizontal" | "h" => Ok(Self::Horiz),
+            "vert" | "vertical" | "v" => Ok(Self::Vert),
+            _ => LayoutError::fail(format!("Invalid Dir `{}`", s)),
+        }
+    }
+}
 impl std::ops::Not for Dir {
     type Output = Self;
     /// Exclamation Operator returns the opposite direction
@@ -112,6 +132,42 @@ pub struct Path {
 pub struct Polygon {
     pub points: Vec<Point>,
 }
+impl Polygon {
+    /// Boolean indication of whether we are in canonical form:
+    /// starting at our lexicographically smallest vertex, and wound counter-clockwise.
+    pub fn is_normalized(&self) -> bool {
+        match self.min_vertex() {
+            None => true,
+            Some(idx) => idx == 0 && self.area2() >= 0,
+        }
+    }
+    /// Put into canonical form, rotating our points to start at the lexicographically
+    /// smallest vertex, and reversing them if wound clockwise.
+    pub fn normalize(&mut self) {
+        if self.area2() < 0 {
+            self.points.reverse();
+        }
+        if let Some(idx) = self.min_vertex() {
+            self.points.rotate_left(idx);
+        }
+    }
+    /// Index of our lexicographically smallest vertex, or `None` if we have no points
+    fn min_vertex(&self) -> Option<usize> {
+        let pts = self.points.iter().enumerate();
+        pts.min_by_key(|(_, p)| (p.x, p.y)).map(|(idx, _)| idx)
+    }
+    /// Twice our signed area, via the shoelace formula.
+    /// Positive for counter-clockwise winding, negative for clockwise.
+    fn area2(&self) -> Int {
+        let len = self.points.len();
+        (0..len)
+            .map(|idx| {
+                let (p, q) = (&self.points[idx], &self.points[(idx + 1) % len]);
+                p.x * q.y - q.x * p.y
+            })
+            .sum()
+    }
+}
 /// # Rectangle
 ///
 /// Axis-aligned rectangle, specified by two opposite corners.
@@ -126,6 +182,20 @@ impl Rect {
     pub fn center(&self) -> Point {
         Point::new((self.p0.x + self.p1.x) / 2, (self.p0.y + self.p1.y) / 2)
     }
+    /// Boolean indication of whether we are in canonical form, with `p0` at our lower-left corner
+    pub fn is_normalized(&self) -> bool {
+        self.p0.x <= self.p1.x && self.p0.y <= self.p1.y
+    }
+    /// Put into canonical form, moving `p0` to our lower-left and `p1` to our upper-right corner
+    pub fn normalize(&mut self) {
+        let (p0, p1) = (&self.p0, &self.p1);
+        let (lo, hi) = (
+            Point::new(p0.x.min(p1.x), p0.y.min(p1.y)),
+            Point::new(p0.x.max(p1.x), p0.y.max(p1.y)),
+        );
+        self.p0 = lo;
+        self.p1 = hi;
+    }
 }
 
 /// # Shape
@@ -148,6 +218,35 @@ impl Default for Shape {
 }
 
 impl Shape {
+    /// Boolean indication of whether we are in canonical form.
+    /// See [Rect::is_normalized] and [Polygon::is_normalized]. [Path]s are always regarded as normalized.
+    pub fn is_normalized(&self) -> bool {
+        match self {
+            Shape::Rect(r) => r.is_normalized(),
+            Shape::Polygon(p) => p.is_normalized(),
+            Shape::Path(_) => true,
+        }
+    }
+    /// Put into canonical form.
+    /// [Rect]s have `p0` at their lower-left corner. [Polygon]s start at their
+    /// lexicographically smallest vertex, and are wound counter-clockwise.
+    /// [Path]s are directional, and are left unchanged.
+    pub fn normalize(&mut self) {
+        match self {
+            Shape::Rect(r) => r.normalize(),
+            Shape::Polygon(p) => p.normalize(),
+            Shape::Path(_) => (),
+        }
+    }
+    /// Boolean indication of whether we have zero area, e.g. a [Rect] with coincident edges,
+    /// a [Polygon] whose vertices are colinear, or a zero-width [Path].
+    pub fn is_degenerate(&self) -> bool {
+        match self {
+            Shape::Rect(r) => r.p0.x == r.p1.x || r.p0.y == r.p1.y,
+            Shape::Polygon(p) => p.area2() == 0,
+            Shape::Path(p) => p.width == 0 || p.points.len() < 2,
+        }
+    }
     /// Boolean indication of whether we intersect with [Shape] `other`.
     pub fn intersects(&self, _other: &Shape) -> bool {
         todo!() // FIXME!
@@ -200,12 +299,11 @@ impl ShapeTrait for Rect {
     /// Boolean indication of whether the [Shape] contains [Point] `pt`.
     /// Containment is *inclusive* for all [Shape] types.
     /// [Point]s on their boundary, which generally include all points specifying the shape itself, are regarded throughout as "inside" the shape.
+    /// Requires a normalized [Rect], per [Rect::normalize].
     fn contains(&self, pt: &Point) -> bool {
+        debug_assert!(self.is_normalized(), "Non-normalized {:?}", self);
         let (p0, p1) = (&self.p0, &self.p1);
-        p0.x.min(p1.x) <= pt.x
-            && p0.x.max(p1.x) >= pt.x
-            && p0.y.min(p1.y) <= pt.y
-            && p0.y.max(p1.y) >= pt.y
+        p0.x <= pt.x && p1.x >= pt.x && p0.y <= pt.y && p1.y >= pt.y
     }
     fn to_poly(&self) -> Polygon {
         // Create a four-sided polygon, cloning our corners
@@ -323,7 +421,7 @@ impl ShapeTrait for Path {
         let (points, width) = (&self.points, self.width);
         let width = Int::try_from(width).unwrap(); // FIXME: probably store these signed, check them on creation
         for k in 0..points.len() - 1 {
-            let rect = if points[k].x == points[k + 1].x {
+            let mut rect = if points[k].x == points[k + 1].x {
                 Rect {
                     p0: Point::new(points[k].x - width / 2, points[k].y),
                     p1: Point::new(points[k].x + width / 2, points[k + 1].y),
@@ -336,6 +434,7 @@ impl ShapeTrait for Path {
             } else {
                 unimplemented!("Unsupported Non-Manhattan Path")
             };
+            rect.normalize();
             if rect.contains(pt) {
                 return true;
             }
@@ -391,7 +490,11 @@ impl Transform {
             b: [0., 0.],
         }
     }
-    /// Create a transform from instance fields: location, rotation, and reflection
+    /// Create a transform from instance fields: location, rotation, and reflection.
+    ///
+    /// As in GDSII, reflection about the x-axis is applied first,
+    /// followed by counter-clockwise rotation by `angle` degrees,
+    /// both about the instance's origin. The result is then translated to `loc`.
     pub fn from_instance(loc: &Point, reflect_vert: bool, angle: Option<f64>) -> Self {
         let b = [loc.x as f64, loc.y as f64];
         let (mut sin, mut cos) = (0., 1.);
@@ -399,8 +502,12 @@ impl Transform {
             sin = angle.to_radians().sin();
             cos = angle.to_radians().cos();
         }
-        let cos_refl = if reflect_vert { -cos } else { cos };
-        let a = [[cos, -sin], [sin, cos_refl]];
+        // Reflection negates the second column of the rotation matrix
+        let a = if reflect_vert {
+            [[cos, sin], [sin, -cos]]
+        } else {
+            [[cos, -sin], [sin, cos]]
+        };
         Self { a, b }
     }
     /// Create a new [Transform] that is the cascade of `parent` and `child`.
@@ -542,6 +649,15 @@ pub mod tests {
         assert_eq!(shape0, shape1);
     }
     #[test]
+    fn transform_reflect_then_rotate() {
+        // Reflection is applied before rotation
+        let p = Point::new(1, 0);
+        let trans = Transform::from_instance(&Point::new(0, 0), true, Some(90.));
+        assert_eq!(p.transform(&trans), Point::new(0, 1));
+        let p = Point::new(0, 1);
+        assert_eq!(p.transform(&trans), Point::new(1, 0));
+    }
+    #[test]
     fn test_cascade1() {
         let trans1 = Transform::reflect_vert();
         let trans2 = Transform::translate(1., 1.);
@@ -556,6 +672,51 @@ pub mod tests {
         assert_eq!(pc1, Point::new(2, 0));
     }
     #[test]
+    fn test_normalize() {
+        // Rects in each corner-ordering normalize to the same lower-left-first form
+        let canonical = Rect {
+            p0: Point::new(0, 0),
+            p1: Point::new(4, 2),
+        };
+        for (p0, p1) in [((4, 2), (0, 0)), ((0, 2), (4, 0)), ((4, 0), (0, 2))] {
+            let mut shape = Shape::Rect(Rect {
+                p0: Point::new(p0.0, p0.1),
+                p1: Point::new(p1.0, p1.1),
+            });
+            assert!(!shape.is_normalized());
+            shape.normalize();
+            assert!(shape.is_normalized());
+            assert_eq!(shape, Shape::Rect(canonical.clone()));
+        }
+        // A clockwise polygon, starting mid-way around
+        let mut poly = Polygon {
+            points: vec![
+                Point::new(2, 2),
+                Point::new(2, 0),
+                Point::new(0, 0),
+                Point::new(0, 4),
+            ],
+        };
+        assert!(!poly.is_normalized());
+        poly.normalize();
+        assert!(poly.is_normalized());
+        assert_eq!(
+            poly.points,
+            vec![
+                Point::new(0, 0),
+                Point::new(2, 0),
+                Point::new(2, 2),
+                Point::new(0, 4),
+            ]
+        );
+        // Normalizing is idempotent, and preserves containment
+        let prior = poly.clone();
+        poly.normalize();
+        assert_eq!(poly, prior);
+        assert!(poly.contains(&Point::new(1, 2)));
+        assert!(!poly.contains(&Point::new(2, 4)));
+    }
+    #[test]
     fn test_polygon_contains() {
         // Test polygon-point containment of several flavors
 
@@ -0,0 +1,284 @@
+//!
+//! # Layer Mapping Files
+//!
+//! Translations between layer names and (layer-number, datatype) pairs,
+//! stored outside of Rust code for sharing with viewers and other tools.
+//!
+//! Two formats are supported:
+//! * A simple text format, with one `name layernum datatype purpose` entry per line.
+//!   Blank lines and `#`-prefixed comments are ignored.
+//! * KLayout's `.lyp` layer-properties XML, from which each `<properties>` entry's `<name>` and `<source>` are read.
+//!
+
+// Std-Lib
+use std::collections::BTreeSet;
+
+// Crates.io
+use serde::{Deserialize, Serialize};
+
+// Local imports
+use crate::{
+    data::{Layer, LayerPurpose, Layers},
+    error::{LayoutError, LayoutResult},
+};
+
+/// # Layer Map
+///
+/// Ordered list of [LayerMapEntry], generally parsed from a layer-mapping file.
+///
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LayerMap {
+    pub entries: Vec<LayerMapEntry>,
+}
+/// # Layer Map Entry
+///
+/// Maps (layer-name, purpose) to (layer-number, datatype).
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LayerMapEntry {
+    /// Layer Name
+    pub name: String,
+    /// Layer Number
+    pub layernum: i16,
+    /// Datatype Number
+    pub datatype: i16,
+    /// Layer Purpose
+    pub purpose: LayerPurpose,
+}
+/// # Layer Map Comparison
+///
+/// Layer names present in only one of a [LayerMap] and a set of expected names.
+/// Created by [LayerMap::compare].
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LayerMapDiff {
+    /// Expected names not present in the [LayerMap]
+    pub missing: Vec<String>,
+    /// Names in the [LayerMap] which were not expected
+    pub unused: Vec<String>,
+}
+impl LayerMapDiff {
+    /// Boolean indication of whether all expected names are mapped
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+impl LayerMap {
+    /// Read a layer-map from file `fname`.
+    /// Files with the `.lyp` extension are parsed as KLayout layer-properties,
+    /// and all others in the simple text format.
+    #[cfg(feature = "yaml")]
+    pub fn open(fname: impl AsRef<std::path::Path>) -> LayoutResult<Self> {
+        let fname = fname.as_ref();
+        let txt = std::fs::read_to_string(fname)?;
+        match fname.extension().and_then(|e| e.to_str()) {
+            Some("lyp") => Self::parse_lyp(&txt),
+            _ => Self::parse(&txt),
+        }
+    }
+    /// Parse the simple text layer-map format, with one `name layernum datatype purpose` entry per line.
+    pub fn parse(txt: &str) -> LayoutResult<Self> {
+        let mut entries = Vec::new();
+        for (idx, line) in txt.lines().enumerate() {
+            // Strip comments and whitespace, and skip empty lines
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 4 {
+                return LayoutError::fail(format!(
+                    "Invalid layer-map line {}: `{}`. Expected `name layernum datatype purpose`",
+                    idx + 1,
+                    line
+                ));
+            }
+            let layernum = parse_num(fields[1], idx)?;
+            let datatype = parse_num(fields[2], idx)?;
+            entries.push(LayerMapEntry {
+                name: fields[0].into(),
+                layernum,
+                datatype,
+                purpose: parse_purpose(fields[3], datatype),
+            });
+        }
+        Ok(Self { entries })
+    }
+    /// Parse a KLayout `.lyp` layer-properties file.
+    ///
+    /// Each `<properties>` element must include a `<source>` of the form `layernum/datatype`,
+    /// optionally followed by `@layout-index`.
+    /// Its `<name>` is split into layer-name and purpose at the first `.` or whitespace,
+    /// e.g. `met1.drawing` or `met1 pin`. Names without a purpose map to [LayerPurpose::Drawing].
+    /// Unnamed entries and those with wildcard sources (generally groups) are skipped.
+    pub fn parse_lyp(txt: &str) -> LayoutResult<Self> {
+        let mut entries = Vec::new();
+        // Each entry runs from an opening `<properties>` or `<group-members>` tag
+        // to the next tag opening or closing either.
+        let txt = txt.replace("<group-members>", "<properties>");
+        for props in txt.split("<properties>").skip(1) {
+            let end = ["</properties>", "</group-members>", "<group-members>"]
+                .iter()
+                .filter_map(|t| props.find(t))
+                .min()
+                .unwrap_or(props.len());
+            let props = &props[..end];
+            let source = match xml_text(props, "source") {
+                Some(s) => s,
+                None => return LayoutError::fail("Invalid .lyp entry without `source`"),
+            };
+            if source.contains('*') {
+                continue; // Wildcard sources, generally used by groups, are not mapped
+            }
+            let name = match xml_text(props, "name") {
+                Some(s) if !s.is_empty() => s,
+                _ => continue,
+            };
+            // Sources are of the form `[name ]layernum/datatype[@index]`
+            let source = source.split('@').next().unwrap_or("");
+            let source = source.split_whitespace().last().unwrap_or("");
+            let (layernum, datatype) = match source.split_once('/') {
+                Some((l, d)) => (l.trim(), d.trim()),
+                None => return LayoutError::fail(format!("Invalid .lyp source `{}`", source)),
+            };
+            let layernum = parse_num(layernum, entries.len())?;
+            let datatype = parse_num(datatype, entries.len())?;
+            let (name, purpose) = match name.split_once(|c: char| c == '.' || c.is_whitespace()) {
+                Some((n, p)) => (n.trim(), parse_purpose(p.trim(), datatype)),
+                None => (name, LayerPurpose::Drawing),
+            };
+            entries.push(LayerMapEntry {
+                name: name.into(),
+                layernum,
+                datatype,
+                purpose,
+            });
+        }
+        Ok(Self { entries })
+    }
+    /// Get all [LayerMapEntry]s for layer-name `name`
+    pub fn get(&self, name: &str) -> Vec<&LayerMapEntry> {
+        self.entries.iter().filter(|e| e.name == name).collect()
+    }
+    /// Get the unique layer-names, in order of first appearance
+    pub fn names(&self) -> Vec<&str> {
+        let mut seen = BTreeSet::new();
+        self.entries
+            .iter()
+            .map(|e| e.name.as_str())
+            .filter(|n| seen.insert(*n))
+            .collect()
+    }
+    /// Create the [Layer] for name `name`, if present
+    pub fn layer(&self, name: &str) -> LayoutResult<Option<Layer>> {
+        let entries = self.get(name);
+        let first = match entries.first() {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        let mut layer = Layer::new(first.layernum, name);
+        for e in entries.iter() {
+            if e.layernum != first.layernum {
+                return LayoutError::fail(format!(
+                    "Layer {} mapped to multiple layer-numbers: {} and {}",
+                    name, first.layernum, e.layernum
+                ));
+            }
+            layer.add_purpose(e.datatype, e.purpose.clone())?;
+        }
+        Ok(Some(layer))
+    }
+    /// Convert to a [Layers] set, with one [Layer] per unique name
+    pub fn to_layers(&self) -> LayoutResult<Layers> {
+        let mut layers = Layers::default();
+        self.add_to(&mut layers)?;
+        Ok(layers)
+    }
+    /// Add our layers to `layers`.
+    /// Existing layers of the same name are re-used, and our purposes added to them.
+    /// Fails if such a layer has a different layer-number, or a different purpose on any of our datatypes.
+    /// On failure, `layers` is left unmodified.
+    pub fn add_to(&self, layers: &mut Layers) -> LayoutResult<()> {
+        // Build into a copy, and only swap it in once complete
+        let mut updated = layers.clone();
+        self.add_to_helper(&mut updated)?;
+        *layers = updated;
+        Ok(())
+    }
+    /// Internal implementation of [LayerMap::add_to], which may partially modify `layers` on failure
+    fn add_to_helper(&self, layers: &mut Layers) -> LayoutResult<()> {
+        for name in self.names() {
+            let layer = match self.layer(name)? {
+                Some(layer) => layer,
+                None => continue,
+            };
+            let key = match layers.keyname(name) {
+                Some(key) => key,
+                None => {
+                    layers.add(layer);
+                    continue;
+                }
+            };
+            let existing = match layers.slots.get_mut(key) {
+                Some(existing) => existing,
+                None => return LayoutError::fail(format!("Layer {} not found", name)),
+            };
+            if existing.layernum != layer.layernum {
+                return LayoutError::fail(format!(
+                    "Layer {} mapped to layer-number {}, but already defined as {}",
+                    name, layer.layernum, existing.layernum
+                ));
+            }
+            for e in self.get(name) {
+                existing.add_purpose(e.datatype, e.purpose.clone())?;
+            }
+        }
+        Ok(())
+    }
+    /// Compare our layer-names against `expected`
+    pub fn compare<'e>(&self, expected: impl IntoIterator<Item = &'e str>) -> LayerMapDiff {
+        let expected: Vec<&str> = expected.into_iter().collect();
+        let ours = self.names();
+        LayerMapDiff {
+            missing: expected
+                .iter()
+                .filter(|n| !ours.contains(n))
+                .map(|n| n.to_string())
+                .collect(),
+            unused: ours
+                .iter()
+                .filter(|n| !expected.contains(n))
+                .map(|n| n.to_string())
+                .collect(),
+        }
+    }
+}
+/// Parse a layer or datatype number from layer-map line `idx`
+fn parse_num(s: &str, idx: usize) -> LayoutResult<i16> {
+    s.parse::<i16>().map_err(|_| {
+        LayoutError::msg(format!(
+            "Invalid layer-map number `{}` in entry {}",
+            s,
+            idx + 1
+        ))
+    })
+}
+/// Parse a [LayerPurpose] from its name. Unrecognized names become [LayerPurpose::Named].
+fn parse_purpose(s: &str, datatype: i16) -> LayerPurpose {
+    match s.to_lowercase().as_str() {
+        "drawing" | "drw" => LayerPurpose::Drawing,
+        "pin" => LayerPurpose::Pin,
+        "label" | "text" => LayerPurpose::Label,
+        "obstruction" | "blockage" | "obs" => LayerPurpose::Obstruction,
+        "outline" | "boundary" => LayerPurpose::Outline,
+        _ => LayerPurpose::Named(s.into(), datatype),
+    }
+}
+/// Get the trimmed text content of the first `<tag>` element in `xml`, if present
+fn xml_text<'x>(xml: &'x str, tag: &str) -> Option<&'x str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let stop = xml[start..].find(&close)? + start;
+    Some(xml[start..stop].trim())
+}
@@ -5,7 +5,7 @@
 //!
 
 // Std-Lib
-use std::collections::hash_map::Entry;
+use std::collections::btree_map::Entry;
 use std::convert::{TryFrom, TryInto};
 
 // Local imports
@@ -396,7 +396,9 @@ impl LefImporter {
     ) -> LayoutResult<Shape> {
         let p0 = self.import_point(lefpoints.0)?;
         let p1 = self.import_point(lefpoints.1)?;
-        Ok(Shape::Rect(Rect { p0, p1 }))
+        let mut rect = Rect { p0, p1 };
+        rect.normalize();
+        Ok(Shape::Rect(rect))
     }
     /// Import a [Shape::Path]
     fn import_path(
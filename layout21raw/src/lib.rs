@@ -5,31 +5,53 @@
 //! Consists of geometric primitives and instances of other layout cells,
 //! much akin to nearly any legacy layout system.
 //!
+//! Each import/export format (`gds`, `lef`, `proto`) and YAML/file persistence (`yaml`) is a cargo feature,
+//! all enabled by default. With `default-features = false`, the geometry and data model remain,
+//! without any file I/O, e.g. for use in WebAssembly.
+//!
 
-// Crates.io dependencies, at crate-level for their macros 
+// Crates.io dependencies, at crate-level for their macros
 #[macro_use]
 extern crate enum_dispatch;
 
 // Internal modules
 pub mod bbox;
+pub mod blackbox;
 pub mod data;
 pub mod error;
 pub mod geom;
+pub mod layermap;
+pub mod nets;
+pub mod raster;
+pub mod spatial;
 
 // Re-exports
 #[doc(inline)]
 pub use bbox::*;
 #[doc(inline)]
+pub use blackbox::{BlackboxReport, BlackboxedCell};
+#[doc(inline)]
 pub use data::*;
 #[doc(inline)]
 pub use error::*;
 #[doc(inline)]
 pub use geom::*;
+#[doc(inline)]
+pub use layermap::*;
+#[doc(inline)]
+pub use nets::{net_matches, NetIndex};
+#[doc(inline)]
+pub use raster::{raster_equal, rasterize, BitGrid, DiffPixel};
+#[doc(inline)]
+pub use spatial::CellIndex;
 pub use layout21utils as utils;
 
 // Optional-feature modules
 #[cfg(feature = "gds")]
 pub mod gds;
+#[cfg(feature = "gds")]
+#[doc(inline)]
+pub use gds::{gds21, gds_summary, GdsSummary};
 #[cfg(feature = "lef")]
 pub mod lef;
 #[cfg(feature = "proto")]
@@ -0,0 +1,128 @@
+//!
+//! # Net Queries
+//!
+//! Find the [Element]s labeled with a net, or with any net matching a wildcard pattern,
+//! per [Cell] and without flattening hierarchy.
+//!
+//! Patterns may include `*`, matching any (possibly empty) sequence of characters,
+//! and `?`, matching any single character. E.g. `data_*` matches `data_0` and `data_in`, but not `data`.
+//!
+
+// Std-Lib
+use std::collections::HashMap;
+
+// Local Imports
+use crate::data::{Cell, Element, Library};
+use crate::error::LayoutResult;
+
+impl Cell {
+    /// Get each [Element] of our [Layout](crate::Layout) whose net matches `net`, in order.
+    /// Empty if we have no layout.
+    pub fn elements_by_net<'a>(&'a self, net: &str) -> Vec<&'a Element> {
+        match self.layout {
+            Some(ref layout) => layout
+                .elems
+                .iter()
+                .filter(|e| matches!(e.net, Some(ref n) if net_matches(net, n)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+impl Library {
+    /// Find each [Element] whose net matches `net`, as (cell name, element index) pairs.
+    /// Cells are searched in order, and are not flattened: elements are reported in the cell which defines them.
+    pub fn find_net(&self, net: &str) -> LayoutResult<Vec<(String, usize)>> {
+        NetIndex::new().find_net(self, net)
+    }
+}
+
+/// # Net Index
+///
+/// Per-[Cell] maps from net names to the indices of their [Element]s,
+/// built lazily on the first query of each cell, and re-used thereafter.
+/// Cells are keyed by name; use [NetIndex::invalidate] after modifying one.
+///
+#[derive(Debug, Clone, Default)]
+pub struct NetIndex {
+    cells: HashMap<String, HashMap<String, Vec<usize>>>,
+}
+impl NetIndex {
+    /// Create a new and empty [NetIndex]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Get the indices of the elements of `cell` whose net matches `net`, in ascending order
+    pub fn elements(&mut self, cell: &Cell, net: &str) -> Vec<usize> {
+        let nets = self
+            .cells
+            .entry(cell.name.clone())
+            .or_insert_with(|| index_cell(cell));
+        if !is_pattern(net) {
+            return nets.get(net).cloned().unwrap_or_default();
+        }
+        let mut indices: Vec<usize> = nets
+            .iter()
+            .filter(|(name, _)| net_matches(net, name))
+            .flat_map(|(_, indices)| indices.iter().copied())
+            .collect();
+        indices.sort_unstable();
+        indices
+    }
+    /// Find each element of [Library] `lib` whose net matches `net`, as (cell name, element index) pairs.
+    /// See [Library::find_net].
+    pub fn find_net(&mut self, lib: &Library, net: &str) -> LayoutResult<Vec<(String, usize)>> {
+        let mut found = Vec::new();
+        for ptr in lib.cells.iter() {
+            let cell = ptr.read()?;
+            for idx in self.elements(&cell, net) {
+                found.push((cell.name.clone(), idx));
+            }
+        }
+        Ok(found)
+    }
+    /// Remove any cached index for the cell named `name`, e.g. after modifying its elements
+    pub fn invalidate(&mut self, name: &str) {
+        self.cells.remove(name);
+    }
+}
+/// Create the net-name to element-indices map for `cell`
+fn index_cell(cell: &Cell) -> HashMap<String, Vec<usize>> {
+    let mut nets: HashMap<String, Vec<usize>> = HashMap::new();
+    if let Some(ref layout) = cell.layout {
+        for (idx, elem) in layout.elems.iter().enumerate() {
+            if let Some(ref net) = elem.net {
+                nets.entry(net.clone()).or_default().push(idx);
+            }
+        }
+    }
+    nets
+}
+/// Boolean indication of whether `pattern` includes any wildcards
+fn is_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+/// Boolean indication of whether net-name `name` matches `pattern`
+pub fn net_matches(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    // Greedy matching, backtracking to the most recent `*` on mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((sp, sn)) = star {
+            p = sp + 1;
+            n = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
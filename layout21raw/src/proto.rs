@@ -10,7 +10,7 @@
 //!
 
 // Std-Lib
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 
 // Local imports
@@ -158,6 +158,14 @@ impl<'lib> ProtoExporter<'lib> {
             .iter()
             .map(|x| self.export_annotation(x))
             .collect::<Result<Vec<_>, _>>()?;
+        // Element labels have no place in the proto-schema, and are dropped
+        let nlabels: usize = cell.elems.iter().map(|e| e.labels.len()).sum();
+        if nlabels > 0 {
+            self.warn(format!(
+                "Dropping {} element labels of Layout {}, unsupported by ProtoBuf export",
+                nlabels, cell.name
+            ));
+        }
         // Collect up shapes by layer
         // FIXME: should we store them here this way in the first place? Perhaps.
         let mut layers: HashMap<(i16, i16), Vec<&Element>> = HashMap::new();
@@ -211,6 +219,11 @@ impl<'lib> ProtoExporter<'lib> {
             rotation_clockwise_degrees: 0,
         })
     }
+    /// Generate a warning.
+    /// Thus far, prints to the console.
+    fn warn(&self, msg: impl Into<String>) {
+        eprintln!("Warning: {}", msg.into());
+    }
     /// Export an [Element]
     fn export_element(&mut self, elem: &Element) -> LayoutResult<ProtoShape> {
         // Create its [proto::Shape]
@@ -601,6 +614,7 @@ impl ProtoImporter {
             inner,
             layer,
             purpose,
+            labels: Vec::new(),
         })
     }
     /// Import a proto-defined pointer, AKA [proto::Reference]
@@ -691,7 +705,7 @@ impl ErrorHelper for ProtoImporter {
 impl Layers {
     /// Load the struct from an external Technology protobuf.
     pub fn from_proto(library_pb: &proto::Technology) -> LayoutResult<Layers> {
-        let mut layers_by_number = HashMap::new();
+        let mut layers_by_number = BTreeMap::new();
 
         // Generate the Layers data from the given tech proto:
         for layer_pb in &library_pb.layers {
@@ -747,6 +761,7 @@ fn proto1() -> LayoutResult<()> {
                     p0: Point::default(),
                     p1: Point::default(),
                 }),
+                labels: Vec::new(),
             },
             Element {
                 net: Some("prt_poly_net".to_string()),
@@ -755,6 +770,7 @@ fn proto1() -> LayoutResult<()> {
                 inner: Shape::Polygon(Polygon {
                     points: vec![Point::default(), Point::default(), Point::default()],
                 }),
+                labels: Vec::new(),
             },
             Element {
                 net: Some("prt_path_net".to_string()),
@@ -764,6 +780,7 @@ fn proto1() -> LayoutResult<()> {
                     width: 5,
                     points: vec![Point::default(), Point::default(), Point::default()],
                 }),
+                labels: Vec::new(),
             },
         ],
         insts: Vec::new(),
@@ -0,0 +1,121 @@
+//!
+//! # Rasterized Geometry Comparison
+//!
+//! Coarse geometric equivalence checks, for cases in which exact element-by-element comparisons are too strict,
+//! e.g. when a change legitimately alters how geometry is decomposed into [Element]s.
+//! [rasterize] paints a [Cell]'s flattened geometry on a single layer onto a [BitGrid],
+//! and [raster_equal] compares two such grids.
+//!
+
+// Std-Lib
+use std::collections::BTreeSet;
+
+// Local imports
+use crate::{
+    bbox::BoundBoxTrait,
+    error::{LayoutError, LayoutResult},
+    geom::{Point, ShapeTrait},
+    Cell, Int, LayerKey,
+};
+
+/// # Boolean Raster Grid
+///
+/// Set of painted pixels, each a `grid`-sized square.
+/// Pixel `(i, j)` spans x-coordinates `[i * grid, (i + 1) * grid)`, and similarly in y,
+/// and is painted if its center lies within any shape.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitGrid {
+    /// Pixel size, in database units
+    pub grid: Int,
+    /// Painted pixel indices
+    pub pixels: BTreeSet<(Int, Int)>,
+}
+impl BitGrid {
+    /// Create a new and empty [BitGrid] with pixel size `grid`
+    pub fn new(grid: Int) -> Self {
+        Self {
+            grid,
+            pixels: BTreeSet::new(),
+        }
+    }
+    /// Boolean indication of whether pixel `(i, j)` is painted
+    pub fn get(&self, i: Int, j: Int) -> bool {
+        self.pixels.contains(&(i, j))
+    }
+    /// Get the number of painted pixels
+    pub fn count(&self) -> usize {
+        self.pixels.len()
+    }
+    /// Get the lower-left corner of pixel `(i, j)`
+    fn corner(&self, i: Int, j: Int) -> Point {
+        Point::new(i * self.grid, j * self.grid)
+    }
+}
+
+/// # Raster Difference
+///
+/// A pixel painted in one of two compared [BitGrid]s, but not the other.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffPixel {
+    /// Lower-left corner of the pixel
+    pub loc: Point,
+    /// Whether the pixel is painted in the first grid, and not the second.
+    /// If `false`, it is painted in the second grid only.
+    pub in_a: bool,
+}
+
+/// Rasterize the flattened geometry of `cell` on `layer`, all purposes included,
+/// onto a [BitGrid] with pixel size `grid`.
+/// Fails if `cell` has no [Layout](crate::Layout), or if `grid` is not positive.
+pub fn rasterize(cell: &Cell, layer: LayerKey, grid: Int) -> LayoutResult<BitGrid> {
+    if grid <= 0 {
+        return LayoutError::fail(format!("Invalid raster grid {}", grid));
+    }
+    let layout = match cell.layout {
+        Some(ref layout) => layout,
+        None => {
+            return LayoutError::fail(format!(
+                "Cannot rasterize Cell {} without Layout",
+                cell.name
+            ))
+        }
+    };
+    let mut raster = BitGrid::new(grid);
+    for elem in layout.flatten()?.iter().filter(|e| e.layer == layer) {
+        // Check the center of each pixel within the shape's bounding box
+        let bbox = elem.inner.bbox();
+        let (i0, j0) = (bbox.p0.x.div_euclid(grid), bbox.p0.y.div_euclid(grid));
+        let (i1, j1) = (bbox.p1.x.div_euclid(grid), bbox.p1.y.div_euclid(grid));
+        for i in i0..=i1 {
+            for j in j0..=j1 {
+                let center = Point::new(i * grid + grid / 2, j * grid + grid / 2);
+                if elem.inner.contains(&center) {
+                    raster.pixels.insert((i, j));
+                }
+            }
+        }
+    }
+    Ok(raster)
+}
+
+/// Compare [BitGrid]s `a` and `b`, returning each differing pixel in index order.
+/// Equal grids produce no differences. Fails if `a` and `b` have different pixel sizes.
+pub fn raster_equal(a: &BitGrid, b: &BitGrid) -> LayoutResult<Vec<DiffPixel>> {
+    if a.grid != b.grid {
+        return LayoutError::fail(format!(
+            "Cannot compare rasters of different grids {} and {}",
+            a.grid, b.grid
+        ));
+    }
+    let diffs = a
+        .pixels
+        .symmetric_difference(&b.pixels)
+        .map(|(i, j)| DiffPixel {
+            loc: a.corner(*i, *j),
+            in_a: a.get(*i, *j),
+        })
+        .collect();
+    Ok(diffs)
+}
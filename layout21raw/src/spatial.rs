@@ -0,0 +1,138 @@
+//!
+//! # Spatial Queries
+//!
+//! A uniform-grid index of the [Element](crate::Element)s of a [Cell], for finding those near a region or point
+//! without scanning every element.
+//!
+
+// Local Imports
+use crate::bbox::{BoundBox, BoundBoxTrait};
+use crate::data::Cell;
+use crate::geom::Point;
+use crate::Int;
+
+/// # Cell Index
+///
+/// Uniform-grid spatial index over the bounding boxes of a [Cell]'s [Element](crate::Element)s,
+/// referring to each by its index into the cell's `layout.elems`.
+/// The grid has roughly one bin per element, so queries examine only the elements near their region.
+///
+/// Indices are snapshots: rebuild them via [CellIndex::build] after modifying the cell.
+/// They are not serialized.
+///
+#[derive(Debug, Clone, Default)]
+pub struct CellIndex {
+    /// Bounding box of each element, by element index
+    bboxes: Vec<BoundBox>,
+    /// Lower-left corner of the grid
+    origin: Point,
+    /// Bin sizes, in x and y
+    bin: (Int, Int),
+    /// Numbers of bins, in x and y
+    nbins: (usize, usize),
+    /// Element indices per bin, in row-major order
+    bins: Vec<Vec<usize>>,
+}
+impl CellIndex {
+    /// Build the index of `cell`'s layout elements. Empty if it has no layout.
+    pub fn build(cell: &Cell) -> Self {
+        let bboxes: Vec<BoundBox> = match cell.layout {
+            Some(ref layout) => layout.elems.iter().map(|e| e.inner.bbox()).collect(),
+            None => Vec::new(),
+        };
+        let extent = bboxes
+            .iter()
+            .filter(|b| !b.is_empty())
+            .fold(BoundBox::empty(), |u, b| u.union(b));
+        if extent.is_empty() {
+            return Self {
+                bboxes,
+                ..Default::default()
+            };
+        }
+        // Aim for about one bin per element, in a square arrangement
+        let side = ((bboxes.len() as f64).sqrt().ceil() as usize).max(1);
+        let size = |lo: Int, hi: Int| ((hi - lo) / side as Int + 1).max(1);
+        let mut index = Self {
+            origin: extent.p0,
+            bin: (
+                size(extent.p0.x, extent.p1.x),
+                size(extent.p0.y, extent.p1.y),
+            ),
+            nbins: (side, side),
+            bins: vec![Vec::new(); side * side],
+            bboxes: Vec::new(),
+        };
+        for (idx, bbox) in bboxes.iter().enumerate() {
+            if bbox.is_empty() {
+                continue;
+            }
+            let (xs, ys) = index.bin_ranges(bbox);
+            for y in ys {
+                for x in xs.clone() {
+                    index.bins[y * side + x].push(idx);
+                }
+            }
+        }
+        index.bboxes = bboxes;
+        index
+    }
+    /// Get the number of indexed elements
+    pub fn len(&self) -> usize {
+        self.bboxes.len()
+    }
+    /// Boolean indication of whether we index no elements
+    pub fn is_empty(&self) -> bool {
+        self.bboxes.is_empty()
+    }
+    /// Get the indices of the elements whose bounding boxes intersect or touch `bbox`, in ascending order
+    pub fn query_region(&self, bbox: &BoundBox) -> impl Iterator<Item = usize> + '_ {
+        let mut found = Vec::new();
+        if !bbox.is_empty() && !self.bins.is_empty() {
+            let (xs, ys) = self.bin_ranges(bbox);
+            for y in ys {
+                for x in xs.clone() {
+                    found.extend(self.bins[y * self.nbins.0 + x].iter().copied());
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        let bbox = *bbox;
+        found
+            .into_iter()
+            .filter(move |idx| !self.bboxes[*idx].intersection(&bbox).is_empty())
+    }
+    /// Get the indices of the elements whose bounding boxes contain `pt`, in ascending order.
+    /// As with all of our queries, non-rectangular shapes may not themselves contain `pt`.
+    pub fn query_point(&self, pt: &Point) -> impl Iterator<Item = usize> + '_ {
+        self.query_region(&BoundBox::from_point(pt))
+    }
+    /// Get the ranges of bins, in x and y, which `bbox` overlaps, clamped to the grid
+    fn bin_ranges(&self, bbox: &BoundBox) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let range = |lo: Int, hi: Int, origin: Int, bin: Int, n: usize| {
+            // Regions entirely beyond the grid overlap no bins
+            if hi < origin || lo >= origin + bin * n as Int {
+                return 0..0;
+            }
+            let clamp = |v: Int| (v - origin).div_euclid(bin).clamp(0, n as Int - 1) as usize;
+            clamp(lo)..clamp(hi) + 1
+        };
+        (
+            range(
+                bbox.p0.x,
+                bbox.p1.x,
+                self.origin.x,
+                self.bin.0,
+                self.nbins.0,
+            ),
+            range(
+                bbox.p0.y,
+                bbox.p1.y,
+                self.origin.y,
+                self.bin.1,
+                self.nbins.1,
+            ),
+        )
+    }
+}
@@ -92,7 +92,1695 @@ fn test_gds_to_proto1() -> LayoutResult<()> {
     assert_eq!(plib, plib2);
     Ok(())
 }
+/// Export a small hierarchical library to GDSII, and inspect it via [gds_summary]
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_summary1() -> LayoutResult<()> {
+    let mut lib = Library::new("gds_summary_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    // Create a child cell with two labelled rectangles
+    let mut child = Layout {
+        name: "child".into(),
+        ..Default::default()
+    };
+    for (idx, net) in ["a", "b"].iter().enumerate() {
+        let x = 100 * idx as Int;
+        child.elems.push(Element {
+            net: Some(net.to_string()),
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(x, 0),
+                p1: Point::new(x + 50, 200),
+            }),
+            labels: Vec::new(),
+        });
+    }
+    let child = lib.cells.add(child);
+    // And a parent with two instances of it, one rotated
+    let mut parent = Layout {
+        name: "parent".into(),
+        ..Default::default()
+    };
+    parent.insts.push(Instance {
+        inst_name: "i0".into(),
+        cell: child.clone(),
+        loc: Point::new(1000, 0),
+        reflect_vert: false,
+        angle: None,
+    });
+    parent.insts.push(Instance {
+        inst_name: "i1".into(),
+        cell: child.clone(),
+        loc: Point::new(0, 0),
+        reflect_vert: false,
+        angle: Some(90.),
+    });
+    lib.cells.add(parent);
+
+    // Export to GDS, write it to disk, and summarize the result
+    let gds = lib.to_gds()?;
+    let fname = resource("gds_summary1.gds");
+    gds.save(&fname)?;
+    let summary = gds_summary(&fname)?;
+
+    // Cross-check against the GDS library's own stats
+    assert_eq!(summary.name, "gds_summary_lib");
+    assert_eq!(summary.stats, gds.stats());
+    let names: Vec<_> = summary.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["child", "parent"]);
+
+    let child = summary.strukt("child").unwrap();
+    assert_eq!(child.elems, 4);
+    assert_eq!(child.count(68, 20), 2);
+    assert_eq!(child.count(68, 5), 2);
+    assert!(child.refs.is_empty());
+    assert_eq!(
+        child.bbox,
+        Some(BoundBox::from_points(
+            &Point::new(0, 0),
+            &Point::new(150, 200)
+        ))
+    );
+
+    let parent = summary.strukt("parent").unwrap();
+    assert_eq!(parent.elems, 2);
+    assert!(parent.layers.is_empty());
+    assert_eq!(parent.refs, vec!["child".to_string()]);
+    assert_eq!(
+        parent.bbox,
+        Some(BoundBox::from_points(
+            &Point::new(-200, 0),
+            &Point::new(1150, 200)
+        ))
+    );
+
+    // Check the summary serializes, and displays each struct
+    let yaml = utils::SerializationFormat::Yaml
+        .to_string(&summary)
+        .unwrap();
+    assert!(yaml.contains("parent"));
+    let table = summary.to_string();
+    assert!(table.contains("| child "));
+    assert!(table.contains("| parent "));
+    Ok(())
+}
+/// Export with [gds::CellLabelOptions], labeling each cell with its name
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_cell_labels() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, CellLabelOptions, GdsExportOptions};
+
+    let mut lib = Library::new("cell_labels_lib", Units::Nano);
+    let boundary = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("boundary").unwrap()
+    };
+    for (name, size) in [("small", 100), ("big", 1000)] {
+        let outline = Element {
+            net: None,
+            layer: boundary,
+            purpose: LayerPurpose::Outline,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(size, 2 * size),
+            }),
+            labels: Vec::new(),
+        };
+        lib.cells.add(Layout {
+            name: name.into(),
+            elems: vec![outline],
+            ..Default::default()
+        });
+    }
+    let opts = GdsExportOptions {
+        cell_labels: Some(CellLabelOptions {
+            layer: None,
+            mag: Some(2.0),
+        }),
+        ..Default::default()
+    };
+    // The boundary layer has no label datatype, so this should fail
+    assert!(lib.to_gds_with_options(&opts).is_err());
+    // Add one, and export again
+    lib.layers
+        .write()?
+        .slots
+        .get_mut(boundary)
+        .unwrap()
+        .add_purpose(5, LayerPurpose::Label)?;
+    let gds = lib.to_gds_with_options(&opts)?;
+
+    assert_eq!(gds.structs.len(), 2);
+    for strukt in gds.structs.iter() {
+        let labels: Vec<_> = strukt
+            .elems
+            .iter()
+            .filter_map(|e| match e {
+                GdsElement::GdsTextElem(t) => Some(t),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].string, strukt.name);
+        assert_eq!((labels[0].layer, labels[0].texttype), (236, 5));
+        assert_eq!(labels[0].strans.as_ref().unwrap().mag, Some(2.0));
+    }
+    // Check the label lands in the center of the boundary
+    let big = gds.structs.iter().find(|s| s.name == "big").unwrap();
+    let label = big.elems.last().unwrap();
+    match label {
+        GdsElement::GdsTextElem(t) => assert_eq!((t.xy.x, t.xy.y), (500, 1000)),
+        _ => panic!("Expected a text element"),
+    }
+    // And with the option disabled, no labels are added
+    let gds = lib.to_gds()?;
+    for strukt in gds.structs.iter() {
+        assert_eq!(strukt.elems.len(), 1);
+    }
+    Ok(())
+}
+/// Export and re-import [Cell::props], as text annotations per [gds::GdsExportOptions::cell_props]
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_cell_props() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, GdsExportOptions, GdsImportOptions};
+
+    let mut lib = Library::new("cell_props_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let mut cell = Cell::from(Layout {
+        name: "propped".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(100, 100),
+            }),
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    });
+    cell.props.insert("generator".into(), "layout21".into());
+    cell.props.insert("version".into(), "1.2=3".into());
+    lib.cells.add(cell);
+
+    // With the option disabled, no annotations are added
+    let gds = lib.to_gds()?;
+    assert_eq!(gds.structs[0].elems.len(), 1);
+
+    let opts = GdsExportOptions {
+        cell_props: Some((200, 7)),
+        ..Default::default()
+    };
+    let gds = lib.to_gds_with_options(&opts)?;
+    let texts: Vec<_> = gds.structs[0]
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsTextElem(t) => Some((t.string.as_str(), t.layer, t.texttype)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        texts,
+        vec![("generator=layout21", 200, 7), ("version=1.2=3", 200, 7)]
+    );
+
+    // Importing with the same layer recovers the properties, without adding annotations
+    let iopts = GdsImportOptions {
+        cell_props: Some((200, 7)),
+        ..Default::default()
+    };
+    let lib2 = Library::from_gds_with_options(&gds, None, &iopts)?;
+    let cell2 = lib2.cells[0].read()?;
+    assert_eq!(cell2.props, lib.cells[0].read()?.props);
+    assert!(cell2.layout.as_ref().unwrap().annotations.is_empty());
+    // And without it, they remain annotations
+    let lib3 = Library::from_gds(&gds, None)?;
+    let cell3 = lib3.cells[0].read()?;
+    assert!(cell3.props.is_empty());
+    assert_eq!(cell3.layout.as_ref().unwrap().annotations.len(), 2);
+
+    // Keys including an '=' cannot be exported
+    lib.cells[0].write()?.props.insert("a=b".into(), "c".into());
+    assert!(lib.to_gds_with_options(&opts).is_err());
+    Ok(())
+}
+/// Export with [gds::NetLabelOptions], checking net-label orientation and placement
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_net_labels() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, GdsExportOptions, LabelPlacement, NetLabelOptions};
+
+    let mut lib = Library::new("net_labels_lib", Units::Nano);
+    let (met1, met2) = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        (
+            liblayers.keyname("met1").unwrap(),
+            liblayers.keyname("met2").unwrap(),
+        )
+    };
+    // A near-square rectangle on met1, slightly taller than wide, and a wide one on met2
+    let rect = |layer: LayerKey, x: Int, y: Int| Element {
+        net: Some("net".into()),
+        layer,
+        purpose: LayerPurpose::Drawing,
+        inner: Shape::Rect(Rect {
+            p0: Point::new(0, 0),
+            p1: Point::new(x, y),
+        }),
+        labels: Vec::new(),
+    };
+    lib.cells.add(Layout {
+        name: "labeled".into(),
+        elems: vec![rect(met1, 140, 150), rect(met2, 1000, 100)],
+        ..Default::default()
+    });
+    // Get the (angle, location) of each label
+    type Label = (Option<f64>, (i32, i32));
+    let labels = |net_labels: NetLabelOptions| -> LayoutResult<Vec<Label>> {
+        let opts = GdsExportOptions {
+            net_labels,
+            ..Default::default()
+        };
+        let gds = lib.to_gds_with_options(&opts)?;
+        let labels = gds.structs[0]
+            .elems
+            .iter()
+            .filter_map(|e| match e {
+                GdsElement::GdsTextElem(t) => {
+                    Some((t.strans.as_ref().and_then(|s| s.angle), (t.xy.x, t.xy.y)))
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(labels)
+    };
+    // By default, the taller-than-wide rectangle is rotated
+    let default = labels(NetLabelOptions::default())?;
+    assert_eq!(default, vec![(Some(90.0), (70, 75)), (None, (500, 50))]);
+    // With a 1.5 threshold it stays horizontal
+    let relaxed = labels(NetLabelOptions {
+        rotate_ratio: 1.5,
+        ..Default::default()
+    })?;
+    assert_eq!(relaxed[0], (None, (70, 75)));
+    // Per-layer overrides take priority over the threshold
+    let mut opts = NetLabelOptions {
+        rotate_ratio: 1.5,
+        ..Default::default()
+    };
+    opts.orientations.insert(met2, Dir::Vert);
+    let overridden = labels(opts)?;
+    assert_eq!(overridden[0].0, None);
+    assert_eq!(overridden[1].0, Some(90.0));
+    // And start-placement moves labels to the bottom or left end of each rectangle
+    let start = labels(NetLabelOptions {
+        placement: LabelPlacement::Start,
+        ..Default::default()
+    })?;
+    assert_eq!(start[0].1, (70, 70));
+    assert_eq!(start[1].1, (50, 50));
+    Ok(())
+}
+/// Export additional [Label]s on an [Element], alongside its net label
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_element_labels() -> LayoutResult<()> {
+    use gds::gds21::GdsElement;
+
+    let mut lib = Library::new("element_labels_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let drawing = Label {
+        purpose: Some(LayerPurpose::Drawing),
+        ..Label::new("crit", 1.0)
+    };
+    let elem = Element {
+        net: Some("data".into()),
+        layer: met1,
+        purpose: LayerPurpose::Drawing,
+        inner: Shape::Rect(Rect {
+            p0: Point::new(0, 0),
+            p1: Point::new(100, 10),
+        }),
+        labels: vec![Label::new("data", 0.0), drawing],
+    };
+    // Positions are clamped, and run along the longer dimension
+    assert_eq!(
+        Label::new("x", -1.0).location(&elem.inner),
+        Point::new(0, 5)
+    );
+    assert_eq!(
+        Label::new("x", 2.0).location(&elem.inner),
+        Point::new(100, 5)
+    );
+    assert_eq!(
+        Label::new("x", 0.25).location(&elem.inner),
+        Point::new(25, 5)
+    );
+    let tall = Shape::Rect(Rect {
+        p0: Point::new(0, 0),
+        p1: Point::new(10, 100),
+    });
+    assert_eq!(Label::new("x", 0.5).location(&tall), Point::new(5, 50));
+
+    lib.cells.add(Layout {
+        name: "labeled".into(),
+        elems: vec![elem],
+        ..Default::default()
+    });
+    let gds = lib.to_gds()?;
+    let texts: Vec<_> = gds.structs[0]
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsTextElem(t) => Some((t.string.as_str(), t.texttype, t.xy.x, t.xy.y)),
+            _ => None,
+        })
+        .collect();
+    // The net label is centered as usual, followed by one text element at each end
+    assert_eq!(
+        texts,
+        vec![("data", 5, 50, 5), ("data", 5, 0, 5), ("crit", 20, 100, 5),]
+    );
+    Ok(())
+}
+/// Check each [gds::LongLabelPolicy] for net labels over a [gds::LabelLimit]
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_label_limit() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, GdsExportOptions, LabelLimit, LongLabelPolicy};
+
+    let mut lib = Library::new("label_limit_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    // Two long nets sharing a prefix well past the limit, and a short one
+    let nets = [
+        "core_top_u_fabric_u_switch_3_req_payload_127",
+        "core_top_u_fabric_u_switch_3_req_payload_126",
+        "clk",
+    ];
+    let elems = nets
+        .iter()
+        .map(|net| Element {
+            net: Some(net.to_string()),
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(100, 100),
+            }),
+            labels: Vec::new(),
+        })
+        .collect();
+    lib.cells.add(Layout {
+        name: "labeled".into(),
+        elems,
+        ..Default::default()
+    });
+    let opts = |policy| GdsExportOptions {
+        label_limit: Some(LabelLimit {
+            max_len: 24,
+            policy,
+        }),
+        ..Default::default()
+    };
+    let texts = |gds: &gds::gds21::GdsLibrary| -> Vec<String> {
+        gds.structs[0]
+            .elems
+            .iter()
+            .filter_map(|e| match e {
+                GdsElement::GdsTextElem(t) => Some(t.string.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // By default, and when allowed, labels are exported as-is
+    assert_eq!(texts(&lib.to_gds()?), nets);
+    assert_eq!(
+        texts(&lib.to_gds_with_options(&opts(LongLabelPolicy::Allow))?),
+        nets
+    );
+
+    // Errors list every label over the limit
+    let err = lib
+        .to_gds_with_options(&opts(LongLabelPolicy::Error))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains(nets[0]) && err.contains(nets[1]));
+    assert!(!err.contains("clk"));
+
+    // Truncation keeps the shared prefix, and distinguishes the two by their hashes
+    let (gds, report) = lib.to_gds_with_report(&opts(LongLabelPolicy::Truncate))?;
+    let truncated = texts(&gds);
+    assert_eq!(truncated[2], "clk");
+    assert_ne!(truncated[0], truncated[1]);
+    for (idx, text) in truncated[..2].iter().enumerate() {
+        assert_eq!(text.len(), 24);
+        assert!(text.starts_with("core_top_u_fabric_"));
+        assert_eq!(report.labels[nets[idx]], *text);
+    }
+    assert_eq!(report.labels.len(), 2);
+    // Deterministically, across exports
+    let (_, again) = lib.to_gds_with_report(&opts(LongLabelPolicy::Truncate))?;
+    assert_eq!(again, report);
+    // And limits too short for the hash suffix fail
+    let short = GdsExportOptions {
+        label_limit: Some(LabelLimit {
+            max_len: 7,
+            policy: LongLabelPolicy::Truncate,
+        }),
+        ..Default::default()
+    };
+    assert!(lib.to_gds_with_options(&short).is_err());
+    Ok(())
+}
+/// Check that [gds::GdsExportLimits] abort export, naming the offending cell
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_export_limits() -> LayoutResult<()> {
+    use gds::{GdsExportLimits, GdsExportOptions};
+
+    let mut lib = Library::new("export_limits_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    // Create three cells, with 1, 10, and 100 rectangles each
+    for (name, num) in [("one", 1), ("ten", 10), ("hundred", 100)] {
+        let elems = (0..num)
+            .map(|k| Element {
+                net: None,
+                layer: met1,
+                purpose: LayerPurpose::Drawing,
+                inner: Shape::Rect(Rect {
+                    p0: Point::new(10 * k, 0),
+                    p1: Point::new(10 * k + 5, 100),
+                }),
+                labels: Vec::new(),
+            })
+            .collect();
+        lib.cells.add(Layout {
+            name: name.into(),
+            elems,
+            ..Default::default()
+        });
+    }
+    let export = |limits: GdsExportLimits| {
+        let opts = GdsExportOptions {
+            limits,
+            ..Default::default()
+        };
+        lib.to_gds_with_options(&opts)
+    };
+    // Default limits are unlimited
+    assert_eq!(export(GdsExportLimits::default())?.structs.len(), 3);
+
+    let err = export(GdsExportLimits {
+        max_cell_elems: Some(50),
+        ..Default::default()
+    })
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("Cell hundred has 100 elements"));
+
+    let err = export(GdsExportLimits {
+        max_total_elems: Some(20),
+        ..Default::default()
+    })
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("Cell hundred"));
+    assert!(err.contains("111"));
+
+    let err = export(GdsExportLimits {
+        max_structs: Some(1),
+        ..Default::default()
+    })
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("Cell ten"));
+    Ok(())
+}
+/// Export two cells sharing a child to separate GDS files
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_export_each() -> LayoutResult<()> {
+    use gds::{gds21::GdsLibrary, GdsExportOptions};
+
+    let mut lib = Library::new("export_each_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let child = lib.cells.add(Layout {
+        name: "child".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(10, 10),
+            }),
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    });
+    for name in ["top1", "top2"] {
+        lib.cells.add(Layout {
+            name: name.into(),
+            insts: vec![Instance {
+                inst_name: "i0".into(),
+                cell: child.clone(),
+                loc: Point::new(0, 0),
+                reflect_vert: false,
+                angle: None,
+            }],
+            ..Default::default()
+        });
+    }
+    // Export into a not-yet-created, nested directory, including spaces in its path
+    let root = std::env::temp_dir().join("layout21raw export_each");
+    let _ = std::fs::remove_dir_all(&root);
+    let dir = root.join("nested dir");
+    let opts = GdsExportOptions::default();
+
+    // Unknown names fail before writing anything
+    let err = lib.export_each(&dir, &["top1", "nope"], &opts).unwrap_err();
+    assert!(err.to_string().contains("nope"));
+    assert!(!dir.join("top1.gds").exists());
+
+    // Each file holds its top cell, plus its own copy of the shared child
+    let paths = lib.export_each(&dir, &["top1", "top2"], &opts)?;
+    assert_eq!(paths, vec![dir.join("top1.gds"), dir.join("top2.gds")]);
+    for (path, top) in paths.iter().zip(["top1", "top2"]) {
+        let gds = GdsLibrary::load(path)?;
+        let mut names: Vec<_> = gds.structs.iter().map(|s| s.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["child", top]);
+    }
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+/// Name exported structs after their cells, which their instances reference, rather than their views
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_struct_names() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, GdsExportOptions};
+
+    let mut lib = Library::new("struct_names_lib", Units::Nano);
+    // A cell whose layout has drifted from its name
+    let mut child = Cell::from(Layout {
+        name: "stale".into(),
+        ..Default::default()
+    });
+    child.name = "child".into();
+    let child = lib.cells.add(child);
+    lib.cells.add(Layout {
+        name: "parent".into(),
+        insts: vec![Instance {
+            inst_name: "i0".into(),
+            cell: child.clone(),
+            loc: Point::new(0, 0),
+            reflect_vert: false,
+            angle: None,
+        }],
+        ..Default::default()
+    });
+    let (gds, report) = lib.to_gds_with_report(&GdsExportOptions::default())?;
+    let names: Vec<_> = gds.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["child", "parent"]);
+    assert_eq!(
+        report.warnings,
+        vec!["View of Cell child is named stale; exported as child".to_string()]
+    );
+    let srefs: Vec<_> = gds.structs[1]
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsStructRef(r) => Some(r.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(srefs, vec!["child"]);
+    Ok(())
+}
+/// Check that GDS export invokes its progress callbacks once per cell
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_progress() -> LayoutResult<()> {
+    use gds::GdsExportOptions;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use utils::{Progress, ProgressHook};
+
+    #[derive(Default)]
+    struct Counter {
+        started: AtomicUsize,
+        done: AtomicUsize,
+        elems: AtomicUsize,
+    }
+    impl Progress for Counter {
+        fn on_cell_start(&self, _name: &str, idx: usize, total: usize) {
+            assert!(idx < total);
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+        fn on_cell_done(&self, _name: &str, elems: usize) {
+            self.done.fetch_add(1, Ordering::SeqCst);
+            self.elems.fetch_add(elems, Ordering::SeqCst);
+        }
+    }
+
+    let mut lib = Library::new("progress_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let child = lib.cells.add(Layout {
+        name: "child".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(10, 10),
+            }),
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    });
+    lib.cells.add(Layout {
+        name: "parent".into(),
+        insts: vec![Instance {
+            inst_name: "i0".into(),
+            cell: child,
+            loc: Point::new(0, 0),
+            reflect_vert: false,
+            angle: None,
+        }],
+        ..Default::default()
+    });
+    let counter = Arc::new(Counter::default());
+    let opts = GdsExportOptions {
+        progress: ProgressHook::new(counter.clone()),
+        ..Default::default()
+    };
+    lib.to_gds_with_options(&opts)?;
+    assert_eq!(counter.started.load(Ordering::SeqCst), 2);
+    assert_eq!(counter.done.load(Ordering::SeqCst), 2);
+    // One rectangle in the child, one instance in the parent
+    assert_eq!(counter.elems.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+/// Export an inverted rectangle to GDSII, and check it emits a counter-clockwise boundary from its lower-left corner
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_inverted_rect() -> LayoutResult<()> {
+    use gds::gds21::{GdsElement, GdsPoint};
+
+    let mut lib = Library::new("inverted_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    lib.cells.add(Layout {
+        name: "inverted".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(10, 5),
+                p1: Point::new(0, 0),
+            }),
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    });
+    let gds = lib.to_gds()?;
+    let xy = match &gds.structs[0].elems[0] {
+        GdsElement::GdsBoundary(b) => b.xy.clone(),
+        other => panic!("Expected a GdsBoundary, got {:?}", other),
+    };
+    let expected = GdsPoint::vec(&[(0, 0), (10, 0), (10, 5), (0, 5), (0, 0)]);
+    assert_eq!(xy, expected);
+
+    // Re-import, and check the rectangle arrives normalized
+    let lib2 = Library::from_gds(&gds, None)?;
+    let cell = lib2.cells.first().unwrap().read()?;
+    let elem = &cell.layout.as_ref().unwrap().elems[0];
+    assert!(elem.inner.is_normalized());
+    assert_eq!(
+        elem.inner,
+        Shape::Rect(Rect {
+            p0: Point::new(0, 0),
+            p1: Point::new(10, 5),
+        })
+    );
+    Ok(())
+}
+/// Check bounding boxes and flattening of rotated instances
+#[test]
+fn test_rotated_instance_bbox() -> LayoutResult<()> {
+    let met1 = layers()?.keyname("met1").unwrap();
+    // Create a 2x1 child cell
+    let child = Layout {
+        name: "child".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(0, 0),
+                p1: Point::new(2, 1),
+            }),
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    };
+    let child = utils::Ptr::new(Cell::from(child));
+    // Place it rotated by 90 degrees, at (10, 10)
+    let mut parent = Layout {
+        name: "parent".into(),
+        insts: vec![Instance {
+            inst_name: "i0".into(),
+            cell: child.clone(),
+            loc: Point::new(10, 10),
+            reflect_vert: false,
+            angle: Some(90.),
+        }],
+        ..Default::default()
+    };
+    // Check that its bbox is 1x2, extending left from the instance origin
+    let expected = BoundBox::from_points(&Point::new(9, 10), &Point::new(10, 12));
+    assert_eq!(parent.insts[0].bbox()?, expected);
+    assert_eq!(parent.bbox()?, expected);
+    assert_eq!(parent.bbox()?.size(), (1, 2));
+
+    // And that flattening produces the same result
+    let flat = parent.flatten()?;
+    assert_eq!(flat.len(), 1);
+    assert_eq!(flat[0].inner.bbox(), expected);
+
+    // Angles other than multiples of 90 degrees produce errors, including the angle
+    parent.insts[0].angle = Some(45.);
+    let err = parent.bbox().unwrap_err();
+    assert!(err.to_string().contains("45"));
+    assert!(parent.flatten().is_err());
+    Ok(())
+}
+/// Check each combination of reflection and rotation against the corners of a unit square
+#[test]
+fn test_instance_orientations() -> LayoutResult<()> {
+    let child = utils::Ptr::new(Cell::from(Layout {
+        name: "child".into(),
+        ..Default::default()
+    }));
+    let square = [
+        Point::new(0, 0),
+        Point::new(1, 0),
+        Point::new(1, 1),
+        Point::new(0, 1),
+    ];
+    // (reflect_vert, angle) => transformed corners
+    let table = [
+        ((false, None), [(0, 0), (1, 0), (1, 1), (0, 1)]),
+        ((false, Some(90.)), [(0, 0), (0, 1), (-1, 1), (-1, 0)]),
+        ((false, Some(180.)), [(0, 0), (-1, 0), (-1, -1), (0, -1)]),
+        ((false, Some(270.)), [(0, 0), (0, -1), (1, -1), (1, 0)]),
+        ((true, None), [(0, 0), (1, 0), (1, -1), (0, -1)]),
+        ((true, Some(90.)), [(0, 0), (0, 1), (1, 1), (1, 0)]),
+        ((true, Some(180.)), [(0, 0), (-1, 0), (-1, 1), (0, 1)]),
+        ((true, Some(270.)), [(0, 0), (0, -1), (-1, -1), (-1, 0)]),
+    ];
+    for ((reflect_vert, angle), expected) in table {
+        let inst = Instance {
+            inst_name: "i0".into(),
+            cell: child.clone(),
+            loc: Point::new(0, 0),
+            reflect_vert,
+            angle,
+        };
+        let trans = inst.transform()?;
+        let corners: Vec<_> = square
+            .iter()
+            .map(|p| p.transform(&trans))
+            .map(|p| (p.x, p.y))
+            .collect();
+        assert_eq!(
+            corners, expected,
+            "reflect_vert={reflect_vert}, angle={angle:?}"
+        );
+    }
+    Ok(())
+}
+/// Check that exported GDSII orientations, read back via gds21, match [Instance::transform]
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_instance_orientations() -> LayoutResult<()> {
+    use gds::gds21::{GdsElement, GdsLibrary};
+
+    let mut lib = Library::new("orientations_lib", Units::Nano);
+    let child = lib.cells.add(Layout {
+        name: "child".into(),
+        ..Default::default()
+    });
+    let mut insts = Vec::new();
+    for reflect_vert in [false, true] {
+        for angle in [None, Some(90.), Some(180.), Some(270.)] {
+            insts.push(Instance {
+                inst_name: format!("i{}", insts.len()),
+                cell: child.clone(),
+                loc: Point::new(10, 20),
+                reflect_vert,
+                angle,
+            });
+        }
+    }
+    lib.cells.add(Layout {
+        name: "parent".into(),
+        insts: insts.clone(),
+        ..Default::default()
+    });
+    let path = std::env::temp_dir().join("layout21raw_instance_orientations.gds");
+    lib.to_gds()?.save(&path)?;
+    let gds = GdsLibrary::load(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let parent = gds.structs.iter().find(|s| s.name == "parent").unwrap();
+    let srefs: Vec<_> = parent
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsStructRef(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(srefs.len(), insts.len());
+    let square = [
+        Point::new(0, 0),
+        Point::new(1, 0),
+        Point::new(1, 1),
+        Point::new(0, 1),
+    ];
+    for (sref, inst) in srefs.iter().zip(insts.iter()) {
+        let (reflect_vert, angle) = gds::import_strans(&sref.strans);
+        let loc = Point::new(sref.xy.x as Int, sref.xy.y as Int);
+        let from_gds = Transform::from_instance(&loc, reflect_vert, angle);
+        let expected = inst.transform()?;
+        for p in square.iter() {
+            assert_eq!(p.transform(&from_gds), p.transform(&expected));
+        }
+    }
+    // And importing recovers the original orientations
+    let lib2 = Library::from_gds(&gds, None)?;
+    let parent2 = lib2.cell_named("parent")?.unwrap();
+    let parent2 = parent2.read()?;
+    let insts2 = &parent2.layout.as_ref().unwrap().insts;
+    for (inst2, inst) in insts2.iter().zip(insts.iter()) {
+        assert_eq!(inst2.reflect_vert, inst.reflect_vert);
+        assert_eq!(inst2.angle, inst.angle);
+    }
+    Ok(())
+}
+#[test]
+fn test_placements() -> LayoutResult<()> {
+    let mut lib = Library::new("placements", Units::Nano);
+    let inst = |name: &str, cell: &utils::Ptr<Cell>, loc, reflect_vert, angle| Instance {
+        inst_name: name.into(),
+        cell: cell.clone(),
+        loc,
+        reflect_vert,
+        angle,
+    };
+    // Three levels: `top` instantiates `core` (rotated) and `pad`, and `core` instantiates a reflected `alu`
+    let alu = lib.cells.add(Layout {
+        name: "alu".into(),
+        ..Default::default()
+    });
+    let pad = lib
+        .cells
+        .add(Abstract::new("pad", Polygon { points: Vec::new() }));
+    let core = lib.cells.add(Layout {
+        name: "core".into(),
+        insts: vec![inst("u_alu", &alu, Point::new(10, 0), true, None)],
+        ..Default::default()
+    });
+    lib.cells.add(Layout {
+        name: "top".into(),
+        insts: vec![
+            inst("u_core", &core, Point::new(100, 200), false, Some(90.)),
+            inst("u_pad", &pad, Point::new(-5, 0), false, None),
+        ],
+        ..Default::default()
+    });
+    let placements = lib.placements("top")?;
+    assert_eq!(placements.len(), 2);
+    assert_eq!(placements[0].path, "top/u_core/u_alu");
+    assert_eq!(placements[0].cell, "alu");
+    assert_eq!(placements[0].depth, 2);
+    assert_eq!(placements[0].loc, Point::new(100, 210));
+    // Reflection then rotation maps the alu's x-axis onto the top-level y-axis
+    assert_eq!(
+        Point::new(1, 0).transform(&placements[0].transform),
+        Point::new(100, 211)
+    );
+    assert_eq!(
+        Point::new(0, 1).transform(&placements[0].transform),
+        Point::new(101, 210)
+    );
+    assert_eq!(placements[1].path, "top/u_pad");
+    assert_eq!(placements[1].cell, "pad");
+    assert_eq!(placements[1].depth, 1);
+    assert_eq!(placements[1].loc, Point::new(-5, 0));
+
+    // Leaf cells place themselves, and undefined cells fail
+    let leaf = lib.placements("alu")?;
+    assert_eq!(leaf.len(), 1);
+    assert_eq!(leaf[0].path, "alu");
+    assert_eq!(leaf[0].transform, Transform::identity());
+    assert!(lib.placements("nope").is_err());
+
+    // Recursive instantiation fails, rather than overflowing
+    let top = lib.cell_named("top")?.unwrap();
+    core.write()?.layout.as_mut().unwrap().insts.push(inst(
+        "u_top",
+        &top,
+        Point::new(0, 0),
+        false,
+        None,
+    ));
+    let err = lib.placements("top").unwrap_err();
+    assert!(err.to_string().contains("recursively instantiates"));
+    Ok(())
+}
+/// Parse a KLayout-style `.lyp` layer-properties file into a [LayerMap]
+#[test]
+fn test_layermap_lyp() -> LayoutResult<()> {
+    let lyp = r#"<?xml version="1.0" encoding="utf-8"?>
+<layer-properties>
+ <properties>
+  <frame-color>#0000ff</frame-color>
+  <name>met1.drawing</name>
+  <source>68/20@1</source>
+ </properties>
+ <properties>
+  <name>met1 pin</name>
+  <source>68/16@1</source>
+ </properties>
+ <properties>
+  <name>metals</name>
+  <source>*/*@*</source>
+  <group-members>
+   <name>met2</name>
+   <source>69/20@1</source>
+  </group-members>
+ </properties>
+</layer-properties>
+"#;
+    let map = LayerMap::parse_lyp(lyp)?;
+    assert_eq!(map.names(), vec!["met1", "met2"]);
+    let layers = map.to_layers()?;
+    let met1 = layers.name("met1").unwrap();
+    assert_eq!(met1.layernum, 68);
+    assert_eq!(met1.num(&LayerPurpose::Drawing), Some(20));
+    assert_eq!(met1.num(&LayerPurpose::Pin), Some(16));
+    let met2 = layers.name("met2").unwrap();
+    assert_eq!(met2.layernum, 69);
+    assert_eq!(met2.num(&LayerPurpose::Drawing), Some(20));
+    Ok(())
+}
+/// Import GDSII with a [LayerMap], naming its layers, and re-using those already defined
+#[cfg(feature = "gds")]
+#[test]
+fn test_gds_import_layermap() -> LayoutResult<()> {
+    use gds::GdsImportOptions;
+
+    let mut lib = Library::new("layermap_lib", Units::Nano);
+    let met1 = lib.layers.write()?.add(Layer::from_pairs(
+        68,
+        &[(20, LayerPurpose::Drawing), (16, LayerPurpose::Pin)],
+    )?);
+    for purpose in [LayerPurpose::Drawing, LayerPurpose::Pin] {
+        let mut cell = Cell::new(format!("{:?}", purpose));
+        cell.layout = Some(Layout {
+            name: cell.name.clone(),
+            elems: vec![Element {
+                net: None,
+                layer: met1,
+                purpose,
+                inner: Shape::Rect(Rect {
+                    p0: Point::new(0, 0),
+                    p1: Point::new(100, 100),
+                }),
+                labels: Vec::new(),
+            }],
+            ..Default::default()
+        });
+        lib.cells.add(cell);
+    }
+    let gds = lib.to_gds()?;
+    let map = LayerMap::parse("met1 68 20 drawing\nmet1 68 16 pin\n")?;
+
+    // Imported elements land on the named layer, with their mapped purposes
+    let lib2 = Library::from_gds_with_layermap(&gds, &map)?;
+    let layers = lib2.layers.read()?;
+    let key = layers.keyname("met1").unwrap();
+    assert_eq!(layers.slots().len(), 1);
+    for (cell, purpose) in lib2
+        .cells
+        .iter()
+        .zip([LayerPurpose::Drawing, LayerPurpose::Pin])
+    {
+        let cell = cell.read()?;
+        let elem = &cell.layout.as_ref().unwrap().elems[0];
+        assert_eq!(elem.layer, key);
+        assert_eq!(elem.purpose, purpose);
+    }
+    drop(layers);
+
+    // Layers already provided under the same name are re-used, not duplicated
+    let provided = utils::Ptr::new(map.to_layers()?);
+    let opts = GdsImportOptions {
+        layermap: Some(map.clone()),
+        ..Default::default()
+    };
+    let lib3 = Library::from_gds_with_options(&gds, Some(provided), &opts)?;
+    assert_eq!(lib3.layers.read()?.slots().len(), 1);
+    // Unless their layer-numbers differ
+    let mut other = Layers::default();
+    other.add(Layer::new(69, "met1"));
+    let err =
+        Library::from_gds_with_options(&gds, Some(utils::Ptr::new(other)), &opts).unwrap_err();
+    assert!(err.to_string().contains("already defined"));
+    Ok(())
+}
+/// Conflicting purposes for a datatype fail, leaving [Layer]s and [Layers] unmodified
+#[test]
+fn test_layermap_conflicts() -> LayoutResult<()> {
+    let mut layer = Layer::new(68, "met1");
+    layer.add_purpose(20, LayerPurpose::Drawing)?;
+    layer.add_purpose(20, LayerPurpose::Drawing)?; // Re-adding the same mapping is fine
+    let err = layer.add_purpose(20, LayerPurpose::Pin).unwrap_err();
+    assert!(err.to_string().contains("Datatype 20 of layer 68"));
+    assert_eq!(layer.purpose(20), Some(&LayerPurpose::Drawing));
+    assert_eq!(layer.num(&LayerPurpose::Pin), None);
+
+    let mut layers = Layers::default();
+    layers.add(layer);
+    let before = layers.clone();
+    // The new layer `aaa` would be added first, were it not for the conflict on `met1`
+    let map = LayerMap::parse("aaa 67 20 drawing\nmet1 68 16 pin\nmet1 68 20 pin\n")?;
+    assert!(map.add_to(&mut layers).is_err());
+    assert_eq!(layers, before);
+    assert!(layers.keyname("aaa").is_none());
+
+    // While a compatible map applies in full
+    let map = LayerMap::parse("aaa 67 20 drawing\nmet1 68 16 pin\nmet1 68 20 drawing\n")?;
+    map.add_to(&mut layers)?;
+    assert!(layers.keyname("aaa").is_some());
+    let met1 = layers.name("met1").unwrap();
+    assert_eq!(met1.num(&LayerPurpose::Pin), Some(16));
+    assert_eq!(met1.num(&LayerPurpose::Drawing), Some(20));
+    Ok(())
+}
+/// Read a [LayerMap] from file, available with file persistence enabled
+#[cfg(feature = "yaml")]
+#[test]
+fn test_layermap_open() -> LayoutResult<()> {
+    let path = std::env::temp_dir().join("layout21raw_layermap_open.map");
+    std::fs::write(&path, "# name num dt purpose\nmet1 68 20 drawing\n")?;
+    let map = LayerMap::open(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(map.names(), vec!["met1"]);
+    assert!(LayerMap::open(std::env::temp_dir().join("layout21raw_nonexistent.map")).is_err());
+    Ok(())
+}
+/// Exercise geometry and the data model, which are available without any optional features
+#[test]
+fn test_core_geometry() -> LayoutResult<()> {
+    let met1 = layers()?.keyname("met1").unwrap();
+    let rect = Shape::Rect(Rect {
+        p0: Point::new(0, 0),
+        p1: Point::new(4, 2),
+    });
+    let trans = Transform::from_instance(&Point::new(10, 0), false, Some(90.));
+    let expected = BoundBox::from_points(&Point::new(8, 0), &Point::new(10, 4));
+    assert_eq!(rect.transform(&trans).bbox(), expected);
+
+    let mut lib = Library::new("core_lib", Units::Nano);
+    let child = lib.cells.add(Layout {
+        name: "child".into(),
+        elems: vec![Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: rect,
+            labels: Vec::new(),
+        }],
+        ..Default::default()
+    });
+    let inst = Instance {
+        inst_name: "i0".into(),
+        cell: child,
+        loc: Point::new(10, 0),
+        reflect_vert: false,
+        angle: Some(90.),
+    };
+    assert_eq!(inst.bbox()?, expected);
+    Ok(())
+}
+/// Parse and print [Dir] and [Units]
+#[test]
+fn test_dir_units_parsing() -> LayoutResult<()> {
+    for dir in [Dir::Horiz, Dir::Vert] {
+        assert_eq!(dir.to_string().parse::<Dir>()?, dir);
+        assert_eq!(dir.to_string().to_uppercase().parse::<Dir>()?, dir);
+    }
+    assert_eq!("Vertical".parse::<Dir>()?, Dir::Vert);
+    assert!("diagonal".parse::<Dir>().is_err());
+    for units in [Units::Micro, Units::Nano, Units::Angstrom, Units::Pico] {
+        assert_eq!(units.to_string().parse::<Units>()?, units);
+        assert_eq!(units.to_string().to_lowercase().parse::<Units>()?, units);
+    }
+    assert_eq!("NM".parse::<Units>()?, Units::Nano);
+    assert!("furlongs".parse::<Units>().is_err());
+    Ok(())
+}
+/// Compare separately created, and then mutated, [Library]s
+#[test]
+fn test_library_eq_diff() -> LayoutResult<()> {
+    let create = || -> LayoutResult<Library> {
+        let mut lib = Library::new("diff", Units::Nano);
+        let met1 = lib.layers.write()?.add(Layer::new(68, "met1"));
+        let rect = |x: isize| Element {
+            net: Some("vdd".into()),
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(x, 0),
+                p1: Point::new(x + 2, 1),
+            }),
+            labels: Vec::new(),
+        };
+        let child = lib.cells.add(Layout {
+            name: "child".into(),
+            elems: vec![rect(0), rect(5)],
+            ..Default::default()
+        });
+        lib.cells.add(Layout {
+            name: "parent".into(),
+            insts: vec![Instance {
+                inst_name: "i0".into(),
+                cell: child,
+                loc: Point::new(10, 10),
+                reflect_vert: false,
+                angle: Some(90.),
+            }],
+            ..Default::default()
+        });
+        Ok(lib)
+    };
+    let lib = create()?;
+    assert_eq!(lib, lib.clone());
+    // Separately created libraries compare equal, including their instances of distinct-but-same-named cells
+    let lib2 = create()?;
+    assert_eq!(lib, lib2);
+    assert!(lib.diff(&lib2)?.is_empty());
+
+    // Mutate a single element, and check the diff reports it
+    lib2.cells[0].write()?.layout.as_mut().unwrap().elems[1].net = None;
+    assert_ne!(lib, lib2);
+    assert_eq!(
+        lib.diff(&lib2)?,
+        vec!["cells[0].layout.elems[1]".to_string()]
+    );
+
+    // And an instance angle, which compares bitwise
+    let lib3 = create()?;
+    lib3.cells[1].write()?.layout.as_mut().unwrap().insts[0].angle = Some(180.);
+    assert_eq!(
+        lib.diff(&lib3)?,
+        vec!["cells[1].layout.insts[0]".to_string()]
+    );
+    lib3.cells[1].write()?.layout.as_mut().unwrap().insts[0].angle = Some(f64::NAN);
+    let inst = lib3.cells[1].read()?.layout.as_ref().unwrap().insts[0].clone();
+    assert_eq!(inst, inst.clone());
+
+    // And a cell property
+    let lib4 = create()?;
+    lib4.cells[0]
+        .write()?
+        .props
+        .insert("generator".into(), "layout21".into());
+    assert_ne!(lib, lib4);
+    assert_eq!(lib.diff(&lib4)?, vec!["cells[0].props".to_string()]);
+    Ok(())
+}
+#[test]
+fn test_coalesce() -> LayoutResult<()> {
+    let mut layers = Layers::default();
+    let met1 = layers.add(Layer::new(68, "met1"));
+    let met2 = layers.add(Layer::new(69, "met2"));
+    let rect = |net: Option<&str>, layer: LayerKey, x: (isize, isize), y: (isize, isize)| Element {
+        net: net.map(String::from),
+        layer,
+        purpose: LayerPurpose::Drawing,
+        inner: Shape::Rect(Rect {
+            p0: Point::new(x.0, y.0),
+            p1: Point::new(x.1, y.1),
+        }),
+        labels: Vec::new(),
+    };
+    let vpwr = Some("VPWR");
+    // A strap-heavy fixture: bands of horizontal straps, stacked vertically
+    let mut elems = Vec::new();
+    for band in 0..4 {
+        let y0 = band * 100;
+        elems.push(rect(vpwr, met1, (0, 1000), (y0, y0 + 40)));
+        elems.push(rect(vpwr, met1, (0, 1000), (y0 + 40, y0 + 80))); // Abutting
+        elems.push(rect(Some("VGND"), met1, (0, 1000), (y0 + 80, y0 + 90))); // Other net
+        elems.push(rect(None, met1, (0, 1000), (y0 + 90, y0 + 95))); // Unnamed
+        elems.push(rect(None, met1, (0, 1000), (y0 + 95, y0 + 100))); // Unnamed
+    }
+    elems.push(rect(vpwr, met2, (0, 1000), (0, 40))); // Other layer
+    elems.push(rect(vpwr, met1, (0, 999), (80, 120))); // Other extent
+    let mut cell = Cell::from(Layout {
+        name: "straps".into(),
+        elems: elems.clone(),
+        ..Default::default()
+    });
+    cell.coalesce(Dir::Vert);
+    let coalesced = &cell.layout.as_ref().unwrap().elems;
+    assert_eq!(coalesced.len(), elems.len() - 4);
+
+    // Check geometric equivalence: same total area, and each original covered by a same-net, same-layer result
+    let rect_of = |e: &Element| match e.inner {
+        Shape::Rect(ref r) => r.clone(),
+        _ => unreachable!(),
+    };
+    let area = |elems: &[Element]| -> isize {
+        elems
+            .iter()
+            .map(rect_of)
+            .map(|r| (r.p1.x - r.p0.x) * (r.p1.y - r.p0.y))
+            .sum()
+    };
+    assert_eq!(area(&elems), area(coalesced));
+    for e in elems.iter() {
+        let r = rect_of(e);
+        assert!(coalesced.iter().any(|c| {
+            let cr = rect_of(c);
+            c.net == e.net
+                && c.layer == e.layer
+                && cr.p0.x <= r.p0.x
+                && cr.p0.y <= r.p0.y
+                && cr.p1.x >= r.p1.x
+                && cr.p1.y >= r.p1.y
+        }));
+    }
+    // The first band merged into a single shape, with a single net label, in place of the first original
+    assert_eq!(
+        rect_of(&coalesced[0]),
+        rect_of(&rect(vpwr, met1, (0, 1000), (0, 80)))
+    );
+    assert_eq!(coalesced[1].net.as_deref(), Some("VGND"));
+    let vpwr_met1 = coalesced
+        .iter()
+        .filter(|e| e.net.as_deref() == vpwr && e.layer == met1)
+        .count();
+    assert_eq!(vpwr_met1, 5);
+
+    // Check the coalesced geometry rasterizes identically, on each layer
+    let original = Cell::from(Layout {
+        name: "straps".into(),
+        elems: elems.clone(),
+        ..Default::default()
+    });
+    for layer in [met1, met2] {
+        let a = rasterize(&original, layer, 5)?;
+        let b = rasterize(&cell, layer, 5)?;
+        assert!(a.count() > 0);
+        assert!(raster_equal(&a, &b)?.is_empty());
+    }
+
+    // Nothing merges in the other direction
+    let mut cell2 = cell.clone();
+    cell2.coalesce(Dir::Horiz);
+    assert_eq!(cell2, cell);
+
+    // Labeled rectangles are left unmerged, keeping their labels in place
+    let mut labeled = rect(vpwr, met1, (0, 1000), (0, 40));
+    labeled.labels.push(Label::new("tap", 0.25));
+    let elems = vec![
+        labeled.clone(),
+        rect(vpwr, met1, (0, 1000), (40, 80)),
+        rect(vpwr, met1, (0, 1000), (80, 120)),
+    ];
+    let mut cell3 = Cell::from(Layout {
+        name: "labeled".into(),
+        elems,
+        ..Default::default()
+    });
+    cell3.coalesce(Dir::Vert);
+    let coalesced = &cell3.layout.as_ref().unwrap().elems;
+    assert_eq!(coalesced.len(), 2);
+    assert_eq!(coalesced[0], labeled);
+    assert_eq!(
+        rect_of(&coalesced[1]),
+        rect_of(&rect(vpwr, met1, (0, 1000), (40, 120)))
+    );
+    Ok(())
+}
+/// Check removal of zero-area elements, by [Layout::drop_degenerate], coalescing, and GDSII export
+#[cfg(feature = "gds")]
+#[test]
+fn test_drop_degenerate() -> LayoutResult<()> {
+    use gds::{gds21::GdsElement, GdsExportOptions};
+
+    let mut lib = Library::new("degenerate_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let elem = |inner: Shape| Element {
+        net: Some("VDD".into()),
+        layer: met1,
+        purpose: LayerPurpose::Drawing,
+        inner,
+        labels: Vec::new(),
+    };
+    let rect = |x: (isize, isize), y: (isize, isize)| {
+        elem(Shape::Rect(Rect {
+            p0: Point::new(x.0, y.0),
+            p1: Point::new(x.1, y.1),
+        }))
+    };
+    let elems = vec![
+        rect((0, 100), (0, 10)),  // Valid
+        rect((50, 50), (0, 10)),  // Zero-width, e.g. a cut covering a segment
+        rect((0, 100), (20, 20)), // Zero-height
+        elem(Shape::Polygon(Polygon {
+            points: vec![Point::new(0, 0), Point::new(5, 5), Point::new(10, 10)],
+        })), // Colinear
+        elem(Shape::Path(Path {
+            points: vec![Point::new(0, 0), Point::new(100, 0)],
+            width: 0,
+        })), // Zero-width path
+    ];
+    let layout = Layout {
+        name: "degenerate".into(),
+        elems: elems.clone(),
+        ..Default::default()
+    };
+    assert!(!elems[0].inner.is_degenerate());
+    assert!(elems[1..].iter().all(|e| e.inner.is_degenerate()));
+
+    // Explicit removal
+    let mut cell = Cell::from(layout.clone());
+    assert_eq!(cell.drop_degenerate(), 4);
+    assert_eq!(cell.layout.as_ref().unwrap().elems, vec![elems[0].clone()]);
+    assert_eq!(cell.drop_degenerate(), 0);
+
+    // Coalescing removes the zero-area rectangles, but leaves other shapes
+    let mut cell = Cell::from(layout.clone());
+    cell.coalesce(Dir::Vert);
+    assert_eq!(cell.layout.as_ref().unwrap().elems.len(), 3);
+
+    // Export drops them by default, and keeps them if requested
+    lib.cells.add(layout);
+    let boundaries = |gds: &gds::gds21::GdsLibrary| -> Vec<Vec<(i32, i32)>> {
+        gds.structs[0]
+            .elems
+            .iter()
+            .filter_map(|e| match e {
+                GdsElement::GdsBoundary(b) => Some(b.xy.iter().map(|p| (p.x, p.y)).collect()),
+                _ => None,
+            })
+            .collect()
+    };
+    let gds = lib.to_gds()?;
+    let bounds = boundaries(&gds);
+    assert_eq!(bounds.len(), 1);
+    for xy in bounds.iter() {
+        let xs: std::collections::HashSet<i32> = xy.iter().map(|p| p.0).collect();
+        let ys: std::collections::HashSet<i32> = xy.iter().map(|p| p.1).collect();
+        assert!(xs.len() > 1 && ys.len() > 1);
+    }
+    assert!(!gds.structs[0]
+        .elems
+        .iter()
+        .any(|e| matches!(e, GdsElement::GdsPath(_))));
+    let opts = GdsExportOptions {
+        keep_degenerate: true,
+        ..Default::default()
+    };
+    let gds = lib.to_gds_with_options(&opts)?;
+    assert_eq!(boundaries(&gds).len(), 4);
+    Ok(())
+}
+/// Find elements by net, exactly and by wildcard, per cell and without flattening
+#[test]
+fn test_find_net() -> LayoutResult<()> {
+    let mut lib = Library::new("nets_lib", Units::Nano);
+    let met1 = {
+        let mut liblayers = lib.layers.write()?;
+        *liblayers = layers()?;
+        liblayers.keyname("met1").unwrap()
+    };
+    let elem = |net: Option<&str>| Element {
+        net: net.map(|n| n.to_string()),
+        layer: met1,
+        purpose: LayerPurpose::Drawing,
+        inner: Shape::Rect(Rect {
+            p0: Point::new(0, 0),
+            p1: Point::new(10, 10),
+        }),
+        labels: Vec::new(),
+    };
+    let child = lib.cells.add(Layout {
+        name: "child".into(),
+        elems: vec![elem(Some("clk")), elem(Some("data_0")), elem(None)],
+        ..Default::default()
+    });
+    lib.cells.add(Layout {
+        name: "parent".into(),
+        elems: vec![
+            elem(Some("data")),
+            elem(Some("clk")),
+            elem(Some("data_1")),
+            elem(Some("clkb")),
+        ],
+        insts: vec![Instance {
+            inst_name: "c".into(),
+            cell: child.clone(),
+            loc: Point::new(0, 0),
+            reflect_vert: false,
+            angle: None,
+        }],
+        ..Default::default()
+    });
+
+    // Exact names, reported in the cell defining each element
+    assert_eq!(
+        lib.find_net("clk")?,
+        vec![("child".to_string(), 0), ("parent".to_string(), 1)]
+    );
+    assert_eq!(child.read()?.elements_by_net("clk").len(), 1);
+    assert!(lib.find_net("nope")?.is_empty());
+
+    // Wildcards
+    assert_eq!(
+        lib.find_net("data_*")?,
+        vec![("child".to_string(), 1), ("parent".to_string(), 2)]
+    );
+    assert_eq!(lib.find_net("data*")?.len(), 3);
+    assert_eq!(lib.find_net("clk?")?, vec![("parent".to_string(), 3)]);
+    assert_eq!(lib.find_net("*")?.len(), 6);
+    assert!(net_matches("a*b*c", "aXbYbZc"));
+    assert!(!net_matches("a*b", "aXbY"));
+    assert!(!net_matches("?", ""));
+
+    // Cached indices are re-used until invalidated
+    let mut index = NetIndex::new();
+    assert_eq!(index.elements(&*child.read()?, "clk"), vec![0]);
+    child
+        .write()?
+        .layout
+        .as_mut()
+        .unwrap()
+        .elems
+        .insert(0, elem(Some("clk")));
+    assert_eq!(index.elements(&*child.read()?, "clk"), vec![0]);
+    index.invalidate("child");
+    assert_eq!(index.elements(&*child.read()?, "clk"), vec![0, 1]);
+    Ok(())
+}
+/// Rasterize simple hand-built cells, and compare them
+#[test]
+fn test_rasterize() -> LayoutResult<()> {
+    let mut layers = Layers::default();
+    let met1 = layers.add(Layer::new(68, "met1"));
+    let met2 = layers.add(Layer::new(69, "met2"));
+    let rect = |layer: LayerKey, p0: (Int, Int), p1: (Int, Int)| Element {
+        net: None,
+        layer,
+        purpose: LayerPurpose::Drawing,
+        inner: Shape::Rect(Rect {
+            p0: Point::new(p0.0, p0.1),
+            p1: Point::new(p1.0, p1.1),
+        }),
+        labels: Vec::new(),
+    };
+    // A 20x10 rectangle, drawn whole, and as two overlapping halves
+    let whole = Cell::from(Layout {
+        name: "whole".into(),
+        elems: vec![rect(met1, (0, 0), (20, 10)), rect(met2, (0, 0), (5, 5))],
+        ..Default::default()
+    });
+    let halves = Cell::from(Layout {
+        name: "halves".into(),
+        elems: vec![rect(met1, (0, 0), (12, 10)), rect(met1, (8, 0), (20, 10))],
+        ..Default::default()
+    });
+    let a = rasterize(&whole, met1, 2)?;
+    let b = rasterize(&halves, met1, 2)?;
+    assert_eq!(a.count(), 50);
+    assert!(a.get(0, 0) && a.get(9, 4) && !a.get(10, 0));
+    assert!(raster_equal(&a, &b)?.is_empty());
+
+    // Other layers are not painted, and report differences with their locations
+    let c = rasterize(&whole, met2, 2)?;
+    let b2 = rasterize(&halves, met2, 2)?;
+    assert_eq!(b2.count(), 0);
+    let diffs = raster_equal(&c, &b2)?;
+    assert_eq!(diffs.len(), c.count());
+    assert_eq!(
+        diffs[0],
+        DiffPixel {
+            loc: Point::new(0, 0),
+            in_a: true
+        }
+    );
+
+    // Instances are flattened, e.g. re-creating `whole` from two instances of one half
+    let half = utils::Ptr::new(Cell::from(Layout {
+        name: "half".into(),
+        elems: vec![rect(met1, (0, 0), (10, 10))],
+        ..Default::default()
+    }));
+    let inst = |name: &str, x: Int| Instance {
+        inst_name: name.into(),
+        cell: half.clone(),
+        loc: Point::new(x, 0),
+        reflect_vert: false,
+        angle: None,
+    };
+    let parent = Cell::from(Layout {
+        name: "parent".into(),
+        insts: vec![inst("left", 0), inst("right", 10)],
+        ..Default::default()
+    });
+    assert!(raster_equal(&a, &rasterize(&parent, met1, 2)?)?.is_empty());
+
+    // Cells without a layout, and non-positive grids, fail
+    assert!(rasterize(&Cell::new("empty"), met1, 2).is_err());
+    assert!(rasterize(&whole, met1, 0).is_err());
+    // As do comparisons between grids of different pixel sizes
+    assert!(raster_equal(&a, &rasterize(&whole, met1, 4)?).is_err());
+    Ok(())
+}
 /// Grab the full path of resource-file `fname`
-fn resource(rname: &str) -> String {
-    format!("{}/resources/{}", env!("CARGO_MANIFEST_DIR"), rname)
+#[cfg(feature = "gds")]
+fn resource(rname: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join(rname)
+}
+/// Compare [CellIndex] queries against brute-force scans, on pseudo-random rectangles
+#[test]
+fn test_cell_index() -> LayoutResult<()> {
+    let met1 = layers()?.keyname("met1").unwrap();
+    // Linear congruential generator, for reproducible coordinates
+    let mut seed: u64 = 21;
+    let mut rand = |max: Int| -> Int {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 33) % max as u64) as Int
+    };
+    let mut layout = Layout {
+        name: "random".into(),
+        ..Default::default()
+    };
+    for _ in 0..500 {
+        let (x, y) = (rand(10_000) - 2_000, rand(10_000) - 2_000);
+        // Mostly small shapes, along with a few long wires
+        let (w, h) = match rand(10) {
+            0 => (rand(8_000), 20),
+            _ => (rand(200), rand(200)),
+        };
+        layout.elems.push(Element {
+            net: None,
+            layer: met1,
+            purpose: LayerPurpose::Drawing,
+            inner: Shape::Rect(Rect {
+                p0: Point::new(x, y),
+                p1: Point::new(x + w, y + h),
+            }),
+            labels: Vec::new(),
+        });
+    }
+    let cell = Cell::from(layout);
+    let index = CellIndex::build(&cell);
+    assert_eq!(index.len(), 500);
+    let elems = &cell.layout.as_ref().unwrap().elems;
+
+    for _ in 0..200 {
+        // Regions and points extending beyond the indexed area
+        let p0 = Point::new(rand(14_000) - 4_000, rand(14_000) - 4_000);
+        let p1 = Point::new(p0.x + rand(1_000), p0.y + rand(1_000));
+        let region = BoundBox::from_points(&p0, &p1);
+        let brute: Vec<usize> = (0..elems.len())
+            .filter(|&i| !elems[i].inner.bbox().intersection(&region).is_empty())
+            .collect();
+        assert_eq!(index.query_region(&region).collect::<Vec<_>>(), brute);
+
+        let brute: Vec<usize> = (0..elems.len())
+            .filter(|&i| elems[i].inner.bbox().contains(&p0))
+            .collect();
+        assert_eq!(index.query_point(&p0).collect::<Vec<_>>(), brute);
+    }
+    // Cells without layouts produce empty indices
+    let empty = CellIndex::build(&Cell::new("empty"));
+    assert!(empty.is_empty());
+    assert_eq!(empty.query_point(&Point::new(0, 0)).count(), 0);
+    Ok(())
 }
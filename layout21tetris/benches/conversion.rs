@@ -0,0 +1,171 @@
+//!
+//! # Conversion Benchmarks
+//!
+//! Criterion benchmarks of the conversion hot paths:
+//! track-period creation, track cutting, full conversion to [raw::Library], and GDSII serialization,
+//! along with spatially-indexed queries of the resulting [raw::Cell]s.
+//! Libraries are generated by [layout21tetris::fixtures], which requires the `fixtures` feature,
+//! along with the default `gds` feature.
+//! Run them via:
+//!
+//! ```text
+//! cargo bench -p layout21tetris --features fixtures
+//! ```
+//!
+
+// Crates.io
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+// Local imports
+use layout21tetris::coords::{DbUnits, LayerIndex, TrackIndex};
+use layout21tetris::fixtures::{grid_library, SampleStacks};
+use layout21tetris::raw;
+use layout21tetris::stack::RelZ;
+use layout21tetris::tracks::TrackCross;
+
+/// Create a [LayerPeriod](layout21tetris::stack::LayerPeriod) for each metal layer of the sample stack
+fn layer_periods(c: &mut Criterion) {
+    let stack = SampleStacks::pdka().unwrap();
+    c.bench_function("to_layer_period", |b| {
+        b.iter(|| {
+            for idx in 0..stack.num_metals() {
+                let spec = &stack.metal(LayerIndex(idx)).unwrap().spec;
+                black_box(spec.to_layer_period(black_box(idx), 1_000_000).unwrap());
+            }
+        })
+    });
+}
+/// Cut a single long track thousands of times
+fn track_cuts(c: &mut Criterion) {
+    let ncuts: usize = 5_000;
+    let len = DbUnits(100);
+    let stack = SampleStacks::pdka().unwrap();
+    let spec = &stack.metal(LayerIndex(1)).unwrap().spec;
+    let period = spec.to_layer_period(0, len * (2 * ncuts + 2)).unwrap();
+    let track = period.signals[0].clone();
+    let src = TrackCross::from_relz(LayerIndex(1), TrackIndex(0), TrackIndex(0), RelZ::Below);
+    c.bench_function("track_cut", |b| {
+        b.iter_batched(
+            || track.clone(),
+            |mut track| {
+                for i in 0..ncuts {
+                    let start = len * (2 * i + 1);
+                    track.cut(start, start + len, &src).unwrap();
+                }
+                track
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+/// Convert fixture libraries of increasing size to [raw::Library]
+fn conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("to_raw");
+    group.sample_size(10);
+    for cells in [4, 16, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(cells), &cells, |b, &cells| {
+            // Generate a fresh library and stack per run, outside the measurement
+            b.iter_batched(
+                || {
+                    let lib = grid_library(cells, 20, 50, 1).unwrap();
+                    (lib, SampleStacks::pdka().unwrap())
+                },
+                |(lib, stack)| lib.to_raw(stack).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+/// Convert a [raw::Library] to GDSII, and serialize it
+fn gds_export(c: &mut Criterion) {
+    let lib = grid_library(16, 20, 50, 1).unwrap();
+    let rawlib = lib.to_raw(SampleStacks::pdka().unwrap()).unwrap();
+    let rawlib: &raw::Library = &rawlib.read().unwrap();
+    c.bench_function("to_gds", |b| b.iter(|| rawlib.to_gds().unwrap()));
+    let gds = rawlib.to_gds().unwrap();
+    c.bench_function("gds_write", |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            gds.write(&mut buf).unwrap();
+            buf
+        })
+    });
+}
+
+/// Create a [raw::Cell] of `n` small, scattered rectangles
+fn scattered_cell(n: usize) -> raw::Cell {
+    let side = (n as f64).sqrt().ceil() as raw::Int;
+    let elems = (0..n as raw::Int)
+        .map(|i| {
+            let (x, y) = (100 * (i % side), 100 * (i / side));
+            raw::Element {
+                net: None,
+                layer: Default::default(),
+                purpose: raw::LayerPurpose::Drawing,
+                inner: raw::Shape::Rect(raw::Rect {
+                    p0: raw::Point::new(x, y),
+                    p1: raw::Point::new(x + 60 + (i % 7) * 10, y + 60 + (i % 5) * 10),
+                }),
+                labels: Vec::new(),
+            }
+        })
+        .collect();
+    raw::Cell::from(raw::Layout {
+        name: "scattered".into(),
+        elems,
+        ..Default::default()
+    })
+}
+/// Find every pair of overlapping shapes, as for connectivity extraction,
+/// by brute force and via [raw::CellIndex]
+fn overlap_pairs(c: &mut Criterion) {
+    use raw::BoundBoxTrait;
+    let mut group = c.benchmark_group("overlap_pairs");
+    group.sample_size(10);
+    for n in [5_000, 50_000] {
+        let cell = scattered_cell(n);
+        let bboxes: Vec<_> = cell
+            .layout
+            .as_ref()
+            .unwrap()
+            .elems
+            .iter()
+            .map(|e| e.inner.bbox())
+            .collect();
+        if n <= 5_000 {
+            group.bench_with_input(BenchmarkId::new("brute", n), &bboxes, |b, bboxes| {
+                b.iter(|| {
+                    let mut pairs = 0;
+                    for (i, a) in bboxes.iter().enumerate() {
+                        for b in bboxes[i + 1..].iter() {
+                            pairs += !a.intersection(b).is_empty() as usize;
+                        }
+                    }
+                    pairs
+                })
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("indexed", n), &cell, |b, cell| {
+            b.iter(|| {
+                let index = raw::CellIndex::build(cell);
+                let mut pairs = 0;
+                for (i, bbox) in bboxes.iter().enumerate() {
+                    pairs += index.query_region(bbox).filter(|j| *j > i).count();
+                }
+                pairs
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    layer_periods,
+    track_cuts,
+    conversion,
+    gds_export,
+    overlap_pairs
+);
+criterion_main!(benches);
@@ -16,11 +16,14 @@
 use serde::{Deserialize, Serialize};
 
 // Local imports
+use crate::coords::{LayerIndex, TrackIndex};
 use crate::outline;
+use crate::raw::{LayoutError, LayoutResult};
 use crate::stack::RelZ;
+use crate::validate::ValidStack;
 
 /// Abstract-Layout
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Abstract {
     /// Cell Name
     pub name: String,
@@ -29,6 +32,7 @@ pub struct Abstract {
     /// Number of Metal Layers Used
     pub metals: usize,
     /// Ports
+    #[serde(default)]
     pub ports: Vec<Port>,
 }
 impl Abstract {
@@ -53,36 +57,70 @@ impl Abstract {
     }
 }
 /// Abstract-Layout Port
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Port {
     /// Port/ Signal Name
     pub name: String,
     /// Physical Info
     pub kind: PortKind,
 }
+impl Port {
+    /// Create a new [PortKind::Edge] port, on track `track` of layer `layer`
+    pub fn edge(name: impl Into<String>, layer: LayerIndex, track: TrackIndex, side: Side) -> Self {
+        Self {
+            name: name.into(),
+            kind: PortKind::Edge { layer, track, side },
+        }
+    }
+    /// Create a new [PortKind::ZTopEdge] port, on top-layer track `track`,
+    /// extending inward to track `into` of the layer `relz`.
+    pub fn ztop_edge(
+        name: impl Into<String>,
+        track: TrackIndex,
+        side: Side,
+        into: TrackIndex,
+        relz: RelZ,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            kind: PortKind::ZTopEdge {
+                track,
+                side,
+                into: (into, relz),
+            },
+        }
+    }
+    /// Create a new [PortKind::ZTopInner] port, landing at each of `locs`
+    pub fn ztop_inner(name: impl Into<String>, locs: Vec<TopLoc>) -> Self {
+        Self {
+            name: name.into(),
+            kind: PortKind::ZTopInner { locs },
+        }
+    }
+}
 /// Abstract-Layout Port Inner Detail
 ///
 /// All location and "geometric" information per Port is stored here,
 /// among a few enumerated variants.
 ///
 /// Ports may either connect on x/y edges, or on the top (in the z-axis) layer.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum PortKind {
     /// Ports which connect on x/y outline edges
     Edge {
-        layer: usize,
-        track: usize,
+        layer: LayerIndex,
+        track: TrackIndex,
         side: Side,
     },
     /// Ports accessible from bot top *and* top-layer edges
     /// Note their `layer` field is implicitly defined as the cell's `metals`.
     ZTopEdge {
         /// Track Index
-        track: usize,
+        track: TrackIndex,
         /// Side
         side: Side,
         /// Location into which the pin extends inward
-        into: (usize, RelZ),
+        into: (TrackIndex, RelZ),
     },
     /// Ports which are internal to the cell outline,
     /// but connect from above in the z-stack.
@@ -94,23 +132,258 @@ pub enum PortKind {
     },
 }
 /// A location (track intersection) on our top z-axis layer
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TopLoc {
     /// Track Index
-    track: usize,
+    pub track: TrackIndex,
     /// Intersecting Track Index
-    at: usize,
+    pub at: TrackIndex,
     /// Whether `at` refers to the track-indices above or below
-    relz: RelZ,
+    pub relz: RelZ,
+}
+impl TopLoc {
+    /// Create a new [TopLoc], at the intersection of top-layer track `track`
+    /// and track `at` of the layer above or below, per `relz`
+    pub fn new(track: TrackIndex, at: TrackIndex, relz: RelZ) -> Self {
+        Self { track, at, relz }
+    }
 }
 /// # Port Side Enumeration
 ///
 /// Note there are only two such sides: the "origin-side" [BottomOrLeft] and the "width-side" [TopOrRight].
 /// Each [Layer]'s orientation ([Dir]) dictates between bottom/left and top/right.
 /// Also note the requirements on [Outline] shapes ensure each track has a unique left/right or top/bottom pair of edges.
-/// 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Side {
     BottomOrLeft,
     TopOrRight,
 }
+impl Side {
+    /// Get the opposite side
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::BottomOrLeft => Self::TopOrRight,
+            Self::TopOrRight => Self::BottomOrLeft,
+        }
+    }
+}
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::BottomOrLeft => write!(f, "BottomOrLeft"),
+            Self::TopOrRight => write!(f, "TopOrRight"),
+        }
+    }
+}
+impl std::str::FromStr for Side {
+    type Err = LayoutError;
+    /// Parse a [Side], case-insensitively
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bottomorleft" => Ok(Self::BottomOrLeft),
+            "toporright" => Ok(Self::TopOrRight),
+            _ => LayoutError::fail(format!("Invalid Side `{}`", s)),
+        }
+    }
+}
+/// # Abstract Differences
+///
+/// Differences between two [Abstract]s, as found by [compare].
+/// Empty if the second is a drop-in replacement for the first.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbstractDiff {
+    /// Names of the compared abstracts
+    pub names: (String, String),
+    /// Outlines, if they differ
+    pub outline: Option<(outline::Outline, outline::Outline)>,
+    /// Numbers of metal layers, if they differ
+    pub metals: Option<(usize, usize)>,
+    /// Ports of the first abstract missing from the second, in order
+    pub missing: Vec<String>,
+    /// Ports of the second abstract absent from the first, in order
+    pub extra: Vec<String>,
+    /// Ports whose kinds differ, including their layers and sides
+    pub kinds: Vec<PortKindChange>,
+    /// Ports moved by more than the comparison's tolerance
+    pub moved: Vec<PortMove>,
+}
+impl AbstractDiff {
+    /// Boolean indication of whether there are no differences
+    pub fn is_empty(&self) -> bool {
+        self.outline.is_none()
+            && self.metals.is_none()
+            && self.missing.is_empty()
+            && self.extra.is_empty()
+            && self.kinds.is_empty()
+            && self.moved.is_empty()
+    }
+}
+impl std::fmt::Display for AbstractDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (a, b) = &self.names;
+        if self.is_empty() {
+            return write!(f, "Abstract {} is a drop-in replacement for {}", b, a);
+        }
+        write!(f, "Abstract {} differs from {}:", b, a)?;
+        if let Some((oa, ob)) = &self.outline {
+            write!(f, "\n  Outline: {:?} vs {:?}", oa, ob)?;
+        }
+        if let Some((ma, mb)) = &self.metals {
+            write!(f, "\n  Metal layers: {} vs {}", ma, mb)?;
+        }
+        for port in self.missing.iter() {
+            write!(f, "\n  Missing port: {}", port)?;
+        }
+        for port in self.extra.iter() {
+            write!(f, "\n  Extra port: {}", port)?;
+        }
+        for change in self.kinds.iter() {
+            write!(
+                f,
+                "\n  Port {}: {} vs {}",
+                change.port, change.from, change.to
+            )?;
+        }
+        for moved in self.moved.iter() {
+            write!(
+                f,
+                "\n  Port {}: moved by {} tracks",
+                moved.port, moved.tracks
+            )?;
+        }
+        Ok(())
+    }
+}
+/// # Port-Kind Change
+///
+/// A port whose [PortKind], layer, or side differs between two [Abstract]s, each described in text.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortKindChange {
+    /// Port Name
+    pub port: String,
+    /// Description in the first abstract
+    pub from: String,
+    /// Description in the second abstract
+    pub to: String,
+}
+/// # Port Move
+///
+/// A port of the same kind in two [Abstract]s, whose track indices differ.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortMove {
+    /// Port Name
+    pub port: String,
+    /// Largest difference between any of its track indices
+    pub tracks: usize,
+}
+/// Compare [Abstract]s `a` and `b`, e.g. to check `b` is a drop-in replacement for `a`.
+///
+/// Reports differing outlines and numbers of metal layers, ports missing from or added to `b`,
+/// ports whose kind, layer, or side differ, and those whose track indices differ by more than `tol_tracks`.
+/// Layers are named per `stack`, which must include each layer referred to.
+pub fn compare(
+    a: &Abstract,
+    b: &Abstract,
+    stack: &ValidStack,
+    tol_tracks: usize,
+) -> LayoutResult<AbstractDiff> {
+    let mut diff = AbstractDiff {
+        names: (a.name.clone(), b.name.clone()),
+        ..Default::default()
+    };
+    if a.outline != b.outline {
+        diff.outline = Some((a.outline.clone(), b.outline.clone()));
+    }
+    if a.metals != b.metals {
+        diff.metals = Some((a.metals, b.metals));
+    }
+    for pa in a.ports.iter() {
+        let pb = match b.port(&pa.name) {
+            Some(pb) => pb,
+            None => {
+                diff.missing.push(pa.name.clone());
+                continue;
+            }
+        };
+        let (from, to) = (describe(pa, a, stack)?, describe(pb, b, stack)?);
+        if from != to {
+            diff.kinds.push(PortKindChange {
+                port: pa.name.clone(),
+                from,
+                to,
+            });
+            continue;
+        }
+        let tracks = track_delta(&pa.kind, &pb.kind);
+        if tracks > tol_tracks {
+            diff.moved.push(PortMove {
+                port: pa.name.clone(),
+                tracks,
+            });
+        }
+    }
+    for pb in b.ports.iter() {
+        if a.port(&pb.name).is_none() {
+            diff.extra.push(pb.name.clone());
+        }
+    }
+    Ok(diff)
+}
+/// Describe the kind, layer, and side of [Port] `port` of `abs`, excluding its track indices
+fn describe(port: &Port, abs: &Abstract, stack: &ValidStack) -> LayoutResult<String> {
+    let top = || -> LayoutResult<String> {
+        match abs.metals.checked_sub(1) {
+            Some(top) => Ok(stack.metal(LayerIndex(top))?.spec.name.clone()),
+            None => LayoutError::fail(format!(
+                "Abstract {} has top-layer Port {} but no metal layers",
+                abs.name, port.name
+            )),
+        }
+    };
+    let desc = match &port.kind {
+        PortKind::Edge { layer, side, .. } => {
+            format!("Edge on {} at {}", stack.metal(*layer)?.spec.name, side)
+        }
+        PortKind::ZTopEdge { side, into, .. } => format!(
+            "ZTopEdge on {} at {}, extending into the layer {:?}",
+            top()?,
+            side,
+            into.1
+        ),
+        PortKind::ZTopInner { locs } => {
+            let relz: Vec<RelZ> = locs.iter().map(|l| l.relz).collect();
+            format!("ZTopInner on {}, crossing layers {:?}", top()?, relz)
+        }
+    };
+    Ok(desc)
+}
+/// Get the largest difference between the track indices of same-kind [PortKind]s `a` and `b`
+fn track_delta(a: &PortKind, b: &PortKind) -> usize {
+    let delta = |ta: &TrackIndex, tb: &TrackIndex| ta.index().abs_diff(tb.index());
+    match (a, b) {
+        (PortKind::Edge { track: ta, .. }, PortKind::Edge { track: tb, .. }) => delta(ta, tb),
+        (
+            PortKind::ZTopEdge {
+                track: ta,
+                into: ia,
+                ..
+            },
+            PortKind::ZTopEdge {
+                track: tb,
+                into: ib,
+                ..
+            },
+        ) => delta(ta, tb).max(delta(&ia.0, &ib.0)),
+        (PortKind::ZTopInner { locs: la }, PortKind::ZTopInner { locs: lb }) => la
+            .iter()
+            .zip(lb.iter())
+            .map(|(la, lb)| delta(&la.track, &lb.track).max(delta(&la.at, &lb.at)))
+            .max()
+            .unwrap_or(0),
+        _ => 0, // Differing kinds are reported by [describe]
+    }
+}
@@ -32,7 +32,11 @@ impl Array {
     /// Size of the Array's rectangular `boundbox`, i.e. the zero-origin `boundbox` of its `cell`.
     pub fn boundbox_size(&self) -> LayoutResult<Xy<PrimPitches>> {
         let _unit = self.unit.boundbox_size()?;
-        todo!() // FIXME: do some math on separation, size
+        // FIXME: do some math on separation, size
+        LayoutError::fail(format!(
+            "Bounding box of Array {} is not (yet) supported",
+            self.name
+        ))
     }
 }
 /// Enumeration of types that can be Arrayed
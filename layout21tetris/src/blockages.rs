@@ -0,0 +1,152 @@
+//!
+//! # Raw-Geometry Placement Blockages
+//!
+//! Generates obstruction shapes covering the metal used by converted [raw::Cell]s,
+//! for handing them to external place-and-route tools as blocks.
+//!
+
+// Local imports
+use crate::coords::LayerIndex;
+use crate::raw::{self, BoundBox, BoundBoxTrait, LayerPurpose, LayoutError, LayoutResult};
+use crate::validate::ValidStack;
+
+/// # Blockage Coverage
+///
+/// How tightly generated blockages cover each layer's shapes.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockageCoverage {
+    /// A single rectangle per layer, bounding all of its shapes
+    #[default]
+    BoundingBox,
+    /// A rectangle per cluster of overlapping or abutting shapes, bounding the cluster
+    Merged,
+}
+/// # Raw Blockages
+///
+/// Generation of per-layer obstruction shapes for a [raw::Cell].
+///
+pub trait RawBlockages {
+    /// Generate [BlockageCoverage::BoundingBox] blockages for each metal layer up to and including `up_to_layer`.
+    /// See [RawBlockages::generate_blockages_with].
+    fn generate_blockages(
+        &self,
+        stack: &ValidStack,
+        up_to_layer: usize,
+    ) -> LayoutResult<Vec<raw::Element>> {
+        self.generate_blockages_with(stack, up_to_layer, BlockageCoverage::default())
+    }
+    /// Generate blockages for each metal layer up to and including `up_to_layer`,
+    /// covering our shapes on that layer, including those of our instances, per `coverage`.
+    ///
+    /// Blockages are drawn on each layer's [LayerPurpose::Obstruction], which must be defined on its raw layer,
+    /// and are clipped to our boundary if we have one. Layers without shapes get no blockages.
+    /// The results can be appended to our own [raw::Layout], or collected into a separate obstruction view.
+    fn generate_blockages_with(
+        &self,
+        stack: &ValidStack,
+        up_to_layer: usize,
+        coverage: BlockageCoverage,
+    ) -> LayoutResult<Vec<raw::Element>>;
+}
+impl RawBlockages for raw::Cell {
+    fn generate_blockages_with(
+        &self,
+        stack: &ValidStack,
+        up_to_layer: usize,
+        coverage: BlockageCoverage,
+    ) -> LayoutResult<Vec<raw::Element>> {
+        let layout = match self.layout {
+            Some(ref layout) => layout,
+            None => return Ok(Vec::new()),
+        };
+        let flat = layout.flatten()?;
+        // Clip to the union of any boundary shapes
+        let boundary = flat
+            .iter()
+            .filter(|e| e.purpose == LayerPurpose::Outline)
+            .fold(BoundBox::empty(), |bbox, e| e.inner.union(&bbox));
+
+        let rawlayers = match stack.rawlayers {
+            Some(ref rawlayers) => rawlayers.read()?,
+            None => return LayoutError::fail("Cannot generate blockages without raw layers"),
+        };
+        let mut blockages = Vec::new();
+        for index in 0..=up_to_layer {
+            let metal = stack.metal(LayerIndex(index))?;
+            let key = match metal.spec.raw {
+                Some(key) => key,
+                None => {
+                    return LayoutError::fail(format!(
+                        "Cannot generate blockages on layer {}, which has no raw layer",
+                        metal.spec.name
+                    ))
+                }
+            };
+            let defined = rawlayers
+                .get(key)
+                .and_then(|layer| layer.num(&LayerPurpose::Obstruction));
+            if defined.is_none() {
+                return LayoutError::fail(format!(
+                    "Cannot generate blockages on layer {}, which defines no obstruction purpose",
+                    metal.spec.name
+                ));
+            }
+            // Collect the bounding boxes of the layer's shapes, ignoring labels, outlines, and prior obstructions
+            let shapes = flat.iter().filter(|e| {
+                e.layer == key
+                    && !matches!(
+                        e.purpose,
+                        LayerPurpose::Label | LayerPurpose::Outline | LayerPurpose::Obstruction
+                    )
+            });
+            let mut boxes: Vec<BoundBox> = shapes
+                .map(|e| e.inner.bbox())
+                .filter(|b| !b.is_empty())
+                .collect();
+            boxes = match coverage {
+                BlockageCoverage::BoundingBox => match boxes.split_first() {
+                    Some((first, rest)) => vec![rest.iter().fold(*first, |u, b| u.union(b))],
+                    None => Vec::new(),
+                },
+                BlockageCoverage::Merged => merge_boxes(boxes),
+            };
+            for bbox in boxes {
+                let bbox = if boundary.is_empty() {
+                    bbox
+                } else {
+                    bbox.intersection(&boundary)
+                };
+                if bbox.is_empty() {
+                    continue;
+                }
+                blockages.push(raw::Element {
+                    net: None,
+                    layer: key,
+                    purpose: LayerPurpose::Obstruction,
+                    inner: raw::Shape::Rect(raw::Rect {
+                        p0: bbox.p0,
+                        p1: bbox.p1,
+                    }),
+                    labels: Vec::new(),
+                });
+            }
+        }
+        Ok(blockages)
+    }
+}
+/// Merge overlapping or abutting `boxes` into their unions, until no two touch
+fn merge_boxes(mut boxes: Vec<BoundBox>) -> Vec<BoundBox> {
+    let touch = |a: &BoundBox, b: &BoundBox| {
+        a.p0.x <= b.p1.x && b.p0.x <= a.p1.x && a.p0.y <= b.p1.y && b.p0.y <= a.p1.y
+    };
+    let mut merged: Vec<BoundBox> = Vec::with_capacity(boxes.len());
+    while let Some(mut bbox) = boxes.pop() {
+        // Absorb any merged box which touches this one, and re-check the grown box against the rest
+        while let Some(idx) = merged.iter().position(|m| touch(m, &bbox)) {
+            bbox = bbox.union(&merged.swap_remove(idx));
+        }
+        merged.push(bbox);
+    }
+    merged
+}
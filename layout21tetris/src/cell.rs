@@ -6,19 +6,31 @@
 //! including [Abstract]s, [Layout], interface definitions, and/or "raw" layouts.
 //!
 
+// Std-lib
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
 // Crates.io
 use derive_more;
 
 // Local imports
-use crate::coords::{PrimPitches, Xy};
-use crate::layout::Layout;
+use crate::bbox::HasBoundBox;
+use crate::coords::{LayerIndex, PrimPitches, TrackIndex, Xy};
+use crate::escape::EscapeReport;
+use crate::instance::Instance;
+use crate::layout::{Abutment, Layout};
+use crate::library::{CellRef, Library};
+use crate::net::{NetName, NetRef};
 use crate::raw::{LayoutError, LayoutResult};
-use crate::utils::Ptr;
+use crate::stack::{Assign, Jog, RelZ, ViaLoc, ViaPlacement};
+use crate::tracks::{TrackCross, TrackRef};
+use crate::utils::{Ptr, PtrList};
+use crate::validate::ValidStack;
 use crate::{abs, interface, outline, raw};
 
 /// "Pointer" to a raw (lib, cell) combination.
 /// Wraps with basic [Outline] and `metals` information to enable bounded placement.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawLayoutPtr {
     /// Outline shape, counted in x and y pitches of `stack`
     pub outline: outline::Outline,
@@ -40,7 +52,7 @@ pub enum CellView {
 }
 
 /// Collection of the Views describing a Cell
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Cell {
     /// Cell Name
     pub name: String,
@@ -55,6 +67,9 @@ pub struct Cell {
     /// so that cells are either defined as `raw` or `tetris` implementations,
     /// but not both
     pub raw: Option<RawLayoutPtr>,
+    /// Cell-level properties, e.g. generator names, versions and parameter hashes.
+    /// Copied to the [raw::Cell](crate::raw::Cell) on conversion.
+    pub props: BTreeMap<String, String>,
 }
 impl Cell {
     /// Create a new and initially empty [Cell]
@@ -82,6 +97,10 @@ impl Cell {
             }
         }
     }
+    /// Create a [CellBuilder], for constructing a [Cell] with a [Layout] implementation.
+    pub fn builder(name: impl Into<String>) -> CellBuilder {
+        CellBuilder::new(name)
+    }
     /// Create from a list of [CellView]s and a name.
     pub fn from_views(name: impl Into<String>, views: Vec<CellView>) -> Self {
         let mut myself = Self::default();
@@ -91,11 +110,293 @@ impl Cell {
         }
         myself
     }
+    /// Assign bits `bits` of bus `base`, one per track, starting at [TrackCross] `first`.
+    /// Each successive bit is assigned `stride` signal tracks beyond the last,
+    /// at the same crossing track. Net names are rendered per the `stack`'s [BusStyle](crate::net::BusStyle).
+    /// Fails if the [Cell] has no [Layout], if `stride` is zero, or if its layer runs out of signal tracks.
+    pub fn assign_bus(
+        &mut self,
+        stack: &ValidStack,
+        base: &str,
+        bits: Range<usize>,
+        first: TrackCross,
+        stride: usize,
+    ) -> LayoutResult<()> {
+        let layout = match self.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot assign bus {} in Cell {} without Layout",
+                    base, self.name
+                ))
+            }
+        };
+        if stride == 0 {
+            return LayoutError::fail(format!(
+                "Invalid zero stride for bus {} in Cell {}",
+                base, self.name
+            ));
+        }
+        let layer = first.track.layer;
+        let ntracks = stack.signal_tracks(layer, &layout.outline)?;
+        let mut assns = Vec::with_capacity(bits.len());
+        for (num, bit) in bits.enumerate() {
+            let net = NetRef::bit(base, bit).render(stack.nets.bus_style);
+            NetName::validate(&net, &stack.nets)?;
+            let mut at = first;
+            at.track.track += num * stride;
+            if at.track.track.index() >= ntracks {
+                return LayoutError::fail(format!(
+                    "Cannot assign {} to track {} in Cell {}: layer {} has {} signal tracks",
+                    net, at.track.track, self.name, layer, ntracks
+                ));
+            }
+            assns.push(Assign::new(net, at));
+        }
+        layout.assignments.extend(assns);
+        Ok(())
+    }
+    /// Assign net `net` to signal tracks `tracks` on metal-layer `layer`, as in [Layout::strap].
+    /// Fails if the [Cell] has no [Layout], if `layer` is above its top layer, or if either range is empty.
+    /// The net name is checked against the [Stack](crate::stack::Stack)'s [NetNamePolicy](crate::net::NetNamePolicy) on validation.
+    pub fn strap(
+        &mut self,
+        net: impl Into<String>,
+        layer: usize,
+        tracks: Range<usize>,
+        extent: Range<usize>,
+    ) -> LayoutResult<()> {
+        let net = net.into();
+        let layout = match self.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot add strap {} to Cell {} without Layout",
+                    net, self.name
+                ))
+            }
+        };
+        if layer >= layout.metals {
+            return LayoutError::fail(format!(
+                "Strap {} in Cell {} is above its top layer {}",
+                net,
+                self.name,
+                layout.metals - 1
+            ));
+        }
+        if tracks.is_empty() || extent.is_empty() {
+            return LayoutError::fail(format!(
+                "Invalid empty strap {} in Cell {}: tracks {:?}, extent {:?}",
+                net, self.name, tracks, extent
+            ));
+        }
+        layout.strap(net, layer, tracks, extent);
+        Ok(())
+    }
+    /// Connect adjacent signal tracks `track_a` and `track_b` on metal-layer `layer` with a short [Jog],
+    /// located at signal track `at` of the adjacent layer `relz`, as in [Layout::jog].
+    /// Fails if the [Cell] has no [Layout], if either layer is above its top layer,
+    /// or if the tracks are not adjacent.
+    /// The net name is checked on validation, and track bounds, conflicts, and widths on conversion.
+    pub fn jog(
+        &mut self,
+        net: impl Into<String>,
+        layer: usize,
+        track_a: usize,
+        track_b: usize,
+        at: TrackIndex,
+        relz: RelZ,
+    ) -> LayoutResult<()> {
+        let net = net.into();
+        let layout = match self.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot add jog {} to Cell {} without Layout",
+                    net, self.name
+                ))
+            }
+        };
+        let above = layer + 1 >= layout.metals && relz == RelZ::Above;
+        if layer >= layout.metals || above || (layer == 0 && relz == RelZ::Below) {
+            return LayoutError::fail(format!(
+                "Jog {} in Cell {} is outside its metal layers 0 to {}",
+                net,
+                self.name,
+                layout.metals as isize - 1
+            ));
+        }
+        if track_a + 1 != track_b && track_b + 1 != track_a {
+            return LayoutError::fail(format!(
+                "Jog {} in Cell {} connects non-adjacent tracks {} and {}",
+                net, self.name, track_a, track_b
+            ));
+        }
+        layout.jog(net, layer, track_a, track_b, at, relz);
+        Ok(())
+    }
+    /// Stamp `count` copies of [Instance] `base`, spaced by `step` from its (absolute) location.
+    /// Each is named per `name_fmt`, with its index replacing `{}`, e.g. `bit_{}` produces `bit_0`, `bit_1`, etc.
+    /// Indices listed in `overrides` instead instantiate their paired cell, as resolved in `lib`.
+    /// Returns the generated instance names, in index order.
+    ///
+    /// Fails if the [Cell] has no [Layout], if `base` is not absolutely placed, if `name_fmt` lacks a `{}`,
+    /// if any override index is out of range or fails to resolve, if any name collides with an existing instance,
+    /// or if any instance lies outside the [Layout]'s outline. Nothing is added on failure.
+    pub fn stamp(
+        &mut self,
+        lib: &Library,
+        base: Instance,
+        count: usize,
+        step: Xy<PrimPitches>,
+        name_fmt: &str,
+        overrides: &[(usize, CellRef)],
+    ) -> LayoutResult<Vec<String>> {
+        let layout = match self.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot stamp {} in Cell {} without Layout",
+                    name_fmt, self.name
+                ))
+            }
+        };
+        if !name_fmt.contains("{}") {
+            return LayoutError::fail(format!(
+                "Invalid instance-name format {} in Cell {}: requires a `{{}}`",
+                name_fmt, self.name
+            ));
+        }
+        let origin = *base.loc.abs()?;
+        let mut cells = vec![base.cell.clone(); count];
+        for (idx, cref) in overrides.iter() {
+            match cells.get_mut(*idx) {
+                Some(cell) => *cell = lib.resolve_ref(cref)?,
+                None => {
+                    return LayoutError::fail(format!(
+                        "Invalid override index {} for {} instances of {} in Cell {}",
+                        idx, count, name_fmt, self.name
+                    ))
+                }
+            }
+        }
+        let mut names: HashSet<String> = HashSet::new();
+        for inst in layout.instances.iter() {
+            names.insert(inst.read()?.inst_name.clone());
+        }
+        let mut insts = Vec::with_capacity(count);
+        for (idx, cell) in cells.into_iter().enumerate() {
+            let n = idx as isize;
+            let inst = Instance {
+                inst_name: name_fmt.replace("{}", &idx.to_string()),
+                cell,
+                loc: Xy::new(
+                    PrimPitches::x(origin.x.num + n * step.x.num),
+                    PrimPitches::y(origin.y.num + n * step.y.num),
+                )
+                .into(),
+                ..base.clone()
+            };
+            if !names.insert(inst.inst_name.clone()) {
+                return LayoutError::fail(format!(
+                    "Duplicate instance name {} in Cell {}",
+                    inst.inst_name, self.name
+                ));
+            }
+            let bbox = inst.boundbox()?;
+            if !layout.outline.contains(&bbox.p0) || !layout.outline.contains(&bbox.p1) {
+                return LayoutError::fail(format!(
+                    "{} lies outside the outline of Cell {}",
+                    inst, self.name
+                ));
+            }
+            insts.push(inst);
+        }
+        let names = insts.iter().map(|i| i.inst_name.clone()).collect();
+        for inst in insts {
+            layout.instances.add(inst);
+        }
+        Ok(names)
+    }
+    /// Replace the outline of our [Layout] with `new`.
+    /// Returns an [OutlineDelta] listing the assignments and cuts which fall outside it,
+    /// i.e. on signal tracks beyond those available per `stack`.
+    /// These are reported, but not removed. Any [abs::Abstract] view is left unchanged.
+    /// Fails if the [Cell] has no [Layout].
+    pub fn resize_outline(
+        &mut self,
+        stack: &ValidStack,
+        new: outline::Outline,
+    ) -> LayoutResult<OutlineDelta> {
+        let layout = match self.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot resize outline of Cell {} without Layout",
+                    self.name
+                ))
+            }
+        };
+        let mut ntracks = Vec::with_capacity(layout.metals);
+        for idx in 0..layout.metals {
+            ntracks.push(stack.signal_tracks(LayerIndex(idx), &new)?);
+        }
+        let outside = |cross: &TrackCross| {
+            [cross.track, cross.cross].iter().any(|t| {
+                ntracks
+                    .get(t.layer.index())
+                    .is_none_or(|n| t.track.index() >= *n)
+            })
+        };
+        let assignments = layout
+            .assignments
+            .iter()
+            .filter(|a| outside(&a.at))
+            .cloned()
+            .collect();
+        let cuts = layout.cuts.iter().filter(|c| outside(c)).copied().collect();
+        let old = std::mem::replace(&mut layout.outline, new.clone());
+        Ok(OutlineDelta {
+            old,
+            new,
+            assignments,
+            cuts,
+        })
+    }
+    /// Find all pairs of abutting [Instance]s in our [Layout], as in [Layout::abutments].
+    /// Cells without a [Layout] have none.
+    pub fn abutments(&self) -> LayoutResult<Vec<Abutment>> {
+        match self.layout {
+            Some(ref layout) => layout.abutments(),
+            None => Ok(Vec::new()),
+        }
+    }
+    /// Analyze whether the ports of [Instance] `inst_name` can escape to our outline, as in [Layout::escape_analysis].
+    pub fn escape_analysis(
+        &self,
+        inst_name: &str,
+        stack: &ValidStack,
+    ) -> LayoutResult<EscapeReport> {
+        match self.layout {
+            Some(ref layout) => layout.escape_analysis(inst_name, stack),
+            None => LayoutError::fail(format!(
+                "Cannot analyze escapes of Instance {} in Cell {} without Layout",
+                inst_name, self.name
+            )),
+        }
+    }
+    /// Describe our routes in human-readable text, as in [Layout::describe_routes].
+    /// Cells without a [Layout] have none, and produce an empty description.
+    pub fn describe_routes(&self, stack: &ValidStack) -> LayoutResult<String> {
+        match self.layout {
+            Some(ref layout) => layout.describe_routes(stack),
+            None => Ok(String::new()),
+        }
+    }
     /// Return whichever view highest-prioritorily dictates the outline
     pub fn outline(&self) -> LayoutResult<&outline::Outline> {
-        // We take the "most abstract" view for the outline
-        // (although if there are more than one, they better be the same...
-        // FIXME: this should be a validation step.)
+        // We take the "most abstract" view for the outline.
+        // Validation checks that abstract and layout outlines are congruent.
         // Overall this method probably should move to a "validated" cell in which each view is assured consistent.
         if let Some(ref x) = self.abs {
             Ok(&x.outline)
@@ -115,6 +416,18 @@ impl Cell {
         let outline = self.outline()?;
         Ok(Xy::new(outline.xmax(), outline.ymax()))
     }
+    /// Boolean indication of whether instances of `other` can replace ours, occupying the same footprint,
+    /// either as-is or horizontally reflected.
+    pub fn footprint_compatible(&self, other: &Cell) -> LayoutResult<bool> {
+        let (ours, theirs) = (self.outline()?, other.outline()?);
+        if ours.congruent(theirs) {
+            return Ok(true);
+        }
+        Ok(match theirs.mirrored() {
+            Ok(mirror) => ours.congruent(&mirror),
+            Err(_) => false,
+        })
+    }
     /// Return whichever view highest-prioritorily dictates the top-layer
     pub fn metals(&self) -> LayoutResult<usize> {
         // FIXME: same commentary as `outline` above
@@ -142,6 +455,224 @@ impl Cell {
         }
     }
 }
+/// # Outline Delta
+///
+/// Results of [Cell::resize_outline]: the old and new outlines,
+/// and the assignments and cuts which fall outside the new one.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineDelta {
+    /// Prior Outline
+    pub old: outline::Outline,
+    /// New Outline
+    pub new: outline::Outline,
+    /// Assignments outside the new outline
+    pub assignments: Vec<Assign>,
+    /// Cuts outside the new outline
+    pub cuts: Vec<TrackCross>,
+}
+impl OutlineDelta {
+    /// Boolean indication of whether all assignments and cuts remain within the new outline
+    pub fn is_clean(&self) -> bool {
+        self.assignments.is_empty() && self.cuts.is_empty()
+    }
+}
+/// # Cell Builder
+///
+/// Incrementally constructs a [Cell] with a [Layout] implementation.
+/// Created by [Cell::builder]. Example:
+///
+/// ```text
+/// let cell = Cell::builder("inv")
+///     .outline(Outline::rect(10, 1)?)
+///     .top_layer(LayerIndex(1))
+///     .instance(inst)
+///     .assign("clk", LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Below)
+///     .cut(LayerIndex(0), TrackIndex(1), TrackIndex(1), RelZ::Above)
+///     .build()?;
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct CellBuilder {
+    name: String,
+    outline: Option<outline::Outline>,
+    metals: Option<usize>,
+    instances: Vec<Instance>,
+    assignments: Vec<Assign>,
+    cuts: Vec<TrackCross>,
+    jogs: Vec<Jog>,
+    vias: Vec<ViaPlacement>,
+    suppress_rails: Vec<usize>,
+    rail_rings: bool,
+    props: BTreeMap<String, String>,
+}
+impl CellBuilder {
+    /// Create a new [CellBuilder] for a cell named `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+    /// Set the [Outline](outline::Outline)
+    pub fn outline(mut self, outline: outline::Outline) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+    /// Set the top metal layer (index). The [Layout] uses metal layers `0..=top`.
+    pub fn top_layer(mut self, top: LayerIndex) -> Self {
+        self.metals = Some(top.index() + 1);
+        self
+    }
+    /// Add an [Instance]
+    pub fn instance(mut self, inst: Instance) -> Self {
+        self.instances.push(inst);
+        self
+    }
+    /// Assign net `net` at the given track-coordinates, as in [Layout::assign]
+    pub fn assign(
+        mut self,
+        net: impl Into<String>,
+        layer: LayerIndex,
+        track: TrackIndex,
+        at: TrackIndex,
+        relz: RelZ,
+    ) -> Self {
+        let at = TrackCross::from_relz(layer, track, at, relz);
+        self.assignments.push(Assign::new(net, at));
+        self
+    }
+    /// Add an [Assign], e.g. one with a non-default [ViaPolicy](crate::stack::ViaPolicy)
+    pub fn assignment(mut self, assn: Assign) -> Self {
+        self.assignments.push(assn);
+        self
+    }
+    /// Add [ViaPlacement] `via`, as in [Layout::via]
+    pub fn via(mut self, via: ViaPlacement) -> Self {
+        self.vias.push(via);
+        self
+    }
+    /// Suppress drawing rails on metal layer `layer`. See [Layout::suppress_rails].
+    pub fn suppress_rails(mut self, layer: usize) -> Self {
+        self.suppress_rails.push(layer);
+        self
+    }
+    /// Close the boundary rails into a ring around the cell. See [Layout::rail_rings].
+    pub fn rail_rings(mut self, rings: bool) -> Self {
+        self.rail_rings = rings;
+        self
+    }
+    /// Add a cut at the given track-coordinates, as in [Layout::cut]
+    pub fn cut(mut self, layer: LayerIndex, track: TrackIndex, at: TrackIndex, relz: RelZ) -> Self {
+        self.cuts
+            .push(TrackCross::from_relz(layer, track, at, relz));
+        self
+    }
+    /// Add a [Jog] between adjacent tracks, as in [Layout::jog]
+    pub fn jog(
+        mut self,
+        net: impl Into<String>,
+        layer: usize,
+        track_a: usize,
+        track_b: usize,
+        at: TrackIndex,
+        relz: RelZ,
+    ) -> Self {
+        let at = TrackCross::from_relz(LayerIndex(layer), TrackIndex(track_a), at, relz).cross;
+        self.jogs.push(Jog {
+            net: net.into(),
+            layer,
+            track_a,
+            track_b,
+            at,
+        });
+        self
+    }
+    /// Set cell-property `key` to `value`. See [Cell::props].
+    pub fn prop(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.props.insert(key.into(), value.into());
+        self
+    }
+    /// Create the [Cell].
+    /// Fails if the outline or top layer are unset, if any assignment, cut, jog, or rail suppression
+    /// references a layer above the top layer, or if instance names are not unique.
+    /// Net names are checked against the [Stack](crate::stack::Stack)'s [NetNamePolicy](crate::net::NetNamePolicy) on validation.
+    pub fn build(self) -> LayoutResult<Cell> {
+        let outline = match self.outline {
+            Some(outline) => outline,
+            None => return LayoutError::fail(format!("Cell {} has no outline", self.name)),
+        };
+        let metals = match self.metals {
+            Some(metals) => metals,
+            None => return LayoutError::fail(format!("Cell {} has no top layer", self.name)),
+        };
+        let check_cross = |cross: &TrackCross, desc: &str| -> LayoutResult<()> {
+            if cross.track.layer.index() >= metals || cross.cross.layer.index() >= metals {
+                return LayoutError::fail(format!(
+                    "{} {:?} in Cell {} is above its top layer {}",
+                    desc,
+                    cross,
+                    self.name,
+                    metals - 1
+                ));
+            }
+            Ok(())
+        };
+        for assn in self.assignments.iter() {
+            check_cross(&assn.at, "Assignment")?;
+        }
+        for cut in self.cuts.iter() {
+            check_cross(cut, "Cut")?;
+        }
+        for via in self.vias.iter() {
+            if let ViaLoc::Cross(ref cross) = via.at {
+                check_cross(cross, "Via")?;
+            }
+        }
+        for jog in self.jogs.iter() {
+            let cross = TrackCross::new(
+                TrackRef::new(LayerIndex(jog.layer), TrackIndex(jog.track_a)),
+                jog.at,
+            );
+            check_cross(&cross, "Jog")?;
+        }
+        if let Some(layer) = self.suppress_rails.iter().find(|l| **l >= metals) {
+            return LayoutError::fail(format!(
+                "Rails suppressed on layer {} in Cell {}, above its top layer {}",
+                layer,
+                self.name,
+                metals - 1
+            ));
+        }
+        let mut names = HashSet::new();
+        for inst in self.instances.iter() {
+            if !names.insert(inst.inst_name.as_str()) {
+                return LayoutError::fail(format!(
+                    "Duplicate instance name {} in Cell {}",
+                    inst.inst_name, self.name
+                ));
+            }
+        }
+        let layout = Layout {
+            name: self.name.clone(),
+            metals,
+            outline,
+            instances: PtrList::from_owned(self.instances),
+            assignments: self.assignments,
+            cuts: self.cuts,
+            straps: Vec::new(),
+            jogs: self.jogs,
+            keepouts: Vec::new(),
+            vias: self.vias,
+            suppress_rails: self.suppress_rails,
+            rail_rings: self.rail_rings,
+            places: Vec::new(),
+        };
+        let mut cell: Cell = layout.into();
+        cell.props = self.props;
+        Ok(cell)
+    }
+}
 impl From<CellView> for Cell {
     fn from(src: CellView) -> Self {
         match src {
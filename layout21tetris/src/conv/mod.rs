@@ -2,5 +2,7 @@
 //! Conversion Modules
 //!
 
+#[cfg(feature = "proto")]
 pub mod proto;
 pub mod raw;
+pub mod vias;
@@ -5,16 +5,22 @@
 // Std-Lib
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
+#[cfg(feature = "yaml")]
+use std::path::{Path, PathBuf};
+
+// Crates.io
+#[cfg(feature = "yaml")]
+use serde_yaml::Value;
 
 // Local imports
 use crate::{
     // FIXME: some of these should come from `validate`
     abs::{Abstract, Port},
     cell::Cell,
-    coords::{HasUnits, PrimPitches, Xy},
+    coords::{HasUnits, LayerIndex, PrimPitches, TrackIndex, Xy},
     instance::Instance,
     layout::Layout,
-    library::Library,
+    library::{CellRef, Library},
     outline::Outline,
     placement::Place,
     raw::{Dir, LayoutError, LayoutResult},
@@ -22,6 +28,11 @@ use crate::{
     tracks::{TrackCross, TrackRef},
     utils::{DepOrder, DepOrderer, ErrorContext, ErrorHelper, Ptr},
 };
+#[cfg(feature = "yaml")]
+use crate::{
+    library::{LIBRARY_MIGRATIONS, LIBRARY_SCHEMA},
+    utils::{self, ser, Migration},
+};
 // Proto-crate imports and aliases
 use layout21protos as proto;
 use proto::raw as rawproto;
@@ -115,8 +126,8 @@ impl<'lib> ProtoExporter<'lib> {
         let kind = match &port.kind {
             PortKind::Edge { layer, track, side } => {
                 let track = Some(tproto::TrackRef {
-                    layer: i64::try_from(*layer)?,
-                    track: i64::try_from(*track)?,
+                    layer: i64::try_from(layer.index())?,
+                    track: i64::try_from(track.index())?,
                 });
                 let side = match side {
                     Side::BottomOrLeft => PortSide::BottomOrLeft,
@@ -126,7 +137,7 @@ impl<'lib> ProtoExporter<'lib> {
                 Kind::Edge(EdgePort { track, side })
             }
             PortKind::ZTopEdge { track, side, into } => {
-                let track = i64::try_from(*track)?;
+                let track = i64::try_from(track.index())?;
                 let into = {
                     let layer = match into.1 {
                         RelZ::Above => metals + 1,
@@ -134,7 +145,7 @@ impl<'lib> ProtoExporter<'lib> {
                     };
                     Some(tproto::TrackRef {
                         layer: i64::try_from(layer)?,
-                        track: i64::try_from(into.0)?,
+                        track: i64::try_from(into.0.index())?,
                     })
                 };
                 let side = match side {
@@ -145,7 +156,9 @@ impl<'lib> ProtoExporter<'lib> {
 
                 Kind::ZtopEdge(ZTopEdgePort { track, side, into })
             }
-            PortKind::ZTopInner { locs: _ } => todo!(),
+            PortKind::ZTopInner { locs: _ } => {
+                self.fail("Export of ZTopInner Ports is not (yet) supported")?
+            }
         };
         pport.kind = Some(kind);
         Ok(pport)
@@ -215,8 +228,8 @@ impl<'lib> ProtoExporter<'lib> {
     }
     /// Export a [TrackRef]
     fn export_track_ref(&mut self, track: &TrackRef) -> LayoutResult<tproto::TrackRef> {
-        let layer = i64::try_from(track.layer)?;
-        let track = i64::try_from(track.track)?;
+        let layer = i64::try_from(track.layer.index())?;
+        let track = i64::try_from(track.track.index())?;
         Ok(tproto::TrackRef { layer, track })
     }
     /// Export a list of [HasUnit] dimensioned distance-values to
@@ -279,12 +292,34 @@ impl DepOrder for CellOrder {
 pub struct ProtoLibImporter {
     ctx: Vec<ErrorContext>,               // Error Stack
     cell_map: HashMap<String, Ptr<Cell>>, // Proto cell-name => [Cell]
+    deps: Library,                        // Dependencies, for references to cells not in `cell_map`
+    merged: Vec<String>,                  // Domains whose cells have been merged into `deps.cells`
 }
 impl ProtoLibImporter {
     pub fn import(plib: &tproto::Library) -> LayoutResult<Library> {
         // Run the main import-implementation method
         Self::default().import_lib(&plib)
     }
+    /// Import `plib`, resolving references to cells it does not define against `deps`.
+    /// Local references are resolved as [CellRef::Name]s, and external references as [CellRef::Qualified],
+    /// with their `domain` as the sub-library name.
+    /// The imported [Library] includes the `cells` and `libs` of `deps`, followed by its own.
+    pub fn import_with_deps(plib: &tproto::Library, deps: Library) -> LayoutResult<Library> {
+        Self {
+            deps,
+            ..Default::default()
+        }
+        .import_lib_with_deps(plib)
+    }
+    /// Internal implementation of [ProtoLibImporter::import_with_deps]
+    fn import_lib_with_deps(mut self, plib: &tproto::Library) -> LayoutResult<Library> {
+        let mut lib = self.import_lib(&plib)?;
+        let mut cells = std::mem::take(&mut self.deps.cells);
+        cells.extend(lib.cells.drain(..));
+        lib.cells = cells;
+        lib.libs = std::mem::take(&mut self.deps.libs);
+        Ok(lib)
+    }
     /// Internal implementation method. Convert the top-level library.
     fn import_lib(&mut self, plib: &tproto::Library) -> LayoutResult<Library> {
         let name = plib.domain.clone();
@@ -331,8 +366,12 @@ impl ProtoLibImporter {
         Ok(abs)
     }
     /// Import an Abstract [Port]
-    fn import_abstract_port(&mut self, _pport: &tproto::AbstractPort) -> LayoutResult<Port> {
-        todo!() // FIXME!
+    fn import_abstract_port(&mut self, pport: &tproto::AbstractPort) -> LayoutResult<Port> {
+        // FIXME!
+        self.fail(format!(
+            "Import of Abstract Port {} is not (yet) supported",
+            pport.net
+        ))
     }
     /// Import an [Outline]
     fn import_outline(&mut self, poutline: &tproto::Outline) -> LayoutResult<(Outline, usize)> {
@@ -364,7 +403,7 @@ impl ProtoLibImporter {
     fn import_track_ref(&mut self, pref: &tproto::TrackRef) -> LayoutResult<TrackRef> {
         let layer = usize::try_from(pref.layer)?;
         let track = usize::try_from(pref.track)?;
-        Ok(TrackRef { layer, track })
+        Ok(TrackRef::new(LayerIndex(layer), TrackIndex(track)))
     }
     /// Import a [Layout]
     fn import_layout(&mut self, playout: &tproto::Layout) -> LayoutResult<Layout> {
@@ -438,16 +477,27 @@ impl ProtoLibImporter {
             format!("Invalid tproto::Instance with null Cell: {}", pinst.name),
         )?;
         use proto::utils::reference::To::{External, Local};
-        let cellname: &str = match pref_to {
-            Local(ref name) => Ok(name),
-            External(_) => self.fail("Import of external proto-references not supported"),
-        }?;
-        // Now look that up in our hashmap
-        let cellptr = self.unwrap(
-            self.cell_map.get(cellname),
-            format!("Instance tproto::Instance of undefined cell {}", cellname),
-        )?;
-        Ok(cellptr.clone())
+        let cref = match pref_to {
+            Local(ref name) => {
+                // Look that up in our hashmap, and then in any dependencies
+                if let Some(cellptr) = self.cell_map.get(name) {
+                    return Ok(cellptr.clone());
+                }
+                CellRef::name(name)
+            }
+            // References into merged libraries resolve by name
+            External(ref qname) if self.merged.contains(&qname.domain) => {
+                CellRef::name(&qname.name)
+            }
+            External(ref qname) => CellRef::qualified(&[&qname.domain], &qname.name),
+        };
+        match self.deps.resolve_ref(&cref) {
+            Ok(cellptr) => Ok(cellptr),
+            Err(e) => self.fail(format!(
+                "Instance tproto::Instance of undefined cell {}: {}",
+                cref, e
+            )),
+        }
     }
     /// Import a [tproto::Point] designed to be interpreted as [PrimPitches]
     fn import_xy_prim_pitches(&mut self, pt: &rawproto::Point) -> LayoutResult<Xy<PrimPitches>> {
@@ -473,6 +523,201 @@ impl ProtoLibImporter {
     }
 } // impl ProtoLibImporter
 
+/// # Included-Library Handling
+///
+/// Whether libraries included via `includes` are added as sub-libraries, or merged into their includer.
+///
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IncludeMode {
+    /// Add each included library to `libs`
+    #[default]
+    SubLibrary,
+    /// Add each included library's `cells` and `libs` to those of its includer
+    Merge,
+}
+
+/// # ProtoBuf Library Loader
+///
+/// Loads YAML-format [tproto::Library] files, along with those they include.
+/// Includes are declared by an optional top-level `includes` list of file paths, e.g.
+///
+/// ```text
+/// schema: 1
+/// domain: design
+/// includes: ["stdcells.yaml"]
+/// cells: [...]
+/// ```
+///
+/// Relative paths are searched for first relative to the including file, and then in each of `search_paths`.
+/// Files included more than once are loaded once, and shared.
+/// Each file is first migrated to [LIBRARY_SCHEMA] by our [Migration]s, generally [LIBRARY_MIGRATIONS].
+///
+#[cfg(feature = "yaml")]
+#[derive(Debug, Default)]
+pub struct ProtoLibLoader {
+    search_paths: Vec<PathBuf>,
+    mode: IncludeMode,
+    migrations: Vec<Migration>, // Applied to each file before deserialization
+    chain: Vec<PathBuf>,        // Include chain, for cycle detection and error reporting
+    loaded: HashMap<PathBuf, (Ptr<Library>, Vec<String>)>, // Previously loaded files, and the domains merged into each
+}
+#[cfg(feature = "yaml")]
+impl ProtoLibLoader {
+    /// Load the [Library] at `path`, along with its includes, migrating each per [LIBRARY_MIGRATIONS]
+    pub fn load(
+        path: impl AsRef<Path>,
+        search_paths: &[PathBuf],
+        mode: IncludeMode,
+    ) -> LayoutResult<Library> {
+        Self::load_with_migrations(path, search_paths, mode, LIBRARY_MIGRATIONS)
+    }
+    /// Load the [Library] at `path`, along with its includes,
+    /// applying `migrations` to each untyped document before deserializing it.
+    pub fn load_with_migrations(
+        path: impl AsRef<Path>,
+        search_paths: &[PathBuf],
+        mode: IncludeMode,
+        migrations: &[Migration],
+    ) -> LayoutResult<Library> {
+        let mut myself = Self {
+            search_paths: search_paths.to_vec(),
+            mode,
+            migrations: migrations.to_vec(),
+            ..Default::default()
+        };
+        let (lib, _merged) = myself.load_file(path.as_ref())?;
+        Ok(lib)
+    }
+    /// Load file `path`, which has been located, along with its includes.
+    /// Returns the [Library], along with the domains of any libraries merged into it.
+    fn load_file(&mut self, path: &Path) -> LayoutResult<(Library, Vec<String>)> {
+        let path = path.canonicalize()?;
+        if self.chain.contains(&path) {
+            self.chain.push(path);
+            return LayoutError::fail(format!("Circular include: {}", self.chain_str()));
+        }
+        self.chain.push(path.clone());
+        let txt = std::fs::read_to_string(&path)?;
+        let doc: Value = serde_yaml::from_str(&txt).map_err(ser::Error::from)?;
+        let mut doc = match utils::migrate(doc, &self.migrations) {
+            Ok(doc) => doc,
+            Err(e) => {
+                return LayoutError::fail(format!("Library file {}: {}", path.display(), e));
+            }
+        };
+        // Extract the `schema` version and `includes` list, neither of which are part of the proto-schema
+        let (schema, includes) = match doc.as_mapping_mut() {
+            Some(map) => (
+                map.remove(&Value::from("schema")),
+                map.remove(&Value::from("includes")),
+            ),
+            None => (None, None),
+        };
+        let schema: u32 = match schema {
+            Some(schema) => serde_yaml::from_value(schema).map_err(ser::Error::from)?,
+            None => 0,
+        };
+        if schema > LIBRARY_SCHEMA {
+            return LayoutError::fail(format!(
+                "Library file {} schema {}, crate supports up to {}",
+                path.display(),
+                schema,
+                LIBRARY_SCHEMA
+            ));
+        }
+        if schema < LIBRARY_SCHEMA {
+            return LayoutError::fail(format!(
+                "Library file {} schema {} was not migrated to schema {}",
+                path.display(),
+                schema,
+                LIBRARY_SCHEMA
+            ));
+        }
+        let includes: Vec<String> = match includes {
+            Some(includes) => serde_yaml::from_value(includes).map_err(ser::Error::from)?,
+            None => Vec::new(),
+        };
+        let plib: tproto::Library = serde_yaml::from_value(doc).map_err(ser::Error::from)?;
+
+        // Load each dependency
+        let mut deps = Library::new(&plib.domain);
+        let mut merged = Vec::new();
+        for include in includes.iter() {
+            let incpath = self.locate(include)?.canonicalize()?;
+            let (inclib, incmerged) = match self.loaded.get(&incpath) {
+                Some(loaded) => loaded.clone(),
+                None => {
+                    let (inclib, incmerged) = self.load_file(&incpath)?;
+                    let loaded = (Ptr::new(inclib), incmerged);
+                    self.loaded.insert(incpath, loaded.clone());
+                    loaded
+                }
+            };
+            match self.mode {
+                IncludeMode::SubLibrary => {
+                    if !deps.libs.contains(&inclib) {
+                        deps.libs.push(inclib);
+                    }
+                }
+                IncludeMode::Merge => {
+                    let inclib = inclib.read()?;
+                    merged.push(inclib.name.clone());
+                    merged.extend(incmerged);
+                    for ptr in inclib.cells.iter() {
+                        if !deps.cells.contains(ptr) {
+                            deps.cells.push(ptr.clone());
+                        }
+                    }
+                    for ptr in inclib.libs.iter() {
+                        if !deps.libs.contains(ptr) {
+                            deps.libs.push(ptr.clone());
+                        }
+                    }
+                }
+            }
+        }
+        let importer = ProtoLibImporter {
+            deps,
+            merged: merged.clone(),
+            ..Default::default()
+        };
+        let lib = importer.import_lib_with_deps(&plib)?;
+        self.chain.pop();
+        Ok((lib, merged))
+    }
+    /// Locate included file `include`, relative to the current includer or any of our search paths
+    fn locate(&self, include: &str) -> LayoutResult<PathBuf> {
+        let include = Path::new(include);
+        if include.is_absolute() {
+            if include.exists() {
+                return Ok(include.to_path_buf());
+            }
+        } else {
+            let includer_dir = self.chain.last().and_then(|p| p.parent());
+            for dir in includer_dir
+                .into_iter()
+                .chain(self.search_paths.iter().map(|p| p.as_path()))
+            {
+                let candidate = dir.join(include);
+                if candidate.exists() {
+                    return Ok(candidate);
+                }
+            }
+        }
+        LayoutError::fail(format!(
+            "Included file {} not found. Include chain: {}",
+            include.display(),
+            self.chain_str()
+        ))
+    }
+    /// Format our include chain for error messages
+    fn chain_str(&self) -> String {
+        let files: Vec<String> = self.chain.iter().map(|p| p.display().to_string()).collect();
+        files.join(" -> ")
+    }
+}
+
 impl ErrorHelper for ProtoLibImporter {
     type Error = LayoutError;
     fn err(&self, msg: impl Into<String>) -> LayoutError {
@@ -485,6 +730,7 @@ impl ErrorHelper for ProtoLibImporter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "yaml")]
     use crate::utils::SerializationFormat::Yaml;
 
     #[test]
@@ -553,6 +799,50 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn proto_roundtrip_stable() -> LayoutResult<()> {
+        // Proto round-trips preserve cell names, but reorder cells, and hence [CellId]s, into dependency order.
+        // Once so ordered, further round-trips preserve [CellId]s, and produce identical serialized output.
+        use crate::library::CellId;
+
+        let mut lib = Library::new("proto_rt_stable");
+        let child = Ptr::new(Cell::from(Layout::new("child", 1, Outline::rect(2, 2)?)));
+        let mut parent = Layout::new("parent", 2, Outline::rect(10, 10)?);
+        parent.instances.add(
+            Instance::builder()
+                .inst_name("i0")
+                .cell(child.clone())
+                .loc((1, 1))
+                .build()?,
+        );
+        // Insert the parent first; export places dependencies first
+        lib.cells.push(Ptr::new(parent.into()));
+        lib.cells.push(child.clone());
+        assert_eq!(lib.cell_id(&child), Some(CellId(1)));
+        assert_eq!(lib.cell_id_named("parent")?, Some(CellId(0)));
+
+        let yaml1 = Yaml.to_string(&ProtoExporter::export(&lib)?)?;
+        let lib2 = ProtoLibImporter::import(&Yaml.from_str(&yaml1)?)?;
+        // The child moves ahead of its parent, changing both their ids
+        assert_eq!(lib2.cell_id_named("child")?, Some(CellId(0)));
+        assert_eq!(lib2.cell_id_named("parent")?, Some(CellId(1)));
+        assert_eq!(lib2.cell_id_named("other")?, None);
+        let child2 = lib2.cell_named("child")?.unwrap();
+        assert_eq!(lib2.cell_id(&child2), Some(CellId(0)));
+        let parent2 = lib2.cell(CellId(1)).unwrap().read()?;
+        let inst = parent2.layout.as_ref().unwrap().instances[0].read()?;
+        assert_eq!(inst.cell, child2);
+
+        let yaml2 = Yaml.to_string(&ProtoExporter::export(&lib2)?)?;
+        assert_eq!(yaml1, yaml2);
+        let lib3 = ProtoLibImporter::import(&Yaml.from_str(&yaml2)?)?;
+        assert_eq!(lib3.cell_id_named("child")?, Some(CellId(0)));
+        assert_eq!(lib3.cell_id_named("parent")?, Some(CellId(1)));
+        assert_eq!(Yaml.to_string(&ProtoExporter::export(&lib3)?)?, yaml2);
+        Ok(())
+    }
+
     #[test]
     fn proto_yaml1() -> LayoutResult<()> {
         // Proto export, then YAML export
@@ -565,6 +855,7 @@ mod tests {
         // Yaml.save(&plib, "proto_yaml1.yaml")?;
         Ok(())
     }
+    #[cfg(feature = "yaml")]
     #[test]
     fn proto_yaml2() -> LayoutResult<()> {
         // Import from YAML
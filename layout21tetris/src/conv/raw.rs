@@ -3,25 +3,36 @@
 //!
 
 // Std-lib
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
 
 // Crates.io
-use slotmap::{new_key_type, SlotMap};
+use serde::{Deserialize, Serialize};
+use slotmap::{new_key_type, SecondaryMap, SlotMap};
 
 // Local imports
 use crate::{
-    abs, cell,
-    coords::{DbUnits, HasUnits, PrimPitches, UnitSpeced, Xy},
+    abs::{self, Side},
+    bbox::HasBoundBox,
+    cell,
+    conv::vias::{ViaGenerator, ViaSpec},
+    coords::{DbUnits, HasUnits, Int, LayerIndex, PrimPitches, TrackIndex, UnitSpeced, Xy},
     instance::Instance,
-    layout::Layout,
+    layout::{Keepout, Layout},
     library::Library,
+    occupancy::{RawTrackOccupancy, TrackOccupancy},
     outline::Outline,
     raw::{self, Dir, LayoutError, LayoutResult, Point},
-    stack::{LayerPeriod, RelZ},
-    tracks::{Track, TrackCross, TrackSegmentType},
-    utils::{ErrorContext, ErrorHelper, Ptr, PtrList, Unwrapper},
+    routes::{CellRouteDb, LayerRoutes},
+    stack::{Assign, Jog, LayerPeriod, MetalLayer, RelZ, Strap, ViaLayer, ViaPolicy},
+    tracks::{
+        ConflictPolicy, RailKind, Track, TrackCross, TrackRef, TrackSegment, TrackSegmentType,
+        TrackType,
+    },
+    utils::{CancelToken, ErrorContext, ErrorHelper, ProgressHook, Ptr, PtrList, Unwrapper},
     validate,
 };
 
@@ -30,6 +41,58 @@ new_key_type! {
     /// Keys for [ValidAssign] entries
     pub struct AssignKey;
 }
+/// Interned net identifier, handed out by a [NetInterner]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetId(usize);
+impl NetId {
+    /// Get our index into the [NetInterner]'s names
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+/// # Net Interner
+///
+/// Hands out a [NetId] per distinct net name, so that each name is stored once,
+/// however many assignments refer to it. Used per cell during raw conversion.
+#[derive(Debug, Clone, Default)]
+pub struct NetInterner {
+    /// Net names, indexed by [NetId]
+    names: Vec<String>,
+    /// [NetId]s, keyed by name
+    ids: HashMap<String, NetId>,
+}
+impl NetInterner {
+    /// Create a new, empty [NetInterner]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Get the [NetId] of net `name`, adding it if not already present
+    pub fn intern(&mut self, name: &str) -> NetId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = NetId(self.names.len());
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+    /// Get the [NetId] of net `name`, if present
+    pub fn get(&self, name: &str) -> Option<NetId> {
+        self.ids.get(name).copied()
+    }
+    /// Get the name of net `id`, if it was handed out by this [NetInterner]
+    pub fn name(&self, id: NetId) -> Option<&str> {
+        self.names.get(id.0).map(|s| s.as_str())
+    }
+    /// Get the number of distinct nets
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    /// Boolean indication of whether no nets have been interned
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
 /// A short-lived Cell, largely organized by layer
 #[derive(Debug, Clone)]
 struct TempCell<'lib> {
@@ -43,10 +106,16 @@ struct TempCell<'lib> {
     cuts: Vec<Vec<&'lib TrackCross>>,
     /// Validated Assignments
     assignments: SlotMap<AssignKey, validate::ValidAssign>,
+    /// Nets of the cell's assignments
+    nets: NetInterner,
+    /// Interned net of each assignment
+    assn_nets: SecondaryMap<AssignKey, NetId>,
     /// Assignments, arranged by Layer
     top_assns: Vec<Vec<AssignKey>>,
     /// Assignments, arranged by Layer
     bot_assns: Vec<Vec<AssignKey>>,
+    /// Validated directly-placed vias
+    vias: Vec<validate::ValidVia>,
 }
 /// Temporary arrangement of data for a [Layer] within a [Cell]
 #[derive(Debug, Clone)]
@@ -55,14 +124,21 @@ struct TempCellLayer<'lib> {
     layer: &'lib validate::ValidMetalLayer,
     /// Reference to the parent cell
     cell: &'lib TempCell<'lib>,
-    /// Instances which intersect with this layer and period
-    instances: PtrList<Instance>,
+    /// Instances which reach up to this layer, the range of periods each blocks,
+    /// and for raw-layout instances on their top layer, the parent-frame tracks they occupy
+    instances: Vec<InstanceBlockage>,
     /// Pitch per layer-period
     pitch: DbUnits,
     /// Number of layer-periods
     nperiods: usize,
     /// Spanning distance in the layer's "infinite" dimension
     span: DbUnits,
+    /// Cuts, arranged by period
+    cuts: Vec<Vec<&'lib TrackCross>>,
+    /// Assignments for which this is the top layer, arranged by period
+    top_assns: Vec<Vec<AssignKey>>,
+    /// Assignments for which this is the bottom layer, arranged by period
+    bot_assns: Vec<Vec<AssignKey>>,
 }
 
 /// Short-Lived structure of the stuff relevant for converting a single LayerPeriod,
@@ -72,11 +148,567 @@ struct TempPeriod<'lib> {
     periodnum: usize,
     cell: &'lib TempCell<'lib>,
     layer: &'lib TempCellLayer<'lib>,
-    /// Instance Blockages
-    blockages: Vec<(PrimPitches, PrimPitches, Ptr<Instance>)>,
-    cuts: Vec<&'lib TrackCross>,
-    top_assns: Vec<AssignKey>,
-    bot_assns: Vec<AssignKey>,
+    /// Instance and Keep-Out Blockages
+    blockages: Vec<(PrimPitches, PrimPitches, BlockageSrc)>,
+    /// Single-track blockages of raw-layout instances: (track index within the period, start, stop, instance)
+    track_blockages: Vec<(usize, DbUnits, DbUnits, Ptr<Instance>)>,
+    cuts: &'lib [&'lib TrackCross],
+    top_assns: &'lib [AssignKey],
+    bot_assns: &'lib [AssignKey],
+}
+/// Source of a track blockage: an [Instance], or a [Keepout] by its index in the [Layout]
+#[derive(Debug, Clone)]
+enum BlockageSrc {
+    Instance(Ptr<Instance>),
+    Keepout(usize),
+}
+impl BlockageSrc {
+    /// Get our [BlockageKey]
+    fn key(&self) -> BlockageKey {
+        match self {
+            BlockageSrc::Instance(ptr) => BlockageKey::Instance(Arc::as_ptr(ptr) as usize),
+            BlockageSrc::Keepout(idx) => BlockageKey::Keepout(*idx),
+        }
+    }
+}
+/// Cache-key identifier of a [BlockageSrc].
+/// [Instance]s are identified by address, as in [Ptr]'s implementation of [Hash].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum BlockageKey {
+    Instance(usize),
+    Keepout(usize),
+    /// Single track, by index within its period, blocked by an [Instance]
+    InstanceTrack(usize, usize),
+}
+/// Instance reaching up to a [TempCellLayer], the range of periods it blocks, and any tracks it occupies
+type InstanceBlockage = (Ptr<Instance>, Range<usize>, Option<Vec<TrackOccupancy>>);
+/// Key for caching blocked [LayerPeriod]s, which are shared between periods with identical blockages.
+/// Includes whether the period is flipped, and its sorted list of (start, stop, source) blockages.
+type PeriodCacheKey = (bool, Vec<(DbUnits, DbUnits, BlockageKey)>);
+/// Cache of blocked [LayerPeriod]s, for a single layer in a single cell.
+/// Values are the period-number at which each entry was created, and the blocked [LayerPeriod].
+type PeriodCache = HashMap<PeriodCacheKey, (usize, LayerPeriod)>;
+/// Location of a via to be instantiated from a generated via-cell per `spec`,
+/// whose bottom metal is layer-index `layer`.
+#[derive(Debug, Clone, Copy)]
+struct ViaCellPlacement {
+    layer: usize,
+    loc: Xy<DbUnits>,
+    spec: ViaSpec,
+}
+
+/// # Raw Export Options
+#[derive(Debug, Clone)]
+pub struct RawExportOptions {
+    /// Share blocked [LayerPeriod]s between periods with identical blockages.
+    /// Enabled by default; disabling produces identical results, generally more slowly.
+    pub cache_periods: bool,
+    /// Collect a [CellRouteDb] for each converted [Layout], in [ConversionReport::routes].
+    /// Disabled by default.
+    pub route_db: bool,
+    /// Handling of [Strap]s which overlap prior net assignments.
+    /// Errors by default.
+    pub strap_conflicts: ConflictPolicy,
+    /// Separator between sub-library and cell names, in the flattened names of sub-library cells.
+    /// Defaults to `__`, e.g. `stdcells__inv`.
+    pub lib_separator: String,
+    /// Instantiate vias from generated cells per this [ViaSpec], rather than drawing them inline.
+    /// Disabled by default.
+    pub via_cells: Option<ViaSpec>,
+    /// Named via-cell variants, selected per [Assign](crate::stack::Assign) by [ViaPolicy::Variant].
+    /// Empty by default.
+    pub via_variants: BTreeMap<String, ViaSpec>,
+    /// Handling of the cells of the [Library]'s `rawlibs`.
+    /// Included in the output by default.
+    pub raw_libs: RawLibHandling,
+    /// Datatype of marker rectangles drawn for each [Keepout], on each of its metal layers.
+    /// The datatype must be defined for each such layer. Disabled by default.
+    pub keepout_marker: Option<i16>,
+    /// Clip rails to the cell outline along edges at which instances of the cell abut others,
+    /// as found by [Layout::abutments], so that abutted neighbors don't draw duplicate half-rails.
+    /// Applies only to abutted instances, which refer to a clipped variant of their cell,
+    /// named per [clipped_cell_name]. Disabled by default.
+    pub clip_abutted_rails: bool,
+    /// Add a unit cell per metal layer, comprising a single period of its tracks,
+    /// and record their names in [ConversionReport::unit_cells].
+    /// Names are per [unit_cell_name], suffixed `_1`, `_2`, etc. if already taken. Disabled by default.
+    pub unit_cells: bool,
+    /// Handling of [Instance]s of abstract-only cells, i.e. those with an [abs::Abstract] but no implementation.
+    /// Exported as boundary-only cells by default.
+    pub abstracts: AbstractHandling,
+    /// Minimum length of emitted rail and unassigned-wire segments, e.g. the stubs left between blockages and cell edges.
+    /// Shorter segments, and zero-length segments in any case, are dropped.
+    /// Defaults to each layer's `cutsize`.
+    pub min_segment: Option<DbUnits>,
+    /// Handling of segments shorter than their layer's [MetalLayer::min_length].
+    /// Unassigned ones are removed by default.
+    pub min_length: MinLengthPolicy,
+    /// Progress callbacks, invoked as each cell and layer is converted.
+    /// No-op by default.
+    pub progress: ProgressHook,
+    /// Cancellation token, checked as each cell, layer, and layer-period is converted.
+    /// Once cancelled, conversion fails with [LayoutError::Cancelled]. Disabled by default.
+    pub cancel: Option<CancelToken>,
+    /// Full or floorplan-only conversion. Full by default.
+    pub mode: ConversionMode,
+    /// (Layer, datatype) number-pair of debug markers drawn at each [Instance] origin:
+    /// an L-shaped polygon oriented per the instance's reflections,
+    /// accompanied by a [raw::TextElement] annotation of the instance name.
+    /// The datatype must be defined on the layer.
+    /// Exclude them from GDSII via [raw::gds::GdsExportOptions::exclude_layers]. Disabled by default.
+    pub instance_markers: Option<(i16, i16)>,
+    /// Handling of assignments landing where their top layer is blocked by an [Instance], or on a cut.
+    /// Errors by default.
+    pub on_unroutable: OnUnroutable,
+    /// Record the [Provenance] of each emitted [raw::Element], in [ConversionReport::provenance].
+    /// Disabled by default.
+    pub provenance: bool,
+}
+/// # Conversion Mode
+///
+/// Whether conversion produces full layout, or only a lightweight floorplan view.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConversionMode {
+    /// Convert all tracks, assignments, cuts, and vias
+    #[default]
+    Full,
+    /// Emit only each cell's outline and instances, skipping all track, assignment, and via work.
+    /// Abstracts are similarly reduced to their outlines.
+    FloorplanOnly,
+}
+/// # Unroutable-Assignment Policy
+///
+/// Treatment of [Assign](crate::stack::Assign)s which cannot be routed:
+/// those whose intersection lies within an [Instance] blockage on their top layer, or on a cut on either layer.
+/// Assignments landing on instance blockages on only their *bottom* layer are routable,
+/// generally connecting to the instance's pins, and are unaffected.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnUnroutable {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Drop the assignment, along with its via, record it in [ConversionReport::unroutable], and warn
+    SkipWarn,
+}
+/// # Raw-Library Handling
+///
+/// Whether the cells of imported [raw::Library]s, e.g. primitive standard-cell libraries,
+/// are included in the converted [raw::Library], or left as external references by name.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RawLibHandling {
+    /// Include imported raw cells in the output
+    #[default]
+    Include,
+    /// Omit imported raw cells; instances refer to them by name only
+    External,
+}
+/// # Abstract-Only Cell Handling
+///
+/// Treatment of [Instance]s of cells with an [abs::Abstract] but no implementation,
+/// which convert to raw cells comprising little more than their boundaries.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AbstractHandling {
+    /// Instantiate the boundary-only cells
+    #[default]
+    BoundaryOnly,
+    /// Omit such instances, with a warning for each
+    Omit,
+    /// Fail, listing all such instantiated cells
+    RequireImpl,
+}
+/// # Minimum-Length Policy
+///
+/// Treatment of "sliver" segments, shorter than their layer's [MetalLayer::min_length],
+/// and adjacent to a cut or blockage. See [Track::slivers].
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MinLengthPolicy {
+    /// Remove unassigned-wire and rail slivers by extending their adjacent cuts or blockages,
+    /// and record each in [ConversionReport::slivers].
+    /// Removing rail slivers, which carry power or ground, also generates a warning.
+    /// Slivers of assigned wires and straps fail.
+    #[default]
+    Remove,
+    /// Fail on any sliver
+    Error,
+}
+impl Default for RawExportOptions {
+    fn default() -> Self {
+        Self {
+            cache_periods: true,
+            route_db: false,
+            strap_conflicts: ConflictPolicy::Error,
+            lib_separator: "__".into(),
+            via_cells: None,
+            via_variants: BTreeMap::new(),
+            raw_libs: RawLibHandling::default(),
+            keepout_marker: None,
+            clip_abutted_rails: false,
+            unit_cells: false,
+            abstracts: AbstractHandling::default(),
+            min_segment: None,
+            min_length: MinLengthPolicy::default(),
+            progress: ProgressHook::default(),
+            cancel: None,
+            mode: ConversionMode::default(),
+            instance_markers: None,
+            on_unroutable: OnUnroutable::default(),
+            provenance: false,
+        }
+    }
+}
+/// # Raw Conversion Report
+///
+/// Statistics collected during conversion to [raw::Library].
+/// Fields may be omitted from serialized reports, and take their default values.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversionReport {
+    /// Number of layer-periods converted
+    pub periods: usize,
+    /// Number of layer-periods copied from the period-cache, rather than created anew
+    pub period_cache_hits: usize,
+    /// Post-route track databases, one per converted [Layout].
+    /// Only populated if enabled via [RawExportOptions::route_db].
+    pub routes: Vec<CellRouteDb>,
+    /// Per-layer unit cells. Only populated if enabled via [RawExportOptions::unit_cells].
+    pub unit_cells: UnitCells,
+    /// Names of abstract-only cells which are instantiated, in conversion order
+    pub abstract_only: Vec<String>,
+    /// Non-fatal issues, e.g. instances omitted per [AbstractHandling::Omit]
+    pub warnings: Vec<String>,
+    /// Sliver segments removed per [MinLengthPolicy::Remove], in conversion order
+    pub slivers: Vec<Sliver>,
+    /// Assignments dropped per [OnUnroutable::SkipWarn], in conversion order
+    pub unroutable: Vec<Unroutable>,
+    /// Element provenance, one per converted [Layout].
+    /// Only populated if enabled via [RawExportOptions::provenance].
+    pub provenance: Vec<CellProvenance>,
+}
+impl ConversionReport {
+    /// Get the [CellRouteDb] for the cell named `name`, if present
+    pub fn route_db(&self, name: &str) -> Option<&CellRouteDb> {
+        self.routes.iter().find(|r| r.name == name)
+    }
+    /// Get the [Provenance] of element `elem` of the cell named `name`, if present
+    pub fn provenance_of(&self, name: &str, elem: usize) -> Option<&Provenance> {
+        let cell = self.provenance.iter().find(|p| p.cell == name)?;
+        cell.elems.get(elem)
+    }
+}
+/// # Cell Provenance
+///
+/// The [Provenance] of each element of a converted [Layout],
+/// parallel to, i.e. indexed like, the `elems` of its [raw::Layout].
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CellProvenance {
+    /// Cell Name
+    pub cell: String,
+    /// Provenance per element
+    pub elems: Vec<Provenance>,
+}
+/// # Element Provenance
+///
+/// The source of a converted [raw::Element], for tracing geometry back to the [Layout] content which produced it.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Source Kind
+    pub source: ProvenanceSource,
+    /// Metal-Layer Index. For vias, that of their bottom layer.
+    pub layer: Option<usize>,
+    /// Layer-Period (row or column) Index, for track segments and assignment vias
+    pub period: Option<usize>,
+    /// Blockages and cuts which end a track segment, at either end
+    pub bounds: Vec<SegmentBound>,
+}
+impl Provenance {
+    /// Create a new [Provenance] from `source`, with no layer, period, or bounds
+    pub fn new(source: ProvenanceSource) -> Self {
+        Self {
+            source,
+            layer: None,
+            period: None,
+            bounds: Vec::new(),
+        }
+    }
+}
+/// # Provenance Source Kinds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvenanceSource {
+    /// Power/ground rail, generated from its layer's track definitions
+    Rail(RailKind),
+    /// Wire assigned to `net` by the [Assign] at `at`
+    Assign { net: String, at: TrackCross },
+    /// Unassigned wire
+    Wire,
+    /// Part of a [Strap] on `net`
+    Strap { net: String },
+    /// [Jog] on `net`
+    Jog { net: String },
+    /// Via, either of the [Assign] at `assign`, or placed directly
+    Via {
+        net: Option<String>,
+        assign: Option<TrackCross>,
+    },
+    /// Strap or via of the rail ring, per [Layout::rail_rings]
+    RailRing,
+    /// Cell boundary
+    Outline,
+    /// Marker of the [Keepout] named `name`, per [RawExportOptions::keepout_marker]
+    Keepout { name: String },
+    /// Marker of the [Instance] named `name`, per [RawExportOptions::instance_markers]
+    Instance { name: String },
+}
+/// # Track-Segment Bound
+///
+/// A cut or blockage adjacent to a track segment, which ends it.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SegmentBound {
+    /// Blockage by the [Instance] or [Keepout] named `src`
+    Blockage { src: String },
+    /// Cut at `at`
+    Cut { at: TrackCross },
+}
+/// Extend `prov`, if collecting, with the [Provenance] of each element of `elems` it does not yet cover, per `source`
+fn annotate(
+    prov: Option<&mut Vec<Provenance>>,
+    elems: &[raw::Element],
+    source: impl Fn(&raw::Element) -> Provenance,
+) {
+    if let Some(prov) = prov {
+        let start = prov.len();
+        prov.extend(elems[start..].iter().map(source));
+    }
+}
+/// # Removed Sliver Segment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sliver {
+    /// Cell Name
+    pub cell: String,
+    /// Metal-Layer Index
+    pub layer: usize,
+    /// Track Type
+    pub ttype: TrackType,
+    /// Track Index, among those of its type
+    pub track: usize,
+    /// Start Location
+    pub start: DbUnits,
+    /// Stop Location
+    pub stop: DbUnits,
+}
+/// # Unroutable Assignment
+///
+/// An [Assign](crate::stack::Assign) dropped from conversion per [OnUnroutable::SkipWarn].
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Unroutable {
+    /// Cell Name
+    pub cell: String,
+    /// Net Name
+    pub net: String,
+    /// Assigned Intersection
+    pub at: TrackCross,
+    /// Name of the blocking [Instance], or for cuts, `cut` and its [TrackCross]
+    pub blocker: String,
+}
+/// # Unit-Cell Registry
+///
+/// Names of the per-layer unit [raw::Cell]s generated per [RawExportOptions::unit_cells],
+/// keyed by metal-layer index.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitCells {
+    pub cells: BTreeMap<usize, String>,
+}
+impl UnitCells {
+    /// Get the name of the unit cell for metal-layer `layer`, if present
+    pub fn get(&self, layer: usize) -> Option<&str> {
+        self.cells.get(&layer).map(|s| s.as_str())
+    }
+}
+/// Get the name of the unit cell for [MetalLayer] `layer`, e.g. `met1_unit`.
+/// Characters other than ASCII alphanumerics, `_`, `?`, and `$` are not valid in GDSII structure names,
+/// and are replaced with `_`.
+pub fn unit_cell_name(layer: &MetalLayer) -> String {
+    let name: String = layer
+        .name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '?' | '$' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{}_unit", name)
+}
+/// Abutted cell edges, as (clipped direction, side) pairs, in order: bottom, top, left, right
+const ABUTTED_EDGES: [(Dir, Side); 4] = [
+    (Dir::Vert, Side::BottomOrLeft),
+    (Dir::Vert, Side::TopOrRight),
+    (Dir::Horiz, Side::BottomOrLeft),
+    (Dir::Horiz, Side::TopOrRight),
+];
+/// Abutted cell edges, as (clipped direction, side) pairs, in the order of [ABUTTED_EDGES]
+type AbuttedEdges = Vec<(Dir, Side)>;
+/// Get the name of the variant of cell `name` with rails clipped along abutted `edges`,
+/// per [RawExportOptions::clip_abutted_rails], e.g. `inv_clip_bt` for one clipped at its bottom and top edges.
+pub fn clipped_cell_name(name: &str, edges: &[(Dir, Side)]) -> String {
+    let suffix: String = edges
+        .iter()
+        .map(|edge| match edge {
+            (Dir::Vert, Side::BottomOrLeft) => 'b',
+            (Dir::Vert, Side::TopOrRight) => 't',
+            (Dir::Horiz, Side::BottomOrLeft) => 'l',
+            (Dir::Horiz, Side::TopOrRight) => 'r',
+        })
+        .collect();
+    format!("{}_clip_{}", name, suffix)
+}
+/// Render [Track] `track` on `layer` to a vector of [raw::Element] rectangles.
+/// Wires, rails, and straps are rendered; cuts and blockages are skipped.
+/// Degenerate segments are dropped, as are rail and unassigned-wire stubs shorter than `min_segment`.
+/// This is the same geometry produced by [RawExporter] conversion, less any rail clipping.
+/// Each segment's [raw::Label]s are carried over to its element.
+pub fn render_track(
+    track: &Track,
+    layer: &validate::ValidMetalLayer,
+    min_segment: DbUnits,
+) -> LayoutResult<Vec<raw::Element>> {
+    let mut elems = Vec::new();
+    render_track_into(track, layer, min_segment, &mut elems)?;
+    Ok(elems)
+}
+/// Render [Track] `track` as in [render_track], appending its elements to `elems`
+pub fn render_track_into(
+    track: &Track,
+    layer: &validate::ValidMetalLayer,
+    min_segment: DbUnits,
+    elems: &mut Vec<raw::Element>,
+) -> LayoutResult<()> {
+    render_track_impl(track, layer, min_segment, elems, None)
+}
+/// Implementation of [render_track_into].
+/// If `prov` is provided, the [Provenance] of each element is appended to it, as part of the given layer-period.
+fn render_track_impl(
+    track: &Track,
+    layer: &validate::ValidMetalLayer,
+    min_segment: DbUnits,
+    elems: &mut Vec<raw::Element>,
+    mut prov: Option<(&mut Vec<Provenance>, usize)>,
+) -> LayoutResult<()> {
+    let rawlayer = match layer.raw {
+        Some(k) => k,
+        None => {
+            return LayoutError::fail(format!(
+                "Cannot render track on layer {} with no raw layer",
+                layer.spec.name
+            ))
+        }
+    };
+    let point = |x: DbUnits, y: DbUnits| raw::Point::new(x.0, y.0);
+    if track.data.width <= DbUnits(0) {
+        return Ok(()); // Zero-width tracks produce no geometry
+    }
+    for (idx, seg) in track.segments.iter().enumerate() {
+        use TrackSegmentType::*;
+        // Skip degenerate segments, and short stubs which carry no assigned net
+        let len = seg.stop - seg.start;
+        let stub = match seg.tp {
+            Wire { src: None } | Rail(_) => len < min_segment,
+            _ => false,
+        };
+        if len <= DbUnits(0) || stub {
+            continue;
+        }
+        let net: Option<String> = match seg.tp {
+            Wire { ref src } => src.as_ref().map(|src| src.net.clone()),
+            Rail(rk) => Some(rk.to_string()),
+            Strap { ref net } => Some(net.clone()),
+            Cut { .. } | Blockage { .. } => continue,
+        };
+        // Convert the inner shape
+        let (start, stop) = (track.data.start, track.data.start + track.data.width);
+        let mut inner = match track.data.dir {
+            Dir::Horiz => raw::Shape::Rect(raw::Rect {
+                p0: point(seg.start, start),
+                p1: point(seg.stop, stop),
+            }),
+            Dir::Vert => raw::Shape::Rect(raw::Rect {
+                p0: point(start, seg.start),
+                p1: point(stop, seg.stop),
+            }),
+        };
+        inner.normalize();
+        // Draw colored tracks on their mask's purpose, if the layer defines one
+        let purpose = track
+            .data
+            .color
+            .and_then(|c| layer.color_purposes.get(usize::from(c)))
+            .cloned()
+            .unwrap_or(raw::LayerPurpose::Drawing);
+        elems.push(raw::Element {
+            net,
+            layer: rawlayer,
+            purpose,
+            inner,
+            labels: seg.labels.clone(),
+        });
+        if let Some((ref mut prov, period)) = prov {
+            let source = match seg.tp {
+                Wire {
+                    src: Some(ref assn),
+                } => ProvenanceSource::Assign {
+                    net: assn.net.clone(),
+                    at: assn.at,
+                },
+                Wire { src: None } => ProvenanceSource::Wire,
+                Rail(rk) => ProvenanceSource::Rail(rk),
+                Strap { ref net } => ProvenanceSource::Strap { net: net.clone() },
+                Cut { .. } | Blockage { .. } => unreachable!(),
+            };
+            // Note any cuts and blockages on either side
+            let neighbors = [idx.checked_sub(1), Some(idx + 1)];
+            let bounds = neighbors
+                .iter()
+                .filter_map(|n| track.segments.get((*n)?))
+                .filter_map(|n| match n.tp {
+                    Cut { src } => Some(SegmentBound::Cut { at: src }),
+                    Blockage { ref src } => Some(SegmentBound::Blockage { src: src.clone() }),
+                    _ => None,
+                })
+                .collect();
+            prov.push(Provenance {
+                source,
+                layer: Some(layer.index),
+                period: Some(period),
+                bounds,
+            });
+        }
+    }
+    Ok(())
+}
+/// Render each rail and signal [Track] of [LayerPeriod] `period`, per [render_track]
+pub fn render_period(
+    period: &LayerPeriod,
+    layer: &validate::ValidMetalLayer,
+    min_segment: DbUnits,
+) -> LayoutResult<Vec<raw::Element>> {
+    let mut elems = Vec::new();
+    for track in period.rails.iter().chain(period.signals.iter()) {
+        render_track_into(track, layer, min_segment, &mut elems)?;
+    }
+    Ok(elems)
+}
+/// Boolean indication of whether `cell` has an [abs::Abstract] but no implementation
+fn abstract_only(cell: &cell::Cell) -> bool {
+    cell.abs.is_some() && cell.layout.is_none() && cell.raw.is_none()
 }
 /// # Converter from [Library] and constituent elements to [raw::Library]
 #[derive(Debug)]
@@ -85,16 +717,71 @@ pub struct RawExporter {
     lib: Library,
     /// Source (validated) [Stack]
     stack: validate::ValidStack,
+    /// Export Options
+    opts: RawExportOptions,
+    /// Conversion statistics
+    report: ConversionReport,
     /// HashMap from source [Cell] to exported [raw::Cell],
     /// largely for lookup during conversion of [Instance]s
     rawcells: HashMap<Ptr<cell::Cell>, Ptr<raw::Cell>>,
+    /// HashMap from sub-library [Cell] to its flattened name, per [RawExportOptions::lib_separator]
+    names: HashMap<Ptr<cell::Cell>, String>,
+    /// Via-cell generator, if enabled via [RawExportOptions::via_cells]
+    vias: Option<ViaGenerator>,
+    /// Abutted edges of each abutted [Instance], in the frame of its [Cell],
+    /// per [RawExportOptions::clip_abutted_rails]
+    abutted: HashMap<Ptr<Instance>, AbuttedEdges>,
+    /// Rail-clipped variants of each [Cell], keyed by their abutted edges
+    clipped: HashMap<(Ptr<cell::Cell>, AbuttedEdges), Ptr<raw::Cell>>,
+    /// Raw libraries sharing our stack's raw layers, whose cells block only the tracks they occupy
+    shared_layers: HashSet<Ptr<raw::Library>>,
+    /// Abutted edges of the [Cell] being converted, along which its rails are clipped
+    rail_clip: AbuttedEdges,
+    /// Dry-run mode, per [RawExporter::check], in which no geometry is emitted
+    dry_run: bool,
     /// Context stack, largely for error reporting
     ctx: Vec<ErrorContext>,
 }
-impl<'lib> RawExporter {
+impl RawExporter {
     /// Convert the combination of a [Library] `lib` and [Stack] `stack` to a [raw::Library].
     /// Both `lib` and `stack` are consumed in the process.
     pub fn convert(lib: Library, stack: validate::ValidStack) -> LayoutResult<Ptr<raw::Library>> {
+        let (rawlib, _report) =
+            Self::convert_with_options(lib, stack, &RawExportOptions::default())?;
+        Ok(rawlib)
+    }
+    /// Convert the combination of a [Library] `lib` and [Stack] `stack` to a [raw::Library], with [RawExportOptions] `opts`.
+    /// Returns the [raw::Library] along with a [ConversionReport].
+    pub fn convert_with_options(
+        lib: Library,
+        stack: validate::ValidStack,
+        opts: &RawExportOptions,
+    ) -> LayoutResult<(Ptr<raw::Library>, ConversionReport)> {
+        Self::run(lib, stack, opts, false)
+    }
+    /// Check the combination of a [Library] `lib` and [Stack] `stack` for conversion, with [RawExportOptions] `opts`,
+    /// without creating any of its geometry.
+    /// Performs all placement, blockage, cut, assignment, and via checks of [RawExporter::convert_with_options],
+    /// and returns the same [ConversionReport].
+    pub fn check(
+        lib: Library,
+        stack: validate::ValidStack,
+        opts: &RawExportOptions,
+    ) -> LayoutResult<ConversionReport> {
+        let (_rawlib, report) = Self::run(lib, stack, opts, true)?;
+        Ok(report)
+    }
+    /// Shared implementation of [RawExporter::convert_with_options] and [RawExporter::check]
+    fn run(
+        lib: Library,
+        stack: validate::ValidStack,
+        opts: &RawExportOptions,
+        dry_run: bool,
+    ) -> LayoutResult<(Ptr<raw::Library>, ConversionReport)> {
+        // Fail early on vacuous stacks, before placement and validation report less helpful errors
+        if stack.num_metals() == 0 {
+            return LayoutError::fail("Raw export failed: Stack has no metal layers");
+        }
         // Put the combination through absolute-placement
         use crate::placer::Placer;
         let (lib, stack) = Placer::place(lib, stack)?;
@@ -105,18 +792,29 @@ impl<'lib> RawExporter {
         let mut myself = Self {
             lib,
             stack,
+            opts: opts.clone(),
+            report: ConversionReport::default(),
             rawcells: HashMap::new(),
+            names: HashMap::new(),
+            abutted: HashMap::new(),
+            clipped: HashMap::new(),
+            shared_layers: HashSet::new(),
+            rail_clip: Vec::new(),
+            vias: (opts.via_cells.is_some() || !opts.via_variants.is_empty())
+                .then(|| ViaGenerator::new(opts.via_cells.unwrap_or_default())),
+            dry_run,
             ctx: Vec::new(),
         };
         myself.export_stack()?;
-        myself.export_lib()
+        let rawlib = myself.export_lib()?;
+        Ok((rawlib, myself.report))
     }
     /// "Convert" our [Stack]. Really just checks a few properties are valid.
     fn export_stack(&mut self) -> LayoutResult<()> {
         // Require our [Stack] specify both:
         // (a) set of [raw::Layers], and
         // (b) a boundary layer
-        // Both these fields are frequently `unwrap`ed hereafter.
+        // Both these fields are retrieved hereafter via [RawExporter::rawlayers] and [RawExporter::boundary_layer].
         if !self.stack.rawlayers.is_some() {
             return self.fail("Raw export failed: no [raw::Layers] specified");
         }
@@ -128,49 +826,218 @@ impl<'lib> RawExporter {
     /// Convert everything in our [Library]
     fn export_lib(&mut self) -> LayoutResult<Ptr<raw::Library>> {
         self.ctx.push(ErrorContext::Library(self.lib.name.clone()));
+        // Note which raw-libs share our raw layers, ahead of merging them and taking write-access
+        for rawlibptr in self.lib.rawlibs.iter() {
+            if Some(&rawlibptr.read()?.layers) == self.stack.rawlayers.as_ref() {
+                self.shared_layers.insert(rawlibptr.clone());
+            }
+        }
         // Get our starter raw-lib, either anew or from any we've imported
-        let rawlibptr = if self.lib.rawlibs.len() == 0 {
+        let external = self.opts.raw_libs == RawLibHandling::External;
+        let rawlibptr = if self.lib.rawlibs.len() == 0 || external {
             // Create a new [raw::Library]
             let mut rawlib = raw::Library::new(&self.lib.name, self.stack.units);
-            rawlib.layers = Ptr::clone(self.stack.rawlayers.as_ref().unwrap());
-            Ok(Ptr::new(rawlib))
-        } else if self.lib.rawlibs.len() == 1 {
-            // Pop the sole raw-library, and use it as a starting point
-            let rawlibptr = self.lib.rawlibs.pop().unwrap();
-            let mut rawlib = rawlibptr.write()?;
-            rawlib.name = self.lib.name.to_string();
-            if rawlib.units != self.stack.units {
-                // Check that the units match, or fail
-                return self.fail(format!(
-                    "NotImplemented: varying units between raw and tetris libraries: {:?} vs {:?}",
-                    rawlib.units, self.stack.units,
-                ));
-            }
-            drop(rawlib);
-            Ok(rawlibptr)
+            rawlib.layers = Ptr::clone(self.rawlayers()?);
+            Ptr::new(rawlib)
         } else {
-            // Multiple raw-libraries will require some merging
-            // Probably not difficult, but not done yet either.
-            self.fail("NotImplemented: with multiple [raw::Library")
-        }?;
+            // Merge the cells of each raw-library into a new one, leaving the originals unmodified.
+            // Each must share our units, and the layers of the first.
+            let mut rawlib = raw::Library::new(&self.lib.name, self.stack.units);
+            let mut sources: HashMap<String, String> = HashMap::new();
+            for (idx, otherptr) in self.lib.rawlibs.iter().enumerate() {
+                let other = otherptr.read()?;
+                if other.units != self.stack.units {
+                    return self.fail(format!(
+                        "NotImplemented: varying units between raw and tetris libraries: {:?} vs {:?}",
+                        other.units, self.stack.units,
+                    ));
+                }
+                if idx == 0 {
+                    rawlib.layers = Ptr::clone(&other.layers);
+                } else if other.layers != rawlib.layers {
+                    return self.fail(format!(
+                        "NotImplemented: merging raw library {} with varying layers",
+                        other.name,
+                    ));
+                }
+                for cellptr in other.cells.iter() {
+                    let name = cellptr.read()?.name.clone();
+                    if let Some(prior) = sources.insert(name.clone(), other.name.clone()) {
+                        return self.fail(format!(
+                            "Duplicate Cell {} in raw libraries {} and {}",
+                            name, prior, other.name
+                        ));
+                    }
+                    rawlib.cells.push(Ptr::clone(cellptr));
+                }
+            }
+            Ptr::new(rawlib)
+        };
         {
+            // Flatten the names of sub-library cells, prefixing them with their sub-library path
+            for (path, srcptr) in self.lib.qualified_cells()? {
+                if !path.is_empty() {
+                    let sep = &self.opts.lib_separator;
+                    let name = format!("{}{}{}", path.join(sep), sep, srcptr.read()?.name);
+                    self.names.insert(srcptr, name);
+                }
+            }
+            if self.opts.clip_abutted_rails {
+                self.collect_abutments()?;
+            }
+            self.collect_abstract_only()?;
             // Get write-access to the raw-lib
             let mut rawlib = rawlibptr.write()?;
             // Convert each defined [Cell] to a [raw::Cell]
-            for srcptr in self.lib.dep_order() {
-                let rawptr = self.export_cell(&*srcptr.read()?, &mut rawlib.cells)?;
+            let order = self.lib.dep_order()?;
+            let total = order.len();
+            for (idx, srcptr) in order.into_iter().enumerate() {
+                let cell = srcptr.read()?;
+                let name = self.names.get(&srcptr).unwrap_or(&cell.name).clone();
+                self.check_cancelled()?;
+                self.opts.progress.on_cell_start(&name, idx, total);
+                let rawptr = self.export_cell(&cell, &name, &mut rawlib.cells)?;
+                drop(cell);
+                let nelems = rawptr.read()?.layout.as_ref().map_or(0, |l| l.elems.len());
+                self.opts.progress.on_cell_done(&name, nelems);
                 self.rawcells.insert(srcptr.clone(), rawptr);
+                if self.opts.clip_abutted_rails {
+                    self.export_clipped_cells(&srcptr, &name, &mut rawlib.cells)?;
+                }
+            }
+            // Add any generated via-cells, ahead of the cells which instantiate them
+            if let Some(ref vias) = self.vias {
+                let cells = vias.cells();
+                for cell in cells.iter() {
+                    let name = &cell.read()?.name;
+                    if rawlib.cell_named(name)?.is_some() {
+                        return self.fail(format!(
+                            "Generated via Cell {} conflicts with an existing Cell of the same name",
+                            name
+                        ));
+                    }
+                }
+                rawlib.cells.splice(0..0, cells);
+            }
+            if self.opts.unit_cells {
+                self.export_unit_cells(&mut rawlib.cells)?;
             }
         } // Ends `rawlib` write-access scope
         self.ctx.pop();
         Ok(rawlibptr)
     }
-    /// Convert a [Cell] to a [raw::Cell] and add to `rawcells`.
+    /// Fail with [LayoutError::Cancelled] if cancellation has been requested via [RawExportOptions::cancel]
+    fn check_cancelled(&self) -> LayoutResult<()> {
+        match self.opts.cancel {
+            Some(ref cancel) if cancel.is_cancelled() => Err(LayoutError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+    /// Add a unit cell per metal layer to `rawcells`, and record each in our [ConversionReport].
+    /// Each comprises one period of the layer's signal and rail tracks, one primitive-pitch long.
+    /// Names already taken by other cells are uniquified with a numeric suffix.
+    fn export_unit_cells(&mut self, rawcells: &mut PtrList<raw::Cell>) -> LayoutResult<()> {
+        let mut taken = HashSet::new();
+        for cell in rawcells.iter() {
+            taken.insert(cell.read()?.name.clone());
+        }
+        for index in 0..self.stack.pitches.len() {
+            let layer = self.stack.metal(LayerIndex(index))?;
+            if layer.raw.is_none() {
+                return self.fail(format!(
+                    "Cannot create unit cell for layer {} with no raw layer",
+                    layer.spec.name
+                ));
+            }
+            let span = self.stack.prim.pitches[layer.spec.dir];
+            // Render the tracks of the layer's first period, across its full (overlapping) extent
+            let start = layer.spec.offset_units()?;
+            let stop = start + layer.pitch + layer.spec.overlap;
+            let mut elems = Vec::new();
+            for data in layer.tracks_in(start.raw()..stop.raw())? {
+                let tp = match data.ttype {
+                    TrackType::Rail(kind) => TrackSegmentType::Rail(kind),
+                    _ => TrackSegmentType::Wire { src: None },
+                };
+                let track = Track {
+                    data,
+                    segments: vec![TrackSegment {
+                        tp,
+                        start: DbUnits(0),
+                        stop: span,
+                        labels: Vec::new(),
+                    }],
+                }
+                .validate()?;
+                self.export_track(&track, layer, 0, &mut elems, None)?;
+            }
+            let base = unit_cell_name(&layer.spec);
+            let mut name = base.clone();
+            let mut num = 0;
+            while taken.contains(&name) {
+                num += 1;
+                name = format!("{}_{}", base, num);
+            }
+            taken.insert(name.clone());
+            let mut cell = raw::Cell::new(&name);
+            cell.layout = Some(raw::Layout {
+                name: name.clone(),
+                elems,
+                ..Default::default()
+            });
+            rawcells.add(cell);
+            self.report.unit_cells.cells.insert(index, name);
+        }
+        Ok(())
+    }
+    /// Collect the abstract-only cells instantiated in our [Library] into our [ConversionReport],
+    /// and apply our [AbstractHandling] policy to them.
+    fn collect_abstract_only(&mut self) -> LayoutResult<()> {
+        for srcptr in self.lib.dep_order()? {
+            let cell = srcptr.read()?;
+            let layout = match cell.layout {
+                Some(ref layout) => layout,
+                None => continue,
+            };
+            for ptr in layout.instances.iter() {
+                let inst = ptr.read()?;
+                if !abstract_only(&*inst.cell.read()?) {
+                    continue;
+                }
+                let name = match self.names.get(&inst.cell) {
+                    Some(name) => name.clone(),
+                    None => inst.cell.read()?.name.clone(),
+                };
+                if self.opts.abstracts == AbstractHandling::Omit {
+                    let msg = format!(
+                        "Omitting Instance {} of abstract-only Cell {} from Cell {}",
+                        inst.inst_name, name, cell.name
+                    );
+                    eprintln!("Warning: {}", msg);
+                    self.report.warnings.push(msg);
+                }
+                if !self.report.abstract_only.contains(&name) {
+                    self.report.abstract_only.push(name);
+                }
+            }
+        }
+        if self.opts.abstracts == AbstractHandling::RequireImpl
+            && !self.report.abstract_only.is_empty()
+        {
+            return self.fail(format!(
+                "Instances of abstract-only Cells require implementations: {}",
+                self.report.abstract_only.join(", ")
+            ));
+        }
+        Ok(())
+    }
+    /// Convert a [Cell] to a [raw::Cell] named `name`, and add to `rawcells`.
     /// FIXME: In reality only one of the cell-views is converted,
     /// generally the "most specific" available view.
     fn export_cell(
         &mut self,
         cell: &cell::Cell,
+        name: &str,
         rawcells: &mut PtrList<raw::Cell>,
     ) -> LayoutResult<Ptr<raw::Cell>> {
         if let Some(ref x) = cell.raw {
@@ -195,19 +1062,97 @@ impl<'lib> RawExporter {
         }
 
         // Create the raw-cell
-        let mut rawcell = raw::Cell::new(&cell.name.to_string());
+        let mut rawcell = raw::Cell::new(name);
+        rawcell.props = cell.props.clone();
         // And create each defined view
+        // Views share the (potentially flattened) cell name
         if let Some(ref x) = cell.layout {
-            rawcell.layout = Some(self.export_layout_impl(x)?);
+            let mut rawlayout = self.export_layout_impl(x)?;
+            rawlayout.name = name.into();
+            rawcell.layout = Some(rawlayout);
         }
         if let Some(ref x) = cell.abs {
-            rawcell.abs = Some(self.export_abstract(x)?);
+            let mut rawabs = self.export_abstract(x)?;
+            rawabs.name = name.into();
+            rawcell.abs = Some(rawabs);
         }
         // Add it to `rawcells`, and return the pointer that comes back
         Ok(rawcells.add(rawcell))
     }
     /// Convert to a raw layout cell
-    fn export_layout_impl(&self, layout: &Layout) -> LayoutResult<raw::Layout> {
+    fn export_layout_impl(&mut self, layout: &Layout) -> LayoutResult<raw::Layout> {
+        let mut report = ConversionReport::default();
+        let mut vias = Vec::new();
+        let mut prov = self.opts.provenance.then(Vec::new);
+        let mut rawlayout =
+            self.export_layout_impl_inner(layout, &mut report, &mut vias, prov.as_mut())?;
+        if layout.metals == 0 && self.opts.mode == ConversionMode::Full {
+            self.report.warnings.push(format!(
+                "Cell {} has no metal layers; only its boundary is exported",
+                layout.name
+            ));
+        }
+        self.report.periods += report.periods;
+        self.report.period_cache_hits += report.period_cache_hits;
+        self.report.routes.extend(report.routes);
+        self.report.slivers.extend(report.slivers);
+        self.report.warnings.extend(report.warnings);
+        for unroutable in report.unroutable.iter() {
+            self.report.warnings.push(format!(
+                "Skipping unroutable assignment of net {} at {:?} in Cell {}, blocked by {}",
+                unroutable.net, unroutable.at, unroutable.cell, unroutable.blocker
+            ));
+        }
+        self.report.unroutable.extend(report.unroutable);
+        if let Some(elems) = prov {
+            self.report.provenance.push(CellProvenance {
+                cell: layout.name.clone(),
+                elems,
+            });
+        }
+        // Instantiate any vias from their generated cells,
+        // named `via{num}`, skipping any names taken by the cell's own instances
+        if let Some(ref mut generator) = self.vias {
+            let taken: HashSet<String> = rawlayout
+                .insts
+                .iter()
+                .map(|inst| inst.inst_name.clone())
+                .collect();
+            let mut num = 0;
+            for via in vias.iter() {
+                let via_layer = self.stack.via_from(LayerIndex(via.layer))?;
+                let cell = generator.cell_with(&self.stack, via_layer, &via.spec)?;
+                let inst_name = loop {
+                    let name = format!("via{}", num);
+                    num += 1;
+                    if !taken.contains(&name) {
+                        break name;
+                    }
+                };
+                rawlayout.insts.push(raw::Instance {
+                    inst_name,
+                    cell,
+                    loc: raw::Point::new(via.loc.x.0, via.loc.y.0),
+                    reflect_vert: false,
+                    angle: None,
+                });
+            }
+        }
+        Ok(rawlayout)
+    }
+    /// Internal implementation of [RawExporter::export_layout_impl], collecting statistics into `report`.
+    /// Vias instantiated from generated cells are collected into `vias` rather than drawn.
+    /// If `prov` is provided, the [Provenance] of each element is collected into it.
+    fn export_layout_impl_inner(
+        &self,
+        layout: &Layout,
+        report: &mut ConversionReport,
+        vias: &mut Vec<ViaCellPlacement>,
+        mut prov: Option<&mut Vec<Provenance>>,
+    ) -> LayoutResult<raw::Layout> {
+        if self.opts.mode == ConversionMode::FloorplanOnly {
+            return self.export_floorplan(layout, prov);
+        }
         if layout.outline.x.len() > 1 {
             return Err(LayoutError::Str(
                 "Non-rectangular outline; conversions not supported (yet)".into(),
@@ -215,37 +1160,677 @@ impl<'lib> RawExporter {
         };
         let mut elems: Vec<raw::Element> = Vec::new();
         // Re-organize the cell into the format most helpful here
-        let temp_cell = self.temp_cell(layout)?;
+        let mut temp_cell = self.temp_cell(layout)?;
+        self.drop_unroutable(&mut temp_cell, report)?;
+        // Post-route database, if enabled
+        let mut routes = if self.opts.route_db {
+            Some(CellRouteDb::new(&layout.name))
+        } else {
+            None
+        };
+        // Cells with no metal layers get a boundary, so they are not entirely empty
+        if layout.metals == 0 && !self.dry_run {
+            elems.push(raw::Element {
+                net: None,
+                layer: self.boundary_layer()?,
+                purpose: raw::LayerPurpose::Outline,
+                inner: raw::Shape::Polygon(self.outline_shape(&layout.outline)?),
+                labels: Vec::new(),
+            });
+            annotate(prov.as_deref_mut(), &elems, |_| {
+                Provenance::new(ProvenanceSource::Outline)
+            });
+        }
         // Convert a layer at a time, starting from bottom
         for layernum in 0..layout.metals {
+            let metal = self.stack.metal(LayerIndex(layernum))?;
+            self.check_cancelled()?;
+            self.opts.progress.on_layer(&metal.spec.name);
             // Organize the cell/layer combo into temporary conversion format
-            let temp_layer = self.temp_cell_layer(&temp_cell, self.stack.metal(layernum)?)?;
+            let temp_layer = self.temp_cell_layer(&temp_cell, metal)?;
+            // Blocked layer-periods, shared between periods with identical blockages
+            let mut cache = PeriodCache::new();
+            // Final layer-periods are retained for the route database, and for checking any placed vias
+            let layer_vias: Vec<&validate::ValidVia> = temp_cell
+                .vias
+                .iter()
+                .filter(|v| v.bot == layernum || v.top == layernum)
+                .collect();
+            let retain = routes.is_some() || !layer_vias.is_empty();
+            let mut layer_routes = retain.then(|| LayerRoutes::new(layernum));
             // Convert each "layer period" one at a time
             for periodnum in 0..temp_layer.nperiods {
+                self.check_cancelled()?;
                 // Again, re-organize into the relevant objects for this "layer period"
                 let temp_period = self.temp_cell_layer_period(&temp_layer, periodnum)?;
                 // And finally start doing stuff!
-                elems.extend(self.export_cell_layer_period(&temp_period)?);
+                elems.extend(self.export_cell_layer_period(
+                    &temp_period,
+                    &mut cache,
+                    report,
+                    vias,
+                    layer_routes.as_mut(),
+                    prov.as_deref_mut(),
+                )?);
+            }
+            // Draw any jogs on this layer
+            if !self.dry_run {
+                for jog in layout.jogs.iter().filter(|j| j.layer == layernum) {
+                    elems.push(self.export_jog(jog)?);
+                    annotate(prov.as_deref_mut(), &elems, |_| Provenance {
+                        layer: Some(layernum),
+                        ..Provenance::new(ProvenanceSource::Jog {
+                            net: jog.net.clone(),
+                        })
+                    });
+                }
+            }
+            // Check each placed via lands on this layer's metal, and draw those for which it is the top layer
+            for via in layer_vias {
+                let periods = &self
+                    .unwrap(layer_routes.as_ref(), "Internal error: no routes for layer")?
+                    .periods;
+                self.check_via(via, metal, periods)?;
+                if via.top != layernum || self.dry_run {
+                    continue;
+                }
+                if let Some(spec) = self.opts.via_cells {
+                    vias.push(ViaCellPlacement {
+                        layer: via.bot,
+                        loc: via.loc,
+                        spec,
+                    });
+                } else {
+                    let via_layer = self.stack.via(via.index)?;
+                    elems.push(self.export_via(via_layer, via.loc, via.src.net.clone())?);
+                    annotate(prov.as_deref_mut(), &elems, |_| Provenance {
+                        layer: Some(via.bot),
+                        ..Provenance::new(ProvenanceSource::Via {
+                            net: via.src.net.clone(),
+                            assign: None,
+                        })
+                    });
+                }
+            }
+            if let (Some(routes), Some(layer_routes)) = (routes.as_mut(), layer_routes) {
+                routes.layers.push(layer_routes);
+            }
+        }
+        if let Some(routes) = routes {
+            report.routes.push(routes);
+        }
+        // Close the boundary rails into a ring, if requested
+        if layout.rail_rings {
+            elems.extend(self.export_rail_ring(layout, vias)?);
+            annotate(prov.as_deref_mut(), &elems, |_| {
+                Provenance::new(ProvenanceSource::RailRing)
+            });
+        }
+        // Draw any [Keepout] markers
+        if let (Some(datatype), false) = (self.opts.keepout_marker, self.dry_run) {
+            for keepout in layout.keepouts.iter() {
+                elems.extend(self.export_keepout_marker(keepout, datatype)?);
+                annotate(prov.as_deref_mut(), &elems, |_| {
+                    Provenance::new(ProvenanceSource::Keepout {
+                        name: keepout.name.clone(),
+                    })
+                });
             }
         }
 
-        // Convert our [Instance]s
-        let insts = layout
-            .instances
-            .iter()
-            .map(|ptr| {
-                let inst = ptr.read()?;
-                self.export_instance(&*inst)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
         // Aaaand create our new [raw::Cell]
+        let mut annotations = Vec::new();
+        let insts = self.export_instances(layout, &mut elems, &mut annotations, prov)?;
+        Ok(raw::Layout {
+            name: layout.name.clone(),
+            insts,
+            elems,
+            annotations,
+        })
+    }
+    /// Close the boundary rails of `layout` into a ring, per [Layout::rail_rings].
+    ///
+    /// Rings are formed on the cell's top two metal layers.
+    /// The lower of the two with rails along both its boundary edges keeps them, each drawn as a strap
+    /// extending past the cell's corners, and the other adds a strap along each of the remaining two edges.
+    /// Clusters of vias connect the straps at each corner.
+    /// Fails if the boundary rails carry different nets, or if any instance reaching either layer overlaps the ring.
+    fn export_rail_ring(
+        &self,
+        layout: &Layout,
+        vias: &mut Vec<ViaCellPlacement>,
+    ) -> LayoutResult<Vec<raw::Element>> {
+        if layout.metals < 2 {
+            return self.fail(format!(
+                "Rail ring of Cell {} requires at least two metal layers",
+                layout.name
+            ));
+        }
+        let lower = self.stack.metal(LayerIndex(layout.metals - 2))?;
+        let upper = self.stack.metal(LayerIndex(layout.metals - 1))?;
+        let (rail_layer, strap_layer, (lo, hi)) = match self.boundary_rails(layout, lower)? {
+            Some(rails) => (lower, upper, rails),
+            None => match self.boundary_rails(layout, upper)? {
+                Some(rails) => (upper, lower, rails),
+                None => {
+                    return self.fail(format!(
+                        "Cell {} has no boundary rails on layers {} or {} to form a ring",
+                        layout.name, lower.spec.name, upper.spec.name
+                    ))
+                }
+            },
+        };
+        let rail_net = |t: &Track| match t.data.ttype {
+            TrackType::Rail(rk) => rk.to_string(),
+            _ => unreachable!(),
+        };
+        let (net, hinet) = (rail_net(&lo), rail_net(&hi));
+        if net != hinet {
+            return self.fail(format!(
+                "Cannot form a rail ring in Cell {}: its boundary rails on layer {} carry different nets {} and {}",
+                layout.name, rail_layer.spec.name, net, hinet
+            ));
+        }
+        // Sort out the ring's rectangles, as (layer, along, across) spans in the rail layer's frame
+        let dir = rail_layer.spec.dir;
+        let len = self.db_units(layout.outline.max(dir))?;
+        let half = lo.data.width / 2;
+        let along = (DbUnits(0) - half, len + half);
+        let span = |t: &Track| (t.data.start, t.data.start + t.data.width);
+        let (outer0, outer1) = (lo.data.start, hi.data.start + hi.data.width);
+        let sides = [
+            (rail_layer, along, span(&lo)),
+            (rail_layer, along, span(&hi)),
+            (strap_layer, (along.0, half), (outer0, outer1)),
+            (strap_layer, (len - half, along.1), (outer0, outer1)),
+        ];
+        let rect = |along: (DbUnits, DbUnits), across: (DbUnits, DbUnits)| match dir {
+            Dir::Horiz => raw::Rect {
+                p0: self.export_point(along.0, across.0),
+                p1: self.export_point(along.1, across.1),
+            },
+            Dir::Vert => raw::Rect {
+                p0: self.export_point(across.0, along.0),
+                p1: self.export_point(across.1, along.1),
+            },
+        };
+        // Check no instance on either layer lands in the ring's path
+        for ptr in layout.instances.iter() {
+            let inst = ptr.read()?;
+            let metals = inst.cell.read()?.metals()?;
+            let bbox = inst.boundbox()?;
+            let (p0, p1) = (self.export_xy(&bbox.p0)?, self.export_xy(&bbox.p1)?);
+            for (layer, along, across) in sides.iter() {
+                let r = rect(*along, *across);
+                let overlaps = p0.x < r.p1.x && r.p0.x < p1.x && p0.y < r.p1.y && r.p0.y < p1.y;
+                if metals > layer.index && overlaps {
+                    return self.fail(format!(
+                        "Instance {} blocks the rail ring of Cell {} on layer {}",
+                        inst.inst_name, layout.name, layer.spec.name
+                    ));
+                }
+            }
+        }
+        if self.dry_run {
+            return Ok(Vec::new());
+        }
+        let mut elems = Vec::with_capacity(sides.len());
+        for (layer, along, across) in sides.iter() {
+            let rawlayer = self.unwrap(
+                layer.raw,
+                format!("Metal layer {} has no raw layer", layer.spec.name),
+            )?;
+            elems.push(raw::Element {
+                net: Some(net.clone()),
+                layer: rawlayer,
+                purpose: raw::LayerPurpose::Drawing,
+                inner: raw::Shape::Rect(rect(*along, *across)),
+                labels: Vec::new(),
+            });
+        }
+        // Add a cluster of vias in each corner, as many as fit at a pitch of twice their size
+        let bot = rail_layer.index.min(strap_layer.index);
+        let via_layer = self.stack.via_from(LayerIndex(bot))?;
+        let cluster = |lo: DbUnits, hi: DbUnits, size: DbUnits| -> Vec<DbUnits> {
+            let n: Int = ((hi - lo - size) / (size + size) + 1).max(1);
+            let mid = (lo + hi) / 2;
+            (0..n).map(|k| mid + size * (2 * k - (n - 1))).collect()
+        };
+        let (along_size, across_size) = match dir {
+            Dir::Horiz => (via_layer.size.x, via_layer.size.y),
+            Dir::Vert => (via_layer.size.y, via_layer.size.x),
+        };
+        for corner in [DbUnits(0), len] {
+            for rail in [&lo, &hi] {
+                let (c0, c1) = span(rail);
+                for a in cluster(corner - half, corner + half, along_size) {
+                    for c in cluster(c0, c1, across_size) {
+                        let loc = match dir {
+                            Dir::Horiz => Xy::new(a, c),
+                            Dir::Vert => Xy::new(c, a),
+                        };
+                        if let Some(spec) = self.opts.via_cells {
+                            vias.push(ViaCellPlacement {
+                                layer: bot,
+                                loc,
+                                spec,
+                            });
+                        } else {
+                            elems.push(self.export_via(via_layer, loc, Some(net.clone()))?);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(elems)
+    }
+    /// Get the rails of `layer` spanning both of the boundary edges of `layout` parallel to it, if it has both
+    fn boundary_rails(
+        &self,
+        layout: &Layout,
+        layer: &validate::ValidMetalLayer,
+    ) -> LayoutResult<Option<(Track, Track)>> {
+        let dir = layer.spec.dir;
+        let span = self.db_units(layout.outline.max(dir))?;
+        let breadth = self.db_units(layout.outline.max(!dir))?;
+        let nperiods = usize::try_from(breadth / layer.pitch)?;
+        if nperiods == 0 {
+            return Ok(None);
+        }
+        let find = |index: usize, edge: DbUnits| -> LayoutResult<Option<Track>> {
+            let period = layer.spec.to_layer_period(index, span)?;
+            Ok(period
+                .rails
+                .into_iter()
+                .find(|t| t.data.start < edge && edge < t.data.start + t.data.width))
+        };
+        match (find(0, DbUnits(0))?, find(nperiods - 1, breadth)?) {
+            (Some(lo), Some(hi)) => Ok(Some((lo, hi))),
+            _ => Ok(None),
+        }
+    }
+    /// Convert [Layout] `layout` to its floorplan view, per [ConversionMode::FloorplanOnly]:
+    /// its outline on the boundary layer, and its instances.
+    fn export_floorplan(
+        &self,
+        layout: &Layout,
+        mut prov: Option<&mut Vec<Provenance>>,
+    ) -> LayoutResult<raw::Layout> {
+        let mut elems = Vec::new();
+        if !self.dry_run {
+            elems.push(raw::Element {
+                net: None,
+                layer: self.boundary_layer()?,
+                purpose: raw::LayerPurpose::Outline,
+                inner: raw::Shape::Polygon(self.export_outline(&layout.outline)?),
+                labels: Vec::new(),
+            });
+            annotate(prov.as_deref_mut(), &elems, |_| {
+                Provenance::new(ProvenanceSource::Outline)
+            });
+        }
+        let mut annotations = Vec::new();
+        let insts = self.export_instances(layout, &mut elems, &mut annotations, prov)?;
         Ok(raw::Layout {
             name: layout.name.clone(),
             insts,
             elems,
-            ..Default::default()
+            annotations,
         })
     }
+    /// Convert the [Instance]s of `layout`, omitting those of abstract-only cells if so configured.
+    /// Any [RawExportOptions::instance_markers] are added to `elems`, their name annotations to `annotations`,
+    /// and their [Provenance] to any `prov`.
+    fn export_instances(
+        &self,
+        layout: &Layout,
+        elems: &mut Vec<raw::Element>,
+        annotations: &mut Vec<raw::TextElement>,
+        mut prov: Option<&mut Vec<Provenance>>,
+    ) -> LayoutResult<Vec<raw::Instance>> {
+        let mut insts = Vec::with_capacity(layout.instances.len());
+        for ptr in layout.instances.iter() {
+            let inst = ptr.read()?;
+            if self.opts.abstracts == AbstractHandling::Omit && abstract_only(&*inst.cell.read()?) {
+                continue;
+            }
+            let mut rawinst = self.export_instance(&inst)?;
+            if let Some(edges) = self.abutted.get(ptr) {
+                if let Some(clipped) = self.clipped.get(&(inst.cell.clone(), edges.clone())) {
+                    rawinst.cell = clipped.clone();
+                }
+            }
+            insts.push(rawinst);
+        }
+        if let (Some((layernum, datatype)), false) = (self.opts.instance_markers, self.dry_run) {
+            for inst in insts.iter() {
+                let (marker, annotation) = self.export_instance_marker(inst, layernum, datatype)?;
+                elems.push(marker);
+                annotations.push(annotation);
+                annotate(prov.as_deref_mut(), elems, |_| {
+                    Provenance::new(ProvenanceSource::Instance {
+                        name: inst.inst_name.clone(),
+                    })
+                });
+            }
+        }
+        Ok(insts)
+    }
+    /// Create the debug marker for `inst` on layer `layernum` and datatype `datatype`, per [RawExportOptions::instance_markers],
+    /// along with its instance-name annotation
+    fn export_instance_marker(
+        &self,
+        inst: &raw::Instance,
+        layernum: i16,
+        datatype: i16,
+    ) -> LayoutResult<(raw::Element, raw::TextElement)> {
+        let (layer, purpose) = {
+            let rawlayers = self.rawlayers()?.read()?;
+            let layer = self.unwrap(
+                rawlayers.keynum(layernum),
+                format!("Instance marker layer {} not defined", layernum),
+            )?;
+            let purpose = rawlayers
+                .get(layer)
+                .and_then(|l| l.purpose(datatype))
+                .cloned();
+            let purpose = self.unwrap(
+                purpose,
+                format!(
+                    "Instance marker datatype {} not defined on layer {}",
+                    datatype, layernum
+                ),
+            )?;
+            (layer, purpose)
+        };
+        // An "L" along the instance's x and y axes, with unequal legs
+        let len = self
+            .stack
+            .prim
+            .pitches
+            .x
+            .raw()
+            .min(self.stack.prim.pitches.y.raw());
+        let thick = len / 4;
+        let local = [
+            (0, 0),
+            (len, 0),
+            (len, thick),
+            (thick, thick),
+            (thick, 2 * len),
+            (0, 2 * len),
+        ];
+        let trans = inst.transform()?;
+        let points = local
+            .iter()
+            .map(|(x, y)| raw::Point::new(*x, *y).transform(&trans))
+            .collect();
+        // Reflections reverse the winding, so put the polygon back into canonical form
+        let mut inner = raw::Shape::Polygon(raw::Polygon { points });
+        inner.normalize();
+        let marker = raw::Element {
+            net: None,
+            layer,
+            purpose,
+            inner,
+            labels: Vec::new(),
+        };
+        let annotation = raw::TextElement {
+            string: inst.inst_name.clone(),
+            loc: inst.loc,
+        };
+        Ok((marker, annotation))
+    }
+    /// Create the marker rectangles for [Keepout] `keepout` on datatype `datatype`, one per metal layer
+    fn export_keepout_marker(
+        &self,
+        keepout: &Keepout,
+        datatype: i16,
+    ) -> LayoutResult<Vec<raw::Element>> {
+        let p0 = Xy::new(
+            PrimPitches::try_new(Dir::Horiz, keepout.x.start)?,
+            PrimPitches::try_new(Dir::Vert, keepout.y.start)?,
+        );
+        let p1 = Xy::new(
+            PrimPitches::try_new(Dir::Horiz, keepout.x.end)?,
+            PrimPitches::try_new(Dir::Vert, keepout.y.end)?,
+        );
+        let rawlayers = self.rawlayers()?.read()?;
+        let mut elems = Vec::new();
+        for layernum in keepout.layers.clone() {
+            let metal = self.stack.metal(LayerIndex(layernum))?;
+            let layerkey = self.unwrap(
+                metal.raw,
+                format!("Metal layer {} has no raw layer", layernum),
+            )?;
+            let purpose = rawlayers
+                .get(layerkey)
+                .and_then(|layer| layer.purpose(datatype))
+                .cloned();
+            let purpose = self.unwrap(
+                purpose,
+                format!(
+                    "Keepout {} marker datatype {} not defined on layer {}",
+                    keepout.name, datatype, metal.spec.name
+                ),
+            )?;
+            elems.push(raw::Element {
+                net: None,
+                layer: layerkey,
+                purpose,
+                inner: raw::Shape::Rect(raw::Rect {
+                    p0: self.export_xy(&p0)?,
+                    p1: self.export_xy(&p1)?,
+                }),
+                labels: Vec::new(),
+            });
+        }
+        Ok(elems)
+    }
+    /// Collect the abutted edges of each [Instance] anywhere in our [Library], into `abutted`.
+    /// Edges are recorded in the frame of the instantiated [Cell], i.e. accounting for reflection,
+    /// and in the order of [ABUTTED_EDGES].
+    fn collect_abutments(&mut self) -> LayoutResult<()> {
+        for srcptr in self.lib.dep_order()? {
+            let cell = srcptr.read()?;
+            let layout = match cell.layout {
+                Some(ref layout) => layout,
+                None => continue,
+            };
+            for abutment in layout.abutments()? {
+                let sides = [
+                    (&abutment.insts.0, Side::TopOrRight),
+                    (&abutment.insts.1, Side::BottomOrLeft),
+                ];
+                for (name, side) in sides {
+                    let ptr = self.unwrap(
+                        layout.instances.iter().find(|p| match p.read() {
+                            Ok(inst) => inst.inst_name == *name,
+                            Err(_) => false,
+                        }),
+                        format!("Internal Error: abutted instance {} not found", name),
+                    )?;
+                    let inst = ptr.read()?;
+                    let reflected = match abutment.dir {
+                        Dir::Horiz => inst.reflect_horiz,
+                        Dir::Vert => inst.reflect_vert,
+                    };
+                    let side = if reflected { side.opposite() } else { side };
+                    let edges = self.abutted.entry(ptr.clone()).or_default();
+                    if !edges.contains(&(abutment.dir, side)) {
+                        edges.push((abutment.dir, side));
+                        edges.sort_by_key(|e| ABUTTED_EDGES.iter().position(|x| x == e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Export a rail-clipped variant of [Cell] `srcptr`, exported as `name`, to `rawcells`,
+    /// for each distinct set of abutted edges among its instances.
+    /// Variants are named per [clipped_cell_name], suffixed `_1`, `_2`, etc. if already taken.
+    fn export_clipped_cells(
+        &mut self,
+        srcptr: &Ptr<cell::Cell>,
+        name: &str,
+        rawcells: &mut PtrList<raw::Cell>,
+    ) -> LayoutResult<()> {
+        let mut edgesets: Vec<AbuttedEdges> = Vec::new();
+        for (instptr, edges) in self.abutted.iter() {
+            if instptr.read()?.cell == *srcptr && !edgesets.contains(edges) {
+                edgesets.push(edges.clone());
+            }
+        }
+        edgesets.sort_by_key(|edges| {
+            edges
+                .iter()
+                .map(|e| ABUTTED_EDGES.iter().position(|x| x == e))
+                .collect::<Vec<_>>()
+        });
+        // Avoid the names of both exported cells, and those yet to be exported
+        let mut taken: HashSet<String> = self.names.values().cloned().collect();
+        for cell in rawcells.iter() {
+            taken.insert(cell.read()?.name.clone());
+        }
+        for cellptr in self.lib.cells.iter() {
+            taken.insert(cellptr.read()?.name.clone());
+        }
+        for edges in edgesets {
+            let base = clipped_cell_name(name, &edges);
+            let mut clipname = base.clone();
+            let mut num = 0;
+            while taken.contains(&clipname) {
+                num += 1;
+                clipname = format!("{}_{}", base, num);
+            }
+            taken.insert(clipname.clone());
+            self.rail_clip = edges.clone();
+            let cell = srcptr.read()?;
+            let result = self.export_cell(&cell, &clipname, rawcells);
+            self.rail_clip = Vec::new();
+            self.clipped.insert((srcptr.clone(), edges), result?);
+        }
+        Ok(())
+    }
+    /// Clip rail-elements `elems`, on a layer running in direction `dir`,
+    /// to `outline` along each of the abutted edges in `rail_clip`.
+    /// Rails clipped down to zero area are removed, along with their entries in any parallel `prov`.
+    fn clip_rails(
+        &self,
+        elems: &mut Vec<raw::Element>,
+        prov: Option<&mut Vec<Provenance>>,
+        dir: Dir,
+        outline: &Outline,
+    ) -> LayoutResult<()> {
+        for (edge_dir, side) in self.rail_clip.iter() {
+            if *edge_dir == dir {
+                continue; // Rails only straddle edges parallel to them
+            }
+            let max = self.db_units(outline.max(*edge_dir))?.raw();
+            for elem in elems.iter_mut() {
+                if let raw::Shape::Rect(ref mut rect) = elem.inner {
+                    let (lo, hi) = match edge_dir {
+                        Dir::Horiz => (&mut rect.p0.x, &mut rect.p1.x),
+                        Dir::Vert => (&mut rect.p0.y, &mut rect.p1.y),
+                    };
+                    match side {
+                        Side::BottomOrLeft => *lo = (*lo).max(0).min(*hi),
+                        Side::TopOrRight => *hi = (*hi).min(max).max(*lo),
+                    }
+                }
+            }
+        }
+        if let Some(prov) = prov {
+            let mut kept = elems.iter().map(|e| !e.inner.is_degenerate());
+            prov.retain(|_| kept.next().unwrap_or(true));
+        }
+        elems.retain(|e| !e.inner.is_degenerate());
+        Ok(())
+    }
+    /// Create the cut rectangle of a via on `via_layer`, centered at `loc`, labeled with `net`
+    fn export_via(
+        &self,
+        via_layer: &ViaLayer,
+        loc: Xy<DbUnits>,
+        net: Option<String>,
+    ) -> LayoutResult<raw::Element> {
+        let layer = match via_layer.raw {
+            Some(k) => k,
+            None => {
+                return self.fail(format!(
+                    "Cannot create via at {:?}: via layer {} has no raw layer",
+                    loc, via_layer.name
+                ))
+            }
+        };
+        Ok(raw::Element {
+            net,
+            layer,
+            purpose: raw::LayerPurpose::Drawing,
+            inner: raw::Shape::Rect(raw::Rect {
+                p0: self.export_point(loc.x - via_layer.size.x / 2, loc.y - via_layer.size.y / 2),
+                p1: self.export_point(loc.x + via_layer.size.x / 2, loc.y + via_layer.size.y / 2),
+            }),
+            labels: Vec::new(),
+        })
+    }
+    /// Check that placed via `via` lands on metal of `layer`, per its final layer-periods `periods`.
+    /// Fails if it lands between tracks, on a cut or blockage, or on metal of a net other than its own.
+    fn check_via(
+        &self,
+        via: &validate::ValidVia,
+        layer: &validate::ValidMetalLayer,
+        periods: &[LayerPeriod],
+    ) -> LayoutResult<()> {
+        let dir = layer.spec.dir;
+        let (off, along) = (via.loc[!dir], via.loc[dir]);
+        let track = periods
+            .iter()
+            .flat_map(|p| p.rails.iter().chain(p.signals.iter()))
+            .find(|t| t.data.start <= off && off < t.data.start + t.data.width);
+        let track = match track {
+            Some(t) => t,
+            None => {
+                return self.fail(format!(
+                    "Via {:?} at {:?} lands on no track of layer {}",
+                    via.src, via.loc, layer.spec.name
+                ))
+            }
+        };
+        let seg = match track.segment_at(along) {
+            Some(seg) => seg,
+            None => {
+                return self.fail(format!(
+                    "Via {:?} at {:?} lies beyond the end of {:?} track {} on layer {}",
+                    via.src, via.loc, track.data.ttype, track.data.index, layer.spec.name
+                ))
+            }
+        };
+        match seg.tp {
+            TrackSegmentType::Cut { ref src } => {
+                return self.fail(format!(
+                    "Via {:?} at {:?} lands on layer {} where cut by {:?}",
+                    via.src, via.loc, layer.spec.name, src
+                ))
+            }
+            TrackSegmentType::Blockage { ref src } => {
+                return self.fail(format!(
+                    "Via {:?} at {:?} lands on layer {} where blocked by {}",
+                    via.src, via.loc, layer.spec.name, src
+                ))
+            }
+            _ => (),
+        }
+        if let (Some(net), Some(other)) = (via.src.net.as_ref(), seg.tp.net()) {
+            if *net != other {
+                return self.fail(format!(
+                    "Via {:?} at {:?} of net {} lands on layer {} metal of net {}",
+                    via.src, via.loc, net, layer.spec.name, other
+                ));
+            }
+        }
+        Ok(())
+    }
     /// Convert an [Instance] to a [raw::Instance]
     fn export_instance(&self, inst: &Instance) -> LayoutResult<raw::Instance> {
         // Get the raw-cell pointer from our mapping.
@@ -265,7 +1850,7 @@ impl<'lib> RawExporter {
         Ok(raw::Instance {
             inst_name: inst.inst_name.clone(),
             cell: rawkey.clone(),
-            loc: self.export_xy(inst.loc.abs()?).into(),
+            loc: self.export_xy(inst.loc.abs()?)?,
             reflect_vert,
             angle,
         })
@@ -274,11 +1859,40 @@ impl<'lib> RawExporter {
     fn temp_cell<'a>(&'a self, layout: &'a Layout) -> LayoutResult<TempCell<'a>> {
         // Collect references to its instances
         let instances = layout.instances.clone();
+        // Cells with no metal layers can have no content on them
+        if layout.metals == 0 {
+            if !layout.assignments.is_empty() {
+                let nets: Vec<&str> = layout.assignments.iter().map(|a| a.net.as_str()).collect();
+                return self.fail(format!(
+                    "Cell {} has no metal layers, but has assignments to nets {}",
+                    layout.name,
+                    nets.join(", ")
+                ));
+            }
+            if !layout.cuts.is_empty()
+                || !layout.vias.is_empty()
+                || !layout.straps.is_empty()
+                || !layout.jogs.is_empty()
+            {
+                return self.fail(format!(
+                    "Cell {} has no metal layers, but has cuts, vias, straps, or jogs",
+                    layout.name
+                ));
+            }
+        }
         // Validate `cuts`, and arrange them by layer
         let mut cuts: Vec<Vec<&TrackCross>> = vec![vec![]; layout.metals];
         for cut in layout.cuts.iter() {
             validate::LibValidator::new(&self.stack).validate_track_cross(cut)?;
-            cuts[cut.track.layer].push(&cut);
+            if cut.track.layer.index() >= layout.metals {
+                return self.fail(format!(
+                    "Cut {:?} in Cell {} is above its top layer {}",
+                    cut,
+                    layout.name,
+                    layout.metals - 1
+                ));
+            }
+            cuts[cut.track.layer.index()].push(cut);
             // FIXME: cell validation should also check that this lies within our outline. probably do this earlier
         }
 
@@ -286,6 +1900,8 @@ impl<'lib> RawExporter {
         let mut bot_assns = vec![vec![]; layout.metals];
         let mut top_assns = vec![vec![]; layout.metals];
         let mut assignments = SlotMap::with_key();
+        let mut nets = NetInterner::new();
+        let mut assn_nets = SecondaryMap::new();
         for assn in layout.assignments.iter() {
             // Validate the assignment
             let v = validate::LibValidator::new(&self.stack).validate_assign(assn)?;
@@ -296,10 +1912,37 @@ impl<'lib> RawExporter {
             // (This also returns the layer, which we ignore.)
             self.stack.metal(bot)?;
             self.stack.metal(top)?;
+            if top.index() >= layout.metals {
+                return self.fail(format!(
+                    "Assignment {:?} in Cell {} is above its top layer {}",
+                    assn,
+                    layout.name,
+                    layout.metals - 1
+                ));
+            }
 
             let k = assignments.insert(v);
-            bot_assns[bot].push(k);
-            top_assns[top].push(k);
+            assn_nets.insert(k, nets.intern(&assn.net));
+            bot_assns[bot.index()].push(k);
+            top_assns[top.index()].push(k);
+        }
+        // Validate all directly-placed vias, and check both their layers are in the cell
+        let mut vias = Vec::with_capacity(layout.vias.len());
+        for via in layout.vias.iter() {
+            let v = validate::LibValidator::new(&self.stack).validate_via(via)?;
+            if v.top >= layout.metals {
+                return self.fail(format!(
+                    "Via {:?} in Cell {} is above its top layer {}",
+                    via,
+                    layout.name,
+                    layout.metals - 1
+                ));
+            }
+            vias.push(v);
+        }
+        // Validate all jogs
+        for jog in layout.jogs.iter() {
+            validate::LibValidator::new(&self.stack).validate_jog(jog, layout)?;
         }
         // And create our (temporary) cell data!
         Ok(TempCell {
@@ -307,56 +1950,252 @@ impl<'lib> RawExporter {
             lib: &self.lib,
             instances,
             assignments,
+            nets,
+            assn_nets,
             top_assns,
             bot_assns,
             cuts,
+            vias,
         })
     }
-    /// Convert a single row/col (period) on a single layer in a single Cell.
-    fn export_cell_layer_period(
+    /// Check each of the assignments of `temp_cell` is routable, per [RawExportOptions::on_unroutable].
+    /// Unroutable assignments either fail, or are removed from `temp_cell` and recorded in `report`.
+    fn drop_unroutable(
+        &self,
+        temp_cell: &mut TempCell,
+        report: &mut ConversionReport,
+    ) -> LayoutResult<()> {
+        let skip = self.opts.on_unroutable == OnUnroutable::SkipWarn;
+        let mut dropped = Vec::new();
+        for (key, assn) in temp_cell.assignments.iter() {
+            let blocker = match self.assign_blocker(temp_cell, assn, skip)? {
+                Some(blocker) => blocker,
+                None => continue,
+            };
+            let net = self.assign_net(temp_cell, key)?;
+            if !skip {
+                return self.fail(format!(
+                    "Assignment of net {} at {:?} in Cell {} is blocked by {}",
+                    net, assn.src.at, temp_cell.cell.name, blocker
+                ));
+            }
+            report.unroutable.push(Unroutable {
+                cell: temp_cell.cell.name.clone(),
+                net: net.to_string(),
+                at: assn.src.at,
+                blocker,
+            });
+            dropped.push(key);
+        }
+        for key in dropped {
+            temp_cell.assignments.remove(key);
+            temp_cell.assn_nets.remove(key);
+            for keys in temp_cell
+                .top_assns
+                .iter_mut()
+                .chain(temp_cell.bot_assns.iter_mut())
+            {
+                keys.retain(|k| *k != key);
+            }
+        }
+        Ok(())
+    }
+    /// Get whatever blocks assignment `assn` in `temp_cell`, if anything, per [OnUnroutable]:
+    /// the name of an [Instance] blocking its top layer at its intersection, or if `cuts` is set,
+    /// any cut on either of its tracks covering its intersection.
+    /// Cuts are otherwise left to fail during assignment.
+    fn assign_blocker(
+        &self,
+        temp_cell: &TempCell,
+        assn: &validate::ValidAssign,
+        cuts: bool,
+    ) -> LayoutResult<Option<String>> {
+        let at = self.track_cross_xy(&assn.src.at)?;
+        let layer = self.stack.metal(assn.top.layer)?;
+        let dir = layer.spec.dir;
+        let (_, nperiods) = self.layer_extent(temp_cell.cell, layer)?;
+        let (period, _) = layer.locate(assn.top.track)?;
+        for ptr in temp_cell.instances.iter() {
+            let inst = ptr.read()?;
+            let child = inst.cell.read()?;
+            if child.metals()? <= layer.index {
+                continue;
+            }
+            let periods = self.instance_periods(&inst, layer, nperiods, &temp_cell.cell.name)?;
+            if !periods.contains(&period) {
+                continue;
+            }
+            // Check the blockage along the track, matching those created in [RawExporter::temp_cell_layer_period]
+            let within = |start: DbUnits, stop: DbUnits| start < at[dir] && at[dir] < stop;
+            let blocked = match self.raw_occupancy(&inst, &child, layer)? {
+                Some(occupied) => occupied
+                    .iter()
+                    .filter(|o| o.track == assn.top.track)
+                    .any(|o| {
+                        o.intervals
+                            .iter()
+                            .any(|(start, stop)| within(*start, *stop))
+                    }),
+                None => {
+                    let bbox = inst.boundbox()?;
+                    within(self.db_units(bbox.p0[dir])?, self.db_units(bbox.p1[dir])?)
+                }
+            };
+            if blocked {
+                return Ok(Some(inst.inst_name.clone()));
+            }
+        }
+        if !cuts {
+            return Ok(None);
+        }
+        for track in [&assn.top, &assn.bot] {
+            let metal = self.stack.metal(track.layer)?;
+            let dir = metal.spec.dir;
+            let half = metal.spec.cutsize / 2;
+            for cut in temp_cell.cuts[track.layer.index()].iter() {
+                if cut.track != *track {
+                    continue;
+                }
+                let dist = self.track_cross_xy(cut)?[dir];
+                if dist - half <= at[dir] && at[dir] <= dist + half {
+                    return Ok(Some(format!("cut {:?}", cut)));
+                }
+            }
+        }
+        Ok(None)
+    }
+    /// Create the [LayerPeriod] for `temp_period`, including its instance blockages, but not its cuts or assignments.
+    ///
+    /// If enabled in our [RawExportOptions], periods with identical blockages and flipping are shared via `cache`.
+    /// Cached periods are copied and shifted to the location of `temp_period`.
+    fn blocked_layer_period(
         &self,
         temp_period: &TempPeriod,
-    ) -> LayoutResult<Vec<raw::Element>> {
-        let mut elems: Vec<raw::Element> = Vec::new();
+        cache: &mut PeriodCache,
+        report: &mut ConversionReport,
+    ) -> LayoutResult<LayerPeriod> {
         let layer = temp_period.layer.layer; // FIXME! Can't love this name.
+        let periodnum = temp_period.periodnum;
+        report.periods += 1;
+
+        // Convert primitive-pitch-based blockages to db units
+        let mut blockages: Vec<(DbUnits, DbUnits, BlockageSrc)> = temp_period
+            .blockages
+            .iter()
+            .map(|(n1, n2, src)| Ok((self.db_units(*n1)?, self.db_units(*n2)?, src.clone())))
+            .collect::<LayoutResult<_>>()?;
+
+        let key = if self.opts.cache_periods {
+            // Check the cache for a matching period
+            let flipped = layer.flipped(periodnum);
+            let mut sorted: Vec<_> = blockages
+                .iter()
+                .map(|(start, stop, src)| (*start, *stop, src.key()))
+                .collect();
+            sorted.extend(
+                temp_period
+                    .track_blockages
+                    .iter()
+                    .map(|(idx, start, stop, ptr)| {
+                        let key = BlockageKey::InstanceTrack(Arc::as_ptr(ptr) as usize, *idx);
+                        (*start, *stop, key)
+                    }),
+            );
+            sorted.sort();
+            let key = (flipped, sorted);
+            if let Some((cached_num, cached)) = cache.get(&key) {
+                // Found one. Copy it, and shift it into our position.
+                let mut layer_period = cached.clone();
+                layer_period.offset(layer.pitch * (periodnum - cached_num))?;
+                layer_period.index = periodnum;
+                report.period_cache_hits += 1;
+                return Ok(layer_period);
+            }
+            Some(key)
+        } else {
+            None
+        };
 
         // Create the layer-period object we'll manipulate most of the way
-        let mut layer_period = temp_period
-            .layer
-            .layer
+        let mut layer_period = layer
             .spec
-            .to_layer_period(temp_period.periodnum, temp_period.layer.span.0)?;
+            .to_layer_period(periodnum, temp_period.layer.span.0)?;
         // Insert blockages on each track
-        for (n1, n2, inst_ptr) in temp_period.blockages.iter() {
-            // Convert primitive-pitch-based blockages to db units
-            let start = self.db_units(*n1);
-            let stop = self.db_units(*n2);
-            // And insert the blockage
-            layer_period.block(start, stop, &inst_ptr).unwrapper(
-                self,
-                format!(
-                    "Could not insert blockage on Layer {:?}, period {} from {:?} to {:?}",
-                    layer, temp_period.periodnum, start, stop
-                ),
+        for (start, stop, src) in blockages.drain(..) {
+            let src_name = match src {
+                BlockageSrc::Instance(ptr) => ptr.read()?.inst_name.clone(),
+                BlockageSrc::Keepout(idx) => temp_period.cell.cell.keepouts[idx].name.clone(),
+            };
+            if let Err(e) = layer_period.block(start, stop, &src_name) {
+                return self.fail(format!(
+                    "Could not insert blockage on Layer {:?}, period {} from {:?} to {:?}: {}",
+                    layer, periodnum, start, stop, e
+                ));
+            }
+        }
+        // And single-track blockages from raw-layout instances
+        for (idx, start, stop, ptr) in temp_period.track_blockages.iter() {
+            let inst = ptr.read()?;
+            let track = self.unwrap(
+                layer_period.signals.get_mut(*idx),
+                "Internal error: invalid track blockage",
             )?;
+            if let Err(e) = track.block(*start, *stop, &inst.inst_name) {
+                return self.fail(format!(
+                    "Could not insert blockage of Instance {} on Layer {}, period {}, track {} from {:?} to {:?}: {}",
+                    inst.inst_name, layer.spec.name, periodnum, idx, start, stop, e
+                ));
+            }
+        }
+        if let Some(key) = key {
+            cache.insert(key, (periodnum, layer_period.clone()));
         }
-        // Place all relevant cuts
+        Ok(layer_period)
+    }
+    /// Convert a single row/col (period) on a single layer in a single Cell.
+    /// If `routes` is provided, the final [LayerPeriod] and its via-assignments are added to it.
+    /// If `prov` is provided, the [Provenance] of each returned element is appended to it.
+    fn export_cell_layer_period<'a>(
+        &self,
+        temp_period: &TempPeriod<'a>,
+        cache: &mut PeriodCache,
+        report: &mut ConversionReport,
+        vias: &mut Vec<ViaCellPlacement>,
+        mut routes: Option<&mut LayerRoutes>,
+        prov: Option<&mut Vec<Provenance>>,
+    ) -> LayoutResult<Vec<raw::Element>> {
+        let mut elems: Vec<raw::Element> = Vec::new();
+        let mut period_prov = prov.as_ref().map(|_| Vec::new());
+        let layer = temp_period.layer.layer; // FIXME! Can't love this name.
+        let periodnum = temp_period.periodnum;
+
+        // Create the layer-period object we'll manipulate most of the way,
+        // including its instance blockages
+        let mut layer_period = self.blocked_layer_period(temp_period, cache, report)?;
+        // Place all relevant cuts, collecting them per track and applying each track's cuts at once
         let nsig = layer_period.signals.len();
+        let mut track_cuts: Vec<Vec<(DbUnits, DbUnits, TrackCross)>> = vec![Vec::new(); nsig];
         for cut in temp_period.cuts.iter() {
-            // Cut the assigned track
-            let track = &mut layer_period.signals[cut.track.track % nsig];
             let cut_loc = self.track_cross_xy(cut)?;
             let dist = cut_loc[layer.spec.dir];
-            let res = track
-                .cut(
-                    dist - layer.spec.cutsize / 2, // start
-                    dist + layer.spec.cutsize / 2, // stop
-                    cut,                           // src
-                )
-                .unwrapper(
-                    self,
-                    format!("Could not make track-cut {:?} in {:?}", cut, temp_period),
-                )?;
+            let (_, idx) = layer.locate(cut.track.track)?;
+            track_cuts[idx].push((
+                dist - layer.spec.cutsize / 2, // start
+                dist + layer.spec.cutsize / 2, // stop
+                **cut,                         // src
+            ));
+        }
+        for (track, cuts) in layer_period.signals.iter_mut().zip(track_cuts.iter()) {
+            if cuts.is_empty() {
+                continue;
+            }
+            // Note the error message is only formatted on failure, as it includes the (large) `temp_period`
+            if let Err(e) = track.cut_many(cuts) {
+                return self.fail(format!(
+                    "Could not make track-cuts {:?} in {:?}: {}",
+                    cuts, temp_period, e
+                ));
+            }
         }
         // Handle Net Assignments
         // Start with those for which we're the lower of the two layers.
@@ -365,34 +2204,61 @@ impl<'lib> RawExporter {
         for assn_id in temp_period.bot_assns.iter() {
             // Note that while `via_layer` is identical over every iteration of this loop, it may not exist if we never enter the loop.
             // So, retrieve it from the `stack` on our first iteration.
-            if via_opt.is_none() {
-                via_opt = Some(self.stack.via_from(layer.index)?);
-            }
-            let via_layer = via_opt.as_ref().unwrap();
+            let via_layer = match via_opt {
+                Some(v) => v,
+                None => *via_opt.insert(self.stack.via_from(LayerIndex(layer.index))?),
+            };
 
             let assn = self.unwrap(
                 temp_period.cell.assignments.get(*assn_id),
                 "Internal error: invalid assignment",
             )?;
             self.assign_track(layer, &mut layer_period, assn, false)?;
-            let assn_loc = self.track_cross_xy(&assn.src.at)?;
-            // Create the via element
-            let e = raw::Element {
-                net: Some(assn.src.net.clone()),
-                layer: via_layer.raw.unwrap(),
-                purpose: raw::LayerPurpose::Drawing,
-                inner: raw::Shape::Rect(raw::Rect {
-                    p0: self.export_point(
-                        assn_loc.x - via_layer.size.x / 2,
-                        assn_loc.y - via_layer.size.y / 2,
-                    ),
-                    p1: self.export_point(
-                        assn_loc.x + via_layer.size.x / 2,
-                        assn_loc.y + via_layer.size.y / 2,
-                    ),
-                }),
+            // Sort out the via-cell spec, if any, per the assignment's [ViaPolicy]
+            let spec = match assn.src.via {
+                ViaPolicy::Auto => self.opts.via_cells,
+                ViaPolicy::None => continue, // Connected elsewhere, e.g. inside a child cell
+                ViaPolicy::Variant(ref name) => match self.opts.via_variants.get(name) {
+                    Some(spec) => Some(*spec),
+                    None => {
+                        return self
+                            .fail(format!("Unknown via variant {} for {:?}", name, assn.src))
+                    }
+                },
             };
-            elems.push(e);
+            if let Some(ref mut routes) = routes {
+                routes.vias.push(assn.src.clone());
+            }
+            let assn_loc = self.track_cross_xy(&assn.src.at)?;
+            if via_layer.raw.is_none() {
+                return self.fail(format!(
+                    "Cannot create via for {:?}: via layer {} has no raw layer",
+                    assn.src, via_layer.name
+                ));
+            }
+            if self.dry_run {
+                continue;
+            }
+            if let Some(spec) = spec {
+                // Collect the via for instantiation from its generated cell
+                vias.push(ViaCellPlacement {
+                    layer: layer.index,
+                    loc: assn_loc,
+                    spec,
+                });
+                continue;
+            }
+            // Create the via element, named by its interned net
+            let net = self.assign_net(temp_period.cell, *assn_id)?;
+            elems.push(self.export_via(via_layer, assn_loc, Some(net.to_string()))?);
+            annotate(period_prov.as_mut(), &elems, |_| Provenance {
+                layer: Some(layer.index),
+                period: Some(periodnum),
+                ..Provenance::new(ProvenanceSource::Via {
+                    net: Some(net.to_string()),
+                    assign: Some(assn.src.at),
+                })
+            });
         }
 
         // Assign all the segments for which we're the top layer
@@ -404,30 +2270,116 @@ impl<'lib> RawExporter {
             self.assign_track(layer, &mut layer_period, assn, true)?;
         }
 
-        // Convert all TrackSegments to raw Elements
-        for t in layer_period.rails.iter() {
-            elems.extend(self.export_track(t, &layer)?);
+        // Claim the tracks of any straps, after all other assignments
+        for strap in temp_period.cell.cell.straps.iter() {
+            if strap.layer == layer.index {
+                self.strap_tracks(temp_period, &mut layer_period, strap)?;
+            }
+        }
+        // And those of any jogs
+        for jog in temp_period.cell.cell.jogs.iter() {
+            if jog.layer == layer.index {
+                self.jog_tracks(temp_period, &mut layer_period, jog)?;
+            }
+        }
+
+        // Enforce the layer's minimum segment length
+        if let Some(min) = layer.spec.min_length {
+            self.enforce_min_length(temp_period, &mut layer_period, min, report)?;
+        }
+
+        // Convert all TrackSegments to raw Elements, skipping rails if the cell suppresses them
+        let suppressed = temp_period.cell.cell.suppress_rails.contains(&layer.index);
+        for t in layer_period.rails.iter().filter(|_| !suppressed) {
+            let mut rails = Vec::new();
+            let mut rail_prov = period_prov.as_ref().map(|_| Vec::new());
+            self.export_track(t, layer, periodnum, &mut rails, rail_prov.as_mut())?;
+            let outline = &temp_period.cell.cell.outline;
+            self.clip_rails(&mut rails, rail_prov.as_mut(), layer.spec.dir, outline)?;
+            elems.extend(rails);
+            if let (Some(period_prov), Some(rail_prov)) = (period_prov.as_mut(), rail_prov) {
+                period_prov.extend(rail_prov);
+            }
         }
         for t in layer_period.signals.iter() {
-            elems.extend(self.export_track(t, &layer)?);
+            self.export_track(t, layer, periodnum, &mut elems, period_prov.as_mut())?;
+        }
+        if let Some(routes) = routes {
+            routes.periods.push(layer_period);
+        }
+        if let (Some(prov), Some(period_prov)) = (prov, period_prov) {
+            prov.extend(period_prov);
         }
         Ok(elems)
     }
+    /// Enforce minimum segment-length `min` on each track of `layer_period`, per [RawExportOptions::min_length]
+    fn enforce_min_length(
+        &self,
+        temp_period: &TempPeriod,
+        layer_period: &mut LayerPeriod,
+        min: DbUnits,
+        report: &mut ConversionReport,
+    ) -> LayoutResult<()> {
+        let layer = temp_period.layer.layer;
+        let cell = &temp_period.cell.cell.name;
+        let (nrails, nsig) = (layer_period.rails.len(), layer_period.signals.len());
+        for track in layer_period
+            .rails
+            .iter_mut()
+            .chain(layer_period.signals.iter_mut())
+        {
+            // Get the track's index among all those of its type in the layer
+            let ntype = match track.data.ttype {
+                TrackType::Rail(_) => nrails,
+                _ => nsig,
+            };
+            let index = temp_period.periodnum * ntype + track.data.index;
+            if self.opts.min_length == MinLengthPolicy::Remove {
+                let removed = match track.data.ttype {
+                    TrackType::Rail(_) => track.remove_rail_slivers(min),
+                    _ => track.remove_slivers(min),
+                };
+                for seg in removed {
+                    if let TrackType::Rail(rk) = track.data.ttype {
+                        let msg = format!(
+                            "Removing {} rail sliver from {:?} to {:?} on track {} of layer {} in Cell {}",
+                            rk.to_string(), seg.start, seg.stop, index, layer.spec.name, cell
+                        );
+                        eprintln!("Warning: {}", msg);
+                        report.warnings.push(msg);
+                    }
+                    report.slivers.push(Sliver {
+                        cell: cell.clone(),
+                        layer: layer.index,
+                        ttype: track.data.ttype,
+                        track: index,
+                        start: seg.start,
+                        stop: seg.stop,
+                    });
+                }
+            }
+            if let Some(idx) = track.slivers(min).first() {
+                let seg = &track.segments[*idx];
+                return self.fail(format!(
+                    "Segment {:?} from {:?} to {:?} on {} track {} of layer {} is shorter than its minimum length {:?}",
+                    seg.tp, seg.start, seg.stop, track.data.ttype, index, layer.spec.name, min
+                ));
+            }
+        }
+        Ok(())
+    }
     /// Set the net corresponding to `assn` on layer `layer`.
-    ///
-    /// The type signature, particularly lifetimes, aren't pretty.
-    /// <'f> is the short "function lifetime" of the argument references
-    pub fn assign_track<'f>(
+    pub fn assign_track(
         &self,
-        layer: &'f validate::ValidMetalLayer,
-        layer_period: &'f mut LayerPeriod<'lib>,
-        assn: &'lib validate::ValidAssign,
+        layer: &validate::ValidMetalLayer,
+        layer_period: &mut LayerPeriod,
+        assn: &validate::ValidAssign,
         top: bool, // Boolean indication of whether to assign `top` or `bot`. FIXME: not our favorite.
     ) -> LayoutResult<()> {
         // Grab a (mutable) reference to the assigned track
-        let nsig = layer_period.signals.len();
         let track = if top { assn.top.track } else { assn.bot.track };
-        let track = &mut layer_period.signals[track % nsig];
+        let (_, idx) = layer.locate(track)?;
+        let track = &mut layer_period.signals[idx];
         // And set the net at the assignment's location
         let assn_loc = self.track_cross_xy(&assn.src.at)?;
         let res = track
@@ -435,6 +2387,91 @@ impl<'lib> RawExporter {
             .unwrapper(self, "Error Assigning Track")?;
         Ok(())
     }
+    /// Get the net name of assignment `key` in `temp_cell`, from its [NetInterner]
+    fn assign_net<'a>(&self, temp_cell: &'a TempCell, key: AssignKey) -> LayoutResult<&'a str> {
+        let id = self.unwrap(
+            temp_cell.assn_nets.get(key),
+            "Internal error: assignment without a net",
+        )?;
+        self.unwrap(temp_cell.nets.name(*id), "Internal error: invalid net id")
+    }
+    /// Claim the tracks of `strap` which fall within `temp_period`
+    fn strap_tracks(
+        &self,
+        temp_period: &TempPeriod,
+        layer_period: &mut LayerPeriod,
+        strap: &Strap,
+    ) -> LayoutResult<()> {
+        let dir = temp_period.layer.layer.spec.dir;
+        let start = self.db_units(PrimPitches::try_new(dir, strap.extent.start)?)?;
+        let stop = self.db_units(PrimPitches::try_new(dir, strap.extent.end)?)?;
+        let layer = temp_period.layer.layer;
+        for tracknum in strap.tracks.clone() {
+            let (period, idx) = layer.locate(TrackIndex(tracknum))?;
+            if period != temp_period.periodnum {
+                continue; // Not in this period
+            }
+            let track = &mut layer_period.signals[idx];
+            track
+                .strap(start, stop, &strap.net, self.opts.strap_conflicts)
+                .unwrapper(self, format!("Error Strapping Track {}", tracknum))?;
+        }
+        Ok(())
+    }
+    /// Claim the tracks of `jog` which fall within `temp_period`, per [Track::jog]
+    fn jog_tracks(
+        &self,
+        temp_period: &TempPeriod,
+        layer_period: &mut LayerPeriod,
+        jog: &Jog,
+    ) -> LayoutResult<()> {
+        let at = self.stack.metal(jog.at.layer)?.center(jog.at.track)?;
+        let layer = temp_period.layer.layer;
+        for tracknum in [jog.track_a, jog.track_b] {
+            let (period, idx) = layer.locate(TrackIndex(tracknum))?;
+            if period != temp_period.periodnum {
+                continue; // Not in this period
+            }
+            let assn = Assign::new(
+                &jog.net,
+                TrackCross::new(
+                    TrackRef::new(LayerIndex(jog.layer), TrackIndex(tracknum)),
+                    jog.at,
+                ),
+            );
+            let track = &mut layer_period.signals[idx];
+            track
+                .jog(at, &assn)
+                .unwrapper(self, format!("Error Jogging Track {}", tracknum))?;
+        }
+        Ok(())
+    }
+    /// Create the rectangle of `jog`, from the far edge of its `track_a` to the near edge of its `track_b`,
+    /// as wide as its crossing track
+    fn export_jog(&self, jog: &Jog) -> LayoutResult<raw::Element> {
+        let metal = self.stack.metal(LayerIndex(jog.layer))?;
+        let layer = self.unwrap(
+            metal.raw,
+            format!("Metal layer {} has no raw layer", metal.spec.name),
+        )?;
+        let (a0, a1) = metal.span(TrackIndex(jog.track_a))?;
+        let (b0, b1) = metal.span(TrackIndex(jog.track_b))?;
+        // Cover `track_a` and the space between the tracks, abutting `track_b`
+        let (lo, hi) = if a0 < b0 { (a0, b0) } else { (b1, a1) };
+        let (c0, c1) = self.stack.metal(jog.at.layer)?.span(jog.at.track)?;
+        // Tracks are spaced opposite their layer's direction; the jog runs across them
+        let (p0, p1) = match metal.spec.dir {
+            Dir::Horiz => (self.export_point(c0, lo), self.export_point(c1, hi)),
+            Dir::Vert => (self.export_point(lo, c0), self.export_point(hi, c1)),
+        };
+        Ok(raw::Element {
+            net: Some(jog.net.clone()),
+            layer,
+            purpose: raw::LayerPurpose::Drawing,
+            inner: raw::Shape::Rect(raw::Rect { p0, p1 }),
+            labels: Vec::new(),
+        })
+    }
     /// Convert a [Abstract] into raw form.
     pub fn export_abstract(&mut self, abs: &abs::Abstract) -> LayoutResult<raw::Abstract> {
         self.ctx.push(ErrorContext::Abstract);
@@ -443,10 +2480,15 @@ impl<'lib> RawExporter {
         let outline = self.export_outline(&abs.outline)?;
         // Create the raw abstract
         let mut rawabs = raw::Abstract::new(&abs.name, outline.clone());
+        // Floorplan views include nothing more
+        if self.opts.mode == ConversionMode::FloorplanOnly {
+            self.ctx.pop();
+            return Ok(rawabs);
+        }
 
         // Draw a blockage on each layer, equal to the shape of the outline
         for layerindex in 0..abs.metals {
-            let layerkey = self.stack.metal(layerindex)?.raw.unwrap();
+            let layerkey = self.metal_raw(LayerIndex(layerindex))?;
             let blk = vec![raw::Shape::Polygon(outline.clone())];
             rawabs.blockages.insert(layerkey, blk);
         }
@@ -468,7 +2510,7 @@ impl<'lib> RawExporter {
     ) -> LayoutResult<raw::AbstractPort> {
         use abs::PortKind::{Edge, ZTopEdge, ZTopInner};
 
-        let (layerkey, shape): (raw::LayerKey, raw::Shape) = match &port.kind {
+        let (layerkey, shapes): (raw::LayerKey, Vec<raw::Shape>) = match &port.kind {
             Edge {
                 layer: layer_index,
                 track,
@@ -480,7 +2522,7 @@ impl<'lib> RawExporter {
                     abs::Side::BottomOrLeft => (DbUnits(0), DbUnits(100)),
                     abs::Side::TopOrRight => {
                         // FIXME: this assumes rectangular outlines; will take some more work for polygons.
-                        let outside = self.db_units(abs.outline.max(layer.dir));
+                        let outside = self.db_units(abs.outline.max(layer.dir))?;
                         (outside - DbUnits(100), outside)
                     }
                 };
@@ -494,11 +2536,11 @@ impl<'lib> RawExporter {
                     pts[1] = pts[1].transpose();
                 }
                 (
-                    self.stack.metal(*layer_index)?.raw.unwrap(),
-                    raw::Shape::Rect(raw::Rect {
-                        p0: self.export_xy(&pts[0]),
-                        p1: self.export_xy(&pts[1]),
-                    }),
+                    self.metal_raw(*layer_index)?,
+                    vec![raw::Shape::Rect(raw::Rect {
+                        p0: self.export_xy(&pts[0])?,
+                        p1: self.export_xy(&pts[1])?,
+                    })],
                 )
             }
             ZTopEdge { track, side, into } => {
@@ -507,24 +2549,24 @@ impl<'lib> RawExporter {
                 } else {
                     Ok(abs.metals - 1)
                 }?;
-                let layer = &self.stack.metal(top_metal)?.spec;
+                let layer = &self.stack.metal(LayerIndex(top_metal))?.spec;
                 let other_layer_index = match into.1 {
                     RelZ::Above => top_metal + 1,
                     RelZ::Below => top_metal - 1,
                 };
-                let other_layer = self.stack.metal(other_layer_index)?;
+                let other_layer = self.stack.metal(LayerIndex(other_layer_index))?;
                 let other_layer_center = other_layer.center(into.0)?;
                 // First get the "infinite dimension" coordinate from the edge
                 let infdims: (DbUnits, DbUnits) = match side {
                     abs::Side::BottomOrLeft => (DbUnits(0), other_layer_center),
                     abs::Side::TopOrRight => {
                         // FIXME: this assumes rectangular outlines; will take some more work for polygons.
-                        let outside = self.db_units(abs.outline.max(layer.dir));
+                        let outside = self.db_units(abs.outline.max(layer.dir))?;
                         (other_layer_center, outside)
                     }
                 };
                 // Now get the "periodic dimension" from our layer-center
-                let perdims: (DbUnits, DbUnits) = self.track_span(top_metal, *track)?;
+                let perdims: (DbUnits, DbUnits) = self.track_span(LayerIndex(top_metal), *track)?;
                 // Presuming we're horizontal, points are here:
                 let mut pts = [Xy::new(infdims.0, perdims.0), Xy::new(infdims.1, perdims.1)];
                 // And if vertical, just transpose them
@@ -533,32 +2575,83 @@ impl<'lib> RawExporter {
                     pts[1] = pts[1].transpose();
                 }
                 (
-                    self.stack.metal(top_metal)?.raw.unwrap(),
-                    raw::Shape::Rect(raw::Rect {
-                        p0: self.export_xy(&pts[0]),
-                        p1: self.export_xy(&pts[1]),
-                    }),
+                    self.metal_raw(LayerIndex(top_metal))?,
+                    vec![raw::Shape::Rect(raw::Rect {
+                        p0: self.export_xy(&pts[0])?,
+                        p1: self.export_xy(&pts[1])?,
+                    })],
                 )
             }
-            ZTopInner { .. } => todo!(),
+            ZTopInner { locs } => {
+                let top_metal = if abs.metals == 0 {
+                    self.fail("Abs Port with no metal layers")
+                } else {
+                    Ok(abs.metals - 1)
+                }?;
+                let layer = &self.stack.metal(LayerIndex(top_metal))?.spec;
+                // One shape per location, spanning the intersection of its two tracks
+                let mut shapes = Vec::with_capacity(locs.len());
+                for loc in locs.iter() {
+                    let other_layer_index = match loc.relz {
+                        RelZ::Above => top_metal + 1,
+                        RelZ::Below => top_metal - 1,
+                    };
+                    let perdims = self.track_span(LayerIndex(top_metal), loc.track)?;
+                    let infdims = self.track_span(LayerIndex(other_layer_index), loc.at)?;
+                    // Presuming we're horizontal, points are here:
+                    let mut pts = [Xy::new(infdims.0, perdims.0), Xy::new(infdims.1, perdims.1)];
+                    // And if vertical, just transpose them
+                    if layer.dir == Dir::Vert {
+                        pts[0] = pts[0].transpose();
+                        pts[1] = pts[1].transpose();
+                    }
+                    shapes.push(raw::Shape::Rect(raw::Rect {
+                        p0: self.export_xy(&pts[0])?,
+                        p1: self.export_xy(&pts[1])?,
+                    }));
+                }
+                (self.metal_raw(LayerIndex(top_metal))?, shapes)
+            }
         };
-        let mut shapes = HashMap::new();
-        shapes.insert(layerkey, vec![shape]);
+        let mut layer_shapes = BTreeMap::new();
+        layer_shapes.insert(layerkey, shapes);
         let rawport = raw::AbstractPort {
             net: port.name.clone(),
-            shapes,
+            shapes: layer_shapes,
         };
         Ok(rawport)
     }
+    /// Get the raw layer of metal layer `layer`, failing if it has none
+    fn metal_raw(&self, layer: LayerIndex) -> LayoutResult<raw::LayerKey> {
+        let metal = self.stack.metal(layer)?;
+        self.unwrap(
+            metal.raw,
+            format!("Metal layer {} has no raw layer", metal.spec.name),
+        )
+    }
     /// Get the positions spanning track number `track` on layer number `layer`
     fn track_span(
         &self,
-        layer_index: usize,
-        track_index: usize,
+        layer_index: LayerIndex,
+        track_index: TrackIndex,
     ) -> LayoutResult<(DbUnits, DbUnits)> {
         let layer = self.stack.metal(layer_index)?;
         layer.span(track_index)
     }
+    /// Get our stack's [raw::Layers], failing if it has none
+    fn rawlayers(&self) -> LayoutResult<&Ptr<raw::Layers>> {
+        self.unwrap(
+            self.stack.rawlayers.as_ref(),
+            "Stack has no raw layers, required for conversion",
+        )
+    }
+    /// Get our stack's boundary layer, failing if it has none
+    fn boundary_layer(&self) -> LayoutResult<raw::LayerKey> {
+        self.unwrap(
+            self.stack.boundary_layer,
+            "Stack has no boundary layer, required for conversion",
+        )
+    }
     /// Convert an [Outline] to a [raw::Shape]
     fn outline_shape(&self, outline: &Outline) -> LayoutResult<raw::Polygon> {
         // FIXME: always uses `Poly`, because some proto-schemas insist on it as the most general.
@@ -573,19 +2666,20 @@ impl<'lib> RawExporter {
         //     return Ok(raw::Shape::Rect { p0, p1 });
         // }
         // Polygon
-        // Create an array of Outline-Points
-        let mut pts = vec![Point { x: 0, y: 0 }];
-        let mut xp: isize;
-        let mut yp: isize = 0;
-        for i in 0..outline.x.len() {
-            xp = self.db_units(outline.x[i]).raw();
-            pts.push(Point::new(xp, yp));
-            yp = self.db_units(outline.y[i]).raw();
-            pts.push(Point::new(xp, yp));
-        }
-        // Add the final implied Point at (x, y[-1])
-        pts.push(Point::new(0, yp));
-        Ok(raw::Polygon { points: pts })
+        // Create an array of Outline-Points, leaving closure implied
+        let pts = outline
+            .points(false)
+            .iter()
+            .map(|p| {
+                Ok(Point::new(
+                    self.db_units(p.x)?.raw(),
+                    self.db_units(p.y)?.raw(),
+                ))
+            })
+            .collect::<LayoutResult<_>>()?;
+        let mut poly = raw::Polygon { points: pts };
+        poly.normalize();
+        Ok(poly)
     }
     /// Convert an [Outline] to a [raw::Element] polygon
     pub fn export_outline(&self, outline: &Outline) -> LayoutResult<raw::Polygon> {
@@ -594,42 +2688,21 @@ impl<'lib> RawExporter {
         // And create the [raw::Element]
         Ok(shape)
     }
-    /// Convert a [Track]-full of [TrackSegment]s to a vector of [raw::Element] rectangles
+    /// Convert a [Track]-full of [TrackSegment]s to [raw::Element] rectangles, per [render_track],
+    /// appending them to `elems`, and their [Provenance] to any `prov`, as part of layer-period `period`
     fn export_track(
         &self,
         track: &Track,
         layer: &validate::ValidMetalLayer,
-    ) -> LayoutResult<Vec<raw::Element>> {
-        let mut elems = Vec::new();
-        for seg in &track.segments {
-            // Convert wires and rails, skip blockages and cuts
-            use TrackSegmentType::*;
-            let net: Option<String> = match seg.tp {
-                Wire { src } => src.map(|src| src.net.clone()),
-                Rail(rk) => Some(rk.to_string()),
-                Cut { .. } | Blockage { .. } => continue,
-            };
-            // Convert the inner shape
-            let inner = match track.data.dir {
-                Dir::Horiz => raw::Shape::Rect(raw::Rect {
-                    p0: self.export_point(seg.start, track.data.start),
-                    p1: self.export_point(seg.stop, track.data.start + track.data.width),
-                }),
-                Dir::Vert => raw::Shape::Rect(raw::Rect {
-                    p0: self.export_point(track.data.start, seg.start),
-                    p1: self.export_point(track.data.start + track.data.width, seg.stop),
-                }),
-            };
-            // And pack it up as a [raw::Element]
-            let e = raw::Element {
-                net,
-                layer: self.stack.metal(layer.index)?.raw.unwrap(),
-                purpose: raw::LayerPurpose::Drawing,
-                inner,
-            };
-            elems.push(e);
+        period: usize,
+        elems: &mut Vec<raw::Element>,
+        prov: Option<&mut Vec<Provenance>>,
+    ) -> LayoutResult<()> {
+        if self.dry_run {
+            return Ok(());
         }
-        Ok(elems)
+        let min = self.opts.min_segment.unwrap_or(layer.spec.cutsize);
+        render_track_impl(track, layer, min, elems, prov.map(|p| (p, period)))
     }
     /// Create a [TempCellLayer] for the intersection of `temp_cell` and `layer`
     fn temp_cell_layer<'a>(
@@ -637,36 +2710,52 @@ impl<'lib> RawExporter {
         temp_cell: &'a TempCell,
         layer: &'a validate::ValidMetalLayer,
     ) -> LayoutResult<TempCellLayer<'a>> {
-        // Sort out which of the cell's [Instance]s come up to this layer
+        let cell = temp_cell.cell;
+        let (span, nperiods) = self.layer_extent(cell, layer)?;
+
+        // Sort out which of the cell's [Instance]s come up to this layer, and which periods each blocks
         let mut instances = Vec::with_capacity(temp_cell.instances.len());
         for ptr in temp_cell.instances.iter() {
             let inst = ptr.read()?;
-            let cell = inst.cell.read()?;
-            if cell.metals()? > layer.index {
-                instances.push(ptr.clone());
+            let child = inst.cell.read()?;
+            if child.metals()? > layer.index {
+                let periods = self.instance_periods(&inst, layer, nperiods, &cell.name)?;
+                let occupied = self.raw_occupancy(&inst, &child, layer)?;
+                instances.push((ptr.clone(), periods, occupied));
             }
         }
-        // And convert it to a [PtrList]
-        let instances = PtrList::from_ptrs(instances);
-
-        // Sort out which direction we're working across
-        let cell = temp_cell.cell;
-        // Convert to database units
-        let x = self.db_units(cell.outline.x[0]); // FIXME: rectangles implied here
-        let y = self.db_units(cell.outline.y[0]);
-        let (span, breadth) = match layer.spec.dir {
-            Dir::Horiz => (x, y),
-            Dir::Vert => (y, x),
-        };
 
-        // FIXME: move to `validate` stage
-        if (breadth % layer.pitch) != 0 {
-            return self.fail(format!(
-                "{} has invalid dimension on {}: {:?}, must be multiple of {:?}",
-                cell.name, layer.spec.name, breadth, layer.pitch,
-            ));
+        // Arrange cuts and assignments by period, once per layer, rather than filtering them per period.
+        // Those beyond the last period are dropped.
+        let mut cuts = vec![Vec::new(); nperiods];
+        for cut in temp_cell.cuts[layer.index].iter() {
+            let (period, _) = layer.locate(cut.track.track)?;
+            if let Some(period) = cuts.get_mut(period) {
+                period.push(*cut);
+            }
+        }
+        let mut top_assns = vec![Vec::new(); nperiods];
+        for id in temp_cell.top_assns[layer.index].iter() {
+            let assn = self.unwrap(
+                temp_cell.assignments.get(*id),
+                "Internal error: invalid assignment",
+            )?;
+            let (period, _) = layer.locate(assn.top.track)?;
+            if let Some(period) = top_assns.get_mut(period) {
+                period.push(*id);
+            }
+        }
+        let mut bot_assns = vec![Vec::new(); nperiods];
+        for id in temp_cell.bot_assns[layer.index].iter() {
+            let assn = self.unwrap(
+                temp_cell.assignments.get(*id),
+                "Internal error: invalid assignment",
+            )?;
+            let (period, _) = layer.locate(assn.bot.track)?;
+            if let Some(period) = bot_assns.get_mut(period) {
+                period.push(*id);
+            }
         }
-        let nperiods = usize::try_from(breadth / layer.pitch).unwrap(); // FIXME: errors
         Ok(TempCellLayer {
             layer,
             cell: temp_cell,
@@ -674,6 +2763,9 @@ impl<'lib> RawExporter {
             nperiods,
             pitch: layer.pitch,
             span,
+            cuts,
+            top_assns,
+            bot_assns,
         })
     }
     /// Create the [TempPeriod] at the intersection of `temp_layer` and `periodnum`
@@ -689,116 +2781,206 @@ impl<'lib> RawExporter {
         // For each row, decide which instances intersect
         // Convert these into blockage-areas for the tracks
         let mut blockages = Vec::with_capacity(temp_layer.instances.len());
-        for ptr in temp_layer.instances.iter() {
-            let inst = &*ptr.read()?;
-            if self.instance_intersects(inst, layer, periodnum)? {
-                // Create the blockage
-                let cell = inst.cell.read()?;
-                let start = inst.loc.abs()?[dir];
-                let stop = start + cell.outline()?.max(dir);
-                blockages.push((start, stop, ptr.clone()));
-            }
-        }
-
-        // Grab indices of the relevant tracks for this period
-        let nsig = temp_layer.layer.period_data.signals.len();
-        let relevant_track_nums = (periodnum * nsig, (periodnum + 1) * nsig);
-        // Filter cuts down to those in this period
-        let cuts: Vec<&TrackCross> = cell.cuts[temp_layer.layer.index]
-            .iter()
-            .filter(|cut| {
-                cut.track.track >= relevant_track_nums.0 && cut.track.track < relevant_track_nums.1
-            })
-            .map(|r| *r)
-            .collect();
-        // Filter assignments down to those in this period
-        let top_assns = cell.top_assns[temp_layer.layer.index]
-            .iter()
-            .filter(|id| {
-                let assn = cell
-                    .assignments
-                    .get(**id)
-                    .ok_or(LayoutError::from("Internal error: invalid assignment"))
-                    .unwrap();
-                assn.top.track >= relevant_track_nums.0 && assn.top.track < relevant_track_nums.1
-            })
-            .copied()
-            .collect();
-        let bot_assns = cell.bot_assns[temp_layer.layer.index]
-            .iter()
-            .filter(|id| {
-                let assn = cell
-                    .assignments
-                    .get(**id)
-                    .ok_or(LayoutError::from("Internal error: invalid assignment"))
-                    .unwrap();
-                assn.bot.track >= relevant_track_nums.0 && assn.bot.track < relevant_track_nums.1
-            })
-            .copied()
-            .collect();
+        let mut track_blockages = Vec::new();
+        for (ptr, periods, occupied) in temp_layer.instances.iter() {
+            if !periods.contains(&periodnum) {
+                continue;
+            }
+            match occupied {
+                Some(occupied) => {
+                    // Raw-layout instances block only the tracks their shapes occupy
+                    for occ in occupied.iter() {
+                        let (period, idx) = layer.locate(occ.track)?;
+                        if period != periodnum {
+                            continue;
+                        }
+                        for (start, stop) in occ.intervals.iter() {
+                            track_blockages.push((idx, *start, *stop, ptr.clone()));
+                        }
+                    }
+                }
+                None => {
+                    let inst = &*ptr.read()?;
+                    // Create the blockage, across the (potentially reflected) instance extent
+                    let bbox = inst.boundbox()?;
+                    let (start, stop) = (bbox.p0[dir], bbox.p1[dir]);
+                    blockages.push((start, stop, BlockageSrc::Instance(ptr.clone())));
+                }
+            }
+        }
+        // Add blockages for each intersecting [Keepout], across its extent
+        for (idx, keepout) in cell.cell.keepouts.iter().enumerate() {
+            if !keepout.layers.contains(&layer.index) {
+                continue;
+            }
+            // Check for intersection in the layer's periodic dimension, as for instances
+            let span = keepout.extent(!dir);
+            let min = self.db_units(PrimPitches::try_new(!dir, span.start)?)?;
+            let max = self.db_units(PrimPitches::try_new(!dir, span.end)?)?;
+            if max > layer.pitch * periodnum && min < layer.pitch * (periodnum + 1) {
+                let extent = keepout.extent(dir);
+                let start = PrimPitches::try_new(dir, extent.start)?;
+                let stop = PrimPitches::try_new(dir, extent.end)?;
+                blockages.push((start, stop, BlockageSrc::Keepout(idx)));
+            }
+        }
+
+        // Grab the cuts and assignments in this period
+        let cuts = &temp_layer.cuts[periodnum];
+        let top_assns = &temp_layer.top_assns[periodnum];
+        let bot_assns = &temp_layer.bot_assns[periodnum];
 
         Ok(TempPeriod {
             periodnum,
             cell,
             layer: temp_layer,
             blockages,
+            track_blockages,
             cuts,
             top_assns,
             bot_assns,
         })
     }
-    /// Boolean indication of whether `inst` intersects `layer` at `periodnum`
+    /// Get the range of `layer`'s periods which `inst` intersects, i.e. those in which it creates blockages.
+    /// "Touching" a period edge-to-edge is *not* considered an intersection.
+    /// Fails if `inst` extends beyond the `nperiods` periods of its parent cell `parent`.
     /// FIXME: rectangular only for now
-    fn instance_intersects(
+    fn instance_periods(
         &self,
         inst: &Instance,
         layer: &validate::ValidMetalLayer,
-        periodnum: usize,
-    ) -> LayoutResult<bool> {
+        nperiods: usize,
+        parent: &str,
+    ) -> LayoutResult<Range<usize>> {
         // Grab the layer's *periodic* direction
         let dir = !layer.spec.dir;
         // Get its starting location in that dimension
-        let inst_start = self.db_units(inst.loc.abs()?[dir]);
-        // Sort out whether it's been reflected in this direction
-        let reflected = match dir {
-            Dir::Horiz => inst.reflect_horiz,
-            Dir::Vert => inst.reflect_vert,
-        };
+        let inst_start = self.db_units(inst.loc.abs()?[dir])?;
         // Grab the span of the cell-outline
         let span = {
             let cell = inst.cell.read()?;
-            self.db_units(cell.outline()?.max(dir))
+            self.db_units(cell.outline()?.max(dir))?
         };
         // And sort out the span of the [Instance], from its cell-outline and reflection
-        let (inst_min, inst_max) = if !reflected {
+        let (inst_min, inst_max) = if !inst.reflected(dir) {
             (inst_start, inst_start + span)
         } else {
             (inst_start - span, inst_start)
         };
-        // And return the boolean intersection. "Touching" edge-to-edge is *not* considered an intersection.
-        Ok(inst_max > layer.pitch * periodnum && inst_min < layer.pitch * (periodnum + 1))
+        if inst_min < DbUnits(0) || inst_max > layer.pitch * nperiods {
+            return self.fail(format!(
+                "Instance {} extends beyond Cell {} on Layer {}: {:?} to {:?}, outside 0 to {:?}",
+                inst.inst_name,
+                parent,
+                layer.spec.name,
+                inst_min,
+                inst_max,
+                layer.pitch * nperiods
+            ));
+        }
+        // Convert to the half-open range of periods, rounding outward
+        let pitch = layer.pitch.raw();
+        let start = inst_min.raw().div_euclid(pitch);
+        let stop = (inst_max.raw() + pitch - 1).div_euclid(pitch);
+        Ok(usize::try_from(start)?..usize::try_from(stop)?)
+    }
+    /// Get the extent of `layout` along `layer`, and its number of periods of `layer`
+    fn layer_extent(
+        &self,
+        layout: &Layout,
+        layer: &validate::ValidMetalLayer,
+    ) -> LayoutResult<(DbUnits, usize)> {
+        // Sort out which direction we're working across
+        // Convert to database units
+        let x = self.db_units(layout.outline.x[0])?; // FIXME: rectangles implied here
+        let y = self.db_units(layout.outline.y[0])?;
+        let (span, breadth) = match layer.spec.dir {
+            Dir::Horiz => (x, y),
+            Dir::Vert => (y, x),
+        };
+
+        // FIXME: move to `validate` stage
+        if (breadth % layer.pitch) != 0 {
+            return self.fail(format!(
+                "{} has invalid dimension on {}: {:?}, must be multiple of {:?}",
+                layout.name, layer.spec.name, breadth, layer.pitch,
+            ));
+        }
+        let nperiods = usize::try_from(breadth / layer.pitch)?;
+        Ok((span, nperiods))
+    }
+    /// Get the tracks of `layer` occupied by `inst` of raw-layout cell `cell`, in the coordinates of its parent,
+    /// per [RawTrackOccupancy::occupied_tracks].
+    /// Returns `None` if `inst` should instead block its full extent: if `cell` has no raw layout,
+    /// if `layer` is not its top metal layer, or if its raw library does not share our stack's raw layers.
+    fn raw_occupancy(
+        &self,
+        inst: &Instance,
+        cell: &cell::Cell,
+        layer: &validate::ValidMetalLayer,
+    ) -> LayoutResult<Option<Vec<TrackOccupancy>>> {
+        let raw = match cell.raw {
+            Some(ref raw) if raw.metals == layer.index + 1 => raw,
+            _ => return Ok(None),
+        };
+        if !self.shared_layers.contains(&raw.lib) {
+            return Ok(None);
+        }
+        let occupied = raw.cell.read()?.occupied_tracks(&self.stack, layer.index)?;
+        // Transform into the parent's frame, per the instance location and reflection
+        let dir = layer.spec.dir;
+        let loc = inst.loc.abs()?;
+        let (across, along) = (self.db_units(loc[!dir])?, self.db_units(loc[dir])?);
+        let mut parent = Vec::with_capacity(occupied.len());
+        for occ in occupied.into_iter() {
+            let center = layer.center(occ.track)?;
+            let center = if inst.reflected(!dir) {
+                across - center
+            } else {
+                across + center
+            };
+            let track = match layer.signed_track_at(center).and_then(|t| t.index()) {
+                Some(track) => track,
+                None => {
+                    return self.fail(format!(
+                        "Track {} of raw cell {} does not land on a track of layer {} in its parent, as Instance {}",
+                        occ.track, cell.name, layer.spec.name, inst.inst_name
+                    ))
+                }
+            };
+            let intervals = occ
+                .intervals
+                .iter()
+                .map(|(start, stop)| match inst.reflected(dir) {
+                    true => (along - *stop, along - *start),
+                    false => (along + *start, along + *stop),
+                })
+                .collect();
+            parent.push(TrackOccupancy { track, intervals });
+        }
+        Ok(Some(parent))
     }
-    /// Convert any [UnitSpeced]-convertible distances into [DbUnits]
-    fn db_units(&self, pt: impl Into<UnitSpeced>) -> DbUnits {
+    /// Convert any [UnitSpeced]-convertible distances into [DbUnits].
+    /// Fails if the conversion overflows, per [PrimPitches::to_db_units].
+    fn db_units(&self, pt: impl Into<UnitSpeced>) -> LayoutResult<DbUnits> {
         let pt: UnitSpeced = pt.into();
         match pt {
-            UnitSpeced::DbUnits(u) => u, // Return as-is
+            UnitSpeced::DbUnits(u) => Ok(u), // Return as-is
             UnitSpeced::PrimPitches(p) => {
                 // Multiply by the primitive pitch in `pt`s direction
-                let pitch = self.stack.prim.pitches[p.dir];
-                (p.num * pitch.raw()).into()
+                p.to_db_units(&self.stack.prim.pitches)
             }
             UnitSpeced::LayerPitches(_p) => {
                 // LayerPitches are always in the layer's "periodic" dimension
-                todo!()
+                self.fail("Conversion of LayerPitches to DbUnits is not (yet) supported")
             }
         }
     }
     /// Convert an [Xy] into a [raw::Point]
-    fn export_xy<T: HasUnits + Into<UnitSpeced>>(&self, xy: &Xy<T>) -> raw::Point {
-        let x = self.db_units(xy.x);
-        let y = self.db_units(xy.y);
-        self.export_point(x, y)
+    fn export_xy<T: HasUnits + Into<UnitSpeced>>(&self, xy: &Xy<T>) -> LayoutResult<raw::Point> {
+        let x = self.db_units(xy.x)?;
+        let y = self.db_units(xy.y)?;
+        Ok(self.export_point(x, y))
     }
     /// Convert a two-tuple of [DbUnits] into a [raw::Point]
     fn export_point(&self, x: DbUnits, y: DbUnits) -> raw::Point {
@@ -806,16 +2988,7 @@ impl<'lib> RawExporter {
     }
     /// Convert a [TrackCross] into an (x,y) ([Xy]) coordinate in [DbUnits]
     fn track_cross_xy(&self, i: &TrackCross) -> LayoutResult<Xy<DbUnits>> {
-        // Find the (x,y) center of our track, initially assuming it runs vertically
-        let x = self.stack.metal(i.track.layer)?.center(i.track.track)?;
-        let y = self.stack.metal(i.cross.layer)?.center(i.cross.track)?;
-
-        // And transpose if it's actually horizontal
-        let mut xy = Xy::new(x, y);
-        if self.stack.metal(i.track.layer)?.spec.dir == Dir::Horiz {
-            xy = xy.transpose();
-        }
-        Ok(xy)
+        self.stack.track_cross_xy(i)
     }
 }
 impl ErrorHelper for RawExporter {
@@ -0,0 +1,176 @@
+//!
+//! # Via Cell Generation
+//!
+//! Creates a [raw::Cell] for each variant of via, for instantiation wherever it is used,
+//! as an alternative to drawing each via's rectangles inline.
+//!
+
+// Std-lib
+use std::collections::BTreeMap;
+
+// Crates.io
+use serde::{Deserialize, Serialize};
+
+// Local imports
+use crate::{
+    coords::{DbUnits, HasUnits, Int, Xy},
+    raw::{self, LayoutError, LayoutResult},
+    stack::{ViaLayer, ViaTarget},
+    utils::Ptr,
+    validate::ValidStack,
+};
+
+/// # Via Specification
+///
+/// Dimensions of generated via cells: an array of `cuts` cut-shapes,
+/// separated by `space`, and enclosed by `enclosure` on each side in both metal layers.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViaSpec {
+    /// Number of cuts in (x, y)
+    pub cuts: Xy<usize>,
+    /// Space between adjacent cuts
+    pub space: DbUnits,
+    /// Metal enclosure beyond the cut array, on every side
+    pub enclosure: DbUnits,
+}
+impl Default for ViaSpec {
+    fn default() -> Self {
+        Self {
+            cuts: Xy::new(1, 1),
+            space: DbUnits(0),
+            enclosure: DbUnits(0),
+        }
+    }
+}
+impl ViaSpec {
+    /// Get the deterministic name of the via cell for [ViaLayer] `via`,
+    /// e.g. `via1_1x1_enc5`, or `via1_2x2_sp10_enc5` for multi-cut vias.
+    pub fn cell_name(&self, via: &ViaLayer) -> String {
+        let space = if self.cuts.x * self.cuts.y > 1 {
+            format!("_sp{}", self.space.raw())
+        } else {
+            String::new()
+        };
+        format!(
+            "{}_{}x{}{}_enc{}",
+            via.name,
+            self.cuts.x,
+            self.cuts.y,
+            space,
+            self.enclosure.raw()
+        )
+    }
+}
+
+/// # Via Cell Generator
+///
+/// Lazily creates, and then shares, one [raw::Cell] per via-layer for a [ViaSpec].
+/// Cells for other specs, e.g. named via variants, are available via [ViaGenerator::cell_with],
+/// and are shared by name with those of the default spec.
+/// Each cell is centered at its origin, and includes its cuts plus enclosures on its bottom and top metal layers.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ViaGenerator {
+    /// Via Specification
+    pub spec: ViaSpec,
+    /// Generated cells, by name
+    cells: BTreeMap<String, Ptr<raw::Cell>>,
+}
+impl ViaGenerator {
+    /// Create a new [ViaGenerator] for `spec`
+    pub fn new(spec: ViaSpec) -> Self {
+        Self {
+            spec,
+            cells: BTreeMap::new(),
+        }
+    }
+    /// Get the cell for [ViaLayer] `via`, creating it on first use
+    pub fn cell(&mut self, stack: &ValidStack, via: &ViaLayer) -> LayoutResult<Ptr<raw::Cell>> {
+        let spec = self.spec;
+        self.cell_with(stack, via, &spec)
+    }
+    /// Get the cell for [ViaLayer] `via` per [ViaSpec] `spec`, rather than our default spec,
+    /// creating it on first use
+    pub fn cell_with(
+        &mut self,
+        stack: &ValidStack,
+        via: &ViaLayer,
+        spec: &ViaSpec,
+    ) -> LayoutResult<Ptr<raw::Cell>> {
+        let name = spec.cell_name(via);
+        if let Some(cell) = self.cells.get(&name) {
+            return Ok(cell.clone());
+        }
+        let cell = Ptr::new(self.generate(spec, stack, via, &name)?);
+        self.cells.insert(name, cell.clone());
+        Ok(cell)
+    }
+    /// Get all generated cells, in name order
+    pub fn cells(&self) -> Vec<Ptr<raw::Cell>> {
+        self.cells.values().cloned().collect()
+    }
+    /// Generate the cell named `name` for [ViaLayer] `via`, per `spec`
+    fn generate(
+        &self,
+        spec: &ViaSpec,
+        stack: &ValidStack,
+        via: &ViaLayer,
+        name: &str,
+    ) -> LayoutResult<raw::Cell> {
+        if spec.cuts.x == 0 || spec.cuts.y == 0 {
+            return LayoutError::fail(format!("Invalid via {} with no cuts", name));
+        }
+        let cut_layer = match via.raw {
+            Some(k) => k,
+            None => return LayoutError::fail(format!("Via layer {} has no raw layer", via.name)),
+        };
+        // Size of the cut array, from which all shapes are centered on the origin
+        let (nx, ny) = (Int::try_from(spec.cuts.x)?, Int::try_from(spec.cuts.y)?);
+        let span = |n: Int, size: DbUnits| DbUnits(n * size.raw() + (n - 1) * spec.space.raw());
+        let span = Xy::new(span(nx, via.size.x), span(ny, via.size.y));
+        let (x0, y0) = (-span.x.raw() / 2, -span.y.raw() / 2);
+
+        let mut elems = Vec::new();
+        for ix in 0..nx {
+            for iy in 0..ny {
+                let px = x0 + ix * (via.size.x.raw() + spec.space.raw());
+                let py = y0 + iy * (via.size.y.raw() + spec.space.raw());
+                let p1 = raw::Point::new(px + via.size.x.raw(), py + via.size.y.raw());
+                elems.push(rect(cut_layer, raw::Point::new(px, py), p1));
+            }
+        }
+        // Enclosures, on each connected metal layer
+        let enc = spec.enclosure.raw();
+        for target in [&via.bot, &via.top] {
+            if let ViaTarget::Metal(idx) = target {
+                let layer = match stack.metal(*idx)?.raw {
+                    Some(k) => k,
+                    None => {
+                        return LayoutError::fail(format!("Metal layer {} has no raw layer", idx))
+                    }
+                };
+                let p0 = raw::Point::new(x0 - enc, y0 - enc);
+                let p1 = raw::Point::new(x0 + span.x.raw() + enc, y0 + span.y.raw() + enc);
+                elems.push(rect(layer, p0, p1));
+            }
+        }
+        let mut cell = raw::Cell::new(name);
+        cell.layout = Some(raw::Layout {
+            name: name.into(),
+            elems,
+            ..Default::default()
+        });
+        Ok(cell)
+    }
+}
+/// Create a net-less drawing rectangle on `layer`
+fn rect(layer: raw::LayerKey, p0: raw::Point, p1: raw::Point) -> raw::Element {
+    raw::Element {
+        net: None,
+        layer,
+        purpose: raw::LayerPurpose::Drawing,
+        inner: raw::Shape::Rect(raw::Rect { p0, p1 }),
+        labels: Vec::new(),
+    }
+}
@@ -11,7 +11,7 @@ use derive_more::{Add, AddAssign, DivAssign, From, MulAssign, Sub, SubAssign, Su
 use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::raw::Dir;
+use crate::raw::{Dir, LayoutError, LayoutResult};
 
 /// # Location Integer Type-Alias
 ///
@@ -80,8 +80,23 @@ impl HasUnits for UnitSpeced {
     Eq,
     PartialOrd,
     Ord,
+    Hash,
 )]
 pub struct DbUnits(pub Int);
+impl DbUnits {
+    /// Checked addition. Returns `None` on overflow.
+    pub fn checked_add(self, rhs: DbUnits) -> Option<DbUnits> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+    /// Checked subtraction. Returns `None` on overflow.
+    pub fn checked_sub(self, rhs: DbUnits) -> Option<DbUnits> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+    /// Checked multiplication by a scalar. Returns `None` on overflow.
+    pub fn checked_mul(self, rhs: Int) -> Option<DbUnits> {
+        self.0.checked_mul(rhs).map(Self)
+    }
+}
 impl HasUnits for DbUnits {
     /// Every so often we need the raw number, fine. Use sparingly.
     #[inline(always)]
@@ -89,6 +104,23 @@ impl HasUnits for DbUnits {
         self.0
     }
 }
+impl TryFrom<usize> for DbUnits {
+    type Error = std::num::TryFromIntError;
+    fn try_from(val: usize) -> Result<Self, Self::Error> {
+        Ok(Self(Int::try_from(val)?))
+    }
+}
+impl TryFrom<DbUnits> for i32 {
+    type Error = std::num::TryFromIntError;
+    fn try_from(val: DbUnits) -> Result<Self, Self::Error> {
+        i32::try_from(val.0)
+    }
+}
+impl std::fmt::Display for DbUnits {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 impl std::ops::Div<DbUnits> for DbUnits {
     type Output = Int;
     fn div(self, rhs: DbUnits) -> Self::Output {
@@ -139,10 +171,40 @@ impl PrimPitches {
     pub fn y(num: Int) -> Self {
         Self::new(Dir::Vert, num)
     }
+    /// Create a new [PrimPitches] from unsigned `num`, e.g. a [Keepout](crate::layout::Keepout) or strap extent.
+    /// Fails if `num` exceeds the range of [Int].
+    pub fn try_new(dir: Dir, num: usize) -> LayoutResult<Self> {
+        match Int::try_from(num) {
+            Ok(num) => Ok(Self::new(dir, num)),
+            Err(_) => LayoutError::fail(format!(
+                "Primitive-pitch count {} exceeds the coordinate range",
+                num
+            )),
+        }
+    }
     /// Create a new [PrimPitches] with opposite sign of `self.num`
     pub fn negate(&self) -> Self {
         Self::new(self.dir, -self.num)
     }
+    /// Convert to [DbUnits], given the primitive `pitches` of a [Stack](crate::stack::Stack).
+    ///
+    /// The single conversion between the two, used for all converter arithmetic.
+    /// Multiplies by the pitch in our direction, failing on overflow rather than wrapping.
+    pub fn to_db_units(&self, pitches: &Xy<DbUnits>) -> LayoutResult<DbUnits> {
+        let pitch = pitches[self.dir];
+        match pitch.checked_mul(self.num) {
+            Some(val) => Ok(val),
+            None => LayoutError::fail(format!(
+                "{} primitive pitches of {} in {} overflows the coordinate range",
+                self, pitch, self.dir
+            )),
+        }
+    }
+}
+impl std::fmt::Display for PrimPitches {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.num)
+    }
 }
 impl HasUnits for PrimPitches {
     /// Every so often we need the raw number, fine. Use sparingly.
@@ -264,6 +326,106 @@ impl std::ops::MulAssign<usize> for LayerPitches {
     }
 }
 
+/// # Metal-Layer Index
+///
+/// Index of a metal layer into its [Stack](crate::stack::Stack)'s `metals`.
+/// Serialized transparently, as its underlying [usize].
+///
+/// Distinct from [TrackIndex], and taken by value rather than via `Into`,
+/// so that passing a track index, or a bare [usize], in place of a layer index is a compile-time error.
+///
+/// ```compile_fail
+/// use layout21tetris::prelude::*;
+/// // Layer and track swapped
+/// let _ = TrackRef::new(TrackIndex(1), LayerIndex(2));
+/// ```
+#[derive(
+    Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(transparent)]
+pub struct LayerIndex(pub usize);
+impl LayerIndex {
+    /// Get the underlying [usize] index
+    #[inline(always)]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+impl From<LayerIndex> for usize {
+    fn from(i: LayerIndex) -> Self {
+        i.0
+    }
+}
+impl std::fmt::Display for LayerIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+/// # Track Index
+///
+/// Index of a [Track](crate::tracks::Track) within its metal layer.
+/// Serialized transparently, as its underlying [usize].
+///
+/// Distinct from [LayerIndex], and taken by value rather than via `Into`,
+/// so that passing a layer index, or a bare [usize], in place of a track index is a compile-time error.
+#[derive(
+    Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(transparent)]
+pub struct TrackIndex(pub usize);
+impl TrackIndex {
+    /// Get the underlying [usize] index
+    #[inline(always)]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+impl std::ops::Add<usize> for TrackIndex {
+    type Output = Self;
+    fn add(self, rhs: usize) -> Self::Output {
+        Self(self.0 + rhs)
+    }
+}
+impl std::ops::AddAssign<usize> for TrackIndex {
+    fn add_assign(&mut self, rhs: usize) {
+        self.0 += rhs;
+    }
+}
+impl From<TrackIndex> for usize {
+    fn from(i: TrackIndex) -> Self {
+        i.0
+    }
+}
+impl std::fmt::Display for TrackIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+/// # Signed Track Index
+///
+/// Intermediate track index which may be negative,
+/// e.g. a child's track after reflecting or offsetting its instance near the parent origin.
+/// Transform math is done in [SignedTrack]s, and converted back to [TrackIndex]
+/// via the fallible [SignedTrack::index] at API boundaries.
+#[derive(From, Add, Sub, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignedTrack(pub Int);
+impl SignedTrack {
+    /// Convert to an unsigned [TrackIndex]. Returns `None` if negative.
+    pub fn index(self) -> Option<TrackIndex> {
+        usize::try_from(self.0).ok().map(TrackIndex)
+    }
+}
+impl From<TrackIndex> for SignedTrack {
+    fn from(i: TrackIndex) -> Self {
+        Self(i.0 as Int)
+    }
+}
+impl std::fmt::Display for SignedTrack {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Paired "type" zero-data enum for [UnitSpeced]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum UnitType {
@@ -0,0 +1,357 @@
+//!
+//! # Pin-Escape Analysis
+//!
+//! Estimates whether the ports of an [Instance] can be routed out of it, before committing to a floorplan.
+//! For each port, counts the free track segments on its adjacent metal layers,
+//! running from just outside the port's location out to the parent [Layout]'s outline.
+//! Segments are free if no other [Instance], [Keepout](crate::layout::Keepout), cut, or net-assignment lies upon them.
+//!
+
+// Local imports
+use crate::abs::{PortKind, Side};
+use crate::bbox::HasBoundBox;
+use crate::coords::{DbUnits, HasUnits, LayerIndex, PrimPitches, TrackIndex};
+use crate::instance::{Instance, PortLoc};
+use crate::layout::Layout;
+use crate::raw::{Dir, LayoutError, LayoutResult};
+use crate::tracks::TrackRef;
+use crate::validate::{ValidMetalLayer, ValidStack};
+
+/// # Escape Report
+///
+/// Results of escape analysis of a single [Instance], as produced by [Layout::escape_analysis].
+/// Ports without any free escapes, listed by [EscapeReport::trapped], cannot be routed in the parent as placed.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EscapeReport {
+    /// Instance Name
+    pub inst: String,
+    /// Per-Port Escapes, in port order
+    pub ports: Vec<PortEscapes>,
+}
+impl EscapeReport {
+    /// Get all ports without any free escape
+    pub fn trapped(&self) -> Vec<&PortEscapes> {
+        self.ports.iter().filter(|p| p.escapes.is_empty()).collect()
+    }
+    /// Boolean indication of whether every port has at least one free escape
+    pub fn is_clean(&self) -> bool {
+        self.trapped().is_empty()
+    }
+}
+/// # Escapes of a Single Port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortEscapes {
+    /// Port Name
+    pub port: String,
+    /// Metal Layer of the Port
+    pub layer: usize,
+    /// Free Escapes
+    pub escapes: Vec<Escape>,
+}
+/// # Port Escape
+///
+/// A free segment of `track`, running from the port's location to the parent outline on `side`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Escape {
+    /// Adjacent-Layer Track
+    pub track: TrackRef,
+    /// Side of the parent outline reached
+    pub side: Side,
+}
+
+/// Span of coordinates in a single dimension
+type Span = (DbUnits, DbUnits);
+
+/// # Escape Analyzer
+///
+/// Performs escape analysis of the [Instance]s of `layout`.
+///
+#[derive(Debug)]
+pub(crate) struct EscapeAnalyzer<'a> {
+    layout: &'a Layout,
+    stack: &'a ValidStack,
+}
+impl<'a> EscapeAnalyzer<'a> {
+    pub(crate) fn new(layout: &'a Layout, stack: &'a ValidStack) -> Self {
+        Self { layout, stack }
+    }
+    /// Analyze the escapes of each port of [Instance] `inst_name`
+    pub(crate) fn analyze(&self, inst_name: &str) -> LayoutResult<EscapeReport> {
+        let mut target = None;
+        for ptr in self.layout.instances.iter() {
+            if ptr.read()?.inst_name == inst_name {
+                target = Some(ptr.clone());
+                break;
+            }
+        }
+        let target = match target {
+            Some(ptr) => ptr,
+            None => {
+                return LayoutError::fail(format!(
+                    "No Instance {} in Layout {}",
+                    inst_name, self.layout.name
+                ))
+            }
+        };
+        let inst = &*target.read()?;
+        let cell = inst.cell.read()?;
+        let abs = match cell.abs {
+            Some(ref abs) => abs,
+            None => {
+                return LayoutError::fail(format!(
+                    "Escape analysis of Instance {} requires an Abstract of Cell {}",
+                    inst_name, cell.name
+                ))
+            }
+        };
+        let mut report = EscapeReport {
+            inst: inst_name.into(),
+            ports: Vec::with_capacity(abs.ports.len()),
+        };
+        for port in abs.ports.iter() {
+            let top = abs.metals.checked_sub(1);
+            let (layer, escapes) = match (&port.kind, top) {
+                (PortKind::Edge { layer, track, side }, _) => (
+                    layer.index(),
+                    self.port_escapes(inst, layer.index(), track.index(), *side)?,
+                ),
+                (PortKind::ZTopEdge { track, side, .. }, Some(top)) => {
+                    (top, self.port_escapes(inst, top, track.index(), *side)?)
+                }
+                (PortKind::ZTopInner { .. }, Some(top)) => {
+                    (top, self.landing_escapes(inst, &port.name, top)?)
+                }
+                (_, None) => {
+                    return LayoutError::fail(format!(
+                        "Port {} of Abstract {} has no metal layers",
+                        port.name, abs.name
+                    ))
+                }
+            };
+            report.ports.push(PortEscapes {
+                port: port.name.clone(),
+                layer,
+                escapes,
+            });
+        }
+        Ok(report)
+    }
+    /// Find the free escapes of the port on `track` of `layer`, at the `side` edge of `inst`
+    fn port_escapes(
+        &self,
+        inst: &Instance,
+        layer: usize,
+        track: usize,
+        side: Side,
+    ) -> LayoutResult<Vec<Escape>> {
+        let dir = self.stack.metal(LayerIndex(layer))?.spec.dir;
+        // Locate the port across its track, accounting for reflection
+        let origin = self.db_units(inst.loc.abs()?[!dir]);
+        let local = self
+            .stack
+            .metal(LayerIndex(layer))?
+            .center(TrackIndex(track))?;
+        let center = if inst.reflected(!dir) {
+            origin - local
+        } else {
+            origin + local
+        };
+        // And along it, at the instance edge
+        let outward = if inst.reflected(dir) {
+            side.opposite()
+        } else {
+            side
+        };
+        let (min, max) = self.inst_extent(inst, dir)?;
+        let edge = match outward {
+            Side::BottomOrLeft => min,
+            Side::TopOrRight => max,
+        };
+
+        let mut escapes = Vec::new();
+        let adjacent = [layer.checked_sub(1), Some(layer + 1)];
+        for adj in adjacent.into_iter().flatten() {
+            // Skip layers beyond the top of the stack
+            let adj = match self.stack.metal(LayerIndex(adj)) {
+                Ok(adj) => adj,
+                Err(_) => continue,
+            };
+            let (track, across) = match self.outside_track(adj, edge, outward)? {
+                Some(t) => t,
+                None => continue,
+            };
+            let track = TrackRef::new(LayerIndex(adj.index), TrackIndex(track));
+            // Check the segments running in each direction from the port to the outline
+            let limit = self.db_units(self.layout.outline.max(!dir));
+            for (side, along) in [
+                (Side::BottomOrLeft, (DbUnits(0), center)),
+                (Side::TopOrRight, (center, limit)),
+            ] {
+                if self.is_free(&track, across, along)? {
+                    escapes.push(Escape { track, side });
+                }
+            }
+        }
+        Ok(escapes)
+    }
+    /// Find the free escapes of the [PortKind::ZTopInner] port `name` of `inst`, on top-layer `layer`.
+    /// Escapes run along the track of the layer above which passes over each of its locations,
+    /// from the location out to the parent outline. Any single reachable location makes the port reachable.
+    fn landing_escapes(
+        &self,
+        inst: &Instance,
+        name: &str,
+        layer: usize,
+    ) -> LayoutResult<Vec<Escape>> {
+        // Ports on the top of the stack have no layer above
+        let above = match self.stack.metal(LayerIndex(layer + 1)) {
+            Ok(above) => above,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let locs = match inst.port_loc(name, self.stack)? {
+            PortLoc::ZTopInner { locs, .. } => locs,
+            _ => return LayoutError::fail(format!("Port {} is not a ZTopInner port", name)),
+        };
+        let limit = self.db_units(self.layout.outline.max(above.spec.dir));
+        let mut escapes = Vec::new();
+        for cross in locs.iter() {
+            // Locate the landing point along the layer above, from the top-layer track,
+            // and across it, from the intersecting track
+            let along = self
+                .stack
+                .metal(LayerIndex(layer))?
+                .center(cross.track.track)?;
+            let across = self
+                .stack
+                .metal(cross.cross.layer)?
+                .center(cross.cross.track)?;
+            let (track, span) = match self.track_over(above, across)? {
+                Some(t) => t,
+                None => continue, // Lands between tracks of the layer above
+            };
+            let track = TrackRef::new(LayerIndex(above.index), TrackIndex(track));
+            for (side, seg) in [
+                (Side::BottomOrLeft, (DbUnits(0), along)),
+                (Side::TopOrRight, (along, limit)),
+            ] {
+                let escape = Escape { track, side };
+                if !escapes.contains(&escape) && self.is_free(&track, span, seg)? {
+                    escapes.push(escape);
+                }
+            }
+        }
+        Ok(escapes)
+    }
+    /// Find the signal track of `layer` whose span includes `dist`, if any. Returns the track index and its span.
+    fn track_over(
+        &self,
+        layer: &ValidMetalLayer,
+        dist: DbUnits,
+    ) -> LayoutResult<Option<(usize, Span)>> {
+        let nsig = layer.period_data.signals.len();
+        if dist < DbUnits(0) {
+            return Ok(None);
+        }
+        let first = usize::try_from(dist / layer.pitch)? * nsig;
+        for idx in first..first + nsig {
+            let span = layer.span(TrackIndex(idx))?;
+            if span.0 <= dist && dist <= span.1 {
+                return Ok(Some((idx, span)));
+            }
+        }
+        Ok(None)
+    }
+    /// Find the first track of `adj` lying entirely beyond `edge` in direction `outward`,
+    /// and within the parent outline. Returns the track index and its span.
+    fn outside_track(
+        &self,
+        adj: &ValidMetalLayer,
+        edge: DbUnits,
+        outward: Side,
+    ) -> LayoutResult<Option<(usize, Span)>> {
+        let nsig = adj.period_data.signals.len();
+        if nsig == 0 || edge < DbUnits(0) {
+            return Ok(None);
+        }
+        // Candidates lie in the period of `edge`, or one to either side
+        let period = usize::try_from(edge / adj.pitch)?;
+        let first = period.saturating_sub(1) * nsig;
+        let mut candidates = Vec::with_capacity(3 * nsig);
+        for idx in first..first + 3 * nsig {
+            candidates.push((idx, adj.span(TrackIndex(idx))?));
+        }
+        let found = match outward {
+            Side::TopOrRight => candidates.into_iter().find(|(_, s)| s.0 >= edge),
+            Side::BottomOrLeft => candidates.into_iter().rev().find(|(_, s)| s.1 <= edge),
+        };
+        let limit = self.db_units(self.layout.outline.max(!adj.spec.dir));
+        Ok(found.filter(|(_, s)| s.0 >= DbUnits(0) && s.1 <= limit))
+    }
+    /// Boolean indication of whether the segment of `track` spanning `across` and `along`
+    /// is free of instances, keep-outs, cuts, and assignments
+    fn is_free(&self, track: &TrackRef, across: Span, along: Span) -> LayoutResult<bool> {
+        // Dimension across the track, i.e. its periodic dimension
+        let dir = !self.stack.metal(track.layer)?.spec.dir;
+        for ptr in self.layout.instances.iter() {
+            let inst = &*ptr.read()?;
+            if inst.cell.read()?.metals()? <= track.layer.index() {
+                continue;
+            }
+            if overlaps(self.inst_extent(inst, dir)?, across)
+                && overlaps(self.inst_extent(inst, !dir)?, along)
+            {
+                return Ok(false);
+            }
+        }
+        for keepout in self.layout.keepouts.iter() {
+            if !keepout.layers.contains(&track.layer.index()) {
+                continue;
+            }
+            let extent = |dir: Dir| {
+                let span = keepout.extent(dir);
+                (
+                    self.db_units(PrimPitches::new(dir, span.start as isize)),
+                    self.db_units(PrimPitches::new(dir, span.end as isize)),
+                )
+            };
+            if overlaps(extent(dir), across) && overlaps(extent(!dir), along) {
+                return Ok(false);
+            }
+        }
+        // Cuts and assignments block the segment if they lie anywhere along it
+        let within = |cross: &TrackRef| -> LayoutResult<bool> {
+            let loc = self.stack.metal(cross.layer)?.center(cross.track)?;
+            Ok(loc >= along.0 && loc <= along.1)
+        };
+        for cut in self.layout.cuts.iter() {
+            if cut.track == *track && within(&cut.cross)? {
+                return Ok(false);
+            }
+        }
+        for assn in self.layout.assignments.iter() {
+            let at = &assn.at;
+            if (at.track == *track && within(&at.cross)?)
+                || (at.cross == *track && within(&at.track)?)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+    /// Get the extent of `inst` in direction `dir`
+    fn inst_extent(&self, inst: &Instance, dir: Dir) -> LayoutResult<Span> {
+        let bbox = inst.boundbox()?;
+        Ok((self.db_units(bbox.p0[dir]), self.db_units(bbox.p1[dir])))
+    }
+    /// Convert [PrimPitches] `p` to [DbUnits]
+    fn db_units(&self, p: PrimPitches) -> DbUnits {
+        DbUnits(p.num * self.stack.prim.pitches[p.dir].raw())
+    }
+}
+/// Boolean indication of whether spans `a` and `b` overlap.
+/// "Touching" end-to-end is *not* considered an overlap.
+fn overlaps(a: Span, b: Span) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
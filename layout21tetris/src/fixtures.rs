@@ -0,0 +1,601 @@
+//!
+//! # Test Fixtures
+//!
+//! Sample [Stack]s, and generators of synthetic [Library]s built against them,
+//! for tests and benchmarks. Available in unit tests, and elsewhere via the `fixtures` feature.
+//! Also includes a corpus of [Malformed] libraries, which conversion must handle without panicking.
+//!
+
+// Std-Lib Imports
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+
+// Local imports
+use crate::abs;
+use crate::array::{Array, ArrayInstance, Arrayable};
+use crate::cell::Cell;
+use crate::coords::{DbUnits, Int, LayerIndex, PrimPitches, TrackIndex};
+use crate::instance::Instance;
+use crate::library::Library;
+use crate::net::NetNamePolicy;
+use crate::outline::Outline;
+use crate::placement::{Align, Place, Placeable, RelativePlace, SepBy, Separation, Side};
+use crate::raw::{self, Dir, LayoutResult, Units};
+use crate::stack::*;
+use crate::tracks::*;
+use crate::utils::Ptr;
+use crate::validate::ValidStack;
+
+/// # Sample Stacks
+/// Namespace for commonly re-used [Stack]s for testing.
+pub struct SampleStacks;
+
+impl SampleStacks {
+    /// As nearly empty a [Stack] as possible, while being raw-exportable.
+    /// Includes:
+    /// * A `boundary_layer`
+    /// * [raw::Layers] containing solely that boundary layer
+    /// * No metals or via layers
+    ///
+    /// Generally useful for placement activities, particularly among [Instance]s.
+    pub fn empty() -> LayoutResult<ValidStack> {
+        let mut rawlayers = raw::Layers::default();
+        let boundary_layer = Some(rawlayers.add(raw::Layer::from_pairs(
+            0,
+            &[(0, raw::LayerPurpose::Outline)],
+        )?));
+        let stack = Stack {
+            schema: STACK_SCHEMA,
+            units: Units::default(),
+            boundary_layer,
+            prim: PrimitiveLayer::new((100, 100).into()),
+            metals: Vec::new(), // No metal layers
+            vias: Vec::new(),   // No vias
+            rawlayers: Some(Ptr::new(rawlayers)),
+            nets: NetNamePolicy::default(),
+            site: None,
+        };
+        stack.validate()
+    }
+
+    /// Real(istic) PDK [Stack]
+    pub fn pdka() -> LayoutResult<ValidStack> {
+        Self::pdka_stack()?.validate()
+    }
+    /// Un-validated version of [SampleStacks::pdka]
+    pub fn pdka_stack() -> LayoutResult<Stack> {
+        let mut rawlayers = raw::Layers::default();
+        // Shorthands for the common purpose-numbers
+        let metal_purps = [
+            (255, raw::LayerPurpose::Obstruction),
+            (20, raw::LayerPurpose::Drawing),
+            (5, raw::LayerPurpose::Label),
+            (16, raw::LayerPurpose::Pin),
+        ];
+        let via_purps = [
+            (255, raw::LayerPurpose::Obstruction),
+            (44, raw::LayerPurpose::Drawing),
+            (5, raw::LayerPurpose::Label),
+            (16, raw::LayerPurpose::Pin),
+        ];
+        // Add a few base-layers that we are used in imported/ primitive cells, but not in our stack
+        rawlayers.add(raw::Layer::new(64, "nwell").add_pairs(&metal_purps)?);
+        rawlayers.add(raw::Layer::new(67, "li1").add_pairs(&metal_purps)?);
+        // Create the test stack
+        let stack = Stack {
+            schema: STACK_SCHEMA,
+            units: Units::Nano,
+            boundary_layer: Some(rawlayers.add(raw::Layer::from_pairs(
+                236,
+                &[(0, raw::LayerPurpose::Outline)],
+            )?)),
+            prim: PrimitiveLayer {
+                pitches: (460, 2720).into(),
+            },
+            metals: vec![
+                MetalLayer {
+                    name: "met1".into(),
+                    entries: vec![
+                        TrackSpec::gnd(480),
+                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
+                        TrackSpec::gap(200),
+                        TrackSpec::pwr(480),
+                    ],
+                    dir: Dir::Horiz,
+                    offset: (-240).into(),
+                    cutsize: (250).into(),
+                    overlap: (480).into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(68, &metal_purps)?)),
+                    flip: FlipMode::EveryOther,
+                    prim: PrimitiveMode::Split,
+                    min_length: None,
+                    colors: 1,
+                    color_datatypes: Vec::new(),
+                },
+                MetalLayer {
+                    name: "met2".into(),
+                    entries: vec![TrackSpec::sig(140), TrackSpec::gap(320)],
+                    dir: Dir::Vert,
+                    cutsize: (250).into(),
+                    offset: (-70).into(),
+                    overlap: (0).into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(69, &metal_purps)?)),
+                    flip: FlipMode::None,
+                    prim: PrimitiveMode::Stack,
+                    min_length: None,
+                    colors: 1,
+                    color_datatypes: Vec::new(),
+                },
+                MetalLayer {
+                    name: "met3".into(),
+                    entries: vec![
+                        TrackSpec::gnd(480),
+                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
+                        TrackSpec::gap(200),
+                        TrackSpec::pwr(480),
+                    ],
+                    dir: Dir::Horiz,
+                    offset: (-240).into(),
+                    cutsize: (250).into(),
+                    overlap: (480).into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(70, &metal_purps)?)),
+                    flip: FlipMode::EveryOther,
+                    prim: PrimitiveMode::Stack,
+                    min_length: None,
+                    colors: 1,
+                    color_datatypes: Vec::new(),
+                },
+                MetalLayer {
+                    name: "met4".into(),
+                    entries: vec![
+                        TrackSpec::gnd(510),
+                        TrackSpec::repeat(vec![TrackEntry::gap(410), TrackEntry::sig(50)], 8),
+                        TrackSpec::gap(410),
+                        TrackSpec::pwr(510),
+                    ],
+                    dir: Dir::Vert,
+                    cutsize: (250).into(),
+                    offset: (-255).into(),
+                    overlap: (510).into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(71, &metal_purps)?)),
+                    flip: FlipMode::EveryOther,
+                    prim: PrimitiveMode::Stack,
+                    min_length: None,
+                    colors: 1,
+                    color_datatypes: Vec::new(),
+                },
+                MetalLayer {
+                    name: "met5".into(),
+                    entries: vec![
+                        TrackSpec::gnd(480),
+                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
+                        TrackSpec::gap(200),
+                        TrackSpec::pwr(480),
+                    ],
+                    dir: Dir::Horiz,
+                    offset: (-240).into(),
+                    cutsize: (250).into(),
+                    overlap: (480).into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(72, &metal_purps)?)),
+                    flip: FlipMode::EveryOther,
+                    prim: PrimitiveMode::Stack,
+                    min_length: None,
+                    colors: 1,
+                    color_datatypes: Vec::new(),
+                },
+            ],
+            vias: vec![
+                ViaLayer {
+                    name: "mcon".into(),
+                    size: (240, 240).into(),
+                    bot: ViaTarget::Primitive,
+                    top: 0.into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(67, &via_purps)?)),
+                },
+                ViaLayer {
+                    name: "via1".into(),
+                    size: (240, 240).into(),
+                    bot: 0.into(),
+                    top: 1.into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(68, &via_purps)?)),
+                },
+                ViaLayer {
+                    name: "via2".into(),
+                    size: (240, 240).into(),
+                    bot: 1.into(),
+                    top: 2.into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(69, &via_purps)?)),
+                },
+                ViaLayer {
+                    name: "via3".into(),
+                    size: (240, 240).into(),
+                    bot: 2.into(),
+                    top: 3.into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(70, &via_purps)?)),
+                },
+                ViaLayer {
+                    name: "via4".into(),
+                    size: (240, 240).into(),
+                    bot: 3.into(),
+                    top: 4.into(),
+                    raw: Some(rawlayers.add(raw::Layer::from_pairs(71, &via_purps)?)),
+                },
+            ],
+            rawlayers: Some(Ptr::new(rawlayers)),
+            nets: NetNamePolicy::default(),
+            site: None,
+        };
+        Ok(stack)
+    }
+}
+
+/// Create a [Library] with a cell instantiating an abstract-only cell three times, named `lib3`
+pub fn abstract_library() -> LayoutResult<Library> {
+    let mut lib = Library::new("lib3");
+
+    let c2 = lib.cells.insert(abs::Abstract {
+        name: "IsAbs".into(),
+        metals: 1,
+        outline: Outline::rect(100, 10)?,
+        ports: Vec::new(),
+    });
+
+    let mut cell = Cell::builder("HasAbss")
+        .top_layer(LayerIndex(3))
+        .outline(Outline::rect(500, 50)?);
+    for (name, loc) in [
+        ("inst1", (0, 0)),
+        ("inst2", (200, 20)),
+        ("inst4", (400, 40)),
+    ] {
+        let inst = Instance::builder()
+            .inst_name(name)
+            .cell(c2.clone())
+            .loc(loc)
+            .build()?;
+        cell = cell.instance(inst);
+    }
+    lib.cells.insert(cell.build()?);
+    Ok(lib)
+}
+/// Create the [abstract_library], plus a `routed` cell with a cut and two same-net assignments
+/// on each of sixteen met3 tracks. Valid against [SampleStacks::pdka].
+pub fn routed_library() -> LayoutResult<Library> {
+    let mut lib = abstract_library()?;
+    let mut cell = Cell::builder("routed")
+        .top_layer(LayerIndex(3))
+        .outline(Outline::rect(40, 8)?);
+    for track in 0..16 {
+        cell = cell
+            .cut(LayerIndex(2), TrackIndex(track), TrackIndex(3), RelZ::Below)
+            .assign(
+                format!("net{}", track),
+                LayerIndex(2),
+                TrackIndex(track),
+                TrackIndex(1),
+                RelZ::Below,
+            )
+            .assign(
+                format!("net{}", track),
+                LayerIndex(2),
+                TrackIndex(track),
+                TrackIndex(5),
+                RelZ::Below,
+            );
+    }
+    lib.cells.insert(cell.build()?);
+    Ok(lib)
+}
+/// Generate a synthetic [Library] of `cells` leaf cells, each `size_pitches` primitive pitches square,
+/// and a top-level cell instantiating all of them in a grid, padded in width to whole met4 periods.
+///
+/// Each leaf has `assigns_per_cell` net-assignments and half as many cuts, on its top layer (met2),
+/// at pseudo-random locations derived from `seed`. The top cell has as many assignments on met4.
+/// Content is valid against [SampleStacks::pdka], and identical for identical arguments.
+pub fn grid_library(
+    cells: usize,
+    size_pitches: usize,
+    assigns_per_cell: usize,
+    seed: u64,
+) -> LayoutResult<Library> {
+    let stack = SampleStacks::pdka()?;
+    let mut rng = Rng::new(seed);
+    let mut lib = Library::new(format!("GridLibrary{}", seed));
+
+    // Create the leaf cells
+    let size = Int::try_from(size_pitches)?;
+    let mut leaves = Vec::with_capacity(cells);
+    for idx in 0..cells {
+        let mut cell = Cell::builder(format!("Leaf{}", idx))
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(size, size)?)
+            .build()?;
+        fill(&stack, &mut cell, 1, assigns_per_cell, &mut rng)?;
+        leaves.push(lib.cells.insert(cell));
+    }
+
+    // And the top-level grid of them
+    let cols = (1..).find(|c| c * c >= cells).unwrap_or(1);
+    let rows = cells.div_ceil(cols).max(1);
+    // Pad its width out to a whole number of met4 periods
+    let period = stack.metal(LayerIndex(3))?.pitch / stack.prim.pitches[Dir::Horiz];
+    let width = size * Int::try_from(cols)?;
+    let width = (width + period - 1) / period * period;
+    let mut top = Cell::builder("Grid")
+        .top_layer(LayerIndex(3))
+        .outline(Outline::rect(width, size * Int::try_from(rows)?)?);
+    for (idx, leaf) in leaves.into_iter().enumerate() {
+        let loc = (
+            size * Int::try_from(idx % cols)?,
+            size * Int::try_from(idx / cols)?,
+        );
+        let inst = Instance::builder()
+            .inst_name(format!("i{}", idx))
+            .cell(leaf)
+            .loc(loc)
+            .build()?;
+        top = top.instance(inst);
+    }
+    let mut top = top.build()?;
+    fill(&stack, &mut top, 3, assigns_per_cell, &mut rng)?;
+    lib.cells.insert(top);
+    Ok(lib)
+}
+/// Add `assigns` assignments and half as many cuts to layer `layer` of `cell`,
+/// at distinct pseudo-random crossings with the layer below.
+/// Nets are named per track, so that assignments sharing a track segment never conflict.
+fn fill(
+    stack: &ValidStack,
+    cell: &mut Cell,
+    layer: usize,
+    assigns: usize,
+    rng: &mut Rng,
+) -> LayoutResult<()> {
+    let layout = match cell.layout.as_mut() {
+        Some(layout) => layout,
+        None => return Ok(()),
+    };
+    let ntracks = stack.signal_tracks(LayerIndex(layer), &layout.outline)?;
+    let nat = stack.signal_tracks(LayerIndex(layer - 1), &layout.outline)?;
+    let cuts = assigns / 2;
+    let count = (assigns + cuts).min(ntracks * nat);
+    let mut used = BTreeSet::new();
+    while used.len() < count {
+        let cross = (rng.below(ntracks), rng.below(nat));
+        if !used.insert(cross) {
+            continue;
+        }
+        let (track, at) = cross;
+        if used.len() <= cuts {
+            layout.cut(
+                LayerIndex(layer),
+                TrackIndex(track),
+                TrackIndex(at),
+                RelZ::Below,
+            );
+        } else {
+            layout.assign(
+                format!("net{}", track),
+                LayerIndex(layer),
+                TrackIndex(track),
+                TrackIndex(at),
+                RelZ::Below,
+            );
+        }
+    }
+    Ok(())
+}
+/// # Malformed Library
+///
+/// A [Library] and [Stack] combination with malformed content, e.g. dangling references, bad outlines, or absurd indices.
+/// Validation and conversion must handle each without panicking: generally by failing with an error,
+/// or for some (e.g. assignments beyond the cell's last period) by ignoring the offending content.
+///
+pub struct Malformed {
+    /// Short description of what is malformed
+    pub name: &'static str,
+    /// The library
+    pub lib: Library,
+    /// Its (un-validated) stack
+    pub stack: Stack,
+}
+/// Generate the corpus of [Malformed] libraries, each built against [SampleStacks::pdka] unless noted.
+/// Add to it as new failure modes are found.
+pub fn malformed_libraries() -> LayoutResult<Vec<Malformed>> {
+    let pdka = SampleStacks::pdka_stack;
+    let leaf = || -> LayoutResult<Cell> {
+        Cell::builder("Leaf")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(20, 10)?)
+            .build()
+    };
+    // Single-cell library containing `cell`
+    let single = |name: &str, cell: Cell| {
+        let mut lib = Library::new(name);
+        lib.cells.insert(cell);
+        lib
+    };
+    let mut corpus = Vec::new();
+
+    // Instance of a cell which is not in the library. Converted along with its instantiator.
+    let dangling = Ptr::new(leaf()?);
+    let inst = Instance::builder()
+        .inst_name("i0")
+        .cell(dangling)
+        .loc((0, 0))
+        .build()?;
+    let top = Cell::builder("Top")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(40, 20)?)
+        .instance(inst)
+        .build()?;
+    corpus.push(Malformed {
+        name: "dangling_instance",
+        lib: single("DanglingInstance", top),
+        stack: pdka()?,
+    });
+
+    // Outlines with mismatched coordinate counts, and with negative coordinates
+    for (name, x, y) in [
+        ("mismatched_outline", vec![20, 10], vec![10]),
+        ("negative_outline", vec![-20], vec![10]),
+        ("empty_outline", vec![], vec![]),
+    ] {
+        let mut cell = leaf()?;
+        if let Some(ref mut layout) = cell.layout {
+            layout.outline = Outline {
+                x: x.into_iter().map(PrimPitches::x).collect(),
+                y: y.into_iter().map(PrimPitches::y).collect(),
+            };
+        }
+        corpus.push(Malformed {
+            name,
+            lib: single(name, cell),
+            stack: pdka()?,
+        });
+    }
+
+    // Cuts and assignments at absurd layer and track indices.
+    // Assignments beyond the cell's last period are dropped.
+    let mut cell = leaf()?;
+    if let Some(ref mut layout) = cell.layout {
+        layout.assign(
+            "huge",
+            LayerIndex(1),
+            TrackIndex(1_000_000),
+            TrackIndex(1_000_000),
+            RelZ::Below,
+        );
+    }
+    corpus.push(Malformed {
+        name: "absurd_track",
+        lib: single("AbsurdTrack", cell),
+        stack: pdka()?,
+    });
+    let mut cell = leaf()?;
+    if let Some(ref mut layout) = cell.layout {
+        layout.cut(LayerIndex(99), TrackIndex(0), TrackIndex(0), RelZ::Above);
+    }
+    corpus.push(Malformed {
+        name: "absurd_layer",
+        lib: single("AbsurdLayer", cell),
+        stack: pdka()?,
+    });
+    let mut cell = leaf()?;
+    if let Some(ref mut layout) = cell.layout {
+        layout.metals = 99;
+    }
+    corpus.push(Malformed {
+        name: "absurd_metals",
+        lib: single("AbsurdMetals", cell),
+        stack: pdka()?,
+    });
+
+    // Stacks missing their raw layers, or boundary layer
+    let mut stack = pdka()?;
+    stack.rawlayers = None;
+    corpus.push(Malformed {
+        name: "missing_rawlayers",
+        lib: single("MissingRawLayers", leaf()?),
+        stack,
+    });
+    let mut stack = pdka()?;
+    stack.boundary_layer = None;
+    corpus.push(Malformed {
+        name: "missing_boundary_layer",
+        lib: single("MissingBoundaryLayer", leaf()?),
+        stack,
+    });
+
+    // Relative placement to an instance port
+    let mut lib = Library::new("RelativeToPort");
+    let leafptr = lib.cells.insert(leaf()?);
+    let anchor = Ptr::new(
+        Instance::builder()
+            .inst_name("anchor")
+            .cell(leafptr.clone())
+            .loc((0, 0))
+            .build()?,
+    );
+    let rel = Instance {
+        inst_name: "rel".into(),
+        cell: leafptr.clone(),
+        loc: Place::Rel(RelativePlace {
+            to: Placeable::Port {
+                inst: anchor.clone(),
+                port: "nonexistent".into(),
+            },
+            side: Side::Right,
+            align: Align::Side(Side::Bottom),
+            sep: Separation::default(),
+        }),
+        reflect_horiz: false,
+        reflect_vert: false,
+    };
+    let mut top = Cell::builder("Top")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(60, 20)?)
+        .instance(anchor.read()?.clone())
+        .build()?;
+    if let Some(ref mut layout) = top.layout {
+        layout.places.push(Placeable::Instance(Ptr::new(rel)));
+    }
+    lib.cells.insert(top);
+    corpus.push(Malformed {
+        name: "relative_to_port",
+        lib,
+        stack: pdka()?,
+    });
+
+    // Array separated in [DbUnits], which placement does not support
+    let mut lib = Library::new("ArraySepDbUnits");
+    let leafptr = lib.cells.insert(leaf()?);
+    let array = ArrayInstance {
+        name: "arr".into(),
+        array: Ptr::new(Array {
+            name: "row".into(),
+            unit: Arrayable::Instance(leafptr),
+            count: 2,
+            sep: Separation::x(SepBy::UnitSpeced(DbUnits(1000).into())),
+        }),
+        loc: (0, 0).into(),
+        reflect_vert: false,
+        reflect_horiz: false,
+    };
+    let mut top = Cell::builder("Top")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(60, 20)?)
+        .build()?;
+    if let Some(ref mut layout) = top.layout {
+        layout.places.push(Placeable::Array(Ptr::new(array)));
+    }
+    lib.cells.insert(top);
+    corpus.push(Malformed {
+        name: "array_sep_db_units",
+        lib,
+        stack: pdka()?,
+    });
+
+    Ok(corpus)
+}
+/// # Fixture Random-Number Generator
+///
+/// Minimal deterministic pseudo-random generator (SplitMix64),
+/// so that fixtures are reproducible across platforms and releases.
+struct Rng(u64);
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    /// Get a value in the range `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
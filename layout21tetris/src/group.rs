@@ -11,7 +11,7 @@ use crate::{
     cell::Cell,
     coords::{PrimPitches, Xy},
     placement::Place,
-    raw::LayoutResult,
+    raw::{LayoutError, LayoutResult},
     utils::Ptr,
 };
 
@@ -26,7 +26,10 @@ pub struct Group {
 impl Group {
     /// Size of the Instance's rectangular `boundbox`, i.e. the zero-origin `boundbox` of its `cell`.
     pub fn boundbox_size(&self) -> LayoutResult<Xy<PrimPitches>> {
-        todo!()
+        LayoutError::fail(format!(
+            "Bounding box of Group {} is not (yet) supported",
+            self.name
+        ))
     }
 }
 /// Enumeration of types that can be Grouped
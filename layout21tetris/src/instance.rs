@@ -5,15 +5,24 @@
 //!
 
 // Local imports
+use crate::abs;
 use crate::bbox::{BoundBox, HasBoundBox};
 use crate::cell::Cell;
-use crate::coords::{PrimPitches, Xy};
+use crate::coords::{DbUnits, HasUnits, Int, LayerIndex, PrimPitches, SignedTrack, TrackIndex, Xy};
 use crate::placement::Place;
 use crate::raw::{Dir, LayoutError, LayoutResult};
+use crate::stack::RelZ;
+use crate::tracks::{TrackCross, TrackRef};
 use crate::utils::Ptr;
+use crate::validate::ValidStack;
 
 /// Instance of another Cell
-#[derive(Debug, Clone)]
+///
+/// Comparisons include that of `cell` by [Ptr] address.
+/// Can be constructed directly, or via the [InstanceBuilder] returned by [Instance::builder].
+///
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(pattern = "owned", setter(into))]
 pub struct Instance {
     /// Instance Name
     pub inst_name: String,
@@ -23,12 +32,19 @@ pub struct Instance {
     /// This origin-position holds regardless of either `reflect` field.
     /// If specified in absolute coordinates, location-units are [PrimPitches].
     pub loc: Place<Xy<PrimPitches>>,
-    /// Horizontal Reflection
+    /// Horizontal Reflection: mirror about the y-axis (negating x), about the instance origin
+    #[builder(default)]
     pub reflect_horiz: bool,
-    /// Vertical Reflection
+    /// Vertical Reflection: mirror about the x-axis (negating y), about the instance origin.
+    /// Exported as [raw::Instance](crate::raw::Instance) `reflect_vert`, i.e. GDSII's reflection bit.
+    #[builder(default)]
     pub reflect_vert: bool,
 }
 impl Instance {
+    /// Create an [InstanceBuilder], a struct created by the [Builder] macro.
+    pub fn builder() -> InstanceBuilder {
+        InstanceBuilder::default()
+    }
     /// Boolean indication of whether this Instance is reflected in direction `dir`
     pub fn reflected(&self, dir: Dir) -> bool {
         match dir {
@@ -41,6 +57,239 @@ impl Instance {
         let cell = self.cell.read()?;
         cell.boundbox_size()
     }
+    /// Get the location of port `port_name`, in the coordinates of our parent cell.
+    ///
+    /// Applies our location and reflections to the port geometry of our cell's [abs::Abstract].
+    /// Fails if the cell has no abstract view, if it has no such port,
+    /// or if the transformed port does not land on tracks of the parent.
+    pub fn port_loc(&self, port_name: &str, stack: &ValidStack) -> LayoutResult<PortLoc> {
+        let cell = self.cell.read()?;
+        let abs = match cell.abs.as_ref() {
+            Some(abs) => abs,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot locate Port {} on Cell {} with no Abstract view",
+                    port_name, cell.name
+                ))
+            }
+        };
+        let port = match abs.port(port_name) {
+            Some(port) => port,
+            None => {
+                return LayoutError::fail(format!("Cell {} has no Port {}", cell.name, port_name))
+            }
+        };
+        let loc = self.loc.abs()?;
+
+        let portloc = match &port.kind {
+            abs::PortKind::Edge { layer, track, side } => {
+                let dir = stack.metal(*layer)?.spec.dir;
+                let track = self.parent_track(stack, port_name, *layer, *track, loc)?;
+                // The edge-coordinate along `track`, in our own frame.
+                // FIXME: this assumes rectangular outlines, as does abstract-port export.
+                let edge = match side {
+                    abs::Side::BottomOrLeft => PrimPitches::new(dir, 0),
+                    abs::Side::TopOrRight => abs.outline.max(dir),
+                };
+                let (side, at) = if self.reflected(dir) {
+                    (side.opposite(), loc[dir] - edge)
+                } else {
+                    (*side, loc[dir] + edge)
+                };
+                PortLoc::Edge {
+                    track: TrackRef::new(*layer, track),
+                    side,
+                    at,
+                }
+            }
+            abs::PortKind::ZTopEdge { track, side, into } => {
+                let top_metal = match cell.top_metal()? {
+                    Some(m) => m,
+                    None => return LayoutError::fail("No metal layers"),
+                };
+                let dir = stack.metal(LayerIndex(top_metal))?.spec.dir;
+                let port_track =
+                    self.parent_track(stack, port_name, LayerIndex(top_metal), *track, loc)?;
+
+                // Sort out the orthogonal-axis range, in tracks of the adjacent layer
+                let ortho_layer = adjacent_layer(top_metal, into.1)?;
+                let (loc, size) = {
+                    let size = self.boundbox_size()?[dir];
+                    (
+                        layer_tracks(stack, ortho_layer, loc[dir])?,
+                        layer_tracks(stack, ortho_layer, size)?,
+                    )
+                };
+                let into = SignedTrack::from(into.0);
+                let (lo, hi) = match (side, self.reflected(dir)) {
+                    (abs::Side::BottomOrLeft, false) => (loc, loc + into),
+                    (abs::Side::BottomOrLeft, true) => (loc - into, loc),
+                    (abs::Side::TopOrRight, false) => (loc + into, loc + size),
+                    (abs::Side::TopOrRight, true) => (loc - size, loc - into),
+                };
+                let ortho_range = (
+                    self.unsigned_track(port_name, ortho_layer, lo, dir)?,
+                    self.unsigned_track(port_name, ortho_layer, hi, dir)?,
+                );
+                PortLoc::ZTopEdge {
+                    track: TrackRef::new(LayerIndex(top_metal), port_track),
+                    range: (
+                        TrackRef::new(LayerIndex(ortho_layer), ortho_range.0),
+                        TrackRef::new(LayerIndex(ortho_layer), ortho_range.1),
+                    ),
+                }
+            }
+            abs::PortKind::ZTopInner { locs } => {
+                let top_metal = match cell.top_metal()? {
+                    Some(m) => m,
+                    None => return LayoutError::fail("No metal layers"),
+                };
+                let mut crosses = Vec::with_capacity(locs.len());
+                for topl in locs.iter() {
+                    let ortho_layer = adjacent_layer(top_metal, topl.relz)?;
+                    let track = self.parent_track(
+                        stack,
+                        port_name,
+                        LayerIndex(top_metal),
+                        topl.track,
+                        loc,
+                    )?;
+                    let at =
+                        self.parent_track(stack, port_name, LayerIndex(ortho_layer), topl.at, loc)?;
+                    crosses.push(TrackCross::from_parts(
+                        LayerIndex(top_metal),
+                        track,
+                        LayerIndex(ortho_layer),
+                        at,
+                    ));
+                }
+                PortLoc::ZTopInner {
+                    layer: LayerIndex(top_metal),
+                    locs: crosses,
+                }
+            }
+        };
+        Ok(portloc)
+    }
+    /// Transform track `track` of layer `layer` from our cell's frame into that of our parent,
+    /// located at `loc`. Fails if the transformed track-center does not coincide with a parent track,
+    /// or if it lands on a negative track, outside the parent grid.
+    fn parent_track(
+        &self,
+        stack: &ValidStack,
+        port_name: &str,
+        layer: LayerIndex,
+        track: TrackIndex,
+        loc: &Xy<PrimPitches>,
+    ) -> LayoutResult<TrackIndex> {
+        let metal = stack.metal(layer)?;
+        let dir = !metal.spec.dir;
+        let origin = DbUnits(loc[dir].num * stack.prim.pitches[dir].raw());
+        let center = metal.center(track)?;
+        let parent = if self.reflected(dir) {
+            origin - center
+        } else {
+            origin + center
+        };
+        match metal.signed_track_at(parent) {
+            Some(index) => self.unsigned_track(port_name, metal.index, index, dir),
+            None => LayoutError::fail(format!(
+                "Unsupported transform of Instance {}: track {} of layer {} does not land on a parent track",
+                self.inst_name, track, metal.spec.name
+            )),
+        }
+    }
+    /// Convert parent-frame track `track` of layer `layer` to a [TrackIndex],
+    /// failing if it is negative, i.e. off the parent's grid.
+    /// Transforms along axis `dir` are reported as reflections or offsets accordingly.
+    fn unsigned_track(
+        &self,
+        port_name: &str,
+        layer: usize,
+        track: SignedTrack,
+        dir: Dir,
+    ) -> LayoutResult<TrackIndex> {
+        match track.index() {
+            Some(index) => Ok(index),
+            None => LayoutError::fail(format!(
+                "Port {} of Instance {} {} to track {} of layer {}, outside the parent grid",
+                port_name,
+                self.inst_name,
+                if self.reflected(dir) {
+                    "reflects"
+                } else {
+                    "moves"
+                },
+                track,
+                layer
+            )),
+        }
+    }
+}
+/// Get the index of the layer adjacent to `layer`, in direction `relz`
+fn adjacent_layer(layer: usize, relz: RelZ) -> LayoutResult<usize> {
+    match relz {
+        RelZ::Above => Ok(layer + 1),
+        RelZ::Below if layer > 0 => Ok(layer - 1),
+        RelZ::Below => LayoutError::fail("No layer below the bottom metal"),
+    }
+}
+/// Convert [PrimPitches] distance `dist` into a (possibly negative) number of signal tracks on layer `layer`.
+/// Fails if `dist` is not an integer multiple of the pitch of `layer`.
+fn layer_tracks(stack: &ValidStack, layer: usize, dist: PrimPitches) -> LayoutResult<SignedTrack> {
+    let metal = stack.metal(LayerIndex(layer))?;
+    let prim_pitch = stack.prim.pitches[!metal.spec.dir];
+    let dist = DbUnits(dist.num * prim_pitch.raw());
+    if dist % metal.pitch != 0 {
+        return LayoutError::fail(format!(
+            "Invalid Conversion: distance {:?} is not a multiple of Layer {} (pitch={:?})",
+            dist, layer, metal.pitch
+        ));
+    }
+    let periods = dist / metal.pitch;
+    Ok(SignedTrack(
+        periods * metal.period_data.signals.len() as Int,
+    ))
+}
+/// # Port Location
+///
+/// Location of an [abs::Port] of a placed [Instance], in the coordinates of its parent.
+/// Returned by [Instance::port_loc].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortLoc {
+    /// Ports on x/y outline edges
+    Edge {
+        /// Port Track
+        track: TrackRef,
+        /// Instance-side on which the port lies, after reflection
+        side: abs::Side,
+        /// Coordinate of the edge, along `track`
+        at: PrimPitches,
+    },
+    /// Top-layer ports extending inward from an edge
+    ZTopEdge {
+        /// Port Track
+        track: TrackRef,
+        /// Extent on an adjacent layer
+        range: (TrackRef, TrackRef),
+    },
+    /// Top-layer ports internal to the outline
+    ZTopInner {
+        /// Top Layer
+        layer: LayerIndex,
+        /// Locations
+        locs: Vec<TrackCross>,
+    },
+}
+impl PortLoc {
+    /// Get the index of the layer on which the port lies
+    pub fn layer(&self) -> LayerIndex {
+        match self {
+            Self::Edge { track, .. } | Self::ZTopEdge { track, .. } => track.layer,
+            Self::ZTopInner { layer, .. } => *layer,
+        }
+    }
 }
 impl std::fmt::Display for Instance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -13,14 +13,14 @@ use serde::{Deserialize, Serialize};
 /// Includes scalar, vector (bus), and bundle-valued ports.
 /// Does not include physical/ geometric information.
 ///
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Port {
     /// Port Name
     pub name: String,
     /// Port Type & Content
     pub kind: PortKind,
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum PortKind {
     /// Flat Scalar Port, e.g. `clk`
     Scalar,
@@ -29,7 +29,7 @@ pub enum PortKind {
     /// Instance of a Hierarchical Bundle
     Bundle { bundle_name: String },
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Bundle {
     pub name: String,
     pub ports: Vec<Port>,
@@ -4,21 +4,33 @@
 //! Physical implementations of tetris [Cell]s.
 //!
 
+// Std-Lib
+use std::ops::Range;
+
+// Crates.io
+use serde::{Deserialize, Serialize};
+
 // Local imports
 use crate::{
+    bbox::{BoundBox, HasBoundBox},
+    coords::{LayerIndex, PrimPitches, TrackIndex},
+    escape::{EscapeAnalyzer, EscapeReport},
     instance::Instance,
     outline,
     placement::Placeable,
-    stack::{Assign, RelZ},
-    tracks::TrackCross,
+    raw::{Dir, LayoutResult},
+    stack::{Assign, Jog, RelZ, Strap, ViaLayerRef, ViaLoc, ViaPlacement},
+    tracks::{TrackCross, TrackRef},
     utils::PtrList,
+    validate::ValidStack,
 };
 
 /// # Layout Cell Implementation
 ///
 /// A combination of lower-level cell instances and net-assignments to tracks.
+/// Comparisons include those of `instances` and `places` by [Ptr](crate::utils::Ptr) address.
 ///
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, Builder, PartialEq, Eq)]
 #[builder(pattern = "owned", setter(into))]
 pub struct Layout {
     /// Cell Name
@@ -37,6 +49,25 @@ pub struct Layout {
     /// Track cuts
     #[builder(default)]
     pub cuts: Vec<TrackCross>,
+    /// Multi-track net straps
+    #[builder(default)]
+    pub straps: Vec<Strap>,
+    /// Short wrong-way jogs between adjacent tracks
+    #[builder(default)]
+    pub jogs: Vec<Jog>,
+    /// Keep-out regions
+    #[builder(default)]
+    pub keepouts: Vec<Keepout>,
+    /// Vias placed directly, independent of `assignments`
+    #[builder(default)]
+    pub vias: Vec<ViaPlacement>,
+    /// Metal layers (indices) on which the stack's rails are not drawn.
+    /// Signal tracks on these layers are converted as usual.
+    #[builder(default)]
+    pub suppress_rails: Vec<usize>,
+    /// Close the boundary rails into a ring around the cell, per [RawExporter](crate::conv::raw::RawExporter)
+    #[builder(default)]
+    pub rail_rings: bool,
     /// Placeable objects
     #[builder(default)]
     pub places: Vec<Placeable>,
@@ -52,6 +83,12 @@ impl Layout {
             instances: PtrList::new(),
             assignments: Vec::new(),
             cuts: Vec::new(),
+            straps: Vec::new(),
+            jogs: Vec::new(),
+            keepouts: Vec::new(),
+            vias: Vec::new(),
+            suppress_rails: Vec::new(),
+            rail_rings: false,
             places: Vec::new(),
         }
     }
@@ -63,26 +100,211 @@ impl Layout {
     pub fn assign(
         &mut self,
         net: impl Into<String>,
-        layer: usize,
-        track: usize,
-        at: usize,
+        layer: LayerIndex,
+        track: TrackIndex,
+        at: TrackIndex,
         relz: RelZ,
     ) {
         let net = net.into();
         let at = TrackCross::from_relz(layer, track, at, relz);
-        self.assignments.push(Assign { net, at })
+        self.assignments.push(Assign::new(net, at))
     }
     /// Add a cut at the specified coordinates.
-    pub fn cut(&mut self, layer: usize, track: usize, at: usize, relz: RelZ) {
+    pub fn cut(&mut self, layer: LayerIndex, track: TrackIndex, at: TrackIndex, relz: RelZ) {
         let cut = TrackCross::from_relz(layer, track, at, relz);
         self.cuts.push(cut)
     }
+    /// Assign net `net` to signal tracks `tracks` on metal-layer `layer`,
+    /// over `extent` primitive pitches along the layer's direction.
+    pub fn strap(
+        &mut self,
+        net: impl Into<String>,
+        layer: usize,
+        tracks: Range<usize>,
+        extent: Range<usize>,
+    ) {
+        self.straps.push(Strap {
+            net: net.into(),
+            layer,
+            tracks,
+            extent,
+        })
+    }
+    /// Connect adjacent signal tracks `track_a` and `track_b` on metal-layer `layer` with a short [Jog] on net `net`,
+    /// located at signal track `at` of the adjacent layer `relz`.
+    pub fn jog(
+        &mut self,
+        net: impl Into<String>,
+        layer: usize,
+        track_a: usize,
+        track_b: usize,
+        at: TrackIndex,
+        relz: RelZ,
+    ) {
+        let at = TrackCross::from_relz(LayerIndex(layer), TrackIndex(track_a), at, relz).cross;
+        self.jogs.push(Jog {
+            net: net.into(),
+            layer,
+            track_a,
+            track_b,
+            at,
+        })
+    }
+    /// Add keep-out region `name`, blocking metal layers `layers`
+    /// over `x` and `y`, each specified in primitive pitches.
+    pub fn keepout(
+        &mut self,
+        name: impl Into<String>,
+        layers: Range<usize>,
+        x: Range<usize>,
+        y: Range<usize>,
+    ) {
+        self.keepouts.push(Keepout {
+            name: name.into(),
+            layers,
+            x,
+            y,
+        })
+    }
+    /// Add [ViaPlacement] `via`
+    pub fn via(&mut self, via: ViaPlacement) {
+        self.vias.push(via)
+    }
+    /// Collect the name and footprint of each absolutely-placed [Instance], in order.
+    /// Instances which are not absolutely placed are skipped.
+    pub(crate) fn footprints(&self) -> LayoutResult<Vec<(String, BoundBox<PrimPitches>)>> {
+        let mut footprints = Vec::new();
+        for ptr in self.instances.iter() {
+            let inst = ptr.read()?;
+            if inst.loc.abs().is_err() {
+                continue;
+            }
+            footprints.push((inst.inst_name.clone(), inst.boundbox()?));
+        }
+        Ok(footprints)
+    }
+    /// Find all pairs of abutting [Instance]s, i.e. those sharing a non-zero length of outline edge.
+    /// Instances which are not absolutely placed are skipped.
+    pub fn abutments(&self) -> LayoutResult<Vec<Abutment>> {
+        let footprints = self.footprints()?;
+        let mut abutments = Vec::new();
+        for (idx, a) in footprints.iter().enumerate() {
+            for b in footprints[idx + 1..].iter() {
+                for dir in [Dir::Horiz, Dir::Vert] {
+                    // Order the pair from bottom/left to top/right, if they touch in `dir`
+                    let (lo, hi) = if a.1.p1[dir] == b.1.p0[dir] {
+                        (a, b)
+                    } else if b.1.p1[dir] == a.1.p0[dir] {
+                        (b, a)
+                    } else {
+                        continue;
+                    };
+                    // And check they share a non-zero length of edge
+                    let other = !dir;
+                    let start = lo.1.p0[other].num.max(hi.1.p0[other].num);
+                    let stop = lo.1.p1[other].num.min(hi.1.p1[other].num);
+                    if start < stop {
+                        abutments.push(Abutment {
+                            insts: (lo.0.clone(), hi.0.clone()),
+                            dir,
+                            at: lo.1.p1[dir],
+                            span: (
+                                PrimPitches::new(other, start),
+                                PrimPitches::new(other, stop),
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(abutments)
+    }
+    /// Analyze whether the ports of [Instance] `inst_name` can escape to our outline.
+    /// Requires the instance's [Cell](crate::cell::Cell) have an [Abstract](crate::abs::Abstract) view.
+    pub fn escape_analysis(
+        &self,
+        inst_name: &str,
+        stack: &ValidStack,
+    ) -> LayoutResult<EscapeReport> {
+        EscapeAnalyzer::new(self, stack).analyze(inst_name)
+    }
+    /// Describe our assignments, cuts, and vias in human-readable text, one per line.
+    ///
+    /// Each track-intersection is rendered with its layer names, track indices, and track-center coordinates,
+    /// e.g. `clk: met2 track 4 (x=1840) × met1 track 2 (y=1190) [Below]`.
+    pub fn describe_routes(&self, stack: &ValidStack) -> LayoutResult<String> {
+        let mut txt = String::new();
+        for assn in self.assignments.iter() {
+            let cross = describe_cross(&assn.at, stack)?;
+            txt.push_str(&format!("{}: {}\n", assn.net, cross));
+        }
+        for cut in self.cuts.iter() {
+            txt.push_str(&format!("cut: {}\n", describe_cross(cut, stack)?));
+        }
+        for via in self.vias.iter() {
+            let layer = match via.layer {
+                ViaLayerRef::Name(ref name) => name.clone(),
+                ViaLayerRef::Index(idx) => stack.via(idx)?.name.clone(),
+            };
+            let loc = match via.at {
+                ViaLoc::Cross(ref cross) => describe_cross(cross, stack)?,
+                ViaLoc::Point(ref pt) => format!("(x={}, y={})", pt.x.0, pt.y.0),
+            };
+            let net = match via.net {
+                Some(ref net) => format!(" ({})", net),
+                None => String::new(),
+            };
+            txt.push_str(&format!("via {}: {}{}\n", layer, loc, net));
+        }
+        Ok(txt)
+    }
     /// Get a temporary handle for net assignments
     pub fn net<'h>(&'h mut self, net: impl Into<String>) -> NetHandle<'h> {
         let name = net.into();
         NetHandle { name, parent: self }
     }
 }
+/// # Keep-Out Region
+///
+/// Rectangular region in which metal layers `layers` are unavailable for routing.
+/// Converted exactly like the blockages of an [Instance], cutting every track it intersects.
+/// `x` and `y` are specified in primitive pitches, and must lie within the [Layout]'s outline.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Keepout {
+    /// Keep-Out Name
+    pub name: String,
+    /// Blocked Metal-Layer Indices
+    pub layers: Range<usize>,
+    /// Horizontal Extent, in primitive pitches
+    pub x: Range<usize>,
+    /// Vertical Extent, in primitive pitches
+    pub y: Range<usize>,
+}
+impl Keepout {
+    /// Get our extent in direction `dir`, in primitive pitches
+    pub fn extent(&self, dir: Dir) -> Range<usize> {
+        match dir {
+            Dir::Horiz => self.x.clone(),
+            Dir::Vert => self.y.clone(),
+        }
+    }
+}
+/// # Instance Abutment
+///
+/// A pair of [Instance]s sharing an outline edge, as found by [Layout::abutments].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abutment {
+    /// Instance names, ordered from bottom/left to top/right
+    pub insts: (String, String),
+    /// Direction in which the instances are adjacent, i.e. normal to their shared edge
+    pub dir: Dir,
+    /// Location of the shared edge, along `dir`
+    pub at: PrimPitches,
+    /// Extent of the shared edge, along the perpendicular direction
+    pub span: (PrimPitches, PrimPitches),
+}
 /// # Net Handle
 ///
 /// A short-term handle for chaining multiple assignments to a net
@@ -97,8 +319,38 @@ pub struct NetHandle<'h> {
 impl<'h> NetHandle<'h> {
     /// Assign our net at the given coordinates.
     /// Consumes and returns `self` to enable chaining.
-    pub fn at(self, layer: usize, track: usize, at: usize, relz: RelZ) -> Self {
+    pub fn at(self, layer: LayerIndex, track: TrackIndex, at: TrackIndex, relz: RelZ) -> Self {
         self.parent.assign(&self.name, layer, track, at, relz);
         self
     }
 }
+/// Describe [TrackCross] `cross`, as in [Layout::describe_routes]
+fn describe_cross(cross: &TrackCross, stack: &ValidStack) -> LayoutResult<String> {
+    let describe = |tref: &TrackRef| -> LayoutResult<String> {
+        let metal = stack.metal(tref.layer)?;
+        // Tracks are located along the axis opposite their direction
+        let axis = match metal.spec.dir {
+            Dir::Horiz => "y",
+            Dir::Vert => "x",
+        };
+        let center = metal.center(tref.track)?;
+        Ok(format!(
+            "{} track {} ({}={})",
+            metal.spec.name,
+            tref.track.index(),
+            axis,
+            center.0
+        ))
+    };
+    let relz = if cross.cross.layer > cross.track.layer {
+        RelZ::Above
+    } else {
+        RelZ::Below
+    };
+    Ok(format!(
+        "{} × {} [{}]",
+        describe(&cross.track)?,
+        describe(&cross.cross)?,
+        relz
+    ))
+}
@@ -1,6 +1,10 @@
 //!
 //! # Layout21 "Tetris" Semi-Custom Layout System
 //!
+//! GDSII and LEF export (`gds`, `lef`), ProtoBuf conversion (`proto`), and YAML/file persistence (`yaml`) are cargo features,
+//! all enabled by default. With `default-features = false`, the data model, placement, validation,
+//! and conversion to [raw] remain, without any file I/O, e.g. for use in WebAssembly.
+//!
 
 // External macro usages
 #[macro_use]
@@ -10,25 +14,39 @@ extern crate derive_builder;
 pub mod abs;
 pub mod array;
 pub mod bbox;
+pub mod blockages;
 pub mod cell;
 pub mod conv;
 pub mod coords;
+pub mod escape;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 pub mod group;
 pub mod instance;
 pub mod interface;
 pub mod layout;
 pub mod library;
+pub mod net;
+pub mod netscope;
+pub mod occupancy;
 pub mod outline;
+#[cfg(all(feature = "gds", feature = "yaml"))]
+pub mod pipeline;
 pub mod placement;
 pub mod placer;
+pub mod prelude;
+pub mod routes;
 pub mod stack;
 pub mod tracks;
 pub mod validate;
 
 // Re-exports
+#[cfg(feature = "proto")]
 pub use layout21protos as protos;
 pub use layout21raw as raw;
 pub use layout21utils as utils;
+#[cfg(feature = "gds")]
+pub use raw::gds::gds21;
 
 /// Unit Tests Module
 #[cfg(test)]
@@ -3,13 +3,53 @@
 //!
 
 // Std-lib
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(feature = "yaml")]
+use std::path::{Path, PathBuf};
+
+// Crates.io
+use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::raw::LayoutResult;
+use crate::bbox::BoundBox;
+use crate::coords::{Int, PrimPitches, Xy};
+use crate::instance::Instance;
+use crate::layout::Layout;
+use crate::outline::Outline;
+use crate::placement::Place;
+use crate::raw::{Dir, LayoutError, LayoutResult};
+#[cfg(feature = "yaml")]
+use crate::utils::{fnv1a, ser, Migration, SerializationFormat::Yaml};
 use crate::utils::{Ptr, PtrList};
 use crate::{cell, conv, raw, validate};
 
+/// Current [Library] file schema version.
+///
+/// Library files are YAML-format [proto](crate::conv::proto) libraries,
+/// plus a few top-level fields outside the proto-schema, e.g. `schema` and `includes`.
+///
+/// Versions:
+/// * 0: Files written before schema versioning, which lack a `schema` field. Otherwise identical to 1.
+/// * 1: Adds the `schema` field.
+///
+#[cfg(feature = "yaml")]
+pub const LIBRARY_SCHEMA: u32 = 1;
+
+/// [Migration]s between each prior [Library] file schema version and [LIBRARY_SCHEMA], in order.
+/// Schema 0 => 1 only adds the `schema` field, which [utils::migrate](crate::utils::migrate) sets after each step.
+#[cfg(feature = "yaml")]
+pub const LIBRARY_MIGRATIONS: &[Migration] = &[Migration::new(0, 1, |_| Ok(()))];
+
+/// # Cell Identifier
+///
+/// Ordinal position of a [cell::Cell] in its [Library]'s `cells`.
+/// Unlike [Ptr]s, these are deterministic, but they are not generally stable across serialization.
+/// Proto export writes cells in dependency order, so only cell names are sure to survive a round-trip.
+/// Libraries whose cells are already in dependency order, e.g. those imported from proto, keep their [CellId]s.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CellId(pub usize);
+
 /// # Layout Library
 ///
 /// A combination of cell definitions, sub-libraries, and metadata
@@ -22,6 +62,44 @@ pub struct Library {
     pub cells: PtrList<cell::Cell>,
     /// [raw::Library] Definitions
     pub rawlibs: PtrList<raw::Library>,
+    /// Sub-Library Definitions
+    pub libs: PtrList<Library>,
+}
+/// # Cell Reference
+///
+/// Name-based reference to a [cell::Cell] in a [Library] or one of its sub-libraries.
+/// Resolved to a [cell::Cell]-pointer by [Library::resolve_ref].
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CellRef {
+    /// Unqualified cell name, found in the library or any unique sub-library
+    Name(String),
+    /// Cell name within the sub-library at path `lib_path`, e.g. `["stdcells", "inv"]`
+    Qualified { lib_path: Vec<String>, cell: String },
+    /// Name of a [raw::Cell] in one of our `rawlibs`, wrapped by a [cell::Cell] created via [Library::wrap_raw_cell]
+    Raw(String),
+}
+impl CellRef {
+    /// Create an unqualified [CellRef]
+    pub fn name(cell: impl Into<String>) -> Self {
+        Self::Name(cell.into())
+    }
+    /// Create a [CellRef] qualified by sub-library path `lib_path`
+    pub fn qualified(lib_path: &[&str], cell: impl Into<String>) -> Self {
+        Self::Qualified {
+            lib_path: lib_path.iter().map(|s| s.to_string()).collect(),
+            cell: cell.into(),
+        }
+    }
+}
+impl std::fmt::Display for CellRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellRef::Name(cell) => write!(f, "{}", cell),
+            CellRef::Qualified { lib_path, cell } => write!(f, "{}/{}", lib_path.join("/"), cell),
+            CellRef::Raw(cell) => write!(f, "raw:{}", cell),
+        }
+    }
 }
 impl Library {
     /// Create a new and initially empty [Library]
@@ -31,22 +109,770 @@ impl Library {
             ..Default::default()
         }
     }
+    /// Load from YAML-format proto-library file `path`, along with the files it includes via `includes`.
+    /// Files of earlier schema versions are migrated per [LIBRARY_MIGRATIONS].
+    /// Included libraries are added as sub-libraries, as per [conv::proto::IncludeMode::SubLibrary].
+    /// Includes are located relative to their includer, or in any of `search_paths`.
+    #[cfg(feature = "yaml")]
+    pub fn open_with_deps(path: impl AsRef<Path>, search_paths: &[PathBuf]) -> LayoutResult<Self> {
+        conv::proto::ProtoLibLoader::load(path, search_paths, conv::proto::IncludeMode::SubLibrary)
+    }
+    /// Load from YAML-format proto-library file `path`, along with the files it includes via `includes`,
+    /// applying `migrations` to each untyped document before deserializing it.
+    /// Generally called with [LIBRARY_MIGRATIONS], followed by any application-specific [Migration]s.
+    /// Each document must reach [LIBRARY_SCHEMA].
+    #[cfg(feature = "yaml")]
+    pub fn open_with_migrations(
+        path: impl AsRef<Path>,
+        migrations: &[Migration],
+    ) -> LayoutResult<Self> {
+        conv::proto::ProtoLibLoader::load_with_migrations(
+            path,
+            &[],
+            conv::proto::IncludeMode::SubLibrary,
+            migrations,
+        )
+    }
+    /// Save to YAML-format proto-library file `path`, with schema version [LIBRARY_SCHEMA].
+    /// Includes are not declared via `includes`: instantiated cells of sub-libraries are written alongside our own.
+    #[cfg(feature = "yaml")]
+    pub fn save(&self, path: impl AsRef<Path>) -> LayoutResult<()> {
+        let plib = conv::proto::ProtoExporter::export(self)?;
+        let mut doc = serde_yaml::Mapping::new();
+        doc.insert("schema".into(), LIBRARY_SCHEMA.into());
+        let plib = serde_yaml::to_value(&plib).map_err(ser::Error::from)?;
+        if let serde_yaml::Value::Mapping(map) = plib {
+            doc.extend(map);
+        }
+        std::fs::write(path, Yaml.to_string(&doc)?)?;
+        Ok(())
+    }
     /// Export to a [raw::Library]
     pub fn to_raw(self, stack: validate::ValidStack) -> LayoutResult<Ptr<raw::Library>> {
         conv::raw::RawExporter::convert(self, stack)
     }
+    /// Export to a [raw::Library], with [conv::raw::RawExportOptions] `opts`.
+    /// Returns the [raw::Library] along with a [conv::raw::ConversionReport].
+    pub fn to_raw_with_options(
+        self,
+        stack: validate::ValidStack,
+        opts: &conv::raw::RawExportOptions,
+    ) -> LayoutResult<(Ptr<raw::Library>, conv::raw::ConversionReport)> {
+        conv::raw::RawExporter::convert_with_options(self, stack, opts)
+    }
+    /// Check for conversion to a [raw::Library], with [conv::raw::RawExportOptions] `opts`, without creating its geometry.
+    /// Returns the [conv::raw::ConversionReport] which [Library::to_raw_with_options] would produce.
+    pub fn check(
+        self,
+        stack: validate::ValidStack,
+        opts: &conv::raw::RawExportOptions,
+    ) -> LayoutResult<conv::raw::ConversionReport> {
+        conv::raw::RawExporter::check(self, stack, opts)
+    }
+    /// Update [Stack](crate::stack::Stack) `stack`, as used by this [Library], by applying `f`.
+    ///
+    /// Re-validates the updated stack, and each of our cells against it, returning the new [validate::ValidStack].
+    /// Cells are checked ahead of placement: relatively-placed instances are not checked.
+    /// On failure `stack` is rolled back to its prior value, and the error names each cell which fails validation.
+    pub fn update_stack(
+        &self,
+        stack: &mut crate::stack::Stack,
+        f: impl FnOnce(&mut crate::stack::Stack),
+    ) -> LayoutResult<validate::ValidStack> {
+        // Un-quoted messages of wrapped errors
+        let msg = |e: LayoutError| match e {
+            LayoutError::Str(s) => s,
+            e => e.to_string(),
+        };
+        let prior = stack.clone();
+        f(stack);
+        let valid = match stack.clone().validate() {
+            Ok(valid) => valid,
+            Err(e) => {
+                *stack = prior;
+                return LayoutError::fail(format!("Invalid Stack update: {}", msg(e)));
+            }
+        };
+        let mut broken = Vec::new();
+        for ptr in self.cells.iter() {
+            let mut cell = ptr.write()?;
+            let mut validator = validate::LibValidator::unplaced(&valid);
+            if let Err(e) = validator.validate_cell(&mut cell) {
+                broken.push(format!("{}: {}", cell.name, msg(e)));
+            }
+        }
+        if !broken.is_empty() {
+            *stack = prior;
+            return LayoutError::fail(format!(
+                "Stack update invalidates cells of Library {}:\n  {}",
+                self.name,
+                broken.join("\n  ")
+            ));
+        }
+        Ok(valid)
+    }
     /// Add a [Cell]
     pub fn add_cell(&mut self, cell: cell::Cell) -> Ptr<cell::Cell> {
         self.cells.insert(cell)
     }
+    /// Get the [cell::Cell] with [CellId] `id`, if present
+    pub fn cell(&self, id: CellId) -> Option<&Ptr<cell::Cell>> {
+        self.cells.get(id.0)
+    }
+    /// Get the [CellId] of [cell::Cell]-pointer `ptr`, if it is in our `cells`
+    pub fn cell_id(&self, ptr: &Ptr<cell::Cell>) -> Option<CellId> {
+        self.cells.iter().position(|c| c == ptr).map(CellId)
+    }
+    /// Get the [CellId] of the first [cell::Cell] named `name`, if present
+    pub fn cell_id_named(&self, name: &str) -> LayoutResult<Option<CellId>> {
+        for (idx, ptr) in self.cells.iter().enumerate() {
+            if ptr.read()?.name == name {
+                return Ok(Some(CellId(idx)));
+            }
+        }
+        Ok(None)
+    }
+    /// Get the first [cell::Cell] named `name`, if present
+    pub fn cell_named(&self, name: &str) -> LayoutResult<Option<Ptr<cell::Cell>>> {
+        let id = self.cell_id_named(name)?;
+        Ok(id.and_then(|id| self.cell(id)).cloned())
+    }
+    /// Create a new [cell::Cell] named `name`, composed of one [Instance] of each of `parts`, abutted in order along `dir`.
+    ///
+    /// The first part is placed at the origin, and each subsequent part at the accumulated size of those before it.
+    /// Its outline is the concatenation of the parts' outlines, and its top layer the highest of theirs.
+    /// Fails if `parts` is empty or any is not in our `cells`, if the parts' sizes across `dir` differ,
+    /// or if any but the last part has a non-rectangular outline, which would leave a notch in the result.
+    /// Returns the [CellId] of the new cell.
+    pub fn compose(&mut self, name: &str, parts: &[CellId], dir: Dir) -> LayoutResult<CellId> {
+        if parts.is_empty() {
+            return LayoutError::fail(format!("Cannot compose Cell {} from zero parts", name));
+        }
+        let mut outlines: Vec<Outline> = Vec::with_capacity(parts.len());
+        let mut instances = PtrList::new();
+        let mut metals = 0;
+        let mut offset = 0;
+        let is_rect =
+            |o: &Outline| o.x.iter().all(|x| *x == o.xmax()) && o.y.iter().all(|y| *y == o.ymax());
+        for (idx, id) in parts.iter().enumerate() {
+            let ptr = match self.cell(*id) {
+                Some(ptr) => ptr.clone(),
+                None => {
+                    return LayoutError::fail(format!(
+                        "Cannot compose Cell {} from {:?}, which is not in Library {}",
+                        name, id, self.name
+                    ))
+                }
+            };
+            let cell = ptr.read()?;
+            let outline = cell.outline()?.clone();
+            if let Some(first) = outlines.first() {
+                let (ours, theirs) = (outline.max(dir.other()).num, first.max(dir.other()).num);
+                if ours != theirs {
+                    return LayoutError::fail(format!(
+                        "Cannot compose Cell {}: part {} has size {} across {:?}, incompatible with the first part's {}",
+                        name, cell.name, ours, dir, theirs
+                    ));
+                }
+            }
+            if !is_rect(&outline) && idx + 1 < parts.len() {
+                return LayoutError::fail(format!(
+                    "Cannot compose Cell {}: part {} has a non-rectangular outline, and is not the last part",
+                    name, cell.name
+                ));
+            }
+            let loc = match dir {
+                Dir::Horiz => Xy::new(PrimPitches::x(offset), PrimPitches::y(0)),
+                Dir::Vert => Xy::new(PrimPitches::x(0), PrimPitches::y(offset)),
+            };
+            instances.add(Instance {
+                inst_name: format!("{}_{}", cell.name, idx),
+                cell: ptr.clone(),
+                loc: Place::Abs(loc),
+                reflect_horiz: false,
+                reflect_vert: false,
+            });
+            metals = metals.max(cell.metals()?);
+            offset += outline.max(dir).num;
+            outlines.push(outline);
+        }
+        // Concatenate the outlines. All but the last are rectangular, so the last sets any "tetris" steps.
+        let last = outlines.last().unwrap();
+        let prev = offset - last.max(dir).num;
+        let outline = if parts.len() == 1 {
+            last.clone()
+        } else if is_rect(last) {
+            match dir {
+                Dir::Horiz => Outline::new(&[offset], &[last.ymax().num])?,
+                Dir::Vert => Outline::new(&[last.xmax().num], &[offset])?,
+            }
+        } else {
+            let (mut x, mut y) = (Vec::new(), Vec::new());
+            if dir == Dir::Vert {
+                x.push(last.xmax().num);
+                y.push(prev);
+            }
+            for (lx, ly) in last.x.iter().zip(last.y.iter()) {
+                match dir {
+                    Dir::Horiz => {
+                        x.push(prev + lx.num);
+                        y.push(ly.num);
+                    }
+                    Dir::Vert => {
+                        x.push(lx.num);
+                        y.push(prev + ly.num);
+                    }
+                }
+            }
+            Outline::new(&x, &y)?
+        };
+        let mut layout = Layout::new(name, metals, outline);
+        layout.instances = instances;
+        self.add_cell(cell::Cell::from(layout));
+        Ok(CellId(self.cells.len() - 1))
+    }
+    /// Generate a [cell::Cell] from parameters `params`, via generator-function `f`, or re-use one already generated.
+    ///
+    /// Generated cells are named `{name}_{hash}`, after an FNV-1a hash of the YAML-serialized `params`, e.g. `mux_1a2b3c4d`.
+    /// If a cell of that name exists, and was generated from equal parameters, its [CellId] is returned, and `f` is not called.
+    /// Otherwise `f` is called, and its result renamed, validated against `stack`, and added to our `cells`.
+    /// The generator name and YAML-serialized parameters are recorded in its `props`, as `generator` and `params`.
+    /// Fails if a cell of the same name exists but was not generated from equal parameters, e.g. on a hash collision.
+    #[cfg(feature = "yaml")]
+    pub fn generate<P: Serialize>(
+        &mut self,
+        name: &str,
+        params: P,
+        stack: &validate::ValidStack,
+        f: impl FnOnce(&P, &validate::ValidStack) -> LayoutResult<cell::Cell>,
+    ) -> LayoutResult<CellId> {
+        // Serialize the parameters, sans any leading YAML document-marker
+        let blob = Yaml.to_string(&params)?;
+        let blob = blob.trim_start_matches("---").trim().to_string();
+        let cellname = format!("{}_{:08x}", name, fnv1a(&blob) as u32);
+        if let Some(id) = self.cell_id_named(&cellname)? {
+            let same = match self.cell(id) {
+                Some(ptr) => ptr.read()?.props.get("params") == Some(&blob),
+                None => false,
+            };
+            if same {
+                return Ok(id);
+            }
+            return LayoutError::fail(format!(
+                "Cannot generate Cell {} from parameters {}: a Cell of that name exists with different parameters",
+                cellname, blob
+            ));
+        }
+        let mut cell = f(&params, stack)?;
+        cell.name = cellname;
+        if let Some(ref mut layout) = cell.layout {
+            layout.name = cell.name.clone();
+        }
+        if let Some(ref mut abs) = cell.abs {
+            abs.name = cell.name.clone();
+        }
+        validate::LibValidator::unplaced(stack).validate_cell(&mut cell)?;
+        cell.props.insert("generator".into(), name.into());
+        cell.props.insert("params".into(), blob);
+        self.add_cell(cell);
+        Ok(CellId(self.cells.len() - 1))
+    }
+    /// Add a sub-[Library]
+    pub fn add_lib(&mut self, lib: Library) -> Ptr<Library> {
+        self.libs.insert(lib)
+    }
+    /// Resolve [CellRef] `cref` to a [cell::Cell]-pointer.
+    ///
+    /// [CellRef::Qualified] references walk the sub-library tree along their `lib_path`.
+    /// [CellRef::Name] references first search our own `cells`, and then those of all sub-libraries.
+    /// Names found in more than one sub-library, but not in our own `cells`, are ambiguous, and fail.
+    pub fn resolve_ref(&self, cref: &CellRef) -> LayoutResult<Ptr<cell::Cell>> {
+        match cref {
+            CellRef::Raw(name) => {
+                for ptr in self.cells.iter() {
+                    if let Some(ref raw) = ptr.read()?.raw {
+                        if raw.cell.read()?.name == *name {
+                            return Ok(ptr.clone());
+                        }
+                    }
+                }
+                Err(not_found(cref, &self.name))
+            }
+            CellRef::Qualified { lib_path, cell } => self
+                .resolve_qualified(lib_path, cell)?
+                .ok_or_else(|| not_found(cref, &self.name)),
+            CellRef::Name(name) => {
+                if let Some(ptr) = self.cell_named(name)? {
+                    return Ok(ptr);
+                }
+                let mut candidates = Vec::new();
+                for (path, ptr) in self.qualified_cells()? {
+                    if ptr.read()?.name == *name && !path.is_empty() {
+                        candidates.push((path, ptr));
+                    }
+                }
+                match candidates.len() {
+                    0 => Err(not_found(cref, &self.name)),
+                    1 => Ok(candidates.pop().unwrap().1),
+                    _ => {
+                        let names: Vec<String> = candidates
+                            .iter()
+                            .map(|(path, _)| format!("{}/{}", path.join("/"), name))
+                            .collect();
+                        LayoutError::fail(format!(
+                            "Ambiguous reference to Cell {} in Library {}: candidates {}",
+                            name,
+                            self.name,
+                            names.join(", ")
+                        ))
+                    }
+                }
+            }
+        }
+    }
+    /// Resolve the [cell::Cell] named `cell` along sub-library path `lib_path`, as in [Library::resolve_ref].
+    /// Returns `None` if any library along the path, or the cell itself, is not found.
+    fn resolve_qualified(
+        &self,
+        lib_path: &[String],
+        cell: &str,
+    ) -> LayoutResult<Option<Ptr<cell::Cell>>> {
+        let (first, rest) = match lib_path.split_first() {
+            Some(split) => split,
+            None => return self.cell_named(cell),
+        };
+        for sub in self.libs.iter() {
+            let sub = sub.read()?;
+            if sub.name == *first {
+                return sub.resolve_qualified(rest, cell);
+            }
+        }
+        Ok(None)
+    }
+    /// Resolve each of [CellRef]s `crefs`, as in [Library::resolve_ref]
+    pub fn resolve_refs(&self, crefs: &[CellRef]) -> LayoutResult<Vec<Ptr<cell::Cell>>> {
+        crefs.iter().map(|c| self.resolve_ref(c)).collect()
+    }
+    /// Get all [cell::Cell]s in our sub-library tree, each paired with its sub-library path.
+    /// Sub-library cells come first, depth-first, followed by our own `cells` with empty paths.
+    pub fn qualified_cells(&self) -> LayoutResult<Vec<(Vec<String>, Ptr<cell::Cell>)>> {
+        let mut cells = Vec::new();
+        for sub in self.libs.iter() {
+            let sub = sub.read()?;
+            for (mut path, ptr) in sub.qualified_cells()? {
+                path.insert(0, sub.name.clone());
+                cells.push((path, ptr));
+            }
+        }
+        for ptr in self.cells.iter() {
+            cells.push((Vec::new(), ptr.clone()));
+        }
+        Ok(cells)
+    }
+    /// Wrap the [raw::Cell] named `name`, from any of our `rawlibs`, in a new [cell::Cell].
+    /// The raw cell's footprint is declared by `outline`, and its use of the lowest `metals` metal layers.
+    /// Returns the existing wrapper if `name` has already been wrapped.
+    pub fn wrap_raw_cell(
+        &mut self,
+        name: &str,
+        outline: Outline,
+        metals: usize,
+    ) -> LayoutResult<Ptr<cell::Cell>> {
+        if let Ok(ptr) = self.resolve_ref(&CellRef::Raw(name.into())) {
+            return Ok(ptr);
+        }
+        for libptr in self.rawlibs.iter() {
+            let rawlib = libptr.read()?;
+            for cellptr in rawlib.cells.iter() {
+                if cellptr.read()?.name == name {
+                    let wrapped = cell::RawLayoutPtr {
+                        outline,
+                        metals,
+                        lib: libptr.clone(),
+                        cell: cellptr.clone(),
+                    };
+                    drop(rawlib);
+                    return Ok(self.cells.insert(wrapped));
+                }
+            }
+        }
+        LayoutError::fail(format!(
+            "Raw cell {} not found in Library {}",
+            name, self.name
+        ))
+    }
     /// Add a [raw::Library]
     pub fn add_rawlib(&mut self, rawlib: raw::Library) -> Ptr<raw::Library> {
         self.rawlibs.insert(rawlib)
     }
     /// Create an ordered list in which dependent cells follow their dependencies.
-    pub fn dep_order(&self) -> Vec<Ptr<cell::Cell>> {
+    /// Includes the cells of all sub-libraries.
+    /// Fails if any [Cell](cell::Cell) instantiates itself, directly or through its descendants,
+    /// or if any cell or instance cannot be read.
+    pub fn dep_order(&self) -> LayoutResult<Vec<Ptr<cell::Cell>>> {
         DepOrder::order(self)
     }
+    /// Get every leaf [Placement] in the hierarchy of the [cell::Cell] with [CellId] `top`, in depth-first instance order.
+    ///
+    /// Leaves are cells with no instances, e.g. those defined by an abstract or raw layout.
+    /// All instances must be placed in absolute coordinates, e.g. by [Placer::place](crate::placer::Placer::place).
+    /// Fails if `top` is not in our `cells`, or if any cell recursively instantiates itself.
+    pub fn placements(&self, top: CellId) -> LayoutResult<Vec<Placement>> {
+        let ptr = match self.cell(top) {
+            Some(ptr) => ptr,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cell {:?} not found in Library {}",
+                    top, self.name
+                ))
+            }
+        };
+        let top = Placement {
+            path: ptr.read()?.name.clone(),
+            cell: ptr.clone(),
+            depth: 0,
+            loc: Xy::new(PrimPitches::x(0), PrimPitches::y(0)),
+            reflect_horiz: false,
+            reflect_vert: false,
+        };
+        let mut placements = Vec::new();
+        let mut visiting = Vec::new();
+        placements_helper(top, &mut visiting, &mut placements)?;
+        Ok(placements)
+    }
+    /// Prefix the name of each of our [Cell](cell::Cell)s, and of each cell of our `rawlibs`, with `prefix`,
+    /// e.g. to guarantee their uniqueness before merging with another library.
+    ///
+    /// Renames the [Abstract](crate::abs::Abstract) and [Layout] views of each cell along with it.
+    /// [Instance]s refer to their cells by pointer, and so follow along.
+    /// Cells of sub-libraries are not renamed, as their exported names are already qualified by library path.
+    /// Returns the map from each old name to its new one.
+    pub fn prefix_names(&mut self, prefix: &str) -> LayoutResult<RenameMap> {
+        let mut renames = RenameMap::new();
+        for ptr in self.cells.iter() {
+            let mut cell = ptr.write()?;
+            let name = format!("{}{}", prefix, cell.name);
+            if let Some(ref mut abs) = cell.abs {
+                abs.name = name.clone();
+            }
+            if let Some(ref mut layout) = cell.layout {
+                layout.name = name.clone();
+            }
+            let old = std::mem::replace(&mut cell.name, name.clone());
+            renames.insert(old, name);
+        }
+        for rawlib in self.rawlibs.iter() {
+            let rawlib = rawlib.read()?;
+            for ptr in rawlib.cells.iter() {
+                let mut cell = ptr.write()?;
+                let name = format!("{}{}", prefix, cell.name);
+                if let Some(ref mut abs) = cell.abs {
+                    abs.name = name.clone();
+                }
+                if let Some(ref mut layout) = cell.layout {
+                    layout.name = name.clone();
+                }
+                let old = std::mem::replace(&mut cell.name, name.clone());
+                renames.insert(old, name);
+            }
+        }
+        Ok(renames)
+    }
+    /// Check that the views of each [Cell](cell::Cell), including those reached only through [Instance]s,
+    /// share the name of the cell itself, reporting any [Violation::NameDrift].
+    ///
+    /// Name-based lookups, e.g. by [Library::check_outline_consistency], rely on these agreeing.
+    pub fn check_names(&self) -> LayoutResult<Violations> {
+        let mut violations = Violations::default();
+        for ptr in self.dep_order()? {
+            let cell = ptr.read()?;
+            let mut views = Vec::new();
+            if let Some(ref abs) = cell.abs {
+                views.push(("Abstract", abs.name.clone()));
+            }
+            if let Some(ref layout) = cell.layout {
+                views.push(("Layout", layout.name.clone()));
+            }
+            if let Some(ref raw) = cell.raw {
+                views.push(("Raw cell", raw.cell.read()?.name.clone()));
+            }
+            for (view, name) in views {
+                if name != cell.name {
+                    violations.push(Violation::NameDrift {
+                        cell: cell.name.clone(),
+                        view: view.into(),
+                        name,
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+    ///
+    /// Compares each [abs::Abstract](crate::abs::Abstract)'s outline to that of the same-named [Layout](crate::layout::Layout),
+    /// and checks each absolutely-placed [Instance](crate::instance::Instance)'s current footprint
+    /// against its parent's outline and its sibling instances.
+    /// Relatively-placed instances are not checked.
+    pub fn check_outline_consistency(&self) -> LayoutResult<Violations> {
+        let mut violations = Violations::default();
+
+        // Collect the implementation outlines by name
+        let mut layouts: HashMap<String, Outline> = HashMap::new();
+        for ptr in self.cells.iter() {
+            let cell = ptr.read()?;
+            if let Some(ref layout) = cell.layout {
+                layouts.insert(layout.name.clone(), layout.outline.clone());
+            }
+        }
+        for ptr in self.cells.iter() {
+            let cell = ptr.read()?;
+            if let Some(ref abs) = cell.abs {
+                if let Some(layout) = layouts.get(&abs.name) {
+                    if *layout != abs.outline {
+                        violations.push(Violation::AbstractOutline {
+                            cell: abs.name.clone(),
+                            abs: abs.outline.clone(),
+                            layout: layout.clone(),
+                        });
+                    }
+                }
+            }
+            let layout = match cell.layout {
+                Some(ref layout) => layout,
+                None => continue,
+            };
+            // Collect the footprints of each absolutely-placed instance
+            let footprints = layout.footprints()?;
+            let (xmax, ymax) = (layout.outline.xmax().num, layout.outline.ymax().num);
+            for (idx, (name, bbox)) in footprints.iter().enumerate() {
+                if bbox.p0.x.num < 0
+                    || bbox.p0.y.num < 0
+                    || bbox.p1.x.num > xmax
+                    || bbox.p1.y.num > ymax
+                {
+                    violations.push(Violation::OutsideParent {
+                        cell: layout.name.clone(),
+                        inst: name.clone(),
+                    });
+                }
+                for (other, obox) in footprints[idx + 1..].iter() {
+                    if overlaps(bbox, obox) {
+                        violations.push(Violation::Overlap {
+                            cell: layout.name.clone(),
+                            insts: (name.clone(), other.clone()),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(violations)
+    }
+    /// Check for mis-abutted [Instance](crate::instance::Instance)s:
+    /// pairs whose facing edges are separated by, or overlap by, a partial placement site of `stack`.
+    pub fn check_abutments(&self, stack: &validate::ValidStack) -> LayoutResult<Violations> {
+        let mut violations = Violations::default();
+        let site = stack.site_pitches();
+        for ptr in self.cells.iter() {
+            let cell = ptr.read()?;
+            let layout = match cell.layout {
+                Some(ref layout) => layout,
+                None => continue,
+            };
+            let footprints = layout.footprints()?;
+            for (idx, a) in footprints.iter().enumerate() {
+                for b in footprints[idx + 1..].iter() {
+                    for dir in [Dir::Horiz, Dir::Vert] {
+                        let other = !dir;
+                        // Skip pairs which don't face each other along `dir`
+                        if a.1.p0[other].num.max(b.1.p0[other].num)
+                            >= a.1.p1[other].num.min(b.1.p1[other].num)
+                        {
+                            continue;
+                        }
+                        let (lo, hi) = if a.1.p0[dir].num <= b.1.p0[dir].num {
+                            (a, b)
+                        } else {
+                            (b, a)
+                        };
+                        // Distance between facing edges; negative for overlaps
+                        let dist = hi.1.p0[dir].num - lo.1.p1[dir].num;
+                        if dist != 0 && dist.abs() < site[dir].num {
+                            violations.push(Violation::Misabutment {
+                                cell: layout.name.clone(),
+                                insts: (lo.0.clone(), hi.0.clone()),
+                                dir,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+/// Create the error for an unresolved [CellRef]
+fn not_found(cref: &CellRef, lib: &str) -> LayoutError {
+    LayoutError::msg(format!("Cell {} not found in Library {}", cref, lib))
+}
+
+/// # Outline-Consistency Violation
+///
+/// A single finding of [Library::check_outline_consistency] or [Library::check_abutments].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Abstract outline differs from that of its implementing [Layout](crate::layout::Layout)
+    AbstractOutline {
+        cell: String,
+        abs: Outline,
+        layout: Outline,
+    },
+    /// Instance footprint extends beyond its parent's outline
+    OutsideParent { cell: String, inst: String },
+    /// Instance footprints overlap
+    Overlap {
+        cell: String,
+        insts: (String, String),
+    },
+    /// Instance edges facing in `dir` are separated or overlapped by a partial placement site
+    Misabutment {
+        cell: String,
+        insts: (String, String),
+        dir: Dir,
+    },
+    /// A view of a cell is named differently from the cell itself
+    NameDrift {
+        cell: String,
+        view: String,
+        name: String,
+    },
+}
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::AbstractOutline { cell, abs, layout } => write!(
+                f,
+                "Abstract outline of {} ({:?}) differs from its Layout's ({:?})",
+                cell, abs, layout
+            ),
+            Violation::OutsideParent { cell, inst } => write!(
+                f,
+                "Instance {} extends beyond the outline of parent {}",
+                inst, cell
+            ),
+            Violation::Overlap { cell, insts } => write!(
+                f,
+                "Instances {} and {} overlap in {}",
+                insts.0, insts.1, cell
+            ),
+            Violation::Misabutment { cell, insts, dir } => write!(
+                f,
+                "Instances {} and {} are mis-abutted in the {:?} direction in {}",
+                insts.0, insts.1, dir, cell
+            ),
+            Violation::NameDrift { cell, view, name } => {
+                write!(f, "{} of Cell {} is named {}", view, cell, name)
+            }
+        }
+    }
+}
+/// Map from each old cell name to its new one, as returned by [Library::prefix_names]
+pub type RenameMap = BTreeMap<String, String>;
+/// # Library-Consistency Violations
+///
+/// Collection of [Violation]s, as returned by [Library::check_outline_consistency] and [Library::check_names].
+/// Dereferences to the underlying [Vec].
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Violations(pub Vec<Violation>);
+impl std::ops::Deref for Violations {
+    type Target = Vec<Violation>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl std::ops::DerefMut for Violations {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+/// Boolean indication of whether [BoundBox]es `a` and `b` overlap with non-zero area
+fn overlaps(a: &BoundBox<PrimPitches>, b: &BoundBox<PrimPitches>) -> bool {
+    a.p0.x.num < b.p1.x.num
+        && b.p0.x.num < a.p1.x.num
+        && a.p0.y.num < b.p1.y.num
+        && b.p0.y.num < a.p1.y.num
+}
+
+/// # Flattened Placement
+///
+/// A leaf [cell::Cell] placed somewhere in the hierarchy of a top-level cell, as produced by [Library::placements].
+/// Reflections are about the cell's origin, as for [Instance](crate::instance::Instance)s.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    /// Instance path from the top-level cell, e.g. `top/u_core/u_alu`
+    pub path: String,
+    /// Placed cell
+    pub cell: Ptr<cell::Cell>,
+    /// Number of instance-levels below the top-level cell
+    pub depth: usize,
+    /// Location of the cell's origin, in top-level [PrimPitches]
+    pub loc: Xy<PrimPitches>,
+    /// Accumulated horizontal reflection
+    pub reflect_horiz: bool,
+    /// Accumulated vertical reflection
+    pub reflect_vert: bool,
+}
+/// Internal helper and core logic for [Library::placements].
+/// Adds `place` to `placements` if it is a leaf, or otherwise recursively visits its instances.
+fn placements_helper(
+    place: Placement,
+    visiting: &mut Vec<Ptr<cell::Cell>>,
+    placements: &mut Vec<Placement>,
+) -> LayoutResult<()> {
+    let ptr = place.cell.clone();
+    let cell = ptr.read()?;
+    let instances = match cell.layout {
+        Some(ref layout) if !layout.instances.is_empty() => &layout.instances,
+        _ => {
+            placements.push(place);
+            return Ok(());
+        }
+    };
+    visiting.push(ptr.clone());
+    for instptr in instances.iter() {
+        let inst = instptr.read()?;
+        if visiting.contains(&inst.cell) {
+            return LayoutError::fail(format!(
+                "Instance {} of Cell {} recursively instantiates one of its parent Cells",
+                inst.inst_name, cell.name
+            ));
+        }
+        // Apply our reflections to the instance location, and compose them with its own
+        let loc = inst.loc.abs()?;
+        let sign = |reflected: bool| -> Int {
+            if reflected {
+                -1
+            } else {
+                1
+            }
+        };
+        let x = loc.x * sign(place.reflect_horiz);
+        let y = loc.y * sign(place.reflect_vert);
+        let child = Placement {
+            path: format!("{}/{}", place.path, inst.inst_name),
+            cell: inst.cell.clone(),
+            depth: place.depth + 1,
+            loc: Xy::new(place.loc.x + x, place.loc.y + y),
+            reflect_horiz: place.reflect_horiz ^ inst.reflect_horiz,
+            reflect_vert: place.reflect_vert ^ inst.reflect_vert,
+        };
+        placements_helper(child, visiting, placements)?;
+    }
+    visiting.pop();
+    Ok(())
 }
 
 /// # Dependency-Orderer
@@ -59,34 +885,59 @@ pub struct DepOrder<'lib> {
     lib: &'lib Library,
     stack: Vec<Ptr<cell::Cell>>,
     seen: HashSet<Ptr<cell::Cell>>,
+    /// Cells whose dependencies are being visited, for detecting recursive instantiation
+    visiting: HashSet<Ptr<cell::Cell>>,
 }
 impl<'lib> DepOrder<'lib> {
-    fn order(lib: &'lib Library) -> Vec<Ptr<cell::Cell>> {
+    fn order(lib: &'lib Library) -> LayoutResult<Vec<Ptr<cell::Cell>>> {
         let mut myself = Self {
             lib,
             stack: Vec::new(),
             seen: HashSet::new(),
+            visiting: HashSet::new(),
         };
-        for cell in myself.lib.cells.iter() {
-            myself.push(cell);
+        // Visit cells of any sub-libraries, followed by our own
+        for (_path, cell) in myself.lib.qualified_cells()?.iter() {
+            myself.push(cell)?;
         }
-        myself.stack
+        Ok(myself.stack)
     }
-    fn push(&mut self, ptr: &Ptr<cell::Cell>) {
-        // If the Cell hasn't already been visited, depth-first search it
-        if !self.seen.contains(&ptr) {
-            // Read the cell-pointer
-            let cell = ptr.read().unwrap();
-            // If the cell has an implementation, visit its [Instance]s before inserting it
-            if let Some(layout) = &cell.layout {
-                for ptr in layout.instances.iter() {
-                    let inst = ptr.read().unwrap();
-                    self.push(&inst.cell);
+    fn push(&mut self, ptr: &Ptr<cell::Cell>) -> LayoutResult<()> {
+        // If the Cell has already been visited, we're done
+        if self.seen.contains(ptr) {
+            return Ok(());
+        }
+        // Read the cell-pointer
+        let cell = ptr.read()?;
+        // If the cell has an implementation, visit its [Instance]s before inserting it
+        if let Some(layout) = &cell.layout {
+            self.visiting.insert(Ptr::clone(ptr));
+            for (idx, instptr) in layout.instances.iter().enumerate() {
+                let inst = instptr.read().map_err(|_| {
+                    LayoutError::msg(format!(
+                        "Could not read Instance #{} of Cell {}",
+                        idx, cell.name
+                    ))
+                })?;
+                if self.visiting.contains(&inst.cell) {
+                    return LayoutError::fail(format!(
+                        "Instance {} of Cell {} recursively instantiates one of its parent Cells",
+                        inst.inst_name, cell.name
+                    ));
                 }
+                self.push(&inst.cell).map_err(|e| match e {
+                    LayoutError::PtrLock => LayoutError::msg(format!(
+                        "Could not read the Cell of Instance {} of Cell {}",
+                        inst.inst_name, cell.name
+                    )),
+                    e => e,
+                })?;
             }
-            // And insert the cell (pointer) itself
-            self.seen.insert(Ptr::clone(ptr));
-            self.stack.push(Ptr::clone(ptr));
+            self.visiting.remove(ptr);
         }
+        // And insert the cell (pointer) itself
+        self.seen.insert(Ptr::clone(ptr));
+        self.stack.push(Ptr::clone(ptr));
+        Ok(())
     }
 }
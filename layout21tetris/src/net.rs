@@ -0,0 +1,185 @@
+//!
+//! # Net Names
+//!
+//! Validation of net names, which are written verbatim into exported layout text,
+//! and the [NetNamePolicy] configuring it.
+//! Bus bits are referred to by [NetRef]s, and rendered to names per a [BusStyle].
+//!
+
+// Crates.io
+use serde::{Deserialize, Serialize};
+
+// Std-Lib
+use std::fmt;
+use std::str::FromStr;
+
+// Local imports
+use crate::raw::{LayoutError, LayoutResult};
+use crate::tracks::RailKind;
+
+/// # Net-Name Policy
+///
+/// Configures the checks performed by [NetName].
+/// Generally part of a [Stack](crate::stack::Stack).
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct NetNamePolicy {
+    /// Maximum name length, in bytes
+    pub max_len: usize,
+    /// Power and ground net names, reserved for rail tracks
+    pub rail_nets: Vec<String>,
+    /// Handling of signal assignments to `rail_nets`
+    pub rail_conflict: RailConflict,
+    /// Rendering of bus-bit [NetRef]s
+    pub bus_style: BusStyle,
+}
+impl Default for NetNamePolicy {
+    fn default() -> Self {
+        Self {
+            max_len: 256,
+            rail_nets: vec![RailKind::Pwr.to_string(), RailKind::Gnd.to_string()],
+            rail_conflict: RailConflict::default(),
+            bus_style: BusStyle::default(),
+        }
+    }
+}
+/// # Rail-Name Conflict Handling
+///
+/// Whether signal assignments to reserved rail-net names are errors or warnings.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum RailConflict {
+    Error,
+    #[default]
+    Warn,
+}
+
+/// # Net-Name Validation
+///
+/// Namespace for net-name checks, per a [NetNamePolicy].
+///
+#[derive(Debug)]
+pub struct NetName;
+impl NetName {
+    /// Validate net-name `name`.
+    /// Names must be non-empty, no longer than `policy.max_len`,
+    /// and include no whitespace or control characters.
+    pub fn validate(name: &str, policy: &NetNamePolicy) -> LayoutResult<()> {
+        if name.is_empty() {
+            return LayoutError::fail("Invalid empty net name");
+        }
+        if name.len() > policy.max_len {
+            return LayoutError::fail(format!(
+                "Invalid net name {}: length {} exceeds maximum {}",
+                name,
+                name.len(),
+                policy.max_len
+            ));
+        }
+        if let Some(c) = name.chars().find(|c| c.is_whitespace() || c.is_control()) {
+            return LayoutError::fail(format!(
+                "Invalid net name {:?}: includes character {:?}",
+                name, c
+            ));
+        }
+        Ok(())
+    }
+    /// Validate net-name `name` for assignment to a signal track.
+    /// In addition to [NetName::validate], checks `name` against the reserved `policy.rail_nets`.
+    /// Conflicts fail for [RailConflict::Error], and return a warning message for [RailConflict::Warn].
+    pub fn validate_signal(name: &str, policy: &NetNamePolicy) -> LayoutResult<Option<String>> {
+        Self::validate(name, policy)?;
+        if !policy.rail_nets.iter().any(|r| r == name) {
+            return Ok(None);
+        }
+        let msg = format!("Rail net {} assigned to a signal track", name);
+        match policy.rail_conflict {
+            RailConflict::Error => LayoutError::fail(msg),
+            RailConflict::Warn => Ok(Some(msg)),
+        }
+    }
+}
+
+/// # Bus-Bit Naming Style
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum BusStyle {
+    /// Bracketed indices, e.g. `data[3]`
+    #[default]
+    Brackets,
+    /// Underscore-separated indices, e.g. `data_3`
+    Underscore,
+}
+
+/// # Net Reference
+///
+/// A scalar net, or a single bit of a bus, e.g. of an [interface::PortKind::Array](crate::interface::PortKind::Array).
+/// Rendered to a net name by [NetRef::render], and parsed from one by [NetRef::parse].
+/// [Display](fmt::Display) and [FromStr] use [BusStyle::Brackets].
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NetRef {
+    /// Base Name
+    pub base: String,
+    /// Bit Index, if a bus bit
+    pub bit: Option<usize>,
+}
+impl NetRef {
+    /// Create a scalar [NetRef]
+    pub fn scalar(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            bit: None,
+        }
+    }
+    /// Create a [NetRef] to bit `bit` of bus `base`
+    pub fn bit(base: impl Into<String>, bit: usize) -> Self {
+        Self {
+            base: base.into(),
+            bit: Some(bit),
+        }
+    }
+    /// Render to a net name in [BusStyle] `style`
+    pub fn render(&self, style: BusStyle) -> String {
+        match (self.bit, style) {
+            (None, _) => self.base.clone(),
+            (Some(bit), BusStyle::Brackets) => format!("{}[{}]", self.base, bit),
+            (Some(bit), BusStyle::Underscore) => format!("{}_{}", self.base, bit),
+        }
+    }
+    /// Parse from net-name `name` in [BusStyle] `style`.
+    /// Names without a trailing index in `style` are parsed as scalars.
+    pub fn parse(name: &str, style: BusStyle) -> LayoutResult<Self> {
+        if name.is_empty() {
+            return LayoutError::fail("Invalid empty net name");
+        }
+        let split = match style {
+            BusStyle::Brackets => name.strip_suffix(']').and_then(|s| s.rsplit_once('[')),
+            BusStyle::Underscore => name
+                .rsplit_once('_')
+                .filter(|(_, idx)| !idx.is_empty() && idx.chars().all(|c| c.is_ascii_digit())),
+        };
+        let (base, idx) = match split {
+            Some(split) => split,
+            None => return Ok(Self::scalar(name)),
+        };
+        if base.is_empty() {
+            return LayoutError::fail(format!("Invalid net name {} with empty base", name));
+        }
+        let bit = idx
+            .parse::<usize>()
+            .map_err(|_| LayoutError::msg(format!("Invalid bit index in net name {}", name)))?;
+        Ok(Self::bit(base, bit))
+    }
+}
+impl fmt::Display for NetRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(BusStyle::Brackets))
+    }
+}
+impl FromStr for NetRef {
+    type Err = LayoutError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, BusStyle::Brackets)
+    }
+}
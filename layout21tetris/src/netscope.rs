@@ -0,0 +1,255 @@
+//!
+//! # Hierarchical Net Scopes
+//!
+//! Names nets across instance boundaries, and unifies those connected between parent and child cells.
+//! A child's net `out`, within instance `u_inv1`, is named `u_inv1/out` in its parent's scope.
+//! Where a parent net connects to a child port, the two are unified, and reported under the parent's name.
+//!
+//! Connections between parent and child are found in two ways:
+//! * A parent net-assignment landing on a child's [PortKind::ZTopEdge] or [PortKind::ZTopInner] port, and
+//! * Port promotion: a parent [abs::Port] coinciding with a child port, on the same track at the parent's edge (for [PortKind::Edge]),
+//!   or on the same top-layer track (for [PortKind::ZTopEdge]).
+//!
+//! Edge ports connected by abutment between sibling instances are not (yet) detected.
+//!
+
+// Std-Lib Imports
+use std::collections::{BTreeSet, HashMap};
+
+// Local imports
+use crate::abs::{self, PortKind};
+use crate::cell::Cell;
+use crate::coords::{LayerIndex, PrimPitches};
+use crate::instance::PortLoc;
+use crate::raw::LayoutResult;
+use crate::stack::Assign;
+use crate::tracks::{TrackCross, TrackRef};
+use crate::validate::ValidStack;
+
+/// # Net Scope
+///
+/// Hierarchically-named nets, keyed by instance path, with unification of connected nets.
+/// Each set of unified nets is named after its member in the shallowest scope,
+/// breaking ties alphabetically.
+///
+#[derive(Debug, Clone)]
+pub struct NetScope {
+    /// Separator between instance-path components and net names
+    sep: String,
+    /// Full (path-prefixed) name of each net
+    names: Vec<String>,
+    /// Map from full name to index into `names`
+    index: HashMap<String, usize>,
+    /// Union-find parent of each net
+    parents: Vec<usize>,
+    /// Instance-path depth of each net
+    depths: Vec<usize>,
+    /// Whether each net is a port of its cell
+    ports: Vec<bool>,
+}
+impl Default for NetScope {
+    fn default() -> Self {
+        Self::new("/")
+    }
+}
+impl NetScope {
+    /// Create a new and empty [NetScope], separating path components with `sep`
+    pub fn new(sep: impl Into<String>) -> Self {
+        Self {
+            sep: sep.into(),
+            names: Vec::new(),
+            index: HashMap::new(),
+            parents: Vec::new(),
+            depths: Vec::new(),
+            ports: Vec::new(),
+        }
+    }
+    /// Get the full name of net `net` within instance-path `path`, e.g. `u_top/u_inv1/out`
+    pub fn name(&self, path: &[String], net: &str) -> String {
+        let mut name = String::new();
+        for inst in path {
+            name.push_str(inst);
+            name.push_str(&self.sep);
+        }
+        name.push_str(net);
+        name
+    }
+    /// Add net `net` within instance-path `path`, returning its index.
+    /// Returns the existing index if already added.
+    pub fn add(&mut self, path: &[String], net: &str) -> usize {
+        let name = self.name(path, net);
+        if let Some(idx) = self.index.get(&name) {
+            return *idx;
+        }
+        let idx = self.names.len();
+        self.index.insert(name.clone(), idx);
+        self.names.push(name);
+        self.parents.push(idx);
+        self.depths.push(path.len());
+        self.ports.push(false);
+        idx
+    }
+    /// Add port `port` of the cell at instance-path `path`, returning its index
+    pub fn add_port(&mut self, path: &[String], port: &str) -> usize {
+        let idx = self.add(path, port);
+        self.ports[idx] = true;
+        idx
+    }
+    /// Unify the nets at indices `a` and `b`
+    pub fn unify(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        // Keep the representative with the shallowest, then alphabetically-first, name
+        let key = |i: usize| (self.depths[i], &self.names[i]);
+        if key(a) <= key(b) {
+            self.parents[b] = a;
+        } else {
+            self.parents[a] = b;
+        }
+    }
+    /// Get the name of the unified net including net `net` within instance-path `path`, if it has been added
+    pub fn resolve(&self, path: &[String], net: &str) -> Option<&str> {
+        let idx = self.index.get(&self.name(path, net))?;
+        Some(&self.names[self.find(*idx)])
+    }
+    /// Get the sorted names of all unified nets
+    pub fn nets(&self) -> Vec<String> {
+        let nets: BTreeSet<&String> = (0..self.names.len())
+            .map(|i| &self.names[self.find(i)])
+            .collect();
+        nets.into_iter().cloned().collect()
+    }
+    /// Get the sorted full names of each child-instance port left floating:
+    /// not unified with any net in a shallower scope, i.e. unconnected in its parent.
+    pub fn floating(&self) -> Vec<String> {
+        let mut floating: Vec<String> = (0..self.names.len())
+            .filter(|i| self.ports[*i] && self.depths[*i] > 0)
+            .filter(|i| self.depths[self.find(*i)] >= self.depths[*i])
+            .map(|i| self.names[i].clone())
+            .collect();
+        floating.sort();
+        floating
+    }
+    /// Find the representative of the unified net including index `idx`
+    fn find(&self, mut idx: usize) -> usize {
+        while self.parents[idx] != idx {
+            idx = self.parents[idx];
+        }
+        idx
+    }
+    /// Add the nets of `cell`, at instance-path `path`, and recursively those of its instances.
+    /// Instances must be absolutely placed.
+    fn add_cell(
+        &mut self,
+        cell: &Cell,
+        path: &mut Vec<String>,
+        stack: &ValidStack,
+    ) -> LayoutResult<()> {
+        // Our own ports, and their locations for checking promotion
+        let mut own_ports = Vec::new();
+        if let Some(ref abs) = cell.abs {
+            for port in abs.ports.iter() {
+                own_ports.push((self.add_port(path, &port.name), port));
+            }
+        }
+        let layout = match cell.layout {
+            Some(ref layout) => layout,
+            None => return Ok(()),
+        };
+        let mut assigns = Vec::with_capacity(layout.assignments.len());
+        for assn in layout.assignments.iter() {
+            assigns.push((self.add(path, &assn.net), assn));
+        }
+        for strap in layout.straps.iter() {
+            self.add(path, &strap.net);
+        }
+        for ptr in layout.instances.iter() {
+            let inst = ptr.read()?;
+            path.push(inst.inst_name.clone());
+            let child = inst.cell.read()?;
+            self.add_cell(&child, path, stack)?;
+            if let Some(ref abs) = child.abs {
+                for port in abs.ports.iter() {
+                    let idx = self.add_port(path, &port.name);
+                    let loc = inst.port_loc(&port.name, stack)?;
+                    for (net, assn) in assigns.iter() {
+                        if lands_on(assn, &loc) {
+                            self.unify(*net, idx);
+                        }
+                    }
+                    for (net, own) in own_ports.iter() {
+                        if promotes(cell, own, &loc, stack)? {
+                            self.unify(*net, idx);
+                        }
+                    }
+                }
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+}
+impl Cell {
+    /// Create the [NetScope] of our hierarchy, with path-separator `sep`.
+    ///
+    /// Includes the nets of our assignments, straps, and ports, and those of each instance, recursively.
+    /// Instances must be absolutely placed, e.g. by [Placer](crate::placer::Placer),
+    /// and each instantiated cell must have an [abs::Abstract] view to be connected to its parent.
+    pub fn net_scope(&self, stack: &ValidStack, sep: &str) -> LayoutResult<NetScope> {
+        let mut scope = NetScope::new(sep);
+        scope.add_cell(self, &mut Vec::new(), stack)?;
+        Ok(scope)
+    }
+}
+/// Boolean indication of whether [Assign] `assn` lands on a port at `loc`
+fn lands_on(assn: &Assign, loc: &PortLoc) -> bool {
+    let at = &assn.at;
+    match loc {
+        PortLoc::ZTopEdge { track, range } => {
+            let in_range = |t: &TrackRef| {
+                t.layer == range.0.layer && range.0.track <= t.track && t.track <= range.1.track
+            };
+            (at.track == *track && in_range(&at.cross))
+                || (at.cross == *track && in_range(&at.track))
+        }
+        PortLoc::ZTopInner { locs, .. } => locs.iter().any(|l| same_cross(l, at)),
+        PortLoc::Edge { .. } => false,
+    }
+}
+/// Boolean indication of whether [TrackCross]es `a` and `b` are at the same intersection, in either order
+fn same_cross(a: &TrackCross, b: &TrackCross) -> bool {
+    (a.track == b.track && a.cross == b.cross) || (a.track == b.cross && a.cross == b.track)
+}
+/// Boolean indication of whether port `own` of `cell` promotes a child port at `loc`
+fn promotes(cell: &Cell, own: &abs::Port, loc: &PortLoc, stack: &ValidStack) -> LayoutResult<bool> {
+    let promotes = match (&own.kind, loc) {
+        (
+            PortKind::Edge { layer, track, side },
+            PortLoc::Edge {
+                track: ctrack,
+                side: cside,
+                at,
+            },
+        ) => {
+            if *ctrack != TrackRef::new(*layer, *track) || side != cside {
+                return Ok(false);
+            }
+            let dir = stack.metal(*layer)?.spec.dir;
+            let edge = match side {
+                abs::Side::BottomOrLeft => PrimPitches::new(dir, 0),
+                abs::Side::TopOrRight => cell.outline()?.max(dir),
+            };
+            *at == edge
+        }
+        (PortKind::ZTopEdge { track, .. }, PortLoc::ZTopEdge { track: ctrack, .. }) => {
+            match cell.top_metal()? {
+                Some(top) => *ctrack == TrackRef::new(LayerIndex(top), *track),
+                None => false,
+            }
+        }
+        _ => false,
+    };
+    Ok(promotes)
+}
@@ -0,0 +1,114 @@
+//!
+//! # Raw-Geometry Track Occupancy
+//!
+//! Maps the shapes of imported [raw::Cell]s onto the tracks of a [ValidStack],
+//! so that parents instantiating them need only block the tracks they actually use.
+//!
+
+// Local imports
+use crate::coords::{DbUnits, LayerIndex, TrackIndex};
+use crate::raw::{self, BoundBoxTrait, LayerPurpose, LayoutError, LayoutResult};
+use crate::tracks::TrackType;
+use crate::validate::ValidStack;
+
+/// # Track Occupancy
+///
+/// Occupied intervals of a single signal track, along its running direction.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackOccupancy {
+    /// Signal-track index
+    pub track: TrackIndex,
+    /// Sorted, non-overlapping (start, stop) intervals
+    pub intervals: Vec<(DbUnits, DbUnits)>,
+}
+/// # Raw Track Occupancy
+///
+/// Analysis of which tracks of a [ValidStack] a [raw::Cell] occupies.
+///
+pub trait RawTrackOccupancy {
+    /// Get the signal tracks of metal layer `layer` which our shapes overlap, in our own coordinates.
+    ///
+    /// Shapes are matched by the layer's `raw` [raw::LayerKey], and so must share the stack's `rawlayers`,
+    /// e.g. as imported via [raw::Library::from_gds] with those layers.
+    /// Label and outline shapes are ignored, as are those of any instances.
+    /// Only tracks of non-negative periods are reported, per [ValidMetalLayer::tracks_in](crate::validate::ValidMetalLayer::tracks_in).
+    /// Tracks are returned in ascending order.
+    fn occupied_tracks(
+        &self,
+        stack: &ValidStack,
+        layer: usize,
+    ) -> LayoutResult<Vec<TrackOccupancy>>;
+}
+impl RawTrackOccupancy for raw::Cell {
+    fn occupied_tracks(
+        &self,
+        stack: &ValidStack,
+        layer: usize,
+    ) -> LayoutResult<Vec<TrackOccupancy>> {
+        let metal = stack.metal(LayerIndex(layer))?;
+        let key = match metal.spec.raw {
+            Some(key) => key,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot map raw shapes onto layer {}, which has no raw layer",
+                    metal.spec.name
+                ))
+            }
+        };
+        let layout = match self.layout {
+            Some(ref layout) => layout,
+            None => return Ok(Vec::new()),
+        };
+        let dir = metal.spec.dir;
+        // Occupied intervals, keyed by track index
+        let mut tracks: Vec<TrackOccupancy> = Vec::new();
+        for elem in layout.elems.iter() {
+            if elem.layer != key
+                || matches!(elem.purpose, LayerPurpose::Label | LayerPurpose::Outline)
+            {
+                continue;
+            }
+            let bbox = elem.inner.bbox();
+            if bbox.is_empty() {
+                continue;
+            }
+            let span = bbox.p0.coord(!dir)..bbox.p1.coord(!dir);
+            let along = (DbUnits(bbox.p0.coord(dir)), DbUnits(bbox.p1.coord(dir)));
+            // Check each signal track which the shape spans
+            for data in metal.tracks_in(span)? {
+                if data.ttype != TrackType::Signal {
+                    continue;
+                }
+                let track = TrackIndex(data.index);
+                let idx = match tracks.binary_search_by_key(&track, |t| t.track) {
+                    Ok(idx) => idx,
+                    Err(idx) => {
+                        tracks.insert(
+                            idx,
+                            TrackOccupancy {
+                                track,
+                                intervals: Vec::new(),
+                            },
+                        );
+                        idx
+                    }
+                };
+                tracks[idx].intervals.push(along);
+            }
+        }
+        // Sort and merge each track's intervals
+        for occ in tracks.iter_mut() {
+            occ.intervals.sort();
+            let mut merged: Vec<(DbUnits, DbUnits)> = Vec::with_capacity(occ.intervals.len());
+            for (start, stop) in occ.intervals.drain(..) {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(stop),
+                    _ => merged.push((start, stop)),
+                }
+            }
+            occ.intervals = merged;
+        }
+        Ok(tracks)
+    }
+}
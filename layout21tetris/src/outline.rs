@@ -9,7 +9,7 @@ use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::coords::{Int, PrimPitches};
+use crate::coords::{Int, PrimPitches, Xy};
 use crate::raw::{Dir, LayoutError, LayoutResult};
 
 /// # Block Outline
@@ -96,4 +96,98 @@ impl Outline {
             Dir::Vert => self.ymax(),
         }
     }
+    /// Boolean indication of whether point `pt` lies inside or on our boundary.
+    /// As outlines are "tetris shaped", a rectangle with its lower-left corner in the first quadrant
+    /// lies inside if and only if its upper-right corner does.
+    pub fn contains(&self, pt: &Xy<PrimPitches>) -> bool {
+        if pt.x.num < 0 || pt.y.num < 0 {
+            return false;
+        }
+        self.x
+            .iter()
+            .zip(self.y.iter())
+            .any(|(x, y)| pt.x.num <= x.num && pt.y.num <= y.num)
+    }
+    /// Area, in square primitive pitches.
+    /// The sum of the rectangles spanning from the y-axis to each `x`, between successive `y` values.
+    pub fn area(&self) -> Int {
+        let mut area = 0;
+        let mut y0 = 0;
+        for (x, y) in self.x.iter().zip(self.y.iter()) {
+            area += x.num * (y.num - y0);
+            y0 = y.num;
+        }
+        area
+    }
+    /// Get our vertices, counter-clockwise from the origin, as described in the [Outline] docs.
+    /// Repeated and colinear vertices, e.g. from equal successive `x` or `y` values, are omitted.
+    /// If `closed`, the origin is repeated as the final point, as GDSII boundaries require.
+    /// SVG and flattened polygons instead generally leave their closure implied.
+    pub fn points(&self, closed: bool) -> Vec<Xy<PrimPitches>> {
+        let pt = |x: Int, y: Int| Xy::new(PrimPitches::x(x), PrimPitches::y(y));
+        // Whether `b` lies along a straight line between `a` and `c`, or repeats either
+        let colinear = |a: &Xy<PrimPitches>, b: &Xy<PrimPitches>, c: &Xy<PrimPitches>| {
+            (a.x == b.x && b.x == c.x) || (a.y == b.y && b.y == c.y)
+        };
+        // Each step adds a vertex at its `x`, at both the prior and its own `y`.
+        // The final point at (0, y[-1]) closes the outline back to the y-axis.
+        let mut yp = 0;
+        let mut all = vec![pt(0, 0)];
+        for (x, y) in self.x.iter().zip(self.y.iter()) {
+            all.push(pt(x.num, yp));
+            yp = y.num;
+            all.push(pt(x.num, yp));
+        }
+        all.push(pt(0, yp));
+
+        let mut pts: Vec<Xy<PrimPitches>> = Vec::with_capacity(all.len());
+        for p in all.into_iter() {
+            if pts.last() == Some(&p) {
+                continue;
+            }
+            while pts.len() >= 2 && colinear(&pts[pts.len() - 2], &pts[pts.len() - 1], &p) {
+                pts.pop();
+            }
+            pts.push(p);
+        }
+        // And check the closing edge, back to the origin
+        while pts.len() >= 3 && colinear(&pts[pts.len() - 2], &pts[pts.len() - 1], &pts[0]) {
+            pts.pop();
+        }
+        debug_assert_eq!(shoelace(&pts), 2 * self.area());
+        if closed {
+            pts.push(pts[0]);
+        }
+        pts
+    }
+    /// Boolean indication of whether we and `other` describe the same shape.
+    /// Unlike equality, ignores redundant steps, e.g. `x=[4, 4], y=[1, 2]` is congruent to `rect(4, 2)`.
+    pub fn congruent(&self, other: &Outline) -> bool {
+        self.points(false) == other.points(false)
+    }
+    /// Reflect about the y-axis, re-anchored to the origin.
+    ///
+    /// Tetris outlines all extend rightward from the y-axis, while their reflections extend leftward from `xmax`.
+    /// Only outlines which are (congruent to) rectangles therefore have valid reflections.
+    /// Fails for all others, e.g. L-shapes.
+    pub fn mirrored(&self) -> LayoutResult<Outline> {
+        let xmax = self.xmax();
+        if self.x.iter().any(|x| *x != xmax) {
+            return LayoutError::fail(format!(
+                "Reflection of non-rectangular Outline {:?} is not a valid Outline",
+                self
+            ));
+        }
+        Self::from_prim_pitches(vec![xmax], vec![self.ymax()])
+    }
+}
+/// Twice the signed area of the polygon with vertices `pts`, positive for counter-clockwise winding
+fn shoelace(pts: &[Xy<PrimPitches>]) -> Int {
+    let n = pts.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&pts[i], &pts[(i + 1) % n]);
+            a.x.num * b.y.num - b.x.num * a.y.num
+        })
+        .sum()
 }
@@ -0,0 +1,295 @@
+//!
+//! # Export Pipeline
+//!
+//! Converts a [Library] through [raw::Library] to each of our on-disk formats, in a single call.
+//! Optionally also writes a lightweight floorplan-view GDSII, per [ConversionMode::FloorplanOnly],
+//! and human-readable route summaries.
+//!
+
+// Std-Lib Imports
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+// Local imports
+use crate::conv::raw::{
+    ConversionMode, ConversionReport, RawExportOptions, RawExporter, RawLibHandling,
+};
+use crate::library::Library;
+use crate::raw::{self, gds::GdsExportOptions, LayoutError, LayoutResult};
+use crate::utils::SerializationFormat::Yaml;
+use crate::validate::ValidStack;
+
+/// # Export Options
+///
+/// Options for [export_all], including which of its output stages to run.
+///
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Options for conversion to [raw::Library]
+    pub raw: RawExportOptions,
+    /// Write the ProtoBuf-format library
+    pub proto: bool,
+    /// Write GDSII
+    pub gds: bool,
+    /// Options for GDSII export, e.g. its [LabelLimit](raw::gds::LabelLimit)
+    pub gds_options: GdsExportOptions,
+    /// Write a YAML rendition alongside each of the binary formats
+    pub yaml: bool,
+    /// Also write a floorplan-view GDSII, with only cell outlines and instances
+    pub floorplan: bool,
+    /// Also write a human-readable route summary per cell, per [Cell::describe_routes](crate::cell::Cell::describe_routes)
+    pub routes: bool,
+    /// Create the output directory and any missing parents, rather than failing if it does not exist
+    pub create_dir: bool,
+}
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            raw: RawExportOptions::default(),
+            proto: true,
+            gds: true,
+            gds_options: GdsExportOptions::default(),
+            yaml: true,
+            floorplan: false,
+            routes: false,
+            create_dir: true,
+        }
+    }
+}
+impl ExportOptions {
+    /// Check, ahead of any conversion, that [export_all] can write the outputs of `lib` into `out_dir`.
+    ///
+    /// Checks that the name of each output file, per [file_stem], is valid on the host OS,
+    /// and that no two cells' route summaries share a file.
+    /// Then creates `out_dir` if it is missing and [ExportOptions::create_dir] is set,
+    /// and checks that it is writable, by creating and removing a new probe file.
+    /// Fails with [LayoutError::InvalidPath] for invalid paths, and [LayoutError::Unwritable] otherwise.
+    pub fn preflight(&self, lib: &Library, out_dir: &Path) -> LayoutResult<()> {
+        let unwritable = |message: String| {
+            Err(LayoutError::Unwritable {
+                path: out_dir.to_path_buf(),
+                message,
+            })
+        };
+        if out_dir.to_string_lossy().contains('\0') {
+            return Err(LayoutError::InvalidPath {
+                path: out_dir.to_path_buf(),
+                message: "contains a NUL character".into(),
+            });
+        }
+        // Check every file name we may write, before touching the file system
+        let stem = file_stem(&lib.name);
+        let exts = [
+            ".proto.bin",
+            ".proto.yaml",
+            ".gds",
+            ".gds.yaml",
+            ".labels.txt",
+            ".floorplan.gds",
+        ];
+        for ext in exts.iter() {
+            check_file_name(out_dir, &stem, ext)?;
+        }
+        if self.routes {
+            // Distinct cell names may sanitize to the same stem, and would overwrite each other's files
+            let mut stems: BTreeMap<String, String> = BTreeMap::new();
+            for ptr in lib.cells.iter() {
+                let cell = ptr.read()?;
+                if cell.layout.is_none() {
+                    continue;
+                }
+                let cell_stem = file_stem(&cell.name);
+                check_file_name(out_dir, &cell_stem, ".routes.txt")?;
+                if let Some(prior) = stems.insert(cell_stem.clone(), cell.name.clone()) {
+                    return Err(LayoutError::InvalidPath {
+                        path: out_dir.join(format!("{}.routes.txt", cell_stem)),
+                        message: format!("written for both Cells {} and {}", prior, cell.name),
+                    });
+                }
+            }
+        }
+        // Then sort out the directory
+        if !out_dir.exists() {
+            if !self.create_dir {
+                return unwritable("directory does not exist".into());
+            }
+            if let Err(e) = std::fs::create_dir_all(out_dir) {
+                return match e.kind() {
+                    std::io::ErrorKind::InvalidInput => Err(LayoutError::InvalidPath {
+                        path: out_dir.to_path_buf(),
+                        message: e.to_string(),
+                    }),
+                    _ => unwritable(format!("could not create directory: {}", e)),
+                };
+            }
+        }
+        if !out_dir.is_dir() {
+            return unwritable("not a directory".into());
+        }
+        // Create a new probe file, leaving any existing file of the same name untouched
+        let mut num = 0;
+        let probe = loop {
+            let probe = out_dir.join(format!(".{}.{}.preflight", stem, num));
+            let created = OpenOptions::new().write(true).create_new(true).open(&probe);
+            match created {
+                Ok(_) => break probe,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && num < 100 => num += 1,
+                Err(e) => return unwritable(format!("could not create a file: {}", e)),
+            }
+        };
+        if let Err(e) = std::fs::remove_file(&probe) {
+            return unwritable(format!("could not remove a file: {}", e));
+        }
+        Ok(())
+    }
+}
+/// Get the file-name stem for output files named after library or cell `name`.
+/// Path separators, characters reserved on common file systems (`:*?"<>|`), and control characters are replaced with `_`.
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+/// Check that the file name comprising `stem` and extension `ext`, in directory `dir`, is valid on the host OS
+fn check_file_name(dir: &Path, stem: &str, ext: &str) -> LayoutResult<()> {
+    let name = format!("{}{}", stem, ext);
+    let invalid = |message: &str| {
+        Err(LayoutError::InvalidPath {
+            path: dir.join(&name),
+            message: message.into(),
+        })
+    };
+    if stem.chars().all(|c| c == '.') {
+        return invalid("empty or dot-only file-name stem");
+    }
+    if name.len() > 255 {
+        return invalid("file name longer than 255 bytes");
+    }
+    if cfg!(windows) {
+        // Device names are reserved on Windows, with or without extensions
+        let upper = stem.trim_end_matches(' ').to_ascii_uppercase();
+        let reserved = matches!(upper.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+            || ((upper.starts_with("COM") || upper.starts_with("LPT"))
+                && upper.len() == 4
+                && upper.ends_with(|c: char| ('1'..='9').contains(&c)));
+        if reserved {
+            return invalid("reserved device name");
+        }
+    }
+    Ok(())
+}
+/// # Export Artifacts
+///
+/// Results of [export_all]: the paths written, and the [ConversionReport] of its raw conversion.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ExportArtifacts {
+    /// Paths written, in order
+    pub paths: Vec<PathBuf>,
+    /// Report from conversion to [raw::Library], including any warnings from GDSII export
+    pub report: ConversionReport,
+    /// Map from each truncated GDSII label's original text to its exported text,
+    /// per [LongLabelPolicy::Truncate](raw::gds::LongLabelPolicy::Truncate)
+    pub labels: BTreeMap<String, String>,
+}
+/// Export [Library] `lib`, with [ValidStack] `stack`, into directory `out_dir`, per [ExportOptions] `opts`.
+///
+/// Files are named after the library, e.g. `out_dir/mylib.gds`:
+/// * `.proto.bin` and `.proto.yaml` for ProtoBuf,
+/// * `.gds` and `.gds.yaml` for GDSII,
+/// * `.labels.txt` for the GDSII label-truncation table, if any labels are truncated,
+/// * `.floorplan.gds` for the floorplan view, if enabled, and
+/// * `{cell}.routes.txt` for each cell's route summary, if enabled.
+///
+/// Library and cell names are sanitized per [file_stem].
+/// Output locations are checked before conversion, per [ExportOptions::preflight],
+/// which by default creates `out_dir` and any missing parents.
+/// Both `lib` and `stack` are consumed, as by [RawExporter::convert_with_options].
+pub fn export_all(
+    lib: Library,
+    stack: ValidStack,
+    out_dir: impl AsRef<Path>,
+    opts: &ExportOptions,
+) -> LayoutResult<ExportArtifacts> {
+    let out_dir = out_dir.as_ref();
+    opts.preflight(&lib, out_dir)?;
+    let stem = file_stem(&lib.name);
+    // Describe each cell's routes ahead of conversion, which consumes `lib` and `stack`
+    let mut routes = Vec::new();
+    if opts.routes {
+        for ptr in lib.cells.iter() {
+            let cell = ptr.read()?;
+            if cell.layout.is_some() {
+                routes.push((file_stem(&cell.name), cell.describe_routes(&stack)?));
+            }
+        }
+    }
+    // Convert the floorplan view first, referring to any raw-library cells by name,
+    // leaving them unmodified for the full conversion
+    let floorplan = if opts.floorplan {
+        let fopts = RawExportOptions {
+            mode: ConversionMode::FloorplanOnly,
+            raw_libs: RawLibHandling::External,
+            ..opts.raw.clone()
+        };
+        let (rawlib, _) = RawExporter::convert_with_options(lib.clone(), stack.clone(), &fopts)?;
+        let gds = rawlib.read()?.to_gds()?;
+        Some(gds)
+    } else {
+        None
+    };
+    let (rawlib, mut report) = RawExporter::convert_with_options(lib, stack, &opts.raw)?;
+    let rawlib = rawlib.read()?;
+    let mut paths = Vec::new();
+    let mut labels = BTreeMap::new();
+
+    if opts.proto {
+        let protolib = rawlib.to_proto()?;
+        let path = out_dir.join(format!("{}.proto.bin", stem));
+        raw::proto::proto::save(&protolib, &path).map_err(LayoutError::Boxed)?;
+        paths.push(path);
+        if opts.yaml {
+            let path = out_dir.join(format!("{}.proto.yaml", stem));
+            Yaml.save(&protolib, &path)?;
+            paths.push(path);
+        }
+    }
+    if opts.gds {
+        let (gds, gds_report) = rawlib.to_gds_with_report(&opts.gds_options)?;
+        let path = out_dir.join(format!("{}.gds", stem));
+        gds.save(&path)?;
+        paths.push(path);
+        if opts.yaml {
+            let path = out_dir.join(format!("{}.gds.yaml", stem));
+            Yaml.save(&gds, &path)?;
+            paths.push(path);
+        }
+        if !gds_report.labels.is_empty() {
+            let path = out_dir.join(format!("{}.labels.txt", stem));
+            std::fs::write(&path, gds_report.labels_table())?;
+            paths.push(path);
+        }
+        labels = gds_report.labels;
+        report.warnings.extend(gds_report.warnings);
+    }
+    if let Some(gds) = floorplan {
+        let path = out_dir.join(format!("{}.floorplan.gds", stem));
+        gds.save(&path)?;
+        paths.push(path);
+    }
+    for (name, txt) in routes {
+        let path = out_dir.join(format!("{}.routes.txt", name));
+        std::fs::write(&path, txt)?;
+        paths.push(path);
+    }
+    Ok(ExportArtifacts {
+        paths,
+        report,
+        labels,
+    })
+}
@@ -21,7 +21,7 @@ use crate::{
 /// Relative placements use the [RelativePlace] struct,
 /// which can be specified relative to any other [Placeable] object.
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Place<AbsType> {
     /// Absolute
     Abs(AbsType),
@@ -118,6 +118,55 @@ impl Side {
             Self::Right => Self::Top,
         }
     }
+    /// Get the opposite side
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+    /// Get the direction in which our edge runs.
+    /// Left and right edges are vertical, top and bottom edges are horizontal.
+    pub fn dir(&self) -> Dir {
+        match self {
+            Self::Left | Self::Right => Dir::Vert,
+            Self::Top | Self::Bottom => Dir::Horiz,
+        }
+    }
+    /// Get the two [Side]s whose edges run in direction `dir`, origin-side first.
+    /// Inverse of [Side::dir]; [Dir] is defined in `layout21::raw`, so this lives here.
+    pub fn along(dir: Dir) -> (Side, Side) {
+        match dir {
+            Dir::Horiz => (Self::Bottom, Self::Top),
+            Dir::Vert => (Self::Left, Self::Right),
+        }
+    }
+}
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Self::Top => "Top",
+            Self::Bottom => "Bottom",
+            Self::Left => "Left",
+            Self::Right => "Right",
+        };
+        write!(f, "{}", s)
+    }
+}
+impl std::str::FromStr for Side {
+    type Err = LayoutError;
+    /// Parse a [Side], case-insensitively
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => LayoutError::fail(format!("Invalid Side `{}`", s)),
+        }
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Align {
@@ -204,9 +253,23 @@ impl Placeable {
                 let p = p.read()?;
                 p.loc.clone()
             }
-            Placeable::Port { .. } => unimplemented!(),
-            Placeable::Assign(_) => unimplemented!(),
+            Placeable::Port { .. } | Placeable::Assign(_) => {
+                return LayoutError::fail(format!(
+                    "Cannot get the location of a {}, as it has no Place",
+                    self.kind()
+                ))
+            }
         };
         Ok(loc)
     }
+    /// Get a short description of the kind of placeable, e.g. for error messages
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Placeable::Instance(_) => "Instance",
+            Placeable::Array(_) => "Array",
+            Placeable::Group(_) => "Group",
+            Placeable::Port { .. } => "Port",
+            Placeable::Assign(_) => "Assignment",
+        }
+    }
 }
@@ -9,8 +9,8 @@ use std::convert::TryFrom;
 
 // Local imports
 use crate::bbox::HasBoundBox;
-use crate::{instance::Instance, layout::Layout};
-use crate::coords::{LayerPitches, PrimPitches, UnitSpeced, Xy};
+use crate::{instance::{Instance, PortLoc}, layout::Layout};
+use crate::coords::{LayerIndex, PrimPitches, TrackIndex, UnitSpeced, Xy};
 use crate::library::Library;
 use crate::array::{Array, ArrayInstance, Arrayable};
 use crate::placement::{
@@ -20,7 +20,7 @@ use crate::raw::{Dir, LayoutError, LayoutResult};
 use crate::utils::{DepOrder, DepOrderer, ErrorContext, ErrorHelper, Ptr};
 use crate::validate::ValidStack;
 use crate::{
-    abs, stack,
+    stack,
     tracks::{TrackCross, TrackRef},
 };
 
@@ -47,7 +47,7 @@ impl Placer {
     fn place_lib(&mut self) -> LayoutResult<()> {
         self.ctx.push(ErrorContext::Library(self.lib.name.clone()));
         // Iterate over all the library's cells, updating their instance-placements.
-        for cellptr in &self.lib.dep_order() {
+        for cellptr in &self.lib.dep_order()? {
             let mut cell = cellptr.write()?;
             self.ctx.push(ErrorContext::Cell(cell.name.clone()));
             if let Some(ref mut layout) = cell.layout {
@@ -99,13 +99,10 @@ impl Placer {
                 Placeable::Assign(ref ptr) => {
                     let assn = ptr.read()?;
                     let abs: TrackCross = self.resolve_assign_place(&assn.loc)?;
-                    let new_assn = stack::Assign {
-                        net: assn.net.clone(),
-                        at: abs,
-                    };
+                    let new_assn = stack::Assign::new(assn.net.clone(), abs);
                     layout.assignments.push(new_assn);
                 }
-                Placeable::Group(_) => unimplemented!(),
+                Placeable::Group(_) => self.fail("Placement of Groups is not (yet) supported")?,
                 Placeable::Port { .. } => (), // Nothing to do, at least until hitting something that *depends* on the Port location
             }
         }
@@ -153,20 +150,20 @@ impl Placer {
             Some(SepBy::UnitSpeced(u)) => {
                 match u {
                     UnitSpeced::PrimPitches(p) => p.clone(),
-                    _ => unimplemented!(), // TODO: other units
+                    _ => self.fail(format!("Array {} separation units {:?} are not (yet) supported", prefix, u))?, // TODO: other units
                 }
             }
-            Some(SepBy::SizeOf(_)) => unimplemented!(),
+            Some(SepBy::SizeOf(_)) => self.fail(format!("Array {} separation by cell size is not (yet) supported", prefix))?,
         };
         let ysep = match array.sep.y {
             None => PrimPitches::y(0),
             Some(SepBy::UnitSpeced(u)) => {
                 match u {
                     UnitSpeced::PrimPitches(p) => p.clone(),
-                    _ => unimplemented!(), // TODO: other units
+                    _ => self.fail(format!("Array {} separation units {:?} are not (yet) supported", prefix, u))?, // TODO: other units
                 }
             }
-            Some(SepBy::SizeOf(_)) => unimplemented!(),
+            Some(SepBy::SizeOf(_)) => self.fail(format!("Array {} separation by cell size is not (yet) supported", prefix))?,
         };
         let sep = Xy::new(xsep, ysep);
 
@@ -200,7 +197,7 @@ impl Placer {
                     // And add its children to ours
                     insts.extend(children);
                 }
-                Arrayable::Group(_arr) => unimplemented!(),
+                Arrayable::Group(_arr) => self.fail(format!("Array {} of Groups is not (yet) supported", prefix))?,
             };
             // Increment the location by our (two-dimensional) increment.
             loc += sep;
@@ -215,7 +212,7 @@ impl Placer {
     ) -> LayoutResult<Xy<PrimPitches>> {
         // FIXME: this should just need the same stuff as `resolve_instance_place`,
         // once we have a consolidated version of [Instance] that covers Arrays.
-        todo!()
+        self.fail(format!("Relative placement of Array {} is not (yet) supported", _inst.name))
     }
     /// Resolve a location of [Instance] `inst` relative to its [RelativePlace] `rel`.
     fn resolve_instance_place(
@@ -230,9 +227,11 @@ impl Placer {
         let bbox = match rel.to {
             Placeable::Instance(ref ptr) => ptr.read()?.boundbox()?,
             Placeable::Array(ref ptr) => ptr.read()?.boundbox()?,
-            Placeable::Group(_) => unimplemented!(),
-            Placeable::Assign(_) => unimplemented!(),
-            Placeable::Port { .. } => unimplemented!(),
+            Placeable::Group(_) | Placeable::Assign(_) | Placeable::Port { .. } => self.fail(format!(
+                "Instance {} placed relative to a {}, which is not (yet) supported",
+                inst.inst_name,
+                rel.to.kind()
+            ))?,
         };
         // The coordinate axes here are referred to as `side`, corresponding to `rel.side`, and `align`, corresponding to `rel.align`.
         // Mapping these back to (x,y) happens at the very end.
@@ -242,7 +241,7 @@ impl Placer {
         let mut side_coord = bbox.side(rel.side);
         let align_side = match rel.align {
             Align::Side(s) => s,
-            _ => unimplemented!(),
+            _ => self.fail(format!("Instance alignment {:?} is not (yet) supported", rel.align))?,
         };
         let mut align_coord = bbox.side(align_side);
         let side_axis = match rel.side {
@@ -296,7 +295,7 @@ impl Placer {
                     UnitSpeced::DbUnits(_) => self.fail("Invalid separation units: DbUnits")?,
                     UnitSpeced::LayerPitches(_) => {
                         // Do a buncha coordinate transformations
-                        todo!()
+                        self.fail("Instance separation in LayerPitches is not (yet) supported")?
                     }
                     UnitSpeced::PrimPitches(ref p) => {
                         if p.dir != side_axis {
@@ -326,10 +325,7 @@ impl Placer {
     fn resolve_assign_place(&mut self, rel: &RelativePlace) -> LayoutResult<TrackCross> {
         let port_loc = match &rel.to {
             Placeable::Port { inst, port } => self.locate_instance_port(&*inst.read()?, port)?,
-            Placeable::Instance(_) => unimplemented!(),
-            Placeable::Array(_) => unimplemented!(),
-            Placeable::Group(_) => unimplemented!(),
-            Placeable::Assign(_) => unimplemented!(),
+            _ => self.fail(format!("Assignments may only be placed relative to Ports, not a {}", rel.to.kind()))?,
         };
         let ref_cross: (TrackRef, TrackRef) = match port_loc {
             PortLoc::ZTopEdge { track, range } => {
@@ -340,27 +336,24 @@ impl Placer {
                             Side::Top | Side::Right => range.1.track,   // FIXME: reflection support
                         }
                     }
-                    Align::Center => (range.0.track + range.1.track) / 2,
-                    Align::Ports(_, _) => unreachable!(),
+                    Align::Center => TrackIndex((range.0.track.index() + range.1.track.index()) / 2),
+                    Align::Ports(_, _) => self.fail("Port-to-port alignment of Assignments is not (yet) supported")?,
                 };
                 (
                     track.clone(),
-                    TrackRef {
-                        layer: range.0.layer,
-                        track: ortho_track,
-                    },
+                    TrackRef::new(range.0.layer, ortho_track),
                 )
             }
-            _ => unimplemented!(),
+            _ => self.fail(format!("Assignment relative to Port location {:?} is not (yet) supported", port_loc))?,
         };
 
         // Sort out separation (in tracks) in (x, y)
         let _sep_x = match &rel.sep.x {
-            Some(_) => unimplemented!(),
+            Some(_) => self.fail("Assignment x-separation is not (yet) supported")?,
             None => 0_usize,
         };
         let _sep_y = match &rel.sep.y {
-            Some(_) => unimplemented!(),
+            Some(_) => self.fail("Assignment y-separation is not (yet) supported")?,
             None => 0_usize,
         };
         // Sort out the layer-based z-separation
@@ -368,13 +361,13 @@ impl Placer {
             Some(i) => *i,
             None => 0,
         };
-        let newlayer = isize::try_from(ref_cross.0.layer)? + sep_z;
+        let newlayer = isize::try_from(ref_cross.0.layer.index())? + sep_z;
         let newlayer = usize::try_from(newlayer)?;
 
         // Of the two layers in `ref_cross`, one will be in parallel with `newlayer`, and one will be orthogonal to it.
         // Set `par` as the parallel track, and `cross` as the orthogonal track.
         let (par, cross) = {
-            let newlayer_dir = self.stack.metal(newlayer)?.spec.dir;
+            let newlayer_dir = self.stack.metal(LayerIndex(newlayer))?.spec.dir;
             if newlayer_dir == self.stack.metal(ref_cross.0.layer)?.spec.dir {
                 (ref_cross.0, ref_cross.1)
             } else if newlayer_dir == self.stack.metal(ref_cross.1.layer)?.spec.dir {
@@ -390,98 +383,12 @@ impl Placer {
         let rv = TrackCross::new(new_track, cross);
         Ok(rv)
     }
-    /// Resolve a location of [Instance] `inst` relative to its [RelativePlace] `rel`.
+    /// Resolve the parent-frame location of port `portname` on [Instance] `inst`.
     fn locate_instance_port(&mut self, inst: &Instance, portname: &str) -> LayoutResult<PortLoc> {
-        // Port locations are only valid for Cells with Abs definitions. Otherwise fail.
-        let cell = inst.cell.read()?; // Note `cell` is alive for the duration of this function
-        let abs = self.unwrap(
-            cell.abs.as_ref(),
-            format!(
-                "Cannot Location Port {} on Cell {} with no Abs View",
-                portname, cell.name
-            ),
-        )?;
-        // Get the Port-object, or fail
-        let port = self.unwrap(
-            abs.port(portname),
-            format!("Cell {} has no Port {}", cell.name, portname),
-        )?;
-
-        let loc = match &port.kind {
-            abs::PortKind::Edge { .. /*layer, track, side*/ } => unimplemented!(),
-            abs::PortKind::ZTopEdge { track, side, into } => {
-                let top_metal = self.unwrap(cell.top_metal()?, "No metal layers")?;
-                let (dir, nsignals) = {
-                    // Get relevant data from our [Layer], and quickly drop a reference to it.
-                    let layer = self.stack.metal(top_metal)?;
-                    (layer.spec.dir, layer.period_data.signals.len())
-                };
-                let port_track = {
-                    let layer_pitches =
-                        self.layer_pitches(top_metal, inst.loc.abs()?[!dir].into())?;
-
-                    // Get the port's track-index, combining in the instance-location
-                    let (_, period_tracks) = (layer_pitches * nsignals).into_inner();
-                    let period_tracks = usize::try_from(period_tracks)?;
-                    if inst.reflected(!dir) {
-                        period_tracks - track - 1
-                    } else {
-                        period_tracks + track 
-                    }
-                };
-
-                // Sort out the orthogonal-axis range.
-                let ortho_layer = match into.1 {
-                    stack::RelZ::Above => top_metal + 1,
-                    stack::RelZ::Below => top_metal - 1,
-                };
-                let ortho_range = {
-                    let layer = &self.stack.metal(ortho_layer)?;
-                    let nsignals = layer.period_data.signals.len();
-
-                    // Find the origin and size in `ortho_layer` tracks
-                    // FIXME: probably make this a method, and/or a track-index `HasUnits`
-                    let loc = inst.loc.abs()?[dir];
-                    let loc = self.layer_pitches(ortho_layer, loc.into())?;
-                    let (_, loc) = (loc * nsignals).into_inner();
-                    let loc = usize::try_from(loc)?;
-                    let size = inst.boundbox_size()?[dir];
-                    let size = self.layer_pitches(ortho_layer, size.into())?;
-                    let (_, size) = (size * nsignals).into_inner();
-                    let size = usize::try_from(size)?;
-
-                    // Pull out the number of tracks in the orthogonal-axis
-                    let into = into.0;
-                    // And sort out the orthogonal range, based on location, size, and `into` tracks 
-                    match (side, inst.reflected(dir)) {
-                        (abs::Side::BottomOrLeft, false)=> (loc, loc+into),
-                        (abs::Side::BottomOrLeft, true)=> (loc-into, loc),
-                        (abs::Side::TopOrRight, false)=> (loc+into, loc+size),
-                        (abs::Side::TopOrRight, true)=> (loc-size, loc-into),
-                    }
-                };
-
-                // From all that, create a [PortLoc]
-                PortLoc::ZTopEdge {
-                    track: TrackRef {
-                        layer: top_metal,
-                        track: port_track,
-                    },
-                    range: (
-                        TrackRef {
-                            layer: ortho_layer,
-                            track: ortho_range.0,
-                        },
-                        TrackRef {
-                            layer: ortho_layer,
-                            track: ortho_range.1
-                        },
-                    ),
-                }
-            }
-            abs::PortKind::ZTopInner { .. } => unimplemented!(),
-        };
-        Ok(loc)
+        match inst.port_loc(portname, &self.stack) {
+            Ok(loc) => Ok(loc),
+            Err(e) => self.fail(e.to_string()),
+        }
     }
     /// Convert a [TrackRef] to the closest track on another same-direction layer `to_layer`.
     fn convert_track_layer(
@@ -489,12 +396,12 @@ impl Placer {
         trackref: &TrackRef,
         to_layer: usize,
     ) -> LayoutResult<TrackRef> {
-        if trackref.layer == to_layer {
+        if trackref.layer.index() == to_layer {
             // Same layer, no conversion needed
             return Ok(trackref.clone());
         }
         let track_layer_dir = &self.stack.metal(trackref.layer)?.spec.dir;
-        let to_layer_dir = &self.stack.metal(to_layer)?.spec.dir;
+        let to_layer_dir = &self.stack.metal(LayerIndex(to_layer))?.spec.dir;
         if track_layer_dir != to_layer_dir {
             // Orthogonal layers. Fail.
             self.fail(format!(
@@ -506,62 +413,11 @@ impl Placer {
         // First find the starting-track's center in [DbUnits]
         let track_center = self.stack.metal(trackref.layer)?.center(trackref.track)?;
         // And get the corresponding track-index on the other layer
-        let to_layer_index = self.stack.metal(to_layer)?.track_index(track_center)?;
-        Ok(TrackRef {
-            layer: to_layer,
-            track: to_layer_index,
-        })
-    }
-    /// Convert a [UnitSpeced] distance `dist` into [LayerPitches] on layer `layer_index`.
-    /// Fails if `dist` is not an integer multiple of the pitch of `layer_index`.
-    fn layer_pitches(
-        &mut self,
-        layer_index: usize,
-        dist: UnitSpeced,
-    ) -> LayoutResult<LayerPitches> {
-        let layer = &self.stack.metal(layer_index)?;
-        let layer_pitch = layer.pitch;
-        let num = match dist {
-            UnitSpeced::DbUnits(_) => unimplemented!(),
-            UnitSpeced::LayerPitches(_) => unimplemented!(),
-            UnitSpeced::PrimPitches(p) => {
-                let dir = layer.spec.dir;
-                let prim_pitch = self.stack.prim.pitches[dir.other()];
-                if layer_pitch % prim_pitch != 0 {
-                    self.fail(format!(
-                        "Invalid Conversion: Primitive (pitch={:?}) to Layer {} (pitch={:?})",
-                        prim_pitch, layer_index, layer_pitch
-                    ))?;
-                }
-                p.num * (layer_pitch / prim_pitch)
-            }
-        };
-        Ok(LayerPitches::new(layer_index, num))
+        let to_layer_index = self.stack.metal(LayerIndex(to_layer))?.track_index(track_center)?;
+        Ok(TrackRef::new(LayerIndex(to_layer), TrackIndex(to_layer_index)))
     }
 }
 
-/// Resolved Locations corresponding to [abs::PortKind]s
-#[derive(Debug, Clone)]
-pub enum PortLoc {
-    Edge {
-        /// Port Track
-        track: TrackRef,
-        /// Crossing Track
-        at: TrackRef,
-    },
-    ZTopEdge {
-        /// Port Track
-        track: TrackRef,
-        /// Extent on an adjacent layer
-        range: (TrackRef, TrackRef),
-    },
-    ZTopInner {
-        /// Locations
-        locs: Vec<Tbd>,
-    },
-}
-#[derive(Debug, Clone)]
-pub struct Tbd;
 impl ErrorHelper for Placer {
     type Error = LayoutError;
     fn err(&self, msg: impl Into<String>) -> LayoutError {
@@ -618,6 +474,7 @@ impl DepOrder for PlaceOrder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::abs;
     use crate::outline::Outline;
     use crate::cell::Cell;
     use crate::placement::{Place, Placeable, RelAssign, RelativePlace, SepBy, Separation, Side};
@@ -665,7 +522,7 @@ mod tests {
         let parent = lib.cells.add(parent);
 
         // The real code-under-test: run placement
-        let (lib, stack) = Placer::place(lib, SampleStacks::empty()?)?;
+        let (lib, _stack) = Placer::place(lib, SampleStacks::empty()?)?;
 
         // Checks on results
         assert_eq!(lib.cells.len(), 2);
@@ -681,7 +538,8 @@ mod tests {
             let inst = i1.read()?;
             assert_eq!(*inst.loc.abs()?, Xy::<PrimPitches>::from((50, 51)));
         }
-        exports(lib, stack)
+        // Export requires metal layers, which the empty stack lacks
+        exports(lib, SampleStacks::pdka()?)
     }
     #[test]
     fn test_place3() -> LayoutResult<()> {
@@ -941,10 +799,8 @@ mod tests {
             assert_eq!(parent_layout.assignments.len(), 1);
             let assn = &parent_layout.assignments[0];
             assert_eq!(assn.net, "NETPPP");
-            assert_eq!(assn.at.track.layer, 2);
-            assert_eq!(assn.at.track.track, 0);
-            assert_eq!(assn.at.cross.layer, 1);
-            assert_eq!(assn.at.cross.track, 1);
+            assert_eq!(assn.at.track, TrackRef::new(LayerIndex(2), TrackIndex(0)));
+            assert_eq!(assn.at.cross, TrackRef::new(LayerIndex(1), TrackIndex(1)));
         }
         exports(lib, stack)
     }
@@ -978,14 +834,13 @@ mod tests {
             let mut lil = Cell::new("lil");
             lil.layout = Some(Layout::new("lil", 1, Outline::rect(2, 1)?));
             let mut lil_abs = abs::Abstract::new("lil", 1, Outline::rect(2, 1)?);
-            lil_abs.ports.push(abs::Port {
-                name: "PPP".into(),
-                kind: abs::PortKind::ZTopEdge {
-                    track: 0,
-                    side: abs::Side::BottomOrLeft,
-                    into: (2, stack::RelZ::Above),
-                },
-            });
+            lil_abs.ports.push(abs::Port::ztop_edge(
+                "PPP",
+                TrackIndex(0),
+                abs::Side::BottomOrLeft,
+                TrackIndex(2),
+                stack::RelZ::Above,
+            ));
             lil.abs = Some(lil_abs);
             let lil = lib.cells.add(lil);
             Ok(SampleLib {
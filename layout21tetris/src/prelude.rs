@@ -0,0 +1,109 @@
+//!
+//! # Prelude
+//!
+//! Re-exports of the commonly-used data-model types and builders,
+//! for glob-importing via `use layout21tetris::prelude::*`.
+//!
+//! Includes the [gds21] types returned by GDSII export, with the `gds` feature,
+//! so that downstream crates need not depend on `gds21` directly.
+//!
+//! ## Example
+//!
+//! Create a [Stack], a [Cell] using it, and export the two to [raw] and GDSII:
+//!
+//! ```
+//! use layout21tetris::prelude::*;
+//!
+//! # fn main() -> LayoutResult<()> {
+//! // Create a two-metal stack
+//! let mut rawlayers = raw::Layers::default();
+//! let purps = [(20, raw::LayerPurpose::Drawing), (5, raw::LayerPurpose::Label)];
+//! let boundary = rawlayers.add(raw::Layer::from_pairs(0, &[(0, raw::LayerPurpose::Outline)])?);
+//! let metal = |name: &str, dir: Dir, raw: raw::LayerKey| MetalLayer {
+//!     name: name.into(),
+//!     entries: vec![TrackSpec::sig(50), TrackSpec::gap(50)],
+//!     dir,
+//!     offset: (-25).into(),
+//!     cutsize: (20).into(),
+//!     overlap: (0).into(),
+//!     raw: Some(raw),
+//!     flip: FlipMode::None,
+//!     prim: PrimitiveMode::Stack,
+//!     min_length: None,
+//!     colors: 1,
+//!     color_datatypes: Vec::new(),
+//! };
+//! let metals = vec![
+//!     metal("met1", Dir::Horiz, rawlayers.add(raw::Layer::from_pairs(1, &purps)?)),
+//!     metal("met2", Dir::Vert, rawlayers.add(raw::Layer::from_pairs(2, &purps)?)),
+//! ];
+//! let via1 = ViaLayer {
+//!     name: "via1".into(),
+//!     size: (20, 20).into(),
+//!     bot: 0.into(),
+//!     top: 1.into(),
+//!     raw: Some(rawlayers.add(raw::Layer::from_pairs(3, &purps)?)),
+//! };
+//! let stack = Stack {
+//!     schema: STACK_SCHEMA,
+//!     units: raw::Units::default(),
+//!     boundary_layer: Some(boundary),
+//!     prim: PrimitiveLayer::new((100, 100).into()),
+//!     metals,
+//!     vias: vec![via1],
+//!     rawlayers: Some(Ptr::new(rawlayers)),
+//!     nets: Default::default(),
+//!     site: None,
+//! };
+//! let stack = stack.validate()?;
+//!
+//! // Create a cell with a single net-assignment and cut
+//! let cell = Cell::builder("example")
+//!     .top_layer(LayerIndex(1))
+//!     .outline(Outline::rect(4, 4)?)
+//!     .assign("a", LayerIndex(0), TrackIndex(1), TrackIndex(1), RelZ::Above)
+//!     .cut(LayerIndex(0), TrackIndex(1), TrackIndex(2), RelZ::Above)
+//!     .build()?;
+//! let mut lib = Library::new("example_lib");
+//! lib.cells.insert(cell);
+//!
+//! // Export to raw, and then to GDSII
+//! let rawlib = lib.to_raw(stack)?;
+//! # #[cfg(feature = "gds")]
+//! # {
+//! let gds: GdsLibrary = rawlib.read()?.to_gds()?;
+//! assert_eq!(gds.structs.len(), 1);
+//! # }
+//! # Ok(())
+//! # }
+//! ```
+//!
+
+// Data model
+pub use crate::abs::{Abstract, Port, PortKind};
+pub use crate::cell::{Cell, CellBuilder};
+pub use crate::coords::{DbUnits, HasUnits, LayerIndex, PrimPitches, SignedTrack, TrackIndex, Xy};
+pub use crate::instance::{Instance, InstanceBuilder, PortLoc};
+pub use crate::layout::Layout;
+pub use crate::library::{CellRef, Library};
+pub use crate::outline::Outline;
+pub use crate::placement::{Place, Placeable};
+
+// Stacks and tracks
+pub use crate::stack::{
+    Assign, FlipMode, MetalLayer, PrimitiveLayer, PrimitiveMode, RelZ, Stack, ViaLayer,
+    ViaLayerRef, ViaLoc, ViaPlacement, ViaPolicy, ViaTarget, STACK_SCHEMA,
+};
+pub use crate::tracks::{RailKind, TrackCross, TrackEntry, TrackRef, TrackSpec};
+pub use crate::validate::ValidStack;
+
+// Raw conversion
+pub use crate::conv::raw::{ConversionReport, RawExportOptions, RawExporter};
+pub use crate::raw::{self, Dir, LayoutError, LayoutResult};
+pub use crate::utils::{Ptr, PtrList};
+
+// GDSII types, as returned by raw export
+#[cfg(feature = "gds")]
+pub use crate::raw::gds::gds21;
+#[cfg(feature = "gds")]
+pub use crate::raw::gds::gds21::{GdsLibrary, GdsStruct};
@@ -0,0 +1,117 @@
+//!
+//! # Post-Route Track Database
+//!
+//! Persistent records of the final state of each [Track] in a [Layout],
+//! as produced during raw conversion.
+//! Includes each track's segments, their assigned nets, and their cut locations,
+//! and can be re-applied onto a [Cell] to regenerate its assignments and cuts.
+//!
+
+// Crates.io
+use serde::{Deserialize, Serialize};
+
+// Local imports
+use crate::cell::Cell;
+use crate::layout::Layout;
+use crate::raw::{LayoutError, LayoutResult};
+use crate::stack::{Assign, LayerPeriod};
+use crate::tracks::{TrackCross, TrackSegmentType};
+#[cfg(feature = "yaml")]
+use crate::utils::SerdeFile;
+
+/// # Cell Routing Database
+///
+/// The post-conversion state of every [Track] in a [Layout], arranged by layer.
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CellRouteDb {
+    /// Cell Name
+    pub name: String,
+    /// Per-Layer Routes, ordered from the bottom layer up
+    pub layers: Vec<LayerRoutes>,
+}
+/// # Routes on a Single Layer
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LayerRoutes {
+    /// Metal-Layer Index
+    pub index: usize,
+    /// Final state of each [LayerPeriod], in periodic order
+    pub periods: Vec<LayerPeriod>,
+    /// Assignments for which this is the lower of the two layers, i.e. those which create vias from it.
+    /// Stored in conversion order, as they are not necessarily reflected in `periods`,
+    /// e.g. when landing on an instance blockage.
+    pub vias: Vec<Assign>,
+}
+impl LayerRoutes {
+    /// Create a new and initially empty [LayerRoutes] for metal-layer `index`
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            ..Default::default()
+        }
+    }
+    /// Get all [TrackCross] cut-locations on this layer, in positional order
+    pub fn cuts(&self) -> Vec<TrackCross> {
+        let mut cuts = Vec::new();
+        for period in self.periods.iter() {
+            for track in period.signals.iter() {
+                for seg in track.segments.iter() {
+                    if let TrackSegmentType::Cut { src } = seg.tp {
+                        cuts.push(src);
+                    }
+                }
+            }
+        }
+        cuts
+    }
+}
+impl CellRouteDb {
+    /// Create a new and initially empty [CellRouteDb]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+    /// Get all net [Assign]ments, layer by layer
+    pub fn assignments(&self) -> Vec<Assign> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.vias.iter().cloned())
+            .collect()
+    }
+    /// Get all [TrackCross] cut-locations, layer by layer
+    pub fn cuts(&self) -> Vec<TrackCross> {
+        self.layers.iter().flat_map(|layer| layer.cuts()).collect()
+    }
+    /// Re-apply our assignments and cuts onto [Cell] `cell`.
+    /// Replaces any existing assignments and cuts in its [Layout].
+    /// Fails if `cell` has no [Layout], or if it has fewer metal layers than we do.
+    pub fn apply(&self, cell: &mut Cell) -> LayoutResult<()> {
+        let layout = match cell.layout {
+            Some(ref mut layout) => layout,
+            None => {
+                return LayoutError::fail(format!(
+                    "Cannot apply routes for {} to Cell {} without Layout",
+                    self.name, cell.name
+                ))
+            }
+        };
+        self.apply_layout(layout)
+    }
+    /// Re-apply our assignments and cuts onto [Layout] `layout`.
+    /// Replaces any existing assignments and cuts.
+    pub fn apply_layout(&self, layout: &mut Layout) -> LayoutResult<()> {
+        if let Some(layer) = self.layers.iter().find(|l| l.index >= layout.metals) {
+            return LayoutError::fail(format!(
+                "Cannot apply routes on layer {} to Layout {} with {} metal layers",
+                layer.index, layout.name, layout.metals
+            ));
+        }
+        layout.assignments = self.assignments();
+        layout.cuts = self.cuts();
+        Ok(())
+    }
+}
+#[cfg(feature = "yaml")]
+impl SerdeFile for CellRouteDb {}
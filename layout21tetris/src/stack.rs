@@ -1,39 +1,154 @@
 // Std-lib imports
 use std::fmt::Debug;
+use std::ops::Range;
 
 // Crates.io
 use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::coords::{DbUnits, Xy};
-use crate::instance::Instance;
-use crate::raw::{self, Dir, LayoutResult, Units};
-use crate::utils::Ptr;
+use crate::coords::{DbUnits, LayerIndex, Xy};
+use crate::net::{NetName, NetNamePolicy};
+use crate::raw::{self, Dir, LayoutError, LayoutResult, Units};
+#[cfg(feature = "yaml")]
+use crate::utils::{self, Migration};
+use crate::utils::{Ptr, SerializationFormat};
 use crate::{tracks::*, validate};
 
+/// Current [Stack] serialization schema version.
+///
+/// Versions:
+/// * 0: Files written before schema versioning, which lack a `schema` field. Otherwise identical to 1.
+/// * 1: Adds the `schema` field.
+///
+pub const STACK_SCHEMA: u32 = 1;
+
+/// [Migration]s between each prior [Stack] schema version and [STACK_SCHEMA], in order.
+/// Schema 0 => 1 only adds the `schema` field, which [utils::migrate] sets after each step.
+#[cfg(feature = "yaml")]
+pub const STACK_MIGRATIONS: &[Migration] = &[Migration::new(0, 1, |_| Ok(()))];
+
 /// # Stack
 ///
 /// The z-stack, primarily including metal, via, and primitive layers
-#[derive(Debug, Clone)]
+///
+/// Serializable, generally to YAML, via [Stack::save] and [Stack::open].
+/// Optional fields may be omitted from serialized files, and take their default values.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stack {
+    /// Serialization schema version, generally [STACK_SCHEMA].
+    /// Files which lack it are loaded as schema 0.
+    #[serde(default)]
+    pub schema: u32,
     /// Measurement units
+    #[serde(default)]
     pub units: Units,
     /// Primitive Layer
     pub prim: PrimitiveLayer,
+    /// Placement site, i.e. the quantum to which cell outlines and instance locations snap.
+    /// Must be an integer multiple of the primitive pitches. Defaults to the primitive pitches.
+    /// Conversion geometry continues to use the track periods of each layer.
+    #[serde(default)]
+    pub site: Option<Xy<DbUnits>>,
     /// Set of metal layers
     pub metals: Vec<MetalLayer>,
     /// Set of via layers
+    #[serde(default)]
     pub vias: Vec<ViaLayer>,
     /// [raw::Layer] Mappings
+    #[serde(default)]
     pub rawlayers: Option<Ptr<raw::Layers>>,
     /// Layer used for cell outlines/ boundaries
+    #[serde(default)]
     pub boundary_layer: Option<raw::LayerKey>,
+    /// Net-name validation policy
+    #[serde(default)]
+    pub nets: NetNamePolicy,
 }
 impl Stack {
+    /// Load a [Stack] from `fmt`-format file `fname`, migrating it to [STACK_SCHEMA] if necessary.
+    #[cfg(feature = "yaml")]
+    pub fn open(
+        fname: impl AsRef<std::path::Path>,
+        fmt: SerializationFormat,
+    ) -> LayoutResult<Self> {
+        let stack: Self = fmt.open(fname)?;
+        stack.migrate()
+    }
+    /// Parse a [Stack] from `fmt`-format string `txt`, migrating it to [STACK_SCHEMA] if necessary.
+    pub fn parse(txt: &str, fmt: SerializationFormat) -> LayoutResult<Self> {
+        let stack: Self = fmt.from_str(txt)?;
+        stack.migrate()
+    }
+    /// Load a [Stack] from YAML (or JSON) file `fname`,
+    /// applying `migrations` to the untyped document before deserializing it.
+    /// Generally called with [STACK_MIGRATIONS], followed by any application-specific [Migration]s.
+    #[cfg(feature = "yaml")]
+    pub fn open_with_migrations(
+        fname: impl AsRef<std::path::Path>,
+        migrations: &[Migration],
+    ) -> LayoutResult<Self> {
+        let stack: Self = utils::open_with_migrations(fname, migrations)?;
+        stack.migrate()
+    }
+    /// Save to `fmt`-format file `fname`
+    #[cfg(feature = "yaml")]
+    pub fn save(
+        &self,
+        fname: impl AsRef<std::path::Path>,
+        fmt: SerializationFormat,
+    ) -> LayoutResult<()> {
+        Ok(fmt.save(self, fname)?)
+    }
+    /// Migrate from an earlier schema version to [STACK_SCHEMA].
+    /// Fails for schemas newer than [STACK_SCHEMA].
+    pub fn migrate(mut self) -> LayoutResult<Self> {
+        if self.schema > STACK_SCHEMA {
+            return LayoutError::fail(format!(
+                "Stack file schema {}, crate supports up to {}",
+                self.schema, STACK_SCHEMA
+            ));
+        }
+        // Schema 0 => 1: no content changes
+        self.schema = STACK_SCHEMA;
+        Ok(self)
+    }
     /// Run validation, consuming `self` and creating a [validate::ValidStack]
     pub fn validate(self) -> LayoutResult<validate::ValidStack> {
         validate::validate_stack(self)
     }
+    /// Apply [raw::LayerMap] `map`, setting the `raw` layer of each metal and via layer by name.
+    ///
+    /// Layers are added to `rawlayers`, which is created if not already present, per [raw::LayerMap::add_to].
+    /// Existing layers of the same name are re-used, so applying a map repeatedly adds no duplicates.
+    /// Layers in `map` which are not in the stack, e.g. primitive base-layers, are also added.
+    /// Fails if any stack layer is not present in `map`, listing the names missing from each side.
+    pub fn apply_layermap(&mut self, map: &raw::LayerMap) -> LayoutResult<()> {
+        let names = self
+            .metals
+            .iter()
+            .map(|m| m.name.as_str())
+            .chain(self.vias.iter().map(|v| v.name.as_str()));
+        let diff = map.compare(names);
+        if !diff.is_complete() {
+            return Err(raw::LayoutError::msg(format!(
+                "Stack layers missing from layer-map: {:?}. Layer-map layers not in stack: {:?}",
+                diff.missing, diff.unused
+            )));
+        }
+        let rawlayers = self
+            .rawlayers
+            .get_or_insert_with(|| Ptr::new(raw::Layers::default()));
+        let mut rawlayers = rawlayers.write()?;
+        map.add_to(&mut rawlayers)?;
+        for metal in self.metals.iter_mut() {
+            metal.raw = rawlayers.keyname(&metal.name);
+        }
+        for via in self.vias.iter_mut() {
+            via.raw = rawlayers.keyname(&via.name);
+        }
+        Ok(())
+    }
 }
 /// # MetalLayer
 ///
@@ -52,15 +167,165 @@ pub struct MetalLayer {
     /// Track Size & Type Entries
     pub entries: Vec<TrackSpec>,
     /// Offset, in our periodic dimension
-    pub offset: DbUnits,
+    #[serde(default)]
+    pub offset: LayerOffset,
     /// Overlap between periods
+    #[serde(default)]
     pub overlap: DbUnits,
     /// Setting for period-by-period flipping
+    #[serde(default)]
     pub flip: FlipMode,
     /// Primitive-layer relationship
+    #[serde(default)]
     pub prim: PrimitiveMode,
     /// [raw::Layer] for exports
+    #[serde(default)]
     pub raw: Option<raw::LayerKey>,
+    /// Minimum length of wire and rail segments, e.g. those left between nearby cuts and blockages.
+    /// Enforced during raw conversion, per [RawExportOptions::min_length](crate::conv::raw::RawExportOptions::min_length).
+    #[serde(default)]
+    pub min_length: Option<DbUnits>,
+    /// Number of masks for multi-patterning, among which [TrackEntry::color]s are assigned.
+    /// Defaults to one, i.e. single patterning.
+    #[serde(default = "MetalLayer::default_colors")]
+    pub colors: usize,
+    /// Datatype of each mask color, indexed by color, on which signal tracks are drawn.
+    /// Each must be defined on the layer's `raw` layer.
+    /// Empty by default, drawing all tracks on the `raw` layer's drawing purpose.
+    #[serde(default)]
+    pub color_datatypes: Vec<i16>,
+}
+/// # Track Plan
+///
+/// The tracks of a single period of a [MetalLayer], for inspection of [Stack] definitions.
+/// Created by [MetalLayer::track_plan], and printable via [Display](std::fmt::Display).
+/// Includes warnings of any suspicious entries, e.g. zero-width entries or signals wider than rails.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackPlan {
+    /// Layer Name
+    pub layer: String,
+    /// Layer Direction
+    pub dir: Dir,
+    /// Each entry, including gaps, in periodic order
+    pub entries: Vec<PlannedEntry>,
+    /// Period Pitch
+    pub pitch: DbUnits,
+    /// Number of Signal Tracks
+    pub nsignals: usize,
+    /// Number of Rail Tracks
+    pub nrails: usize,
+    /// Suspicious-entry warnings
+    pub warnings: Vec<String>,
+}
+/// # Track-Plan Entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedEntry {
+    /// Index among the period's signals or rails. `None` for gaps.
+    pub index: Option<usize>,
+    /// Track Type
+    pub ttype: TrackType,
+    /// Starting location, in the periodic dimension
+    pub start: DbUnits,
+    /// Width
+    pub width: DbUnits,
+    /// Center location, in the periodic dimension
+    pub center: DbUnits,
+}
+impl std::fmt::Display for TrackPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Layer {} ({:?}): pitch {}, {} signals, {} rails",
+            self.layer, self.dir, self.pitch.0, self.nsignals, self.nrails
+        )?;
+        for e in self.entries.iter() {
+            let index = match e.index {
+                Some(i) => i.to_string(),
+                None => "-".into(),
+            };
+            writeln!(
+                f,
+                "  {:<6} {:>3}  start {:>6}  width {:>6}  center {:>6}",
+                e.ttype.to_string(),
+                index,
+                e.start.0,
+                e.width.0,
+                e.center.0
+            )?;
+        }
+        for w in self.warnings.iter() {
+            writeln!(f, "  Warning: {}", w)?;
+        }
+        Ok(())
+    }
+}
+
+/// # Metal-Layer Offset
+///
+/// Specification of a [MetalLayer]'s `offset`, in its periodic dimension.
+/// Resolved to [DbUnits] by [MetalLayer::offset_units], and during [Stack] validation.
+///
+/// Serialized [LayerOffset::Units] are bare integers, as for layers written before the alternatives were added.
+/// The others are written as e.g. `CenterRailOnOrigin` or `{Tracks: 2}`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "LayerOffsetRepr", into = "LayerOffsetRepr")]
+pub enum LayerOffset {
+    /// Offset in [DbUnits]
+    Units(DbUnits),
+    /// Center the layer's first entry, which must be a rail, on the origin
+    CenterRailOnOrigin,
+    /// Place the start of entry number `n` on the origin
+    Tracks(isize),
+}
+impl Default for LayerOffset {
+    fn default() -> Self {
+        Self::Units(DbUnits(0))
+    }
+}
+impl From<isize> for LayerOffset {
+    fn from(n: isize) -> Self {
+        Self::Units(DbUnits(n))
+    }
+}
+impl From<DbUnits> for LayerOffset {
+    fn from(d: DbUnits) -> Self {
+        Self::Units(d)
+    }
+}
+/// Serialized form of [LayerOffset]; [LayerOffset::Units] are bare integers.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum LayerOffsetRepr {
+    Units(DbUnits),
+    Other(LayerOffsetOther),
+}
+/// Serialized form of non-[LayerOffset::Units] offsets
+#[derive(Clone, Serialize, Deserialize)]
+enum LayerOffsetOther {
+    CenterRailOnOrigin,
+    Tracks(isize),
+}
+impl From<LayerOffsetRepr> for LayerOffset {
+    fn from(r: LayerOffsetRepr) -> Self {
+        match r {
+            LayerOffsetRepr::Units(d) => Self::Units(d),
+            LayerOffsetRepr::Other(LayerOffsetOther::CenterRailOnOrigin) => {
+                Self::CenterRailOnOrigin
+            }
+            LayerOffsetRepr::Other(LayerOffsetOther::Tracks(n)) => Self::Tracks(n),
+        }
+    }
+}
+impl From<LayerOffset> for LayerOffsetRepr {
+    fn from(o: LayerOffset) -> Self {
+        match o {
+            LayerOffset::Units(d) => Self::Units(d),
+            LayerOffset::CenterRailOnOrigin => Self::Other(LayerOffsetOther::CenterRailOnOrigin),
+            LayerOffset::Tracks(n) => Self::Other(LayerOffsetOther::Tracks(n)),
+        }
+    }
 }
 #[derive(Debug, Clone, Default)]
 pub struct LayerPeriodData {
@@ -68,10 +333,14 @@ pub struct LayerPeriodData {
     pub rails: Vec<TrackData>,
 }
 impl MetalLayer {
+    /// Default number of mask colors, i.e. single patterning
+    fn default_colors() -> usize {
+        1
+    }
     /// Convert this [Layer]'s track-info into a [LayerPeriodData]
     pub(crate) fn to_layer_period_data(&self) -> LayoutResult<LayerPeriodData> {
         let mut period = LayerPeriodData::default();
-        let mut cursor = self.offset;
+        let mut cursor = self.offset_units()?;
         for e in &self.entries() {
             let d = e.width;
             match e.ttype {
@@ -83,6 +352,7 @@ impl MetalLayer {
                         dir: self.dir,
                         start: cursor,
                         width: d,
+                        color: e.color,
                     });
                 }
                 TrackType::Signal => {
@@ -92,6 +362,7 @@ impl MetalLayer {
                         dir: self.dir,
                         start: cursor,
                         width: d,
+                        color: e.color,
                     });
                 }
             };
@@ -100,15 +371,15 @@ impl MetalLayer {
         Ok(period)
     }
     /// Convert this [Layer]'s track-info into a [LayerPeriod]
-    pub(crate) fn to_layer_period<'me, 'lib>(
-        &'me self,
+    pub fn to_layer_period(
+        &self,
         index: usize,
         stop: impl Into<DbUnits>,
-    ) -> LayoutResult<LayerPeriod<'lib>> {
+    ) -> LayoutResult<LayerPeriod> {
         let stop = stop.into();
         let mut period = LayerPeriod::default();
         period.index = index;
-        let mut cursor = self.offset + (self.pitch() * index);
+        let mut cursor = self.offset_units()? + (self.pitch() * index);
         let entries = self.entries();
         let iterator: Box<dyn Iterator<Item = _>> =
             if self.flip == FlipMode::EveryOther && index % 2 == 1 {
@@ -129,11 +400,13 @@ impl MetalLayer {
                                 dir: self.dir,
                                 start: cursor,
                                 width: d,
+                                color: e.color,
                             },
                             segments: vec![TrackSegment {
                                 tp: TrackSegmentType::Rail(railkind),
                                 start: 0.into(),
                                 stop,
+                                labels: Vec::new(),
                             }],
                         }
                         .validate()?,
@@ -148,11 +421,13 @@ impl MetalLayer {
                                 dir: self.dir,
                                 start: cursor,
                                 width: d,
+                                color: e.color,
                             },
                             segments: vec![TrackSegment {
                                 tp: TrackSegmentType::Wire { src: None },
                                 start: 0.into(),
                                 stop,
+                                labels: Vec::new(),
                             }],
                         }
                         .validate()?,
@@ -182,8 +457,110 @@ impl MetalLayer {
         }
         v
     }
-    /// Sum up this [Layer]'s pitch
-    pub(crate) fn pitch(&self) -> DbUnits {
+    /// Iterate over our flattened [Entry]s, as in [MetalLayer::entries], without collecting them
+    pub(crate) fn entries_iter(&self) -> impl DoubleEndedIterator<Item = &TrackEntry> {
+        self.entries.iter().flat_map(|e| {
+            let (entries, nrep) = match e {
+                TrackSpec::Entry(ee) => (std::slice::from_ref(ee), 1),
+                TrackSpec::Repeat(p) => (&p.entries[..], p.nrep),
+            };
+            (0..nrep).flat_map(move |_| entries.iter())
+        })
+    }
+    /// Resolve our [LayerOffset] to [DbUnits]
+    pub fn offset_units(&self) -> LayoutResult<DbUnits> {
+        match self.offset {
+            LayerOffset::Units(d) => Ok(d),
+            LayerOffset::CenterRailOnOrigin => match self.entries().first() {
+                Some(e) if matches!(e.ttype, TrackType::Rail(_)) => Ok(DbUnits(-e.width.0 / 2)),
+                _ => LayoutError::fail(format!(
+                    "Invalid offset CenterRailOnOrigin for layer {}, which does not start with a rail",
+                    self.name
+                )),
+            },
+            LayerOffset::Tracks(n) => {
+                let entries = self.entries();
+                if n < 0 || n as usize > entries.len() {
+                    return LayoutError::fail(format!(
+                        "Invalid offset of {} tracks for layer {} with {} entries",
+                        n,
+                        self.name,
+                        entries.len()
+                    ));
+                }
+                let width: DbUnits = entries[..n as usize].iter().map(|e| e.width).sum();
+                Ok(DbUnits(-width.0))
+            }
+        }
+    }
+    /// Create the [TrackPlan] of a single period of this layer
+    pub fn track_plan(&self) -> LayoutResult<TrackPlan> {
+        let mut plan = TrackPlan {
+            layer: self.name.clone(),
+            dir: self.dir,
+            pitch: self.pitch(),
+            entries: Vec::new(),
+            nsignals: 0,
+            nrails: 0,
+            warnings: Vec::new(),
+        };
+        let mut cursor = self.offset_units()?;
+        for e in self.entries() {
+            let index = match e.ttype {
+                TrackType::Gap => None,
+                TrackType::Signal => {
+                    plan.nsignals += 1;
+                    Some(plan.nsignals - 1)
+                }
+                TrackType::Rail(_) => {
+                    plan.nrails += 1;
+                    Some(plan.nrails - 1)
+                }
+            };
+            plan.entries.push(PlannedEntry {
+                index,
+                ttype: e.ttype,
+                start: cursor,
+                width: e.width,
+                center: cursor + DbUnits(e.width.0 / 2),
+            });
+            cursor += e.width;
+        }
+        // Flag anything suspicious
+        for (idx, e) in plan.entries.iter().enumerate() {
+            if e.width.0 <= 0 {
+                plan.warnings.push(format!(
+                    "Entry {} ({}) has non-positive width {}",
+                    idx, e.ttype, e.width.0
+                ));
+            }
+        }
+        let widths = |signal: bool| {
+            plan.entries.iter().filter(move |e| match e.ttype {
+                TrackType::Signal => signal,
+                TrackType::Rail(_) => !signal,
+                TrackType::Gap => false,
+            })
+        };
+        let widest_signal = widths(true).map(|e| e.width).max();
+        let narrowest_rail = widths(false).map(|e| e.width).min();
+        if let (Some(sig), Some(rail)) = (widest_signal, narrowest_rail) {
+            if sig > rail {
+                plan.warnings.push(format!(
+                    "Signal width {} exceeds rail width {}",
+                    sig.0, rail.0
+                ));
+            }
+        }
+        Ok(plan)
+    }
+    /// Describe a single period of this layer's tracks, as in [TrackPlan]'s [Display](std::fmt::Display)
+    pub fn describe(&self) -> LayoutResult<String> {
+        Ok(self.track_plan()?.to_string())
+    }
+    /// Sum up this [Layer]'s pitch: the width of its entries, less their `overlap`.
+    /// Validation requires this be an integer multiple of the [PrimitiveLayer] pitch across the layer.
+    pub fn pitch(&self) -> DbUnits {
         self.entries().iter().map(|e| e.width).sum::<DbUnits>() - self.overlap
     }
 }
@@ -192,13 +569,14 @@ impl MetalLayer {
 /// Splits track-info between signals and rails.
 /// Stores each as a [Track] struct, which moves to a (start, width) size-format,
 /// and includes a vector of track-segments for cutting and assigning nets.
-#[derive(Debug, Clone, Default)]
-pub struct LayerPeriod<'lib> {
+/// Like [Track], owns all of its data, and can be serialized.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LayerPeriod {
     pub index: usize,
-    pub signals: Vec<Track<'lib>>,
-    pub rails: Vec<Track<'lib>>,
+    pub signals: Vec<Track>,
+    pub rails: Vec<Track>,
 }
-impl<'lib> LayerPeriod<'lib> {
+impl LayerPeriod {
     /// Shift the period by `dist` in its periodic direction
     pub fn offset(&mut self, dist: DbUnits) -> LayoutResult<()> {
         for t in self.rails.iter_mut() {
@@ -220,12 +598,7 @@ impl<'lib> LayerPeriod<'lib> {
         Ok(())
     }
     /// Cut all [Track]s from `start` to `stop`,
-    pub fn cut(
-        &mut self,
-        start: DbUnits,
-        stop: DbUnits,
-        src: &'lib TrackCross,
-    ) -> TrackResult<()> {
+    pub fn cut(&mut self, start: DbUnits, stop: DbUnits, src: &TrackCross) -> TrackResult<()> {
         for t in self.rails.iter_mut() {
             t.cut(start, stop, src)?;
         }
@@ -234,8 +607,8 @@ impl<'lib> LayerPeriod<'lib> {
         }
         Ok(())
     }
-    /// Block all [Track]s from `start` to `stop`,
-    pub fn block(&mut self, start: DbUnits, stop: DbUnits, src: &Ptr<Instance>) -> TrackResult<()> {
+    /// Block all [Track]s from `start` to `stop`, by the instance named `src`
+    pub fn block(&mut self, start: DbUnits, stop: DbUnits, src: &str) -> TrackResult<()> {
         for t in self.rails.iter_mut() {
             t.block(start, stop, src)?;
         }
@@ -257,6 +630,7 @@ pub struct ViaLayer {
     /// Via size
     pub size: Xy<DbUnits>,
     /// Stream-out layer numbers
+    #[serde(default)]
     pub raw: Option<raw::LayerKey>,
 }
 /// # Via Targets
@@ -271,28 +645,90 @@ pub enum ViaTarget {
     /// Connect to the Primitive layer
     Primitive,
     /// Connect to an indexed metal layer
-    Metal(usize),
+    Metal(LayerIndex),
+}
+impl From<LayerIndex> for ViaTarget {
+    fn from(i: LayerIndex) -> Self {
+        Self::Metal(i)
+    }
 }
 impl From<usize> for ViaTarget {
     fn from(i: usize) -> Self {
-        Self::Metal(i)
+        Self::Metal(LayerIndex(i))
     }
 }
 impl From<Option<usize>> for ViaTarget {
     fn from(i: Option<usize>) -> Self {
         match i {
             None => Self::Primitive,
-            Some(i) => Self::Metal(i),
+            Some(i) => Self::Metal(LayerIndex(i)),
         }
     }
 }
+/// # Strap
+///
+/// Assignment of a net onto a band of adjacent signal tracks,
+/// e.g. to form a wide power or clock strap.
+/// Claims signal tracks `tracks` on metal-layer `layer`, over `extent`,
+/// which is specified in primitive pitches along the layer's direction.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Strap {
+    /// Net Name
+    pub net: String,
+    /// Metal-Layer Index
+    pub layer: usize,
+    /// Signal-Track Indices
+    pub tracks: Range<usize>,
+    /// Extent along each track, in primitive pitches
+    pub extent: Range<usize>,
+}
+/// # Jog
+///
+/// Short perpendicular connection between two adjacent signal tracks of metal-layer `layer`,
+/// e.g. for a pin escape under an instance, in lieu of general wrong-way routing.
+/// Located at signal track `at` of an adjacent metal layer, and as wide as that track.
+/// Spans from the far edge of `track_a` to the near edge of `track_b`, abutting the latter,
+/// and assigns `net` to each track at its location.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Jog {
+    /// Net Name
+    pub net: String,
+    /// Metal-Layer Index
+    pub layer: usize,
+    /// First Signal-Track Index
+    pub track_a: usize,
+    /// Second Signal-Track Index, adjacent to `track_a`
+    pub track_b: usize,
+    /// Location, as a signal track on an adjacent metal layer
+    pub at: TrackRef,
+}
+/// # Via Policy
+///
+/// Via insertion for an [Assign]. Suppressing the via is appropriate where the connection is made otherwise,
+/// e.g. inside a child cell, or by abutment.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ViaPolicy {
+    /// Insert the default via, per the conversion options
+    #[default]
+    Auto,
+    /// Insert no via. The net assignment still applies to both tracks.
+    None,
+    /// Instantiate the named via variant, per [RawExportOptions::via_variants](crate::conv::raw::RawExportOptions::via_variants)
+    Variant(String),
+}
 /// Assignment of a net onto a track-intersection
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Assign {
     /// Net Name
     pub net: String,
     /// Track Intersection Location
     pub at: TrackCross,
+    /// Via between the intersecting tracks
+    #[serde(default)]
+    pub via: ViaPolicy,
 }
 impl Assign {
     /// Create a new [Assign]
@@ -300,11 +736,91 @@ impl Assign {
         Self {
             net: net.into(),
             at: at.into(),
+            via: ViaPolicy::Auto,
         }
     }
+    /// Set our [ViaPolicy]
+    pub fn via(mut self, via: ViaPolicy) -> Self {
+        self.via = via;
+        self
+    }
+    /// Create a new [Assign], validating its net name per `policy`, generally that of its [Stack]
+    pub fn try_new(
+        net: impl Into<String>,
+        at: impl Into<TrackCross>,
+        policy: &NetNamePolicy,
+    ) -> LayoutResult<Self> {
+        let net = net.into();
+        NetName::validate(&net, policy)?;
+        Ok(Self::new(net, at))
+    }
 }
-/// Relative Z-Axis Reference to one Layer `Above` or `Below` another
+/// # Via Placement
+///
+/// A via placed directly, rather than generated by an [Assign],
+/// e.g. to stitch together two segments of an existing net, or to add redundant vias after routing.
+/// Conversion requires metal on both of its layers at its location, carrying no net other than `net`.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViaPlacement {
+    /// Via Layer
+    pub layer: ViaLayerRef,
+    /// Location
+    pub at: ViaLoc,
+    /// Net Name, labeling the cut. Optional.
+    #[serde(default)]
+    pub net: Option<String>,
+}
+impl ViaPlacement {
+    /// Create a new [ViaPlacement], with no net
+    pub fn new(layer: impl Into<ViaLayerRef>, at: impl Into<ViaLoc>) -> Self {
+        Self {
+            layer: layer.into(),
+            at: at.into(),
+            net: None,
+        }
+    }
+    /// Set our net
+    pub fn net(mut self, net: impl Into<String>) -> Self {
+        self.net = Some(net.into());
+        self
+    }
+}
+/// Reference to a [ViaLayer], by name or by index into `Stack.vias`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ViaLayerRef {
+    Name(String),
+    Index(usize),
+}
+impl From<&str> for ViaLayerRef {
+    fn from(name: &str) -> Self {
+        Self::Name(name.into())
+    }
+}
+impl From<usize> for ViaLayerRef {
+    fn from(idx: usize) -> Self {
+        Self::Index(idx)
+    }
+}
+/// Location of a [ViaPlacement]:
+/// either the intersection of tracks on its two metal layers, or a physical point.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ViaLoc {
+    Cross(TrackCross),
+    Point(Xy<DbUnits>),
+}
+impl From<TrackCross> for ViaLoc {
+    fn from(cross: TrackCross) -> Self {
+        Self::Cross(cross)
+    }
+}
+impl From<Xy<DbUnits>> for ViaLoc {
+    fn from(pt: Xy<DbUnits>) -> Self {
+        Self::Point(pt)
+    }
+}
+/// Relative Z-Axis Reference to one Layer `Above` or `Below` another
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum RelZ {
     Above,
     Below,
@@ -317,6 +833,25 @@ impl RelZ {
         }
     }
 }
+impl std::fmt::Display for RelZ {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Above => write!(f, "Above"),
+            Self::Below => write!(f, "Below"),
+        }
+    }
+}
+impl std::str::FromStr for RelZ {
+    type Err = LayoutError;
+    /// Parse a [RelZ], case-insensitively
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "above" => Ok(Self::Above),
+            "below" => Ok(Self::Below),
+            _ => LayoutError::fail(format!("Invalid RelZ `{}`", s)),
+        }
+    }
+}
 
 /// Indication of whether a layer flips in its periodic axis with every period,
 /// as most standard-cell-style logic gates do.
@@ -325,6 +860,12 @@ pub enum FlipMode {
     EveryOther,
     None,
 }
+impl Default for FlipMode {
+    /// Layers do not flip by default
+    fn default() -> Self {
+        Self::None
+    }
+}
 /// Indication of whether a layer is owned by, partially included in, or external to the primitive blocks
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum PrimitiveMode {
@@ -335,6 +876,12 @@ pub enum PrimitiveMode {
     /// Owned by the Stack
     Stack,
 }
+impl Default for PrimitiveMode {
+    /// Layers are owned by the [Stack] by default
+    fn default() -> Self {
+        Self::Stack
+    }
+}
 /// Description of the primitive-level cells in a [Stack]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PrimitiveLayer {
@@ -19,3 +19,165 @@ fn behind_curtain(yaml: &str) -> LayoutResult<()> {
     let lib = ProtoLibImporter::import(&plib)?;
     exports(lib, SampleStacks::pdka()?)
 }
+
+/// Load a library which includes another, as a sub-library and merged
+#[test]
+fn includes() -> LayoutResult<()> {
+    use crate::conv::proto::{IncludeMode, ProtoLibLoader};
+    use crate::library::{CellRef, Library};
+
+    let lib = Library::open_with_deps(include_path("design.yaml"), &[])?;
+    assert_eq!(lib.name, "design");
+    assert_eq!(lib.cells.len(), 1);
+    assert_eq!(lib.libs.len(), 1);
+    assert_eq!(lib.libs[0].read()?.name, "stdcells");
+    // Both local and external references resolve to the included cells
+    let inv = lib.resolve_ref(&CellRef::name("inv"))?;
+    let buf = lib.resolve_ref(&CellRef::qualified(&["stdcells"], "buf"))?;
+    {
+        let top = lib.cells[0].read()?;
+        let insts = &top.layout.as_ref().unwrap().instances;
+        assert_eq!(insts[0].read()?.cell, inv);
+        assert_eq!(insts[1].read()?.cell, buf);
+    }
+    exports(lib, SampleStacks::pdka()?)?;
+
+    // Merging adds the included cells to our own
+    let lib = ProtoLibLoader::load(include_path("design.yaml"), &[], IncludeMode::Merge)?;
+    assert_eq!(lib.cells.len(), 3);
+    assert_eq!(lib.libs.len(), 0);
+    assert!(lib.cell_named("inv")?.is_some());
+    Ok(())
+}
+
+/// Check the errors for missing and circular includes
+#[test]
+fn include_errors() -> LayoutResult<()> {
+    use crate::library::Library;
+
+    let err = Library::open_with_deps(include_path("missing.yaml"), &[]).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("nonexistent.yaml"));
+    assert!(msg.contains("missing.yaml"));
+
+    let err = Library::open_with_deps(include_path("cycle_a.yaml"), &[]).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("Circular include"));
+    assert!(msg.contains("cycle_a.yaml -> "));
+    assert!(msg.contains("cycle_b.yaml -> "));
+
+    // Includes are also located via search-paths
+    let dir = std::env::temp_dir().join("layout21tetris_include_errors");
+    std::fs::create_dir_all(&dir)?;
+    let fname = dir.join("searched.yaml");
+    std::fs::write(
+        &fname,
+        "schema: 1\ndomain: searched\nincludes: [\"stdcells.yaml\"]\ncells: []\n",
+    )?;
+    assert!(Library::open_with_deps(&fname, &[]).is_err());
+    let lib = Library::open_with_deps(&fname, &[include_path("")])?;
+    assert_eq!(lib.libs[0].read()?.name, "stdcells");
+    Ok(())
+}
+
+/// Save a library with its schema version, and check loading of older and newer schemas
+#[test]
+fn library_schema() -> LayoutResult<()> {
+    use crate::conv::raw::ConversionReport;
+    use crate::library::{Library, LIBRARY_SCHEMA};
+
+    let dir = std::env::temp_dir().join("layout21tetris_library_schema");
+    std::fs::create_dir_all(&dir)?;
+    let fname = dir.join("saved.yaml");
+    crate::fixtures::abstract_library()?.save(&fname)?;
+    let txt = std::fs::read_to_string(&fname)?;
+    assert!(txt.starts_with(&format!("---\nschema: {}\n", LIBRARY_SCHEMA)));
+    let lib = Library::open_with_deps(&fname, &[])?;
+    assert_eq!(lib.name, "lib3");
+    assert_eq!(lib.cells.len(), 2);
+
+    // Newer schemas fail
+    let fname = dir.join("newer.yaml");
+    std::fs::write(
+        &fname,
+        format!("schema: {}\ndomain: newer\ncells: []\n", LIBRARY_SCHEMA + 1),
+    )?;
+    let err = Library::open_with_deps(&fname, &[]).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains(&format!(
+        "schema {}, crate supports up to {}",
+        LIBRARY_SCHEMA + 1,
+        LIBRARY_SCHEMA
+    )));
+
+    // Conversion reports written before their later fields were added still load
+    let report: ConversionReport = Yaml.from_str("periods: 3\nperiod_cache_hits: 1\n")?;
+    assert_eq!(report.periods, 3);
+    assert!(report.warnings.is_empty());
+    Ok(())
+}
+/// Load old-schema library files, through the built-in and application-specific migrations
+#[test]
+fn library_open_with_migrations() -> LayoutResult<()> {
+    use crate::library::{Library, LIBRARY_MIGRATIONS};
+    use crate::utils::{Migration, MigrationError};
+    use serde_yaml::Value;
+
+    // A schema-0 file, which lacks a `schema` field, is migrated by default
+    let lib = Library::open_with_deps(include_path("design_schema0.yaml"), &[])?;
+    assert_eq!(lib.libs.len(), 1);
+    assert_eq!(lib.libs[0].read()?.name, "stdcells");
+    let lib =
+        Library::open_with_migrations(include_path("design_schema0.yaml"), LIBRARY_MIGRATIONS)?;
+    assert_eq!(lib.libs[0].read()?.name, "stdcells");
+
+    // And through a test-only migration table, whose application-specific step renames each cell's legacy `title` to `name`
+    fn rename_titles(doc: &mut Value) -> Result<(), MigrationError> {
+        let cells = doc.get_mut("cells").and_then(|c| c.as_sequence_mut());
+        for (idx, cell) in cells.into_iter().flatten().enumerate() {
+            let map = cell.as_mapping_mut().unwrap();
+            match map.remove(&"title".into()) {
+                Some(title) => map.insert("name".into(), title),
+                None => {
+                    return Err(MigrationError::new(
+                        format!("cells[{}].title", idx),
+                        "missing",
+                    ))
+                }
+            };
+        }
+        Ok(())
+    }
+    let migrations = [
+        Migration::new(0, 1, |_| Ok(())),
+        Migration::new(1, 1, rename_titles),
+    ];
+    let dir = std::env::temp_dir().join("layout21tetris_library_open_with_migrations");
+    std::fs::create_dir_all(&dir)?;
+    let fname = dir.join("titled.yaml");
+    std::fs::write(&fname, "schema: 1\ndomain: titled\ncells:\n  - title: c0\n")?;
+    let lib = Library::open_with_migrations(&fname, &migrations)?;
+    assert_eq!(lib.cells[0].read()?.name, "c0");
+
+    // Failures report the file, the migration step, and the path within the document
+    std::fs::write(&fname, "schema: 1\ndomain: titled\ncells:\n  - name: c0\n")?;
+    let err = Library::open_with_migrations(&fname, &migrations).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("titled.yaml"), "{}", msg);
+    assert!(msg.contains("step 1"), "{}", msg);
+    assert!(msg.contains("`cells[0].title`"), "{}", msg);
+
+    // Documents left short of the current schema fail, rather than silently dropping their includes
+    let err = Library::open_with_migrations(include_path("design_schema0.yaml"), &[]).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("schema 0 was not migrated"), "{}", msg);
+    Ok(())
+}
+/// Get the path of include-test file `fname`
+fn include_path(fname: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join("tests")
+        .join("includes")
+        .join(fname)
+}
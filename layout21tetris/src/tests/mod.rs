@@ -2,13 +2,29 @@
 //! # Unit Tests
 //!
 
+// Std-Lib
+use std::ops::Range;
+
 // Local imports
 use crate::{
-    abs, cell::Cell, conv, instance::Instance, layout::Layout, library::Library, outline::Outline,
-    raw::LayoutResult, stack::*, tracks::*, utils::PtrList, validate::ValidStack,
+    abs,
+    cell::Cell,
+    conv,
+    coords::{LayerIndex, TrackIndex},
+    fixtures::{abstract_library, routed_library},
+    instance::Instance,
+    layout::Layout,
+    library::Library,
+    outline::Outline,
+    raw::LayoutResult,
+    stack::*,
+    tracks::*,
+    utils::{Ptr, PtrList},
+    validate::ValidStack,
 };
 
 // Modules
+#[cfg(feature = "yaml")]
 pub mod demos;
 pub mod ro;
 pub mod stacks;
@@ -24,6 +40,12 @@ fn empty_cell() -> LayoutResult<()> {
         instances: PtrList::new(),
         assignments: Vec::new(),
         cuts: Vec::new(),
+        straps: Vec::new(),
+        jogs: Vec::new(),
+        keepouts: Vec::new(),
+        vias: Vec::new(),
+        suppress_rails: Vec::new(),
+        rail_rings: false,
         places: Vec::new(),
     };
     let mut lib = Library::new("EmptyCellLib");
@@ -33,73 +55,239 @@ fn empty_cell() -> LayoutResult<()> {
 /// Create a layout-implementation
 #[test]
 fn create_layout() -> LayoutResult<()> {
-    Layout {
-        name: "HereGoes".into(),
-        metals: 4,
-        outline: Outline::rect(50, 5)?,
-        instances: PtrList::new(),
-        assignments: vec![Assign {
-            net: "clk".into(),
-            at: TrackCross::from_relz(1, 0, 1, RelZ::Above),
-        }],
-        cuts: Vec::new(),
-        places: Vec::new(),
-    };
+    Cell::builder("HereGoes")
+        .top_layer(LayerIndex(3))
+        .outline(Outline::rect(50, 5)?)
+        .assign(
+            "clk",
+            LayerIndex(1),
+            TrackIndex(0),
+            TrackIndex(1),
+            RelZ::Above,
+        )
+        .build()?;
     Ok(())
 }
 /// Create a library
 #[test]
 fn create_lib1() -> LayoutResult<()> {
+    exports(lib1()?, SampleStacks::pdka()?)
+}
+/// Create the library of [create_lib1]
+fn lib1() -> LayoutResult<Library> {
     let mut lib = Library::new("lib1");
 
-    lib.cells.insert(Layout {
-        name: "HereGoes".into(),
-        metals: 3,
-        outline: Outline::rect(50, 5)?,
-        instances: PtrList::new(),
-        assignments: vec![Assign {
-            net: "clk".into(),
-            at: TrackCross::from_relz(1, 4, 2, RelZ::Below),
-        }],
-        cuts: vec![
-            TrackCross::from_relz(0, 1, 1, RelZ::Above),
-            TrackCross::from_relz(0, 1, 3, RelZ::Above),
-            TrackCross::from_relz(0, 1, 5, RelZ::Above),
-            TrackCross::from_relz(1, 1, 1, RelZ::Below),
-            TrackCross::from_relz(1, 1, 3, RelZ::Below),
-            TrackCross::from_relz(1, 1, 5, RelZ::Below),
-        ],
-        places: Vec::new(),
+    lib.cells.insert(
+        Cell::builder("HereGoes")
+            .top_layer(LayerIndex(2))
+            .outline(Outline::rect(50, 5)?)
+            .assign(
+                "clk",
+                LayerIndex(1),
+                TrackIndex(4),
+                TrackIndex(2),
+                RelZ::Below,
+            )
+            .cut(LayerIndex(0), TrackIndex(1), TrackIndex(1), RelZ::Above)
+            .cut(LayerIndex(0), TrackIndex(1), TrackIndex(3), RelZ::Above)
+            .cut(LayerIndex(0), TrackIndex(1), TrackIndex(5), RelZ::Above)
+            .cut(LayerIndex(1), TrackIndex(1), TrackIndex(1), RelZ::Below)
+            .cut(LayerIndex(1), TrackIndex(1), TrackIndex(3), RelZ::Below)
+            .cut(LayerIndex(1), TrackIndex(1), TrackIndex(5), RelZ::Below)
+            .build()?,
+    );
+    Ok(lib)
+}
+/// Describe the routes of [create_lib1], and check their coordinates against [ValidStack::track_cross_xy]
+#[test]
+fn describe_routes_lib1() -> LayoutResult<()> {
+    let lib = lib1()?;
+    let stack = SampleStacks::pdka()?;
+    let cell = lib.cells.iter().next().unwrap().read()?;
+    let txt = cell.describe_routes(&stack)?;
+    let expected = "\
+clk: met2 track 4 (x=1840) × met1 track 2 (y=1190) [Below]
+cut: met1 track 1 (y=850) × met2 track 1 (x=460) [Above]
+cut: met1 track 1 (y=850) × met2 track 3 (x=1380) [Above]
+cut: met1 track 1 (y=850) × met2 track 5 (x=2300) [Above]
+cut: met2 track 1 (x=460) × met1 track 1 (y=850) [Below]
+cut: met2 track 1 (x=460) × met1 track 3 (y=1530) [Below]
+cut: met2 track 1 (x=460) × met1 track 5 (y=2210) [Below]
+";
+    assert_eq!(txt, expected);
+    // Coordinates match those of the stack
+    let layout = cell.layout.as_ref().unwrap();
+    let xy = stack.track_cross_xy(&layout.assignments[0].at)?;
+    assert_eq!((xy.x.0, xy.y.0), (1840, 1190));
+    let xy = stack.track_cross_xy(&layout.cuts[2])?;
+    assert_eq!((xy.x.0, xy.y.0), (2300, 850));
+    // Cells without layouts have nothing to describe
+    let abs_only = Cell::from(abs::Abstract {
+        name: "abs".into(),
+        metals: 1,
+        outline: Outline::rect(1, 1)?,
+        ports: Vec::new(),
     });
-    exports(lib, SampleStacks::pdka()?)
+    assert_eq!(abs_only.describe_routes(&stack)?, "");
+    Ok(())
 }
 /// Create a cell with instances
 #[test]
 fn create_lib2() -> LayoutResult<()> {
+    exports(lib2()?, SampleStacks::pdka()?)
+}
+/// Create the library of [create_lib2]
+fn lib2() -> LayoutResult<Library> {
     let mut lib = Library::new("lib2");
     let c2 = Layout::new("IsInst", 2, Outline::rect(100, 10)?);
     let c2 = lib.cells.insert(c2);
 
-    lib.cells.insert(Layout {
-        name: "HasInst".into(),
-        metals: 4,
-        outline: Outline::rect(200, 20)?,
-        instances: vec![Instance {
-            inst_name: "inst1".into(),
-            cell: c2,
-            loc: (20, 2).into(),
-            reflect_horiz: false,
-            reflect_vert: false,
-        }]
-        .into(),
-        assignments: vec![Assign {
-            net: "clk".into(),
-            at: TrackCross::from_relz(1, 1, 1, RelZ::Above),
-        }],
-        cuts: Vec::new(),
-        places: Vec::new(),
-    });
-    exports(lib, SampleStacks::pdka()?)
+    let inst1 = Instance::builder()
+        .inst_name("inst1")
+        .cell(c2)
+        .loc((20, 2))
+        .build()?;
+    lib.cells.insert(
+        Cell::builder("HasInst")
+            .top_layer(LayerIndex(3))
+            .outline(Outline::rect(200, 20)?)
+            .instance(inst1)
+            .assign(
+                "clk",
+                LayerIndex(1),
+                TrackIndex(1),
+                TrackIndex(1),
+                RelZ::Above,
+            )
+            .build()?,
+    );
+    Ok(lib)
+}
+/// Export a single cell of [create_lib2] and its dependencies to GDS
+#[cfg(feature = "gds")]
+#[test]
+fn partial_gds_export() -> LayoutResult<()> {
+    use crate::raw::gds::GdsExportOptions;
+
+    let mut lib = lib2()?;
+    lib.cells
+        .insert(Layout::new("Unused", 2, Outline::rect(10, 10)?));
+    let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let names = |top: &str| -> LayoutResult<Vec<String>> {
+        let gds = rawlib.to_gds_cell(top, &GdsExportOptions::default())?;
+        Ok(gds.structs.iter().map(|s| s.name.clone()).collect())
+    };
+    assert_eq!(names("HasInst")?, vec!["IsInst", "HasInst"]);
+    assert_eq!(names("IsInst")?, vec!["IsInst"]);
+    assert!(names("NotACell").is_err());
+    // The full library remains intact
+    assert_eq!(rawlib.to_gds()?.structs.len(), 3);
+    Ok(())
+}
+/// Carry [Cell] properties through conversion and into GDSII
+#[cfg(feature = "gds")]
+#[test]
+fn cell_props() -> LayoutResult<()> {
+    use crate::raw::gds::{gds21::GdsElement, GdsExportOptions};
+
+    let mut lib = Library::new("cell_props");
+    lib.cells.insert(
+        Cell::builder("Propped")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(5, 5)?)
+            .prop("generator", "tetris")
+            .prop("params", "0xabc123")
+            .build()?,
+    );
+    let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let props = rawlib.cells[0].read()?.props.clone();
+    assert_eq!(props.len(), 2);
+    assert_eq!(props["generator"], "tetris");
+
+    let opts = GdsExportOptions {
+        cell_props: Some((255, 1)),
+        ..Default::default()
+    };
+    let gds = rawlib.to_gds_with_options(&opts)?;
+    let strukt = gds.structs.iter().find(|s| s.name == "Propped").unwrap();
+    let texts: Vec<&str> = strukt
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsTextElem(t) if (t.layer, t.texttype) == (255, 1) => {
+                Some(t.string.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(texts, vec!["generator=tetris", "params=0xabc123"]);
+    Ok(())
+}
+/// Blackbox the child cell of [create_lib2], keeping only its boundary
+#[cfg(feature = "gds")]
+#[test]
+fn blackbox_lib2() -> LayoutResult<()> {
+    use crate::raw::{self, LayerPurpose};
+
+    let stack = SampleStacks::pdka()?;
+    let boundary_layer = stack.boundary_layer.unwrap();
+    let boundary = {
+        let layers = stack.rawlayers.as_ref().unwrap().read()?;
+        let layer = layers.get(boundary_layer).unwrap();
+        (layer.layernum, layer.num(&LayerPurpose::Outline).unwrap())
+    };
+    let rawlib = conv::raw::RawExporter::convert(lib2()?, stack)?;
+    let mut rawlib = rawlib.write()?;
+    let child = rawlib.cell_named("IsInst")?.unwrap();
+    let parent = rawlib.cell_named("HasInst")?.unwrap();
+    // Add a boundary to the child, which full conversion does not draw for cells with metal layers
+    {
+        let mut child = child.write()?;
+        let layout = child.layout.as_mut().unwrap();
+        let bbox = layout.bbox()?;
+        layout.elems.push(raw::Element {
+            net: None,
+            layer: boundary_layer,
+            purpose: LayerPurpose::Outline,
+            inner: raw::Shape::Rect(raw::Rect {
+                p0: bbox.p0,
+                p1: bbox.p1,
+            }),
+            labels: Vec::new(),
+        });
+    }
+    let nelems = |ptr: &Ptr<raw::Cell>| ptr.read().unwrap().layout.as_ref().unwrap().elems.len();
+    let (child_elems, parent_elems) = (nelems(&child), nelems(&parent));
+    assert!(child_elems > 1);
+
+    // Unknown names fail before anything is modified
+    let err = rawlib
+        .blackbox(&["IsInst", "nope"], &[boundary])
+        .unwrap_err();
+    assert!(err.to_string().contains("nope"));
+    assert_eq!(nelems(&child), child_elems);
+
+    let report = rawlib.blackbox(&["IsInst"], &[boundary])?;
+    assert_eq!(report.cells.len(), 1);
+    assert_eq!(report.cells[0].name, "IsInst");
+    assert_eq!(report.cells[0].elems_kept, 1);
+    assert_eq!(report.cells[0].elems_removed, child_elems - 1);
+    // The child retains only its boundary
+    {
+        let child = child.read()?;
+        let layout = child.layout.as_ref().unwrap();
+        assert_eq!(layout.elems.len(), 1);
+        assert_eq!(layout.elems[0].purpose, LayerPurpose::Outline);
+        assert!(layout.insts.is_empty());
+    }
+    // While the parent is unchanged, and still refers to it
+    assert_eq!(nelems(&parent), parent_elems);
+    let parent = parent.read()?;
+    assert_eq!(parent.layout.as_ref().unwrap().insts[0].cell, child);
+    assert_eq!(rawlib.to_gds()?.structs.len(), 2);
+    Ok(())
 }
 
 /// Create an abstract layout, with its variety of supported port types
@@ -107,38 +295,30 @@ fn create_lib2() -> LayoutResult<()> {
 fn create_abstract() -> LayoutResult<()> {
     let outline = Outline::rect(11, 11)?;
     let ports = vec![
-        abs::Port {
-            name: "edge_bot".into(),
-            kind: abs::PortKind::Edge {
-                layer: 2,
-                track: 2,
-                side: abs::Side::BottomOrLeft,
-            },
-        },
-        abs::Port {
-            name: "edge_top".into(),
-            kind: abs::PortKind::Edge {
-                layer: 2,
-                track: 4,
-                side: abs::Side::TopOrRight,
-            },
-        },
-        abs::Port {
-            name: "edge_left".into(),
-            kind: abs::PortKind::Edge {
-                layer: 1,
-                track: 1,
-                side: abs::Side::BottomOrLeft,
-            },
-        },
-        abs::Port {
-            name: "edge_right".into(),
-            kind: abs::PortKind::Edge {
-                layer: 1,
-                track: 5,
-                side: abs::Side::TopOrRight,
-            },
-        },
+        abs::Port::edge(
+            "edge_bot",
+            LayerIndex(2),
+            TrackIndex(2),
+            abs::Side::BottomOrLeft,
+        ),
+        abs::Port::edge(
+            "edge_top",
+            LayerIndex(2),
+            TrackIndex(4),
+            abs::Side::TopOrRight,
+        ),
+        abs::Port::edge(
+            "edge_left",
+            LayerIndex(1),
+            TrackIndex(1),
+            abs::Side::BottomOrLeft,
+        ),
+        abs::Port::edge(
+            "edge_right",
+            LayerIndex(1),
+            TrackIndex(5),
+            abs::Side::TopOrRight,
+        ),
     ];
     abs::Abstract {
         name: "abstrack".into(),
@@ -152,78 +332,4530 @@ fn create_abstract() -> LayoutResult<()> {
 /// Create a cell with abstract instances
 #[test]
 fn create_lib3() -> LayoutResult<()> {
-    let mut lib = Library::new("lib3");
+    exports(abstract_library()?, SampleStacks::pdka()?)
+}
+/// Convert [create_lib3] to its floorplan view, comprising solely outlines and instances
+#[cfg(feature = "gds")]
+#[test]
+fn floorplan_view() -> LayoutResult<()> {
+    use crate::conv::raw::{ConversionMode, RawExportOptions, RawExporter};
+    use crate::gds21::GdsElement;
 
-    let c2 = lib.cells.insert(abs::Abstract {
-        name: "IsAbs".into(),
-        metals: 1,
-        outline: Outline::rect(100, 10)?,
-        ports: Vec::new(),
-    });
+    let opts = RawExportOptions {
+        mode: ConversionMode::FloorplanOnly,
+        ..Default::default()
+    };
+    let (rawlib, report) =
+        RawExporter::convert_with_options(abstract_library()?, SampleStacks::pdka()?, &opts)?;
+    assert_eq!(report.periods, 0);
+    let gds = rawlib.read()?.to_gds()?;
+    let has_abss = gds.structs.iter().find(|s| s.name == "HasAbss").unwrap();
+    let count = |f: fn(&GdsElement) -> bool| has_abss.elems.iter().filter(|e| f(e)).count();
+    assert_eq!(has_abss.elems.len(), 4);
+    assert_eq!(count(|e| matches!(e, GdsElement::GdsBoundary(_))), 1);
+    assert_eq!(count(|e| matches!(e, GdsElement::GdsStructRef(_))), 3);
+    // The abstract is reduced to its outline
+    let rawlib = rawlib.read()?;
+    let is_abs = rawlib.cell_named("IsAbs")?.unwrap();
+    let is_abs = is_abs.read()?;
+    let rawabs = is_abs.abs.as_ref().unwrap();
+    assert!(rawabs.ports.is_empty() && rawabs.blockages.is_empty());
+    Ok(())
+}
+/// Draw debug markers at instance origins, oriented per their reflections, and exclude them from GDSII
+#[cfg(feature = "gds")]
+#[test]
+fn instance_markers() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::raw::gds::GdsExportOptions;
+    use crate::raw::{self, BoundBoxTrait};
 
-    lib.cells.insert(Layout {
-        name: "HasAbss".into(),
-        metals: 4,
-        outline: Outline::rect(500, 50)?,
-        instances: vec![
-            Instance {
-                inst_name: "inst1".into(),
-                cell: c2.clone(),
-                loc: (0, 0).into(),
-                reflect_horiz: false,
-                reflect_vert: false,
-            },
-            Instance {
-                inst_name: "inst2".into(),
-                cell: c2.clone(),
-                loc: (200, 20).into(),
-                reflect_horiz: false,
-                reflect_vert: false,
-            },
-            Instance {
-                inst_name: "inst4".into(),
-                cell: c2.clone(),
-                loc: (400, 40).into(),
-                reflect_horiz: false,
-                reflect_vert: false,
-            },
+    // Define a dedicated marker layer
+    let stack = || -> LayoutResult<ValidStack> {
+        let stack = SampleStacks::pdka()?;
+        {
+            let mut rawlayers = stack.rawlayers.as_ref().unwrap().write()?;
+            rawlayers.add(raw::Layer::from_pairs(
+                200,
+                &[(63, raw::LayerPurpose::Other(63))],
+            )?);
+        }
+        Ok(stack)
+    };
+    let lib = || -> LayoutResult<Library> {
+        let mut lib = Library::new("instance_markers");
+        let leaf = lib
+            .cells
+            .insert(Layout::new("leaf", 1, Outline::rect(4, 1)?));
+        let mut top = Cell::builder("top")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(20, 2)?);
+        for (name, x, reflect_horiz) in [("plain", 4, false), ("flipped", 12, true)] {
+            let inst = Instance::builder()
+                .inst_name(name)
+                .cell(leaf.clone())
+                .loc((x, 0))
+                .reflect_horiz(reflect_horiz)
+                .build()?;
+            top = top.instance(inst);
+        }
+        lib.cells.insert(top.build()?);
+        Ok(lib)
+    };
+    let opts = RawExportOptions {
+        instance_markers: Some((200, 63)),
+        ..Default::default()
+    };
+    let (rawlib, _) = RawExporter::convert_with_options(lib()?, stack()?, &opts)?;
+    let rawlib = rawlib.read()?;
+    let top = rawlib.cell_named("top")?.unwrap();
+    let top = top.read()?;
+    let layout = top.layout.as_ref().unwrap();
+    let markers: Vec<&raw::Element> = layout
+        .elems
+        .iter()
+        .filter(|e| e.purpose == raw::LayerPurpose::Other(63))
+        .collect();
+    assert_eq!(markers.len(), 2);
+    assert!(markers
+        .iter()
+        .all(|e| e.net.is_none() && e.inner.is_normalized()));
+    // Each marker is annotated with its instance name, at its origin
+    let marker = |name: &str| -> (raw::BoundBox, raw::Point) {
+        let inst = layout.insts.iter().find(|i| i.inst_name == name).unwrap();
+        let annotations = layout.annotations.iter();
+        let annotation = annotations.filter(|a| a.string == name).collect::<Vec<_>>();
+        assert_eq!(annotation.len(), 1);
+        assert_eq!(annotation[0].loc, inst.loc);
+        let marker = markers.iter().find(|e| {
+            let bbox = e.inner.bbox();
+            bbox.p0.y == inst.loc.y && (bbox.p0.x == inst.loc.x || bbox.p1.x == inst.loc.x)
+        });
+        (marker.unwrap().inner.bbox(), inst.loc)
+    };
+    // Each marker sits at its instance's origin, extending right of it unless reflected
+    let (plain, plain_loc) = marker("plain");
+    assert_eq!(plain.p0, plain_loc);
+    let (flipped, flipped_loc) = marker("flipped");
+    assert_eq!(flipped.p1.x, flipped_loc.x);
+    assert_eq!(flipped.p0.y, flipped_loc.y);
+    assert_eq!(plain.p1.x - plain.p0.x, flipped.p1.x - flipped.p0.x);
+
+    // Markers are excluded from GDSII on request
+    let count = |opts: &GdsExportOptions| -> LayoutResult<usize> {
+        let gds = rawlib.to_gds_with_options(opts)?;
+        let top = gds.structs.iter().find(|s| s.name == "top").unwrap();
+        Ok(top.elems.len())
+    };
+    let all = count(&GdsExportOptions::default())?;
+    let excluded = count(&GdsExportOptions {
+        exclude_layers: vec![(200, 63)],
+        ..Default::default()
+    })?;
+    assert_eq!(all - excluded, 2);
+
+    // Undefined marker layers and datatypes fail
+    for markers in [(200, 64), (201, 63)] {
+        let opts = RawExportOptions {
+            instance_markers: Some(markers),
+            ..Default::default()
+        };
+        assert!(RawExporter::convert_with_options(lib()?, stack()?, &opts).is_err());
+    }
+    Ok(())
+}
+/// Check each [AbstractHandling] of instances of abstract-only cells
+#[test]
+fn abstract_handling() -> LayoutResult<()> {
+    use crate::conv::raw::{AbstractHandling, RawExportOptions, RawExporter};
+    use crate::raw;
+
+    let convert = |abstracts: AbstractHandling| {
+        let opts = RawExportOptions {
+            abstracts,
+            ..Default::default()
+        };
+        RawExporter::convert_with_options(abstract_library()?, SampleStacks::pdka()?, &opts)
+    };
+    let ninsts = |rawlib: &Ptr<raw::Library>| -> LayoutResult<usize> {
+        let rawlib = rawlib.read()?;
+        let cell = rawlib
+            .cells
+            .iter()
+            .find(|c| c.read().unwrap().name == "HasAbss");
+        let cell = cell.unwrap().read()?;
+        Ok(cell.layout.as_ref().unwrap().insts.len())
+    };
+    // Boundary-only instances are exported, and reported
+    let (rawlib, report) = convert(AbstractHandling::BoundaryOnly)?;
+    assert_eq!(ninsts(&rawlib)?, 3);
+    assert_eq!(report.abstract_only, vec!["IsAbs".to_string()]);
+    assert!(report.warnings.is_empty());
+    // Omitted instances are dropped, with a warning for each
+    let (rawlib, report) = convert(AbstractHandling::Omit)?;
+    assert_eq!(ninsts(&rawlib)?, 0);
+    assert_eq!(report.abstract_only, vec!["IsAbs".to_string()]);
+    assert_eq!(report.warnings.len(), 3);
+    // And requiring implementations fails, naming the cell
+    let err = convert(AbstractHandling::RequireImpl).unwrap_err();
+    assert!(format!("{}", err).contains("IsAbs"));
+    Ok(())
+}
+/// Check the structural validations performed by [CellBuilder]
+#[test]
+fn cell_builder_errors() -> LayoutResult<()> {
+    let cell = || Cell::builder("Errs").outline(Outline::rect(5, 5).unwrap());
+    assert!(cell().build().is_err()); // No top layer
+    assert!(Cell::builder("Errs")
+        .top_layer(LayerIndex(1))
+        .build()
+        .is_err()); // No outline
+    assert!(cell()
+        .top_layer(LayerIndex(1))
+        .assign(
+            "a",
+            LayerIndex(1),
+            TrackIndex(0),
+            TrackIndex(0),
+            RelZ::Above
+        )
+        .build()
+        .is_err());
+    assert!(cell()
+        .top_layer(LayerIndex(1))
+        .cut(LayerIndex(1), TrackIndex(0), TrackIndex(0), RelZ::Below)
+        .build()
+        .is_ok());
+    assert!(cell()
+        .top_layer(LayerIndex(1))
+        .cut(LayerIndex(2), TrackIndex(0), TrackIndex(0), RelZ::Below)
+        .build()
+        .is_err());
+    Ok(())
+}
+#[test]
+fn net_name_validation() -> LayoutResult<()> {
+    use crate::net::{NetName, NetNamePolicy, RailConflict};
+    use crate::validate::LibValidator;
+
+    let policy = NetNamePolicy::default();
+    NetName::validate("clk", &policy)?;
+    NetName::validate("data<3>", &policy)?;
+    assert!(NetName::validate("", &policy).is_err());
+    assert!(NetName::validate("a b", &policy).is_err());
+    assert!(NetName::validate("clk\n", &policy).is_err());
+    assert!(NetName::validate("a\u{7}", &policy).is_err());
+    assert!(NetName::validate(&"x".repeat(257), &policy).is_err());
+    let short = NetNamePolicy {
+        max_len: 3,
+        ..Default::default()
+    };
+    assert!(NetName::validate("clk", &short).is_ok());
+    assert!(NetName::validate("clk2", &short).is_err());
+
+    // Construction
+    let at = TrackCross::from_relz(LayerIndex(1), TrackIndex(0), TrackIndex(1), RelZ::Above);
+    assert!(Assign::try_new("clk", at, &policy).is_ok());
+    assert!(Assign::try_new(" ", at, &policy).is_err());
+    assert!(Assign::try_new("clk2", at, &short).is_err());
+
+    // Builders defer to the stack's policy, checked on conversion
+    let long = "x".repeat(300);
+    let lib = |net: &str| -> LayoutResult<Library> {
+        let mut lib = Library::new("NetsLib");
+        lib.cells.insert(
+            Cell::builder("Nets")
+                .top_layer(LayerIndex(2))
+                .outline(Outline::rect(5, 5)?)
+                .assign(
+                    net,
+                    LayerIndex(1),
+                    TrackIndex(0),
+                    TrackIndex(1),
+                    RelZ::Below,
+                )
+                .build()?,
+        );
+        Ok(lib)
+    };
+    assert!(lib("")?.to_raw(SampleStacks::pdka()?).is_err());
+    assert!(lib(&long)?.to_raw(SampleStacks::pdka()?).is_err());
+    let mut permissive = SampleStacks::pdka()?;
+    permissive.nets.max_len = 512;
+    lib(&long)?.to_raw(permissive)?;
+
+    // Rail names warn by default, and fail per [RailConflict::Error]
+    let mut stack = SampleStacks::pdka()?;
+    let mut validator = LibValidator::new(&stack);
+    validator.validate_assign(&Assign::new("clk", at))?;
+    assert!(validator.warnings.is_empty());
+    validator.validate_assign(&Assign::new("VDD", at))?;
+    assert_eq!(validator.warnings.len(), 1);
+    assert!(validator.warnings[0].contains("VDD"));
+    assert!(validator.validate_assign(&Assign::new("", at)).is_err());
+    stack.nets.rail_conflict = RailConflict::Error;
+    let mut validator = LibValidator::new(&stack);
+    assert!(validator.validate_assign(&Assign::new("VSS", at)).is_err());
+    assert!(validator.warnings.is_empty());
+    Ok(())
+}
+#[test]
+fn assign_bus() -> LayoutResult<()> {
+    use crate::net::{BusStyle, NetRef};
+
+    let mut stack = SampleStacks::pdka()?;
+    let outline = Outline::rect(50, 5)?;
+    let cell = || {
+        Cell::builder("Bus")
+            .top_layer(LayerIndex(3))
+            .outline(outline.clone())
+            .build()
+    };
+    let first = TrackCross::from_relz(LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Above);
+    for style in [BusStyle::Brackets, BusStyle::Underscore] {
+        stack.nets.bus_style = style;
+        let mut cell = cell()?;
+        cell.assign_bus(&stack, "data", 0..8, first, 2)?;
+        let assns = &cell.layout.as_ref().unwrap().assignments;
+        assert_eq!(assns.len(), 8);
+        for (bit, assn) in assns.iter().enumerate() {
+            let name = match style {
+                BusStyle::Brackets => format!("data[{}]", bit),
+                BusStyle::Underscore => format!("data_{}", bit),
+            };
+            assert_eq!(assn.net, name);
+            assert_eq!(
+                assn.at,
+                TrackCross::from_relz(
+                    LayerIndex(1),
+                    TrackIndex(2 + 2 * bit),
+                    TrackIndex(3),
+                    RelZ::Above
+                )
+            );
+            assert_eq!(NetRef::parse(&assn.net, style)?, NetRef::bit("data", bit));
+        }
+    }
+    // Running out of tracks fails, and adds no assignments
+    let ntracks = stack.signal_tracks(LayerIndex(1), &outline)?;
+    let mut cell = cell()?;
+    let first = TrackCross::from_relz(LayerIndex(1), TrackIndex(0), TrackIndex(3), RelZ::Above);
+    assert!(cell
+        .assign_bus(&stack, "wide", 0..ntracks, first, 1)
+        .is_ok());
+    assert!(cell
+        .assign_bus(&stack, "wide", 0..ntracks + 1, first, 1)
+        .is_err());
+    assert_eq!(cell.layout.as_ref().unwrap().assignments.len(), ntracks);
+    assert!(Cell::new("NoLayout")
+        .assign_bus(&stack, "data", 0..1, first, 1)
+        .is_err());
+    // As do zero strides, which would assign every bit to the same track
+    assert!(Cell::builder("Bus")
+        .top_layer(LayerIndex(3))
+        .outline(outline)
+        .build()?
+        .assign_bus(&stack, "data", 0..2, first, 0)
+        .is_err());
+
+    // Parsing and rendering
+    assert_eq!("clk".parse::<NetRef>()?, NetRef::scalar("clk"));
+    assert_eq!("data[31]".parse::<NetRef>()?, NetRef::bit("data", 31));
+    assert_eq!(NetRef::bit("data", 31).to_string(), "data[31]");
+    assert_eq!(
+        NetRef::parse("net_a", BusStyle::Underscore)?,
+        NetRef::scalar("net_a")
+    );
+    assert_eq!(
+        NetRef::parse("data_3", BusStyle::Brackets)?,
+        NetRef::scalar("data_3")
+    );
+    // Trailing separators without an index are part of scalar names
+    assert_eq!(
+        NetRef::parse("data_", BusStyle::Underscore)?,
+        NetRef::scalar("data_")
+    );
+    assert!("[3]".parse::<NetRef>().is_err());
+    assert!("data[x]".parse::<NetRef>().is_err());
+    assert!("".parse::<NetRef>().is_err());
+    Ok(())
+}
+#[test]
+fn strap() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::raw;
+
+    let cell = || -> LayoutResult<Cell> {
+        let mut cell = Cell::builder("Strapped")
+            .top_layer(LayerIndex(3))
+            .outline(Outline::rect(50, 5)?)
+            .build()?;
+        cell.strap("VPWR", 2, 1..4, 10..20)?;
+        Ok(cell)
+    };
+    // Convert to raw, and return the (net, rect) pairs on metal layer `layer`
+    let convert =
+        |cell: Cell, opts: &RawExportOptions| -> LayoutResult<Vec<(Option<String>, raw::Rect)>> {
+            let stack = SampleStacks::pdka()?;
+            let layerkey = stack.metal(LayerIndex(2))?.raw.unwrap();
+            let mut lib = Library::new("StrapLib");
+            lib.cells.insert(cell);
+            let (rawlib, _report) = RawExporter::convert_with_options(lib, stack, opts)?;
+            let rawlib = rawlib.read()?;
+            let rawcell = rawlib.cells[0].read()?;
+            let layout = rawcell.layout.as_ref().unwrap();
+            Ok(layout
+                .elems
+                .iter()
+                .filter(|e| e.layer == layerkey)
+                .filter_map(|e| match e.inner {
+                    raw::Shape::Rect(ref r) => Some((e.net.clone(), r.clone())),
+                    _ => None,
+                })
+                .collect())
+        };
+    // Three strap segments, each spanning the strap's extent
+    let rects = convert(cell()?, &RawExportOptions::default())?;
+    let straps: Vec<_> = rects
+        .iter()
+        .filter(|(net, _)| net.as_deref() == Some("VPWR"))
+        .collect();
+    assert_eq!(straps.len(), 3);
+    for (_, rect) in straps.iter() {
+        assert_eq!(rect.p0.x, 10 * 460);
+        assert_eq!(rect.p1.x, 20 * 460);
+    }
+
+    // Conflicting assignments fail by default, and are overridden per [ConflictPolicy::Override]
+    let conflicted = || -> LayoutResult<Cell> {
+        let mut cell = cell()?;
+        let layout = cell.layout.as_mut().unwrap();
+        layout.assign(
+            "clk",
+            LayerIndex(2),
+            TrackIndex(2),
+            TrackIndex(1),
+            RelZ::Above,
+        );
+        Ok(cell)
+    };
+    assert!(convert(conflicted()?, &RawExportOptions::default()).is_err());
+    let opts = RawExportOptions {
+        strap_conflicts: ConflictPolicy::Override,
+        ..Default::default()
+    };
+    let rects = convert(conflicted()?, &opts)?;
+    let count = |name: &str| {
+        rects
+            .iter()
+            .filter(|(net, _)| net.as_deref() == Some(name))
+            .count()
+    };
+    assert_eq!(count("VPWR"), 3);
+    assert_eq!(count("clk"), 2); // On either side of the strap
+
+    // Cuts within the strap always conflict
+    let mut cut = cell()?;
+    let layout = cut.layout.as_mut().unwrap();
+    layout.strap("VPWR", 2, 5..6, 0..50);
+    layout.cut(LayerIndex(2), TrackIndex(5), TrackIndex(3), RelZ::Above);
+    assert!(convert(cut, &opts).is_err());
+
+    // Invalid straps
+    let mut c = cell()?;
+    assert!(c.strap("VPWR", 4, 1..4, 10..20).is_err());
+    assert!(c.strap("VPWR", 2, 1..1, 10..20).is_err());
+    assert!(Cell::new("NoLayout")
+        .strap("VPWR", 2, 1..4, 10..20)
+        .is_err());
+    c.strap("VPWR", 2, 1..4, 10..51)?;
+    assert!(convert(c, &opts).is_err()); // Extends beyond the outline
+    let mut unnamed = cell()?;
+    unnamed.strap("", 2, 1..4, 10..20)?;
+    assert!(convert(unnamed, &opts).is_err()); // Invalid net names fail on validation
+    Ok(())
+}
+/// Jog between adjacent tracks, checking the jog's extents, conflicts, and minimum width
+#[test]
+fn jog() -> LayoutResult<()> {
+    use crate::conv::raw::RawExporter;
+    use crate::raw;
+
+    let cell = || -> LayoutResult<Cell> {
+        let mut cell = Cell::builder("Jogged")
+            .top_layer(LayerIndex(3))
+            .outline(Outline::rect(10, 1)?)
+            .assign(
+                "a",
+                LayerIndex(0),
+                TrackIndex(2),
+                TrackIndex(1),
+                RelZ::Above,
+            )
+            .build()?;
+        cell.jog("a", 0, 2, 3, TrackIndex(4), RelZ::Above)?;
+        Ok(cell)
+    };
+    // Convert to raw, and return the rects on metal layer `layer` with net `net`
+    let convert = |cell: Cell, layer: usize, net: &str| -> LayoutResult<Vec<raw::Rect>> {
+        let stack = SampleStacks::pdka()?;
+        let layerkey = stack.metal(LayerIndex(layer))?.raw.unwrap();
+        let mut lib = Library::new("JogLib");
+        lib.cells.insert(cell);
+        let rawlib = RawExporter::convert(lib, stack)?;
+        let rawlib = rawlib.read()?;
+        let rawcell = rawlib.cells[0].read()?;
+        let layout = rawcell.layout.as_ref().unwrap();
+        Ok(layout
+            .elems
+            .iter()
+            .filter(|e| e.layer == layerkey && e.net.as_deref() == Some(net))
+            .filter_map(|e| match e.inner {
+                raw::Shape::Rect(ref r) => Some(r.clone()),
+                _ => None,
+            })
+            .collect())
+    };
+    // The jog spans from the far edge of met1 track 2, at y=1190, to the near edge of track 3, at y=1530,
+    // and is as wide as met2 track 4, at x=1840
+    let rects = convert(cell()?, 0, "a")?;
+    let jog = raw::Rect {
+        p0: raw::Point::new(1840 - 70, 1190 - 70),
+        p1: raw::Point::new(1840 + 70, 1530 - 70),
+    };
+    assert!(rects.contains(&jog));
+    // Reversing the tracks covers track 3 instead
+    let mut reversed = Cell::builder("Jogged")
+        .top_layer(LayerIndex(3))
+        .outline(Outline::rect(10, 1)?)
+        .assign(
+            "a",
+            LayerIndex(0),
+            TrackIndex(2),
+            TrackIndex(1),
+            RelZ::Above,
+        )
+        .build()?;
+    reversed.jog("a", 0, 3, 2, TrackIndex(4), RelZ::Above)?;
+    let rects = convert(reversed, 0, "a")?;
+    assert!(rects.contains(&raw::Rect {
+        p0: raw::Point::new(1840 - 70, 1190 + 70),
+        p1: raw::Point::new(1840 + 70, 1530 + 70),
+    }));
+    // And is no narrower than the tracks it connects
+    assert!(jog.p1.x - jog.p0.x >= 140);
+
+    // Either track assigned to another net at the jog conflicts
+    let mut conflicted = cell()?;
+    let layout = conflicted.layout.as_mut().unwrap();
+    layout.assign(
+        "b",
+        LayerIndex(0),
+        TrackIndex(3),
+        TrackIndex(4),
+        RelZ::Above,
+    );
+    assert!(convert(conflicted, 0, "a").is_err());
+    // As does a cut
+    let mut cut = cell()?;
+    cut.layout
+        .as_mut()
+        .unwrap()
+        .cut(LayerIndex(0), TrackIndex(2), TrackIndex(4), RelZ::Above);
+    assert!(convert(cut, 0, "a").is_err());
+
+    // Jogs on met3, located on the narrower met4 tracks, fail the minimum-width check
+    let mut narrow = cell()?;
+    narrow.jog("a", 2, 2, 3, TrackIndex(1), RelZ::Above)?;
+    assert!(convert(narrow, 2, "a").is_err());
+
+    // Invalid jogs
+    let mut c = cell()?;
+    assert!(c.jog("a", 0, 2, 4, TrackIndex(4), RelZ::Above).is_err());
+    assert!(c.jog("a", 0, 2, 3, TrackIndex(4), RelZ::Below).is_err());
+    assert!(c.jog("a", 3, 2, 3, TrackIndex(4), RelZ::Above).is_err());
+    assert!(Cell::new("NoLayout")
+        .jog("a", 0, 2, 3, TrackIndex(4), RelZ::Above)
+        .is_err());
+    c.jog("a", 0, 5, 6, TrackIndex(4), RelZ::Above)?;
+    assert!(convert(c, 0, "a").is_err()); // Beyond the outline
+    let mut unnamed = cell()?;
+    unnamed.jog("", 0, 2, 3, TrackIndex(4), RelZ::Above)?;
+    assert!(convert(unnamed, 0, "").is_err()); // Invalid net names fail on validation
+
+    // Jogging onto a rail track conflicts, rather than panicking
+    use crate::coords::DbUnits;
+    let mut rail = Track {
+        data: TrackData {
+            ttype: TrackType::Rail(RailKind::Pwr),
+            index: 0,
+            dir: raw::Dir::Horiz,
+            start: DbUnits(0),
+            width: DbUnits(480),
+            color: None,
+        },
+        segments: vec![TrackSegment {
+            tp: TrackSegmentType::Rail(RailKind::Pwr),
+            start: DbUnits(0),
+            stop: DbUnits(1000),
+            labels: Vec::new(),
+        }],
+    };
+    let assn = Assign::new(
+        "a",
+        TrackCross::from_relz(LayerIndex(0), TrackIndex(0), TrackIndex(0), RelZ::Above),
+    );
+    let err = rail.jog(DbUnits(500), &assn).unwrap_err();
+    assert!(matches!(
+        err,
+        TrackError::Conflict(_, TrackConflict::Rail(RailKind::Pwr))
+    ));
+    Ok(())
+}
+/// Close the boundary rails of an empty cell into a ring
+#[test]
+fn rail_rings() -> LayoutResult<()> {
+    use crate::conv::raw::RawExporter;
+    use crate::raw;
+
+    // Convert to raw, and return the rects on each of met1, met2, and via1, as (net, rect) pairs
+    type Rects = Vec<(Option<String>, raw::Rect)>;
+    let convert = |lib: Library| -> LayoutResult<[Rects; 3]> {
+        let stack = SampleStacks::pdka()?;
+        let keys = [
+            stack.metal(LayerIndex(0))?.raw.unwrap(),
+            stack.metal(LayerIndex(1))?.raw.unwrap(),
+            stack.via(1)?.raw.unwrap(),
+        ];
+        let rawlib = RawExporter::convert(lib, stack)?;
+        let rawlib = rawlib.read()?;
+        let rawcell = rawlib.cells.last().unwrap().read()?;
+        let layout = rawcell.layout.as_ref().unwrap();
+        let rects = |key: raw::LayerKey| -> Rects {
+            layout
+                .elems
+                .iter()
+                .filter(|e| e.layer == key)
+                .filter_map(|e| match e.inner {
+                    raw::Shape::Rect(ref r) => Some((e.net.clone(), r.clone())),
+                    _ => None,
+                })
+                .collect()
+        };
+        Ok(keys.map(rects))
+    };
+    let ringed = |x: isize, y: isize| -> LayoutResult<Library> {
+        let mut lib = Library::new("RingLib");
+        lib.cells.insert(
+            Cell::builder("Ringed")
+                .top_layer(LayerIndex(1))
+                .outline(Outline::rect(x, y)?)
+                .rail_rings(true)
+                .build()?,
+        );
+        Ok(lib)
+    };
+    let rect = |x0, y0, x1, y1| raw::Rect {
+        p0: raw::Point::new(x0, y0),
+        p1: raw::Point::new(x1, y1),
+    };
+    let vss = Some("VSS".to_string());
+
+    // Four periods tall, with VSS rails along both the top and bottom edges
+    let [met1, met2, via1] = convert(ringed(5, 4)?)?;
+    let (w, h) = (5 * 460, 4 * 2720);
+    assert!(met1.contains(&(vss.clone(), rect(-240, -240, w + 240, 240))));
+    assert!(met1.contains(&(vss.clone(), rect(-240, h - 240, w + 240, h + 240))));
+    // Two straps on met2, along the left and right edges, are its only VSS geometry
+    let straps: Vec<_> = met2.iter().filter(|(net, _)| *net == vss).collect();
+    assert_eq!(straps.len(), 2);
+    assert!(straps.contains(&&(vss.clone(), rect(-240, -240, 240, h + 240))));
+    assert!(straps.contains(&&(vss.clone(), rect(w - 240, -240, w + 240, h + 240))));
+    // And one via at each corner
+    assert_eq!(via1.len(), 4);
+    for (x, y) in [(0, 0), (w, 0), (0, h), (w, h)] {
+        assert!(via1.contains(&(vss.clone(), rect(x - 120, y - 120, x + 120, y + 120))));
+    }
+
+    // Five periods tall, the top and bottom rails are VDD and VSS, and cannot be joined
+    assert!(convert(ringed(5, 5)?).is_err());
+
+    // Instances in the ring's path block it
+    let mut lib = ringed(5, 4)?;
+    let leaf = lib
+        .cells
+        .insert(Layout::new("Leaf", 1, Outline::rect(1, 1)?));
+    let blocked = Cell::builder("Blocked")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(5, 4)?)
+        .instance(
+            Instance::builder()
+                .inst_name("corner")
+                .cell(leaf)
+                .loc((0, 0))
+                .build()?,
+        )
+        .rail_rings(true)
+        .build()?;
+    lib.cells.insert(blocked);
+    assert!(convert(lib).is_err());
+    Ok(())
+}
+/// Convert a large, heavily cut and assigned cell, with and without period-caching
+#[test]
+fn large_cell_conversion() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+
+    let create = || -> LayoutResult<Library> {
+        let stack = SampleStacks::pdka()?;
+        let outline = Outline::rect(400, 42)?;
+        let nx = stack.signal_tracks(LayerIndex(1), &outline)?;
+        let ny = stack.signal_tracks(LayerIndex(2), &outline)?;
+        let mut cell = Cell::builder("Large")
+            .top_layer(LayerIndex(3))
+            .outline(outline)
+            .build()?;
+        let layout = cell.layout.as_mut().unwrap();
+        // Alternate cuts and assignments along every horizontal track
+        for track in 0..ny {
+            for at in (0..nx - 1).step_by(2) {
+                layout.cut(
+                    LayerIndex(2),
+                    TrackIndex(track),
+                    TrackIndex(at),
+                    RelZ::Below,
+                );
+                layout.assign(
+                    format!("net{}", track % 8),
+                    LayerIndex(2),
+                    TrackIndex(track),
+                    TrackIndex(at + 1),
+                    RelZ::Below,
+                );
+            }
+        }
+        let mut lib = Library::new("LargeLib");
+        lib.cells.insert(cell);
+        Ok(lib)
+    };
+    let opts = RawExportOptions {
+        route_db: true,
+        ..Default::default()
+    };
+    let (rawlib, report) =
+        RawExporter::convert_with_options(create()?, SampleStacks::pdka()?, &opts)?;
+    let segments: usize = report.routes[0]
+        .layers
+        .iter()
+        .flat_map(|l| l.periods.iter())
+        .flat_map(|p| p.signals.iter().chain(p.rails.iter()))
+        .map(|t| t.segments.len())
+        .sum();
+    assert!(segments > 100_000);
+
+    let opts = RawExportOptions {
+        cache_periods: false,
+        ..Default::default()
+    };
+    let (uncached, _) = RawExporter::convert_with_options(create()?, SampleStacks::pdka()?, &opts)?;
+    assert_eq!(*rawlib.read()?, *uncached.read()?);
+    Ok(())
+}
+/// Name vias after their assignments' nets via a [NetInterner], matching the un-interned nets of the route database
+#[test]
+fn interned_via_nets() -> LayoutResult<()> {
+    use crate::conv::raw::{NetInterner, RawExportOptions, RawExporter};
+
+    let mut nets = NetInterner::new();
+    assert!(nets.is_empty());
+    let a = nets.intern("a");
+    let b = nets.intern("b");
+    assert_ne!(a, b);
+    assert_eq!(nets.intern("a"), a);
+    assert_eq!(nets.len(), 2);
+    assert_eq!(nets.get("b"), Some(b));
+    assert_eq!(nets.get("c"), None);
+    assert_eq!(nets.name(a), Some("a"));
+
+    let stack = SampleStacks::pdka()?;
+    let outline = Outline::rect(40, 6)?;
+    let nx = stack.signal_tracks(LayerIndex(1), &outline)?;
+    let ny = stack.signal_tracks(LayerIndex(2), &outline)?;
+    let mut cell = Cell::builder("Assigned")
+        .top_layer(LayerIndex(3))
+        .outline(outline)
+        .build()?;
+    let layout = cell.layout.as_mut().unwrap();
+    for track in 0..ny {
+        for at in (0..nx).step_by(3) {
+            layout.assign(
+                format!("net{}", (track + at) % 5),
+                LayerIndex(2),
+                TrackIndex(track),
+                TrackIndex(at),
+                RelZ::Below,
+            );
+        }
+    }
+    let mut lib = Library::new("AssignedLib");
+    lib.cells.insert(cell);
+    let opts = RawExportOptions {
+        route_db: true,
+        ..Default::default()
+    };
+    let via_layer = stack.via_from(LayerIndex(1))?.raw;
+    let (rawlib, report) = RawExporter::convert_with_options(lib, stack, &opts)?;
+    // The route database records each via's source [Assign], and its net name, as assigned
+    let expected: Vec<Option<String>> = report.routes[0].layers[1]
+        .vias
+        .iter()
+        .map(|assn| Some(assn.net.clone()))
+        .collect();
+    assert!(expected.len() > 10);
+    let rawlib = rawlib.read()?;
+    let rawcell = rawlib.cells[0].read()?;
+    let layout = rawcell.layout.as_ref().unwrap();
+    let nets: Vec<Option<String>> = layout
+        .elems
+        .iter()
+        .filter(|e| Some(e.layer) == via_layer)
+        .map(|e| e.net.clone())
+        .collect();
+    assert_eq!(nets, expected);
+    Ok(())
+}
+/// Compare outlines by shape, and reflect them
+#[test]
+fn outline_congruence() -> LayoutResult<()> {
+    let ell = Outline::new(&[4, 2], &[1, 3])?;
+    // Redundant steps leave the shape unchanged, though not equal
+    let redundant = Outline::new(&[4, 4, 2], &[1, 1, 3])?;
+    assert_ne!(ell, redundant);
+    assert!(ell.congruent(&redundant));
+    // Same-area, different-shape outlines are not congruent
+    let other = Outline::new(&[6, 1], &[1, 3])?;
+    assert_eq!(ell.area(), other.area());
+    assert!(!ell.congruent(&other));
+    assert!(!ell.congruent(&Outline::rect(2, 5)?));
+
+    // L-shapes have no valid reflection, while rectangles reflect onto themselves
+    assert!(ell.mirrored().is_err());
+    let rect = Outline::new(&[4, 4], &[1, 3])?;
+    assert_eq!(rect.mirrored()?, Outline::rect(4, 3)?);
+    assert!(rect.mirrored()?.congruent(&rect));
+
+    // Footprint compatibility between cells follows, and mismatched views fail validation
+    let cell = |outline: &Outline| Cell::from(Layout::new("c", 1, outline.clone()));
+    assert!(cell(&ell).footprint_compatible(&cell(&redundant))?);
+    assert!(!cell(&ell).footprint_compatible(&cell(&other))?);
+    assert!(cell(&rect).footprint_compatible(&cell(&Outline::rect(4, 3)?))?);
+    let mut mismatched = cell(&ell);
+    mismatched.abs = Some(abs::Abstract::new("c", 1, other));
+    let mut lib = Library::new("outline_congruence");
+    lib.cells.insert(mismatched);
+    let err = crate::validate::validate_lib(&lib, &SampleStacks::pdka()?).unwrap_err();
+    assert!(err.to_string().contains("Outline mismatch"));
+    Ok(())
+}
+/// Check [Outline::points] vertex lists, closure, and area
+#[test]
+fn outline_points() -> LayoutResult<()> {
+    use crate::coords::Int;
+
+    let raw = |o: &Outline, closed: bool| -> Vec<(Int, Int)> {
+        o.points(closed)
+            .iter()
+            .map(|p| (p.x.num, p.y.num))
+            .collect()
+    };
+    // Rectangular
+    let rect = Outline::rect(4, 3)?;
+    assert_eq!(raw(&rect, false), vec![(0, 0), (4, 0), (4, 3), (0, 3)]);
+    assert_eq!(rect.area(), 12);
+    // L-shaped, counter-clockwise from the origin
+    let ell = Outline::new(&[4, 2], &[3, 6])?;
+    let expected = vec![(0, 0), (4, 0), (4, 3), (2, 3), (2, 6), (0, 6)];
+    assert_eq!(raw(&ell, false), expected);
+    assert_eq!(ell.area(), 18);
+    // Closure repeats the origin
+    let closed = raw(&ell, true);
+    assert_eq!(closed[..6], expected[..]);
+    assert_eq!(closed[6], (0, 0));
+    // Three steps
+    let stair = Outline::new(&[6, 4, 1], &[1, 2, 5])?;
+    assert_eq!(
+        raw(&stair, false),
+        vec![
+            (0, 0),
+            (6, 0),
+            (6, 1),
+            (4, 1),
+            (4, 2),
+            (1, 2),
+            (1, 5),
+            (0, 5)
         ]
-        .into(),
-        assignments: Vec::new(),
-        cuts: Vec::new(),
-        places: Vec::new(),
-    });
-    exports(lib, SampleStacks::pdka()?)
+    );
+    assert_eq!(stair.area(), 6 + 4 + 3);
+    // Repeated x or y values produce no repeated or colinear vertices
+    let flat = Outline::new(&[4, 4], &[3, 6])?;
+    assert_eq!(raw(&flat, false), vec![(0, 0), (4, 0), (4, 6), (0, 6)]);
+    let flat = Outline::new(&[4, 2], &[3, 3])?;
+    assert_eq!(raw(&flat, false), vec![(0, 0), (4, 0), (4, 3), (0, 3)]);
+    let flat = Outline::new(&[4, 2], &[0, 6])?;
+    assert_eq!(raw(&flat, false), vec![(0, 0), (2, 0), (2, 6), (0, 6)]);
+
+    // Exhaustively check every 1-, 2-, and 3-step outline with coordinates up to 3
+    let shoelace = |pts: &[(Int, Int)]| -> Int {
+        let n = pts.len();
+        (0..n)
+            .map(|i| pts[i].0 * pts[(i + 1) % n].1 - pts[(i + 1) % n].0 * pts[i].1)
+            .sum()
+    };
+    let mut outlines = Vec::new();
+    let vals: Vec<Int> = (1..=3).collect();
+    for &x0 in vals.iter() {
+        for &y0 in vals.iter() {
+            outlines.push((vec![x0], vec![y0]));
+            for &x1 in vals.iter().filter(|x| **x <= x0) {
+                for &y1 in vals.iter().filter(|y| **y >= y0) {
+                    outlines.push((vec![x0, x1], vec![y0, y1]));
+                    for &x2 in vals.iter().filter(|x| **x <= x1) {
+                        for &y2 in vals.iter().filter(|y| **y >= y1) {
+                            outlines.push((vec![x0, x1, x2], vec![y0, y1, y2]));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for (x, y) in outlines.iter() {
+        let outline = Outline::new(x, y)?;
+        let pts = raw(&outline, false);
+        // Starts at the origin, and closes by repeating it
+        assert_eq!(pts[0], (0, 0));
+        let closed = raw(&outline, true);
+        assert_eq!(closed.len(), pts.len() + 1);
+        assert_eq!(closed.last(), Some(&(0, 0)));
+        // Counter-clockwise, with area equal to that of its stacked rectangles
+        assert_eq!(shoelace(&pts), 2 * outline.area());
+        assert!(outline.area() > 0);
+        // Rectilinear, with every vertex a true corner
+        let n = pts.len();
+        assert!(n >= 4 && n % 2 == 0);
+        for i in 0..n {
+            let (a, b, c) = (pts[i], pts[(i + 1) % n], pts[(i + 2) % n]);
+            assert!(a.0 == b.0 || a.1 == b.1);
+            assert!(!((a.0 == b.0 && b.0 == c.0) || (a.1 == b.1 && b.1 == c.1)));
+        }
+    }
+    Ok(())
 }
-/// Helper function. Export [Library] `lib` in several formats.
-pub fn exports(lib: Library, stack: ValidStack) -> LayoutResult<()> {
-    // Serializable formats will generally be written as YAML.
-    use crate::utils::SerializationFormat::Yaml;
+/// Resize cell outlines, and check for the resulting inconsistencies
+#[test]
+fn outline_consistency() -> LayoutResult<()> {
+    use crate::library::Violation;
 
-    let rawlib = conv::raw::RawExporter::convert(lib, stack)?;
+    let stack = SampleStacks::pdka()?;
+    let outline = Outline::rect(20, 4)?;
+    let small = Outline::rect(10, 4)?;
+    // Assign and cut on the last signal tracks of the shrunken outline, and just beyond them
+    let last = stack.signal_tracks(LayerIndex(1), &small)? - 1;
+    let mut child = Cell::builder("child")
+        .top_layer(LayerIndex(1))
+        .outline(outline.clone())
+        .assign(
+            "a",
+            LayerIndex(1),
+            TrackIndex(last),
+            TrackIndex(1),
+            RelZ::Below,
+        )
+        .assign(
+            "b",
+            LayerIndex(1),
+            TrackIndex(last + 1),
+            TrackIndex(1),
+            RelZ::Below,
+        )
+        .cut(
+            LayerIndex(1),
+            TrackIndex(last + 1),
+            TrackIndex(2),
+            RelZ::Below,
+        )
+        .build()?;
+    child.abs = Some(abs::Abstract::new("child", 2, outline.clone()));
+    let child = Ptr::new(child);
+
+    let mut lib = Library::new("outline_consistency");
+    lib.cells.push(child.clone());
+    let mut parent = Cell::builder("parent")
+        .top_layer(LayerIndex(2))
+        .outline(Outline::rect(40, 4)?);
+    for (name, x) in [("i0", 0), ("i1", 20)] {
+        let inst = Instance::builder()
+            .inst_name(name)
+            .cell(child.clone())
+            .loc((x, 0))
+            .build()?;
+        parent = parent.instance(inst);
+    }
+    let parent = lib.cells.insert(parent.build()?);
+    assert!(lib.check_outline_consistency()?.is_empty());
+
+    // Shrink the child's layout. Its abstract is now stale.
+    let delta = child.write()?.resize_outline(&stack, small.clone())?;
+    assert_eq!(delta.old, outline);
+    assert_eq!(delta.new, small);
+    assert!(!delta.is_clean());
+    let nets: Vec<&str> = delta.assignments.iter().map(|a| a.net.as_str()).collect();
+    assert_eq!(nets, vec!["b"]);
+    assert_eq!(
+        delta.cuts,
+        vec![TrackCross::from_relz(
+            LayerIndex(1),
+            TrackIndex(last + 1),
+            TrackIndex(2),
+            RelZ::Below
+        )]
+    );
+    let violations = lib.check_outline_consistency()?;
+    assert_eq!(
+        violations.0,
+        vec![Violation::AbstractOutline {
+            cell: "child".into(),
+            abs: outline.clone(),
+            layout: small.clone(),
+        }]
+    );
+    // Update the abstract to match; its parent's placements remain valid
+    child.write()?.abs.as_mut().unwrap().outline = small.clone();
+    assert!(lib.check_outline_consistency()?.is_empty());
+
+    // Grow the child, and shrink the parent. Both placements are now stale.
+    child.write()?.abs.as_mut().unwrap().outline = Outline::rect(30, 4)?;
+    parent
+        .write()?
+        .resize_outline(&stack, Outline::rect(30, 4)?)?;
+    let violations = lib.check_outline_consistency()?;
+    assert!(violations.contains(&Violation::OutsideParent {
+        cell: "parent".into(),
+        inst: "i1".into(),
+    }));
+    assert!(violations.contains(&Violation::Overlap {
+        cell: "parent".into(),
+        insts: ("i0".into(), "i1".into()),
+    }));
+    // Cells without layouts cannot be resized
+    assert!(Cell::new("empty").resize_outline(&stack, small).is_err());
+    Ok(())
+}
+/// Check the overflow-checked paths of [DbUnits] and [PrimPitches] conversions
+#[test]
+fn checked_coords() -> LayoutResult<()> {
+    use crate::coords::{DbUnits, Int, PrimPitches, Xy};
+    use crate::raw::Dir;
+    use std::convert::TryFrom;
+
+    let pitches = Xy::new(DbUnits(460), DbUnits(2720));
+    // In-range conversions match plain multiplication
+    let p = PrimPitches::try_new(Dir::Vert, 3)?;
+    assert_eq!(p.to_db_units(&pitches)?, DbUnits(3 * 2720));
+    assert_eq!(p.to_string(), "3");
+    assert_eq!(DbUnits(-5).to_string(), "-5");
+    let max = Int::MAX / 460;
+    assert_eq!(
+        PrimPitches::x(max).to_db_units(&pitches)?,
+        DbUnits(max * 460)
+    );
+    // And those just beyond the coordinate range fail, rather than wrapping
+    let err = PrimPitches::x(max + 1).to_db_units(&pitches).unwrap_err();
+    assert!(err.to_string().contains("overflows"));
+    assert!(PrimPitches::y(Int::MIN).to_db_units(&pitches).is_err());
+    assert!(PrimPitches::try_new(Dir::Horiz, usize::MAX).is_err());
+    assert!(PrimPitches::try_new(Dir::Horiz, Int::MAX as usize).is_ok());
+
+    // Checked arithmetic on [DbUnits]
+    let big = DbUnits(Int::MAX - 1);
+    assert_eq!(big.checked_add(DbUnits(1)), Some(DbUnits(Int::MAX)));
+    assert_eq!(big.checked_add(DbUnits(2)), None);
+    assert_eq!(DbUnits(Int::MIN).checked_sub(DbUnits(1)), None);
+    assert_eq!(DbUnits(Int::MAX / 2).checked_mul(3), None);
+    assert_eq!(DbUnits(7).checked_mul(-2), Some(DbUnits(-14)));
+
+    // And conversions to and from primitive integers
+    assert_eq!(DbUnits::try_from(12usize)?, DbUnits(12));
+    assert!(DbUnits::try_from(usize::MAX).is_err());
+    assert_eq!(i32::try_from(DbUnits(i32::MAX as Int))?, i32::MAX);
+    assert!(i32::try_from(DbUnits(i32::MAX as Int + 1)).is_err());
+    assert!(i32::try_from(DbUnits(i32::MIN as Int - 1)).is_err());
+    Ok(())
+}
+/// Compose cells by abutting them into a new cell
+#[test]
+fn compose() -> LayoutResult<()> {
+    use crate::coords::Xy;
+    use crate::library::CellId;
+    use crate::raw::Dir;
+
+    let mut lib = Library::new("compose");
+    let add =
+        |lib: &mut Library, name: &str, top: usize, outline: Outline| -> LayoutResult<CellId> {
+            let cell = Cell::builder(name)
+                .top_layer(LayerIndex(top))
+                .outline(outline)
+                .build()?;
+            lib.cells.insert(cell);
+            Ok(CellId(lib.cells.len() - 1))
+        };
+    let a = add(&mut lib, "a", 1, Outline::rect(5, 3)?)?;
+    let b = add(&mut lib, "b", 2, Outline::rect(5, 4)?)?;
+
+    // Stack `a` and `b` vertically into a 5x7 cell
+    let ab = lib.compose("ab", &[a, b], Dir::Vert)?;
+    {
+        let cell = lib.cell(ab).unwrap().read()?;
+        assert_eq!(cell.name, "ab");
+        assert_eq!(*cell.outline()?, Outline::rect(5, 7)?);
+        assert_eq!(cell.metals()?, 3);
+        let layout = cell.layout.as_ref().unwrap();
+        let mut places = Vec::new();
+        for inst in layout.instances.iter() {
+            let inst = inst.read()?;
+            places.push((inst.inst_name.clone(), inst.loc.abs()?.raw()));
+        }
+        assert_eq!(
+            places,
+            vec![
+                ("a_0".to_string(), Xy::new(0, 0)),
+                ("b_1".to_string(), Xy::new(0, 3)),
+            ]
+        );
+    }
+    assert!(lib.check_outline_consistency()?.is_empty());
+    // The composite exports like any other cell
+    lib.clone().to_raw(SampleStacks::pdka()?)?;
+
+    // A non-rectangular last part sets the steps of the composite outline
+    let ell = add(&mut lib, "ell", 1, Outline::new(&[5, 2], &[1, 2])?)?;
+    let ae = lib.compose("ae", &[a, ell], Dir::Vert)?;
+    let expected = Outline::new(&[5, 5, 2], &[3, 4, 5])?;
+    assert_eq!(*lib.cell(ae).unwrap().read()?.outline()?, expected);
+
+    // Mismatched cross-sizes fail, reporting both, as do non-rectangular parts before the last
+    let wide = add(&mut lib, "wide", 1, Outline::rect(6, 4)?)?;
+    let err = lib.compose("aw", &[a, wide], Dir::Vert).unwrap_err();
+    assert!(err.to_string().contains("size 6"));
+    assert!(err.to_string().contains("first part's 5"));
+    let err = lib.compose("ab_h", &[a, b], Dir::Horiz).unwrap_err();
+    assert!(err.to_string().contains("size 4"));
+    assert!(lib.compose("ea", &[ell, a], Dir::Vert).is_err());
+    assert!(lib.compose("none", &[], Dir::Vert).is_err());
+    assert!(lib.compose("missing", &[CellId(99)], Dir::Vert).is_err());
+    Ok(())
+}
+/// Reference cells in sub-libraries, and export them with flattened names
+#[cfg(feature = "gds")]
+#[test]
+fn sub_libraries() -> LayoutResult<()> {
+    use crate::library::CellRef;
+
+    // Two sub-libraries, each with a cell named `inv`, one nested within the other
+    let mut cells = Library::new("cells");
+    cells.add_cell(Layout::new("inv", 1, Outline::rect(10, 1)?).into());
+    cells.add_cell(Layout::new("buf", 1, Outline::rect(10, 1)?).into());
+    let mut more = Library::new("more");
+    more.add_cell(Layout::new("inv", 1, Outline::rect(20, 1)?).into());
+    cells.add_lib(more);
+    let mut lib = Library::new("sub_libraries");
+    lib.add_lib(cells);
+
+    // Resolve references
+    let inv = lib.resolve_ref(&CellRef::qualified(&["cells"], "inv"))?;
+    assert_eq!(inv.read()?.outline()?, &Outline::rect(10, 1)?);
+    let inv2 = lib.resolve_ref(&CellRef::qualified(&["cells", "more"], "inv"))?;
+    assert_eq!(inv2.read()?.outline()?, &Outline::rect(20, 1)?);
+    let buf = lib.resolve_ref(&CellRef::name("buf"))?;
+    assert_eq!(
+        lib.resolve_refs(&[CellRef::qualified(&["cells"], "buf")])?,
+        vec![buf.clone()]
+    );
+    assert!(lib
+        .resolve_ref(&CellRef::qualified(&["more"], "inv"))
+        .is_err());
+    assert!(lib.resolve_ref(&CellRef::name("nand")).is_err());
+    // Unqualified `inv` is ambiguous, and lists both candidates
+    let err = lib.resolve_ref(&CellRef::name("inv")).unwrap_err();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("cells/inv") && msg.contains("cells/more/inv"));
+
+    // Instantiate each in a parent cell
+    let mut parent = Cell::builder("parent")
+        .top_layer(LayerIndex(2))
+        .outline(Outline::rect(40, 4)?);
+    for (name, cell, y) in [("i0", inv, 0), ("i1", inv2, 1), ("i2", buf, 2)] {
+        let inst = Instance::builder()
+            .inst_name(name)
+            .cell(cell)
+            .loc((0, y))
+            .build()?;
+        parent = parent.instance(inst);
+    }
+    lib.add_cell(parent.build()?);
+    // Unqualified names in our own `cells` take priority over sub-libraries
+    lib.add_cell(Layout::new("inv", 1, Outline::rect(30, 1)?).into());
+    let inv3 = lib.resolve_ref(&CellRef::name("inv"))?;
+    assert_eq!(inv3.read()?.outline()?, &Outline::rect(30, 1)?);
+
+    let rawlib = lib.clone().to_raw(SampleStacks::pdka()?)?;
     let rawlib = rawlib.read()?;
+    let mut names = Vec::new();
+    for cell in rawlib.cells.iter() {
+        names.push(cell.read()?.name.clone());
+    }
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "cells__buf",
+            "cells__inv",
+            "cells__more__inv",
+            "inv",
+            "parent"
+        ]
+    );
+    // With unique names, GDS export succeeds
+    let gds = rawlib.to_gds()?;
+    assert_eq!(gds.structs.len(), 5);
 
-    // Export to ProtoBuf, save as YAML and binary
-    let protolib = rawlib.to_proto()?;
-    Yaml.save(
-        &protolib,
-        &resource(&format!("{}.proto.yaml", &protolib.domain)),
-    )
-    .unwrap();
-    crate::raw::proto::proto::save(
-        &protolib,
-        &resource(&format!("{}.proto.bin", &protolib.domain)),
+    // Missing cells along qualified paths are not found, while other errors are reported as-is,
+    // e.g. those of a lock poisoned by a panic
+    let path = ["cells", "more"];
+    let err = lib
+        .resolve_ref(&CellRef::qualified(&path, "nand"))
+        .unwrap_err();
+    assert!(err.to_string().contains("not found"));
+    let nested = lib.resolve_ref(&CellRef::qualified(&path, "inv"))?;
+    let _ = std::thread::spawn(move || {
+        let _guard = nested.write().unwrap();
+        panic!("poisoning the lock");
+    })
+    .join();
+    let err = lib
+        .resolve_ref(&CellRef::qualified(&path, "inv"))
+        .unwrap_err();
+    assert!(matches!(err, crate::raw::LayoutError::PtrLock));
+    Ok(())
+}
+/// Instantiate vias from generated cells
+#[cfg(feature = "gds")]
+#[test]
+fn via_cells() -> LayoutResult<()> {
+    use crate::conv::raw::RawExportOptions;
+    use crate::conv::vias::ViaSpec;
+    use crate::coords::DbUnits;
+
+    let create = || -> LayoutResult<Library> {
+        let mut lib = Library::new("via_cells");
+        lib.add_cell(
+            Cell::builder("vias")
+                .top_layer(LayerIndex(2))
+                .outline(Outline::rect(20, 4)?)
+                .assign(
+                    "a",
+                    LayerIndex(1),
+                    TrackIndex(1),
+                    TrackIndex(1),
+                    RelZ::Below,
+                )
+                .assign(
+                    "b",
+                    LayerIndex(1),
+                    TrackIndex(3),
+                    TrackIndex(3),
+                    RelZ::Below,
+                )
+                .assign(
+                    "c",
+                    LayerIndex(1),
+                    TrackIndex(5),
+                    TrackIndex(5),
+                    RelZ::Below,
+                )
+                .assign(
+                    "d",
+                    LayerIndex(2),
+                    TrackIndex(2),
+                    TrackIndex(4),
+                    RelZ::Below,
+                )
+                .build()?,
+        );
+        Ok(lib)
+    };
+    let spec = ViaSpec {
+        enclosure: DbUnits(5),
+        ..Default::default()
+    };
+    let opts = RawExportOptions {
+        via_cells: Some(spec),
+        ..Default::default()
+    };
+    let (rawlib, _) = create()?.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let rawlib = rawlib.read()?;
+    let names: Vec<String> = rawlib
+        .cells
+        .iter()
+        .map(|c| c.read().unwrap().name.clone())
+        .collect();
+    assert_eq!(names, vec!["via1_1x1_enc5", "via2_1x1_enc5", "vias"]);
+
+    // The three `via1` placements share a single cell
+    let via1 = rawlib.cells[0].clone();
+    let cell = rawlib.cells[2].read()?;
+    let layout = cell.layout.as_ref().unwrap();
+    assert_eq!(layout.insts.len(), 4);
+    let shared = layout.insts.iter().filter(|i| i.cell == via1).count();
+    assert_eq!(shared, 3);
+
+    // Check the cut and enclosure dimensions
+    let via1 = via1.read()?;
+    let elems = &via1.layout.as_ref().unwrap().elems;
+    assert_eq!(elems.len(), 3);
+    let sizes: Vec<(isize, isize)> = elems
+        .iter()
+        .map(|e| match e.inner {
+            crate::raw::Shape::Rect(ref r) => (r.p1.x - r.p0.x, r.p1.y - r.p0.y),
+            _ => panic!("Invalid via shape"),
+        })
+        .collect();
+    assert_eq!(sizes, vec![(240, 240), (250, 250), (250, 250)]);
+    drop(via1);
+    drop(cell);
+    assert_eq!(rawlib.to_gds()?.structs.len(), 3);
+
+    // Inline conversion instead draws one cut-rectangle per via, and no instances
+    let rawlib = create()?.to_raw(SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    assert_eq!(rawlib.cells.len(), 1);
+    let cell = rawlib.cells[0].read()?;
+    assert_eq!(cell.layout.as_ref().unwrap().insts.len(), 0);
+    drop(cell);
+    drop(rawlib);
+
+    // Via instances are named around any of the cell's own instances
+    let mut lib = Library::new("via_names");
+    let leaf = lib
+        .cells
+        .insert(Layout::new("leaf", 1, Outline::rect(1, 1)?));
+    let mut cell = Cell::builder("vias")
+        .top_layer(LayerIndex(2))
+        .outline(Outline::rect(20, 4)?)
+        .assign(
+            "a",
+            LayerIndex(1),
+            TrackIndex(1),
+            TrackIndex(1),
+            RelZ::Below,
+        )
+        .assign(
+            "b",
+            LayerIndex(1),
+            TrackIndex(3),
+            TrackIndex(3),
+            RelZ::Below,
+        );
+    for (name, x) in [("via0", 10), ("via2", 12)] {
+        let inst = Instance::builder()
+            .inst_name(name)
+            .cell(leaf.clone())
+            .loc((x, 0))
+            .build()?;
+        cell = cell.instance(inst);
+    }
+    lib.add_cell(cell.build()?);
+    let (rawlib, _) = lib.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let cell = rawlib.read()?.cell_named("vias")?.unwrap();
+    let mut names: Vec<String> = cell
+        .read()?
+        .layout
+        .as_ref()
+        .unwrap()
+        .insts
+        .iter()
+        .map(|i| i.inst_name.clone())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["via0", "via1", "via2", "via3"]);
+
+    // Generated via cells conflicting with existing cells fail
+    let mut lib = create()?;
+    lib.add_cell(
+        Cell::builder("via1_1x1_enc5")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .build()?,
+    );
+    let err = lib
+        .to_raw_with_options(SampleStacks::pdka()?, &opts)
+        .unwrap_err();
+    assert!(err.to_string().contains("via1_1x1_enc5"));
+    Ok(())
+}
+/// Suppress or select the via of individual assignments, per [ViaPolicy]
+#[test]
+fn via_policy() -> LayoutResult<()> {
+    use crate::conv::raw::RawExportOptions;
+    use crate::conv::vias::ViaSpec;
+    use crate::coords::{DbUnits, Xy};
+    use std::collections::BTreeMap;
+
+    let create = |c: ViaPolicy| -> LayoutResult<Library> {
+        let mut lib = Library::new("via_policy");
+        let assn = |net: &str, track: usize, via: ViaPolicy| {
+            Assign::new(
+                net,
+                TrackCross::from_relz(
+                    LayerIndex(1),
+                    TrackIndex(track),
+                    TrackIndex(track),
+                    RelZ::Below,
+                ),
+            )
+            .via(via)
+        };
+        lib.add_cell(
+            Cell::builder("vias")
+                .top_layer(LayerIndex(2))
+                .outline(Outline::rect(20, 4)?)
+                .assignment(assn("a", 1, ViaPolicy::Auto))
+                .assignment(assn("b", 3, ViaPolicy::None))
+                .assignment(assn("c", 5, c))
+                .build()?,
+        );
+        Ok(lib)
+    };
+    let stack = SampleStacks::pdka()?;
+    let via1 = stack.via_from(LayerIndex(0))?.raw.unwrap();
+    let metals = [
+        stack.metal(LayerIndex(0))?.raw.unwrap(),
+        stack.metal(LayerIndex(1))?.raw.unwrap(),
+    ];
+
+    // Suppression: no cut for `b`, while its net still labels both its tracks
+    let rawlib = create(ViaPolicy::Auto)?.to_raw(SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let cell = rawlib.cells[0].read()?;
+    let elems = &cell.layout.as_ref().unwrap().elems;
+    let cut_nets: Vec<&str> = elems
+        .iter()
+        .filter(|e| e.layer == via1)
+        .map(|e| e.net.as_deref().unwrap())
+        .collect();
+    assert_eq!(cut_nets, vec!["a", "c"]);
+    for metal in metals {
+        assert!(elems
+            .iter()
+            .any(|e| e.layer == metal && e.net.as_deref() == Some("b")));
+    }
+    drop(cell);
+    drop(rawlib);
+
+    // Variant selection: `c` instantiates the generated cell for its named variant,
+    // while `a` is drawn inline, as via-cells are otherwise disabled
+    let big = ViaSpec {
+        cuts: Xy::new(2, 2),
+        space: DbUnits(10),
+        enclosure: DbUnits(5),
+    };
+    let opts = RawExportOptions {
+        via_variants: BTreeMap::from([("big".to_string(), big)]),
+        ..Default::default()
+    };
+    let variant = ViaPolicy::Variant("big".into());
+    let (rawlib, _) = create(variant)?.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let rawlib = rawlib.read()?;
+    let names: Vec<String> = rawlib
+        .cells
+        .iter()
+        .map(|c| c.read().unwrap().name.clone())
+        .collect();
+    assert_eq!(names, vec!["via1_2x2_sp10_enc5", "vias"]);
+    let cell = rawlib.cells[1].read()?;
+    let layout = cell.layout.as_ref().unwrap();
+    assert_eq!(layout.insts.len(), 1);
+    assert_eq!(layout.insts[0].cell, rawlib.cells[0]);
+    let cuts = layout.elems.iter().filter(|e| e.layer == via1).count();
+    assert_eq!(cuts, 1);
+    let gen = rawlib.cells[0].read()?;
+    let gen_cuts = gen.layout.as_ref().unwrap().elems.iter();
+    assert_eq!(gen_cuts.filter(|e| e.layer == via1).count(), 4);
+    drop(gen);
+    drop(cell);
+    drop(rawlib);
+
+    // Unknown variants fail
+    let unknown = ViaPolicy::Variant("huge".into());
+    assert!(create(unknown)?
+        .to_raw_with_options(SampleStacks::pdka()?, &opts)
+        .is_err());
+
+    // Assignments serialized without a via-policy load as `Auto`
+    #[cfg(feature = "yaml")]
+    {
+        let yaml = "net: a\nat:\n  track: {layer: 1, track: 1}\n  cross: {layer: 0, track: 1}\n";
+        let assn: Assign = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(assn.via, ViaPolicy::Auto);
+    }
+    Ok(())
+}
+/// Place vias directly, independent of assignments
+#[test]
+fn via_placements() -> LayoutResult<()> {
+    use crate::coords::{DbUnits, Xy};
+    use crate::raw;
+
+    let convert = |vias: Vec<ViaPlacement>, cuts: Vec<TrackCross>| {
+        let mut lib = Library::new("via_placements");
+        let mut cell = Cell::builder("vias")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(20, 4)?)
+            .assign(
+                "a",
+                LayerIndex(1),
+                TrackIndex(1),
+                TrackIndex(1),
+                RelZ::Below,
+            )
+            .assign(
+                "b",
+                LayerIndex(1),
+                TrackIndex(5),
+                TrackIndex(5),
+                RelZ::Below,
+            );
+        for via in vias {
+            cell = cell.via(via);
+        }
+        let mut cell = cell.build()?;
+        cell.layout.as_mut().unwrap().cuts.extend(cuts);
+        lib.cells.insert(cell);
+        lib.to_raw(SampleStacks::pdka()?)
+    };
+    let stack = SampleStacks::pdka()?;
+    let via1 = stack.via_from(LayerIndex(0))?.raw.unwrap();
+    let cuts = |rawlib: &Ptr<raw::Library>| -> LayoutResult<Vec<raw::Element>> {
+        let rawlib = rawlib.read()?;
+        let cell = rawlib.cells[0].read()?;
+        let elems = &cell.layout.as_ref().unwrap().elems;
+        Ok(elems.iter().filter(|e| e.layer == via1).cloned().collect())
+    };
+    // A redundant via on net `a`, next to the one generated by its assignment.
+    // Its metal-1 track is unassigned, and its metal-2 track is assigned to `a`.
+    let redundant = TrackCross::from_relz(LayerIndex(1), TrackIndex(1), TrackIndex(3), RelZ::Below);
+    let via = ViaPlacement::new("via1", redundant).net("a");
+    let rawlib = convert(vec![via.clone()], vec![])?;
+    let found: Vec<raw::Element> = cuts(&rawlib)?
+        .into_iter()
+        .filter(|e| e.net.as_deref() == Some("a"))
+        .collect();
+    assert_eq!(found.len(), 2);
+
+    // The same via, by layer index and physical location
+    let center = match found[1].inner {
+        raw::Shape::Rect(ref r) => Xy::new(
+            DbUnits((r.p0.x + r.p1.x) / 2),
+            DbUnits((r.p0.y + r.p1.y) / 2),
+        ),
+        _ => panic!("Invalid via shape"),
+    };
+    let index = stack.vias.iter().position(|v| v.name == "via1").unwrap();
+    let by_point = ViaPlacement::new(index, center).net("a");
+    let by_point = cuts(&convert(vec![by_point], vec![])?)?;
+    assert_eq!(by_point.iter().filter(|e| found.contains(e)).count(), 2);
+
+    // Placements on other nets' metal fail
+    let foreign = ViaPlacement::new(
+        "via1",
+        TrackCross::from_relz(LayerIndex(1), TrackIndex(1), TrackIndex(5), RelZ::Below),
     )
-    .unwrap();
+    .net("a");
+    let err = convert(vec![foreign], vec![]).unwrap_err();
+    assert!(format!("{}", err).contains("of net b"));
+    // As do those on cut-away metal
+    let cut = TrackCross::from_relz(LayerIndex(0), TrackIndex(3), TrackIndex(1), RelZ::Above);
+    let err = convert(vec![via], vec![cut]).unwrap_err();
+    assert!(format!("{}", err).contains("where cut by"));
+    // And those between tracks, or on undefined via layers
+    let between = ViaPlacement::new("via1", Xy::new(DbUnits(200), DbUnits(0))); // In a met2 gap
+    let err = convert(vec![between], vec![]).unwrap_err();
+    assert!(format!("{}", err).contains("lands on no track of layer met2"));
+    let undefined = ViaPlacement::new("via9", redundant);
+    assert!(convert(vec![undefined], vec![]).is_err());
+    Ok(())
+}
+/// Instantiate cells from hand-built primitive [raw::Library]s
+#[cfg(feature = "gds")]
+#[test]
+fn primitive_raw_libs() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawLibHandling};
+    use crate::library::CellRef;
+    use crate::raw;
 
-    // Export to GDSII
-    let gds = rawlib.to_gds()?;
-    Yaml.save(&gds, &resource(&format!("{}.gds.yaml", &gds.name)))
-        .unwrap();
-    gds.save(&resource(&format!("{}.gds", &gds.name)))?;
+    let create = || -> LayoutResult<Library> {
+        let stack = SampleStacks::pdka()?;
+        let met1 = stack.metal(LayerIndex(0))?.raw.unwrap();
+        let mut lib = Library::new("primitive_raw_libs");
+        // Add two primitive libraries, each with a single cell
+        for (libname, cellname) in [("prims1", "prim1"), ("prims2", "prim2")] {
+            let mut rawlib = raw::Library::new(libname, stack.units);
+            rawlib.layers = stack.rawlayers.clone().unwrap();
+            let mut rawcell = raw::Cell::new(cellname);
+            rawcell.layout = Some(raw::Layout {
+                name: cellname.into(),
+                elems: vec![raw::Element {
+                    net: None,
+                    layer: met1,
+                    purpose: raw::LayerPurpose::Drawing,
+                    inner: raw::Shape::Rect(raw::Rect {
+                        p0: raw::Point::new(0, 0),
+                        p1: raw::Point::new(100, 100),
+                    }),
+                    labels: Vec::new(),
+                }],
+                ..Default::default()
+            });
+            rawlib.cells.add(rawcell);
+            lib.add_rawlib(rawlib);
+        }
+        let prim1 = lib.wrap_raw_cell("prim1", Outline::rect(2, 1)?, 1)?;
+        assert_eq!(lib.wrap_raw_cell("prim1", Outline::rect(2, 1)?, 1)?, prim1);
+        assert_eq!(lib.resolve_ref(&CellRef::Raw("prim1".into()))?, prim1);
+        let prim2 = lib.wrap_raw_cell("prim2", Outline::rect(2, 1)?, 1)?;
+        assert!(lib.wrap_raw_cell("prim3", Outline::rect(2, 1)?, 1).is_err());
+        assert!(lib.resolve_ref(&CellRef::Raw("prim3".into())).is_err());
+
+        let mut top = Cell::builder("top")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(10, 2)?);
+        for (name, cell, x) in [("p1", prim1, 0), ("p2", prim2, 4)] {
+            let inst = Instance::builder()
+                .inst_name(name)
+                .cell(cell)
+                .loc((x, 0))
+                .build()?;
+            top = top.instance(inst);
+        }
+        lib.add_cell(top.build()?);
+        Ok(lib)
+    };
+    let struct_names = |gds: &crate::raw::gds::gds21::GdsLibrary| -> Vec<String> {
+        let mut names: Vec<String> = gds.structs.iter().map(|s| s.name.clone()).collect();
+        names.sort();
+        names
+    };
+
+    // By default, the primitive cells are included in the output
+    let rawlib = create()?.to_raw(SampleStacks::pdka()?)?;
+    let gds = rawlib.read()?.to_gds()?;
+    assert_eq!(struct_names(&gds), vec!["prim1", "prim2", "top"]);
+    let top = gds.structs.iter().find(|s| s.name == "top").unwrap();
+    let refs: Vec<&str> = top
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            crate::raw::gds::gds21::GdsElement::GdsStructRef(r) => Some(r.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(refs, vec!["prim1", "prim2"]);
+
+    // Merged into a new library, leaving the primitive libraries unmodified
+    let lib = create()?;
+    let prims1 = lib.rawlibs[0].clone();
+    let rawlib = lib.to_raw(SampleStacks::pdka()?)?;
+    assert_eq!(rawlib.read()?.name, "primitive_raw_libs");
+    assert_eq!(prims1.read()?.name, "prims1");
+    assert_eq!(prims1.read()?.cells.len(), 1);
+
+    // Cells defined in more than one primitive library fail
+    let lib = create()?;
+    let prim1 = lib.rawlibs[0].read()?.cells[0].clone();
+    lib.rawlibs[1].write()?.cells.push(prim1);
+    let err = lib.to_raw(SampleStacks::pdka()?).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Duplicate Cell prim1 in raw libraries prims1 and prims2"));
+
+    // Or left as external references
+    let opts = RawExportOptions {
+        raw_libs: RawLibHandling::External,
+        ..Default::default()
+    };
+    let (rawlib, _) = create()?.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let gds = rawlib.read()?.to_gds()?;
+    assert_eq!(struct_names(&gds), vec!["top"]);
     Ok(())
 }
-/// Grab the full path of resource-file `fname`
-fn resource(rname: &str) -> String {
-    format!("{}/resources/{}", env!("CARGO_MANIFEST_DIR"), rname)
+/// Block tracks with a central [Keepout](crate::layout::Keepout)
+#[test]
+fn keepouts() -> LayoutResult<()> {
+    use crate::conv::raw::{ConversionReport, RawExportOptions, RawExporter};
+    use crate::raw;
+
+    let cell = |keepout: (Range<usize>, Range<usize>, Range<usize>)| -> LayoutResult<Cell> {
+        let mut cell = Cell::builder("KeptOut")
+            .top_layer(LayerIndex(3))
+            .outline(Outline::rect(20, 4)?)
+            .build()?;
+        let (layers, x, y) = keepout;
+        cell.layout.as_mut().unwrap().keepout("ko", layers, x, y);
+        Ok(cell)
+    };
+    let convert = |cell: Cell,
+                   opts: &RawExportOptions|
+     -> LayoutResult<(Ptr<raw::Library>, ConversionReport)> {
+        let mut lib = Library::new("KeepoutLib");
+        lib.cells.insert(cell);
+        RawExporter::convert_with_options(lib, SampleStacks::pdka()?, opts)
+    };
+    let opts = RawExportOptions {
+        route_db: true,
+        keepout_marker: Some(255),
+        ..Default::default()
+    };
+    // A central keepout on met1-met3
+    let (rawlib, report) = convert(cell((0..3, 8..12, 1..3))?, &opts)?;
+    let blocked = |layer: usize| {
+        report.routes[0].layers[layer]
+            .periods
+            .iter()
+            .flat_map(|p| p.signals.iter().chain(p.rails.iter()))
+            .flat_map(|t| t.segments.iter())
+            .any(|s| matches!(s.tp, TrackSegmentType::Blockage { ref src } if src == "ko"))
+    };
+    assert!(blocked(0));
+    assert!(blocked(1));
+    assert!(blocked(2));
+    assert!(!blocked(3));
+    // With one marker rectangle per blocked layer
+    let rawlib = rawlib.read()?;
+    let rawcell = rawlib.cells[0].read()?;
+    let markers = rawcell
+        .layout
+        .as_ref()
+        .unwrap()
+        .elems
+        .iter()
+        .filter(|e| e.purpose == raw::LayerPurpose::Obstruction)
+        .count();
+    assert_eq!(markers, 3);
+
+    // Keepouts outside the outline fail
+    assert!(convert(cell((0..3, 18..25, 1..3))?, &opts).is_err());
+    // As do assignments inside them, naming the keepout
+    let mut assigned = cell((1..2, 0..20, 0..4))?;
+    assigned.layout.as_mut().unwrap().assign(
+        "a",
+        LayerIndex(1),
+        TrackIndex(1),
+        TrackIndex(1),
+        RelZ::Above,
+    );
+    let err = convert(assigned, &opts).unwrap_err();
+    assert!(format!("{:?}", err).contains("Keepout ko"));
+    // And cuts, including the underlying track error
+    let mut cut = cell((1..2, 0..20, 0..4))?;
+    cut.layout
+        .as_mut()
+        .unwrap()
+        .cut(LayerIndex(1), TrackIndex(1), TrackIndex(1), RelZ::Above);
+    let err = format!("{:?}", convert(cut, &opts).unwrap_err());
+    assert!(err.contains("Could not make track-cuts"));
+    assert!(err.contains("Conflicting Instance Blockages"));
+    Ok(())
+}
+/// Validate cell outlines against a placement site two primitive-pitches tall
+#[test]
+fn placement_sites() -> LayoutResult<()> {
+    use crate::coords::{DbUnits, HasUnits, PrimPitches, Xy};
+    use crate::validate::validate_lib;
+
+    let mut stack = SampleStacks::pdka_stack()?;
+    let pitches = stack.prim.pitches;
+    stack.site = Some(Xy::new(pitches.x, DbUnits(2 * pitches.y.raw())));
+    let stack = stack.validate()?;
+    assert_eq!(
+        stack.site_pitches(),
+        Xy::new(PrimPitches::x(1), PrimPitches::y(2))
+    );
+    assert_eq!(stack.snap_to_site(PrimPitches::y(3)), PrimPitches::y(2));
+
+    let lib = |rows: isize| -> LayoutResult<Library> {
+        let mut lib = Library::new("SiteLib");
+        lib.cells.insert(
+            Cell::builder("Rows")
+                .top_layer(LayerIndex(1))
+                .outline(Outline::rect(10, rows)?)
+                .build()?,
+        );
+        Ok(lib)
+    };
+    validate_lib(&lib(4)?, &stack)?;
+    let err = validate_lib(&lib(3)?, &stack).unwrap_err();
+    assert!(format!("{:?}", err).contains("placement sites"));
+
+    // Sites which aren't multiples of the primitive pitch are invalid
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.site = Some(Xy::new(pitches.x, DbUnits(pitches.y.raw() + 1)));
+    assert!(stack.validate().is_err());
+    Ok(())
+}
+/// Detect abutted instances, report mis-abutted ones, and clip duplicate half-rails
+#[test]
+fn abutments() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::coords::{DbUnits, HasUnits, PrimPitches, Xy};
+    use crate::layout::Abutment;
+    use crate::library::Violation;
+    use crate::raw::{self, Dir};
+
+    // Two vertically abutted instances, and a third at `x` to the right of the first
+    let create = |x: isize| -> LayoutResult<Library> {
+        let unit = Cell::builder("unit")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 1)?)
+            .build()?;
+        let unit = Ptr::new(unit);
+        let mut lib = Library::new("AbutLib");
+        lib.cells.push(unit.clone());
+        let mut pair = Cell::builder("pair")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(12, 2)?);
+        for (name, loc) in [("i0", (0, 0)), ("i1", (0, 1)), ("i2", (x, 0))] {
+            let inst = Instance::builder()
+                .inst_name(name)
+                .cell(unit.clone())
+                .loc(loc)
+                .build()?;
+            pair = pair.instance(inst);
+        }
+        lib.cells.insert(pair.build()?);
+        Ok(lib)
+    };
+    let pair = |lib: &Library| lib.cell_named("pair").map(|c| c.unwrap());
+    let vert = Abutment {
+        insts: ("i0".into(), "i1".into()),
+        dir: Dir::Vert,
+        at: PrimPitches::y(1),
+        span: (PrimPitches::x(0), PrimPitches::x(4)),
+    };
+    let horiz = Abutment {
+        insts: ("i0".into(), "i2".into()),
+        dir: Dir::Horiz,
+        at: PrimPitches::x(4),
+        span: (PrimPitches::y(0), PrimPitches::y(1)),
+    };
+    // Placement sites two primitive-pitches wide
+    let mut stack = SampleStacks::pdka_stack()?;
+    let pitches = stack.prim.pitches;
+    stack.site = Some(Xy::new(DbUnits(2 * pitches.x.raw()), pitches.y));
+    let stack = stack.validate()?;
+
+    // Perfectly abutted
+    let lib = create(4)?;
+    assert_eq!(pair(&lib)?.read()?.abutments()?, vec![vert.clone(), horiz]);
+    assert!(lib.check_abutments(&stack)?.is_empty());
+    // Off by half a site
+    let lib = create(5)?;
+    assert_eq!(pair(&lib)?.read()?.abutments()?, vec![vert.clone()]);
+    assert_eq!(
+        lib.check_abutments(&stack)?.0,
+        vec![Violation::Misabutment {
+            cell: "pair".into(),
+            insts: ("i0".into(), "i2".into()),
+            dir: Dir::Horiz,
+        }]
+    );
+
+    // Convert, and collect the y-extents of the met1 rails of each cell instantiated by `pair`, by instance name
+    type Rails = Vec<(String, String, Vec<(isize, isize)>)>;
+    let rails = |clip: bool| -> LayoutResult<Rails> {
+        let opts = RawExportOptions {
+            clip_abutted_rails: clip,
+            ..Default::default()
+        };
+        let stack = SampleStacks::pdka()?;
+        let layerkey = stack.metal(LayerIndex(0))?.raw.unwrap();
+        let (rawlib, _) = RawExporter::convert_with_options(create(6)?, stack, &opts)?;
+        let rawlib = rawlib.read()?;
+        let pair = rawlib.cell_named("pair")?.unwrap();
+        let pair = pair.read()?;
+        let mut rails = Vec::new();
+        for inst in pair.layout.as_ref().unwrap().insts.iter() {
+            let cell = inst.cell.read()?;
+            let extents = cell
+                .layout
+                .as_ref()
+                .unwrap()
+                .elems
+                .iter()
+                .filter(|e| e.layer == layerkey && e.net.is_some())
+                .filter_map(|e| match e.inner {
+                    raw::Shape::Rect(ref r) => Some((r.p0.y, r.p1.y)),
+                    _ => None,
+                })
+                .collect();
+            rails.push((inst.inst_name.clone(), cell.name.clone(), extents));
+        }
+        Ok(rails)
+    };
+    // Rails straddle the unit's top and bottom edges, unless clipped
+    let ymax = pitches.y.raw();
+    let unclipped = rails(false)?;
+    for (_, cell, extents) in unclipped.iter() {
+        assert_eq!(cell, "unit");
+        assert_eq!(extents.len(), 2);
+        assert!(extents.iter().any(|(y0, _)| *y0 < 0));
+        assert!(extents.iter().any(|(_, y1)| *y1 > ymax));
+    }
+    // Clipping applies only along the abutted edge of each abutted instance
+    let clipped = rails(true)?;
+    let names: Vec<_> = clipped
+        .iter()
+        .map(|(i, c, _)| (i.as_str(), c.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![("i0", "unit_clip_t"), ("i1", "unit_clip_b"), ("i2", "unit")]
+    );
+    let (i0, i1, i2) = (&clipped[0].2, &clipped[1].2, &clipped[2].2);
+    assert_eq!(i0.len(), 2);
+    assert!(i0.iter().any(|(y0, _)| *y0 < 0));
+    assert!(i0.iter().all(|(_, y1)| *y1 <= ymax));
+    assert_eq!(i1.len(), 2);
+    assert!(i1.iter().all(|(y0, _)| *y0 >= 0));
+    assert!(i1.iter().any(|(_, y1)| *y1 > ymax));
+    // The non-abutted instance keeps its full rails
+    assert_eq!(i2, &unclipped[2].2);
+    Ok(())
+}
+/// Check escape analysis of an [Instance] blocked on three sides
+#[test]
+fn escape_analysis() -> LayoutResult<()> {
+    use crate::abs::{Abstract, Port, Side};
+    use crate::escape::Escape;
+
+    let stack = SampleStacks::pdka()?;
+    let mut lib = Library::new("EscapeLib");
+    // The analyzed macro, with a met1 port on each side
+    let mut abs = Abstract::new("macro", 2, Outline::rect(4, 1)?);
+    for (name, side) in [("a", Side::BottomOrLeft), ("b", Side::TopOrRight)] {
+        abs.ports
+            .push(Port::edge(name, LayerIndex(0), TrackIndex(2), side));
+    }
+    let mac = lib.cells.insert(Cell::from(abs));
+    // Blockers above, below, and to the right of it
+    let wide = Cell::builder("wide")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(12, 2)?)
+        .build()?;
+    let wide = lib.cells.insert(wide);
+    let right = Cell::builder("right")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(8, 1)?)
+        .build()?;
+    let right = lib.cells.insert(right);
+    let mut parent = Cell::builder("parent")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(20, 5)?);
+    for (name, cell, loc) in [
+        ("mac", &mac, (8, 2)),
+        ("above", &wide, (8, 3)),
+        ("below", &wide, (8, 0)),
+        ("right", &right, (12, 2)),
+    ] {
+        let inst = Instance::builder()
+            .inst_name(name)
+            .cell(cell.clone())
+            .loc(loc)
+            .build()?;
+        parent = parent.instance(inst);
+    }
+    let mut parent = parent.build()?;
+
+    // Port `b` is trapped, while `a` escapes in both directions on the met2 track left of the macro
+    let report = parent.escape_analysis("mac", &stack)?;
+    assert_eq!(report.inst, "mac");
+    let trapped: Vec<&str> = report.trapped().iter().map(|p| p.port.as_str()).collect();
+    assert_eq!(trapped, vec!["b"]);
+    let track = TrackRef::new(LayerIndex(1), TrackIndex(7));
+    assert_eq!(
+        report.ports[0].escapes,
+        vec![
+            Escape {
+                track,
+                side: Side::BottomOrLeft
+            },
+            Escape {
+                track,
+                side: Side::TopOrRight
+            },
+        ]
+    );
+    // Cutting that track above the port leaves only its downward escape
+    let layout = parent.layout.as_mut().unwrap();
+    layout.cut(LayerIndex(1), TrackIndex(7), TrackIndex(20), RelZ::Below);
+    let report = parent.escape_analysis("mac", &stack)?;
+    assert_eq!(report.ports[0].escapes.len(), 1);
+    assert_eq!(report.ports[0].escapes[0].side, Side::BottomOrLeft);
+    // And assigning it below traps `a` as well
+    let layout = parent.layout.as_mut().unwrap();
+    layout.assign(
+        "x",
+        LayerIndex(1),
+        TrackIndex(7),
+        TrackIndex(3),
+        RelZ::Below,
+    );
+    let report = parent.escape_analysis("mac", &stack)?;
+    assert!(!report.is_clean());
+    assert_eq!(report.trapped().len(), 2);
+
+    // Instances without abstracts, and missing instances, fail
+    assert!(parent.escape_analysis("above", &stack).is_err());
+    assert!(parent.escape_analysis("nope", &stack).is_err());
+    Ok(())
+}
+/// Stamp a column of instances, with end-cap overrides
+#[test]
+fn stamp_instances() -> LayoutResult<()> {
+    use crate::coords::{PrimPitches, Xy};
+    use crate::library::CellRef;
+
+    let mut lib = Library::new("stamp_instances");
+    let bit = lib
+        .cells
+        .insert(abs::Abstract::new("bit", 1, Outline::rect(4, 1)?));
+    let dummy = lib
+        .cells
+        .insert(abs::Abstract::new("dummy", 1, Outline::rect(4, 1)?));
+    let mut col = Cell::builder("col")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(4, 8)?)
+        .build()?;
+    let base = Instance::builder()
+        .inst_name("base")
+        .cell(bit.clone())
+        .loc((0, 0))
+        .build()?;
+    let step = Xy::new(PrimPitches::x(0), PrimPitches::y(1));
+    let overrides = [(0, CellRef::name("dummy")), (7, CellRef::name("dummy"))];
+    let names = col.stamp(&lib, base.clone(), 8, step, "bit_{}", &overrides)?;
+    let expected: Vec<String> = (0..8).map(|i| format!("bit_{}", i)).collect();
+    assert_eq!(names, expected);
+
+    let layout = col.layout.as_ref().unwrap();
+    assert_eq!(layout.instances.len(), 8);
+    for (idx, ptr) in layout.instances.iter().enumerate() {
+        let inst = ptr.read()?;
+        assert_eq!(inst.inst_name, names[idx]);
+        assert_eq!(*inst.loc.abs()?, Xy::from((0, idx as isize)));
+        let expected = if idx == 0 || idx == 7 { &dummy } else { &bit };
+        assert_eq!(inst.cell, *expected);
+    }
+
+    // Colliding names, placements beyond the outline, and invalid overrides all fail, adding nothing
+    assert!(col
+        .stamp(&lib, base.clone(), 1, step, "bit_{}", &[])
+        .is_err());
+    let mut col2 = Cell::builder("col2")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(4, 8)?)
+        .build()?;
+    assert!(col2
+        .stamp(&lib, base.clone(), 9, step, "bit_{}", &[])
+        .is_err());
+    let bad = [(8, CellRef::name("dummy"))];
+    assert!(col2
+        .stamp(&lib, base.clone(), 8, step, "bit_{}", &bad)
+        .is_err());
+    let missing = [(1, CellRef::name("nope"))];
+    assert!(col2
+        .stamp(&lib, base.clone(), 8, step, "bit_{}", &missing)
+        .is_err());
+    assert!(col2.stamp(&lib, base, 8, step, "bit", &[]).is_err());
+    assert_eq!(col2.layout.as_ref().unwrap().instances.len(), 0);
+    Ok(())
+}
+/// Check the periods blocked by multi-row instances, including at the parent's edges
+#[test]
+fn instance_block_periods() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+
+    // Convert a two-row instance at `y` in a three-row parent,
+    // returning the met1 periods in which it creates blockages
+    let blocked = |y: isize, reflect_vert: bool| -> LayoutResult<Vec<usize>> {
+        let mut lib = Library::new("RowsLib");
+        let unit = Cell::builder("unit")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(2, 2)?)
+            .build()?;
+        let unit = lib.cells.insert(unit);
+        let inst = Instance::builder()
+            .inst_name("i")
+            .cell(unit)
+            .loc((0, y))
+            .reflect_vert(reflect_vert)
+            .build()?;
+        let parent = Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 3)?)
+            .instance(inst)
+            .build()?;
+        lib.cells.insert(parent);
+        let opts = RawExportOptions {
+            route_db: true,
+            ..Default::default()
+        };
+        let (_, report) = RawExporter::convert_with_options(lib, SampleStacks::pdka()?, &opts)?;
+        let routes = report.route_db("parent").unwrap();
+        Ok(routes.layers[0]
+            .periods
+            .iter()
+            .filter(|p| {
+                p.signals.iter().any(|t| {
+                    t.segments
+                        .iter()
+                        .any(|s| matches!(s.tp, TrackSegmentType::Blockage { .. }))
+                })
+            })
+            .map(|p| p.index)
+            .collect())
+    };
+    // Ending exactly at the parent's edge blocks its last row, and nothing beyond
+    assert_eq!(blocked(1, false)?, vec![1, 2]);
+    assert_eq!(blocked(0, false)?, vec![0, 1]);
+    // Reflection shifts coverage below the origin
+    assert_eq!(blocked(2, true)?, vec![0, 1]);
+    assert_eq!(blocked(3, true)?, vec![1, 2]);
+    // And extending past the parent fails
+    assert!(blocked(2, false).is_err());
+    assert!(blocked(1, true).is_err());
+    Ok(())
+}
+/// Check the per-layer unit cells, and their registry
+#[test]
+fn unit_cells() -> LayoutResult<()> {
+    use crate::conv::raw::{unit_cell_name, RawExportOptions, RawExporter};
+    use crate::raw;
+
+    let mut lib = Library::new("UnitLib");
+    let cell = Cell::builder("empty")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(2, 1)?)
+        .build()?;
+    lib.cells.insert(cell);
+    let opts = RawExportOptions {
+        unit_cells: true,
+        ..Default::default()
+    };
+    let stack = SampleStacks::pdka()?;
+    let plan = stack.track_plan(LayerIndex(0))?;
+    let (rawlib, report) = RawExporter::convert_with_options(lib, stack, &opts)?;
+
+    // One unit cell per metal layer
+    let names: Vec<&str> = report
+        .unit_cells
+        .cells
+        .values()
+        .map(|s| s.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "met1_unit",
+            "met2_unit",
+            "met3_unit",
+            "met4_unit",
+            "met5_unit"
+        ]
+    );
+    assert_eq!(report.unit_cells.get(0), Some("met1_unit"));
+    assert_eq!(report.unit_cells.get(5), None);
+
+    // Whose geometry matches a single period of each layer's tracks
+    let rawlib = rawlib.read()?;
+    let cell = rawlib
+        .cells
+        .iter()
+        .find(|c| c.read().unwrap().name == "met1_unit");
+    let cell = cell.unwrap().read()?;
+    let mut spans: Vec<(isize, isize)> = cell
+        .layout
+        .as_ref()
+        .unwrap()
+        .elems
+        .iter()
+        .map(|e| match e.inner {
+            raw::Shape::Rect(ref r) => (r.p0.y, r.p1.y),
+            _ => panic!("Unexpected non-rectangular unit-cell shape"),
+        })
+        .collect();
+    spans.sort();
+    let expected: Vec<(isize, isize)> = plan
+        .entries
+        .iter()
+        .filter(|e| e.ttype != TrackType::Gap)
+        .map(|e| (e.start.0, e.start.0 + e.width.0))
+        .collect();
+    assert_eq!(spans, expected);
+
+    // Names are sanitized for GDS
+    let mut layer = SampleStacks::pdka_stack()?.metals.remove(0);
+    layer.name = "met 1.a".into();
+    assert_eq!(unit_cell_name(&layer), "met_1_a_unit");
+
+    // And uniquified around existing cells
+    let mut lib = Library::new("UnitLib");
+    for name in ["met1_unit", "met1_unit_1"] {
+        let cell = Cell::builder(name)
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(2, 1)?)
+            .build()?;
+        lib.cells.insert(cell);
+    }
+    let (rawlib, report) = RawExporter::convert_with_options(lib, SampleStacks::pdka()?, &opts)?;
+    assert_eq!(report.unit_cells.get(0), Some("met1_unit_2"));
+    assert_eq!(report.unit_cells.get(1), Some("met2_unit"));
+    let rawlib = rawlib.read()?;
+    assert!(rawlib.cell_named("met1_unit_2")?.is_some());
+    assert_eq!(rawlib.cells.len(), 7);
+    Ok(())
+}
+/// Check rail stubs beside an instance flush against the parent's left edge
+#[cfg(feature = "gds")]
+#[test]
+fn rail_stubs() -> LayoutResult<()> {
+    use crate::raw::gds::gds21::{GdsElement, GdsPoint};
+
+    let mut lib = Library::new("StubLib");
+    let unit = Cell::builder("unit")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(3, 1)?)
+        .build()?;
+    let unit = lib.cells.insert(unit);
+    let inst = Instance::builder()
+        .inst_name("i")
+        .cell(unit)
+        .loc((0, 0))
+        .build()?;
+    let parent = Cell::builder("parent")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(10, 2)?)
+        .instance(inst)
+        .build()?;
+    lib.cells.insert(parent);
+    let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+    let gds = rawlib.read()?.to_gds()?;
+    let parent = gds.structs.iter().find(|s| s.name == "parent").unwrap();
+
+    // Collect the met1 (GDS layer 68) drawn rectangles, and the labels of its rails
+    let mut rects = Vec::new();
+    let mut labels = Vec::new();
+    for elem in parent.elems.iter() {
+        match elem {
+            GdsElement::GdsBoundary(b) if b.layer == 68 && b.datatype == 20 => {
+                rects.push((b.xy[0].clone(), b.xy[2].clone()));
+            }
+            GdsElement::GdsTextElem(t)
+                if t.layer == 68 && (t.string == "VDD" || t.string == "VSS") =>
+            {
+                labels.push(t.xy.clone());
+            }
+            _ => (),
+        }
+    }
+    // No zero-length rectangles are drawn
+    assert!(!rects.is_empty());
+    for (p0, p1) in rects.iter() {
+        assert!(p0.x != p1.x && p0.y != p1.y);
+    }
+    // And each rail label sits over visible metal, outside the instance
+    let inside = |p: &GdsPoint, (p0, p1): &(GdsPoint, GdsPoint)| {
+        p.x >= p0.x.min(p1.x)
+            && p.x <= p0.x.max(p1.x)
+            && p.y >= p0.y.min(p1.y)
+            && p.y <= p0.y.max(p1.y)
+    };
+    assert!(!labels.is_empty());
+    for label in labels.iter() {
+        assert!(rects.iter().any(|r| inside(label, r)));
+        assert!(!(label.x < 3 * 460 && label.y > 0 && label.y < 2720));
+    }
+    Ok(())
+}
+/// Check dry-run conversion reports match those of full conversion
+#[test]
+fn dry_run_check() -> LayoutResult<()> {
+    use crate::conv::raw::RawExportOptions;
+
+    let opts = RawExportOptions {
+        route_db: true,
+        ..Default::default()
+    };
+    let (_, full) = routed_library()?.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let dry = routed_library()?.check(SampleStacks::pdka()?, &opts)?;
+    assert!(full.periods > 0);
+    assert_eq!(dry, full);
+
+    // Errors are reported just the same, e.g. conflicting assignments
+    let mut lib = routed_library()?;
+    let cell = Cell::builder("conflicts")
+        .top_layer(LayerIndex(2))
+        .outline(Outline::rect(8, 2)?)
+        .cut(LayerIndex(1), TrackIndex(1), TrackIndex(1), RelZ::Below)
+        .assign(
+            "a",
+            LayerIndex(1),
+            TrackIndex(1),
+            TrackIndex(1),
+            RelZ::Below,
+        )
+        .build()?;
+    lib.cells.insert(cell);
+    assert!(lib.check(SampleStacks::pdka()?, &opts).is_err());
+    Ok(())
+}
+/// Check that converting and serializing identically-built [Library]s produces identical output
+#[cfg(all(feature = "gds", feature = "yaml"))]
+#[test]
+fn deterministic_export() -> LayoutResult<()> {
+    use crate::utils::SerializationFormat::Yaml;
+
+    let export = || -> LayoutResult<(String, String, Vec<u8>)> {
+        let rawlib = routed_library()?.to_raw(SampleStacks::pdka()?)?;
+        let rawlib = rawlib.read()?;
+        let layers = Yaml.to_string(&*rawlib.layers.read()?).unwrap();
+        let proto = Yaml.to_string(&rawlib.to_proto()?).unwrap();
+        // Fix the GDS timestamps, and compare its binary content
+        let mut gds = rawlib.to_gds()?;
+        gds.set_all_dates(&[2000, 1, 1, 0, 0, 0]);
+        let mut bytes = Vec::new();
+        gds.write(&mut bytes)?;
+        Ok((layers, proto, bytes))
+    };
+    assert_eq!(export()?, export()?);
+    Ok(())
+}
+/// Render a period of met1 directly, and compare against that of converting an empty cell
+#[test]
+fn render_period() -> LayoutResult<()> {
+    use crate::conv::raw::render_period;
+    use crate::raw;
+
+    let stack = SampleStacks::pdka()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    let span = stack.prim.pitches[met1.spec.dir];
+    let period = met1.spec.to_layer_period(0, span)?;
+    let rendered = render_period(&period, met1, met1.spec.cutsize)?;
+    assert_eq!(rendered.len(), 8);
+
+    let mut lib = Library::new("render_period");
+    let cell = Cell::builder("empty")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(1, 1)?)
+        .build()?;
+    lib.cells.insert(cell);
+    let rawlib = lib.to_raw(SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let rawcell = rawlib.cells.iter().next().unwrap().read()?;
+    let converted: Vec<raw::Element> = rawcell
+        .layout
+        .as_ref()
+        .unwrap()
+        .elems
+        .iter()
+        .filter(|e| e.layer == met1.raw.unwrap())
+        .cloned()
+        .collect();
+    assert_eq!(rendered, converted);
+    Ok(())
+}
+/// Render [raw::Label]s on a track segment, as text at its ends alongside its net label
+#[cfg(feature = "gds")]
+#[test]
+fn segment_labels() -> LayoutResult<()> {
+    use crate::conv::raw::render_track;
+    use crate::coords::DbUnits;
+    use crate::raw::{self, gds::gds21::GdsElement};
+
+    let stack = SampleStacks::pdka()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    let period = met1.spec.to_layer_period(0, DbUnits(4600))?;
+    let mut track = period.signals[0].clone();
+    track
+        .strap(DbUnits(1000), DbUnits(2000), "data", ConflictPolicy::Error)
+        .unwrap();
+    track
+        .label(DbUnits(1000), raw::Label::new("data", 0.0))
+        .unwrap();
+    track
+        .label(DbUnits(1999), raw::Label::new("data_end", 1.0))
+        .unwrap();
+    assert!(track
+        .label(DbUnits(5000), raw::Label::new("x", 0.0))
+        .is_err());
+
+    let elems = render_track(&track, met1, met1.spec.cutsize)?;
+    let strap = elems
+        .iter()
+        .find(|e| e.net.as_deref() == Some("data"))
+        .unwrap();
+    assert_eq!(strap.labels.len(), 2);
+    let mid = track.data.start + track.data.width / 2;
+
+    // Export to GDSII: the net label is still placed, at the segment's center, plus one per end
+    let mut rawlib = raw::Library::new("segment_labels", raw::Units::Nano);
+    rawlib.layers = stack.rawlayers.clone().unwrap();
+    rawlib.cells.add(raw::Layout {
+        name: "labeled".into(),
+        elems: vec![strap.clone()],
+        ..Default::default()
+    });
+    let gds = rawlib.to_gds()?;
+    let texts: Vec<_> = gds.structs[0]
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsTextElem(t) => Some((t.string.clone(), t.xy.x, t.xy.y)),
+            _ => None,
+        })
+        .collect();
+    let mid = i32::try_from(mid.0).unwrap();
+    assert_eq!(
+        texts,
+        vec![
+            ("data".to_string(), 1500, mid),
+            ("data".to_string(), 1000, mid),
+            ("data_end".to_string(), 2000, mid),
+        ]
+    );
+
+    // Splitting a segment drops its labels
+    track
+        .label(DbUnits(500), raw::Label::new("x", 0.5))
+        .unwrap();
+    track
+        .cut(
+            DbUnits(200),
+            DbUnits(300),
+            &TrackCross::from_relz(LayerIndex(0), TrackIndex(0), TrackIndex(0), RelZ::Above),
+        )
+        .unwrap();
+    assert!(track.segments[0].labels.is_empty());
+    assert_eq!(track.segment_at(DbUnits(1000)).unwrap().labels.len(), 2);
+    Ok(())
+}
+/// Check that zero-length segments and zero-width tracks render no geometry, and export no zero-area boundaries
+#[cfg(feature = "gds")]
+#[test]
+fn render_degenerate() -> LayoutResult<()> {
+    use crate::conv::raw::render_track;
+    use crate::coords::DbUnits;
+    use crate::raw;
+    use std::collections::HashSet;
+
+    let stack = SampleStacks::pdka()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    let span = stack.prim.pitches[met1.spec.dir];
+    let period = met1.spec.to_layer_period(0, span)?;
+    let track = period.signals[0].clone();
+    let expected = render_track(&track, met1, met1.spec.cutsize)?;
+    assert!(!expected.is_empty());
+
+    // A zero-length segment, as left by a cut exactly covering it
+    let mut with_empty = track.clone();
+    let end = with_empty.segments.last().unwrap().stop;
+    with_empty.segments.push(TrackSegment {
+        tp: TrackSegmentType::Strap { net: "VDD".into() },
+        start: end,
+        stop: end,
+        labels: Vec::new(),
+    });
+    assert_eq!(
+        render_track(&with_empty, met1, met1.spec.cutsize)?,
+        expected
+    );
+
+    // A zero-width track renders nothing
+    let mut zero_width = track.clone();
+    zero_width.data.width = DbUnits(0);
+    assert!(render_track(&zero_width, met1, met1.spec.cutsize)?.is_empty());
+
+    // Export the converted library, and check none of its boundaries are degenerate
+    let mut lib = Library::new("render_degenerate");
+    let cell = Cell::builder("empty")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(1, 1)?)
+        .build()?;
+    lib.cells.insert(cell);
+    let rawlib = lib.to_raw(SampleStacks::pdka()?)?;
+    let gds = rawlib.read()?.to_gds()?;
+    let mut count = 0;
+    for strukt in gds.structs.iter() {
+        for elem in strukt.elems.iter() {
+            if let raw::gds::gds21::GdsElement::GdsBoundary(b) = elem {
+                let xs: HashSet<i32> = b.xy.iter().map(|p| p.x).collect();
+                let ys: HashSet<i32> = b.xy.iter().map(|p| p.y).collect();
+                assert!(xs.len() > 1 && ys.len() > 1);
+                count += 1;
+            }
+        }
+    }
+    assert!(count > 0);
+    Ok(())
+}
+/// Check removal of sliver segments between nearby instance blockages, per [MetalLayer::min_length]
+#[test]
+fn min_length_slivers() -> LayoutResult<()> {
+    use crate::conv::raw::{MinLengthPolicy, RawExportOptions};
+    use crate::coords::DbUnits;
+    use crate::raw;
+
+    let stack = |min_length: Option<isize>| -> LayoutResult<ValidStack> {
+        let mut stack = SampleStacks::pdka_stack()?;
+        stack.metals[0].min_length = min_length.map(DbUnits);
+        stack.validate()
+    };
+    // Two single-pitch blocking instances, separated by a single pitch
+    let create = || -> LayoutResult<Library> {
+        let mut lib = Library::new("min_length_slivers");
+        let blk = lib.cells.insert(abs::Abstract {
+            name: "blk".into(),
+            metals: 1,
+            outline: Outline::rect(1, 1)?,
+            ports: Vec::new(),
+        });
+        let mut cell = Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 1)?);
+        for (name, x) in [("left", 0), ("right", 2)] {
+            let inst = Instance::builder()
+                .inst_name(name)
+                .cell(blk.clone())
+                .loc((x, 0))
+                .build()?;
+            cell = cell.instance(inst);
+        }
+        lib.cells.insert(cell.build()?);
+        Ok(lib)
+    };
+    let convert = |min_length: Option<isize>, policy: MinLengthPolicy| {
+        let opts = RawExportOptions {
+            min_length: policy,
+            ..Default::default()
+        };
+        create()?.to_raw_with_options(stack(min_length)?, &opts)
+    };
+    let met1_elems = |rawlib: &Ptr<raw::Library>| -> LayoutResult<usize> {
+        let met1 = SampleStacks::pdka()?.metal(LayerIndex(0))?.raw.unwrap();
+        let rawlib = rawlib.read()?;
+        let parent = rawlib
+            .cells
+            .iter()
+            .find(|c| c.read().unwrap().name == "parent")
+            .unwrap()
+            .read()?;
+        let layout = parent.layout.as_ref().unwrap();
+        Ok(layout.elems.iter().filter(|e| e.layer == met1).count())
+    };
+    // Without a minimum length, the 460-unit remnants are exported
+    let (rawlib, report) = convert(None, MinLengthPolicy::Remove)?;
+    assert!(met1_elems(&rawlib)? > 0);
+    assert!(report.slivers.is_empty());
+
+    // With a longer minimum, they're removed and reported
+    let (rawlib, report) = convert(Some(500), MinLengthPolicy::Remove)?;
+    assert_eq!(met1_elems(&rawlib)?, 0);
+    assert!(!report.slivers.is_empty());
+    for sliver in report.slivers.iter() {
+        assert_eq!(sliver.cell, "parent");
+        assert_eq!(sliver.layer, 0);
+        assert_eq!(sliver.stop - sliver.start, DbUnits(460));
+    }
+    let between = report.slivers.iter().filter(|s| s.start == DbUnits(460));
+    assert_eq!(between.count(), 8);
+    // Including those of each rail, which are also warned of
+    let rails = report
+        .slivers
+        .iter()
+        .filter(|s| matches!(s.ttype, TrackType::Rail(_)));
+    assert_eq!(rails.count(), report.warnings.len());
+    assert!(report.warnings[0].contains("rail sliver"));
+
+    // Or fail, if so configured
+    assert!(convert(Some(500), MinLengthPolicy::Error).is_err());
+    Ok(())
+}
+/// Check that raw conversion invokes its progress callbacks once per cell, and once per layer
+#[test]
+fn conversion_progress() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::utils::{Progress, ProgressHook};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder {
+        cells: Mutex<Vec<(String, usize, usize)>>,
+        done: Mutex<Vec<String>>,
+        layers: Mutex<Vec<String>>,
+    }
+    impl Progress for Recorder {
+        fn on_cell_start(&self, name: &str, idx: usize, total: usize) {
+            self.cells.lock().unwrap().push((name.into(), idx, total));
+        }
+        fn on_cell_done(&self, name: &str, _elems: usize) {
+            self.done.lock().unwrap().push(name.into());
+        }
+        fn on_layer(&self, name: &str) {
+            self.layers.lock().unwrap().push(name.into());
+        }
+    }
+    let recorder = Arc::new(Recorder::default());
+    let opts = RawExportOptions {
+        progress: ProgressHook::new(recorder.clone()),
+        ..Default::default()
+    };
+    RawExporter::convert_with_options(abstract_library()?, SampleStacks::pdka()?, &opts)?;
+
+    let cells = recorder.cells.lock().unwrap().clone();
+    assert_eq!(
+        cells,
+        vec![("IsAbs".into(), 0, 2), ("HasAbss".into(), 1, 2)]
+    );
+    let done = recorder.done.lock().unwrap().clone();
+    assert_eq!(done, vec!["IsAbs".to_string(), "HasAbss".to_string()]);
+    // Only the implemented cell has layers to convert
+    let layers = recorder.layers.lock().unwrap().clone();
+    assert_eq!(layers, vec!["met1", "met2", "met3", "met4"]);
+    Ok(())
+}
+/// Check that [LayerIndex] and [TrackIndex] serialize transparently, as their underlying integers
+#[cfg(feature = "yaml")]
+#[test]
+fn typed_index_serde() -> LayoutResult<()> {
+    use crate::coords::{LayerIndex, TrackIndex};
+    use crate::utils::SerializationFormat::Yaml;
+
+    let tref = TrackRef::new(LayerIndex(1), TrackIndex(7));
+    assert_eq!(tref, TrackRef::new(LayerIndex(1), TrackIndex(7)));
+    let yaml = Yaml.to_string(&tref).unwrap();
+    let prior: TrackRef = Yaml.from_str("layer: 1\ntrack: 7\n")?;
+    assert_eq!(prior, tref);
+    assert_eq!(Yaml.from_str::<TrackRef>(&yaml)?, tref);
+
+    // Existing YAML ports and via-targets load unchanged
+    let port: abs::Port = Yaml
+        .from_str("name: a\nkind:\n  Edge:\n    layer: 2\n    track: 4\n    side: TopOrRight\n")?;
+    assert_eq!(
+        port,
+        abs::Port::edge("a", LayerIndex(2), TrackIndex(4), abs::Side::TopOrRight)
+    );
+    let target: ViaTarget = Yaml.from_str("Metal: 3")?;
+    assert!(matches!(target, ViaTarget::Metal(LayerIndex(3))));
+    Ok(())
+}
+/// Check that each public module path, and its [prelude](crate::prelude) re-export, name the same items.
+/// Largely a compile-time check, guarding the public API against module reorganization.
+#[test]
+fn module_paths() -> LayoutResult<()> {
+    use crate::prelude;
+
+    /// Compiles only if both arguments are of the same type
+    fn same<T>(_: Option<T>, _: Option<T>) {}
+
+    same(None::<crate::stack::Stack>, None::<prelude::Stack>);
+    same(
+        None::<crate::stack::MetalLayer>,
+        None::<prelude::MetalLayer>,
+    );
+    same(None::<crate::stack::ViaLayer>, None::<prelude::ViaLayer>);
+    same(None::<crate::stack::Assign>, None::<prelude::Assign>);
+    same(None::<crate::tracks::TrackSpec>, None::<prelude::TrackSpec>);
+    same(
+        None::<crate::tracks::TrackCross>,
+        None::<prelude::TrackCross>,
+    );
+    same(None::<crate::cell::Cell>, None::<prelude::Cell>);
+    same(None::<crate::instance::Instance>, None::<prelude::Instance>);
+    same(None::<crate::outline::Outline>, None::<prelude::Outline>);
+    same(None::<crate::layout::Layout>, None::<prelude::Layout>);
+    same(None::<crate::library::Library>, None::<prelude::Library>);
+    same(None::<crate::abs::Abstract>, None::<prelude::Abstract>);
+    same(
+        None::<crate::conv::raw::RawExporter>,
+        None::<prelude::RawExporter>,
+    );
+    same(
+        None::<crate::validate::ValidStack>,
+        None::<prelude::ValidStack>,
+    );
+    same(None::<crate::raw::Library>, None::<layout21raw::Library>);
+    #[cfg(feature = "gds")]
+    same(
+        None::<crate::gds21::GdsLibrary>,
+        None::<prelude::GdsLibrary>,
+    );
+    Ok(())
+}
+/// Helper function. Export [Library] `lib` in several formats, into our resources directory.
+/// Without the `gds` and `yaml` features, only converts it to [raw::Library](crate::raw::Library).
+pub fn exports(lib: Library, stack: ValidStack) -> LayoutResult<()> {
+    #[cfg(all(feature = "gds", feature = "yaml"))]
+    {
+        use crate::pipeline::{export_all, ExportOptions};
+        export_all(lib, stack, resource(""), &ExportOptions::default())?;
+    }
+    #[cfg(not(all(feature = "gds", feature = "yaml")))]
+    conv::raw::RawExporter::convert(lib, stack)?;
+    Ok(())
+}
+/// Run the export pipeline into a temporary directory, with and without its optional stages
+#[cfg(all(feature = "gds", feature = "yaml"))]
+#[test]
+fn export_pipeline() -> LayoutResult<()> {
+    use crate::pipeline::{export_all, ExportOptions};
+    use crate::raw::gds::{GdsExportOptions, LabelLimit, LongLabelPolicy};
+    use crate::raw::LayoutError;
+
+    // Export into a not-yet-created, nested directory, including spaces in its path
+    let root = std::env::temp_dir().join("layout21tetris export_pipeline");
+    let _ = std::fs::remove_dir_all(&root);
+    let dir = root.join("nested dir").join("out");
+    let lib_with = |net: &str| -> LayoutResult<Library> {
+        let mut lib = Library::new("PipelineLib");
+        let mut layout = Layout::new("cell", 2, Outline::rect(5, 1)?);
+        layout
+            .net(net)
+            .at(LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Below);
+        lib.cells.add(layout);
+        Ok(lib)
+    };
+    let lib = || lib_with("clk");
+
+    // By default, every stage runs
+    let artifacts = export_all(
+        lib()?,
+        SampleStacks::pdka()?,
+        &dir,
+        &ExportOptions::default(),
+    )?;
+    let names: Vec<_> = artifacts
+        .paths
+        .iter()
+        .map(|p| p.strip_prefix(&dir).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "PipelineLib.proto.bin",
+            "PipelineLib.proto.yaml",
+            "PipelineLib.gds",
+            "PipelineLib.gds.yaml"
+        ]
+    );
+    for path in artifacts.paths.iter() {
+        assert!(std::fs::metadata(path)?.len() > 0);
+    }
+    assert!(artifacts.report.periods > 0);
+
+    // Skipping YAML and ProtoBuf leaves only the GDS
+    let opts = ExportOptions {
+        proto: false,
+        yaml: false,
+        ..Default::default()
+    };
+    let artifacts = export_all(lib()?, SampleStacks::pdka()?, &dir, &opts)?;
+    assert_eq!(artifacts.paths, vec![dir.join("PipelineLib.gds")]);
+
+    // Enabling the floorplan view adds its GDS, smaller than the full one
+    let opts = ExportOptions {
+        floorplan: true,
+        ..opts
+    };
+    let artifacts = export_all(lib()?, SampleStacks::pdka()?, &dir, &opts)?;
+    assert_eq!(
+        artifacts.paths,
+        vec![
+            dir.join("PipelineLib.gds"),
+            dir.join("PipelineLib.floorplan.gds")
+        ]
+    );
+    let size = |name: &str| std::fs::metadata(dir.join(name)).unwrap().len();
+    assert!(size("PipelineLib.floorplan.gds") < size("PipelineLib.gds"));
+
+    // Enabling route summaries adds one per layout cell
+    let opts = ExportOptions {
+        floorplan: false,
+        routes: true,
+        ..opts
+    };
+    let artifacts = export_all(lib()?, SampleStacks::pdka()?, &dir, &opts)?;
+    assert_eq!(
+        artifacts.paths,
+        vec![dir.join("PipelineLib.gds"), dir.join("cell.routes.txt")]
+    );
+    let routes = std::fs::read_to_string(dir.join("cell.routes.txt"))?;
+    assert!(routes.starts_with("clk: met2 track 2"));
+
+    // Truncating long labels writes their mapping table alongside the GDS
+    let long = "core_top_u_fabric_u_switch_3_req_payload_127";
+    let gds_options = GdsExportOptions {
+        label_limit: Some(LabelLimit {
+            max_len: 32,
+            policy: LongLabelPolicy::Truncate,
+        }),
+        ..Default::default()
+    };
+    let opts = ExportOptions {
+        routes: false,
+        gds_options,
+        ..opts
+    };
+    let artifacts = export_all(lib_with(long)?, SampleStacks::pdka()?, &dir, &opts)?;
+    assert_eq!(
+        artifacts.paths,
+        vec![
+            dir.join("PipelineLib.gds"),
+            dir.join("PipelineLib.labels.txt")
+        ]
+    );
+    let short = &artifacts.labels[long];
+    assert_eq!(short.len(), 32);
+    let table = std::fs::read_to_string(dir.join("PipelineLib.labels.txt"))?;
+    assert_eq!(table, format!("{} {}\n", short, long));
+
+    // Unwritable destinations, here beneath an existing file, fail rather than panic
+    let unwritable = dir.join("PipelineLib.gds").join("out");
+    let err = export_all(lib()?, SampleStacks::pdka()?, &unwritable, &opts).unwrap_err();
+    assert!(matches!(err, LayoutError::Unwritable { .. }));
+    assert!(err.to_string().contains("could not create directory"));
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+/// Check output locations ahead of export, per [ExportOptions::preflight]
+#[cfg(all(feature = "gds", feature = "yaml"))]
+#[test]
+fn export_preflight() -> LayoutResult<()> {
+    use crate::pipeline::{export_all, file_stem, ExportOptions};
+    use crate::raw::LayoutError;
+    use std::path::Path;
+
+    let root = std::env::temp_dir().join("layout21tetris export_preflight");
+    let _ = std::fs::remove_dir_all(&root);
+    let lib = |name: &str| -> LayoutResult<Library> {
+        let mut lib = Library::new(name);
+        lib.cells.add(Layout::new("cell", 2, Outline::rect(5, 1)?));
+        Ok(lib)
+    };
+    let opts = ExportOptions::default();
+
+    // Missing directories are created by default, and left empty
+    let dir = root.join("out");
+    opts.preflight(&lib("Lib")?, &dir)?;
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+    // Or rejected, if creation is disabled
+    let strict = ExportOptions {
+        create_dir: false,
+        ..Default::default()
+    };
+    let err = strict.preflight(&lib("Lib")?, &root.join("missing"));
+    assert!(matches!(err, Err(LayoutError::Unwritable { .. })));
+
+    // Names are sanitized into valid file names, other than those which remain invalid
+    assert_eq!(file_stem("a/b:c\tX"), "a_b_c_X");
+    opts.preflight(&lib("top/sub:lib")?, &dir)?;
+    let invalid = |err: LayoutResult<()>| matches!(err, Err(LayoutError::InvalidPath { .. }));
+    assert!(invalid(opts.preflight(&lib("..")?, &dir)));
+    assert!(invalid(opts.preflight(&lib(&"x".repeat(300))?, &dir)));
+    // As are paths with invalid characters, before any conversion
+    let err = export_all(
+        lib("Lib")?,
+        SampleStacks::pdka()?,
+        Path::new("bad\0dir"),
+        &opts,
+    );
+    assert!(invalid(err.map(|_| ())));
+    // And distinct cell names which map to the same route-summary file
+    let routes = ExportOptions {
+        routes: true,
+        ..Default::default()
+    };
+    let mut dups = lib("Lib")?;
+    dups.cells
+        .add(Layout::new("top/sub", 2, Outline::rect(5, 1)?));
+    dups.cells
+        .add(Layout::new("top_sub", 2, Outline::rect(5, 1)?));
+    let err = routes.preflight(&dups, &dir).unwrap_err();
+    assert!(matches!(err, LayoutError::InvalidPath { .. }));
+    assert!(err.to_string().contains("top/sub and top_sub"));
+
+    // Existing files are never overwritten by the probe
+    let existing = dir.join(".Lib.0.preflight");
+    std::fs::write(&existing, b"keep")?;
+    opts.preflight(&lib("Lib")?, &dir)?;
+    assert_eq!(std::fs::read(&existing)?, b"keep");
+    std::fs::remove_file(&existing)?;
+
+    // Read-only directories are unwritable, for users without the privilege to override their permissions
+    let mut perms = std::fs::metadata(&dir)?.permissions();
+    perms.set_readonly(true);
+    std::fs::set_permissions(&dir, perms.clone())?;
+    let privileged = std::fs::write(dir.join("probe"), b"").is_ok();
+    let err = opts.preflight(&lib("Lib")?, &dir);
+    if privileged {
+        assert!(err.is_ok());
+    } else {
+        assert!(matches!(err, Err(LayoutError::Unwritable { .. })));
+    }
+    #[allow(clippy::permissions_set_readonly_false)]
+    perms.set_readonly(false);
+    std::fs::set_permissions(&dir, perms)?;
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+/// Compare separately created, and then mutated, [Layout]s and [Cell]s
+#[test]
+fn layout_eq() -> LayoutResult<()> {
+    let create = || -> LayoutResult<Cell> {
+        let mut layout = Layout::new("eq", 2, Outline::rect(5, 5)?);
+        layout
+            .net("clk")
+            .at(LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Below);
+        layout.cut(LayerIndex(0), TrackIndex(1), TrackIndex(1), RelZ::Above);
+        Ok(layout.into())
+    };
+    let cell = create()?;
+    assert_eq!(cell, create()?);
+    let mut cell2 = create()?;
+    cell2.layout.as_mut().unwrap().assignments[0].net = "rst".into();
+    assert_ne!(cell, cell2);
+    assert_eq!(
+        cell.layout.as_ref().unwrap().cuts,
+        cell2.layout.as_ref().unwrap().cuts
+    );
+    Ok(())
+}
+/// Parse and print each of our small enums, and check the geometric relations between [Side]s and [Dir]s
+#[test]
+fn enum_parsing() -> LayoutResult<()> {
+    use crate::placement::Side;
+    use crate::raw::Dir;
+
+    for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+        assert_eq!(side.to_string().parse::<Side>()?, side);
+        assert_eq!(side.to_string().to_lowercase().parse::<Side>()?, side);
+        assert_eq!(side.opposite().opposite(), side);
+        assert_ne!(side.opposite(), side);
+        assert_eq!(side.opposite().dir(), side.dir());
+        let (s0, s1) = Side::along(side.dir());
+        assert!(s0 == side || s1 == side);
+    }
+    assert_eq!(Side::Left.dir(), Dir::Vert);
+    assert_eq!(Side::Top.dir(), Dir::Horiz);
+    assert_eq!(Side::along(Dir::Horiz), (Side::Bottom, Side::Top));
+    assert!("middle".parse::<Side>().is_err());
+
+    for side in [abs::Side::BottomOrLeft, abs::Side::TopOrRight] {
+        assert_eq!(side.to_string().to_uppercase().parse::<abs::Side>()?, side);
+        assert_ne!(side.opposite(), side);
+    }
+    for relz in [RelZ::Above, RelZ::Below] {
+        assert_eq!(relz.to_string().to_uppercase().parse::<RelZ>()?, relz);
+    }
+    for ttype in [
+        TrackType::Gap,
+        TrackType::Signal,
+        TrackType::Rail(RailKind::Pwr),
+        TrackType::Rail(RailKind::Gnd),
+    ] {
+        assert_eq!(ttype.to_string().parse::<TrackType>()?, ttype);
+    }
+    assert_eq!("VSS".parse::<TrackType>()?, TrackType::Rail(RailKind::Gnd));
+    assert!("wire".parse::<TrackType>().is_err());
+    Ok(())
+}
+/// Locate the ports of placed, reflected abstract-instances in their parent's frame
+#[test]
+fn instance_port_loc() -> LayoutResult<()> {
+    use crate::coords::PrimPitches;
+    use crate::instance::PortLoc;
+
+    let stack = SampleStacks::pdka()?;
+    let mut abs = abs::Abstract::new("leaf", 2, Outline::rect(4, 1)?);
+    abs.ports.push(abs::Port::ztop_edge(
+        "z",
+        TrackIndex(1),
+        abs::Side::BottomOrLeft,
+        TrackIndex(2),
+        RelZ::Below,
+    ));
+    abs.ports.push(abs::Port::edge(
+        "e",
+        LayerIndex(0),
+        TrackIndex(2),
+        abs::Side::TopOrRight,
+    ));
+    let leaf = Ptr::new(Cell::from(abs));
+    let inst = |loc: (isize, isize), reflect_horiz: bool, reflect_vert: bool| {
+        Instance::builder()
+            .inst_name("i")
+            .cell(leaf.clone())
+            .loc(loc)
+            .reflect_horiz(reflect_horiz)
+            .reflect_vert(reflect_vert)
+            .build()
+            .unwrap()
+    };
+    let ztop = |i: &Instance| -> LayoutResult<(usize, (usize, usize))> {
+        match i.port_loc("z", &stack)? {
+            PortLoc::ZTopEdge { track, range } => {
+                assert_eq!(track.layer, LayerIndex(1));
+                assert_eq!(range.0.layer, LayerIndex(0));
+                Ok((
+                    track.track.index(),
+                    (range.0.track.index(), range.1.track.index()),
+                ))
+            }
+            _ => panic!("Expected a ZTopEdge port"),
+        }
+    };
+    // At the origin, port tracks are those of the abstract
+    assert_eq!(ztop(&inst((0, 0), false, false))?, (1, (0, 2)));
+    // Placed elsewhere, they shift by the instance location
+    assert_eq!(ztop(&inst((10, 2), false, false))?, (11, (12, 14)));
+    // And reflected, they mirror about it
+    assert_eq!(ztop(&inst((10, 2), true, false))?, (9, (12, 14)));
+    assert_eq!(ztop(&inst((10, 2), true, true))?, (9, (10, 12)));
+    assert_eq!(
+        inst((10, 2), true, true).port_loc("z", &stack)?.layer(),
+        LayerIndex(1)
+    );
+
+    // Edge ports carry the parent-frame edge coordinate, and side
+    let edge = inst((10, 2), false, false).port_loc("e", &stack)?;
+    assert_eq!(
+        edge,
+        PortLoc::Edge {
+            track: TrackRef::new(LayerIndex(0), TrackIndex(14)),
+            side: abs::Side::TopOrRight,
+            at: PrimPitches::x(14),
+        }
+    );
+    match inst((10, 2), true, false).port_loc("e", &stack)? {
+        PortLoc::Edge { track, side, at } => {
+            assert_eq!(track, TrackRef::new(LayerIndex(0), TrackIndex(14)));
+            assert_eq!(side, abs::Side::BottomOrLeft);
+            assert_eq!(at, PrimPitches::x(6));
+        }
+        _ => panic!("Expected an Edge port"),
+    }
+
+    // Unknown ports, cells without abstracts, and off-track transforms fail
+    assert!(inst((10, 2), false, false)
+        .port_loc("nope", &stack)
+        .is_err());
+    assert!(inst((10, 0), false, true).port_loc("e", &stack).is_err());
+    let no_abs = Instance::builder()
+        .inst_name("l")
+        .cell(Ptr::new(Cell::from(Layout::new(
+            "lay",
+            2,
+            Outline::rect(4, 1)?,
+        ))))
+        .loc((0, 0))
+        .build()?;
+    assert!(no_abs.port_loc("z", &stack).is_err());
+    Ok(())
+}
+/// Transform ports of instances near the parent origin onto negative tracks, which fail rather than wrap
+#[test]
+fn negative_port_tracks() -> LayoutResult<()> {
+    use crate::coords::{SignedTrack, TrackIndex};
+
+    let stack = SampleStacks::pdka()?;
+    let mut abs = abs::Abstract::new("leaf", 2, Outline::rect(4, 1)?);
+    abs.ports.push(abs::Port::ztop_edge(
+        "z",
+        TrackIndex(1),
+        abs::Side::BottomOrLeft,
+        TrackIndex(2),
+        RelZ::Below,
+    ));
+    let leaf = Ptr::new(Cell::from(abs));
+    let inst = |loc: (isize, isize), reflect_horiz: bool, reflect_vert: bool| {
+        Instance::builder()
+            .inst_name("i")
+            .cell(leaf.clone())
+            .loc(loc)
+            .reflect_horiz(reflect_horiz)
+            .reflect_vert(reflect_vert)
+            .build()
+            .unwrap()
+    };
+    let err = |i: Instance| format!("{}", i.port_loc("z", &stack).unwrap_err());
+
+    // Reflected about the origin, the port track lands left of the parent grid
+    let msg = err(inst((0, 0), true, false));
+    assert!(msg.contains("reflects to track -1 of layer 1, outside the parent grid"));
+    // As does its range of adjacent-layer tracks, when reflected vertically
+    let msg = err(inst((4, 0), false, true));
+    assert!(msg.contains("reflects to track -2 of layer 0, outside the parent grid"));
+    // Negative locations fail similarly, without reflection
+    let msg = err(inst((-2, 0), false, false));
+    assert!(msg.contains("moves to track -1 of layer 1"));
+    // While reflections which land back on the grid succeed
+    assert!(inst((2, 2), true, true).port_loc("z", &stack).is_ok());
+
+    // Signed tracks convert to unsigned indices only when non-negative
+    assert_eq!(SignedTrack(3).index(), Some(TrackIndex(3)));
+    assert_eq!(SignedTrack(-3).index(), None);
+    assert_eq!(
+        SignedTrack::from(TrackIndex(2)) - SignedTrack(5),
+        SignedTrack(-3)
+    );
+    // And the layer-level lookup accepts negative coordinates
+    let met2 = stack.metal(LayerIndex(1))?;
+    let center = met2.center(TrackIndex(1))?;
+    assert_eq!(met2.signed_track_at(center), Some(SignedTrack(1)));
+    let nsig = met2.period_data.signals.len() as isize;
+    assert_eq!(
+        met2.signed_track_at(center - met2.pitch * 2_usize),
+        Some(SignedTrack(1 - 2 * nsig))
+    );
+    Ok(())
+}
+/// Check conversion of vacuous setups: stacks without metals, and cells with no metal layers
+#[test]
+fn no_metal_layers() -> LayoutResult<()> {
+    use conv::raw::{RawExportOptions, RawExporter};
+    let opts = RawExportOptions::default();
+    let lib_of = |layout: Layout| {
+        let mut lib = Library::new("no_metal_layers");
+        lib.cells.add(layout);
+        lib
+    };
+    let empty = || Layout::new("empty", 0, Outline::rect(5, 1).unwrap());
+
+    // A stack with no metal layers fails, regardless of the library
+    let err = RawExporter::convert(lib_of(empty()), SampleStacks::empty()?).unwrap_err();
+    assert!(format!("{}", err).contains("no metal layers"));
+
+    // A cell with no metal layers, but with assignments, fails naming them
+    let mut layout = empty();
+    layout
+        .net("clk")
+        .at(LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Below);
+    let err = RawExporter::convert(lib_of(layout), SampleStacks::pdka()?).unwrap_err();
+    assert!(format!("{}", err).contains("assignments to nets clk"));
+    // As does one with a cut on one of its absent layers
+    let mut layout = empty();
+    layout.cut(LayerIndex(0), TrackIndex(1), TrackIndex(1), RelZ::Above);
+    assert!(RawExporter::convert(lib_of(layout), SampleStacks::pdka()?).is_err());
+
+    // And an empty one converts to only its boundary, with a warning
+    let (rawlib, report) =
+        RawExporter::convert_with_options(lib_of(empty()), SampleStacks::pdka()?, &opts)?;
+    assert_eq!(report.warnings.len(), 1);
+    assert!(report.warnings[0].contains("empty"));
+    let rawlib = rawlib.read()?;
+    let rawcell = rawlib.cells[0].read()?;
+    let elems = &rawcell.layout.as_ref().unwrap().elems;
+    assert_eq!(elems.len(), 1);
+    assert_eq!(elems[0].purpose, crate::raw::LayerPurpose::Outline);
+    Ok(())
+}
+/// Find the elements of an assigned net in converted output
+#[test]
+fn find_converted_net() -> LayoutResult<()> {
+    let mut lib = Library::new("find_converted_net");
+    let mut layout = Layout::new("cell", 2, Outline::rect(5, 1)?);
+    layout
+        .net("clk")
+        .at(LayerIndex(1), TrackIndex(2), TrackIndex(3), RelZ::Below);
+    lib.cells.add(layout);
+    let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let found = rawlib.find_net("clk")?;
+    assert!(!found.is_empty());
+    assert!(found.iter().all(|(cell, _)| cell == "cell"));
+    let cell = rawlib.cells[0].read()?;
+    assert_eq!(cell.elements_by_net("clk").len(), found.len());
+    assert_eq!(rawlib.find_net("cl?")?.len(), found.len());
+    Ok(())
+}
+/// Suppress rails on one layer of one cell, leaving its other layers and other cells intact
+#[test]
+fn suppress_rails() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka()?;
+    let (met1, met3) = (
+        stack.metal(LayerIndex(0))?.raw.unwrap(),
+        stack.metal(LayerIndex(2))?.raw.unwrap(),
+    );
+    let mut lib = Library::new("suppress_rails");
+    for (name, suppress) in [("railed", None), ("unrailed", Some(0))] {
+        let mut builder = Cell::builder(name)
+            .top_layer(LayerIndex(2))
+            .outline(Outline::rect(5, 2)?)
+            .assign(
+                "clk",
+                LayerIndex(1),
+                TrackIndex(2),
+                TrackIndex(3),
+                RelZ::Below,
+            );
+        if let Some(layer) = suppress {
+            builder = builder.suppress_rails(layer);
+        }
+        lib.cells.add(builder.build()?);
+    }
+    assert!(Cell::builder("bad")
+        .top_layer(LayerIndex(1))
+        .outline(Outline::rect(5, 2)?)
+        .suppress_rails(2)
+        .build()
+        .is_err());
+
+    let rawlib = conv::raw::RawExporter::convert(lib, stack)?;
+    let rawlib = rawlib.read()?;
+    // Count (met1 rails, met3 rails, assigned signals) per cell
+    let counts = |name: &str| -> LayoutResult<(usize, usize, usize)> {
+        let ptr = rawlib.cell_named(name)?.unwrap();
+        let cell = ptr.read()?;
+        let rails = |layer| {
+            cell.elements_by_net("VDD")
+                .into_iter()
+                .chain(cell.elements_by_net("VSS"))
+                .filter(|e| e.layer == layer)
+                .count()
+        };
+        Ok((rails(met1), rails(met3), cell.elements_by_net("clk").len()))
+    };
+    let railed = counts("railed")?;
+    let unrailed = counts("unrailed")?;
+    assert!(railed.0 > 0 && railed.1 > 0);
+    assert_eq!(unrailed.0, 0);
+    assert_eq!(unrailed.1, railed.1);
+    assert_eq!(unrailed.2, railed.2);
+    Ok(())
+}
+/// Export a [abs::PortKind::ZTopInner] port with several landing points, to raw and LEF
+#[cfg(feature = "lef")]
+#[test]
+fn ztop_inner_ports() -> LayoutResult<()> {
+    use crate::abs::{Abstract, Port, TopLoc};
+    use crate::raw::lef::LefExporter;
+
+    let locs = || {
+        (0..3)
+            .map(|track| TopLoc::new(TrackIndex(track), TrackIndex(1), RelZ::Below))
+            .collect::<Vec<_>>()
+    };
+    let mut abs = Abstract::new("multi", 2, Outline::rect(4, 1)?);
+    abs.ports.push(Port::ztop_inner("z", locs()));
+    let mut lib = Library::new("ztop_inner_ports");
+    let multi = lib.cells.insert(Cell::from(abs));
+
+    // Landing points are placed over the top of the instance, each with escapes on the layer above
+    let parent = Cell::builder("parent")
+        .top_layer(LayerIndex(2))
+        .outline(Outline::rect(12, 3)?)
+        .instance(
+            Instance::builder()
+                .inst_name("i")
+                .cell(multi.clone())
+                .loc((4, 1))
+                .build()?,
+        )
+        .build()?;
+    let report = parent.escape_analysis("i", &SampleStacks::pdka()?)?;
+    assert!(report.is_clean());
+    assert!(report.ports[0].escapes.len() >= 2);
+    assert!(report.ports[0]
+        .escapes
+        .iter()
+        .all(|e| e.track.layer == LayerIndex(2)));
+
+    // Each landing point becomes a shape of the single raw port
+    let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let cell = rawlib.cell_named("multi")?.unwrap();
+    let cell = cell.read()?;
+    let rawabs = cell.abs.as_ref().unwrap();
+    assert_eq!(rawabs.ports.len(), 1);
+    assert_eq!(rawabs.ports[0].net, "z");
+    let shapes: Vec<_> = rawabs.ports[0].shapes.values().collect();
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].len(), 3);
+    // And a single LEF pin, with one rectangle per landing point.
+    // The sample stack's raw layers are numbered but un-named; name them for LEF.
+    for (_, layer) in rawlib.layers.write()?.slots.iter_mut() {
+        layer.name = Some(format!("layer{}", layer.layernum));
+    }
+    let lef = LefExporter::export(&rawlib)?;
+    let pins = &lef.macros[0].pins;
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].ports.len(), 1);
+    assert_eq!(pins[0].ports[0].layers[0].geometries.len(), 3);
+
+    // Empty and off-outline locations fail validation
+    for locs in [
+        Vec::new(),
+        vec![TopLoc::new(TrackIndex(0), TrackIndex(100), RelZ::Below)],
+    ] {
+        let mut abs = Abstract::new("bad", 2, Outline::rect(4, 1)?);
+        abs.ports.push(Port::ztop_inner("z", locs));
+        let mut lib = Library::new("bad");
+        lib.cells.insert(Cell::from(abs));
+        assert!(conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?).is_err());
+    }
+    Ok(())
+}
+/// Generate synthetic fixture libraries, which validate and are reproducible per seed
+#[test]
+fn fixture_libraries() -> LayoutResult<()> {
+    use crate::fixtures::grid_library;
+    use crate::raw;
+    use crate::validate::validate_lib;
+
+    let lib = grid_library(5, 12, 20, 1)?;
+    assert_eq!(lib.cells.len(), 6);
+    validate_lib(&lib, &SampleStacks::pdka()?)?;
+    {
+        let top = lib.cells.iter().last().unwrap().read()?;
+        let layout = top.layout.as_ref().unwrap();
+        assert_eq!(layout.instances.len(), 5);
+        assert_eq!(layout.assignments.len(), 20);
+        assert_eq!(layout.cuts.len(), 10);
+    }
+
+    // Identical seeds produce identical output, and others do not
+    let convert = |lib| -> LayoutResult<raw::Library> {
+        let rawlib = conv::raw::RawExporter::convert(lib, SampleStacks::pdka()?)?;
+        let rawlib = rawlib.read()?.clone();
+        Ok(rawlib)
+    };
+    let first = convert(lib)?;
+    assert_eq!(first, convert(grid_library(5, 12, 20, 1)?)?);
+    assert_ne!(first, convert(grid_library(5, 12, 20, 2)?)?);
+    Ok(())
+}
+/// Grab the full path of resource-file `fname`
+#[cfg(any(feature = "gds", feature = "yaml"))]
+fn resource(rname: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join(rname)
+}
+/// Check [Track::cut] and [Track::cut_many] are idempotent, merge overlapping cuts, and are independent of cut-order
+#[test]
+fn track_cut_order() -> LayoutResult<()> {
+    use crate::coords::DbUnits;
+
+    let stack = SampleStacks::pdka()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    let period = met1.spec.to_layer_period(0, DbUnits(10_000))?;
+    let track = period.signals[0].clone();
+
+    let src = |n: usize| {
+        TrackCross::from_parts(LayerIndex(0), TrackIndex(0), LayerIndex(1), TrackIndex(n))
+    };
+    let cuts: Vec<(DbUnits, DbUnits, TrackCross)> = vec![
+        (DbUnits(100), DbUnits(200), src(1)),
+        (DbUnits(150), DbUnits(250), src(2)),
+        (DbUnits(100), DbUnits(200), src(3)), // Duplicate extent
+        (DbUnits(250), DbUnits(300), src(4)), // Abutting
+        (DbUnits(500), DbUnits(600), src(5)),
+        (DbUnits(520), DbUnits(540), src(6)),      // Contained
+        (DbUnits(9_900), DbUnits(10_000), src(7)), // At the track's end
+    ];
+    // Apply one at a time, in order, as the reference
+    let mut expected = track.clone();
+    for (start, stop, src) in cuts.iter() {
+        expected.cut(*start, *stop, src).unwrap();
+    }
+    let cut_extents: Vec<_> = expected
+        .segments
+        .iter()
+        .filter(|s| matches!(s.tp, TrackSegmentType::Cut { .. }))
+        .map(|s| (s.start, s.stop))
+        .collect();
+    assert_eq!(
+        cut_extents,
+        vec![
+            (DbUnits(100), DbUnits(300)),
+            (DbUnits(500), DbUnits(600)),
+            (DbUnits(9_900), DbUnits(10_000)),
+        ]
+    );
+    // Merged cuts are attributed to their earliest-starting constituent
+    assert_eq!(
+        expected.segment_at(DbUnits(200)).unwrap().tp,
+        TrackSegmentType::Cut { src: src(1) }
+    );
+
+    // Shuffle the cuts, and check both single and batch cutting produce the same segments
+    let mut state: u64 = 0x5eed;
+    let mut shuffled = cuts.clone();
+    for _ in 0..64 {
+        for i in (1..shuffled.len()).rev() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            shuffled.swap(i, (state >> 33) as usize % (i + 1));
+        }
+        let mut single = track.clone();
+        for (start, stop, src) in shuffled.iter() {
+            single.cut(*start, *stop, src).unwrap();
+        }
+        assert_eq!(single, expected);
+        let mut batch = track.clone();
+        batch.cut_many(&shuffled).unwrap();
+        assert_eq!(batch, expected);
+    }
+
+    // Cutting again is idempotent
+    let mut again = expected.clone();
+    again.cut_many(&cuts).unwrap();
+    assert_eq!(again, expected);
+    again.cut(DbUnits(120), DbUnits(180), &src(8)).unwrap();
+    assert_eq!(again, expected);
+
+    // Cuts overlapping a blockage still conflict
+    let mut blocked = track.clone();
+    blocked
+        .block(DbUnits(1_000), DbUnits(2_000), "inst")
+        .unwrap();
+    assert!(blocked.cut(DbUnits(900), DbUnits(1_100), &src(1)).is_err());
+    Ok(())
+}
+/// Check recursive instantiation and unreadable instance-cells produce errors, rather than overflowing or panicking
+#[test]
+fn dep_order_errors() -> LayoutResult<()> {
+    let inst = |name: &str, cell: &Ptr<Cell>| -> LayoutResult<Instance> {
+        Ok(Instance::builder()
+            .inst_name(name)
+            .cell(cell.clone())
+            .loc((0, 0))
+            .build()?)
+    };
+    // Create a pair of cells, each instantiating the other
+    let mut lib = Library::new("dep_order_errors");
+    let a = lib.cells.insert(
+        Cell::builder("a")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .build()?,
+    );
+    let b = lib.cells.insert(
+        Cell::builder("b")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .instance(inst("a_inst", &a)?)
+            .build()?,
+    );
+    let b_inst = inst("b_inst", &b)?;
+    a.write()?.layout.as_mut().unwrap().instances.add(b_inst);
+    let err = lib.dep_order().unwrap_err();
+    assert!(format!("{}", err).contains("recursively instantiates"));
+    assert!(lib.to_raw(SampleStacks::pdka()?).is_err());
+
+    // Poison the lock of a cell reachable only through an instance, and check for an error naming the instance
+    let mut lib = Library::new("dep_order_errors");
+    let child = Ptr::new(
+        Cell::builder("child")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .build()?,
+    );
+    lib.cells.insert(
+        Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .instance(inst("child_inst", &child)?)
+            .build()?,
+    );
+    let poisoner = child.clone();
+    let _ = std::thread::spawn(move || {
+        let _guard = poisoner.write().unwrap();
+        panic!("Poisoning the child cell");
+    })
+    .join();
+    let err = lib.dep_order().unwrap_err();
+    assert!(format!("{}", err).contains("child_inst"));
+    Ok(())
+}
+/// Check [Library::placements] composes offsets and reflections through a three-level hierarchy
+#[test]
+fn library_placements() -> LayoutResult<()> {
+    use crate::coords::{PrimPitches, Xy};
+    use crate::library::CellId;
+
+    let mut lib = Library::new("library_placements");
+    let alu = lib.cells.insert(abs::Abstract {
+        name: "alu".into(),
+        metals: 1,
+        outline: Outline::rect(1, 1)?,
+        ports: Vec::new(),
+    });
+    let core = lib.cells.insert(
+        Cell::builder("core")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 4)?)
+            .instance(
+                Instance::builder()
+                    .inst_name("u_alu")
+                    .cell(alu.clone())
+                    .loc((1, 2))
+                    .reflect_horiz(true)
+                    .build()?,
+            )
+            .build()?,
+    );
+    let top = lib.cells.insert(
+        Cell::builder("top")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(10, 10)?)
+            .instance(
+                Instance::builder()
+                    .inst_name("u_core")
+                    .cell(core.clone())
+                    .loc((2, 3))
+                    .reflect_vert(true)
+                    .build()?,
+            )
+            .instance(
+                Instance::builder()
+                    .inst_name("u_pad")
+                    .cell(alu.clone())
+                    .loc((5, 5))
+                    .build()?,
+            )
+            .build()?,
+    );
+    let placements = lib.placements(lib.cell_id(&top).unwrap())?;
+    assert_eq!(placements.len(), 2);
+    let alu_place = &placements[0];
+    assert_eq!(alu_place.path, "top/u_core/u_alu");
+    assert_eq!(alu_place.cell, alu);
+    assert_eq!(alu_place.depth, 2);
+    // The core's vertical reflection flips the alu's y-offset
+    assert_eq!(alu_place.loc, Xy::new(PrimPitches::x(3), PrimPitches::y(1)));
+    assert!(alu_place.reflect_horiz && alu_place.reflect_vert);
+    let pad_place = &placements[1];
+    assert_eq!(pad_place.path, "top/u_pad");
+    assert_eq!(pad_place.depth, 1);
+    assert_eq!(pad_place.loc, Xy::new(PrimPitches::x(5), PrimPitches::y(5)));
+    assert!(!pad_place.reflect_horiz && !pad_place.reflect_vert);
+
+    // Leaves place themselves at the origin, and unknown cells fail
+    let leaf = lib.placements(lib.cell_id(&alu).unwrap())?;
+    assert_eq!(leaf.len(), 1);
+    assert_eq!(leaf[0].path, "alu");
+    assert_eq!(leaf[0].depth, 0);
+    assert!(lib.placements(CellId(99)).is_err());
+    Ok(())
+}
+/// Update a [Library]'s [Stack], re-validating its cells and rolling back on failure
+#[test]
+fn update_stack() -> LayoutResult<()> {
+    use crate::raw::Dir;
+
+    let full = SampleStacks::pdka_stack()?;
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals.truncate(4);
+    stack.vias.truncate(4);
+    let mut lib = Library::new("update_stack");
+    lib.cells
+        .insert(Layout::new("uses_met3", 3, Outline::rect(10, 2)?));
+
+    // Adding met5 succeeds
+    let valid = lib.update_stack(&mut stack, |s| {
+        s.metals.push(full.metals[4].clone());
+        s.vias.push(full.vias[4].clone());
+    })?;
+    assert_eq!(valid.num_metals(), 5);
+    assert_eq!(stack.metals.len(), 5);
+
+    // Removing met3 (and above) breaks `uses_met3`, and is rolled back
+    let err = lib
+        .update_stack(&mut stack, |s| {
+            s.metals.truncate(2);
+            s.vias.truncate(2);
+        })
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("uses_met3: Cell uses_met3 uses 3 metal layers, more than the Stack's 2"));
+    assert_eq!(stack.metals.len(), 5);
+    assert_eq!(stack.vias.len(), 5);
+
+    // As is an update which invalidates the stack itself
+    let err = lib
+        .update_stack(&mut stack, |s| s.metals[1].dir = Dir::Horiz)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Invalid Stack update"));
+    assert_eq!(stack.metals[1].dir, Dir::Vert);
+    Ok(())
+}
+/// Feed each of the [malformed_libraries](crate::fixtures::malformed_libraries) through validation and conversion,
+/// checking that none panic
+#[cfg(feature = "gds")]
+#[test]
+fn malformed_no_panic() -> LayoutResult<()> {
+    use crate::conv::raw::RawExporter;
+    use crate::validate::validate_lib;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    for case in crate::fixtures::malformed_libraries()? {
+        let name = case.name;
+        let res = catch_unwind(AssertUnwindSafe(|| -> LayoutResult<()> {
+            let stack = case.stack.validate()?;
+            // Validation may pass or fail, e.g. on relative placements, but must not panic
+            let _ = validate_lib(&case.lib, &stack);
+            let rawlib = RawExporter::convert(case.lib, stack)?;
+            rawlib.read()?.to_gds()?;
+            Ok(())
+        }));
+        assert!(res.is_ok(), "Malformed library {} panicked", name);
+    }
+    Ok(())
+}
+/// Name and unify nets across a two-level hierarchy, with one promoted and one assigned connection
+#[test]
+fn net_scope() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka()?;
+    // The child cell: an abstract with a top-layer output and an edge input, plus an internal net
+    let mut abs = abs::Abstract::new("inv", 2, Outline::rect(4, 1)?);
+    abs.ports.push(abs::Port::ztop_edge(
+        "out",
+        TrackIndex(1),
+        abs::Side::BottomOrLeft,
+        TrackIndex(2),
+        RelZ::Below,
+    ));
+    abs.ports.push(abs::Port::edge(
+        "in",
+        LayerIndex(0),
+        TrackIndex(2),
+        abs::Side::TopOrRight,
+    ));
+    let mut inv = Cell::from(abs);
+    let mut layout = Layout::new("inv", 2, Outline::rect(4, 1)?);
+    layout.assign(
+        "mid",
+        LayerIndex(1),
+        TrackIndex(0),
+        TrackIndex(0),
+        RelZ::Below,
+    );
+    inv.layout = Some(layout);
+    let inv = Ptr::new(inv);
+
+    // The parent, promoting `u_inv1/out` to its port `y`, and assigning net `a` onto `u_inv2/out`
+    let inst = |name: &str, loc: (isize, isize)| {
+        Instance::builder()
+            .inst_name(name)
+            .cell(inv.clone())
+            .loc(loc)
+            .build()
+    };
+    let mut layout = Layout::new("top", 2, Outline::rect(20, 1)?);
+    layout.instances.add(inst("u_inv1", (0, 0))?);
+    layout.instances.add(inst("u_inv2", (10, 0))?);
+    layout.assign(
+        "a",
+        LayerIndex(1),
+        TrackIndex(11),
+        TrackIndex(1),
+        RelZ::Below,
+    );
+    let mut top = Cell::from(layout);
+    let mut abs = abs::Abstract::new("top", 2, Outline::rect(20, 1)?);
+    abs.ports.push(abs::Port::ztop_edge(
+        "y",
+        TrackIndex(1),
+        abs::Side::BottomOrLeft,
+        TrackIndex(2),
+        RelZ::Below,
+    ));
+    top.abs = Some(abs);
+
+    let scope = top.net_scope(&stack, "/")?;
+    assert_eq!(
+        scope.nets(),
+        vec![
+            "a",
+            "u_inv1/in",
+            "u_inv1/mid",
+            "u_inv2/in",
+            "u_inv2/mid",
+            "y"
+        ]
+    );
+    let path = |inst: &str| vec![inst.to_string()];
+    assert_eq!(scope.resolve(&path("u_inv1"), "out"), Some("y"));
+    assert_eq!(scope.resolve(&path("u_inv2"), "out"), Some("a"));
+    assert_eq!(scope.resolve(&path("u_inv2"), "mid"), Some("u_inv2/mid"));
+    assert_eq!(scope.resolve(&path("u_inv3"), "out"), None);
+    assert_eq!(scope.floating(), vec!["u_inv1/in", "u_inv2/in"]);
+
+    // Separators are configurable
+    let scope = top.net_scope(&stack, ".")?;
+    assert!(scope.nets().contains(&"u_inv1.mid".to_string()));
+    let path = vec!["u_top".to_string(), "u_inv1".to_string()];
+    assert_eq!(scope.name(&path, "out"), "u_top.u_inv1.out");
+    Ok(())
+}
+/// Block only the parent tracks occupied by the top-layer shapes of a raw macro
+#[test]
+fn raw_track_occupancy() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::coords::{DbUnits, TrackIndex};
+    use crate::occupancy::{RawTrackOccupancy, TrackOccupancy};
+    use crate::raw;
+
+    let stack = SampleStacks::pdka()?;
+    let met2 = stack.metal(LayerIndex(1))?;
+    // A two-metal macro, with met2 shapes over its tracks 1 and 3, plus a label which is ignored
+    let rect = |track: usize, purpose: raw::LayerPurpose| -> LayoutResult<raw::Element> {
+        let (x0, x1) = met2.span(TrackIndex(track))?;
+        Ok(raw::Element {
+            net: None,
+            layer: met2.spec.raw.unwrap(),
+            purpose,
+            inner: raw::Shape::Rect(raw::Rect {
+                p0: raw::Point::new(x0.0, 100),
+                p1: raw::Point::new(x1.0, 2000),
+            }),
+            labels: Vec::new(),
+        })
+    };
+    let mut rawcell = raw::Cell::new("macro");
+    rawcell.layout = Some(raw::Layout {
+        name: "macro".into(),
+        elems: vec![
+            rect(1, raw::LayerPurpose::Drawing)?,
+            rect(3, raw::LayerPurpose::Drawing)?,
+            rect(5, raw::LayerPurpose::Label)?,
+        ],
+        ..Default::default()
+    });
+    let occupied = rawcell.occupied_tracks(&stack, 1)?;
+    assert_eq!(
+        occupied,
+        vec![
+            TrackOccupancy {
+                track: TrackIndex(1),
+                intervals: vec![(DbUnits(100), DbUnits(2000))],
+            },
+            TrackOccupancy {
+                track: TrackIndex(3),
+                intervals: vec![(DbUnits(100), DbUnits(2000))],
+            },
+        ]
+    );
+    // Nothing lands on met1's tracks
+    assert!(rawcell.occupied_tracks(&stack, 0)?.is_empty());
+
+    // Instantiate it, offset by four tracks, and check which parent tracks are blocked
+    let blocked = |reflect_vert: bool| -> LayoutResult<Vec<(usize, DbUnits, DbUnits)>> {
+        let stack = SampleStacks::pdka()?;
+        let mut lib = Library::new("raw_track_occupancy");
+        let mut rawlib = raw::Library::new("macros", stack.units);
+        rawlib.layers = stack.rawlayers.clone().unwrap();
+        rawlib.cells.add(rawcell.clone());
+        lib.add_rawlib(rawlib);
+        let mac = lib.wrap_raw_cell("macro", Outline::rect(10, 1)?, 2)?;
+        let inst = Instance::builder()
+            .inst_name("m")
+            .cell(mac)
+            .loc((4, if reflect_vert { 1 } else { 0 }))
+            .reflect_vert(reflect_vert)
+            .build()?;
+        let parent = Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(20, 1)?)
+            .instance(inst)
+            .build()?;
+        lib.cells.insert(parent);
+        let opts = RawExportOptions {
+            route_db: true,
+            ..Default::default()
+        };
+        let (_, report) = RawExporter::convert_with_options(lib, stack, &opts)?;
+        let routes = report.route_db("parent").unwrap();
+        let mut blocked = Vec::new();
+        for period in routes.layers[1].periods.iter() {
+            for seg in period.signals[0].segments.iter() {
+                if let TrackSegmentType::Blockage { .. } = seg.tp {
+                    blocked.push((period.index, seg.start, seg.stop));
+                }
+            }
+        }
+        Ok(blocked)
+    };
+    assert_eq!(
+        blocked(false)?,
+        vec![
+            (5, DbUnits(100), DbUnits(2000)),
+            (7, DbUnits(100), DbUnits(2000)),
+        ]
+    );
+    // Vertical reflection mirrors the blocked intervals along the tracks
+    assert_eq!(
+        blocked(true)?,
+        vec![
+            (5, DbUnits(720), DbUnits(2620)),
+            (7, DbUnits(720), DbUnits(2620)),
+        ]
+    );
+    Ok(())
+}
+/// Resolve assignments in the far corners of a many-period cell into their expected rows and columns
+#[test]
+fn far_corner_assignments() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka()?;
+    let (met1, met2) = (stack.metal(LayerIndex(0))?, stack.metal(LayerIndex(1))?);
+    // A 10x10 cell spans ten periods of each of met1 (six tracks per period) and met2 (one per period)
+    let outline = Outline::rect(10, 10)?;
+    assert_eq!(stack.signal_tracks(LayerIndex(0), &outline)?, 60);
+    assert_eq!(stack.signal_tracks(LayerIndex(1), &outline)?, 10);
+    let corners = [("sw", 0, 0), ("se", 9, 0), ("nw", 0, 59), ("ne", 9, 59)];
+    let mut builder = Cell::builder("corners")
+        .top_layer(LayerIndex(1))
+        .outline(outline);
+    for (net, col, row) in corners.iter() {
+        builder = builder.assign(
+            *net,
+            LayerIndex(1),
+            TrackIndex(*col),
+            TrackIndex(*row),
+            RelZ::Below,
+        );
+    }
+    let mut lib = Library::new("far_corner_assignments");
+    lib.add_cell(builder.build()?);
+
+    // Both sides of each crossing locate into the same (period, index) pairs as conversion
+    assert_eq!(met1.locate(TrackIndex(59))?, (9, 5));
+    assert_eq!(met2.locate(TrackIndex(9))?, (9, 0));
+
+    let rawlib = lib.to_raw(SampleStacks::pdka()?)?;
+    let rawlib = rawlib.read()?;
+    let cell = rawlib.cells[0].read()?;
+    let elems = &cell.layout.as_ref().unwrap().elems;
+    for (net, col, row) in corners.iter() {
+        // Find the via, the only 240x240 rectangle on the net
+        let vias: Vec<crate::raw::Point> = elems
+            .iter()
+            .filter(|e| e.net.as_deref() == Some(*net))
+            .filter_map(|e| match e.inner {
+                crate::raw::Shape::Rect(ref r) if r.p1.x - r.p0.x == 240 => Some(
+                    crate::raw::Point::new((r.p0.x + r.p1.x) / 2, (r.p0.y + r.p1.y) / 2),
+                ),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vias.len(), 1, "net {}", net);
+        let via = vias[0];
+        // Check it lands in the expected column of met2 periods, and row of met1 periods
+        assert_eq!(via.x.div_euclid(met2.pitch.0), *col as isize);
+        assert_eq!(via.y.div_euclid(met1.pitch.0), (*row / 6) as isize);
+        // And at the centers of both its tracks
+        assert_eq!(via.x, met2.center(TrackIndex(*col))?.0);
+        assert_eq!(via.y, met1.center(TrackIndex(*row))?.0);
+    }
+    Ok(())
+}
+/// Locate the tracks of flipped periods of an asymmetric layer where conversion renders them
+#[test]
+fn flipped_track_centers() -> LayoutResult<()> {
+    use crate::coords::DbUnits;
+
+    let mut stack = SampleStacks::pdka_stack()?;
+    // Shift met1's first signal track down, so its flipped periods no longer mirror onto themselves
+    stack.metals[0].entries = vec![
+        TrackSpec::gnd(480),
+        TrackSpec::gap(100),
+        TrackSpec::sig(140),
+        TrackSpec::gap(300),
+        TrackSpec::repeat(vec![TrackEntry::sig(140), TrackEntry::gap(200)], 5),
+        TrackSpec::pwr(480),
+    ];
+    let stack = stack.validate()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    assert!(met1.flipped(1));
+    for period in 0..4 {
+        let rendered = met1.spec.to_layer_period(period, DbUnits(100))?;
+        for (k, track) in rendered.signals.iter().enumerate() {
+            let idx = period * 6 + k;
+            let span = (track.data.start, track.data.start + track.data.width);
+            assert_eq!(met1.span(TrackIndex(idx))?, span);
+            assert_eq!(met1.locate(TrackIndex(idx))?, (period, k));
+            // And the inverse lookup agrees
+            let center = met1.center(TrackIndex(idx))?;
+            assert_eq!(met1.signed_track_at(center).unwrap().0, idx as isize);
+        }
+    }
+    Ok(())
+}
+/// Iterate over the tracks of many periods, with global indices
+#[test]
+fn tracks_in() -> LayoutResult<()> {
+    use crate::coords::DbUnits;
+
+    let stack = SampleStacks::pdka()?;
+    let met1 = stack.metal(LayerIndex(0))?;
+    let summarize = |span: Range<isize>| -> LayoutResult<Vec<(TrackType, usize)>> {
+        Ok(met1.tracks_in(span)?.map(|t| (t.ttype, t.index)).collect())
+    };
+    let (gnd, pwr) = (
+        TrackType::Rail(RailKind::Gnd),
+        TrackType::Rail(RailKind::Pwr),
+    );
+    let sigs = |range: Range<usize>| range.map(|idx| (TrackType::Signal, idx));
+    // Exactly one pitch: the first period's ground rail, starting at our negative offset,
+    // its six signals, and the power rail it shares with the next period
+    let mut expected = vec![(gnd, 0)];
+    expected.extend(sigs(0..6));
+    expected.push((pwr, 1));
+    assert_eq!(summarize(0..2720)?, expected);
+    // Two pitches: the second (flipped) period adds six signals and its final rail
+    expected.extend(sigs(6..12));
+    expected.push((gnd, 3));
+    assert_eq!(summarize(0..5440)?, expected);
+    // Starting mid-period, across the shared rail, but short of the next period's signals
+    let mut expected: Vec<_> = sigs(2..6).collect();
+    expected.push((pwr, 1));
+    assert_eq!(summarize(1000..3000)?, expected);
+    // Empty spans include nothing
+    assert_eq!(summarize(1000..1000)?, vec![]);
+
+    // Yielded signals agree with the locations of their global indices
+    for t in met1.tracks_in(0..5 * 2720)? {
+        if t.ttype == TrackType::Signal {
+            assert_eq!(
+                met1.span(TrackIndex(t.index))?,
+                (t.start, t.start + t.width)
+            );
+        }
+    }
+    // And the negative offset of met2 places its first track partially before the origin
+    let met2 = stack.metal(LayerIndex(1))?;
+    let starts: Vec<DbUnits> = met2.tracks_in(0..460)?.map(|t| t.start).collect();
+    assert_eq!(starts, vec![DbUnits(-70), DbUnits(390)]);
+    Ok(())
+}
+/// Fail, or skip with a warning, assignments landing on instance blockages and cuts, per [OnUnroutable]
+#[test]
+fn unroutable_assignments() -> LayoutResult<()> {
+    use crate::conv::raw::{OnUnroutable, RawExportOptions, RawExporter, Unroutable};
+
+    let convert = |cut: bool, policy: OnUnroutable| {
+        // A two-metal block, instantiated under met2 tracks 4 and 5
+        let mut lib = Library::new("UnroutableLib");
+        let blk = Cell::builder("blk")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(2, 1)?)
+            .build()?;
+        let blk = lib.cells.insert(blk);
+        let inst = Instance::builder()
+            .inst_name("u_blk")
+            .cell(blk)
+            .loc((4, 0))
+            .build()?;
+        // Assign net `a` directly over the block, and net `b` beside it
+        let mut parent = Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(10, 1)?)
+            .instance(inst)
+            .assign(
+                "a",
+                LayerIndex(1),
+                TrackIndex(5),
+                TrackIndex(2),
+                RelZ::Below,
+            )
+            .assign(
+                "b",
+                LayerIndex(1),
+                TrackIndex(8),
+                TrackIndex(2),
+                RelZ::Below,
+            );
+        if cut {
+            // And net `c` onto a cut
+            parent = parent
+                .assign(
+                    "c",
+                    LayerIndex(1),
+                    TrackIndex(9),
+                    TrackIndex(2),
+                    RelZ::Below,
+                )
+                .cut(LayerIndex(1), TrackIndex(9), TrackIndex(2), RelZ::Below);
+        }
+        lib.cells.insert(parent.build()?);
+        let opts = RawExportOptions {
+            on_unroutable: policy,
+            ..Default::default()
+        };
+        RawExporter::convert_with_options(lib, SampleStacks::pdka()?, &opts)
+    };
+
+    // By default, conversion fails, naming the blocking instance
+    let err = convert(false, OnUnroutable::Error).unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("blocked by u_blk"),
+        "{:?}",
+        err
+    );
+
+    // Skipping drops both unroutable assignments, and converts the rest
+    let (rawlib, report) = convert(true, OnUnroutable::SkipWarn)?;
+    assert_eq!(
+        report.unroutable,
+        vec![
+            Unroutable {
+                cell: "parent".into(),
+                net: "a".into(),
+                at: TrackCross::from_relz(LayerIndex(1), TrackIndex(5), TrackIndex(2), RelZ::Below),
+                blocker: "u_blk".into(),
+            },
+            Unroutable {
+                cell: "parent".into(),
+                net: "c".into(),
+                at: TrackCross::from_relz(LayerIndex(1), TrackIndex(9), TrackIndex(2), RelZ::Below),
+                blocker: format!(
+                    "cut {:?}",
+                    TrackCross::from_relz(LayerIndex(1), TrackIndex(9), TrackIndex(2), RelZ::Below)
+                ),
+            },
+        ]
+    );
+    assert_eq!(report.warnings.len(), 2);
+    let rawlib = rawlib.read()?;
+    let parent = rawlib
+        .cells
+        .iter()
+        .find(|c| c.read().unwrap().name == "parent");
+    let parent = parent.unwrap().read()?;
+    let nets: Vec<&str> = parent
+        .layout
+        .as_ref()
+        .unwrap()
+        .elems
+        .iter()
+        .filter_map(|e| e.net.as_deref())
+        .collect();
+    assert!(nets.contains(&"b"));
+    assert!(!nets.contains(&"a"));
+    assert!(!nets.contains(&"c"));
+    Ok(())
+}
+/// Generate parameterized cells, re-using those already generated from equal parameters
+#[cfg(feature = "yaml")]
+#[test]
+fn generate_cells() -> LayoutResult<()> {
+    use crate::utils::fnv1a;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct MuxParams {
+        inputs: usize,
+    }
+    let stack = SampleStacks::pdka()?;
+    let mut calls = 0;
+    let mut mux = |lib: &mut Library, inputs: usize| {
+        lib.generate("mux", MuxParams { inputs }, &stack, |p, _| {
+            calls += 1;
+            Cell::builder("anything")
+                .top_layer(LayerIndex(1))
+                .outline(Outline::rect(p.inputs as isize, 1)?)
+                .build()
+        })
+    };
+    let mut lib = Library::new("GeneratedLib");
+    // Equal parameters generate a single cell
+    let mux4 = mux(&mut lib, 4)?;
+    assert_eq!(mux(&mut lib, 4)?, mux4);
+    assert_eq!(lib.cells.len(), 1);
+    // Different parameters generate another, differently named
+    let mux8 = mux(&mut lib, 8)?;
+    assert_ne!(mux8, mux4);
+    assert_eq!(lib.cells.len(), 2);
+    assert_eq!(calls, 2);
+
+    let name = |id| -> LayoutResult<String> { Ok(lib.cell(id).unwrap().read()?.name.clone()) };
+    let (name4, name8) = (name(mux4)?, name(mux8)?);
+    // Named after a hash of their serialized parameters
+    assert_eq!(name4, format!("mux_{:08x}", fnv1a("inputs: 4") as u32));
+    assert_ne!(name4, name8);
+    // Each records its generator and parameters
+    let cell = lib.cell(mux4).unwrap().read()?;
+    assert_eq!(cell.props["generator"], "mux");
+    assert_eq!(cell.props["params"], "inputs: 4");
+    assert_eq!(cell.layout.as_ref().unwrap().name, name4);
+    drop(cell);
+
+    // A same-named cell not generated from equal parameters is a collision
+    let mut lib = Library::new("CollidingLib");
+    lib.add_cell(
+        Cell::builder(&name4)
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 1)?)
+            .build()?,
+    );
+    let generated = lib.generate("mux", MuxParams { inputs: 4 }, &stack, |_, _| {
+        panic!("Generator should not be called on collision")
+    });
+    assert!(generated.is_err());
+    Ok(())
+}
+/// Compare [abs::Abstract]s, checking for drop-in replacements
+#[test]
+fn compare_abstracts() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka()?;
+    let abstrakt = |shift: usize| -> LayoutResult<abs::Abstract> {
+        Ok(abs::Abstract {
+            name: format!("abs{}", shift),
+            outline: Outline::rect(11, 11)?,
+            metals: 2,
+            ports: vec![
+                abs::Port::edge(
+                    "a",
+                    LayerIndex(1),
+                    TrackIndex(2 + shift),
+                    abs::Side::BottomOrLeft,
+                ),
+                abs::Port::edge("b", LayerIndex(0), TrackIndex(4), abs::Side::TopOrRight),
+            ],
+        })
+    };
+    let orig = abstrakt(0)?;
+
+    // An identical copy is a drop-in replacement
+    let diff = abs::compare(&orig, &abstrakt(0)?, &stack, 0)?;
+    assert!(diff.is_empty());
+    assert_eq!(
+        diff.to_string(),
+        "Abstract abs0 is a drop-in replacement for abs0"
+    );
+
+    // Shifting a port by one track exceeds a zero-track tolerance, but not a one-track tolerance
+    let shifted = abstrakt(1)?;
+    let diff = abs::compare(&orig, &shifted, &stack, 0)?;
+    assert_eq!(
+        diff.moved,
+        vec![abs::PortMove {
+            port: "a".into(),
+            tracks: 1
+        }]
+    );
+    assert!(diff.outline.is_none() && diff.kinds.is_empty());
+    assert!(diff.to_string().contains("Port a: moved by 1 tracks"));
+    assert!(abs::compare(&orig, &shifted, &stack, 1)?.is_empty());
+
+    // Missing, extra, and changed ports are all reported
+    let mut changed = abstrakt(0)?;
+    changed.outline = Outline::rect(12, 11)?;
+    changed.ports[0].name = "c".into();
+    changed.ports[1] = abs::Port::edge("b", LayerIndex(1), TrackIndex(4), abs::Side::TopOrRight);
+    let diff = abs::compare(&orig, &changed, &stack, 0)?;
+    assert!(diff.outline.is_some());
+    assert_eq!(diff.missing, vec!["a".to_string()]);
+    assert_eq!(diff.extra, vec!["c".to_string()]);
+    assert_eq!(diff.kinds.len(), 1);
+    assert_eq!(diff.kinds[0].port, "b");
+    assert_ne!(diff.kinds[0].from, diff.kinds[0].to);
+    // And the diff serializes
+    #[cfg(feature = "yaml")]
+    {
+        let yaml = serde_yaml::to_string(&diff).unwrap();
+        let back: abs::AbstractDiff = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(back, diff);
+    }
+    Ok(())
+}
+/// Cancel a conversion running on another thread, and check it stops within the current cell
+#[test]
+fn conversion_cancelled() -> LayoutResult<()> {
+    use crate::conv::raw::{RawExportOptions, RawExporter};
+    use crate::raw::LayoutError;
+    use crate::utils::{CancelToken, Progress, ProgressHook};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::Mutex;
+
+    /// Reports each cell start, and waits for acknowledgement of the first
+    struct Handshake {
+        started: Mutex<Sender<usize>>,
+        ack: Mutex<Receiver<()>>,
+    }
+    impl Progress for Handshake {
+        fn on_cell_start(&self, _name: &str, idx: usize, _total: usize) {
+            self.started.lock().unwrap().send(idx).unwrap();
+            if idx == 0 {
+                self.ack.lock().unwrap().recv().unwrap();
+            }
+        }
+    }
+    let mut lib = Library::new("conversion_cancelled");
+    for k in 0..32 {
+        lib.cells.insert(
+            Cell::builder(format!("cell{}", k))
+                .top_layer(LayerIndex(3))
+                .outline(Outline::rect(40, 40)?)
+                .build()?,
+        );
+    }
+    let (started_tx, started_rx) = channel();
+    let (ack_tx, ack_rx) = channel();
+    let cancel = CancelToken::new();
+    let opts = RawExportOptions {
+        progress: ProgressHook::new(Handshake {
+            started: Mutex::new(started_tx),
+            ack: Mutex::new(ack_rx),
+        }),
+        cancel: Some(cancel.clone()),
+        ..Default::default()
+    };
+    let stack = SampleStacks::pdka()?;
+    // Errors aren't [Send], so report only whether conversion was cancelled
+    let handle = std::thread::spawn(move || {
+        let result = RawExporter::convert_with_options(lib, stack, &opts);
+        matches!(result, Err(LayoutError::Cancelled))
+    });
+    // Cancel once the first cell is underway
+    assert_eq!(started_rx.recv().unwrap(), 0);
+    cancel.cancel();
+    ack_tx.send(()).unwrap();
+    assert!(handle.join().unwrap());
+    // No further cells were started
+    assert_eq!(started_rx.try_iter().count(), 0);
+    Ok(())
+}
+/// Assign, check, and export multi-patterning mask colors
+#[test]
+fn track_colors() -> LayoutResult<()> {
+    use crate::raw::{self, LayerPurpose};
+
+    // Uncolored signals alternate between masks
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[0].colors = 2;
+    let valid = stack.clone().validate()?;
+    let colors: Vec<Option<u8>> = valid
+        .metal(LayerIndex(0))?
+        .period_data
+        .signals
+        .iter()
+        .map(|t| t.color)
+        .collect();
+    assert_eq!(colors, [0, 1, 0, 1, 0, 1].map(Some));
+    // While single-patterned layers remain uncolored
+    let met2 = valid.metal(LayerIndex(1))?;
+    assert!(met2.period_data.signals.iter().all(|t| t.color.is_none()));
+
+    // Explicitly coloring adjacent signals alike is invalid
+    let mut same = stack.clone();
+    same.metals[0].entries = vec![
+        TrackSpec::gnd(480),
+        TrackSpec::gap(200),
+        TrackSpec::Entry(TrackEntry::sig(140).with_color(0)),
+        TrackSpec::gap(200),
+        TrackSpec::Entry(TrackEntry::sig(140).with_color(0)),
+        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 4),
+        TrackSpec::gap(200),
+        TrackSpec::pwr(480),
+    ];
+    let err = same.validate().unwrap_err();
+    assert!(format!("{:?}", err).contains("share mask color"));
+    // As is a single-signal layer, which abuts itself in the next period
+    let mut single = stack.clone();
+    single.metals[1].colors = 2;
+    assert!(single.validate().is_err());
+
+    // Export each color on its own datatype
+    let rawlayers = stack.rawlayers.clone().unwrap();
+    let key = rawlayers.write()?.add(raw::Layer::from_pairs(
+        168,
+        &[
+            (20, LayerPurpose::Drawing),
+            (41, LayerPurpose::Named("mask1".into(), 41)),
+            (42, LayerPurpose::Named("mask2".into(), 42)),
+        ],
+    )?);
+    stack.metals[0].raw = Some(key);
+    stack.metals[0].color_datatypes = vec![41, 42];
+    let mut lib = Library::new("TrackColors");
+    lib.cells.insert(
+        Cell::builder("colored")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(1, 1)?)
+            .build()?,
+    );
+    let rawlib = lib.to_raw(stack.clone().validate()?)?;
+    let rawlib = rawlib.read()?;
+    let cell = rawlib.cells[0].read()?;
+    let elems = &cell.layout.as_ref().unwrap().elems;
+    let count = |purpose: &LayerPurpose| {
+        let on = |e: &&raw::Element| e.layer == key && &e.purpose == purpose;
+        elems.iter().filter(on).count()
+    };
+    assert_eq!(count(&LayerPurpose::Named("mask1".into(), 41)), 3);
+    assert_eq!(count(&LayerPurpose::Named("mask2".into(), 42)), 3);
+    assert_eq!(count(&LayerPurpose::Drawing), 2); // The rails
+
+    // Datatypes must be defined on the layer
+    stack.metals[0].color_datatypes = vec![41, 43];
+    assert!(stack.validate().is_err());
+    Ok(())
+}
+/// Generate placement blockages for a converted cell
+#[test]
+fn raw_blockages() -> LayoutResult<()> {
+    use crate::blockages::{BlockageCoverage, RawBlockages};
+    use crate::raw::{self, BoundBox, BoundBoxTrait, LayerPurpose};
+
+    let stack = SampleStacks::pdka()?;
+    let mut lib = Library::new("RawBlockages");
+    lib.cells.insert(
+        Cell::builder("blocked")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(2, 1)?)
+            .build()?,
+    );
+    let rawlib = lib.to_raw(stack.clone())?;
+    let rawlib = rawlib.read()?;
+    let mut cell = rawlib.cells[0].read()?.clone();
+    let layer_of = |e: &raw::Element| {
+        (0..4).find(|&i| stack.metal(LayerIndex(i)).unwrap().raw == Some(e.layer))
+    };
+
+    // Without a boundary, blockages cover the half-rails beyond the cell outline
+    let unbounded = cell.generate_blockages(&stack, 3)?;
+    assert_eq!(unbounded.len(), 2);
+    assert!(unbounded.iter().any(|e| e.inner.bbox().p0.y < 0));
+
+    // Add the cell's boundary, which clips them
+    let boundary = BoundBox::from_points(&raw::Point::new(0, 0), &raw::Point::new(920, 2720));
+    cell.layout.as_mut().unwrap().elems.push(raw::Element {
+        net: None,
+        layer: stack.boundary_layer.unwrap(),
+        purpose: LayerPurpose::Outline,
+        inner: raw::Shape::Rect(raw::Rect {
+            p0: boundary.p0,
+            p1: boundary.p1,
+        }),
+        labels: Vec::new(),
+    });
+    let blockages = cell.generate_blockages(&stack, 3)?;
+    // Blockages exist only on the layers with geometry, one per layer, within the boundary
+    let layers: Vec<_> = blockages.iter().map(layer_of).collect();
+    assert_eq!(layers, vec![Some(0), Some(1)]);
+    for elem in blockages.iter() {
+        assert_eq!(elem.purpose, LayerPurpose::Obstruction);
+        assert_eq!(elem.inner.intersection(&boundary), elem.inner.bbox());
+    }
+
+    // Merged coverage instead draws a blockage per cluster of touching shapes, e.g. per met1 track
+    let merged = cell.generate_blockages_with(&stack, 3, BlockageCoverage::Merged)?;
+    let met1 = merged.iter().filter(|e| layer_of(e) == Some(0)).count();
+    assert!(met1 > 1);
+    for elem in merged.iter() {
+        assert_eq!(elem.inner.intersection(&boundary), elem.inner.bbox());
+    }
+    Ok(())
+}
+/// Prefix each cell name of a library, and check for drift between cell and view names
+#[cfg(feature = "gds")]
+#[test]
+fn prefix_names() -> LayoutResult<()> {
+    use crate::library::{RenameMap, Violation};
+    use crate::raw::gds::gds21::GdsElement;
+
+    let mut lib = Library::new("prefix_names");
+    let child = lib.cells.insert(
+        Cell::builder("child")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(2, 1)?)
+            .build()?,
+    );
+    lib.cells.insert(
+        Cell::builder("parent")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 1)?)
+            .instance(
+                Instance::builder()
+                    .inst_name("u_child")
+                    .cell(child.clone())
+                    .loc((0, 0))
+                    .build()?,
+            )
+            .build()?,
+    );
+    let renames = lib.prefix_names("sram_")?;
+    let expected: RenameMap = [("child", "sram_child"), ("parent", "sram_parent")]
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+    assert_eq!(renames, expected);
+    assert!(lib.check_names()?.is_empty());
+
+    // Exported structs and their references carry the prefix
+    let gds = lib
+        .clone()
+        .to_raw(SampleStacks::pdka()?)?
+        .read()?
+        .to_gds()?;
+    let names: Vec<&str> = gds.structs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["sram_child", "sram_parent"]);
+    let parent = gds
+        .structs
+        .iter()
+        .find(|s| s.name == "sram_parent")
+        .unwrap();
+    let refs: Vec<&str> = parent
+        .elems
+        .iter()
+        .filter_map(|e| match e {
+            GdsElement::GdsStructRef(r) => Some(r.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(refs, vec!["sram_child"]);
+
+    // Desynchronize the instantiated cell's layout name, and check it is reported
+    child.write()?.layout.as_mut().unwrap().name = "child".into();
+    let violations = lib.check_names()?;
+    assert_eq!(
+        violations.0,
+        vec![Violation::NameDrift {
+            cell: "sram_child".into(),
+            view: "Layout".into(),
+            name: "child".into(),
+        }]
+    );
+    assert_eq!(
+        violations[0].to_string(),
+        "Layout of Cell sram_child is named child"
+    );
+    Ok(())
+}
+/// Trace converted elements back to their sources, per [RawExportOptions::provenance]
+#[test]
+fn element_provenance() -> LayoutResult<()> {
+    use crate::conv::raw::{
+        Provenance, ProvenanceSource, RawExportOptions, RawExporter, SegmentBound,
+    };
+    use crate::tracks::RailKind;
+
+    let mut lib = Library::new("element_provenance");
+    let child = lib.cells.insert(
+        Cell::builder("child")
+            .top_layer(LayerIndex(1))
+            .outline(Outline::rect(4, 1)?)
+            .build()?,
+    );
+    // A parent with the child mid-way along its met1 rails, and a net assigned beyond it
+    let mut layout = Layout::new("parent", 2, Outline::rect(20, 1)?);
+    layout.instances.add(
+        Instance::builder()
+            .inst_name("u_child")
+            .cell(child)
+            .loc((8, 0))
+            .build()?,
+    );
+    layout.assign(
+        "a",
+        LayerIndex(1),
+        TrackIndex(1),
+        TrackIndex(1),
+        RelZ::Below,
+    );
+    let at = layout.assignments[0].at;
+    lib.cells.insert(Cell::from(layout));
+
+    // Provenance is only collected on request
+    let (_, report) = RawExporter::convert_with_options(
+        lib.clone(),
+        SampleStacks::pdka()?,
+        &RawExportOptions::default(),
+    )?;
+    assert!(report.provenance.is_empty());
+    let opts = RawExportOptions {
+        provenance: true,
+        ..Default::default()
+    };
+    let (rawlib, report) = RawExporter::convert_with_options(lib, SampleStacks::pdka()?, &opts)?;
+    let rawlib = rawlib.read()?;
+    let parent = rawlib
+        .cells
+        .iter()
+        .find(|c| c.read().unwrap().name == "parent")
+        .unwrap()
+        .read()?;
+    let elems = &parent.layout.as_ref().unwrap().elems;
+    let prov = report
+        .provenance
+        .iter()
+        .find(|p| p.cell == "parent")
+        .unwrap();
+    assert_eq!(prov.elems.len(), elems.len());
+    assert_eq!(report.provenance_of("parent", 0), prov.elems.first());
+    assert_eq!(report.provenance_of("parent", elems.len()), None);
+
+    // The assigned met1 and met2 wires name their net and intersection, as does their via
+    let assigned: Vec<(usize, &Provenance)> = elems
+        .iter()
+        .zip(prov.elems.iter())
+        .filter(|(_, p)| {
+            p.source
+                == ProvenanceSource::Assign {
+                    net: "a".into(),
+                    at,
+                }
+        })
+        .map(|(e, p)| {
+            assert_eq!(e.net.as_deref(), Some("a"));
+            (p.layer.unwrap(), p)
+        })
+        .collect();
+    let layers: Vec<usize> = assigned.iter().map(|(layer, _)| *layer).collect();
+    assert_eq!(layers, vec![0, 1]);
+    let via = ProvenanceSource::Via {
+        net: Some("a".into()),
+        assign: Some(at),
+    };
+    assert_eq!(prov.elems.iter().filter(|p| p.source == via).count(), 1);
+
+    // Each met1 rail is split around the child, and names it as its bound
+    let rails: Vec<&Provenance> = prov
+        .elems
+        .iter()
+        .filter(|p| p.source == ProvenanceSource::Rail(RailKind::Gnd))
+        .collect();
+    assert_eq!(rails.len(), 2);
+    for rail in rails {
+        assert_eq!(rail.layer, Some(0));
+        assert_eq!(
+            rail.bounds,
+            vec![SegmentBound::Blockage {
+                src: "u_child".into()
+            }]
+        );
+    }
+    Ok(())
 }
@@ -5,18 +5,18 @@
 // Local imports
 use crate::abs;
 use crate::array::{Array, ArrayInstance, Arrayable};
-use crate::cell::{Cell, RawLayoutPtr};
-use crate::coords::{PrimPitches, Xy};
+use crate::cell::Cell;
+use crate::coords::{LayerIndex, PrimPitches, TrackIndex, Xy};
 use crate::library::Library;
 use crate::outline::Outline;
 use crate::placement::{Align, Place, RelAssign};
-use crate::raw::{self, LayoutResult};
+use crate::raw::LayoutResult;
 use crate::stack::RelZ;
 use crate::utils::Ptr;
 use crate::{instance::Instance, layout::Layout};
 
 // Test-locals
-use super::{exports, resource, stacks::SampleStacks};
+use super::{exports, stacks::SampleStacks};
 
 /// Create an abs unit-cell
 fn abstract_unit_cell(_lib: &mut Library) -> LayoutResult<Ptr<Cell>> {
@@ -31,30 +31,27 @@ fn abstract_unit() -> LayoutResult<abs::Abstract> {
         metals: 1,
         outline: Outline::rect(unitsize.0, unitsize.1)?,
         ports: vec![
-            abs::Port {
-                name: "en".into(),
-                kind: abs::PortKind::ZTopEdge {
-                    track: 2,
-                    side: abs::Side::BottomOrLeft,
-                    into: (5, RelZ::Above),
-                },
-            },
-            abs::Port {
-                name: "inp".into(),
-                kind: abs::PortKind::ZTopEdge {
-                    track: 3,
-                    side: abs::Side::TopOrRight,
-                    into: (11, RelZ::Above),
-                },
-            },
-            abs::Port {
-                name: "out".into(),
-                kind: abs::PortKind::ZTopEdge {
-                    track: 5,
-                    side: abs::Side::TopOrRight,
-                    into: (11, RelZ::Above),
-                },
-            },
+            abs::Port::ztop_edge(
+                "en",
+                TrackIndex(2),
+                abs::Side::BottomOrLeft,
+                TrackIndex(5),
+                RelZ::Above,
+            ),
+            abs::Port::ztop_edge(
+                "inp",
+                TrackIndex(3),
+                abs::Side::TopOrRight,
+                TrackIndex(11),
+                RelZ::Above,
+            ),
+            abs::Port::ztop_edge(
+                "out",
+                TrackIndex(5),
+                abs::Side::TopOrRight,
+                TrackIndex(11),
+                RelZ::Above,
+            ),
         ],
     };
     Ok(unit)
@@ -94,42 +91,111 @@ fn ro_abs(unit: Ptr<Cell>) -> LayoutResult<Cell> {
             let m1track = (y * 12 + 9) as usize;
             let m3track = m1track + x as usize;
             ro.net(format!("dly{}", x))
-                .at(1, m2track, m1track, RelZ::Below)
-                .at(2, m3track, m2track, RelZ::Below);
+                .at(
+                    LayerIndex(1),
+                    TrackIndex(m2track),
+                    TrackIndex(m1track),
+                    RelZ::Below,
+                )
+                .at(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track),
+                    RelZ::Below,
+                );
             if x != 0 {
                 // Cut M3 to the *right* of the input
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *left* of the input
-                ro.cut(2, m3track, m2track - 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track - 1),
+                    RelZ::Below,
+                );
             }
             // Assign the output
             let m3track = m1track + ((x + 1) % 3) as usize;
             let m1track = (y * 12 + 11) as usize;
             ro.net(format!("dly{}", ((x + 1) % 3)))
-                .at(1, m2track + 2, m1track, RelZ::Below)
-                .at(2, m3track, m2track + 2, RelZ::Below);
+                .at(
+                    LayerIndex(1),
+                    TrackIndex(m2track + 2),
+                    TrackIndex(m1track),
+                    RelZ::Below,
+                )
+                .at(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 2),
+                    RelZ::Below,
+                );
             if x != 2 {
                 // Cut M3 to the *left* of the output
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *right* of the output
-                ro.cut(2, m3track, m2track + 3, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 3),
+                    RelZ::Below,
+                );
             }
 
             // Assign the enable
             let m1track = (y * 12 + 8) as usize;
             let m2track = (m2entrack + y) as usize;
-            ro.net(format!("en{}{}", x, y))
-                .at(1, m2track, m1track, RelZ::Below);
-            ro.cut(1, m2track, m1track + 1, RelZ::Below); // Cut just above
+            ro.net(format!("en{}{}", x, y)).at(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track),
+                RelZ::Below,
+            );
+            ro.cut(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track + 1),
+                RelZ::Below,
+            ); // Cut just above
         }
 
         // Make top & bottom M2 cuts
-        ro.cut(1, m2track, m2botcut, RelZ::Below);
-        ro.cut(1, m2track, m2topcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2botcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2topcut, RelZ::Below);
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
     }
     Ok(ro.into())
 }
@@ -224,47 +290,105 @@ fn ro_rel(unit: Ptr<Cell>) -> LayoutResult<Cell> {
             // Assign the input
             let m1track = (y * 12 + 9) as usize;
             let m3track = m1track + x as usize;
-            ro.net(format!("dly{}", x))
-                .at(2, m3track, m2track, RelZ::Below);
+            ro.net(format!("dly{}", x)).at(
+                LayerIndex(2),
+                TrackIndex(m3track),
+                TrackIndex(m2track),
+                RelZ::Below,
+            );
 
             if x != 0 {
                 // Cut M3 to the *right* of the input
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *left* of the input
-                ro.cut(2, m3track, m2track - 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track - 1),
+                    RelZ::Below,
+                );
             }
             // Assign the output
             let m3track = m1track + ((x + 1) % 3) as usize;
 
-            ro.net(format!("dly{}", ((x + 1) % 3)))
-                .at(2, m3track, m2track + 2, RelZ::Below);
+            ro.net(format!("dly{}", ((x + 1) % 3))).at(
+                LayerIndex(2),
+                TrackIndex(m3track),
+                TrackIndex(m2track + 2),
+                RelZ::Below,
+            );
 
             if x != 2 {
                 // Cut M3 to the *left* of the output
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *right* of the output
-                ro.cut(2, m3track, m2track + 3, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 3),
+                    RelZ::Below,
+                );
             }
 
             // Assign the enable
             let m1track = (y * 12 + 8) as usize;
             let m2track = (m2entrack + y) as usize;
-            ro.net(format!("en{}{}", x, y))
-                .at(1, m2track, m1track, RelZ::Below);
-            ro.cut(1, m2track, m1track + 1, RelZ::Below); // Cut just above
+            ro.net(format!("en{}{}", x, y)).at(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track),
+                RelZ::Below,
+            );
+            ro.cut(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track + 1),
+                RelZ::Below,
+            ); // Cut just above
         }
 
         // Make top & bottom M2 cuts
-        ro.cut(1, m2track, m2botcut, RelZ::Below);
-        ro.cut(1, m2track, m2topcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2botcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2topcut, RelZ::Below);
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
     }
     Ok(ro.into())
 }
 /// Test importing and wrapping an existing GDSII into a [Library]/[Cell]
+#[cfg(feature = "gds")]
 #[test]
 fn wrap_gds() -> LayoutResult<()> {
     let mut lib = Library::new("wrap_gds");
@@ -272,7 +396,12 @@ fn wrap_gds() -> LayoutResult<()> {
     exports(lib, SampleStacks::pdka()?)
 }
 /// Most internal implementation of the `wrap_gds` test
+#[cfg(feature = "gds")]
 fn _wrap_gds(lib: &mut Library) -> LayoutResult<Ptr<Cell>> {
+    use super::resource;
+    use crate::cell::RawLayoutPtr;
+    use crate::raw;
+
     // Import a [GdsLibrary] to a [raw::Library]
     let gds_fname = resource("ginv.gds");
     let gds = raw::gds::gds21::GdsLibrary::load(&gds_fname)?;
@@ -395,42 +524,111 @@ fn ro_array(unit: Ptr<Cell>) -> LayoutResult<Cell> {
             let m1track = (y * 12 + 9) as usize;
             let m3track = m1track + x as usize;
             ro.net(format!("dly{}", x))
-                .at(1, m2track, m1track, RelZ::Below)
-                .at(2, m3track, m2track, RelZ::Below);
+                .at(
+                    LayerIndex(1),
+                    TrackIndex(m2track),
+                    TrackIndex(m1track),
+                    RelZ::Below,
+                )
+                .at(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track),
+                    RelZ::Below,
+                );
             if x != 0 {
                 // Cut M3 to the *right* of the input
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *left* of the input
-                ro.cut(2, m3track, m2track - 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track - 1),
+                    RelZ::Below,
+                );
             }
             // Assign the output
             let m3track = m1track + ((x + 1) % 3) as usize;
             let m1track = (y * 12 + 11) as usize;
             ro.net(format!("dly{}", ((x + 1) % 3)))
-                .at(1, m2track + 2, m1track, RelZ::Below)
-                .at(2, m3track, m2track + 2, RelZ::Below);
+                .at(
+                    LayerIndex(1),
+                    TrackIndex(m2track + 2),
+                    TrackIndex(m1track),
+                    RelZ::Below,
+                )
+                .at(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 2),
+                    RelZ::Below,
+                );
             if x != 2 {
                 // Cut M3 to the *left* of the output
-                ro.cut(2, m3track, m2track + 1, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 1),
+                    RelZ::Below,
+                );
             } else {
                 // Cut M3 to the *right* of the output
-                ro.cut(2, m3track, m2track + 3, RelZ::Below);
+                ro.cut(
+                    LayerIndex(2),
+                    TrackIndex(m3track),
+                    TrackIndex(m2track + 3),
+                    RelZ::Below,
+                );
             }
 
             // Assign the enable
             let m1track = (y * 12 + 8) as usize;
             let m2track = (m2entrack + y) as usize;
-            ro.net(format!("en{}{}", x, y))
-                .at(1, m2track, m1track, RelZ::Below);
-            ro.cut(1, m2track, m1track + 1, RelZ::Below); // Cut just above
+            ro.net(format!("en{}{}", x, y)).at(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track),
+                RelZ::Below,
+            );
+            ro.cut(
+                LayerIndex(1),
+                TrackIndex(m2track),
+                TrackIndex(m1track + 1),
+                RelZ::Below,
+            ); // Cut just above
         }
 
         // Make top & bottom M2 cuts
-        ro.cut(1, m2track, m2botcut, RelZ::Below);
-        ro.cut(1, m2track, m2topcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2botcut, RelZ::Below);
-        ro.cut(1, m2track + 2, m2topcut, RelZ::Below);
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2botcut),
+            RelZ::Below,
+        );
+        ro.cut(
+            LayerIndex(1),
+            TrackIndex(m2track + 2),
+            TrackIndex(m2topcut),
+            RelZ::Below,
+        );
     }
     Ok(ro.into())
 }
@@ -448,14 +646,17 @@ fn _ro_test(
     exports(lib, SampleStacks::pdka()?) // And export everything to our handful of formats
 }
 // Execute a bunch of combinations, each as a separate test
+#[cfg(feature = "gds")]
 #[test]
 fn ro_wrap_gds_abs() -> LayoutResult<()> {
     _ro_test("RoWrapGdsAbs", _wrap_gds, ro_abs)
 }
+#[cfg(feature = "gds")]
 #[test]
 fn ro_wrap_gds_rel() -> LayoutResult<()> {
     _ro_test("RoWrapGdsRel", _wrap_gds, ro_rel)
 }
+#[cfg(feature = "gds")]
 #[test]
 fn ro_wrap_gds_array() -> LayoutResult<()> {
     _ro_test("RoWrapGdsArray", _wrap_gds, ro_array)
@@ -470,5 +671,91 @@ fn ro_abs_rel() -> LayoutResult<()> {
 }
 #[test]
 fn ro_abs_array() -> LayoutResult<()> {
-    _ro_test("RoAbsArray", abstract_unit_cell, ro_rel)
+    _ro_test("RoAbsArray", abstract_unit_cell, ro_array)
+}
+/// Check that period-caching produces output identical to the uncached conversion
+#[cfg(feature = "gds")]
+#[test]
+fn ro_period_cache() -> LayoutResult<()> {
+    use crate::conv::raw::RawExportOptions;
+
+    let to_gds = |cache_periods: bool| -> LayoutResult<_> {
+        let mut lib = Library::new("RoPeriodCache");
+        let unit = abstract_unit_cell(&mut lib)?;
+        lib.cells.insert(ro_abs(unit)?);
+        let opts = RawExportOptions {
+            cache_periods,
+            ..Default::default()
+        };
+        let (rawlib, report) = lib.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+        let gds = rawlib.read()?.to_gds()?;
+        Ok((gds, report))
+    };
+    let (mut cached, cached_report) = to_gds(true)?;
+    let (mut uncached, uncached_report) = to_gds(false)?;
+
+    // Check the cache got used, and only when enabled
+    assert_eq!(cached_report.periods, uncached_report.periods);
+    assert!(cached_report.period_cache_hits > 0);
+    assert_eq!(uncached_report.period_cache_hits, 0);
+
+    // Align the two libraries' timestamps, and compare their binary content
+    let date = cached.dates.modified.clone();
+    cached.set_all_dates(date.clone());
+    uncached.set_all_dates(date);
+    let (mut cached_bytes, mut uncached_bytes) = (Vec::new(), Vec::new());
+    cached.write(&mut cached_bytes)?;
+    uncached.write(&mut uncached_bytes)?;
+    assert_eq!(cached_bytes, uncached_bytes);
+    Ok(())
+}
+/// Round-trip a routed cell through its [CellRouteDb], and check the re-applied routes produce identical GDS
+#[cfg(all(feature = "gds", feature = "yaml"))]
+#[test]
+fn ro_route_db() -> LayoutResult<()> {
+    use crate::conv::raw::RawExportOptions;
+    use crate::routes::CellRouteDb;
+    use crate::utils::SerializationFormat::Yaml;
+
+    let opts = RawExportOptions {
+        route_db: true,
+        ..Default::default()
+    };
+    // Convert the original cell, collecting its route-database
+    let mut lib = Library::new("RoRouteDb");
+    let unit = abstract_unit_cell(&mut lib)?;
+    lib.cells.insert(ro_abs(unit)?);
+    let (rawlib, report) = lib.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let mut gds = rawlib.read()?.to_gds()?;
+    let db = report.route_db("RO").unwrap();
+    assert_eq!(db.layers.len(), 4);
+    assert!(!db.cuts().is_empty());
+    assert!(!db.assignments().is_empty());
+
+    // Save and re-load it
+    let db: CellRouteDb = Yaml.from_str(&Yaml.to_string(db)?)?;
+    assert_eq!(&db, report.route_db("RO").unwrap());
+
+    // Strip the assignments and cuts from a fresh copy of the cell, and apply the database onto it
+    let mut lib = Library::new("RoRouteDb");
+    let unit = abstract_unit_cell(&mut lib)?;
+    let mut ro = ro_abs(unit)?;
+    let layout = ro.layout.as_mut().unwrap();
+    layout.assignments.clear();
+    layout.cuts.clear();
+    db.apply(&mut ro)?;
+    lib.cells.insert(ro);
+    let (rawlib, report) = lib.to_raw_with_options(SampleStacks::pdka()?, &opts)?;
+    let mut gds2 = rawlib.read()?.to_gds()?;
+    assert_eq!(report.route_db("RO").unwrap(), &db);
+
+    // Align the two libraries' timestamps, and compare their binary content
+    let date = gds.dates.modified.clone();
+    gds.set_all_dates(date.clone());
+    gds2.set_all_dates(date);
+    let (mut bytes, mut bytes2) = (Vec::new(), Vec::new());
+    gds.write(&mut bytes)?;
+    gds2.write(&mut bytes2)?;
+    assert_eq!(bytes, bytes2);
+    Ok(())
 }
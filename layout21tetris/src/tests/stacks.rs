@@ -1,187 +1,489 @@
 //!
 //! # Test Sample [Stack]s
 //!
+//! The [SampleStacks] themselves live in [crate::fixtures], shared with fixture generation.
+//!
 
 // Local imports
+use crate::coords::{DbUnits, LayerIndex, TrackIndex};
+pub use crate::fixtures::SampleStacks;
+use crate::net::NetNamePolicy;
 use crate::raw::{self, Dir, LayoutResult, Units};
 use crate::stack::*;
 use crate::tracks::*;
-use crate::utils::Ptr;
-use crate::validate::ValidStack;
-
-/// # Sample Stacks
-/// Namespace for commonly re-used [Stack]s for testing.
-pub struct SampleStacks;
-
-impl SampleStacks {
-    /// As nearly empty a [Stack] as possible, while being raw-exportable.
-    /// Includes:
-    /// * A `boundary_layer`
-    /// * [raw::Layers] containing solely that boundary layer
-    /// * No metals or via layers
-    /// Generally useful for placement activities, particularly among [Instace]s.
-    pub fn empty() -> LayoutResult<ValidStack> {
-        let mut rawlayers = raw::Layers::default();
-        let boundary_layer = Some(rawlayers.add(raw::Layer::from_pairs(
-            0,
-            &[(0, raw::LayerPurpose::Outline)],
-        )?));
-        let stack = Stack {
-            units: Units::default(),
-            boundary_layer,
-            prim: PrimitiveLayer::new((100, 100).into()),
-            metals: Vec::new(), // No metal layers
-            vias: Vec::new(),   // No vias
-            rawlayers: Some(Ptr::new(rawlayers)),
-        };
-        Ok(stack.validate()?)
+
+/// Apply a [raw::LayerMap] to a [Stack], and check its layers pick up the mapped numbers
+#[test]
+fn apply_layermap() -> LayoutResult<()> {
+    let map = raw::LayerMap::parse(
+        "
+        # name  layernum  datatype  purpose
+        met1    68  20  drawing
+        met1    68  16  pin
+        met2    69  20  drawing
+        met2    69  16  pin
+        met2    69   5  label
+        via1    68  44  drawing
+        nwell   64  20  drawing
+        ",
+    )?;
+    let via = |name: &str, bot: usize| ViaLayer {
+        name: name.into(),
+        size: (240, 240).into(),
+        bot: bot.into(),
+        top: (bot + 1).into(),
+        raw: None,
+    };
+    let metal = |name: &str, dir: Dir| MetalLayer {
+        name: name.into(),
+        entries: vec![TrackSpec::sig(140), TrackSpec::gap(320)],
+        dir,
+        cutsize: (250).into(),
+        offset: (-70).into(),
+        overlap: (0).into(),
+        raw: None,
+        flip: FlipMode::None,
+        prim: PrimitiveMode::Stack,
+        min_length: None,
+        colors: 1,
+        color_datatypes: Vec::new(),
+    };
+    let mut stack = Stack {
+        schema: STACK_SCHEMA,
+        units: Units::Nano,
+        boundary_layer: None,
+        prim: PrimitiveLayer::new((460, 2720).into()),
+        metals: vec![metal("met1", Dir::Horiz), metal("met2", Dir::Vert)],
+        vias: vec![via("via1", 0)],
+        rawlayers: None,
+        nets: NetNamePolicy::default(),
+        site: None,
+    };
+    stack.apply_layermap(&map)?;
+
+    let rawlayers = stack.rawlayers.as_ref().unwrap().read()?;
+    let met2 = rawlayers.get(stack.metals[1].raw.unwrap()).unwrap();
+    assert_eq!(met2.name, Some("met2".into()));
+    assert_eq!(met2.layernum, 69);
+    assert_eq!(met2.num(&raw::LayerPurpose::Drawing), Some(20));
+    assert_eq!(met2.num(&raw::LayerPurpose::Pin), Some(16));
+    assert_eq!(met2.num(&raw::LayerPurpose::Label), Some(5));
+    let via1 = rawlayers.get(stack.vias[0].raw.unwrap()).unwrap();
+    assert_eq!(via1.layernum, 68);
+    assert_eq!(via1.num(&raw::LayerPurpose::Drawing), Some(44));
+    // Layers outside the stack are also added
+    assert_eq!(rawlayers.name("nwell").unwrap().layernum, 64);
+    let nlayers = rawlayers.slots().len();
+    drop(rawlayers);
+
+    // Re-applying the map re-uses the existing layers
+    let met2_key = stack.metals[1].raw;
+    stack.apply_layermap(&map)?;
+    assert_eq!(stack.metals[1].raw, met2_key);
+    assert_eq!(
+        stack.rawlayers.as_ref().unwrap().read()?.slots().len(),
+        nlayers
+    );
+
+    // Layers missing from the map produce an error
+    stack.metals.push(metal("met3", Dir::Horiz));
+    let err = stack.apply_layermap(&map).unwrap_err();
+    assert!(err.to_string().contains("met3"));
+    Ok(())
+}
+
+/// Create a [LayerPeriod] from a short-lived [MetalLayer], then cut, block, assign, and serialize it
+/// Layer periods must be integer multiples of the primitive pitch across each layer
+#[test]
+fn layer_period_fits_prim_pitch() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka_stack()?;
+    assert_eq!(stack.metals[0].pitch(), DbUnits(2720));
+    // Multi-period layers, such as met4, are allowed
+    assert_eq!(stack.metals[3].pitch(), DbUnits(4600));
+    stack.validate()?;
+
+    // Widen the last met1 entry by 10, throwing its period off the primitive grid
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[0].entries[3] = TrackSpec::pwr(490);
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains(
+        "Invalid layer met1: period 2730 (sum of entries less overlap) is not an integer multiple of the Vert-direction primitive pitch 2720"
+    ));
+    // As do layers which are not shared with primitives
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[1].entries[1] = TrackSpec::gap(310);
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains("Invalid layer met2: period 450"));
+    assert!(err.contains("Horiz-direction primitive pitch 460"));
+    Ok(())
+}
+/// Flattened layer entries match whether collected or iterated, in either direction
+#[test]
+fn flattened_entries() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka_stack()?;
+    for layer in stack.metals.iter() {
+        let entries = layer.entries();
+        let iterated: Vec<TrackEntry> = layer.entries_iter().cloned().collect();
+        assert_eq!(iterated, entries);
+        let reversed: Vec<TrackEntry> = layer.entries_iter().rev().cloned().collect();
+        assert_eq!(reversed, entries.into_iter().rev().collect::<Vec<_>>());
     }
+    Ok(())
+}
+/// Stacks with same-direction adjacent metal layers, or vias between them, fail validation
+#[test]
+fn same_direction_layers() -> LayoutResult<()> {
+    // Adjacent metals
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[1].dir = Dir::Horiz;
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains("met1") && err.contains("met2"));
 
-    /// Real(istic) PDK [Stack]
-    pub fn pdka() -> LayoutResult<ValidStack> {
-        let mut rawlayers = raw::Layers::default();
-        // Shorthands for the common purpose-numbers
-        let metal_purps = [
-            (255, raw::LayerPurpose::Obstruction),
-            (20, raw::LayerPurpose::Drawing),
-            (5, raw::LayerPurpose::Label),
-            (16, raw::LayerPurpose::Pin),
-        ];
-        let via_purps = [
-            (255, raw::LayerPurpose::Obstruction),
-            (44, raw::LayerPurpose::Drawing),
-            (5, raw::LayerPurpose::Label),
-            (16, raw::LayerPurpose::Pin),
-        ];
-        // Add a few base-layers that we are used in imported/ primitive cells, but not in our stack
-        rawlayers.add(raw::Layer::new(64, "nwell").add_pairs(&metal_purps)?);
-        rawlayers.add(raw::Layer::new(67, "li1").add_pairs(&metal_purps)?);
-        // Create the test stack
-        let stack = Stack {
-            units: Units::Nano,
-            boundary_layer: Some(rawlayers.add(raw::Layer::from_pairs(
-                236,
-                &[(0, raw::LayerPurpose::Outline)],
-            )?)),
-            prim: PrimitiveLayer {
-                pitches: (460, 2720).into(),
-            },
-            metals: vec![
-                MetalLayer {
-                    name: "met1".into(),
-                    entries: vec![
-                        TrackSpec::gnd(480),
-                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
-                        TrackSpec::gap(200),
-                        TrackSpec::pwr(480),
-                    ],
-                    dir: Dir::Horiz,
-                    offset: (-240).into(),
-                    cutsize: (250).into(),
-                    overlap: (480).into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(68, &metal_purps)?)),
-                    flip: FlipMode::EveryOther,
-                    prim: PrimitiveMode::Split,
-                },
-                MetalLayer {
-                    name: "met2".into(),
-                    entries: vec![TrackSpec::sig(140), TrackSpec::gap(320)],
-                    dir: Dir::Vert,
-                    cutsize: (250).into(),
-                    offset: (-70).into(),
-                    overlap: (0).into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(69, &metal_purps)?)),
-                    flip: FlipMode::None,
-                    prim: PrimitiveMode::Stack,
-                },
-                MetalLayer {
-                    name: "met3".into(),
-                    entries: vec![
-                        TrackSpec::gnd(480),
-                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
-                        TrackSpec::gap(200),
-                        TrackSpec::pwr(480),
-                    ],
-                    dir: Dir::Horiz,
-                    offset: (-240).into(),
-                    cutsize: (250).into(),
-                    overlap: (480).into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(70, &metal_purps)?)),
-                    flip: FlipMode::EveryOther,
-                    prim: PrimitiveMode::Stack,
-                },
-                MetalLayer {
-                    name: "met4".into(),
-                    entries: vec![
-                        TrackSpec::gnd(510),
-                        TrackSpec::repeat(vec![TrackEntry::gap(410), TrackEntry::sig(50)], 8),
-                        TrackSpec::gap(410),
-                        TrackSpec::pwr(510),
-                    ],
-                    dir: Dir::Vert,
-                    cutsize: (250).into(),
-                    offset: (-255).into(),
-                    overlap: (510).into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(71, &metal_purps)?)),
-                    flip: FlipMode::EveryOther,
-                    prim: PrimitiveMode::Stack,
-                },
-                MetalLayer {
-                    name: "met5".into(),
-                    entries: vec![
-                        TrackSpec::gnd(480),
-                        TrackSpec::repeat(vec![TrackEntry::gap(200), TrackEntry::sig(140)], 6),
-                        TrackSpec::gap(200),
-                        TrackSpec::pwr(480),
-                    ],
-                    dir: Dir::Horiz,
-                    offset: (-240).into(),
-                    cutsize: (250).into(),
-                    overlap: (480).into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(72, &metal_purps)?)),
-                    flip: FlipMode::EveryOther,
-                    prim: PrimitiveMode::Stack,
-                },
-            ],
-            vias: vec![
-                ViaLayer {
-                    name: "mcon".into(),
-                    size: (240, 240).into(),
-                    bot: ViaTarget::Primitive,
-                    top: ViaTarget::Metal(0),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(67, &via_purps)?)),
-                },
-                ViaLayer {
-                    name: "via1".into(),
-                    size: (240, 240).into(),
-                    bot: 0.into(),
-                    top: 1.into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(68, &via_purps)?)),
-                },
-                ViaLayer {
-                    name: "via2".into(),
-                    size: (240, 240).into(),
-                    bot: 1.into(),
-                    top: 2.into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(69, &via_purps)?)),
-                },
-                ViaLayer {
-                    name: "via3".into(),
-                    size: (240, 240).into(),
-                    bot: 2.into(),
-                    top: 3.into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(70, &via_purps)?)),
-                },
-                ViaLayer {
-                    name: "via4".into(),
-                    size: (240, 240).into(),
-                    bot: 3.into(),
-                    top: 4.into(),
-                    raw: Some(rawlayers.add(raw::Layer::from_pairs(71, &via_purps)?)),
-                },
+    // A via between two non-adjacent, same-direction metals
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.vias[1].top = 2.into();
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains("via1") && err.contains("met1") && err.contains("met3"));
+    Ok(())
+}
+/// Check raw layers are defined in the stack's `rawlayers`, and that missing ones fail conversion rather than panic
+#[test]
+fn raw_layer_definitions() -> LayoutResult<()> {
+    use crate::abs::{Abstract, Port, Side};
+    use crate::conv::raw::RawExporter;
+    use crate::library::Library;
+    use crate::outline::Outline;
+
+    // A metal layer whose raw layer is from another set of [raw::Layers]
+    let mut others = raw::Layers::default();
+    let mut key = None;
+    for num in 0..20 {
+        key = Some(others.add(raw::Layer::new(num, "other")));
+    }
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[1].raw = key;
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains("met2"));
+    // As does a via layer
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.vias[1].raw = key;
+    let err = stack.validate().unwrap_err().to_string();
+    assert!(err.contains("via1"));
+
+    // A metal layer without one validates, but fails to export an abstract using it
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[0].raw = None;
+    let stack = stack.validate()?;
+    let mut abs = Abstract::new("abs", 1, Outline::rect(4, 1)?);
+    abs.ports.push(Port::edge(
+        "a",
+        LayerIndex(0),
+        TrackIndex(2),
+        Side::BottomOrLeft,
+    ));
+    let mut lib = Library::new("raw_layer_definitions");
+    lib.cells.add(abs);
+    let err = RawExporter::convert(lib, stack).unwrap_err().to_string();
+    assert!(err.contains("met1 has no raw layer"));
+    Ok(())
+}
+/// Resolve [LayerOffset]s specified relative to the layer's tracks
+#[test]
+fn layer_offsets() -> LayoutResult<()> {
+    use crate::coords::DbUnits;
+    let layer = |rail: isize, offset: LayerOffset| MetalLayer {
+        name: "met1".into(),
+        entries: vec![
+            TrackSpec::gnd(rail),
+            TrackSpec::repeat(vec![TrackEntry::gap(230), TrackEntry::sig(140)], 2),
+            TrackSpec::gap(230),
+            TrackSpec::pwr(rail),
+        ],
+        dir: Dir::Horiz,
+        cutsize: (250).into(),
+        offset,
+        overlap: rail.into(),
+        raw: None,
+        flip: FlipMode::None,
+        prim: PrimitiveMode::Stack,
+        min_length: None,
+        colors: 1,
+        color_datatypes: Vec::new(),
+    };
+    // Centering the 490-wide rail reproduces the explicit offset
+    let centered = layer(490, LayerOffset::CenterRailOnOrigin);
+    assert_eq!(centered.offset_units()?, DbUnits(-245));
+    assert_eq!(
+        centered.to_layer_period(0, 10_000)?,
+        layer(490, (-245).into()).to_layer_period(0, 10_000)?
+    );
+    // And keeps it centered when its width changes
+    let period = layer(510, LayerOffset::CenterRailOnOrigin).to_layer_period(0, 10_000)?;
+    let rail = &period.rails[0].data;
+    assert_eq!(rail.start + DbUnits(rail.width.0 / 2), DbUnits(0));
+    // Track-counted offsets place the start of the given entry at the origin
+    assert_eq!(
+        layer(490, LayerOffset::Tracks(2)).offset_units()?,
+        DbUnits(-720)
+    );
+    assert!(layer(490, LayerOffset::Tracks(99)).offset_units().is_err());
+
+    // Bare integers deserialize as [LayerOffset::Units]
+    #[cfg(feature = "yaml")]
+    {
+        use crate::utils::SerializationFormat::Yaml;
+        let offsets: Vec<LayerOffset> = Yaml.from_str("[-245, CenterRailOnOrigin, {Tracks: 2}]")?;
+        assert_eq!(
+            offsets,
+            vec![
+                LayerOffset::Units(DbUnits(-245)),
+                LayerOffset::CenterRailOnOrigin,
+                LayerOffset::Tracks(2)
+            ]
+        );
+        let yaml = Yaml.to_string(&offsets)?;
+        assert_eq!(Yaml.from_str::<Vec<LayerOffset>>(&yaml)?, offsets);
+        assert!(yaml.contains("- -245\n"));
+    }
+    Ok(())
+}
+#[test]
+fn track_plan() -> LayoutResult<()> {
+    let stack = SampleStacks::pdka()?;
+    let plan = stack.track_plan(LayerIndex(0))?;
+    assert_eq!(plan.nsignals, 6);
+    assert_eq!(plan.nrails, 2);
+    assert_eq!(plan.pitch.0, 2720);
+    assert!(plan.warnings.is_empty());
+    let desc = plan.to_string();
+    let lines: Vec<&str> = desc.lines().collect();
+    assert_eq!(lines.len(), 16);
+    assert_eq!(
+        lines[..4],
+        [
+            "Layer met1 (Horiz): pitch 2720, 6 signals, 2 rails",
+            "  Gnd      0  start   -240  width    480  center      0",
+            "  Gap      -  start    240  width    200  center    340",
+            "  Signal   0  start    440  width    140  center    510",
+        ]
+    );
+    assert_eq!(
+        lines[15],
+        "  Pwr      1  start   2480  width    480  center   2720"
+    );
+    assert_eq!(stack.metal(LayerIndex(0))?.spec.describe()?, desc);
+
+    // Flag layers spanning several primitive pitches, such as met4
+    let met4 = stack.track_plan(LayerIndex(3))?;
+    assert_eq!(
+        met4.warnings,
+        vec!["Layer pitch 4600 differs from the Horiz-direction primitive pitch 460"]
+    );
+    // But not those whose pitch only differs from the stack's least-common-multiple period,
+    // here met3, beneath a double-pitch met1
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[0].entries = vec![TrackSpec::sig(140), TrackSpec::gap(5300)];
+    stack.metals[0].overlap = DbUnits(0);
+    let stack = stack.validate()?;
+    let met3 = stack.track_plan(LayerIndex(2))?;
+    assert_ne!(met3.pitch, stack.pitches[2]);
+    assert!(met3.warnings.is_empty());
+
+    // Flag signals wider than rails, and zero-width entries
+    let mut stack = SampleStacks::pdka_stack()?;
+    stack.metals[0].entries = vec![
+        TrackSpec::gnd(100),
+        TrackSpec::sig(200),
+        TrackSpec::gap(0),
+        TrackSpec::pwr(100),
+    ];
+    let plan = stack.metals[0].track_plan()?;
+    assert_eq!(plan.warnings.len(), 2);
+    assert!(plan
+        .to_string()
+        .contains("Warning: Signal width 200 exceeds rail width 100"));
+    Ok(())
+}
+#[cfg(feature = "yaml")]
+#[test]
+fn layer_period_serialization() -> LayoutResult<()> {
+    let period = {
+        let layer = MetalLayer {
+            name: "met1".into(),
+            entries: vec![
+                TrackSpec::gnd(490),
+                TrackSpec::repeat(vec![TrackEntry::gap(230), TrackEntry::sig(140)], 2),
+                TrackSpec::gap(230),
+                TrackSpec::pwr(490),
             ],
-            rawlayers: Some(Ptr::new(rawlayers)),
+            dir: Dir::Horiz,
+            cutsize: (250).into(),
+            offset: (-245).into(),
+            overlap: (490).into(),
+            raw: None,
+            flip: FlipMode::None,
+            prim: PrimitiveMode::Stack,
+            min_length: None,
+            colors: 1,
+            color_datatypes: Vec::new(),
+        };
+        let mut period = layer.to_layer_period(1, 10_000)?;
+        let cross =
+            TrackCross::from_parts(LayerIndex(0), TrackIndex(1), LayerIndex(1), TrackIndex(3));
+        period.signals[1]
+            .cut(1000.into(), 1250.into(), &cross)
+            .unwrap();
+        period.block(5000.into(), 6000.into(), "inst0").unwrap();
+        let assn = Assign::new(
+            "clk",
+            TrackCross::from_parts(LayerIndex(0), TrackIndex(0), LayerIndex(1), TrackIndex(1)),
+        );
+        period.signals[0].set_net(500.into(), &assn).unwrap();
+        period
+        // `layer` dropped here; the period owns all of its data
+    };
+    assert_eq!(period.index, 1);
+    assert_eq!(period.signals.len(), 2);
+    assert_eq!(period.rails.len(), 2);
+    assert_eq!(period.signals[1].segments.len(), 5);
+
+    // Round-trip through YAML
+    let yaml = crate::utils::SerializationFormat::Yaml.to_string(&period)?;
+    let period2: LayerPeriod = crate::utils::SerializationFormat::Yaml.from_str(&yaml)?;
+    assert_eq!(period2.index, period.index);
+    assert_eq!(period2.signals[0].data.start, period.signals[0].data.start);
+    match &period2.signals[0].segments[0].tp {
+        TrackSegmentType::Wire { src: Some(assn) } => assert_eq!(assn.net, "clk"),
+        tp => panic!("Unexpected segment type {:?}", tp),
+    }
+    match &period2.signals[1].segments[1].tp {
+        TrackSegmentType::Cut { src } => assert_eq!(src.cross.track.index(), 3),
+        tp => panic!("Unexpected segment type {:?}", tp),
+    }
+    match &period2.rails[0].segments[1].tp {
+        TrackSegmentType::Blockage { src } => assert_eq!(src, "inst0"),
+        tp => panic!("Unexpected segment type {:?}", tp),
+    }
+    Ok(())
+}
+
+/// Load the committed YAML snapshot of [SampleStacks::pdka], to catch accidental breaking changes to its serialized format
+#[cfg(feature = "yaml")]
+#[test]
+fn stack_schema_snapshot() -> LayoutResult<()> {
+    use crate::utils::SerializationFormat::Yaml;
+    let fname = super::resource("stack_schema1.yaml");
+    let stack = SampleStacks::pdka_stack()?;
+    // Un-comment to re-generate the snapshot, after a deliberate schema change:
+    // stack.save(&fname, Yaml)?;
+    let loaded = Stack::open(&fname, Yaml)?;
+    assert_eq!(loaded.schema, STACK_SCHEMA);
+    // Compare field-by-field; [raw::Layers] include hash-maps, which serialize in varying order.
+    assert_eq!(loaded.units, stack.units);
+    assert_eq!(loaded.prim, stack.prim);
+    assert_eq!(
+        Yaml.to_string(&loaded.metals)?,
+        Yaml.to_string(&stack.metals)?
+    );
+    assert_eq!(Yaml.to_string(&loaded.vias)?, Yaml.to_string(&stack.vias)?);
+    assert_eq!(loaded.boundary_layer, stack.boundary_layer);
+    let rawlayers = |s: &Stack| s.rawlayers.as_ref().unwrap().read().unwrap().clone();
+    assert_eq!(rawlayers(&loaded), rawlayers(&stack));
+    loaded.validate()?;
+    Ok(())
+}
+/// Load a schema-0 [Stack], with optional fields omitted, and check newer schemas fail
+#[cfg(feature = "yaml")]
+#[test]
+fn stack_schema_migration() -> LayoutResult<()> {
+    use crate::utils::SerializationFormat::Yaml;
+    let yaml = "
+prim:
+  pitches:
+    x: 100
+    y: 100
+metals:
+  - name: met1
+    dir: Horiz
+    cutsize: 50
+    entries:
+      - Entry:
+          ttype: Signal
+          width: 50
+      - Entry:
+          ttype: Gap
+          width: 50
+";
+    let stack = Stack::parse(yaml, Yaml)?;
+    assert_eq!(stack.schema, STACK_SCHEMA);
+    assert_eq!(stack.units, Units::Nano);
+    assert!(stack.vias.is_empty());
+    assert_eq!(stack.metals[0].flip, FlipMode::None);
+    assert_eq!(stack.metals[0].prim, PrimitiveMode::Stack);
+    assert!(stack.metals[0].raw.is_none());
+
+    let err = Stack::parse(&format!("schema: 99\n{}", yaml), Yaml).unwrap_err();
+    assert!(err.to_string().contains("schema 99"));
+    Ok(())
+}
+#[cfg(feature = "yaml")]
+#[test]
+fn stack_open_with_migrations() -> LayoutResult<()> {
+    use crate::utils::{Migration, MigrationError};
+    use serde_yaml::Value;
+
+    // A hand-written "old" document, in which `metals` were named `layers`
+    let yaml = "
+prim:
+  pitches:
+    x: 100
+    y: 100
+layers:
+  - name: met1
+    dir: Horiz
+    cutsize: 50
+    entries:
+      - Entry:
+          ttype: Signal
+          width: 50
+      - Entry:
+          ttype: Gap
+          width: 50
+";
+    // Migration renaming `layers` to `metals`, checking each has a `name`
+    fn rename_layers(doc: &mut Value) -> Result<(), MigrationError> {
+        let map = doc.as_mapping_mut().unwrap();
+        let layers = match map.remove(&"layers".into()) {
+            Some(l) => l,
+            None => return Err(MigrationError::new("layers", "missing")),
         };
-        Ok(stack.validate()?)
+        for (idx, layer) in layers.as_sequence().into_iter().flatten().enumerate() {
+            if layer.get("name").is_none() {
+                let path = format!("layers[{}].name", idx);
+                return Err(MigrationError::new(path, "missing"));
+            }
+        }
+        map.insert("metals".into(), layers);
+        Ok(())
     }
+    let migrations = [Migration::identity(0), Migration::new(0, 1, rename_layers)];
+
+    let fname = std::env::temp_dir().join("stack_open_with_migrations.yaml");
+    std::fs::write(&fname, yaml)?;
+    let stack = Stack::open_with_migrations(&fname, &migrations)?;
+    assert_eq!(stack.schema, STACK_SCHEMA);
+    assert_eq!(stack.metals.len(), 1);
+    assert_eq!(stack.metals[0].name, "met1");
+
+    // The built-in migrations leave `layers` in place, and deserialization fails
+    assert!(Stack::open_with_migrations(&fname, STACK_MIGRATIONS).is_err());
+
+    // Migration failures report the step and the path within the document
+    std::fs::write(&fname, yaml.replace("name: met1", "nom: met1"))?;
+    let err = Stack::open_with_migrations(&fname, &migrations).unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("step 1"), "{}", err);
+    assert!(err.contains("`layers[0].name`"), "{}", err);
+    std::fs::remove_file(&fname)?;
+    Ok(())
 }
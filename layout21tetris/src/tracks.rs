@@ -5,16 +5,18 @@ use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
 // Local imports
-use crate::coords::DbUnits;
-use crate::instance::Instance;
-use crate::raw::{Dir, LayoutError, LayoutResult};
+use crate::coords::{DbUnits, LayerIndex, TrackIndex};
+use crate::raw::{self, Dir, LayoutError, LayoutResult};
 use crate::stack::{Assign, RelZ};
-use crate::utils::Ptr;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TrackEntry {
     pub ttype: TrackType,
     pub width: DbUnits,
+    /// Mask color, on layers with several [MetalLayer::colors](crate::stack::MetalLayer::colors).
+    /// Signals without one are assigned colors alternating from their predecessors.
+    #[serde(default)]
+    pub color: Option<u8>,
 }
 impl TrackEntry {
     /// Helper method: create of [TrackEntry] of [TrackType] [TrackType::Gap]
@@ -22,6 +24,7 @@ impl TrackEntry {
         TrackEntry {
             width: width.into(),
             ttype: TrackType::Gap,
+            color: None,
         }
     }
     /// Helper method: create of [TrackEntry] of [TrackType] [TrackType::Signal]
@@ -29,16 +32,46 @@ impl TrackEntry {
         TrackEntry {
             width: width.into(),
             ttype: TrackType::Signal,
+            color: None,
         }
     }
+    /// Set our mask color
+    pub fn with_color(mut self, color: u8) -> Self {
+        self.color = Some(color);
+        self
+    }
 }
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum TrackType {
     Gap,
     Signal,
     Rail(RailKind),
 }
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+impl std::fmt::Display for TrackType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Gap => write!(f, "Gap"),
+            Self::Signal => write!(f, "Signal"),
+            Self::Rail(RailKind::Pwr) => write!(f, "Pwr"),
+            Self::Rail(RailKind::Gnd) => write!(f, "Gnd"),
+        }
+    }
+}
+impl std::str::FromStr for TrackType {
+    type Err = LayoutError;
+    /// Parse a [TrackType], case-insensitively.
+    /// Rails are named by their [RailKind], either `pwr`/`vdd` or `gnd`/`vss`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gap" => Ok(Self::Gap),
+            "signal" | "sig" => Ok(Self::Signal),
+            "pwr" | "vdd" => Ok(Self::Rail(RailKind::Pwr)),
+            "gnd" | "vss" => Ok(Self::Rail(RailKind::Gnd)),
+            _ => LayoutError::fail(format!("Invalid TrackType `{}`", s)),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum RailKind {
     Pwr,
     Gnd,
@@ -65,30 +98,35 @@ impl TrackSpec {
         Self::Entry(TrackEntry {
             width: width.into(),
             ttype: TrackType::Gap,
+            color: None,
         })
     }
     pub fn sig(width: impl Into<DbUnits>) -> Self {
         Self::Entry(TrackEntry {
             width: width.into(),
             ttype: TrackType::Signal,
+            color: None,
         })
     }
     pub fn rail(width: impl Into<DbUnits>, rk: RailKind) -> Self {
         Self::Entry(TrackEntry {
             width: width.into(),
             ttype: TrackType::Rail(rk),
+            color: None,
         })
     }
     pub fn pwr(width: impl Into<DbUnits>) -> Self {
         Self::Entry(TrackEntry {
             width: width.into(),
             ttype: TrackType::Rail(RailKind::Pwr),
+            color: None,
         })
     }
     pub fn gnd(width: impl Into<DbUnits>) -> Self {
         Self::Entry(TrackEntry {
             width: width.into(),
             ttype: TrackType::Rail(RailKind::Gnd),
+            color: None,
         })
     }
     pub fn repeat(e: impl Into<Vec<TrackEntry>>, nrep: usize) -> Self {
@@ -109,7 +147,7 @@ impl Repeat {
         }
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TrackData {
     /// Track Type (Rail, Signal)
     pub ttype: TrackType,
@@ -121,20 +159,26 @@ pub struct TrackData {
     pub start: DbUnits,
     /// Track width
     pub width: DbUnits,
+    /// Mask color, on multi-patterned layers
+    #[serde(default)]
+    pub color: Option<u8>,
 }
 /// # Track
 ///
 /// An "instantiated" track, including:
 /// * Track-long data in a [TrackData], and
 /// * A set of [TrackSegment]s
-#[derive(Debug, Clone)]
-pub struct Track<'lib> {
+///
+/// Tracks own all of their data, and hold no references to their source [Layer](crate::stack::MetalLayer)s, [Assign]s, or [Instance](crate::instance::Instance)s.
+/// They can therefore outlive the conversion which creates them, and be serialized.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Track {
     /// Track-long data
     pub data: TrackData,
     /// Set of wire-segments, in positional order
-    pub segments: Vec<TrackSegment<'lib>>,
+    pub segments: Vec<TrackSegment>,
 }
-impl<'lib> Track<'lib> {
+impl Track {
     /// Verify a (generally just-created) [Track] is valid
     pub fn validate(self) -> LayoutResult<Self> {
         if self.data.width < DbUnits(0) {
@@ -143,7 +187,7 @@ impl<'lib> Track<'lib> {
         Ok(self)
     }
     /// Set the net of the track-segment at `at` to `net`
-    pub fn set_net(&mut self, at: DbUnits, assn: &'lib Assign) -> TrackResult<()> {
+    pub fn set_net(&mut self, at: DbUnits, assn: &Assign) -> TrackResult<()> {
         // First find the segment to be modified
         let mut seg = None;
         for s in self.segments.iter_mut() {
@@ -172,23 +216,126 @@ impl<'lib> Track<'lib> {
                 }
                 TrackSegmentType::Wire { ref mut src, .. } => {
                     // The good case - assignment succeeds.
-                    src.replace(assn);
+                    src.replace(assn.clone());
                     Ok(())
                 }
+                TrackSegmentType::Strap { ref net } if *net == assn.net => Ok(()),
+                TrackSegmentType::Strap { .. } => Err(TrackError::Conflict(
+                    TrackConflict::Assign(assn.clone()),
+                    TrackConflict::from(seg.tp.clone()),
+                )),
             },
         }
     }
+    /// Claim the track-segment at `at` for the net of `assn`, on behalf of a [Jog](crate::stack::Jog).
+    ///
+    /// Unlike [Track::set_net], the segment must be either an unassigned wire,
+    /// or a wire or strap already carrying the same net.
+    /// Cuts, blockages, and metal of other nets are all conflicts.
+    pub fn jog(&mut self, at: DbUnits, assn: &Assign) -> TrackResult<()> {
+        let seg = self
+            .segments
+            .iter_mut()
+            .find(|s| s.start <= at && s.stop >= at)
+            .ok_or(TrackError::OutOfBounds(at))?;
+        match seg.tp {
+            TrackSegmentType::Wire { ref mut src } => match src {
+                None => {
+                    src.replace(assn.clone());
+                    Ok(())
+                }
+                Some(ref prior) if prior.net == assn.net => Ok(()),
+                Some(ref prior) => Err(TrackError::Conflict(
+                    TrackConflict::Assign(assn.clone()),
+                    TrackConflict::Assign(prior.clone()),
+                )),
+            },
+            TrackSegmentType::Strap { ref net } if *net == assn.net => Ok(()),
+            _ => Err(TrackError::Conflict(
+                TrackConflict::Assign(assn.clone()),
+                TrackConflict::from(seg.tp.clone()),
+            )),
+        }
+    }
+    /// Claim the region from `start` to `stop` for strap-net `net`.
+    ///
+    /// Splits any overlapping wire segments, inserting a single [TrackSegmentType::Strap] segment in their place.
+    /// Cuts, blockages, and other straps in the region are always conflicts.
+    /// Wire segments assigned to other nets conflict per `policy`.
+    pub fn strap(
+        &mut self,
+        start: DbUnits,
+        stop: DbUnits,
+        net: &str,
+        policy: ConflictPolicy,
+    ) -> TrackResult<()> {
+        // Bounds-check against the end of our segments, which are the end of the cell
+        self.bounds_check(stop)?;
+        // Check each overlapping segment for conflicts
+        let strap = || TrackConflict::Strap(net.to_string());
+        for seg in self.segments.iter() {
+            if seg.stop <= start || seg.start >= stop {
+                continue; // No overlap
+            }
+            match seg.tp {
+                TrackSegmentType::Wire { src: None } => (),
+                TrackSegmentType::Wire {
+                    src: Some(ref assn),
+                } => {
+                    if assn.net != net && policy == ConflictPolicy::Error {
+                        return Err(TrackError::Conflict(
+                            strap(),
+                            TrackConflict::Assign(assn.clone()),
+                        ));
+                    }
+                }
+                _ => return Err(TrackError::Conflict(strap(), seg.tp.clone().into())),
+            }
+        }
+        // All clear. Split the overlapping segments, keeping any parts outside the strap.
+        let mut segments = Vec::with_capacity(self.segments.len() + 2);
+        for seg in self.segments.drain(..) {
+            if seg.stop <= start || seg.start >= stop {
+                segments.push(seg);
+                continue;
+            }
+            if seg.start < start {
+                segments.push(TrackSegment {
+                    tp: seg.tp.clone(),
+                    start: seg.start,
+                    stop: start,
+                    labels: Vec::new(),
+                });
+            }
+            if seg.start <= start {
+                segments.push(TrackSegment {
+                    tp: TrackSegmentType::Strap { net: net.into() },
+                    start,
+                    stop,
+                    labels: Vec::new(),
+                });
+            }
+            if seg.stop > stop {
+                segments.push(TrackSegment {
+                    tp: seg.tp,
+                    start: stop,
+                    stop: seg.stop,
+                    labels: Vec::new(),
+                });
+            }
+        }
+        self.segments = segments;
+        Ok(())
+    }
     /// Insert a cut or blockage corresponding to `blockage`.
     pub fn cut_or_block(
         &mut self,
         start: DbUnits,
         stop: DbUnits,
-        tp: TrackSegmentType<'lib>,
+        tp: TrackSegmentType,
     ) -> TrackResult<()> {
         // First bounds-check against the end of our segments, which are the end of the cell
-        if stop > self.segments.last().unwrap().stop {
-            return Err(TrackError::OutOfBounds(stop));
-        }
+        self.bounds_check(stop)?;
         // Find the segment where the blockage starts
         let segidx = self
             .segments
@@ -206,13 +353,16 @@ impl<'lib> Track<'lib> {
                 ));
             }
             TrackSegmentType::Cut { src } => {
-                return Err(TrackError::CutConflict(
-                    TrackConflict::from(tp),
-                    src.clone(),
-                ));
+                return Err(TrackError::CutConflict(TrackConflict::from(tp), src));
             }
             TrackSegmentType::Wire { .. } => seg.tp.clone(),
             TrackSegmentType::Rail(_) => seg.tp.clone(),
+            TrackSegmentType::Strap { .. } => {
+                return Err(TrackError::Conflict(
+                    TrackConflict::from(tp),
+                    TrackConflict::from(seg.tp.clone()),
+                ));
+            }
         };
         // Make sure the cut only effects one segment, or fail
         if seg.stop < stop {
@@ -223,36 +373,223 @@ impl<'lib> Track<'lib> {
         // All clear; time to cut it.
         // In the more-common case in which the cut-end and segment-end *do not* coincide, create and insert a new segment.
         let mut to_be_inserted: Vec<(usize, TrackSegment)> = Vec::new();
-        to_be_inserted.push((segidx + 1, TrackSegment { start, stop, tp }));
+        to_be_inserted.push((
+            segidx + 1,
+            TrackSegment {
+                start,
+                stop,
+                tp,
+                labels: Vec::new(),
+            },
+        ));
         if seg.stop != stop {
             let newseg = TrackSegment {
                 tp: tpcopy,
                 start: stop,
                 stop: seg.stop,
+                labels: Vec::new(),
             };
             to_be_inserted.push((segidx + 2, newseg));
         }
         // Update the existing segment (and importantly, drop its mutable borrow)
         seg.stop = start;
+        seg.labels.clear();
         for (idx, seg) in to_be_inserted {
             self.segments.insert(idx, seg);
         }
         Ok(())
     }
-    /// Insert a blockage from `start` to `stop`.
+    /// Insert a blockage from `start` to `stop`, caused by the instance named `src`.
     /// Fails if the region is not a contiguous wire segment.
-    pub fn block(&mut self, start: DbUnits, stop: DbUnits, src: &Ptr<Instance>) -> TrackResult<()> {
-        self.cut_or_block(start, stop, TrackSegmentType::Blockage { src: src.clone() })
+    pub fn block(&mut self, start: DbUnits, stop: DbUnits, src: &str) -> TrackResult<()> {
+        self.cut_or_block(start, stop, TrackSegmentType::Blockage { src: src.into() })
     }
     /// Cut from `start` to `stop`.
-    /// Fails if the region is not a contiguous wire segment.
-    pub fn cut(
+    ///
+    /// Cuts are idempotent, and merge with any existing cuts they overlap or abut,
+    /// such that overlapping cuts are equivalent to a single cut of their union.
+    /// Fails if the region overlaps a blockage or strap.
+    pub fn cut(&mut self, start: DbUnits, stop: DbUnits, src: &TrackCross) -> TrackResult<()> {
+        self.cut_many(&[(start, stop, *src)])
+    }
+    /// Apply each of `cuts`, as (start, stop, src) tuples.
+    ///
+    /// Overlapping and abutting cuts are merged before being applied,
+    /// and the resultant segments are independent of the order of `cuts`.
+    /// Each merged cut is attributed to the source of its earliest-starting constituent,
+    /// including any existing cuts it is merged with. Ties are broken by [TrackCross] ordering.
+    pub fn cut_many(&mut self, cuts: &[(DbUnits, DbUnits, TrackCross)]) -> TrackResult<()> {
+        let mut cuts = cuts.to_vec();
+        cuts.sort_by_key(|(start, stop, src)| (*start, *src, *stop));
+        let mut merged: Vec<(DbUnits, DbUnits, TrackCross)> = Vec::with_capacity(cuts.len());
+        for (start, stop, src) in cuts {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(stop),
+                _ => merged.push((start, stop, src)),
+            }
+        }
+        for (start, stop, src) in merged {
+            self.cut_union(start, stop, src)?;
+        }
+        Ok(())
+    }
+    /// Check that `stop` does not extend beyond our last segment.
+    /// A track with no segments has no valid locations.
+    fn bounds_check(&self, stop: DbUnits) -> TrackResult<()> {
+        match self.segments.last() {
+            Some(last) if stop <= last.stop => Ok(()),
+            _ => Err(TrackError::OutOfBounds(stop)),
+        }
+    }
+    /// Cut from `start` to `stop`, merging with any existing cuts which overlap or abut the region
+    fn cut_union(&mut self, start: DbUnits, stop: DbUnits, src: TrackCross) -> TrackResult<()> {
+        // First bounds-check against the end of our segments, which are the end of the cell
+        self.bounds_check(stop)?;
+        // Find the range of segments affected: those overlapping the region, plus any abutting cuts
+        let hits = |seg: &TrackSegment| match seg.tp {
+            TrackSegmentType::Cut { .. } => seg.stop >= start && seg.start <= stop,
+            _ => seg.stop > start && seg.start < stop,
+        };
+        let first = self
+            .segments
+            .iter()
+            .position(hits)
+            .ok_or(TrackError::OutOfBounds(start))?;
+        let last = self.segments.iter().rposition(hits).unwrap();
+        // Check for conflicts, and sort out the extent and source of the merged cut
+        let (mut ustart, mut ustop, mut usrc) = (start, stop, src);
+        for seg in self.segments[first..=last].iter() {
+            match seg.tp {
+                TrackSegmentType::Blockage { ref src } => {
+                    return Err(TrackError::BlockageConflict(
+                        TrackConflict::Cut(usrc),
+                        src.clone(),
+                    ));
+                }
+                TrackSegmentType::Strap { .. } => {
+                    return Err(TrackError::Conflict(
+                        TrackConflict::Cut(usrc),
+                        TrackConflict::from(seg.tp.clone()),
+                    ));
+                }
+                TrackSegmentType::Cut { src } => {
+                    if (seg.start, src) < (ustart, usrc) {
+                        usrc = src;
+                    }
+                    ustart = ustart.min(seg.start);
+                    ustop = ustop.max(seg.stop);
+                }
+                TrackSegmentType::Wire { .. } | TrackSegmentType::Rail(_) => (),
+            }
+        }
+        // All clear; replace the affected segments.
+        // Any un-cut portions of the first and last are retained, as in [Track::cut_or_block].
+        let (head, tail) = (&self.segments[first], &self.segments[last]);
+        let mut replacement = Vec::with_capacity(3);
+        if !matches!(head.tp, TrackSegmentType::Cut { .. }) {
+            replacement.push(TrackSegment {
+                tp: head.tp.clone(),
+                start: head.start,
+                stop: ustart,
+                labels: Vec::new(),
+            });
+        }
+        replacement.push(TrackSegment {
+            tp: TrackSegmentType::Cut { src: usrc },
+            start: ustart,
+            stop: ustop,
+            labels: Vec::new(),
+        });
+        if !matches!(tail.tp, TrackSegmentType::Cut { .. }) && tail.stop != ustop {
+            replacement.push(TrackSegment {
+                tp: tail.tp.clone(),
+                start: ustop,
+                stop: tail.stop,
+                labels: Vec::new(),
+            });
+        }
+        self.segments.splice(first..=last, replacement);
+        Ok(())
+    }
+    /// Get the indices of our "sliver" segments: wires, rails, and straps shorter than `min`,
+    /// adjacent to a cut or blockage. Zero-length segments are not included.
+    pub fn slivers(&self, min: DbUnits) -> Vec<usize> {
+        (0..self.segments.len())
+            .filter(|idx| self.is_sliver(*idx, min))
+            .collect()
+    }
+    /// Remove each sliver, per [Track::slivers], which carries no net assignment,
+    /// by extending an adjacent cut or blockage over it.
+    /// Returns the removed segments. Slivers of assigned wires, straps, and rails are left in place.
+    pub fn remove_slivers(&mut self, min: DbUnits) -> Vec<TrackSegment> {
+        self.remove_slivers_where(min, |tp| matches!(tp, TrackSegmentType::Wire { src: None }))
+    }
+    /// Remove each rail sliver, per [Track::slivers], as in [Track::remove_slivers].
+    /// Rails carry their power or ground nets, so unlike unassigned wires, their slivers are only removed on request.
+    /// Returns the removed segments.
+    pub fn remove_rail_slivers(&mut self, min: DbUnits) -> Vec<TrackSegment> {
+        self.remove_slivers_where(min, |tp| matches!(tp, TrackSegmentType::Rail(_)))
+    }
+    /// Remove each sliver whose type satisfies `pred`, returning the removed segments
+    fn remove_slivers_where(
         &mut self,
-        start: DbUnits,
-        stop: DbUnits,
-        src: &'lib TrackCross,
-    ) -> TrackResult<()> {
-        self.cut_or_block(start, stop, TrackSegmentType::Cut { src })
+        min: DbUnits,
+        pred: impl Fn(&TrackSegmentType) -> bool,
+    ) -> Vec<TrackSegment> {
+        let mut removed = Vec::new();
+        let mut idx = 0;
+        while idx < self.segments.len() {
+            if !pred(&self.segments[idx].tp) || !self.is_sliver(idx, min) {
+                idx += 1;
+                continue;
+            }
+            let seg = self.segments.remove(idx);
+            // Extend the prior cut or blockage if there is one, or otherwise the next
+            if idx > 0 && is_cut_or_block(&self.segments[idx - 1].tp) {
+                self.segments[idx - 1].stop = seg.stop;
+            } else {
+                self.segments[idx].start = seg.start;
+            }
+            removed.push(seg);
+        }
+        removed
+    }
+    /// Boolean indication of whether segment number `idx` is a sliver, per [Track::slivers]
+    fn is_sliver(&self, idx: usize, min: DbUnits) -> bool {
+        let seg = &self.segments[idx];
+        let len = seg.stop - seg.start;
+        if is_cut_or_block(&seg.tp) || len <= DbUnits(0) || len >= min {
+            return false;
+        }
+        let prev = idx.checked_sub(1).and_then(|i| self.segments.get(i));
+        let next = self.segments.get(idx + 1);
+        prev.iter()
+            .chain(next.iter())
+            .any(|s| is_cut_or_block(&s.tp))
+    }
+    /// Get the [TrackSegment] spanning position `at`, if any.
+    /// Positions on the boundary between two segments resolve to the latter.
+    pub fn segment_at(&self, at: DbUnits) -> Option<&TrackSegment> {
+        self.segments.iter().find(|s| s.start <= at && at < s.stop)
+    }
+    /// Get the net of the metal at position `at`, if it is both present and assigned.
+    /// Cuts, blockages, and unassigned wires have no net.
+    pub fn net_at(&self, at: DbUnits) -> Option<String> {
+        self.segment_at(at).and_then(|s| s.tp.net())
+    }
+    /// Add [raw::Label] `label` to the [TrackSegment] spanning position `at`
+    pub fn label(&mut self, at: DbUnits, label: raw::Label) -> TrackResult<()> {
+        match self
+            .segments
+            .iter_mut()
+            .find(|s| s.start <= at && at < s.stop)
+        {
+            Some(seg) => {
+                seg.labels.push(label);
+                Ok(())
+            }
+            None => Err(TrackError::OutOfBounds(at)),
+        }
     }
     /// Set the stop position for our last [TrackSegment] to `stop`
     pub fn stop(&mut self, stop: DbUnits) -> LayoutResult<()> {
@@ -264,37 +601,78 @@ impl<'lib> Track<'lib> {
         Ok(())
     }
 }
+/// Boolean indication of whether `tp` is a cut or blockage
+fn is_cut_or_block(tp: &TrackSegmentType) -> bool {
+    matches!(
+        tp,
+        TrackSegmentType::Cut { .. } | TrackSegmentType::Blockage { .. }
+    )
+}
 /// # Segments of un-split, single-net wire on a [Track]
-#[derive(Debug, Clone)]
-pub struct TrackSegment<'lib> {
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrackSegment {
     /// Segment-Type
-    pub tp: TrackSegmentType<'lib>,
+    pub tp: TrackSegmentType,
     /// Start Location, in [Stack]'s `units`
     pub start: DbUnits,
     /// End/Stop Location, in [Stack]'s `units`
     pub stop: DbUnits,
+    /// Additional text [raw::Label]s, positioned as fractions of the segment's length.
+    /// Splitting the segment, e.g. by cuts, blockages, or straps, drops them,
+    /// so add them once the track's segments are final.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<raw::Label>,
 }
-#[derive(Debug, Clone)]
-pub enum TrackSegmentType<'lib> {
-    Cut { src: &'lib TrackCross },
-    Blockage { src: Ptr<Instance> },
-    Wire { src: Option<&'lib Assign> },
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TrackSegmentType {
+    /// Cut, created by the [TrackCross] `src`
+    Cut { src: TrackCross },
+    /// Instance blockage, created by the [Instance](crate::instance::Instance) named `src`
+    Blockage { src: String },
+    /// Wire, with optional net-assignment `src`
+    Wire { src: Option<Assign> },
+    /// Power/ground rail
     Rail(RailKind),
+    /// Part of a [Strap](crate::stack::Strap) on net `net`
+    Strap { net: String },
+}
+impl TrackSegmentType {
+    /// Get our net name, if we are assigned metal. Cuts, blockages, and unassigned wires have none.
+    pub fn net(&self) -> Option<String> {
+        match self {
+            TrackSegmentType::Wire { src } => src.as_ref().map(|assn| assn.net.clone()),
+            TrackSegmentType::Rail(rk) => Some(rk.to_string()),
+            TrackSegmentType::Strap { net } => Some(net.clone()),
+            TrackSegmentType::Cut { .. } | TrackSegmentType::Blockage { .. } => None,
+        }
+    }
+}
+/// # Track Conflict Policy
+///
+/// Handling of net-assignment conflicts, e.g. between a [Strap](crate::stack::Strap) and prior [Assign]s.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail on conflicting assignments
+    #[default]
+    Error,
+    /// Override earlier conflicting assignments with later ones
+    Override,
 }
 /// # Track Reference
 ///
-/// Integer-pair representing a pointer to a [Layer] and track-index.
+/// Pair of a [Layer] index and track-index.
 ///
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TrackRef {
     /// Layer Index
-    pub layer: usize,
+    pub layer: LayerIndex,
     /// Track Index
-    pub track: usize,
+    pub track: TrackIndex,
 }
 impl TrackRef {
     /// Create a new [TrackRef]
-    pub fn new(layer: usize, track: usize) -> Self {
+    pub fn new(layer: LayerIndex, track: TrackIndex) -> Self {
         Self { layer, track }
     }
 }
@@ -302,7 +680,14 @@ impl TrackRef {
 ///
 /// Located intersection between opposite-direction [Layer]s in [Track]-Space
 ///
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Both `track` and `cross` use global track indexing, counting signal tracks from the origin of their cell,
+/// across all of its periods. On a layer with `n` signal tracks per period, track `i` is signal track `i % n`
+/// of period `i / n`, numbered in ascending coordinate order within each period.
+/// Both the primary and crossing coordinates are resolved by [ValidMetalLayer::locate](crate::validate::ValidMetalLayer::locate),
+/// so e.g. track 20 of a layer with six signal tracks per period always lands in its fourth period (index 3),
+/// regardless of the period in which it is crossed.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TrackCross {
     /// "Primary" [Track] being referred to
     pub track: TrackRef,
@@ -313,25 +698,25 @@ impl TrackCross {
     pub fn new(track: TrackRef, cross: TrackRef) -> Self {
         Self { track, cross }
     }
-    /// Create from four [usize], representing the two (layer-index, track-index) pairs.
-    pub fn from_parts(layer1: usize, index1: usize, layer2: usize, index2: usize) -> Self {
+    /// Create from two (layer-index, track-index) pairs.
+    pub fn from_parts(
+        layer1: LayerIndex,
+        index1: TrackIndex,
+        layer2: LayerIndex,
+        index2: TrackIndex,
+    ) -> Self {
         Self {
             track: TrackRef::new(layer1, index1),
             cross: TrackRef::new(layer2, index2),
         }
     }
     /// Create from a (layer-index, track-index) pair and a [RelZ]
-    pub fn from_relz(layer: usize, track: usize, at: usize, relz: RelZ) -> Self {
+    pub fn from_relz(layer: LayerIndex, track: TrackIndex, at: TrackIndex, relz: RelZ) -> Self {
         let layer2 = match relz {
-            RelZ::Above => layer + 1,
-            RelZ::Below => layer - 1,
-        };
-        let track = TrackRef { layer, track };
-        let cross = TrackRef {
-            layer: layer2,
-            track: at,
+            RelZ::Above => LayerIndex(layer.index() + 1),
+            RelZ::Below => LayerIndex(layer.index() - 1),
         };
-        Self::new(track, cross)
+        Self::new(TrackRef::new(layer, track), TrackRef::new(layer2, at))
     }
 }
 
@@ -339,7 +724,12 @@ impl TrackCross {
 pub enum TrackConflict {
     Assign(Assign),
     Cut(TrackCross),
-    Blockage(Ptr<Instance>),
+    /// Instance blockage, by instance name
+    Blockage(String),
+    /// Strap, by net name
+    Strap(String),
+    /// Power/ground rail
+    Rail(RailKind),
 }
 impl std::fmt::Display for TrackConflict {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -347,16 +737,19 @@ impl std::fmt::Display for TrackConflict {
             // Delegate simpler types to [Debug]
             TrackConflict::Assign(a) => std::fmt::Debug::fmt(a, f),
             TrackConflict::Cut(c) => std::fmt::Debug::fmt(c, f),
-            // And for more complicated ones, [Display]
-            TrackConflict::Blockage(i) => std::fmt::Debug::fmt(i, f),
+            TrackConflict::Blockage(i) => write!(f, "Instance {}", i),
+            TrackConflict::Strap(n) => write!(f, "Strap {}", n),
+            TrackConflict::Rail(rk) => write!(f, "Rail {}", rk.to_string()),
         }
     }
 }
-impl From<TrackSegmentType<'_>> for TrackConflict {
-    fn from(tp: TrackSegmentType<'_>) -> Self {
+impl From<TrackSegmentType> for TrackConflict {
+    fn from(tp: TrackSegmentType) -> Self {
         match tp {
-            TrackSegmentType::Cut { src } => TrackConflict::Cut(src.clone()),
-            TrackSegmentType::Blockage { src } => TrackConflict::Blockage(src.clone()),
+            TrackSegmentType::Cut { src } => TrackConflict::Cut(src),
+            TrackSegmentType::Blockage { src } => TrackConflict::Blockage(src),
+            TrackSegmentType::Strap { net } => TrackConflict::Strap(net),
+            TrackSegmentType::Rail(rk) => TrackConflict::Rail(rk),
             _ => unreachable!(),
         }
     }
@@ -366,7 +759,7 @@ pub enum TrackError {
     Overlap(DbUnits, DbUnits),
     Conflict(TrackConflict, TrackConflict),
     CutConflict(TrackConflict, TrackCross),
-    BlockageConflict(TrackConflict, Ptr<Instance>),
+    BlockageConflict(TrackConflict, String),
 }
 pub type TrackResult<T> = Result<T, TrackError>;
 impl std::fmt::Debug for TrackError {
@@ -383,7 +776,7 @@ impl std::fmt::Debug for TrackError {
             TrackError::BlockageConflict(t0, t1) => {
                 write!(
                     f,
-                    "Conflicting Instance Blockages: \n * {}\n * {:?}\n",
+                    "Conflicting Instance Blockages: \n * {}\n * Instance {}\n",
                     t0, t1
                 )
             }
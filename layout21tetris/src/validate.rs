@@ -6,19 +6,22 @@
 
 // Std-Lib Imports
 use std::convert::TryFrom;
+use std::ops::Range;
 
 // Local imports
 use crate::{
-    abs::Abstract,
+    abs::{Abstract, PortKind, TopLoc},
     cell::Cell,
-    coords::{DbUnits, HasUnits},
+    coords::{DbUnits, HasUnits, Int, LayerIndex, PrimPitches, SignedTrack, TrackIndex, Xy},
     instance::Instance,
-    layout::Layout,
+    layout::{Keepout, Layout},
     library::Library,
-    raw::{self, LayoutError, LayoutResult, Units},
-    stack::{Assign, LayerPeriodData, MetalLayer, PrimitiveLayer, Stack},
-    stack::{PrimitiveMode, ViaLayer, ViaTarget},
-    tracks::{TrackCross, TrackRef},
+    net::{NetName, NetNamePolicy},
+    outline::Outline,
+    raw::{self, Dir, LayoutError, LayoutResult, Units},
+    stack::{Assign, FlipMode, Jog, LayerPeriodData, MetalLayer, PrimitiveLayer, Stack, Strap},
+    stack::{RelZ, TrackPlan, ViaLayer, ViaLayerRef, ViaLoc, ViaPlacement, ViaTarget},
+    tracks::{TrackCross, TrackData, TrackEntry, TrackRef, TrackSpec, TrackType},
     utils::{ErrorHelper, Ptr},
 };
 
@@ -52,7 +55,9 @@ impl StackValidator {
             vias,
             metals,
             prim,
+            site,
             rawlayers,
+            nets,
             ..
         } = stack;
         // Validate the primitive layer
@@ -65,6 +70,31 @@ impl StackValidator {
             "Invalid zero or negative Primitive pitch",
         )?;
 
+        // Validate the placement site, which defaults to the primitive pitches
+        let site = site.unwrap_or(prim.pitches);
+        for dir in [Dir::Horiz, Dir::Vert] {
+            self.assert(
+                site[dir].raw() > 0 && site[dir].raw() % prim.pitches[dir].raw() == 0,
+                format!(
+                    "Invalid placement site {:?}: not a positive multiple of the primitive pitches {:?}",
+                    site, prim.pitches
+                ),
+            )?;
+        }
+
+        // Check that adjacent metal layers run in perpendicular directions,
+        // as required for their track-intersections to be meaningful.
+        // Checked first, as a same-direction layer generally also fails to fit the primitive grid.
+        for pair in metals.windows(2) {
+            let (bot, top) = (&pair[0], &pair[1]);
+            self.assert(
+                bot.dir != top.dir,
+                format!(
+                    "Invalid Stack: adjacent metal layers {} and {} both run {:?}",
+                    bot.name, top.name, bot.dir
+                ),
+            )?;
+        }
         // Validate each metal layer
         let mut valid_metals = Vec::new();
         for (num, layer) in metals.into_iter().enumerate() {
@@ -81,7 +111,73 @@ impl StackValidator {
             }
             pitches[num] = pitch;
         }
-        // FIXME: add checks on [ViaLayer]s
+        // Check that each [ViaLayer] connecting two metals connects an existing, perpendicular pair
+        for via in vias.iter() {
+            if let (ViaTarget::Metal(bot), ViaTarget::Metal(top)) = (&via.bot, &via.top) {
+                let (bot, top) =
+                    match (valid_metals.get(bot.index()), valid_metals.get(top.index())) {
+                        (Some(bot), Some(top)) => (&bot.spec, &top.spec),
+                        _ => {
+                            return self.fail(format!(
+                                "Invalid Via {} between non-existent metal layers {:?} and {:?}",
+                                via.name, via.bot, via.top
+                            ))
+                        }
+                    };
+                self.assert(
+                    bot.dir != top.dir,
+                    format!(
+                        "Invalid Via {}: metal layers {} and {} both run {:?}",
+                        via.name, bot.name, top.name, bot.dir
+                    ),
+                )?;
+            }
+        }
+        // Check that each metal and via layer's raw layer, if any, is defined in our `rawlayers`
+        if let Some(ref rawlayers) = rawlayers {
+            let rawlayers = rawlayers.read()?;
+            let named_keys = valid_metals
+                .iter()
+                .map(|m| (&m.spec.name, m.raw))
+                .chain(vias.iter().map(|v| (&v.name, v.raw)));
+            for (name, key) in named_keys {
+                if let Some(key) = key {
+                    self.assert(
+                        rawlayers.get(key).is_some(),
+                        format!(
+                            "Invalid Stack: raw layer of layer {} is not defined in its `rawlayers`",
+                            name
+                        ),
+                    )?;
+                }
+            }
+            // Resolve each layer's color datatypes to raw purposes
+            for metal in valid_metals.iter_mut() {
+                for datatype in metal.spec.color_datatypes.iter() {
+                    let purpose = metal
+                        .raw
+                        .and_then(|key| rawlayers.get(key))
+                        .and_then(|layer| layer.purpose(*datatype))
+                        .cloned();
+                    let purpose = self.unwrap(
+                        purpose,
+                        format!(
+                            "Invalid Stack: color datatype {} of layer {} is not defined on its raw layer",
+                            datatype, metal.spec.name
+                        ),
+                    )?;
+                    metal.color_purposes.push(purpose);
+                }
+            }
+        } else if let Some(metal) = valid_metals
+            .iter()
+            .find(|m| !m.spec.color_datatypes.is_empty())
+        {
+            return self.fail(format!(
+                "Invalid Stack: color datatypes of layer {} require `rawlayers`",
+                metal.spec.name
+            ));
+        }
         // Stack checks out! Return its derived data
         Ok(ValidStack {
             units,
@@ -89,14 +185,16 @@ impl StackValidator {
             pitches,
             metals: valid_metals,
             prim,
+            site,
             rawlayers,
             boundary_layer,
+            nets,
         })
     }
     /// Perform validation on a [Layer], return a corresponding [ValidMetalLayer]
     pub fn validate_metal<'prim>(
         &mut self,
-        layer: MetalLayer,
+        mut layer: MetalLayer,
         index: usize,
         prim: &'prim PrimitiveLayer,
     ) -> LayoutResult<ValidMetalLayer> {
@@ -119,14 +217,24 @@ impl StackValidator {
                 layer
             ),
         )?;
-        // Check for fit on the primitive grid, if the layer is in primitives
-        match layer.prim {
-            PrimitiveMode::Split | PrimitiveMode::Prim => {
-                let prim_pitch = prim.pitches[!layer.dir];
-                self.assert(pitch % prim_pitch == 0, format!("Invalid layer {:?} shared with Primitives is not an integer multiple of the primitive pitch in the {:?} direction", layer, !layer.dir))?;
-            }
-            PrimitiveMode::Stack => (),
-        }
+        // Check the layer's period fits the primitive grid in its periodic direction.
+        // Periods spanning several primitive pitches are allowed, as for wide power-grid layers,
+        // but any remainder would drift the layer's tracks relative to instance placement.
+        let prim_pitch = prim.pitches[!layer.dir];
+        self.assert(
+            pitch % prim_pitch == 0,
+            format!(
+                "Invalid layer {}: period {} (sum of entries less overlap) is not an integer multiple of the {:?}-direction primitive pitch {}",
+                layer.name,
+                pitch.raw(),
+                !layer.dir,
+                prim_pitch.raw()
+            ),
+        )?;
+        // Resolve the layer's offset to [DbUnits], so that downstream users needn't
+        layer.offset = layer.offset_units()?.into();
+        // Assign and check any multi-patterning mask colors
+        self.color_tracks(&mut layer)?;
         // Convert to a prototype [LayerPeriod]
         // This is frequently used for calculating track locations
         let period_data = layer.to_layer_period_data()?;
@@ -136,17 +244,82 @@ impl StackValidator {
             index,
             period_data,
             pitch,
+            color_purposes: Vec::new(),
         })
     }
+    /// Assign mask colors to the uncolored signal entries of multi-patterned `layer`,
+    /// alternating from each one's predecessor, and check that no two adjacent signal tracks share a color.
+    /// Adjacency spans period boundaries, accounting for flipped periods. Rails separate their neighbors; gaps do not.
+    /// Flattens the entries of such layers, so that each carries its own color.
+    fn color_tracks(&mut self, layer: &mut MetalLayer) -> LayoutResult<()> {
+        let colors = layer.colors.max(1);
+        self.assert(
+            layer.color_datatypes.is_empty() || layer.color_datatypes.len() == colors,
+            format!(
+                "Invalid layer {}: {} color datatypes for {} colors",
+                layer.name,
+                layer.color_datatypes.len(),
+                colors
+            ),
+        )?;
+        for entry in layer.entries_iter() {
+            if let Some(color) = entry.color {
+                self.assert(
+                    usize::from(color) < colors,
+                    format!(
+                        "Invalid layer {}: color {} of {} colors",
+                        layer.name, color, colors
+                    ),
+                )?;
+            }
+        }
+        if colors == 1 {
+            return Ok(()); // Single-patterned, nothing to assign
+        }
+        let mut entries = layer.entries();
+        let mut prev: Option<u8> = None;
+        for entry in entries.iter_mut().filter(|e| e.ttype == TrackType::Signal) {
+            let color = entry
+                .color
+                .unwrap_or_else(|| prev.map_or(0, |p| ((usize::from(p) + 1) % colors) as u8));
+            entry.color = Some(color);
+            prev = Some(color);
+        }
+        // Collect pairs of adjacent tracks, including those abutting across period boundaries
+        let tracks: Vec<&TrackEntry> = entries
+            .iter()
+            .filter(|e| e.ttype != TrackType::Gap)
+            .collect();
+        let mut pairs: Vec<(&TrackEntry, &TrackEntry)> =
+            tracks.windows(2).map(|w| (w[0], w[1])).collect();
+        if let (Some(first), Some(last)) = (tracks.first(), tracks.last()) {
+            match layer.flip {
+                FlipMode::None => pairs.push((last, first)),
+                FlipMode::EveryOther => pairs.extend([(*last, *last), (*first, *first)]),
+            }
+        }
+        for (a, b) in pairs {
+            if a.ttype == TrackType::Signal && b.ttype == TrackType::Signal && a.color == b.color {
+                return self.fail(format!(
+                    "Invalid layer {}: adjacent signal tracks share mask color {:?}",
+                    layer.name, a.color
+                ));
+            }
+        }
+        layer.entries = entries.into_iter().map(TrackSpec::Entry).collect();
+        Ok(())
+    }
 }
 
 /// Derived data for a [Stack], after it has gone through some validation steps.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ValidStack {
     /// Measurement units
     pub units: Units,
     /// Primitive layer
     pub prim: PrimitiveLayer,
+    /// Placement site
+    pub site: Xy<DbUnits>,
     /// Set of via layers
     pub vias: Vec<ViaLayer>,
     /// Metal Layers
@@ -158,18 +331,77 @@ pub struct ValidStack {
     pub rawlayers: Option<Ptr<raw::Layers>>,
     /// Layer used for cell outlines/ boundaries
     pub boundary_layer: Option<raw::LayerKey>,
+    /// Net-name validation policy
+    pub nets: NetNamePolicy,
 }
 impl ValidStack {
+    /// Create the [TrackPlan] of metal layer `idx`,
+    /// additionally flagging a layer pitch which differs from the primitive pitch in its periodic dimension,
+    /// e.g. that of a multi-pitch power-grid layer
+    pub fn track_plan(&self, idx: LayerIndex) -> LayoutResult<TrackPlan> {
+        let metal = self.metal(idx)?;
+        let mut plan = metal.spec.track_plan()?;
+        let prim_pitch = self.prim.pitches[!metal.spec.dir];
+        if plan.pitch != prim_pitch {
+            plan.warnings.push(format!(
+                "Layer pitch {} differs from the {:?}-direction primitive pitch {}",
+                plan.pitch.0, !metal.spec.dir, prim_pitch.0
+            ));
+        }
+        Ok(plan)
+    }
+    /// Get the placement site, in primitive pitches
+    pub fn site_pitches(&self) -> Xy<PrimPitches> {
+        Xy::new(
+            PrimPitches::x(self.site.x.raw() / self.prim.pitches.x.raw()),
+            PrimPitches::y(self.site.y.raw() / self.prim.pitches.y.raw()),
+        )
+    }
+    /// Boolean indication of whether `dist` lies on the placement-site grid
+    pub fn on_site(&self, dist: PrimPitches) -> bool {
+        dist.num % self.site_pitches()[dist.dir].num == 0
+    }
+    /// Snap `dist` down to the nearest placement-site boundary
+    pub fn snap_to_site(&self, dist: PrimPitches) -> PrimPitches {
+        let site = self.site_pitches()[dist.dir].num;
+        PrimPitches::new(dist.dir, dist.num.div_euclid(site) * site)
+    }
+    /// Get the number of metal layers
+    pub fn num_metals(&self) -> usize {
+        self.metals.len()
+    }
     /// Get Metal-Layer number `idx`. Returns `None` if `idx` is out of bounds.
-    pub fn metal(&self, idx: usize) -> LayoutResult<&ValidMetalLayer> {
+    pub fn metal(&self, idx: LayerIndex) -> LayoutResult<&ValidMetalLayer> {
+        let idx = idx.index();
         if idx >= self.metals.len() {
             LayoutError::fail(format!("Invalid metal index {}", idx))
         } else {
             Ok(&self.metals[idx])
         }
     }
+    /// Get the number of signal tracks on metal-layer `idx` within [Outline] `outline`
+    pub fn signal_tracks(&self, idx: LayerIndex, outline: &Outline) -> LayoutResult<usize> {
+        let metal = self.metal(idx)?;
+        // Tracks repeat across the layer's periodic dimension, opposite its direction
+        let breadth = outline.max(!metal.spec.dir);
+        let breadth = DbUnits(breadth.num * self.prim.pitches[breadth.dir].raw());
+        let nperiods = usize::try_from(breadth / metal.pitch)?;
+        Ok(nperiods * metal.period_data.signals.len())
+    }
+    /// Get the (x,y) center of [TrackCross] `cross`, in [DbUnits]
+    pub fn track_cross_xy(&self, cross: &TrackCross) -> LayoutResult<Xy<DbUnits>> {
+        // Find the (x,y) center of our track, initially assuming it runs vertically
+        let x = self.metal(cross.track.layer)?.center(cross.track.track)?;
+        let y = self.metal(cross.cross.layer)?.center(cross.cross.track)?;
+        // And transpose if it's actually horizontal
+        let xy = Xy::new(x, y);
+        match self.metal(cross.track.layer)?.spec.dir {
+            Dir::Horiz => Ok(xy.transpose()),
+            Dir::Vert => Ok(xy),
+        }
+    }
     /// Get the via-layer whose bottom "target" is metal-layer `idx`.
-    pub fn via_from(&self, idx: usize) -> LayoutResult<&ViaLayer> {
+    pub fn via_from(&self, idx: LayerIndex) -> LayoutResult<&ViaLayer> {
         for via_layer in self.vias.iter() {
             if let ViaTarget::Metal(k) = via_layer.bot {
                 if k == idx {
@@ -188,7 +420,7 @@ impl ValidStack {
         }
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ValidMetalLayer {
     /// Original Layer Spec
     pub spec: MetalLayer,
@@ -202,6 +434,8 @@ pub struct ValidMetalLayer {
     pub pitch: DbUnits,
     /// Raw layer-key
     pub raw: Option<raw::LayerKey>,
+    /// Raw purpose of each mask color, per [MetalLayer::color_datatypes]
+    pub color_purposes: Vec<raw::LayerPurpose>,
 }
 impl ValidMetalLayer {
     /// Get the track-index at [DbUnits] `dist`
@@ -213,29 +447,142 @@ impl ValidMetalLayer {
         let remainder = DbUnits(dist % self.pitch);
         let mut index = usize::try_from(npitches)? * self.period_data.signals.len();
 
-        index += self
+        index += match self
             .period_data
             .signals
             .iter()
             .position(|sig| sig.start + sig.width > remainder)
-            .unwrap();
+        {
+            Some(i) => i,
+            None => {
+                return LayoutError::fail(format!(
+                    "No track on layer {} at or after {:?}",
+                    self.spec.name, dist
+                ))
+            }
+        };
         Ok(index)
     }
+    /// Get the signal track centered at `dist`, in our periodic dimension.
+    /// Both `dist` and the resulting index may be negative, i.e. beyond our origin.
+    /// Returns `None` if no track is centered at `dist`.
+    pub fn signed_track_at(&self, dist: DbUnits) -> Option<SignedTrack> {
+        let periods = dist.raw().div_euclid(self.pitch.raw());
+        let remainder = DbUnits(dist.raw().rem_euclid(self.pitch.raw()));
+        let nsig = self.period_data.signals.len();
+        // Compare against the tracks of a non-negative period of the same flipping
+        let period = usize::try_from(periods.rem_euclid(2)).ok()?;
+        let base = self.pitch * period;
+        let pos = (0..nsig).position(|k| match self.period_span(period, k) {
+            Ok((start, stop)) => (start + stop) / 2 - base == remainder,
+            Err(_) => false,
+        })?;
+        Some(SignedTrack(periods * nsig as Int + pos as Int))
+    }
+    /// Boolean indication of whether period `period` is flipped, per our [FlipMode]
+    pub fn flipped(&self, period: usize) -> bool {
+        self.spec.flip == FlipMode::EveryOther && period % 2 == 1
+    }
+    /// Locate signal-track `idx` as a (period, index within the period) pair.
+    ///
+    /// Global track indices count signal tracks from the origin of their cell, and are shared by
+    /// the primary and crossing sides of each [TrackCross](crate::tracks::TrackCross).
+    /// Track `idx` is signal track `idx % n` of period `idx / n`, for `n` signal tracks per period.
+    /// Within each period, signal tracks are numbered in ascending coordinate order,
+    /// including in the flipped periods of [FlipMode::EveryOther] layers.
+    pub fn locate(&self, idx: TrackIndex) -> LayoutResult<(usize, usize)> {
+        let idx = idx.index();
+        let nsig = self.period_data.signals.len();
+        if nsig == 0 {
+            return LayoutError::fail(format!(
+                "Invalid track {} on layer {}, which has no signal tracks",
+                idx, self.spec.name
+            ));
+        }
+        Ok((idx / nsig, idx % nsig))
+    }
     /// Get the center-coordinate of signal-track `idx`, in our periodic dimension
-    pub fn center(&self, idx: usize) -> LayoutResult<DbUnits> {
-        // FIXME: incorrect for asymmetric tracks via `FlipMode` turned on!
-        let len = self.period_data.signals.len();
-        let track = &self.period_data.signals[idx % len];
-        let mut cursor = self.pitch * (idx / len);
-        cursor += track.start + track.width / 2;
-        Ok(cursor)
+    pub fn center(&self, idx: TrackIndex) -> LayoutResult<DbUnits> {
+        let (start, stop) = self.span(idx)?;
+        Ok((start + stop) / 2)
     }
     /// Get the spanning-coordinates of signal-track `idx`, in our periodic dimension
-    pub fn span(&self, idx: usize) -> LayoutResult<(DbUnits, DbUnits)> {
-        let len = self.period_data.signals.len();
-        let track = &self.period_data.signals[idx % len];
-        let cursor = self.pitch * (idx / len) + track.start;
-        Ok((cursor, cursor + track.width))
+    pub fn span(&self, idx: TrackIndex) -> LayoutResult<(DbUnits, DbUnits)> {
+        let (period, k) = self.locate(idx)?;
+        self.period_span(period, k)
+    }
+    /// Iterate over our signal and rail tracks whose extents intersect `span`, in our periodic dimension.
+    ///
+    /// Yields a [TrackData] per track, in ascending coordinate order, with its physical start and width,
+    /// and its global index among tracks of its type, per [ValidMetalLayer::locate].
+    /// Only tracks of non-negative periods are included, i.e. those of a cell at the origin,
+    /// including any which our (typically negative) offset places before it.
+    /// Rails shared between adjacent periods via our `overlap` are yielded once, as part of the earlier period.
+    pub fn tracks_in(
+        &self,
+        span: Range<Int>,
+    ) -> LayoutResult<impl Iterator<Item = TrackData> + '_> {
+        let offset = self.spec.offset_units()?.raw();
+        let pitch = self.pitch.raw();
+        let extent = pitch + self.spec.overlap.raw();
+        let (nsig, nrails) = (self.period_data.signals.len(), self.period_data.rails.len());
+        // Periods whose extents intersect `span`, from the first ending after its start to the last starting before its end
+        let first = ((span.start - offset - extent).div_euclid(pitch) + 1).max(0);
+        let last = (span.end - offset - 1).div_euclid(pitch);
+        let mut prev = None;
+        let tracks = (first..=last)
+            .flat_map(move |period| {
+                let entries: Box<dyn Iterator<Item = &TrackEntry>> =
+                    if self.flipped(period as usize) {
+                        Box::new(self.spec.entries_iter().rev())
+                    } else {
+                        Box::new(self.spec.entries_iter())
+                    };
+                let mut cursor = offset + period * pitch;
+                let (mut sig, mut rail) = (period as usize * nsig, period as usize * nrails);
+                entries.filter_map(move |e| {
+                    let start = cursor;
+                    cursor += e.width.raw();
+                    let index = match e.ttype {
+                        TrackType::Gap => return None,
+                        TrackType::Signal => &mut sig,
+                        TrackType::Rail(_) => &mut rail,
+                    };
+                    *index += 1;
+                    Some(TrackData {
+                        ttype: e.ttype,
+                        index: *index - 1,
+                        dir: self.spec.dir,
+                        start: DbUnits(start),
+                        width: e.width,
+                        color: e.color,
+                    })
+                })
+            })
+            .filter(move |t| t.start.raw() < span.end && (t.start + t.width).raw() > span.start)
+            .filter(move |t| {
+                // Skip the second of each pair of overlapping rails
+                let shared = prev == Some((t.start, t.width));
+                prev = Some((t.start, t.width));
+                !shared
+            });
+        Ok(tracks)
+    }
+    /// Get the spanning-coordinates of signal track `k` within period `period`, per [ValidMetalLayer::locate]
+    fn period_span(&self, period: usize, k: usize) -> LayoutResult<(DbUnits, DbUnits)> {
+        let signals = &self.period_data.signals;
+        let base = self.pitch * period;
+        if !self.flipped(period) {
+            let track = &signals[k];
+            let start = base + track.start;
+            return Ok((start, start + track.width));
+        }
+        // Flipped periods lay out our entries in reverse, across their full (overlapping) extent
+        let track = &signals[signals.len() - 1 - k];
+        let offset = self.spec.offset_units()?;
+        let extent = self.pitch + self.spec.overlap;
+        let start = base + offset + offset + extent - track.start - track.width;
+        Ok((start, start + track.width))
     }
 }
 /// Validate [Library] `lib`. Requires a valid `stack`.
@@ -245,10 +592,33 @@ pub fn validate_lib(lib: &Library, stack: &ValidStack) -> LayoutResult<()> {
 /// # Library Validator
 pub struct LibValidator<'stk> {
     pub stack: &'stk ValidStack,
+    /// Non-fatal issues, e.g. per [crate::net::RailConflict::Warn]
+    pub warnings: Vec<String>,
+    /// Whether layouts must be absolutely placed.
+    /// If not, relatively-placed instances and un-resolved placements are not checked.
+    placed: bool,
 }
 impl<'stk> LibValidator<'stk> {
     pub(crate) fn new(stack: &'stk ValidStack) -> Self {
-        Self { stack }
+        Self {
+            stack,
+            warnings: Vec::new(),
+            placed: true,
+        }
+    }
+    /// Create a validator for not-yet-placed [Library] content, e.g. ahead of [crate::placer::Placer]
+    pub(crate) fn unplaced(stack: &'stk ValidStack) -> Self {
+        Self {
+            placed: false,
+            ..Self::new(stack)
+        }
+    }
+    /// Generate a warning.
+    /// Collected in `warnings`, and printed to the console.
+    fn warn(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        eprintln!("Warning: {}", msg);
+        self.warnings.push(msg);
     }
     pub(crate) fn validate_lib(&mut self, lib: &Library) -> LayoutResult<()> {
         self.assert(lib.name.len() > 0, "Library name is empty")?;
@@ -256,12 +626,44 @@ impl<'stk> LibValidator<'stk> {
             let mut cell = cellptr.write()?;
             self.validate_cell(&mut *cell)?;
         }
+        for libptr in lib.libs.iter() {
+            self.validate_lib(&*libptr.read()?)?;
+        }
         // FIXME: validate raw-content
         Ok(())
     }
     pub(crate) fn validate_cell(&mut self, cell: &mut Cell) -> LayoutResult<()> {
-        // FIXME: add checks on `metals`, `outline`
         self.assert(cell.name.len() > 0, "Cell name is empty")?;
+        let metals = [
+            cell.abs.as_ref().map(|a| a.metals),
+            cell.layout.as_ref().map(|l| l.metals),
+        ];
+        for metals in metals.iter().flatten() {
+            self.assert(
+                *metals <= self.stack.num_metals(),
+                format!(
+                    "Cell {} uses {} metal layers, more than the Stack's {}",
+                    cell.name,
+                    metals,
+                    self.stack.num_metals()
+                ),
+            )?;
+        }
+        if let Some(ref abs) = cell.abs {
+            self.validate_outline(&abs.outline, &cell.name)?;
+        }
+        if let Some(ref layout) = cell.layout {
+            self.validate_outline(&layout.outline, &cell.name)?;
+        }
+        if let (Some(ref abs), Some(ref layout)) = (&cell.abs, &cell.layout) {
+            self.assert(
+                abs.outline.congruent(&layout.outline),
+                format!(
+                    "Outline mismatch between Abstract {:?} and Layout {:?} of Cell {}",
+                    abs.outline, layout.outline, cell.name
+                ),
+            )?;
+        }
         if let Some(ref mut abs) = cell.abs {
             self.assert(
                 abs.name == cell.name,
@@ -286,13 +688,64 @@ impl<'stk> LibValidator<'stk> {
         // FIXME: validate any raw and circuit content
         Ok(())
     }
-    pub(crate) fn validate_abstract(&mut self, _abs: &Abstract) -> LayoutResult<()> {
-        Ok(()) // FIXME!
+    pub(crate) fn validate_abstract(&mut self, abs: &Abstract) -> LayoutResult<()> {
+        // FIXME: check edge and top-edge ports
+        for port in abs.ports.iter() {
+            if let PortKind::ZTopInner { ref locs } = port.kind {
+                self.validate_top_locs(abs, &port.name, locs)?;
+            }
+        }
+        Ok(())
+    }
+    /// Validate that each of `locs`, of the [PortKind::ZTopInner] port `name`,
+    /// lies on the signal tracks of `abs`'s top layer and its adjacent layer.
+    fn validate_top_locs(
+        &mut self,
+        abs: &Abstract,
+        name: &str,
+        locs: &[TopLoc],
+    ) -> LayoutResult<()> {
+        self.assert(
+            !locs.is_empty(),
+            format!("Port {} of Abstract {} has no locations", name, abs.name),
+        )?;
+        let top = match abs.metals.checked_sub(1) {
+            Some(top) => top,
+            None => {
+                return self.fail(format!(
+                    "Port {} of Abstract {} has no metal layers",
+                    name, abs.name
+                ))
+            }
+        };
+        let ntop = self.stack.signal_tracks(LayerIndex(top), &abs.outline)?;
+        for loc in locs.iter() {
+            let adj = match loc.relz {
+                RelZ::Above => Some(top + 1),
+                RelZ::Below => top.checked_sub(1),
+            };
+            let nadj = match adj {
+                Some(adj) if adj < self.stack.num_metals() => {
+                    self.stack.signal_tracks(LayerIndex(adj), &abs.outline)?
+                }
+                _ => 0,
+            };
+            self.assert(
+                loc.track.index() < ntop && loc.at.index() < nadj,
+                format!(
+                    "Invalid location {:?} of Port {} of Abstract {}, with {} top-layer and {} adjacent-layer tracks",
+                    loc, name, abs.name, ntop, nadj
+                ),
+            )?;
+        }
+        Ok(())
     }
     pub(crate) fn validate_layout(&mut self, layout: &Layout) -> LayoutResult<()> {
         for instptr in layout.instances.iter() {
             let inst = instptr.read()?;
-            self.validate_instance(&*inst)?;
+            if self.placed || inst.loc.abs().is_ok() {
+                self.validate_instance(&inst)?;
+            }
         }
         for cut in layout.cuts.iter() {
             self.validate_track_cross(cut)?;
@@ -300,28 +753,84 @@ impl<'stk> LibValidator<'stk> {
         for assn in layout.assignments.iter() {
             self.validate_assign(assn)?;
         }
+        for strap in layout.straps.iter() {
+            self.validate_strap(strap, &layout.outline)?;
+        }
+        for jog in layout.jogs.iter() {
+            self.validate_jog(jog, layout)?;
+        }
+        for keepout in layout.keepouts.iter() {
+            self.validate_keepout(keepout, layout)?;
+        }
+        for via in layout.vias.iter() {
+            self.validate_via(via)?;
+        }
+        for layer in layout.suppress_rails.iter() {
+            self.assert(
+                *layer < layout.metals,
+                format!(
+                    "Rails suppressed on layer {} in Cell {}, which has {} metal layers",
+                    layer, layout.name, layout.metals
+                ),
+            )?;
+        }
         self.assert(
-            layout.places.len() == 0,
+            !self.placed || layout.places.is_empty(),
             "Internal Error: Layout being validated without first being Placed ",
         )?;
         Ok(())
     }
-    pub(crate) fn validate_instance(&mut self, _inst: &Instance) -> LayoutResult<()> {
-        Ok(()) // FIXME!
+    /// Validate that `outline`, of Cell `name`, is well-formed per [Outline::from_prim_pitches],
+    /// and that each of its vertices lies on the placement-site grid
+    pub(crate) fn validate_outline(&mut self, outline: &Outline, name: &str) -> LayoutResult<()> {
+        if let Err(e) = Outline::from_prim_pitches(outline.x.clone(), outline.y.clone()) {
+            let msg = match e {
+                LayoutError::Str(s) => s,
+                e => format!("{}", e),
+            };
+            return self.fail(format!("Cell {}: {}: {:?}", name, msg, outline));
+        }
+        for dist in outline.x.iter().chain(outline.y.iter()) {
+            self.assert(
+                self.stack.on_site(*dist),
+                format!(
+                    "Outline {:?} of Cell {} is not an integer number of placement sites {:?}",
+                    outline,
+                    name,
+                    self.stack.site_pitches()
+                ),
+            )?;
+        }
+        Ok(())
     }
-    pub(crate) fn validate_assign(&mut self, assn: &Assign) -> LayoutResult<ValidAssign> {
-        // Net "validation": just empty-string checking, at least for now
+    /// Validate that `inst`, which must be absolutely placed, lies on the placement-site grid
+    pub(crate) fn validate_instance(&mut self, inst: &Instance) -> LayoutResult<()> {
+        let loc = inst.loc.abs()?;
         self.assert(
-            assn.net.len() > 0,
-            format!("Invalid zero-length net assigned at {:?}", assn.at),
+            self.stack.on_site(loc.x) && self.stack.on_site(loc.y),
+            format!(
+                "Instance {} at {:?} is not on the placement-site grid {:?}",
+                inst.inst_name,
+                loc,
+                self.stack.site_pitches()
+            ),
         )?;
+        Ok(())
+    }
+    pub(crate) fn validate_assign(&mut self, assn: &Assign) -> LayoutResult<ValidAssign> {
+        // Validate the net name. Assignments are always to signal tracks.
+        let warning = NetName::validate_signal(&assn.net, &self.stack.nets)
+            .map_err(|e| self.err(format!("{} at {:?}", e, assn.at)))?;
+        if let Some(msg) = warning {
+            self.warn(format!("{} at {:?}", msg, assn.at));
+        }
         // Validate the track-cross location
         let i = &assn.at;
         self.validate_track_cross(i)?;
         // Arrange the two by top/bottom
-        let (top, bot) = if i.track.layer == i.cross.layer + 1 {
+        let (top, bot) = if i.track.layer.index() == i.cross.layer.index() + 1 {
             (i.track, i.cross)
-        } else if i.track.layer == i.cross.layer - 1 {
+        } else if i.track.layer.index() + 1 == i.cross.layer.index() {
             (i.cross, i.track)
         } else {
             return self.fail(format!("Invalid Assign on non-adjacent layers: {:?}", assn));
@@ -332,15 +841,200 @@ impl<'stk> LibValidator<'stk> {
             src: assn.clone(),
         })
     }
+    pub(crate) fn validate_strap(&mut self, strap: &Strap, outline: &Outline) -> LayoutResult<()> {
+        // Straps are generally expected to carry rail nets, so check only the name itself
+        NetName::validate(&strap.net, &self.stack.nets)?;
+        let ntracks = self.stack.signal_tracks(LayerIndex(strap.layer), outline)?;
+        self.assert(
+            !strap.tracks.is_empty() && strap.tracks.end <= ntracks,
+            format!(
+                "Invalid tracks for Strap {:?} on layer with {} signal tracks",
+                strap, ntracks
+            ),
+        )?;
+        let span = outline.max(self.stack.metal(LayerIndex(strap.layer))?.spec.dir);
+        self.assert(
+            !strap.extent.is_empty() && strap.extent.end as isize <= span.num,
+            format!(
+                "Invalid extent for Strap {:?} in outline {:?}",
+                strap, outline
+            ),
+        )?;
+        Ok(())
+    }
+    /// Validate [Jog] `jog` in `layout`: that its tracks are adjacent and within the outline,
+    /// that its location is a track of an adjacent layer within the cell,
+    /// and that it is no narrower than either of the tracks it connects.
+    pub(crate) fn validate_jog(&mut self, jog: &Jog, layout: &Layout) -> LayoutResult<()> {
+        let warning = NetName::validate_signal(&jog.net, &self.stack.nets)
+            .map_err(|e| self.err(format!("{} in Jog {:?}", e, jog)))?;
+        if let Some(msg) = warning {
+            self.warn(format!("{} in Jog {:?}", msg, jog));
+        }
+        let at_layer = jog.at.layer.index();
+        self.assert(
+            jog.layer < layout.metals && at_layer < layout.metals,
+            format!(
+                "Jog {:?} in Cell {}, which has {} metal layers",
+                jog, layout.name, layout.metals
+            ),
+        )?;
+        self.assert(
+            at_layer + 1 == jog.layer || jog.layer + 1 == at_layer,
+            format!("Invalid Jog on non-adjacent layers: {:?}", jog),
+        )?;
+        self.assert(
+            jog.track_a + 1 == jog.track_b || jog.track_b + 1 == jog.track_a,
+            format!("Jog {:?} connects non-adjacent tracks", jog),
+        )?;
+        let ntracks = self
+            .stack
+            .signal_tracks(LayerIndex(jog.layer), &layout.outline)?;
+        self.assert(
+            jog.track_a.max(jog.track_b) < ntracks,
+            format!(
+                "Invalid tracks for Jog {:?} on layer with {} signal tracks",
+                jog, ntracks
+            ),
+        )?;
+        let ntracks = self
+            .stack
+            .signal_tracks(LayerIndex(at_layer), &layout.outline)?;
+        self.assert(
+            jog.at.track.index() < ntracks,
+            format!(
+                "Invalid location for Jog {:?} on layer with {} signal tracks",
+                jog, ntracks
+            ),
+        )?;
+        // Check the jog is at least as wide as the tracks it connects
+        let width = |layer: usize, track: usize| -> LayoutResult<DbUnits> {
+            let (start, stop) = self
+                .stack
+                .metal(LayerIndex(layer))?
+                .span(TrackIndex(track))?;
+            Ok(stop - start)
+        };
+        let jog_width = width(at_layer, jog.at.track.index())?;
+        let min = width(jog.layer, jog.track_a)?.max(width(jog.layer, jog.track_b)?);
+        self.assert(
+            jog_width >= min,
+            format!(
+                "Jog {:?} is {:?} wide, narrower than its {:?} tracks",
+                jog, jog_width, min
+            ),
+        )?;
+        Ok(())
+    }
+    /// Validate [ViaPlacement] `via`: that its via-layer exists and connects two metal layers,
+    /// and that any track-intersection location lies on those two layers.
+    pub(crate) fn validate_via(&mut self, via: &ViaPlacement) -> LayoutResult<ValidVia> {
+        let stack = self.stack;
+        let index = match via.layer {
+            ViaLayerRef::Index(idx) => idx,
+            ViaLayerRef::Name(ref name) => match stack.vias.iter().position(|v| v.name == *name) {
+                Some(idx) => idx,
+                None => return self.fail(format!("Via {:?} on undefined via layer {}", via, name)),
+            },
+        };
+        let layer = stack.via(index)?;
+        let (bot, top) = match (&layer.bot, &layer.top) {
+            (ViaTarget::Metal(bot), ViaTarget::Metal(top)) => (bot.index(), top.index()),
+            _ => {
+                return self.fail(format!(
+                    "Via {:?}: via layer {} does not connect two metal layers",
+                    via, layer.name
+                ))
+            }
+        };
+        if let Some(ref net) = via.net {
+            NetName::validate(net, &stack.nets)?;
+        }
+        let loc = match via.at {
+            ViaLoc::Point(pt) => pt,
+            ViaLoc::Cross(ref cross) => {
+                self.validate_track_cross(cross)?;
+                let layers = (cross.track.layer.index(), cross.cross.layer.index());
+                self.assert(
+                    layers == (bot, top) || layers == (top, bot),
+                    format!(
+                        "Via {:?}: location is not on the metal layers of via layer {}",
+                        via, layer.name
+                    ),
+                )?;
+                self.track_cross_xy(cross)?
+            }
+        };
+        Ok(ValidVia {
+            src: via.clone(),
+            index,
+            bot,
+            top,
+            loc,
+        })
+    }
+    /// Validate that `keepout` lies within `layout`, and that none of its assignments land inside it.
+    pub(crate) fn validate_keepout(
+        &mut self,
+        keepout: &Keepout,
+        layout: &Layout,
+    ) -> LayoutResult<()> {
+        self.assert(
+            !keepout.layers.is_empty() && keepout.layers.end <= layout.metals,
+            format!(
+                "Invalid layers for Keepout {:?} in Layout with {} metal layers",
+                keepout, layout.metals
+            ),
+        )?;
+        let outline = &layout.outline;
+        self.assert(
+            !keepout.x.is_empty()
+                && !keepout.y.is_empty()
+                && keepout.x.end as isize <= outline.xmax().num
+                && keepout.y.end as isize <= outline.ymax().num,
+            format!("Keepout {:?} lies outside outline {:?}", keepout, outline),
+        )?;
+        // Convert the region to [DbUnits], and check each assignment's location against it
+        let pitches = self.stack.prim.pitches;
+        let span = |r: &std::ops::Range<usize>, pitch: DbUnits| {
+            (
+                DbUnits(r.start as isize * pitch.raw()),
+                DbUnits(r.end as isize * pitch.raw()),
+            )
+        };
+        let (x0, x1) = span(&keepout.x, pitches.x);
+        let (y0, y1) = span(&keepout.y, pitches.y);
+        for assn in layout.assignments.iter() {
+            let (track, cross) = (assn.at.track.layer, assn.at.cross.layer);
+            if !keepout.layers.contains(&track.index()) && !keepout.layers.contains(&cross.index())
+            {
+                continue;
+            }
+            let loc = self.track_cross_xy(&assn.at)?;
+            if loc.x >= x0 && loc.x < x1 && loc.y >= y0 && loc.y < y1 {
+                return self.fail(format!(
+                    "Assignment of net {} at {:?} lands inside Keepout {}",
+                    assn.net, assn.at, keepout.name
+                ));
+            }
+        }
+        Ok(())
+    }
+    /// Get the (x,y) center of [TrackCross] `i`, in [DbUnits]
+    fn track_cross_xy(&self, i: &TrackCross) -> LayoutResult<Xy<DbUnits>> {
+        self.stack.track_cross_xy(i)
+    }
     pub(crate) fn validate_track_cross(&mut self, i: &TrackCross) -> LayoutResult<()> {
         // Validate both [TrackRef]s
         self.validate_track_ref(&i.track)?;
         self.validate_track_ref(&i.cross)?;
         // Verify that the two are in opposite directions
-        if self.stack.metal(i.track.layer)?.spec.dir == self.stack.metal(i.cross.layer)?.spec.dir {
+        let track = &self.stack.metal(i.track.layer)?.spec;
+        let cross = &self.stack.metal(i.cross.layer)?.spec;
+        if track.dir == cross.dir {
             self.fail(format!(
-                "TrackCross {:?} and {:?} are in the same direction",
-                i.track, i.cross
+                "TrackCross {:?} and {:?} are in the same direction: layers {} and {} both run {:?}",
+                i.track, i.cross, track.name, cross.name, track.dir
             ))?;
         }
         Ok(())
@@ -348,7 +1042,7 @@ impl<'stk> LibValidator<'stk> {
     pub(crate) fn validate_track_ref(&mut self, i: &TrackRef) -> LayoutResult<()> {
         // Check that we won't reach outside the stack
         self.assert(
-            i.layer < self.stack.metals.len(),
+            i.layer.index() < self.stack.metals.len(),
             format!("Invalid TrackRef outside Stack: {:?}", i),
         )?;
         Ok(())
@@ -373,3 +1067,16 @@ pub struct ValidAssign {
     pub top: TrackRef,
     pub bot: TrackRef,
 }
+/// # Validated Via Placement
+///
+/// Includes the index of its via layer, the indices of the two metal layers it connects,
+/// and its location in [DbUnits].
+///
+#[derive(Debug, Clone)]
+pub struct ValidVia {
+    pub src: ViaPlacement,
+    pub index: usize,
+    pub bot: usize,
+    pub top: usize,
+    pub loc: Xy<DbUnits>,
+}
@@ -0,0 +1,57 @@
+//!
+//! # Stable Hashing
+//!
+//! Hashes for names derived from content, e.g. of generated cells and shortened labels,
+//! which must be reproducible across platforms, compiler versions, and runs.
+//!
+
+// Std-Lib
+use std::hash::Hasher;
+
+/// 64-bit FNV-1a offset basis
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+/// 64-bit FNV-1a prime
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// # FNV-1a Hasher
+///
+/// 64-bit FNV-1a [Hasher], which unlike [std::collections::hash_map::DefaultHasher]
+/// is specified to be stable across releases.
+///
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(FNV_OFFSET)
+    }
+}
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+/// 64-bit FNV-1a hash of `bytes`
+pub fn fnv1a(bytes: impl AsRef<[u8]>) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(bytes.as_ref());
+    hasher.finish()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values from the FNV specification
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a("foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -8,6 +8,11 @@ pub use ptr::*;
 pub mod ser;
 pub use ser::*;
 
+#[cfg(feature = "yaml")]
+pub mod migrate;
+#[cfg(feature = "yaml")]
+pub use migrate::*;
+
 pub mod error;
 pub use error::*;
 
@@ -19,3 +24,9 @@ pub use dep_order::*;
 
 pub mod enumstr;
 pub use enumstr::*;
+
+pub mod progress;
+pub use progress::*;
+
+pub mod hash;
+pub use hash::*;
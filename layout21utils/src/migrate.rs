@@ -0,0 +1,131 @@
+//!
+//! # Schema Migrations
+//!
+//! Upgrades of serialized documents written with earlier schema versions.
+//!
+//! Each [Migration] transforms an untyped [serde_yaml::Value] document from one schema version to the next.
+//! [migrate] applies a sequence of them, starting from the document's top-level `schema` field
+//! (or zero if it is absent), before the typed deserialization performed by [open_with_migrations].
+//!
+//! JSON documents are valid YAML, and can be migrated in the same way.
+//!
+
+// Std-Lib
+use std::path::Path;
+
+// Crates.io
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+
+// Local imports
+use crate::ser::Error;
+
+/// Signature of [Migration] functions, which transform a document in-place
+pub type MigrationFn = fn(&mut Value) -> Result<(), MigrationError>;
+
+/// # Schema Migration
+///
+/// Transforms a document from schema-version `from` to schema-version `to`.
+///
+#[derive(Clone, Copy)]
+pub struct Migration {
+    /// Source schema version
+    pub from: u32,
+    /// Destination schema version
+    pub to: u32,
+    /// Transformation function
+    pub func: MigrationFn,
+}
+impl Migration {
+    /// Create a new [Migration]
+    pub const fn new(from: u32, to: u32, func: MigrationFn) -> Self {
+        Self { from, to, func }
+    }
+    /// Create the identity [Migration] for schema-version `schema`, which makes no changes
+    pub const fn identity(schema: u32) -> Self {
+        Self::new(schema, schema, |_| Ok(()))
+    }
+}
+impl std::fmt::Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Migration({} => {})", self.from, self.to)
+    }
+}
+
+/// # Migration Error
+///
+/// Failure within a single [Migration], including the path to the offending value within the document,
+/// e.g. `metals[2].name`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationError {
+    /// Path within the document
+    pub path: String,
+    /// Error message
+    pub msg: String,
+}
+impl MigrationError {
+    /// Create a new [MigrationError] at `path`
+    pub fn new(path: impl Into<String>, msg: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            msg: msg.into(),
+        }
+    }
+}
+
+/// Apply `migrations` to `doc`, in sequence.
+///
+/// The starting version is read from `doc`'s top-level `schema` field, or zero if absent.
+/// Migrations from earlier versions are skipped; each remaining one must start from the version produced by its predecessor.
+/// The `schema` field is updated after each step.
+pub fn migrate(mut doc: Value, migrations: &[Migration]) -> Result<Value, Error> {
+    let mut schema = match doc.get("schema") {
+        None => 0,
+        Some(v) => match v.as_u64() {
+            Some(n) => n as u32,
+            None => return Err(Error::msg(format!("Invalid schema version `{:?}`", v))),
+        },
+    };
+    for (step, migration) in migrations.iter().enumerate() {
+        if migration.from < schema {
+            continue;
+        }
+        if migration.from > schema {
+            return Err(Error::msg(format!(
+                "Migration step {} ({:?}) cannot apply to document with schema {}",
+                step, migration, schema
+            )));
+        }
+        if let Err(e) = (migration.func)(&mut doc) {
+            return Err(Error::msg(format!(
+                "Migration step {} ({:?}) failed at `{}`: {}",
+                step, migration, e.path, e.msg
+            )));
+        }
+        schema = migration.to;
+        if let Value::Mapping(ref mut map) = doc {
+            map.insert("schema".into(), Value::Number(schema.into()));
+        }
+    }
+    Ok(doc)
+}
+
+/// Parse YAML (or JSON) string `txt`, apply `migrations`, and then deserialize the result
+pub fn from_str_with_migrations<T: DeserializeOwned>(
+    txt: &str,
+    migrations: &[Migration],
+) -> Result<T, Error> {
+    let doc: Value = serde_yaml::from_str(txt)?;
+    let doc = migrate(doc, migrations)?;
+    Ok(serde_yaml::from_value(doc)?)
+}
+
+/// Load YAML (or JSON) from file at path `fname`, apply `migrations`, and then deserialize the result
+pub fn open_with_migrations<T: DeserializeOwned>(
+    fname: impl AsRef<Path>,
+    migrations: &[Migration],
+) -> Result<T, Error> {
+    let txt = std::fs::read_to_string(fname)?;
+    from_str_with_migrations(&txt, migrations)
+}
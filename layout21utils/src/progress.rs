@@ -0,0 +1,98 @@
+//!
+//! # Progress Reporting
+//!
+//! Hooks for feedback during long-running conversions and exports.
+//!
+
+// Std-Lib
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// # Progress Callbacks
+///
+/// Invoked at cell and layer boundaries of long-running conversions.
+/// Each method defaults to a no-op.
+/// Implementations must be [Send] and [Sync], as they may be invoked from several threads.
+///
+pub trait Progress: Send + Sync {
+    /// Called as conversion of cell `name` begins, the `idx`th of `total`
+    fn on_cell_start(&self, _name: &str, _idx: usize, _total: usize) {}
+    /// Called as conversion of cell `name` completes, having produced `elems` elements
+    fn on_cell_done(&self, _name: &str, _elems: usize) {}
+    /// Called as conversion of layer `name` begins, within the current cell
+    fn on_layer(&self, _name: &str) {}
+}
+
+/// Shared [Progress] implementations, e.g. for inspection by their creator after use
+impl<T: Progress + ?Sized> Progress for Arc<T> {
+    fn on_cell_start(&self, name: &str, idx: usize, total: usize) {
+        (**self).on_cell_start(name, idx, total)
+    }
+    fn on_cell_done(&self, name: &str, elems: usize) {
+        (**self).on_cell_done(name, elems)
+    }
+    fn on_layer(&self, name: &str) {
+        (**self).on_layer(name)
+    }
+}
+
+/// # No-Op Progress
+///
+/// The default [Progress], which does nothing.
+///
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoProgress;
+impl Progress for NoProgress {}
+
+/// # Progress Hook
+///
+/// Shared pointer to a [Progress] implementation, for inclusion in option-structs.
+/// Defaults to [NoProgress].
+///
+#[derive(Clone)]
+pub struct ProgressHook(pub Arc<dyn Progress>);
+impl ProgressHook {
+    /// Create a new [ProgressHook] from `progress`
+    pub fn new(progress: impl Progress + 'static) -> Self {
+        Self(Arc::new(progress))
+    }
+}
+impl Default for ProgressHook {
+    fn default() -> Self {
+        Self::new(NoProgress)
+    }
+}
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressHook")
+    }
+}
+impl std::ops::Deref for ProgressHook {
+    type Target = dyn Progress;
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+/// # Cancellation Token
+///
+/// Shared flag for cooperatively cancelling long-running conversions, e.g. from a GUI or server thread.
+/// Clones share the same flag; once cancelled, a token remains cancelled.
+///
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+impl CancelToken {
+    /// Create a new, un-cancelled [CancelToken]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Request cancellation, of every user of this token and its clones
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+    /// Boolean indication of whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -9,6 +9,7 @@ use std::sync::{Arc, RwLock};
 
 // Crates.io
 use by_address::ByAddress;
+use serde::{de::Deserializer, ser::Error, ser::Serializer, Deserialize, Serialize};
 
 ///
 /// # Ptr  
@@ -19,7 +20,7 @@ use by_address::ByAddress;
 /// Attribute access is largely forwarded through [Deref] calls,
 /// allowing for fairly natural syntax after grabbing `read()` or `write()` access.
 /// For example:
-/// 
+///
 /// ```text
 /// let data = ptr.read()?;
 /// data.some_function();
@@ -98,6 +99,21 @@ impl<T> PartialEq for Ptr<T> {
     }
 }
 impl<T> Eq for Ptr<T> {}
+/// Serialization writes the pointed-to value, as if it were not behind a [Ptr].
+/// Note shared pointers are therefore written once per reference, and do not remain shared upon deserialization.
+impl<T: Serialize> Serialize for Ptr<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let guard = self
+            .read()
+            .map_err(|_| S::Error::custom("Cannot serialize Ptr with poisoned lock"))?;
+        guard.serialize(serializer)
+    }
+}
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ptr<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Ptr::new)
+    }
+}
 impl<T> Hash for Ptr<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.hash(state)
@@ -108,6 +124,7 @@ impl<T> Hash for Ptr<T> {
 ///
 /// Newtype wrapping a [Vec<Ptr>], adding an interface designed for ease of getting referable [Ptr]s upon insertion.
 /// Other methods are passed into the underlying [Vec] via [Deref] and [DerefMut].
+/// Comparisons are element-wise, by [Ptr] address.
 ///
 #[derive(Debug, Clone)]
 pub struct PtrList<T: ?Sized>(Vec<Ptr<T>>);
@@ -140,6 +157,12 @@ impl<T> PtrList<T> {
         self.add(t)
     }
 }
+impl<T> PartialEq for PtrList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl<T> Eq for PtrList<T> {}
 impl<T> Default for PtrList<T> {
     fn default() -> Self {
         Self::new()
@@ -46,6 +46,7 @@ use textwrap::dedent;
 #[derive(Clone, Copy)]
 pub enum SerializationFormat {
     Json,
+    #[cfg(feature = "yaml")]
     Yaml,
     Toml,
 }
@@ -54,6 +55,7 @@ impl SerializationFormat {
     pub fn to_string(&self, data: &impl Serialize) -> Result<String, Error> {
         match *self {
             Self::Json => Ok(serde_json::to_string_pretty(data)?),
+            #[cfg(feature = "yaml")]
             Self::Yaml => Ok(serde_yaml::to_string(data)?),
             Self::Toml => Ok(toml::to_string_pretty(data)?),
         }
@@ -63,6 +65,7 @@ impl SerializationFormat {
         let s = dedent(s);
         match *self {
             Self::Json => Ok(serde_json::from_str(&s)?),
+            #[cfg(feature = "yaml")]
             Self::Yaml => Ok(serde_yaml::from_str(&s)?),
             Self::Toml => Ok(toml::from_str(&s)?),
         }
@@ -123,6 +126,7 @@ pub fn open<T: DeserializeOwned>(
     let mut file = BufReader::new(file);
     let rv: T = match fmt {
         SerializationFormat::Json => serde_json::from_reader(file)?,
+        #[cfg(feature = "yaml")]
         SerializationFormat::Yaml => serde_yaml::from_reader(file)?,
         SerializationFormat::Toml => {
             // TOML doesn't have that nice reader method, so we kinda recreate (a probably slower) one
@@ -145,6 +149,12 @@ impl std::fmt::Display for Error {
     }
 }
 impl std::error::Error for Error {}
+impl Error {
+    /// Create an [Error] from a string message
+    pub fn msg(msg: impl Into<String>) -> Self {
+        Self(msg.into().into())
+    }
+}
 
 // Automatic conversion from the other error types generated above
 impl From<serde_json::Error> for Error {
@@ -152,6 +162,7 @@ impl From<serde_json::Error> for Error {
         Self(Box::new(e))
     }
 }
+#[cfg(feature = "yaml")]
 impl From<serde_yaml::Error> for Error {
     fn from(e: serde_yaml::Error) -> Self {
         Self(Box::new(e))